@@ -27,10 +27,15 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::transport::HttpTransportClient;
+use crate::transport::{HttpTransportClient, PoolConfig, TlsConfig};
 use crate::types::{ErrorResponse, Id, NotificationSer, ParamsSer, RequestSer, Response};
 use async_trait::async_trait;
-use jsonrpsee_core::client::{CertificateStore, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT};
+use hyper::client::HttpConnector;
+use jsonrpsee_core::client::{
+	batch::parse_batch_response, BatchEntryResponse, BatchRequestBuilder, BatchResponse, CertificateStore,
+	ClientMiddleware, ClientT, IdKind, MiddlewareCall, RequestIdManager, RequestLimitPolicy, RetryPolicy, Subscription,
+	SubscriptionBuffer, SubscriptionClientT,
+};
 use jsonrpsee_core::tracing::RpcTracing;
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
 use jsonrpsee_types::error::CallError;
@@ -44,12 +49,110 @@ pub struct HttpClientBuilder {
 	max_request_body_size: u32,
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
+	request_limit_policy: RequestLimitPolicy,
 	certificate_store: CertificateStore,
+	#[cfg(feature = "tls")]
+	tls_config: Option<rustls::ClientConfig>,
 	id_kind: IdKind,
 	max_log_length: u32,
+	connector: HttpConnector,
+	tcp: TcpConfig,
+	pool: PoolConfig,
+	retry_policy: Option<RetryPolicy>,
+	middleware: Option<Arc<dyn ClientMiddleware>>,
+	headers: Vec<(String, String)>,
+}
+
+/// Settings of an [`HttpClientBuilder`], as returned by [`HttpClientBuilder::config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientConfig {
+	/// Maximum size of a request body in bytes.
+	pub max_request_body_size: u32,
+	/// Request timeout.
+	pub request_timeout: Duration,
+	/// Maximum number of concurrent requests.
+	pub max_concurrent_requests: usize,
+	/// What is done with the requests made while the maximum number of concurrent requests is reached.
+	pub request_limit_policy: RequestLimitPolicy,
+	/// Data type of the request object ID.
+	pub id_kind: IdKind,
+	/// Max length for logging for requests and responses in number characters.
+	pub max_log_length: u32,
+	/// Whether `TCP_NODELAY` is enabled on the sockets.
+	pub tcp_nodelay: bool,
+	/// Idle time before the first TCP keepalive probe, `None` for the operating system default.
+	pub tcp_keepalive: Option<Duration>,
+	/// Size of the send buffer of the sockets, `None` for the operating system default.
+	pub tcp_send_buffer_size: Option<usize>,
+	/// Size of the receive buffer of the sockets, `None` for the operating system default.
+	pub tcp_recv_buffer_size: Option<usize>,
+	/// Maximum number of idle connections kept open per host.
+	pub pool_max_idle_per_host: usize,
+	/// Time after which idle connections are closed, `None` if they are kept open.
+	pub pool_idle_timeout: Option<Duration>,
+	/// Whether connections are reused for several requests.
+	pub http_keep_alive: bool,
+	/// Policy of retrying failed method calls, `None` if they aren't retried.
+	pub retry_policy: Option<RetryPolicy>,
+}
+
+/// TCP options applied to the connector, kept around so that they can be read back.
+#[derive(Debug, Clone, Copy, Default)]
+struct TcpConfig {
+	nodelay: bool,
+	keepalive: Option<Duration>,
+	send_buffer_size: Option<usize>,
+	recv_buffer_size: Option<usize>,
 }
 
 impl HttpClientBuilder {
+	/// Preset for talking to a public gateway over the internet.
+	///
+	/// Favours failing fast and keeping the load on the remote end low: requests time out after 20 seconds,
+	/// request bodies are limited to 1 MiB, at most 64 requests run concurrently, `TCP_NODELAY` is enabled
+	/// and idle connections are probed with TCP keepalive after 60 seconds.
+	pub fn for_public_gateway() -> Self {
+		Self::default()
+			.request_timeout(Duration::from_secs(20))
+			.max_request_body_size(1024 * 1024)
+			.max_concurrent_requests(64)
+			.tcp_nodelay(true)
+			.tcp_keepalive(Duration::from_secs(60))
+	}
+
+	/// Preset for talking to a node running on the same machine or on a trusted network.
+	///
+	/// Allows slow and large requests, such as the ones of indexers: requests time out after 5 minutes,
+	/// request bodies are limited to 100 MiB, at most 1024 requests run concurrently and `TCP_NODELAY`
+	/// is enabled.
+	pub fn for_local_node() -> Self {
+		Self::default()
+			.request_timeout(Duration::from_secs(300))
+			.max_request_body_size(100 * 1024 * 1024)
+			.max_concurrent_requests(1024)
+			.tcp_nodelay(true)
+	}
+
+	/// Returns the current settings of the builder, for instance to inspect a preset.
+	pub fn config(&self) -> HttpClientConfig {
+		HttpClientConfig {
+			max_request_body_size: self.max_request_body_size,
+			request_timeout: self.request_timeout,
+			max_concurrent_requests: self.max_concurrent_requests,
+			request_limit_policy: self.request_limit_policy,
+			id_kind: self.id_kind,
+			max_log_length: self.max_log_length,
+			tcp_nodelay: self.tcp.nodelay,
+			tcp_keepalive: self.tcp.keepalive,
+			tcp_send_buffer_size: self.tcp.send_buffer_size,
+			tcp_recv_buffer_size: self.tcp.recv_buffer_size,
+			pool_max_idle_per_host: self.pool.max_idle_per_host,
+			pool_idle_timeout: self.pool.idle_timeout,
+			http_keep_alive: self.pool.keep_alive,
+			retry_policy: self.retry_policy.clone(),
+		}
+	}
+
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
@@ -68,12 +171,31 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Set what to do with the requests made while [`max_concurrent_requests`](HttpClientBuilder::max_concurrent_requests)
+	/// requests are pending (default is to reject them with [`Error::MaxSlotsExceeded`]).
+	///
+	/// With [`RequestLimitPolicy::Queue`], they are sent once enough pending requests are completed.
+	pub fn request_limit_policy(mut self, policy: RequestLimitPolicy) -> Self {
+		self.request_limit_policy = policy;
+		self
+	}
+
 	/// Set which certificate store to use.
 	pub fn certificate_store(mut self, certificate_store: CertificateStore) -> Self {
 		self.certificate_store = certificate_store;
 		self
 	}
 
+	/// Use a custom TLS configuration for `https` URLs, for instance to trust other root certificates or to
+	/// authenticate with a client certificate (default is built from the certificate store).
+	///
+	/// The certificate store is ignored when a custom configuration is set.
+	#[cfg(feature = "tls")]
+	pub fn tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls_config = Some(config);
+		self
+	}
+
 	/// Configure the data type of the request object ID (default is number).
 	pub fn id_format(mut self, id_kind: IdKind) -> Self {
 		self.id_kind = id_kind;
@@ -88,15 +210,105 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Enables or disables `TCP_NODELAY` on the sockets (default is disabled).
+	pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+		self.connector.set_nodelay(enabled);
+		self.tcp.nodelay = enabled;
+		self
+	}
+
+	/// Enables TCP keepalive on the sockets, sending the first probe after `time` of idleness
+	/// (default is the operating system default).
+	pub fn tcp_keepalive(mut self, time: Duration) -> Self {
+		self.connector.set_keepalive(Some(time));
+		self.tcp.keepalive = Some(time);
+		self
+	}
+
+	/// Sets the size of the send buffer of the sockets (default is the operating system default).
+	pub fn tcp_send_buffer_size(mut self, size: usize) -> Self {
+		self.connector.set_send_buffer_size(Some(size));
+		self.tcp.send_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets the size of the receive buffer of the sockets (default is the operating system default).
+	pub fn tcp_recv_buffer_size(mut self, size: usize) -> Self {
+		self.connector.set_recv_buffer_size(Some(size));
+		self.tcp.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets the maximum number of idle connections kept open per host (default is unlimited).
+	pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+		self.pool.max_idle_per_host = max;
+		self
+	}
+
+	/// Sets the time after which idle connections are closed, `None` to keep them open
+	/// (default is 90 seconds).
+	pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.pool.idle_timeout = timeout;
+		self
+	}
+
+	/// Enables or disables the reuse of connections for several requests (default is enabled).
+	///
+	/// When disabled, a new connection is opened for each request.
+	pub fn http_keep_alive(mut self, enabled: bool) -> Self {
+		self.pool.keep_alive = enabled;
+		self
+	}
+
+	/// Add a header sent with every request (default is none), for instance an `Authorization` header.
+	///
+	/// The `Content-Type` and `Accept` headers can't be overridden.
+	pub fn add_header(mut self, name: &str, value: &str) -> Self {
+		self.headers.push((name.to_owned(), value.to_owned()));
+		self
+	}
+
+	/// Retry failed method calls according to `policy` (disabled by default), see [`RetryPolicy`].
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry_policy = Some(policy);
+		self
+	}
+
+	/// Report the calls made by the client to `middleware` (disabled by default), see [`ClientMiddleware`].
+	pub fn set_middleware(mut self, middleware: impl ClientMiddleware) -> Self {
+		self.middleware = Some(Arc::new(middleware));
+		self
+	}
+
 	/// Build the HTTP client with target to connect to.
 	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
-		let transport =
-			HttpTransportClient::new(target, self.max_request_body_size, self.certificate_store, self.max_log_length)
-				.map_err(|e| Error::Transport(e.into()))?;
+		#[cfg(feature = "tls")]
+		let tls = match self.tls_config {
+			Some(config) => TlsConfig::Custom(config),
+			None => TlsConfig::Store(self.certificate_store),
+		};
+		#[cfg(not(feature = "tls"))]
+		let tls = TlsConfig::Store(self.certificate_store);
+
+		let transport = HttpTransportClient::new(
+			target,
+			self.max_request_body_size,
+			tls,
+			self.max_log_length,
+			self.connector,
+			self.pool,
+			&self.headers,
+		)
+		.map_err(|e| Error::Transport(e.into()))?;
 		Ok(HttpClient {
 			transport,
-			id_manager: Arc::new(RequestIdManager::new(self.max_concurrent_requests, self.id_kind)),
+			id_manager: Arc::new(
+				RequestIdManager::new(self.max_concurrent_requests, self.id_kind)
+					.limit_policy(self.request_limit_policy),
+			),
 			request_timeout: self.request_timeout,
+			retry_policy: self.retry_policy,
+			middleware: self.middleware,
 		})
 	}
 }
@@ -107,9 +319,18 @@ impl Default for HttpClientBuilder {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
+			request_limit_policy: RequestLimitPolicy::Reject,
 			certificate_store: CertificateStore::Native,
+			#[cfg(feature = "tls")]
+			tls_config: None,
 			id_kind: IdKind::Number,
 			max_log_length: 4096,
+			connector: HttpConnector::new(),
+			tcp: TcpConfig::default(),
+			pool: PoolConfig::default(),
+			retry_policy: None,
+			middleware: None,
+			headers: Vec::new(),
 		}
 	}
 }
@@ -123,32 +344,20 @@ pub struct HttpClient {
 	request_timeout: Duration,
 	/// Request ID manager.
 	id_manager: Arc<RequestIdManager>,
+	/// Policy of retrying failed method calls.
+	retry_policy: Option<RetryPolicy>,
+	/// Middleware the calls are reported to.
+	middleware: Option<Arc<dyn ClientMiddleware>>,
 }
 
-#[async_trait]
-impl ClientT for HttpClient {
-	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
-		let trace = RpcTracing::notification(method);
-		let _enter = trace.span().enter();
-
-		let notif = serde_json::to_string(&NotificationSer::new(method, params)).map_err(Error::ParseError)?;
-
-		let fut = self.transport.send(notif).in_current_span();
-
-		match tokio::time::timeout(self.request_timeout, fut).await {
-			Ok(Ok(ok)) => Ok(ok),
-			Err(_) => Err(Error::RequestTimeout),
-			Ok(Err(e)) => Err(Error::Transport(e.into())),
-		}
-	}
-
-	/// Perform a request towards the server.
-	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+impl HttpClient {
+	async fn request_once<R>(&self, method: &str, params: Option<ParamsSer<'_>>) -> Result<R, Error>
 	where
 		R: DeserializeOwned,
 	{
-		let guard = self.id_manager.next_request_id()?;
+		let guard = self.id_manager.acquire_request_id().await?;
 		let id = guard.inner();
+		let call = MiddlewareCall::new(self.middleware.as_deref(), method, params.as_ref(), &id);
 		let request = RequestSer::new(&id, method, params);
 		let trace = RpcTracing::method_call(method);
 		let _enter = trace.span().enter();
@@ -175,17 +384,18 @@ impl ClientT for HttpClient {
 		};
 
 		if response.id == id {
+			call.complete(true);
 			Ok(response.result)
 		} else {
 			Err(Error::InvalidRequestId)
 		}
 	}
 
-	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
-	where
-		R: DeserializeOwned + Default + Clone,
-	{
-		let guard = self.id_manager.next_request_ids(batch.len())?;
+	async fn batch_request_once(
+		&self,
+		batch: Vec<(&str, Option<ParamsSer<'_>>)>,
+	) -> Result<Vec<BatchEntryResponse>, Error> {
+		let guard = self.id_manager.acquire_request_ids(batch.len()).await?;
 		let ids: Vec<Id> = guard.inner();
 		let trace = RpcTracing::batch();
 		let _enter = trace.span().enter();
@@ -194,8 +404,10 @@ impl ClientT for HttpClient {
 		// NOTE(niklasad1): `ID` is not necessarily monotonically increasing.
 		let mut ordered_requests = Vec::with_capacity(batch.len());
 		let mut request_set = FxHashMap::with_capacity_and_hasher(batch.len(), Default::default());
+		let mut calls = Vec::with_capacity(batch.len());
 
 		for (pos, (method, params)) in batch.into_iter().enumerate() {
+			calls.push(MiddlewareCall::new(self.middleware.as_deref(), method, params.as_ref(), &ids[pos]));
 			batch_request.push(RequestSer::new(&ids[pos], method, params));
 			ordered_requests.push(&ids[pos]);
 			request_set.insert(&ids[pos], pos);
@@ -212,23 +424,117 @@ impl ClientT for HttpClient {
 			Ok(Err(e)) => return Err(Error::Transport(e.into())),
 		};
 
-		let rps: Vec<Response<_>> =
-			serde_json::from_slice(&body).map_err(|_| match serde_json::from_slice::<ErrorResponse>(&body) {
-				Ok(e) => Error::Call(CallError::Custom(e.error_object().clone().into_owned())),
-				Err(e) => Error::ParseError(e),
-			})?;
+		let rps = match parse_batch_response(&body) {
+			Some(rps) => rps,
+			None => {
+				return Err(match serde_json::from_slice::<ErrorResponse>(&body) {
+					Ok(e) => Error::Call(CallError::Custom(e.error_object().clone().into_owned())),
+					Err(e) => Error::ParseError(e),
+				})
+			}
+		};
 
-		// NOTE: `R::default` is placeholder and will be replaced in loop below.
-		let mut responses = vec![R::default(); ordered_requests.len()];
-		for rp in rps {
-			let pos = match request_set.get(&rp.id) {
+		// NOTE: `None` is placeholder and will be replaced in loop below.
+		let mut responses = vec![None; ordered_requests.len()];
+		for (id, rp) in rps {
+			let pos = match request_set.get(&id) {
 				Some(pos) => *pos,
 				None => return Err(Error::InvalidRequestId),
 			};
-			responses[pos] = rp.result
+			responses[pos] = Some(rp);
+		}
+		let responses: Vec<_> =
+			responses.into_iter().map(|rp| rp.ok_or(Error::InvalidRequestId)).collect::<Result<_, _>>()?;
+		for (call, rp) in calls.into_iter().zip(&responses) {
+			call.complete(rp.is_ok());
 		}
 		Ok(responses)
 	}
+
+	/// Sends `batch`, retrying it according to the retry policy of the client.
+	async fn batch_request_with_retries(
+		&self,
+		batch: Vec<(&str, Option<ParamsSer<'_>>)>,
+	) -> Result<Vec<BatchEntryResponse>, Error> {
+		let mut attempts = 0;
+
+		loop {
+			attempts += 1;
+			let err = match self.batch_request_once(batch.clone()).await {
+				Err(err) => err,
+				res => return res,
+			};
+
+			let methods = batch.iter().map(|(method, _)| *method);
+			match self.retry_policy.as_ref().and_then(|policy| policy.retry_batch_after(methods, attempts, &err)) {
+				Some(backoff) => {
+					tracing::debug!("Retrying batch in {:?}: {}", backoff, err);
+					tokio::time::sleep(backoff).await;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl ClientT for HttpClient {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		let trace = RpcTracing::notification(method);
+		let _enter = trace.span().enter();
+
+		let notif = serde_json::to_string(&NotificationSer::new(method, params)).map_err(Error::ParseError)?;
+
+		let fut = self.transport.send(notif).in_current_span();
+
+		match tokio::time::timeout(self.request_timeout, fut).await {
+			Ok(Ok(ok)) => Ok(ok),
+			Err(_) => Err(Error::RequestTimeout),
+			Ok(Err(e)) => Err(Error::Transport(e.into())),
+		}
+	}
+
+	/// Perform a request towards the server.
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let mut attempts = 0;
+
+		loop {
+			attempts += 1;
+			let err = match self.request_once(method, params.clone()).await {
+				Err(err) => err,
+				res => return res,
+			};
+
+			match self.retry_policy.as_ref().and_then(|policy| policy.retry_after(method, attempts, &err)) {
+				Some(backoff) => {
+					tracing::debug!("Retrying call to `{}` in {:?}: {}", method, backoff, err);
+					tokio::time::sleep(backoff).await;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.batch_request_with_retries(batch)
+			.await?
+			.into_iter()
+			.map(|rp| match rp {
+				Ok(val) => serde_json::from_value(val).map_err(Error::ParseError),
+				Err(err) => Err(Error::Call(CallError::Custom(err))),
+			})
+			.collect()
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		self.batch_request_with_retries(batch.into_calls()).await.map(BatchResponse::new)
+	}
 }
 
 #[async_trait]
@@ -246,6 +552,32 @@ impl SubscriptionClientT for HttpClient {
 		Err(Error::HttpNotImplemented)
 	}
 
+	/// Send a subscription request to the server. Not implemented for HTTP; will always return [`Error::HttpNotImplemented`].
+	async fn subscribe_with_buffer<'a, N>(
+		&self,
+		_subscribe_method: &'a str,
+		_params: Option<ParamsSer<'a>>,
+		_unsubscribe_method: &'a str,
+		_buffer: SubscriptionBuffer,
+	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		Err(Error::HttpNotImplemented)
+	}
+
+	/// Send a batch of subscription requests to the server. Not implemented for HTTP; will always return
+	/// [`Error::HttpNotImplemented`].
+	async fn batch_subscribe<'a, N>(
+		&self,
+		_batch: Vec<(&'a str, Option<ParamsSer<'a>>, &'a str)>,
+	) -> Result<Vec<Subscription<N>>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		Err(Error::HttpNotImplemented)
+	}
+
 	/// Subscribe to a specific method. Not implemented for HTTP; will always return [`Error::HttpNotImplemented`].
 	async fn subscribe_to_method<'a, N>(&self, _method: &'a str) -> Result<Subscription<N>, Error>
 	where
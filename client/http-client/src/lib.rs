@@ -42,5 +42,8 @@ pub mod transport;
 #[cfg(test)]
 mod tests;
 
-pub use client::{HttpClient, HttpClientBuilder};
+pub use client::{HttpClient, HttpClientBuilder, HttpClientConfig};
+pub use jsonrpsee_core::client::{ClientMiddleware, RequestLimitPolicy, RetryOn, RetryPolicy};
 pub use jsonrpsee_types as types;
+#[cfg(feature = "tls")]
+pub use rustls;
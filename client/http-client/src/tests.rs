@@ -34,6 +34,7 @@ use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::Id;
 use jsonrpsee_test_utils::TimeoutFutureExt;
 use jsonrpsee_types::error::{CallError, ErrorObjectOwned};
+use std::time::Duration;
 
 #[tokio::test]
 async fn method_call_works() {
@@ -155,6 +156,51 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[test]
+fn presets_are_introspectable() {
+	let gateway = HttpClientBuilder::for_public_gateway().config();
+	assert_eq!(gateway.request_timeout, Duration::from_secs(20));
+	assert_eq!(gateway.max_concurrent_requests, 64);
+	assert!(gateway.tcp_nodelay);
+	assert_eq!(gateway.tcp_keepalive, Some(Duration::from_secs(60)));
+
+	let local = HttpClientBuilder::for_local_node().config();
+	assert_eq!(local.request_timeout, Duration::from_secs(300));
+	assert!(local.max_request_body_size > gateway.max_request_body_size);
+	assert_eq!(local.tcp_keepalive, None);
+
+	// Presets are regular builders and can be tuned further.
+	let tuned = HttpClientBuilder::for_local_node().id_format(IdKind::String).tcp_recv_buffer_size(4096).config();
+	assert_eq!(tuned.id_kind, IdKind::String);
+	assert_eq!(tuned.tcp_recv_buffer_size, Some(4096));
+	assert_eq!(tuned.max_concurrent_requests, local.max_concurrent_requests);
+}
+
+#[tokio::test]
+async fn pool_settings_work() {
+	let config = HttpClientBuilder::default().config();
+	assert_eq!(config.pool_max_idle_per_host, usize::MAX);
+	assert_eq!(config.pool_idle_timeout, Some(Duration::from_secs(90)));
+	assert!(config.http_keep_alive);
+
+	let builder = HttpClientBuilder::default()
+		.pool_max_idle_per_host(8)
+		.pool_idle_timeout(Some(Duration::from_secs(5)))
+		.http_keep_alive(false);
+	let config = builder.config();
+	assert_eq!(config.pool_max_idle_per_host, 8);
+	assert_eq!(config.pool_idle_timeout, Some(Duration::from_secs(5)));
+	assert!(!config.http_keep_alive);
+
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let client = builder.build(format!("http://{}", server_addr)).unwrap();
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, "hello");
+}
+
 async fn run_batch_request_with_response<'a>(
 	batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
 	response: String,
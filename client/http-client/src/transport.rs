@@ -12,10 +12,55 @@ use jsonrpsee_core::client::CertificateStore;
 use jsonrpsee_core::error::GenericTransportError;
 use jsonrpsee_core::http_helpers;
 use jsonrpsee_core::tracing::{rx_log_from_bytes, tx_log_from_str};
+use std::time::Duration;
 use thiserror::Error;
 
 const CONTENT_TYPE_JSON: &str = "application/json";
 
+/// TLS settings of the connections to `https` URLs.
+#[derive(Debug, Clone)]
+pub(crate) enum TlsConfig {
+	/// Configuration trusting the roots of a certificate store.
+	#[cfg_attr(not(feature = "tls"), allow(dead_code))]
+	Store(CertificateStore),
+	/// Custom configuration.
+	#[cfg(feature = "tls")]
+	Custom(rustls::ClientConfig),
+}
+
+impl From<CertificateStore> for TlsConfig {
+	fn from(store: CertificateStore) -> Self {
+		Self::Store(store)
+	}
+}
+
+/// Settings of the connection pool of the hyper client.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PoolConfig {
+	/// Maximum number of idle connections kept open per host.
+	pub(crate) max_idle_per_host: usize,
+	/// Time after which idle connections are closed, `None` to keep them open.
+	pub(crate) idle_timeout: Option<Duration>,
+	/// Whether connections are reused for several requests.
+	pub(crate) keep_alive: bool,
+}
+
+impl Default for PoolConfig {
+	fn default() -> Self {
+		Self { max_idle_per_host: usize::MAX, idle_timeout: Some(Duration::from_secs(90)), keep_alive: true }
+	}
+}
+
+impl PoolConfig {
+	fn client_builder(&self) -> hyper::client::Builder {
+		let mut builder = Client::builder();
+		builder.pool_idle_timeout(self.idle_timeout);
+		// Without idle connections in the pool, hyper closes each connection once its response is read.
+		builder.pool_max_idle_per_host(if self.keep_alive { self.max_idle_per_host } else { 0 });
+		builder
+	}
+}
+
 #[derive(Debug, Clone)]
 enum HyperClient {
 	/// Hyper client with https connector.
@@ -42,6 +87,8 @@ pub struct HttpTransportClient {
 	target: Uri,
 	/// HTTP client
 	client: HyperClient,
+	/// Headers added to every request.
+	headers: hyper::HeaderMap,
 	/// Configurable max request body size
 	max_request_body_size: u32,
 	/// Max length for logging for requests and responses
@@ -55,32 +102,51 @@ impl HttpTransportClient {
 	pub(crate) fn new(
 		target: impl AsRef<str>,
 		max_request_body_size: u32,
-		cert_store: CertificateStore,
+		tls: TlsConfig,
 		max_log_length: u32,
+		connector: HttpConnector,
+		pool: PoolConfig,
+		headers: &[(String, String)],
 	) -> Result<Self, Error> {
 		let target: Uri = target.as_ref().parse().map_err(|e| Error::Url(format!("Invalid URL: {}", e)))?;
 		if target.port_u16().is_none() {
 			return Err(Error::Url("Port number is missing in the URL".into()));
 		}
 
+		let mut header_map = hyper::HeaderMap::with_capacity(headers.len());
+		for (name, value) in headers {
+			let name = hyper::header::HeaderName::from_bytes(name.as_bytes())
+				.map_err(|_| Error::Header(format!("Invalid header name `{}`", name)))?;
+			let value = hyper::header::HeaderValue::from_str(value)
+				.map_err(|_| Error::Header(format!("Invalid value of the header `{}`", name)))?;
+			header_map.append(name, value);
+		}
+
 		let client = match target.scheme_str() {
-			Some("http") => HyperClient::Http(Client::new()),
+			Some("http") => HyperClient::Http(pool.client_builder().build(connector)),
 			#[cfg(feature = "tls")]
 			Some("https") => {
-				let connector = match cert_store {
-					CertificateStore::Native => hyper_rustls::HttpsConnectorBuilder::new()
+				let mut connector = connector;
+				connector.enforce_http(false);
+				let connector = match tls {
+					TlsConfig::Custom(config) => hyper_rustls::HttpsConnectorBuilder::new()
+						.with_tls_config(config)
+						.https_or_http()
+						.enable_http1()
+						.wrap_connector(connector),
+					TlsConfig::Store(CertificateStore::Native) => hyper_rustls::HttpsConnectorBuilder::new()
 						.with_native_roots()
 						.https_or_http()
 						.enable_http1()
-						.build(),
-					CertificateStore::WebPki => hyper_rustls::HttpsConnectorBuilder::new()
+						.wrap_connector(connector),
+					TlsConfig::Store(CertificateStore::WebPki) => hyper_rustls::HttpsConnectorBuilder::new()
 						.with_webpki_roots()
 						.https_or_http()
 						.enable_http1()
-						.build(),
+						.wrap_connector(connector),
 					_ => return Err(Error::InvalidCertficateStore),
 				};
-				HyperClient::Https(Client::builder().build::<_, hyper::Body>(connector))
+				HyperClient::Https(pool.client_builder().build::<_, hyper::Body>(connector))
 			}
 			_ => {
 				#[cfg(feature = "tls")]
@@ -90,7 +156,7 @@ impl HttpTransportClient {
 				return Err(Error::Url(err.into()));
 			}
 		};
-		Ok(Self { target, client, max_request_body_size, max_log_length })
+		Ok(Self { target, client, headers: header_map, max_request_body_size, max_log_length })
 	}
 
 	async fn inner_send(&self, body: String) -> Result<hyper::Response<hyper::Body>, Error> {
@@ -100,11 +166,11 @@ impl HttpTransportClient {
 			return Err(Error::RequestTooLarge);
 		}
 
-		let req = hyper::Request::post(&self.target)
-			.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.header(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.body(From::from(body))
-			.expect("URI and request headers are valid; qed");
+		let mut req = hyper::Request::post(&self.target).body(From::from(body)).expect("URI is valid; qed");
+		let headers = req.headers_mut();
+		headers.extend(self.headers.clone());
+		headers.insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON));
+		headers.insert(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON));
 
 		let response = self.client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
 		if response.status().is_success() {
@@ -162,6 +228,10 @@ pub enum Error {
 	/// Invalid certificate store.
 	#[error("Invalid certificate store")]
 	InvalidCertficateStore,
+
+	/// Invalid header.
+	#[error("{0}")]
+	Header(String),
 }
 
 impl<T> From<GenericTransportError<T>> for Error
@@ -179,7 +249,7 @@ where
 
 #[cfg(test)]
 mod tests {
-	use super::{CertificateStore, Error, HttpTransportClient};
+	use super::{CertificateStore, Error, HttpConnector, HttpTransportClient, PoolConfig};
 
 	fn assert_target(
 		client: &HttpTransportClient,
@@ -198,37 +268,111 @@ mod tests {
 
 	#[test]
 	fn invalid_http_url_rejected() {
-		let err = HttpTransportClient::new("ws://localhost:9933", 80, CertificateStore::Native, 80).unwrap_err();
+		let err = HttpTransportClient::new(
+			"ws://localhost:9933",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[cfg(feature = "tls")]
 	#[test]
 	fn https_works() {
-		let client = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native, 80).unwrap();
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap();
+		assert_target(&client, "localhost", "https", "/", 9933, 80);
+	}
+
+	#[cfg(feature = "tls")]
+	#[test]
+	fn https_with_custom_tls_config_works() {
+		use super::TlsConfig;
+
+		let config = rustls::ClientConfig::builder()
+			.with_safe_defaults()
+			.with_root_certificates(rustls::RootCertStore::empty())
+			.with_no_client_auth();
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			TlsConfig::Custom(config),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "https", "/", 9933, 80);
 	}
 
 	#[cfg(not(feature = "tls"))]
 	#[test]
 	fn https_fails_without_tls_feature() {
-		let err = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native, 80).unwrap_err();
+		let err = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn faulty_port() {
-		let err = HttpTransportClient::new("http://localhost:-43", 80, CertificateStore::Native, 80).unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-43",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
-		let err = HttpTransportClient::new("http://localhost:-99999", 80, CertificateStore::Native, 80).unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-99999",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn url_with_path_works() {
-		let client =
-			HttpTransportClient::new("http://localhost:9944/my-special-path", 1337, CertificateStore::Native, 80)
-				.unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9944/my-special-path",
+			1337,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "http", "/my-special-path", 9944, 1337);
 	}
 
@@ -237,8 +381,11 @@ mod tests {
 		let client = HttpTransportClient::new(
 			"http://127.0.0.1:9999/my?name1=value1&name2=value2",
 			u32::MAX,
-			CertificateStore::WebPki,
+			CertificateStore::WebPki.into(),
 			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
 		)
 		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my?name1=value1&name2=value2", 9999, u32::MAX);
@@ -246,15 +393,48 @@ mod tests {
 
 	#[test]
 	fn url_with_fragment_is_ignored() {
-		let client =
-			HttpTransportClient::new("http://127.0.0.1:9944/my.htm#ignore", 999, CertificateStore::Native, 80).unwrap();
+		let client = HttpTransportClient::new(
+			"http://127.0.0.1:9944/my.htm#ignore",
+			999,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my.htm", 9944, 999);
 	}
 
+	#[test]
+	fn invalid_header_rejected() {
+		let headers = [("X-Api-Key".to_owned(), "secret\n".to_owned())];
+		let err = HttpTransportClient::new(
+			"http://localhost:9933",
+			80,
+			CertificateStore::Native.into(),
+			80,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&headers,
+		)
+		.unwrap_err();
+		assert!(matches!(err, Error::Header(_)));
+	}
+
 	#[tokio::test]
 	async fn request_limit_works() {
 		let eighty_bytes_limit = 80;
-		let client = HttpTransportClient::new("http://localhost:9933", 80, CertificateStore::WebPki, 99).unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9933",
+			80,
+			CertificateStore::WebPki.into(),
+			99,
+			HttpConnector::new(),
+			PoolConfig::default(),
+			&[],
+		)
+		.unwrap();
 		assert_eq!(client.max_request_body_size, eighty_bytes_limit);
 
 		let body = "a".repeat(81);
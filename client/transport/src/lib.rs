@@ -35,6 +35,11 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 pub mod ws;
 
+/// Transport over pipes, such as the standard input and output of a process.
+#[cfg(feature = "stdio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stdio")))]
+pub mod stdio;
+
 /// Websocket transport via web-sys.
 #[cfg(all(feature = "web", target_arch = "wasm32"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "web")))]
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io;
+use std::process::Stdio;
+
+use jsonrpsee_core::async_trait;
+use jsonrpsee_core::client::{ReceivedMessage, TransportReceiverT, TransportSenderT};
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+pub use jsonrpsee_core::stdio::Framing;
+
+/// Error that can occur when reading or writing messages over pipes.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// Error from the underlying pipes.
+	#[error("I/O error: {0}")]
+	Io(#[from] io::Error),
+	/// The server closed its output.
+	#[error("The server closed the connection")]
+	Closed,
+	/// The pipes of the spawned process could not be captured.
+	#[error("The standard input or output of the server process is not available")]
+	NoPipes,
+}
+
+/// Sending end of a pipe transport.
+pub struct Sender<W> {
+	writer: W,
+	framing: Framing,
+}
+
+impl<W> fmt::Debug for Sender<W> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Sender").field("framing", &self.framing).finish()
+	}
+}
+
+/// Receiving end of a pipe transport.
+pub struct Receiver<R> {
+	reader: BufReader<R>,
+	framing: Framing,
+	max_response_size: usize,
+}
+
+impl<R> fmt::Debug for Receiver<R> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Receiver")
+			.field("framing", &self.framing)
+			.field("max_response_size", &self.max_response_size)
+			.finish()
+	}
+}
+
+impl<R> Receiver<R> {
+	/// Set the maximum size of a message sent by the server, 10 MiB by default.
+	pub fn max_response_size(mut self, max: usize) -> Self {
+		self.max_response_size = max;
+		self
+	}
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Send + Unpin + 'static> TransportSenderT for Sender<W> {
+	type Error = Error;
+
+	async fn send(&mut self, body: String) -> Result<(), Self::Error> {
+		tracing::trace!("send: {}", body);
+		jsonrpsee_core::stdio::write_message(&mut self.writer, self.framing, body.as_bytes()).await?;
+		Ok(())
+	}
+
+	async fn send_ping(&mut self) -> Result<(), Self::Error> {
+		tracing::trace!("send ping - not supported over pipes");
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl<R: AsyncRead + Send + Unpin + 'static> TransportReceiverT for Receiver<R> {
+	type Error = Error;
+
+	async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+		match jsonrpsee_core::stdio::read_message(&mut self.reader, self.framing, self.max_response_size).await? {
+			Some(message) => Ok(ReceivedMessage::Bytes(message)),
+			None => Err(Error::Closed),
+		}
+	}
+}
+
+/// Create a transport sender & receiver pair exchanging messages over `reader` and `writer`.
+pub fn connect<R: AsyncRead, W: AsyncWrite>(reader: R, writer: W, framing: Framing) -> (Sender<W>, Receiver<R>) {
+	let receiver = Receiver { reader: BufReader::new(reader), framing, max_response_size: TEN_MB_SIZE_BYTES as usize };
+	(Sender { writer, framing }, receiver)
+}
+
+/// Spawn `command` and create a transport sender & receiver pair exchanging messages over its standard input
+/// and output.
+///
+/// The standard error of the process is inherited. It is killed once the returned [`Child`] is dropped.
+pub fn spawn(
+	command: &mut Command,
+	framing: Framing,
+) -> Result<(Sender<ChildStdin>, Receiver<ChildStdout>, Child), Error> {
+	let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).kill_on_drop(true).spawn()?;
+	let stdin = child.stdin.take().ok_or(Error::NoPipes)?;
+	let stdout = child.stdout.take().ok_or(Error::NoPipes)?;
+	let (sender, receiver) = connect(stdout, stdin, framing);
+	Ok((sender, receiver, child))
+}
@@ -24,6 +24,9 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+#[cfg(feature = "tls")]
+mod pinning;
+mod proxy;
 mod stream;
 
 use std::io;
@@ -32,6 +35,8 @@ use std::time::Duration;
 
 use futures_util::io::{BufReader, BufWriter};
 use jsonrpsee_core::client::{CertificateStore, ReceivedMessage, TransportReceiverT, TransportSenderT};
+use jsonrpsee_core::deflate::Deflate;
+use jsonrpsee_core::tcp::TcpSettings;
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_core::{async_trait, Cow};
 use soketto::connection::Error::Utf8;
@@ -43,7 +48,14 @@ use thiserror::Error;
 use tokio::net::TcpStream;
 
 pub use http::{uri::InvalidUri, Uri};
+pub use jsonrpsee_core::deflate::DeflateConfig;
+pub use jsonrpsee_core::tcp::TcpKeepalive;
+#[cfg(feature = "tls")]
+pub use pinning::CertificatePin;
+pub use proxy::Proxy;
 pub use soketto::handshake::client::Header;
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls;
 
 /// Sending end of WebSocket transport.
 #[derive(Debug)]
@@ -71,6 +83,18 @@ pub struct WsTransportClientBuilder<'a> {
 	pub max_request_body_size: u32,
 	/// Max number of redirections.
 	pub max_redirections: usize,
+	/// Options applied to the TCP socket.
+	pub tcp: TcpSettings,
+	/// Compression of the messages, if the server supports it.
+	pub compression: Option<DeflateConfig>,
+	/// Proxy through which the connection is established, if any.
+	pub proxy: Option<Proxy>,
+	/// TLS configuration used instead of the one built from the certificate store, if any.
+	#[cfg(feature = "tls")]
+	pub tls_config: Option<std::sync::Arc<rustls::ClientConfig>>,
+	/// Pins of the certificate of the server, one of which must match if any.
+	#[cfg(feature = "tls")]
+	pub certificate_pins: Vec<CertificatePin>,
 }
 
 impl<'a> Default for WsTransportClientBuilder<'a> {
@@ -81,6 +105,13 @@ impl<'a> Default for WsTransportClientBuilder<'a> {
 			connection_timeout: Duration::from_secs(10),
 			headers: Vec::new(),
 			max_redirections: 5,
+			tcp: TcpSettings { nodelay: Some(true), ..Default::default() },
+			compression: None,
+			proxy: None,
+			#[cfg(feature = "tls")]
+			tls_config: None,
+			#[cfg(feature = "tls")]
+			certificate_pins: Vec::new(),
 		}
 	}
 }
@@ -92,6 +123,25 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self
 	}
 
+	/// Use a custom TLS configuration for `wss` URLs, for instance to trust other root certificates or to
+	/// authenticate with a client certificate (default is built from the certificate store).
+	///
+	/// The certificate store is ignored when a custom configuration is set.
+	#[cfg(feature = "tls")]
+	pub fn tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls_config = Some(std::sync::Arc::new(config));
+		self
+	}
+
+	/// Pin the certificate of the server for `wss` URLs (default is none), see [`CertificatePin`].
+	///
+	/// When called several times, the connection succeeds if any of the pins matches, which allows rotating keys.
+	#[cfg(feature = "tls")]
+	pub fn pin_certificate(mut self, pin: CertificatePin) -> Self {
+		self.certificate_pins.push(pin);
+		self
+	}
+
 	/// Set max request body size (default is 10 MB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
@@ -118,6 +168,49 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Offer to compress the messages with the `permessage-deflate` extension (default is disabled). Messages
+	/// are left uncompressed if the server doesn't support it.
+	pub fn compression(mut self, config: DeflateConfig) -> Self {
+		self.compression = Some(config);
+		self
+	}
+
+	/// Connect to the server through a SOCKS5 or an HTTP proxy (default is a direct connection).
+	pub fn proxy(mut self, proxy: Proxy) -> Self {
+		self.proxy = Some(proxy);
+		self
+	}
+
+	/// Enables or disables `TCP_NODELAY` on the socket (default is enabled).
+	pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+		self.tcp.nodelay = Some(enabled);
+		self
+	}
+
+	/// Enables TCP keepalive on the socket (default is the operating system default).
+	pub fn tcp_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
+		self.tcp.keepalive = Some(keepalive);
+		self
+	}
+
+	/// Sets the size of the send buffer of the socket (default is the operating system default).
+	pub fn tcp_send_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.send_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets the size of the receive buffer of the socket (default is the operating system default).
+	pub fn tcp_recv_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets `SO_LINGER` on the socket, `None` disables lingering (default is the operating system default).
+	pub fn tcp_linger(mut self, linger: Option<Duration>) -> Self {
+		self.tcp.linger = Some(linger);
+		self
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -156,6 +249,11 @@ pub enum WsHandshakeError {
 	#[error("Invalid DNS name: {0}")]
 	InvalidDnsName(#[source] tokio_rustls::webpki::InvalidDnsNameError),
 
+	/// The certificate of the server doesn't match any of the pinned ones.
+	#[cfg(feature = "tls")]
+	#[error("Certificate of the server doesn't match any pinned certificate")]
+	CertificatePinMismatch,
+
 	/// Server rejected the handshake.
 	#[error("Connection rejected with status code: {status_code}")]
 	Rejected {
@@ -167,6 +265,10 @@ pub enum WsHandshakeError {
 	#[error("Connection timeout exceeded: {0:?}")]
 	Timeout(Duration),
 
+	/// The proxy failed to relay the connection.
+	#[error("Proxy error: {0}")]
+	Proxy(Cow<'static, str>),
+
 	/// Failed to resolve IP addresses for this hostname.
 	#[error("Failed to resolve IP addresses for this hostname: {0}")]
 	ResolutionFailed(io::Error),
@@ -243,7 +345,7 @@ impl TransportReceiverT for Receiver {
 impl<'a> WsTransportClientBuilder<'a> {
 	/// Try to establish the connection.
 	pub async fn build(self, uri: Uri) -> Result<(Sender, Receiver), WsHandshakeError> {
-		let target: Target = uri.try_into()?;
+		let target = Target::new(uri, self.proxy.is_none())?;
 		self.try_connect(target).await
 	}
 
@@ -253,7 +355,7 @@ impl<'a> WsTransportClientBuilder<'a> {
 		// Only build TLS connector if `wss` in URL.
 		#[cfg(feature = "tls")]
 		let mut connector = match target._mode {
-			Mode::Tls => Some(build_tls_config(&self.certificate_store)?),
+			Mode::Tls => Some(self.tls_connector()?),
 			Mode::Plain => None,
 		};
 
@@ -262,22 +364,35 @@ impl<'a> WsTransportClientBuilder<'a> {
 
 			// The sockaddrs might get reused if the server replies with a relative URI.
 			let sockaddrs = std::mem::take(&mut target.sockaddrs);
-			for sockaddr in &sockaddrs {
+			// Behind a proxy, the host name is resolved by the proxy.
+			let endpoints: Vec<Endpoint> = match &self.proxy {
+				Some(proxy) => vec![Endpoint::Proxy(proxy)],
+				None => sockaddrs.iter().copied().map(Endpoint::Direct).collect(),
+			};
+			for endpoint in endpoints {
 				#[cfg(feature = "tls")]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout, &target.host, connector.as_ref()).await {
+				let tcp_stream = match connect(
+					endpoint,
+					self.connection_timeout,
+					&self.tcp,
+					&target,
+					connector.as_ref().map(|connector| (connector, self.certificate_pins.as_slice())),
+				)
+				.await
+				{
 					Ok(stream) => stream,
 					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
+						tracing::debug!("Failed to connect to {:?}", endpoint);
 						err = Some(Err(e));
 						continue;
 					}
 				};
 
 				#[cfg(not(feature = "tls"))]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout).await {
+				let tcp_stream = match connect(endpoint, self.connection_timeout, &self.tcp, &target).await {
 					Ok(stream) => stream,
 					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
+						tracing::debug!("Failed to connect to {:?}", endpoint);
 						err = Some(Err(e));
 						continue;
 					}
@@ -290,6 +405,10 @@ impl<'a> WsTransportClientBuilder<'a> {
 				);
 
 				client.set_headers(&self.headers);
+				if let Some(config) = self.compression {
+					let max_message_len = self.max_request_body_size as usize;
+					client.add_extension(Box::new(Deflate::new(connection::Mode::Client, config, max_message_len)));
+				}
 
 				// Perform the initial handshake.
 				match client.handshake().await {
@@ -312,7 +431,7 @@ impl<'a> WsTransportClientBuilder<'a> {
 							Ok(uri) => {
 								// Absolute URI.
 								if uri.scheme().is_some() {
-									target = uri.try_into().map_err(|e| {
+									target = Target::new(uri, self.proxy.is_none()).map_err(|e| {
 										tracing::error!("Redirection failed: {:?}", e);
 										e
 									})?;
@@ -321,7 +440,7 @@ impl<'a> WsTransportClientBuilder<'a> {
 									#[cfg(feature = "tls")]
 									match target._mode {
 										Mode::Tls if connector.is_none() => {
-											connector = Some(build_tls_config(&self.certificate_store)?);
+											connector = Some(self.tls_connector()?);
 										}
 										Mode::Tls => (),
 										// Drop connector if it was configured previously.
@@ -369,28 +488,59 @@ impl<'a> WsTransportClientBuilder<'a> {
 		}
 		err.unwrap_or(Err(WsHandshakeError::NoAddressFound(target.host)))
 	}
+
+	#[cfg(feature = "tls")]
+	fn tls_connector(&self) -> Result<tokio_rustls::TlsConnector, WsHandshakeError> {
+		match &self.tls_config {
+			Some(config) => Ok(config.clone().into()),
+			None => build_tls_config(&self.certificate_store),
+		}
+	}
+}
+
+/// Remote end of the TCP connection.
+#[derive(Debug, Clone, Copy)]
+enum Endpoint<'a> {
+	/// One of the resolved addresses of the server.
+	Direct(SocketAddr),
+	/// A proxy, which relays the connection to the server.
+	Proxy(&'a Proxy),
+}
+
+async fn open_socket(endpoint: Endpoint<'_>, tcp: &TcpSettings, target: &Target) -> Result<TcpStream, WsHandshakeError> {
+	let mut socket = match endpoint {
+		Endpoint::Direct(sockaddr) => TcpStream::connect(sockaddr).await?,
+		Endpoint::Proxy(proxy) => TcpStream::connect(proxy.addr()).await?,
+	};
+	if let Err(err) = tcp.apply(&socket) {
+		tracing::warn!("configuring the socket failed: {:?}", err);
+	}
+	if let Endpoint::Proxy(proxy) = endpoint {
+		proxy.tunnel(&mut socket, &target.host, target.port).await?;
+	}
+	Ok(socket)
 }
 
 #[cfg(feature = "tls")]
 async fn connect(
-	sockaddr: SocketAddr,
+	endpoint: Endpoint<'_>,
 	timeout_dur: Duration,
-	host: &str,
-	tls_connector: Option<&tokio_rustls::TlsConnector>,
+	tcp: &TcpSettings,
+	target: &Target,
+	tls: Option<(&tokio_rustls::TlsConnector, &[CertificatePin])>,
 ) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
+	let socket = open_socket(endpoint, tcp, target);
 	let timeout = tokio::time::sleep(timeout_dur);
 	tokio::select! {
 		socket = socket => {
 			let socket = socket?;
-			if let Err(err) = socket.set_nodelay(true) {
-				tracing::warn!("set nodelay failed: {:?}", err);
-			}
-			match tls_connector {
+			match tls {
 				None => Ok(EitherStream::Plain(socket)),
-				Some(connector) => {
-					let server_name: tokio_rustls::rustls::ServerName = host.try_into().map_err(|e| WsHandshakeError::Url(format!("Invalid host: {} {:?}", host, e).into()))?;
+				Some((connector, pins)) => {
+					let host = &target.host;
+					let server_name: tokio_rustls::rustls::ServerName = host.as_str().try_into().map_err(|e| WsHandshakeError::Url(format!("Invalid host: {} {:?}", host, e).into()))?;
 					let tls_stream = connector.connect(server_name, socket).await?;
+					pinning::verify_pins(pins, tls_stream.get_ref().1.peer_certificates())?;
 					Ok(EitherStream::Tls(tls_stream))
 				}
 			}
@@ -400,16 +550,17 @@ async fn connect(
 }
 
 #[cfg(not(feature = "tls"))]
-async fn connect(sockaddr: SocketAddr, timeout_dur: Duration) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
+async fn connect(
+	endpoint: Endpoint<'_>,
+	timeout_dur: Duration,
+	tcp: &TcpSettings,
+	target: &Target,
+) -> Result<EitherStream, WsHandshakeError> {
+	let socket = open_socket(endpoint, tcp, target);
 	let timeout = tokio::time::sleep(timeout_dur);
 	tokio::select! {
 		socket = socket => {
-			let socket = socket?;
-			if let Err(err) = socket.set_nodelay(true) {
-				tracing::warn!("set nodelay failed: {:?}", err);
-			}
-			Ok(EitherStream::Plain(socket))
+			Ok(EitherStream::Plain(socket?))
 		}
 		_ = timeout => Err(WsHandshakeError::Timeout(timeout_dur))
 	}
@@ -447,6 +598,8 @@ pub struct Target {
 	sockaddrs: Vec<SocketAddr>,
 	/// The host name (domain or IP address).
 	host: String,
+	/// The port number.
+	port: u16,
 	/// The Host request header specifies the host and port number of the server to which the request is being sent.
 	host_header: String,
 	/// WebSocket stream mode, see [`Mode`] for further documentation.
@@ -459,6 +612,13 @@ impl TryFrom<Uri> for Target {
 	type Error = WsHandshakeError;
 
 	fn try_from(uri: Uri) -> Result<Self, Self::Error> {
+		Self::new(uri, true)
+	}
+}
+
+impl Target {
+	/// Parses `uri`, resolving the socket addresses of its host name if `resolve` is true.
+	fn new(uri: Uri, resolve: bool) -> Result<Self, WsHandshakeError> {
 		let _mode = match uri.scheme_str() {
 			Some("ws") => Mode::Plain,
 			#[cfg(feature = "tls")]
@@ -479,10 +639,14 @@ impl TryFrom<Uri> for Target {
 		let host_header = format!("{}:{}", host, port);
 		let parts = uri.into_parts();
 		let path_and_query = parts.path_and_query.ok_or_else(|| WsHandshakeError::Url("No path in URL".into()))?;
-		let sockaddrs = host_header.to_socket_addrs().map_err(WsHandshakeError::ResolutionFailed)?;
+		let sockaddrs = match resolve {
+			true => host_header.to_socket_addrs().map_err(WsHandshakeError::ResolutionFailed)?.collect(),
+			false => Vec::new(),
+		};
 		Ok(Self {
-			sockaddrs: sockaddrs.collect(),
+			sockaddrs,
 			host,
+			port,
 			host_header,
 			_mode,
 			path_and_query: path_and_query.to_string(),
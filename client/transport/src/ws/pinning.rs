@@ -0,0 +1,165 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Pinning of the certificate of the server.
+
+use tokio_rustls::rustls::Certificate;
+
+use super::WsHandshakeError;
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_EXPLICIT_VERSION: u8 = 0xa0;
+
+/// Pin of the certificate of the server, checked after the TLS handshake in addition to the usual validation
+/// of the certificate chain.
+///
+/// The pin must match the end-entity certificate presented by the server, otherwise the connection fails
+/// with [`WsHandshakeError::CertificatePinMismatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificatePin {
+	/// SHA-256 hash of the DER encoded certificate.
+	Certificate([u8; 32]),
+	/// SHA-256 hash of the DER encoded `SubjectPublicKeyInfo` of the certificate, which still matches after the
+	/// certificate is renewed with the same key.
+	PublicKey([u8; 32]),
+}
+
+impl CertificatePin {
+	fn matches(&self, cert: &[u8]) -> bool {
+		match self {
+			Self::Certificate(hash) => sha256(cert) == *hash,
+			Self::PublicKey(hash) => subject_public_key_info(cert).is_some_and(|spki| sha256(spki) == *hash),
+		}
+	}
+}
+
+/// Checks that the end-entity certificate of the server matches one of `pins`, if any.
+pub(crate) fn verify_pins(pins: &[CertificatePin], certs: Option<&[Certificate]>) -> Result<(), WsHandshakeError> {
+	if pins.is_empty() {
+		return Ok(());
+	}
+	match certs.and_then(|certs| certs.first()) {
+		Some(cert) if pins.iter().any(|pin| pin.matches(&cert.0)) => Ok(()),
+		_ => Err(WsHandshakeError::CertificatePinMismatch),
+	}
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+	ring::digest::digest(&ring::digest::SHA256, data).as_ref().try_into().expect("SHA-256 hashes are 32 bytes; qed")
+}
+
+/// DER encoded element.
+struct DerElement<'a> {
+	tag: u8,
+	/// The whole element, including its tag and length.
+	raw: &'a [u8],
+	content: &'a [u8],
+	/// Bytes following the element.
+	rest: &'a [u8],
+}
+
+fn parse_der(data: &[u8]) -> Option<DerElement<'_>> {
+	let (&tag, rest) = data.split_first()?;
+	let (&len_byte, rest) = rest.split_first()?;
+	let (len, rest) = if len_byte < 0x80 {
+		(len_byte as usize, rest)
+	} else {
+		let len_len = (len_byte & 0x7f) as usize;
+		if len_len == 0 || len_len > 4 || rest.len() < len_len {
+			return None;
+		}
+		let len = rest[..len_len].iter().fold(0, |len, &b| (len << 8) | b as usize);
+		(len, &rest[len_len..])
+	};
+	if rest.len() < len {
+		return None;
+	}
+	let header_len = data.len() - rest.len();
+	Some(DerElement { tag, raw: &data[..header_len + len], content: &rest[..len], rest: &rest[len..] })
+}
+
+/// Extracts the `SubjectPublicKeyInfo` of a DER encoded X.509 certificate.
+fn subject_public_key_info(cert: &[u8]) -> Option<&[u8]> {
+	let cert = parse_der(cert)?;
+	let tbs_certificate = parse_der(cert.content)?;
+
+	let mut field = parse_der(tbs_certificate.content)?;
+	if field.tag == DER_EXPLICIT_VERSION {
+		field = parse_der(field.rest)?;
+	}
+	// Skip the serial number, the signature algorithm, the issuer, the validity and the subject.
+	for _ in 0..5 {
+		field = parse_der(field.rest)?;
+	}
+
+	(field.tag == DER_SEQUENCE).then_some(field.raw)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{subject_public_key_info, verify_pins, CertificatePin};
+	use tokio_rustls::rustls::Certificate;
+
+	// Self-signed certificate for `localhost` with a P-256 key.
+	const CERT: &str = "MIIBfjCCASOgAwIBAgIUEKMzfCTZVE8KxQL8EJLbbSUGA/4wCgYIKoZIzj0EAwIwFDESMBAGA1UEAwwJbG9jYWxob3N0MB4XDTI2MTAxODA4NDQ1MloXDTM2MTAxNTA4NDQ1MlowFDESMBAGA1UEAwwJbG9jYWxob3N0MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEUaXdTGyc8kc4m1ryhLrTdUiDGMiHV2jQew7W4KWAH9808m2wZ1dkUliNoM0URXjZRDhCajWl4yK7ntgHFw7YXKNTMFEwHQYDVR0OBBYEFCClaMtdH8zKVdPaHv4SYT6QrH3EMB8GA1UdIwQYMBaAFCClaMtdH8zKVdPaHv4SYT6QrH3EMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIhAO8WqUHIgID7l4UcXbinsA63I2p/fFfTsZ570j81RJTVAiEAlCMPeZIW4qgrq6Q0OtU5l3HOII/FKmi/pkJfQfGq/Zk=";
+	const CERT_SHA256: &str = "0dc7d74fb08622e4fce7be5235cc1479361fabcdec952ba943f0e75114e67c5f";
+	const SPKI_SHA256: &str = "238c9a297e5ed596477ecf61f273f5fcf1e93198aa34fe91866315e726cafeaf";
+
+	fn hash(hex: &str) -> [u8; 32] {
+		let mut hash = [0; 32];
+		for (i, byte) in hash.iter_mut().enumerate() {
+			*byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+		}
+		hash
+	}
+
+	#[test]
+	fn subject_public_key_info_is_extracted() {
+		let cert = base64::decode(CERT).unwrap();
+		let spki = subject_public_key_info(&cert).unwrap();
+		// `SEQUENCE` of the algorithm identifiers and of the 65 bytes uncompressed P-256 point.
+		assert_eq!(spki.len(), 91);
+		assert_eq!(spki[0], 0x30);
+		assert!(subject_public_key_info(&cert[..100]).is_none());
+	}
+
+	#[test]
+	fn pins_are_verified() {
+		let certs = [Certificate(base64::decode(CERT).unwrap())];
+
+		assert!(verify_pins(&[], None).is_ok());
+		assert!(verify_pins(&[CertificatePin::Certificate(hash(CERT_SHA256))], Some(&certs)).is_ok());
+		assert!(verify_pins(&[CertificatePin::PublicKey(hash(SPKI_SHA256))], Some(&certs)).is_ok());
+
+		let wrong = [CertificatePin::Certificate(hash(SPKI_SHA256)), CertificatePin::PublicKey(hash(CERT_SHA256))];
+		assert!(verify_pins(&wrong, Some(&certs)).is_err());
+		assert!(verify_pins(&[CertificatePin::Certificate(hash(CERT_SHA256))], None).is_err());
+
+		// Any matching pin is enough, for instance during a key rotation.
+		let rotation = [CertificatePin::PublicKey(hash(CERT_SHA256)), CertificatePin::PublicKey(hash(SPKI_SHA256))];
+		assert!(verify_pins(&rotation, Some(&certs)).is_ok());
+	}
+}
@@ -0,0 +1,250 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tunnelling of the connection through a SOCKS5 or an HTTP proxy.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use jsonrpsee_core::Cow;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::WsHandshakeError;
+
+const SOCKS5_VERSION: u8 = 5;
+const SOCKS5_NO_AUTH: u8 = 0;
+const SOCKS5_USERNAME_PASSWORD: u8 = 2;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS5_CONNECT: u8 = 1;
+const SOCKS5_IPV4: u8 = 1;
+const SOCKS5_DOMAIN_NAME: u8 = 3;
+const SOCKS5_IPV6: u8 = 4;
+
+/// Maximum length of the response head of an HTTP proxy.
+const MAX_HTTP_RESPONSE_HEAD_LEN: usize = 8 * 1024;
+
+/// Proxy through which the connection to the server is established.
+///
+/// The host name of the server is resolved by the proxy, which makes it possible to reach
+/// hosts that can't be resolved locally, such as Tor onion services.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Proxy {
+	kind: ProxyKind,
+	addr: String,
+	credentials: Option<(String, String)>,
+}
+
+// Keeps the password out of the logs.
+impl fmt::Debug for Proxy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Proxy")
+			.field("kind", &self.kind)
+			.field("addr", &self.addr)
+			.field("username", &self.credentials.as_ref().map(|(username, _)| username))
+			.finish()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProxyKind {
+	Socks5,
+	HttpConnect,
+}
+
+impl Proxy {
+	/// SOCKS5 proxy listening on `addr` (`host:port`), for instance `127.0.0.1:9050` for Tor.
+	pub fn socks5(addr: impl Into<String>) -> Self {
+		Self { kind: ProxyKind::Socks5, addr: addr.into(), credentials: None }
+	}
+
+	/// HTTP proxy listening on `addr` (`host:port`), which tunnels the connection after a `CONNECT` request.
+	pub fn http(addr: impl Into<String>) -> Self {
+		Self { kind: ProxyKind::HttpConnect, addr: addr.into(), credentials: None }
+	}
+
+	/// Authenticate to the proxy with a username and a password (default is no authentication).
+	///
+	/// SOCKS5 proxies are offered the username/password method, HTTP proxies receive a `Proxy-Authorization`
+	/// header with basic credentials.
+	pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+		self.credentials = Some((username.into(), password.into()));
+		self
+	}
+
+	/// Address of the proxy.
+	pub(crate) fn addr(&self) -> &str {
+		&self.addr
+	}
+
+	/// Asks the proxy, connected to through `stream`, to relay the connection to `host:port`.
+	pub(crate) async fn tunnel<S>(&self, stream: &mut S, host: &str, port: u16) -> Result<(), WsHandshakeError>
+	where
+		S: AsyncRead + AsyncWrite + Unpin,
+	{
+		match self.kind {
+			ProxyKind::Socks5 => self.socks5_tunnel(stream, host, port).await,
+			ProxyKind::HttpConnect => self.http_tunnel(stream, host, port).await,
+		}
+	}
+
+	async fn socks5_tunnel<S>(&self, stream: &mut S, host: &str, port: u16) -> Result<(), WsHandshakeError>
+	where
+		S: AsyncRead + AsyncWrite + Unpin,
+	{
+		if self.credentials.is_some() {
+			stream.write_all(&[SOCKS5_VERSION, 2, SOCKS5_NO_AUTH, SOCKS5_USERNAME_PASSWORD]).await?;
+		} else {
+			stream.write_all(&[SOCKS5_VERSION, 1, SOCKS5_NO_AUTH]).await?;
+		}
+
+		let mut reply = [0; 2];
+		stream.read_exact(&mut reply).await?;
+		if reply[0] != SOCKS5_VERSION {
+			return Err(proxy_error("Invalid SOCKS5 reply"));
+		}
+		match (reply[1], &self.credentials) {
+			(SOCKS5_NO_AUTH, _) => (),
+			(SOCKS5_USERNAME_PASSWORD, Some((username, password))) => {
+				let mut request = vec![1];
+				push_len_prefixed(&mut request, username)?;
+				push_len_prefixed(&mut request, password)?;
+				stream.write_all(&request).await?;
+
+				let mut reply = [0; 2];
+				stream.read_exact(&mut reply).await?;
+				if reply[1] != 0 {
+					return Err(proxy_error("SOCKS5 authentication failed"));
+				}
+			}
+			(SOCKS5_NO_ACCEPTABLE_METHOD, _) => return Err(proxy_error("No acceptable SOCKS5 authentication method")),
+			(method, _) => return Err(proxy_error(format!("Unexpected SOCKS5 authentication method: {}", method))),
+		}
+
+		let mut request = vec![SOCKS5_VERSION, SOCKS5_CONNECT, 0];
+		match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+			Ok(IpAddr::V4(ip)) => {
+				request.push(SOCKS5_IPV4);
+				request.extend_from_slice(&ip.octets());
+			}
+			Ok(IpAddr::V6(ip)) => {
+				request.push(SOCKS5_IPV6);
+				request.extend_from_slice(&ip.octets());
+			}
+			Err(_) => {
+				request.push(SOCKS5_DOMAIN_NAME);
+				push_len_prefixed(&mut request, host)?;
+			}
+		}
+		request.extend_from_slice(&port.to_be_bytes());
+		stream.write_all(&request).await?;
+
+		let mut reply = [0; 4];
+		stream.read_exact(&mut reply).await?;
+		if reply[0] != SOCKS5_VERSION {
+			return Err(proxy_error("Invalid SOCKS5 reply"));
+		}
+		if reply[1] != 0 {
+			return Err(proxy_error(format!("SOCKS5 connection failed: {}", socks5_reply_message(reply[1]))));
+		}
+
+		// The address the proxy bound to is of no use, but must be consumed.
+		let addr_len = match reply[3] {
+			SOCKS5_IPV4 => 4,
+			SOCKS5_IPV6 => 16,
+			SOCKS5_DOMAIN_NAME => {
+				let mut len = [0; 1];
+				stream.read_exact(&mut len).await?;
+				len[0] as usize
+			}
+			_ => return Err(proxy_error("Invalid SOCKS5 reply")),
+		};
+		let mut bound_addr = vec![0; addr_len + 2];
+		stream.read_exact(&mut bound_addr).await?;
+
+		Ok(())
+	}
+
+	async fn http_tunnel<S>(&self, stream: &mut S, host: &str, port: u16) -> Result<(), WsHandshakeError>
+	where
+		S: AsyncRead + AsyncWrite + Unpin,
+	{
+		let authority = format!("{}:{}", host, port);
+		let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+		if let Some((username, password)) = &self.credentials {
+			let credentials = base64::encode(format!("{}:{}", username, password));
+			request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+		}
+		request.push_str("\r\n");
+		stream.write_all(request.as_bytes()).await?;
+
+		// Read the response head byte by byte, to not consume what the server sends after it.
+		let mut head = Vec::new();
+		while !head.ends_with(b"\r\n\r\n") {
+			if head.len() >= MAX_HTTP_RESPONSE_HEAD_LEN {
+				return Err(proxy_error("Response of the HTTP proxy is too large"));
+			}
+			head.push(stream.read_u8().await?);
+		}
+
+		let status_line = head.split(|&b| b == b'\r').next().unwrap_or_default();
+		let status_code = std::str::from_utf8(status_line).ok().and_then(|line| {
+			let mut parts = line.split(' ');
+			parts.next().filter(|version| version.starts_with("HTTP/1."))?;
+			parts.next()?.parse::<u16>().ok()
+		});
+
+		match status_code {
+			Some(code) if (200..300).contains(&code) => Ok(()),
+			Some(code) => Err(proxy_error(format!("HTTP proxy refused the tunnel with status code {}", code))),
+			None => Err(proxy_error("Invalid response of the HTTP proxy")),
+		}
+	}
+}
+
+fn push_len_prefixed(buf: &mut Vec<u8>, value: &str) -> Result<(), WsHandshakeError> {
+	let len = u8::try_from(value.len()).map_err(|_| proxy_error(format!("`{}` is too long for SOCKS5", value)))?;
+	buf.push(len);
+	buf.extend_from_slice(value.as_bytes());
+	Ok(())
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+	match code {
+		1 => "general failure",
+		2 => "connection not allowed by ruleset",
+		3 => "network unreachable",
+		4 => "host unreachable",
+		5 => "connection refused",
+		6 => "TTL expired",
+		7 => "command not supported",
+		8 => "address type not supported",
+		_ => "unknown error",
+	}
+}
+
+fn proxy_error(msg: impl Into<Cow<'static, str>>) -> WsHandshakeError {
+	WsHandshakeError::Proxy(msg.into())
+}
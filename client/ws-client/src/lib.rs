@@ -34,16 +34,26 @@
 //!
 //! This library uses `tokio` as the runtime and does not support other runtimes.
 
+mod reconnecting;
 #[cfg(test)]
 mod tests;
 
+pub use jsonrpsee_client_transport::ws::{DeflateConfig, Proxy, TcpKeepalive};
 pub use jsonrpsee_core::client::Client as WsClient;
+pub use jsonrpsee_core::client::{
+	ClientMiddleware, OverflowPolicy, RequestLimitPolicy, RetryOn, RetryPolicy, SubscriptionBuffer,
+};
+pub use reconnecting::{ConnectionEvent, ReconnectPolicy, ReconnectingWsClient};
 pub use jsonrpsee_types as types;
+#[cfg(feature = "tls")]
+pub use jsonrpsee_client_transport::ws::{rustls, CertificatePin};
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use jsonrpsee_client_transport::ws::{Header, InvalidUri, Uri, WsTransportClientBuilder};
 use jsonrpsee_core::client::{CertificateStore, ClientBuilder, IdKind};
+use jsonrpsee_core::tcp::TcpSettings;
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
 
 /// Builder for [`WsClient`].
@@ -74,11 +84,23 @@ pub struct WsClientBuilder<'a> {
 	request_timeout: Duration,
 	connection_timeout: Duration,
 	ping_interval: Option<Duration>,
+	pong_timeout: Option<Duration>,
+	retry_policy: Option<RetryPolicy>,
+	middleware: Option<Arc<dyn ClientMiddleware>>,
 	headers: Vec<Header<'a>>,
 	max_concurrent_requests: usize,
+	request_limit_policy: RequestLimitPolicy,
 	max_notifs_per_subscription: usize,
+	subscription_overflow_policy: OverflowPolicy,
 	max_redirections: usize,
 	id_kind: IdKind,
+	tcp: TcpSettings,
+	compression: Option<DeflateConfig>,
+	proxy: Option<Proxy>,
+	#[cfg(feature = "tls")]
+	tls_config: Option<std::sync::Arc<rustls::ClientConfig>>,
+	#[cfg(feature = "tls")]
+	certificate_pins: Vec<CertificatePin>,
 }
 
 impl<'a> Default for WsClientBuilder<'a> {
@@ -89,11 +111,23 @@ impl<'a> Default for WsClientBuilder<'a> {
 			request_timeout: Duration::from_secs(60),
 			connection_timeout: Duration::from_secs(10),
 			ping_interval: None,
+			pong_timeout: None,
+			retry_policy: None,
+			middleware: None,
 			headers: Vec::new(),
 			max_concurrent_requests: 256,
+			request_limit_policy: RequestLimitPolicy::Reject,
 			max_notifs_per_subscription: 1024,
+			subscription_overflow_policy: OverflowPolicy::Close,
 			max_redirections: 5,
 			id_kind: IdKind::Number,
+			tcp: TcpSettings { nodelay: Some(true), ..Default::default() },
+			compression: None,
+			proxy: None,
+			#[cfg(feature = "tls")]
+			tls_config: None,
+			#[cfg(feature = "tls")]
+			certificate_pins: Vec::new(),
 		}
 	}
 }
@@ -105,6 +139,20 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::tls_config`] (default is built from the certificate store).
+	#[cfg(feature = "tls")]
+	pub fn tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls_config = Some(std::sync::Arc::new(config));
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::pin_certificate`] (default is none).
+	#[cfg(feature = "tls")]
+	pub fn pin_certificate(mut self, pin: CertificatePin) -> Self {
+		self.certificate_pins.push(pin);
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::max_request_body_size`] (default is 10 MB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
@@ -129,6 +177,24 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation [`ClientBuilder::pong_timeout`] (disabled by default).
+	pub fn pong_timeout(mut self, timeout: Duration) -> Self {
+		self.pong_timeout = Some(timeout);
+		self
+	}
+
+	/// See documentation [`ClientBuilder::retry_policy`] (disabled by default).
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry_policy = Some(policy);
+		self
+	}
+
+	/// See documentation [`ClientBuilder::set_middleware`] (disabled by default).
+	pub fn set_middleware(mut self, middleware: impl ClientMiddleware) -> Self {
+		self.middleware = Some(Arc::new(middleware));
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::add_header`] (default is none).
 	pub fn add_header(mut self, name: &'a str, value: &'a str) -> Self {
 		self.headers.push(Header { name, value: value.as_bytes() });
@@ -141,12 +207,24 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation [`ClientBuilder::request_limit_policy`] (default is to reject the calls).
+	pub fn request_limit_policy(mut self, policy: RequestLimitPolicy) -> Self {
+		self.request_limit_policy = policy;
+		self
+	}
+
 	/// See documentation [`ClientBuilder::max_notifs_per_subscription`] (default is 1024).
 	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
 		self.max_notifs_per_subscription = max;
 		self
 	}
 
+	/// See documentation [`ClientBuilder::subscription_overflow_policy`] (default is to close the subscription).
+	pub fn subscription_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+		self.subscription_overflow_policy = policy;
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::max_redirections`] (default is 5).
 	pub fn max_redirections(mut self, redirect: usize) -> Self {
 		self.max_redirections = redirect;
@@ -159,6 +237,48 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::compression`] (default is disabled).
+	pub fn compression(mut self, config: DeflateConfig) -> Self {
+		self.compression = Some(config);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::proxy`] (default is a direct connection).
+	pub fn proxy(mut self, proxy: Proxy) -> Self {
+		self.proxy = Some(proxy);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tcp_nodelay`] (default is enabled).
+	pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+		self.tcp.nodelay = Some(enabled);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tcp_keepalive`] (default is the operating system default).
+	pub fn tcp_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
+		self.tcp.keepalive = Some(keepalive);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tcp_send_buffer_size`] (default is the operating system default).
+	pub fn tcp_send_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.send_buffer_size = Some(size);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tcp_recv_buffer_size`] (default is the operating system default).
+	pub fn tcp_recv_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tcp_linger`] (default is the operating system default).
+	pub fn tcp_linger(mut self, linger: Option<Duration>) -> Self {
+		self.tcp.linger = Some(linger);
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// You must provide the port number in the URL.
 	///
@@ -166,12 +286,22 @@ impl<'a> WsClientBuilder<'a> {
 	///
 	/// Panics if being called outside of `tokio` runtime context.
 	pub async fn build(self, url: impl AsRef<str>) -> Result<WsClient, Error> {
+		// The transport may be built with TLS support by another crate even if the `tls` feature is disabled here.
+		#[allow(clippy::needless_update)]
 		let transport_builder = WsTransportClientBuilder {
 			certificate_store: self.certificate_store,
 			connection_timeout: self.connection_timeout,
 			headers: self.headers,
 			max_request_body_size: self.max_request_body_size,
 			max_redirections: self.max_redirections,
+			tcp: self.tcp,
+			compression: self.compression,
+			proxy: self.proxy,
+			#[cfg(feature = "tls")]
+			tls_config: self.tls_config,
+			#[cfg(feature = "tls")]
+			certificate_pins: self.certificate_pins,
+			..Default::default()
 		};
 
 		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
@@ -179,14 +309,46 @@ impl<'a> WsClientBuilder<'a> {
 
 		let mut client = ClientBuilder::default()
 			.max_notifs_per_subscription(self.max_notifs_per_subscription)
+			.subscription_overflow_policy(self.subscription_overflow_policy)
 			.request_timeout(self.request_timeout)
 			.max_concurrent_requests(self.max_concurrent_requests)
+			.request_limit_policy(self.request_limit_policy)
 			.id_format(self.id_kind);
 
 		if let Some(interval) = self.ping_interval {
 			client = client.ping_interval(interval);
 		}
 
+		if let Some(timeout) = self.pong_timeout {
+			client = client.pong_timeout(timeout);
+		}
+
+		if let Some(policy) = self.retry_policy {
+			client = client.retry_policy(policy);
+		}
+
+		if let Some(middleware) = self.middleware {
+			client = client.set_middleware(middleware);
+		}
+
 		Ok(client.build_with_tokio(sender, receiver))
 	}
 }
+
+impl WsClientBuilder<'static> {
+	/// Build a client with specified URL to connect to, which re-establishes the connection according to
+	/// `policy` whenever it is lost. See [`ReconnectingWsClient`] for details.
+	///
+	/// Fails if the first connection can't be established.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build_reconnecting(
+		self,
+		url: impl Into<String>,
+		policy: ReconnectPolicy,
+	) -> Result<ReconnectingWsClient, Error> {
+		ReconnectingWsClient::connect(self, url.into(), policy).await
+	}
+}
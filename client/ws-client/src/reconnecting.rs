@@ -0,0 +1,481 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! WebSocket client re-establishing its connection when lost.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_channel::{mpsc, oneshot};
+use futures_util::StreamExt;
+use jsonrpsee_core::client::{
+	notification_channel, BatchRequestBuilder, BatchResponse, ClientT, FrontToBack, NotificationSender, Subscription,
+	SubscriptionBuffer, SubscriptionClientT,
+};
+use jsonrpsee_core::{Error, JsonValue};
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+use tokio::sync::watch;
+
+use crate::{WsClient, WsClientBuilder};
+
+/// When and how often a lost connection is re-established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+	initial_delay: Duration,
+	max_delay: Duration,
+	max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+	fn default() -> Self {
+		Self { initial_delay: Duration::from_millis(500), max_delay: Duration::from_secs(30), max_attempts: None }
+	}
+}
+
+impl ReconnectPolicy {
+	/// Create the default policy, which retries forever, doubling the delay between the attempts from 500
+	/// milliseconds up to 30 seconds.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the delay before the first attempt to reconnect (default is 500 milliseconds), which doubles with every
+	/// failed attempt.
+	pub fn initial_delay(mut self, delay: Duration) -> Self {
+		self.initial_delay = delay;
+		self
+	}
+
+	/// Set the maximum delay between two attempts to reconnect (default is 30 seconds).
+	pub fn max_delay(mut self, delay: Duration) -> Self {
+		self.max_delay = delay;
+		self
+	}
+
+	/// Give up after `attempts` failed attempts in a row (default is to never give up).
+	pub fn max_attempts(mut self, attempts: u32) -> Self {
+		self.max_attempts = Some(attempts);
+		self
+	}
+
+	fn delay(&self, attempt: u32) -> Duration {
+		self.initial_delay.saturating_mul(2_u32.saturating_pow(attempt)).min(self.max_delay)
+	}
+}
+
+/// Change of the connectivity of a [`ReconnectingWsClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+	/// The connection was lost for the given reason, the client is reconnecting.
+	Disconnected(String),
+	/// The connection was re-established after the given number of attempts, the subscriptions are replayed.
+	Reconnected(u32),
+	/// Reconnecting failed too many times in a row, the client is closed for good.
+	GaveUp,
+}
+
+#[derive(Debug, Clone)]
+enum State {
+	Connected(Arc<WsClient>),
+	Reconnecting,
+	Closed,
+}
+
+#[derive(Debug)]
+struct Shared {
+	state: watch::Sender<State>,
+	listeners: Mutex<Vec<mpsc::UnboundedSender<ConnectionEvent>>>,
+}
+
+impl Shared {
+	fn emit(&self, event: ConnectionEvent) {
+		self.listeners.lock().expect("lock poisoned; qed").retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+	}
+}
+
+/// [`WsClient`] re-establishing its connection with an exponential backoff whenever it is lost, as configured by a
+/// [`ReconnectPolicy`].
+///
+/// The subscriptions made through it are replayed once reconnected, so that they keep yielding notifications
+/// across the outage, although the notifications sent by the server meanwhile are lost.
+///
+/// Calls made while the client is reconnecting fail right away with [`Error::Reconnecting`], and so do the calls
+/// pending when the connection was lost. The changes of connectivity are reported to the receivers of
+/// [`ReconnectingWsClient::events`].
+///
+/// Dropping it closes the connection and ends the subscriptions.
+#[derive(Debug)]
+pub struct ReconnectingWsClient {
+	shared: Arc<Shared>,
+	/// Settings of the buffer of the subscriptions made with [`SubscriptionClientT::subscribe`].
+	subscription_buffer: SubscriptionBuffer,
+	/// Stops the task re-establishing the connection when dropped.
+	_stop: oneshot::Sender<()>,
+}
+
+impl ReconnectingWsClient {
+	pub(crate) async fn connect(
+		builder: WsClientBuilder<'static>,
+		url: String,
+		policy: ReconnectPolicy,
+	) -> Result<Self, Error> {
+		let subscription_buffer =
+			SubscriptionBuffer::new(builder.max_notifs_per_subscription, builder.subscription_overflow_policy);
+		let client = Arc::new(builder.clone().build(&url).await?);
+		let (state, _) = watch::channel(State::Connected(client.clone()));
+		let shared = Arc::new(Shared { state, listeners: Mutex::new(Vec::new()) });
+		let (stop_tx, stop_rx) = oneshot::channel();
+
+		let supervisor = shared.clone();
+		tokio::spawn(async move {
+			reconnect_task(&supervisor, builder, url, policy, client, stop_rx).await;
+			supervisor.state.send_replace(State::Closed);
+		});
+
+		Ok(Self { shared, subscription_buffer, _stop: stop_tx })
+	}
+
+	/// Checks if the client is connected to the target.
+	pub fn is_connected(&self) -> bool {
+		matches!(&*self.shared.state.borrow(), State::Connected(client) if client.is_connected())
+	}
+
+	/// Returns a stream of the changes of connectivity from now on.
+	pub fn events(&self) -> mpsc::UnboundedReceiver<ConnectionEvent> {
+		let (tx, rx) = mpsc::unbounded();
+		self.shared.listeners.lock().expect("lock poisoned; qed").push(tx);
+		rx
+	}
+
+	fn client(&self) -> Result<Arc<WsClient>, Error> {
+		match &*self.shared.state.borrow() {
+			State::Connected(client) if client.is_connected() => Ok(client.clone()),
+			State::Connected(_) | State::Reconnecting => Err(Error::Reconnecting),
+			State::Closed => Err(Error::RestartNeeded("gave up reconnecting".into())),
+		}
+	}
+
+	/// Wrap a subscription made on `client`, replaying it on the next connections.
+	fn replay<Notif>(
+		&self,
+		resubscribe: Resubscribe,
+		client: Arc<WsClient>,
+		subscription: Subscription<JsonValue>,
+	) -> Subscription<Notif> {
+		let kind = subscription.kind().clone();
+		let buffer = match &resubscribe {
+			Resubscribe::Subscription { buffer, .. } => *buffer,
+			Resubscribe::Method(_) => self.subscription_buffer,
+		};
+		let (notifs_tx, notifs_rx) = notification_channel(buffer);
+		let (closed_tx, closed_rx) = mpsc::channel(1);
+
+		tokio::spawn(replay_task(self.shared.clone(), resubscribe, client, subscription, notifs_tx, closed_rx));
+		Subscription::new(closed_tx, notifs_rx, kind)
+	}
+}
+
+/// Blame the failure of a call on the lost connection, if it was lost.
+fn lost(client: &WsClient, err: Error) -> Error {
+	if client.is_connected() {
+		err
+	} else {
+		Error::Reconnecting
+	}
+}
+
+/// Re-establish the connection whenever it's lost, until stopped or giving up.
+async fn reconnect_task(
+	shared: &Shared,
+	builder: WsClientBuilder<'static>,
+	url: String,
+	policy: ReconnectPolicy,
+	mut client: Arc<WsClient>,
+	mut stop: oneshot::Receiver<()>,
+) {
+	loop {
+		let reason = tokio::select! {
+			reason = client.on_disconnect() => reason,
+			_ = &mut stop => return,
+		};
+
+		tracing::warn!("[reconnecting]: connection lost: {}; reconnecting", reason);
+		shared.state.send_replace(State::Reconnecting);
+		shared.emit(ConnectionEvent::Disconnected(reason.to_string()));
+
+		let mut attempts = 0;
+		client = loop {
+			tokio::select! {
+				_ = tokio::time::sleep(policy.delay(attempts)) => {}
+				_ = &mut stop => return,
+			}
+			attempts += 1;
+
+			match builder.clone().build(&url).await {
+				Ok(client) => break Arc::new(client),
+				Err(err) => tracing::debug!("[reconnecting]: attempt {} failed: {}", attempts, err),
+			}
+
+			if policy.max_attempts.is_some_and(|max| attempts >= max) {
+				tracing::warn!("[reconnecting]: giving up after {} attempts", attempts);
+				shared.emit(ConnectionEvent::GaveUp);
+				return;
+			}
+		};
+
+		tracing::info!("[reconnecting]: reconnected after {} attempts", attempts);
+		shared.state.send_replace(State::Connected(client.clone()));
+		shared.emit(ConnectionEvent::Reconnected(attempts));
+	}
+}
+
+/// How to make a subscription again on a new connection.
+#[derive(Debug)]
+enum Resubscribe {
+	Subscription {
+		subscribe_method: String,
+		params: Option<JsonValue>,
+		unsubscribe_method: String,
+		buffer: SubscriptionBuffer,
+	},
+	Method(String),
+}
+
+impl Resubscribe {
+	fn subscription(
+		subscribe_method: &str,
+		params: Option<ParamsSer<'_>>,
+		unsubscribe_method: &str,
+		buffer: SubscriptionBuffer,
+	) -> Result<Self, Error> {
+		Ok(Self::Subscription {
+			subscribe_method: subscribe_method.to_owned(),
+			params: params.map(serde_json::to_value).transpose()?,
+			unsubscribe_method: unsubscribe_method.to_owned(),
+			buffer,
+		})
+	}
+
+	async fn subscribe(&self, client: &WsClient) -> Result<Subscription<JsonValue>, Error> {
+		match self {
+			Self::Subscription { subscribe_method, params, unsubscribe_method, buffer } => {
+				client.subscribe_with_buffer(subscribe_method, params_ser(params), unsubscribe_method, *buffer).await
+			}
+			Self::Method(method) => client.subscribe_to_method(method).await,
+		}
+	}
+}
+
+fn params_ser(params: &Option<JsonValue>) -> Option<ParamsSer<'_>> {
+	match params {
+		Some(JsonValue::Array(params)) => Some(ParamsSer::ArrayRef(params)),
+		Some(JsonValue::Object(params)) => {
+			Some(ParamsSer::Map(params.iter().map(|(name, value)| (name.as_str(), value.clone())).collect()))
+		}
+		_ => None,
+	}
+}
+
+/// Forward the notifications of a subscription, making it again on every new connection until it's closed.
+async fn replay_task(
+	shared: Arc<Shared>,
+	resubscribe: Resubscribe,
+	mut client: Arc<WsClient>,
+	mut subscription: Subscription<JsonValue>,
+	notifs_tx: NotificationSender,
+	mut closed_rx: mpsc::Receiver<FrontToBack>,
+) {
+	let mut state = shared.state.subscribe();
+	drop(shared);
+
+	loop {
+		loop {
+			tokio::select! {
+				notif = subscription.next() => match notif {
+					Some(Ok(notif)) => if notifs_tx.try_send(notif).is_err() {
+						return;
+					},
+					Some(Err(Error::SubscriptionLagged(skipped))) => notifs_tx.skipped(skipped),
+					Some(Err(err @ Error::SubscriptionClosedByServer(_))) => {
+						notifs_tx.close_with_error(err);
+						return;
+					}
+					Some(Err(_)) => continue,
+					None => break,
+				},
+				// Dropping the subscription unsubscribes.
+				_ = closed_rx.next() => return,
+			}
+		}
+
+		// Closed by the server or because the notifications weren't read fast enough.
+		if client.is_connected() {
+			return;
+		}
+
+		subscription = loop {
+			client = match next_client(&mut state, &mut closed_rx).await {
+				Some(client) => client,
+				None => return,
+			};
+
+			match resubscribe.subscribe(&client).await {
+				Ok(subscription) => break subscription,
+				// Lost the new connection already.
+				Err(_) if !client.is_connected() => continue,
+				Err(err) => {
+					tracing::warn!("[reconnecting]: failed to replay subscription {:?}: {}", resubscribe, err);
+					return;
+				}
+			}
+		};
+	}
+}
+
+/// Wait for a live connection, `None` if the client is closed or the subscription dropped meanwhile.
+async fn next_client(
+	state: &mut watch::Receiver<State>,
+	closed_rx: &mut mpsc::Receiver<FrontToBack>,
+) -> Option<Arc<WsClient>> {
+	loop {
+		match state.borrow_and_update().clone() {
+			State::Connected(client) if client.is_connected() => return Some(client),
+			State::Closed => return None,
+			State::Connected(_) | State::Reconnecting => {}
+		}
+
+		tokio::select! {
+			changed = state.changed() => changed.ok()?,
+			_ = closed_rx.next() => return None,
+		}
+	}
+}
+
+#[async_trait]
+impl ClientT for ReconnectingWsClient {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		let client = self.client()?;
+		client.notification(method, params).await.map_err(|err| lost(&client, err))
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let client = self.client()?;
+		client.request(method, params).await.map_err(|err| lost(&client, err))
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		let client = self.client()?;
+		client.batch_request(batch).await.map_err(|err| lost(&client, err))
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		let client = self.client()?;
+		client.batch(batch).await.map_err(|err| lost(&client, err))
+	}
+}
+
+#[async_trait]
+impl SubscriptionClientT for ReconnectingWsClient {
+	async fn subscribe<'a, Notif>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		self.subscribe_with_buffer(subscribe_method, params, unsubscribe_method, self.subscription_buffer).await
+	}
+
+	async fn subscribe_with_buffer<'a, Notif>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+		buffer: SubscriptionBuffer,
+	) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		let client = self.client()?;
+		let resubscribe = Resubscribe::subscription(subscribe_method, params, unsubscribe_method, buffer)?;
+		let subscription = resubscribe.subscribe(&client).await.map_err(|err| lost(&client, err))?;
+
+		Ok(self.replay(resubscribe, client, subscription))
+	}
+
+	async fn batch_subscribe<'a, Notif>(
+		&self,
+		batch: Vec<(&'a str, Option<ParamsSer<'a>>, &'a str)>,
+	) -> Result<Vec<Subscription<Notif>>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		let client = self.client()?;
+		let resubscribes = batch
+			.into_iter()
+			.map(|(subscribe_method, params, unsubscribe_method)| {
+				Resubscribe::subscription(subscribe_method, params, unsubscribe_method, self.subscription_buffer)
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let batch = resubscribes
+			.iter()
+			.filter_map(|resubscribe| match resubscribe {
+				Resubscribe::Subscription { subscribe_method, params, unsubscribe_method, .. } => {
+					Some((subscribe_method.as_str(), params_ser(params), unsubscribe_method.as_str()))
+				}
+				Resubscribe::Method(_) => None,
+			})
+			.collect();
+		let subscriptions = client.batch_subscribe(batch).await.map_err(|err| lost(&client, err))?;
+
+		Ok(resubscribes
+			.into_iter()
+			.zip(subscriptions)
+			.map(|(resubscribe, subscription)| self.replay(resubscribe, client.clone(), subscription))
+			.collect())
+	}
+
+	async fn subscribe_to_method<'a, Notif>(&self, method: &'a str) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		let client = self.client()?;
+		let resubscribe = Resubscribe::Method(method.to_owned());
+		let subscription = resubscribe.subscribe(&client).await.map_err(|err| lost(&client, err))?;
+
+		Ok(self.replay(resubscribe, client, subscription))
+	}
+}
@@ -242,6 +242,32 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_partial_keeps_successful_responses() {
+	let batch_request = vec![("say_hello", None), ("say_goodbye", rpc_params![0_u64, 1, 2]), ("get_swag", None)];
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"here's your swag","id":2}]"#.to_string();
+	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response)
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+	let response: Vec<Result<String, Error>> =
+		client.batch_request_partial(batch_request.clone()).with_default_timeout().await.unwrap().unwrap();
+
+	assert_eq!(response.len(), 3);
+	assert_eq!(response[0].as_ref().unwrap(), "hello");
+	assert!(
+		matches!(&response[1], Err(Error::Call(CallError::Custom(err))) if err.code() == ErrorCode::MethodNotFound.code())
+	);
+	assert_eq!(response[2].as_ref().unwrap(), "here's your swag");
+
+	// `batch_request` fails on the first error.
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+	let err = client.batch_request::<String>(batch_request).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == ErrorCode::MethodNotFound.code()));
+}
+
 #[tokio::test]
 async fn is_connected_works() {
 	let server = WebSocketTestServer::with_hardcoded_response(
@@ -323,3 +349,23 @@ async fn redirections() {
 	let response = client.request::<String>("anything", None).with_default_timeout().await.unwrap();
 	assert_eq!(response.unwrap(), String::from(expected));
 }
+
+#[tokio::test]
+async fn unresponsive_server_is_detected() {
+	let server =
+		WebSocketTestServer::unresponsive("127.0.0.1:0".parse().unwrap()).with_default_timeout().await.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default()
+		.ping_interval(std::time::Duration::from_millis(50))
+		.pong_timeout(std::time::Duration::from_millis(100))
+		.build(&uri)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+	assert!(!client.is_connected());
+	let err: Result<String, Error> = client.request("say_hello", None).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::ConnectionStale)));
+}
@@ -25,32 +25,32 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::client::async_client::manager::{RequestManager, RequestStatus};
-use crate::client::{RequestMessage, TransportSenderT};
+use crate::client::{notification_channel, BatchEntryResponse, RequestMessage, TransportSenderT};
 use crate::Error;
 
-use futures_channel::mpsc;
 use futures_timer::Delay;
 use futures_util::future::{self, Either};
 
-use jsonrpsee_types::error::CallError;
+use jsonrpsee_types::error::{CallError, ErrorObject, SUBSCRIPTION_CLOSED};
 use jsonrpsee_types::response::SubscriptionError;
 use jsonrpsee_types::{
 	ErrorResponse, Id, Notification, ParamsSer, RequestSer, Response, SubscriptionId, SubscriptionResponse,
 };
+use serde_json::value::RawValue;
 use serde_json::Value as JsonValue;
 
 /// Attempts to process a batch response.
 ///
 /// On success the result is sent to the frontend.
-pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Response<JsonValue>>) -> Result<(), Error> {
+pub(crate) fn process_batch_response(
+	manager: &mut RequestManager,
+	rps: Vec<(Id<'static>, BatchEntryResponse)>,
+) -> Result<(), Error> {
 	let mut digest = Vec::with_capacity(rps.len());
-	let mut ordered_responses = vec![JsonValue::Null; rps.len()];
-	let mut rps_unordered: Vec<_> = Vec::with_capacity(rps.len());
+	let mut ordered_responses = vec![Ok(JsonValue::Null); rps.len()];
 
-	for rp in rps {
-		let id = rp.id.into_owned();
+	for (id, _) in &rps {
 		digest.push(id.clone());
-		rps_unordered.push((id, rp.result));
 	}
 
 	digest.sort_unstable();
@@ -62,7 +62,7 @@ pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Resp
 		}
 	};
 
-	for (id, rp) in rps_unordered {
+	for (id, rp) in rps {
 		let pos =
 			batch_state.order.get(&id).copied().expect("All request IDs valid checked by RequestManager above; qed");
 		ordered_responses[pos] = rp;
@@ -71,6 +71,46 @@ pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Resp
 	Ok(())
 }
 
+/// Returns whether `rps` are the responses to a batch of subscription requests.
+pub(crate) fn is_subscription_batch(manager: &mut RequestManager, rps: &[&RawValue]) -> bool {
+	let id = match rps.first() {
+		Some(rp) => match serde_json::from_str::<Response<&RawValue>>(rp.get()) {
+			Ok(rp) => rp.id.into_owned(),
+			Err(_) => match serde_json::from_str::<ErrorResponse>(rp.get()) {
+				Ok(err) => err.id().clone().into_owned(),
+				Err(_) => return false,
+			},
+		},
+		None => return false,
+	};
+
+	matches!(manager.request_status(&id), RequestStatus::PendingSubscription)
+}
+
+/// Attempts to process the responses to a batch of subscription requests, each of which is handled like the
+/// response to a single subscription request.
+///
+/// Returns the unsubscribe messages of the subscriptions that were dropped by the frontend meanwhile.
+/// Returns `Err(_)` if a response couldn't be handled.
+pub(crate) fn process_subscription_batch_response(
+	manager: &mut RequestManager,
+	rps: Vec<&RawValue>,
+) -> Result<Vec<RequestMessage>, Error> {
+	let mut unsubs = Vec::new();
+
+	for rp in rps {
+		if let Ok(response) = serde_json::from_str::<Response<JsonValue>>(rp.get()) {
+			unsubs.extend(process_single_response(manager, response)?);
+		} else if let Ok(err) = serde_json::from_str::<ErrorResponse>(rp.get()) {
+			process_error_response(manager, err)?;
+		} else {
+			return Err(Error::Custom(format!("Unparseable message: {}", rp.get())));
+		}
+	}
+
+	Ok(unsubs)
+}
+
 /// Attempts to process a subscription response.
 ///
 /// Returns `Ok()` if the response was successfully sent to the frontend.
@@ -106,7 +146,8 @@ pub(crate) fn process_subscription_response(
 	}
 }
 
-/// Attempts to close a subscription when a [`SubscriptionError`] is received.
+/// Attempts to close a subscription when a [`SubscriptionError`] is received. If the server gave the error as the
+/// reason it closed the subscription, the subscription yields [`Error::SubscriptionClosedByServer`] as its last item.
 ///
 /// Returns `Ok(())` if the subscription was removed
 /// Return `Err(e)` if the subscription was not found.
@@ -123,7 +164,14 @@ pub(crate) fn process_subscription_close_response(
 		}
 	};
 
-	manager.remove_subscription(request_id, sub_id).expect("Both request ID and sub ID in RequestManager; qed");
+	let (_, sink, _, _) =
+		manager.remove_subscription(request_id, sub_id).expect("Both request ID and sub ID in RequestManager; qed");
+	if response.params.reason {
+		let reason = serde_json::from_value(response.params.error.clone()).unwrap_or_else(|_| {
+			ErrorObject::owned(SUBSCRIPTION_CLOSED, "Subscription closed by the server", Some(response.params.error))
+		});
+		sink.close_with_error(Error::SubscriptionClosedByServer(reason));
+	}
 	Ok(())
 }
 
@@ -137,8 +185,9 @@ pub(crate) fn process_notification(manager: &mut RequestManager, notif: Notifica
 			Ok(()) => Ok(()),
 			Err(err) => {
 				tracing::error!("Error sending notification, dropping handler for {:?} error: {:?}", notif.method, err);
+				let err = Error::Custom(format!("Notification handler for {} dropped: {:?}", notif.method, err));
 				let _ = manager.remove_notification_handler(notif.method.into_owned());
-				Err(Error::Internal(err.into_send_error()))
+				Err(err)
 			}
 		},
 		None => {
@@ -156,7 +205,6 @@ pub(crate) fn process_notification(manager: &mut RequestManager, notif: Notifica
 pub(crate) fn process_single_response(
 	manager: &mut RequestManager,
 	response: Response<JsonValue>,
-	max_capacity_per_subscription: usize,
 ) -> Result<Option<RequestMessage>, Error> {
 	let response_id = response.id.into_owned();
 	match manager.request_status(&response_id) {
@@ -170,7 +218,7 @@ pub(crate) fn process_single_response(
 			Ok(None)
 		}
 		RequestStatus::PendingSubscription => {
-			let (unsub_id, send_back_oneshot, unsubscribe_method) =
+			let (unsub_id, send_back_oneshot, unsubscribe_method, buffer) =
 				manager.complete_pending_subscription(response_id.clone()).ok_or(Error::InvalidRequestId)?;
 
 			let sub_id: Result<SubscriptionId, _> = response.result.try_into();
@@ -182,7 +230,7 @@ pub(crate) fn process_single_response(
 				}
 			};
 
-			let (subscribe_tx, subscribe_rx) = mpsc::channel(max_capacity_per_subscription);
+			let (subscribe_tx, subscribe_rx) = notification_channel(buffer);
 			if manager
 				.insert_subscription(response_id.clone(), unsub_id, sub_id.clone(), subscribe_tx, unsubscribe_method)
 				.is_ok()
@@ -244,7 +292,7 @@ pub(crate) fn process_error_response(manager: &mut RequestManager, err: ErrorRes
 			Ok(())
 		}
 		RequestStatus::PendingSubscription => {
-			let (_, send_back, _) = manager.complete_pending_subscription(id).expect("State checked above; qed");
+			let (_, send_back, _, _) = manager.complete_pending_subscription(id).expect("State checked above; qed");
 			let _ = send_back.send(Err(Error::Call(CallError::Custom(err.error_object().clone().into_owned()))));
 			Ok(())
 		}
@@ -34,8 +34,9 @@
 
 use std::collections::{hash_map::Entry, HashMap};
 
+use crate::client::{BatchEntryResponse, NotificationReceiver, NotificationSender, SubscriptionBuffer};
 use crate::Error;
-use futures_channel::{mpsc, oneshot};
+use futures_channel::oneshot;
 use jsonrpsee_types::{Id, SubscriptionId};
 use rustc_hash::FxHashMap;
 use serde_json::value::Value as JsonValue;
@@ -43,7 +44,7 @@ use serde_json::value::Value as JsonValue;
 #[derive(Debug)]
 enum Kind {
 	PendingMethodCall(PendingCallOneshot),
-	PendingSubscription((RequestId, PendingSubscriptionOneshot, UnsubscribeMethod)),
+	PendingSubscription((RequestId, PendingSubscriptionOneshot, UnsubscribeMethod, SubscriptionBuffer)),
 	Subscription((RequestId, SubscriptionSink, UnsubscribeMethod)),
 }
 
@@ -61,9 +62,9 @@ pub(crate) enum RequestStatus {
 }
 
 type PendingCallOneshot = Option<oneshot::Sender<Result<JsonValue, Error>>>;
-type PendingBatchOneshot = oneshot::Sender<Result<Vec<JsonValue>, Error>>;
-type PendingSubscriptionOneshot = oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId<'static>), Error>>;
-type SubscriptionSink = mpsc::Sender<JsonValue>;
+type PendingBatchOneshot = oneshot::Sender<Result<Vec<BatchEntryResponse>, Error>>;
+type PendingSubscriptionOneshot = oneshot::Sender<Result<(NotificationReceiver, SubscriptionId<'static>), Error>>;
+type SubscriptionSink = NotificationSender;
 type UnsubscribeMethod = String;
 type RequestId = Id<'static>;
 
@@ -143,14 +144,17 @@ impl RequestManager {
 		unsub_req_id: RequestId,
 		send_back: PendingSubscriptionOneshot,
 		unsubscribe_method: UnsubscribeMethod,
+		buffer: SubscriptionBuffer,
 	) -> Result<(), PendingSubscriptionOneshot> {
 		// The request IDs are not in the manager and the `sub_id` and `unsub_id` are not equal.
 		if !self.requests.contains_key(&sub_req_id)
 			&& !self.requests.contains_key(&unsub_req_id)
 			&& sub_req_id != unsub_req_id
 		{
-			self.requests
-				.insert(sub_req_id, Kind::PendingSubscription((unsub_req_id.clone(), send_back, unsubscribe_method)));
+			self.requests.insert(
+				sub_req_id,
+				Kind::PendingSubscription((unsub_req_id.clone(), send_back, unsubscribe_method, buffer)),
+			);
 			self.requests.insert(unsub_req_id, Kind::PendingMethodCall(None));
 			Ok(())
 		} else {
@@ -209,7 +213,7 @@ impl RequestManager {
 	pub(crate) fn complete_pending_subscription(
 		&mut self,
 		request_id: RequestId,
-	) -> Option<(RequestId, PendingSubscriptionOneshot, UnsubscribeMethod)> {
+	) -> Option<(RequestId, PendingSubscriptionOneshot, UnsubscribeMethod, SubscriptionBuffer)> {
 		match self.requests.entry(request_id) {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingSubscription(_)) => {
 				let (_req_id, kind) = request.remove_entry();
@@ -315,7 +319,8 @@ impl RequestManager {
 #[cfg(test)]
 mod tests {
 	use super::{Error, RequestManager};
-	use futures_channel::{mpsc, oneshot};
+	use crate::client::{notification_channel, NotificationReceiver, SubscriptionBuffer};
+	use futures_channel::oneshot;
 	use jsonrpsee_types::{Id, SubscriptionId};
 	use serde_json::Value as JsonValue;
 
@@ -330,13 +335,19 @@ mod tests {
 
 	#[test]
 	fn insert_remove_subscription_works() {
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (sub_tx, _) = notification_channel(SubscriptionBuffer::default());
 		let mut manager = RequestManager::new();
 		assert!(manager
-			.insert_pending_subscription(Id::Number(1), Id::Number(2), pending_sub_tx, "unsubscribe_method".into())
+			.insert_pending_subscription(
+				Id::Number(1),
+				Id::Number(2),
+				pending_sub_tx,
+				"unsubscribe_method".into(),
+				SubscriptionBuffer::default()
+			)
 			.is_ok());
-		let (unsub_req_id, _send_back_oneshot, unsubscribe_method) =
+		let (unsub_req_id, _send_back_oneshot, unsubscribe_method, _buffer) =
 			manager.complete_pending_subscription(Id::Number(1)).unwrap();
 		assert_eq!(unsub_req_id, Id::Number(2));
 		assert!(manager
@@ -357,16 +368,28 @@ mod tests {
 
 	#[test]
 	fn insert_subscription_with_same_sub_and_unsub_id_should_err() {
-		let (tx1, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (tx2, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (tx3, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (tx4, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
+		let (tx1, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (tx2, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (tx3, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (tx4, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
 		let mut manager = RequestManager::new();
 		assert!(manager
-			.insert_pending_subscription(Id::Str("1".into()), Id::Str("1".into()), tx1, "unsubscribe_method".into())
+			.insert_pending_subscription(
+				Id::Str("1".into()),
+				Id::Str("1".into()),
+				tx1,
+				"unsubscribe_method".into(),
+				SubscriptionBuffer::default()
+			)
 			.is_err());
 		assert!(manager
-			.insert_pending_subscription(Id::Str("0".into()), Id::Str("1".into()), tx2, "unsubscribe_method".into())
+			.insert_pending_subscription(
+				Id::Str("0".into()),
+				Id::Str("1".into()),
+				tx2,
+				"unsubscribe_method".into(),
+				SubscriptionBuffer::default()
+			)
 			.is_ok());
 		assert!(
 			manager
@@ -374,7 +397,8 @@ mod tests {
 					Id::Str("99".into()),
 					Id::Str("0".into()),
 					tx3,
-					"unsubscribe_method".into()
+					"unsubscribe_method".into(),
+					SubscriptionBuffer::default()
 				)
 				.is_err(),
 			"unsub request ID already occupied"
@@ -385,7 +409,8 @@ mod tests {
 					Id::Str("99".into()),
 					Id::Str("1".into()),
 					tx4,
-					"unsubscribe_method".into()
+					"unsubscribe_method".into(),
+					SubscriptionBuffer::default()
 				)
 				.is_err(),
 			"sub request ID already occupied"
@@ -396,14 +421,20 @@ mod tests {
 	fn pending_method_call_faulty() {
 		let (request_tx1, _) = oneshot::channel::<Result<JsonValue, Error>>();
 		let (request_tx2, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (sub_tx, _) = notification_channel(SubscriptionBuffer::default());
 
 		let mut manager = RequestManager::new();
 		assert!(manager.insert_pending_call(Id::Number(0), Some(request_tx1)).is_ok());
 		assert!(manager.insert_pending_call(Id::Number(0), Some(request_tx2)).is_err());
 		assert!(manager
-			.insert_pending_subscription(Id::Number(0), Id::Number(1), pending_sub_tx, "beef".to_string())
+			.insert_pending_subscription(
+				Id::Number(0),
+				Id::Number(1),
+				pending_sub_tx,
+				"beef".to_string(),
+				SubscriptionBuffer::default()
+			)
 			.is_err());
 		assert!(manager
 			.insert_subscription(
@@ -423,17 +454,29 @@ mod tests {
 	#[test]
 	fn pending_subscription_faulty() {
 		let (request_tx, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx1, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (pending_sub_tx2, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx1, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (pending_sub_tx2, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (sub_tx, _) = notification_channel(SubscriptionBuffer::default());
 
 		let mut manager = RequestManager::new();
 		assert!(manager
-			.insert_pending_subscription(Id::Number(99), Id::Number(100), pending_sub_tx1, "beef".to_string())
+			.insert_pending_subscription(
+				Id::Number(99),
+				Id::Number(100),
+				pending_sub_tx1,
+				"beef".to_string(),
+				SubscriptionBuffer::default()
+			)
 			.is_ok());
 		assert!(manager.insert_pending_call(Id::Number(99), Some(request_tx)).is_err());
 		assert!(manager
-			.insert_pending_subscription(Id::Number(99), Id::Number(1337), pending_sub_tx2, "vegan".to_string())
+			.insert_pending_subscription(
+				Id::Number(99),
+				Id::Number(1337),
+				pending_sub_tx2,
+				"vegan".to_string(),
+				SubscriptionBuffer::default()
+			)
 			.is_err());
 
 		assert!(manager
@@ -454,9 +497,9 @@ mod tests {
 	#[test]
 	fn active_subscriptions_faulty() {
 		let (request_tx, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx1, _) = mpsc::channel::<JsonValue>(1);
-		let (sub_tx2, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotificationReceiver, SubscriptionId), Error>>();
+		let (sub_tx1, _) = notification_channel(SubscriptionBuffer::default());
+		let (sub_tx2, _) = notification_channel(SubscriptionBuffer::default());
 
 		let mut manager = RequestManager::new();
 
@@ -467,7 +510,13 @@ mod tests {
 			.insert_subscription(Id::Number(3), Id::Number(4), SubscriptionId::Num(1), sub_tx2, "bibimbap".to_string())
 			.is_err());
 		assert!(manager
-			.insert_pending_subscription(Id::Number(3), Id::Number(4), pending_sub_tx, "beef".to_string())
+			.insert_pending_subscription(
+				Id::Number(3),
+				Id::Number(4),
+				pending_sub_tx,
+				"beef".to_string(),
+				SubscriptionBuffer::default()
+			)
 			.is_err());
 		assert!(manager.insert_pending_call(Id::Number(3), Some(request_tx)).is_err());
 
@@ -4,16 +4,19 @@ mod helpers;
 mod manager;
 
 use crate::client::{
-	async_client::helpers::process_subscription_close_response, BatchMessage, ClientT, ReceivedMessage,
-	RegisterNotificationMessage, RequestMessage, Subscription, SubscriptionClientT, SubscriptionKind,
-	SubscriptionMessage, TransportReceiverT, TransportSenderT,
+	async_client::helpers::process_subscription_close_response, batch::parse_batch_response, BatchEntryResponse,
+	BatchMessage, BatchRequestBuilder, BatchResponse, ClientMiddleware, ClientT, MiddlewareCall, ReceivedMessage,
+	RegisterNotificationMessage, RequestMessage, RetryPolicy, Subscription, SubscriptionBuffer, SubscriptionClientT,
+	SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT, notification_channel, OverflowPolicy,
 };
 use crate::tracing::{rx_log_from_json, tx_log_from_str, RpcTracing};
 
 use core::time::Duration;
+use std::sync::Arc;
 use helpers::{
-	build_unsubscribe_message, call_with_timeout, process_batch_response, process_error_response, process_notification,
-	process_single_response, process_subscription_response, stop_subscription,
+	build_unsubscribe_message, call_with_timeout, is_subscription_batch, process_batch_response, process_error_response,
+	process_notification, process_single_response, process_subscription_batch_response, process_subscription_response,
+	stop_subscription,
 };
 use manager::RequestManager;
 
@@ -25,15 +28,17 @@ use futures_timer::Delay;
 use futures_util::future::{self, Either, Fuse};
 use futures_util::sink::SinkExt;
 use futures_util::stream::StreamExt;
+use futures_util::future::FusedFuture;
 use futures_util::FutureExt;
 use jsonrpsee_types::{
-	response::SubscriptionError, ErrorResponse, Id, Notification, NotificationSer, ParamsSer, RequestSer, Response,
+	error::CallError, response::SubscriptionError, ErrorResponse, Id, Notification, NotificationSer, ParamsSer, RequestSer, Response,
 	SubscriptionResponse,
 };
 use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
 use tracing_futures::Instrument;
 
-use super::{FrontToBack, IdKind, RequestIdManager};
+use super::{FrontToBack, IdKind, RequestIdManager, RequestLimitPolicy};
 
 /// Wrapper over a [`oneshot::Receiver`](futures_channel::oneshot::Receiver) that reads
 /// the underlying channel once and then stores the result in String.
@@ -45,6 +50,8 @@ enum ErrorFromBack {
 	Read(String),
 	/// Error message is unread.
 	Unread(oneshot::Receiver<Error>),
+	/// The connection was closed because it was stale.
+	Stale,
 }
 
 impl ErrorFromBack {
@@ -52,6 +59,7 @@ impl ErrorFromBack {
 		match self {
 			Self::Unread(rx) => {
 				let msg = match rx.await {
+					Ok(Error::ConnectionStale) => return (Self::Stale, Error::ConnectionStale),
 					Ok(msg) => msg.to_string(),
 					// This should never happen because the receiving end is still alive.
 					// Would be a bug in the logic of the background task.
@@ -61,6 +69,7 @@ impl ErrorFromBack {
 				(Self::Read(msg), err)
 			}
 			Self::Read(msg) => (Self::Read(msg.clone()), Error::RestartNeeded(msg)),
+			Self::Stale => (Self::Stale, Error::ConnectionStale),
 		}
 	}
 }
@@ -70,10 +79,14 @@ impl ErrorFromBack {
 pub struct ClientBuilder {
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
-	max_notifs_per_subscription: usize,
+	request_limit_policy: RequestLimitPolicy,
+	subscription_buffer: SubscriptionBuffer,
 	id_kind: IdKind,
 	max_log_length: u32,
 	ping_interval: Option<Duration>,
+	pong_timeout: Option<Duration>,
+	retry_policy: Option<RetryPolicy>,
+	middleware: Option<Arc<dyn ClientMiddleware>>,
 }
 
 impl Default for ClientBuilder {
@@ -81,10 +94,14 @@ impl Default for ClientBuilder {
 		Self {
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
-			max_notifs_per_subscription: 1024,
+			request_limit_policy: RequestLimitPolicy::Reject,
+			subscription_buffer: SubscriptionBuffer::default(),
 			id_kind: IdKind::Number,
 			max_log_length: 4096,
 			ping_interval: None,
+			pong_timeout: None,
+			retry_policy: None,
+			middleware: None,
 		}
 	}
 }
@@ -102,17 +119,32 @@ impl ClientBuilder {
 		self
 	}
 
-	/// Set max concurrent notification capacity for each subscription; when the capacity is exceeded the subscription
-	/// will be dropped (default is 1024).
+	/// Set what to do with the calls made while [`max_concurrent_requests`](ClientBuilder::max_concurrent_requests)
+	/// calls are pending (default is to reject them with [`Error::MaxSlotsExceeded`]), see
+	/// [`RequestLimitPolicy`](crate::client::RequestLimitPolicy).
+	pub fn request_limit_policy(mut self, policy: RequestLimitPolicy) -> Self {
+		self.request_limit_policy = policy;
+		self
+	}
+
+	/// Set max concurrent notification capacity for each subscription; when the capacity is exceeded the
+	/// [`subscription_overflow_policy`](ClientBuilder::subscription_overflow_policy) applies (default is 1024).
 	///
-	/// You may prevent the subscription from being dropped by polling often enough
+	/// You may prevent the buffer from overflowing by polling often enough
 	/// [`Subscription::next()`](../../jsonrpsee_core/client/struct.Subscription.html#method.next) such that
 	/// it can keep with the rate as server produces new items on the subscription.
-	///
-	/// **Note**: The actual capacity is `num_senders + max_subscription_capacity`
-	/// because it is passed to [`futures_channel::mpsc::channel`].
 	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
-		self.max_notifs_per_subscription = max;
+		self.subscription_buffer.capacity = max;
+		self
+	}
+
+	/// Set what to do with the notifications received while the buffer of a subscription is full (default is to
+	/// close the subscription), see [`OverflowPolicy`](crate::client::OverflowPolicy).
+	///
+	/// This applies to the subscriptions made with [`SubscriptionClientT::subscribe`], the ones made with
+	/// [`SubscriptionClientT::subscribe_with_buffer`] have their own settings.
+	pub fn subscription_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+		self.subscription_buffer.policy = policy;
 		self
 	}
 
@@ -146,6 +178,31 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Close the connection if nothing, not even a pong, is received within `timeout` of submitting a ping
+	/// (disabled by default). Only applies if a [`ping_interval`](ClientBuilder::ping_interval) is set.
+	///
+	/// Calls made once the connection was closed fail with [`Error::ConnectionStale`], so that a dead
+	/// connection is detected without waiting for the request timeouts.
+	pub fn pong_timeout(mut self, timeout: Duration) -> Self {
+		self.pong_timeout = Some(timeout);
+		self
+	}
+
+	/// Retry failed method calls according to `policy` (disabled by default), see
+	/// [`RetryPolicy`](crate::client::RetryPolicy).
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry_policy = Some(policy);
+		self
+	}
+
+	/// Report the calls made by the client and the notifications it receives to `middleware` (disabled by
+	/// default), see [`ClientMiddleware`](crate::client::ClientMiddleware).
+	#[cfg(not(target_arch = "wasm32"))]
+	pub fn set_middleware(mut self, middleware: impl ClientMiddleware) -> Self {
+		self.middleware = Some(Arc::new(middleware));
+		self
+	}
+
 	/// Build the client with given transport.
 	///
 	/// ## Panics
@@ -160,18 +217,32 @@ impl ClientBuilder {
 	{
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
-		let max_notifs_per_subscription = self.max_notifs_per_subscription;
 		let ping_interval = self.ping_interval;
+		let pong_timeout = self.pong_timeout;
+		let middleware = self.middleware.clone();
 
 		tokio::spawn(async move {
-			background_task(sender, receiver, from_front, err_tx, max_notifs_per_subscription, ping_interval).await;
+			background_task(
+				sender,
+				receiver,
+				from_front,
+				err_tx,
+				ping_interval,
+				pong_timeout,
+				middleware,
+			)
+			.await;
 		});
 		Client {
 			to_back,
 			request_timeout: self.request_timeout,
 			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
-			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind)
+				.limit_policy(self.request_limit_policy),
 			max_log_length: self.max_log_length,
+			retry_policy: self.retry_policy,
+			middleware: self.middleware,
+			subscription_buffer: self.subscription_buffer,
 		}
 	}
 
@@ -185,17 +256,21 @@ impl ClientBuilder {
 	{
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
-		let max_notifs_per_subscription = self.max_notifs_per_subscription;
+		let middleware = self.middleware.clone();
 
 		wasm_bindgen_futures::spawn_local(async move {
-			background_task(sender, receiver, from_front, err_tx, max_notifs_per_subscription, None).await;
+			background_task(sender, receiver, from_front, err_tx, None, None, middleware).await;
 		});
 		Client {
 			to_back,
 			request_timeout: self.request_timeout,
 			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
-			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind)
+				.limit_policy(self.request_limit_policy),
 			max_log_length: self.max_log_length,
+			retry_policy: self.retry_policy,
+			middleware: self.middleware,
+			subscription_buffer: self.subscription_buffer,
 		}
 	}
 }
@@ -216,6 +291,12 @@ pub struct Client {
 	///
 	/// Entries bigger than this limit will be truncated.
 	max_log_length: u32,
+	/// Policy of retrying failed method calls.
+	retry_policy: Option<RetryPolicy>,
+	/// Middleware the calls are reported to.
+	middleware: Option<Arc<dyn ClientMiddleware>>,
+	/// Settings of the buffer of the subscriptions made with [`SubscriptionClientT::subscribe`].
+	subscription_buffer: SubscriptionBuffer,
 }
 
 impl Client {
@@ -224,6 +305,11 @@ impl Client {
 		!self.to_back.is_closed()
 	}
 
+	/// Completes once the connection to the target was closed, returning the reason.
+	pub async fn on_disconnect(&self) -> Error {
+		self.read_error_from_backend().await
+	}
+
 	// Reads the error message from the backend thread.
 	async fn read_error_from_backend(&self) -> Error {
 		let mut err_lock = self.error.lock().await;
@@ -232,46 +318,18 @@ impl Client {
 		*err_lock = next_state;
 		err
 	}
-}
 
-impl Drop for Client {
-	fn drop(&mut self) {
-		self.to_back.close_channel();
-	}
-}
-
-#[async_trait]
-impl ClientT for Client {
-	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
-		// NOTE: we use this to guard against max number of concurrent requests.
-		let _req_id = self.id_manager.next_request_id()?;
-		let notif = NotificationSer::new(method, params);
-		let trace = RpcTracing::batch();
-		let _enter = trace.span().enter();
-
-		let raw = serde_json::to_string(&notif).map_err(Error::ParseError)?;
-		tx_log_from_str(&raw, self.max_log_length);
-
-		let mut sender = self.to_back.clone();
-		let fut = sender.send(FrontToBack::Notification(raw)).in_current_span();
-
-		match future::select(fut, Delay::new(self.request_timeout)).await {
-			Either::Left((Ok(()), _)) => Ok(()),
-			Either::Left((Err(_), _)) => Err(self.read_error_from_backend().await),
-			Either::Right((_, _)) => Err(Error::RequestTimeout),
-		}
-	}
-
-	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	async fn request_once<R>(&self, method: &str, params: Option<ParamsSer<'_>>) -> Result<R, Error>
 	where
 		R: DeserializeOwned,
 	{
 		let (send_back_tx, send_back_rx) = oneshot::channel();
-		let guard = self.id_manager.next_request_id()?;
+		let guard = self.id_manager.acquire_request_id().await?;
 		let id = guard.inner();
 		let trace = RpcTracing::method_call(method);
 		let _enter = trace.span().enter();
 
+		let call = MiddlewareCall::new(self.middleware.as_deref(), method, params.as_ref(), &id);
 		let raw = serde_json::to_string(&RequestSer::new(&id, method, params)).map_err(Error::ParseError)?;
 		tx_log_from_str(&raw, self.max_log_length);
 
@@ -294,20 +352,24 @@ impl ClientT for Client {
 
 		rx_log_from_json(&Response::new(&json_value, id), self.max_log_length);
 
-		serde_json::from_value(json_value).map_err(Error::ParseError)
+		let res = serde_json::from_value(json_value).map_err(Error::ParseError);
+		call.complete(res.is_ok());
+		res
 	}
 
-	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
-	where
-		R: DeserializeOwned + Default + Clone,
-	{
-		let guard = self.id_manager.next_request_ids(batch.len())?;
+	async fn batch_request_once(
+		&self,
+		batch: Vec<(&str, Option<ParamsSer<'_>>)>,
+	) -> Result<Vec<BatchEntryResponse>, Error> {
+		let guard = self.id_manager.acquire_request_ids(batch.len()).await?;
 		let batch_ids: Vec<Id> = guard.inner();
 		let mut batches = Vec::with_capacity(batch.len());
+		let mut calls = Vec::with_capacity(batch.len());
 		let log = RpcTracing::batch();
 		let _enter = log.span().enter();
 
 		for (idx, (method, params)) in batch.into_iter().enumerate() {
+			calls.push(MiddlewareCall::new(self.middleware.as_deref(), method, params.as_ref(), &batch_ids[idx]));
 			batches.push(RequestSer::new(&batch_ids[idx], method, params));
 		}
 
@@ -328,17 +390,114 @@ impl ClientT for Client {
 		}
 
 		let res = call_with_timeout(self.request_timeout, send_back_rx).in_current_span().await;
-		let json_values = match res {
+		let responses = match res {
 			Ok(Ok(v)) => v,
 			Ok(Err(err)) => return Err(err),
 			Err(_) => return Err(self.read_error_from_backend().await),
 		};
 
-		rx_log_from_json(&json_values, self.max_log_length);
+		rx_log_from_json(&responses, self.max_log_length);
+
+		for (call, rp) in calls.into_iter().zip(&responses) {
+			call.complete(rp.is_ok());
+		}
+
+		Ok(responses)
+	}
+
+	/// Sends `batch`, retrying it according to the retry policy of the client.
+	async fn batch_request_with_retries(
+		&self,
+		batch: Vec<(&str, Option<ParamsSer<'_>>)>,
+	) -> Result<Vec<BatchEntryResponse>, Error> {
+		let mut attempts = 0;
+
+		loop {
+			attempts += 1;
+			let err = match self.batch_request_once(batch.clone()).await {
+				Err(err) => err,
+				res => return res,
+			};
+
+			let methods = batch.iter().map(|(method, _)| *method);
+			match self.retry_policy.as_ref().and_then(|policy| policy.retry_batch_after(methods, attempts, &err)) {
+				Some(backoff) => {
+					tracing::debug!("Retrying batch in {:?}: {}", backoff, err);
+					Delay::new(backoff).await;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+}
 
-		let values: Result<_, _> =
-			json_values.into_iter().map(|val| serde_json::from_value(val).map_err(Error::ParseError)).collect();
-		Ok(values?)
+impl Drop for Client {
+	fn drop(&mut self) {
+		self.to_back.close_channel();
+	}
+}
+
+#[async_trait]
+impl ClientT for Client {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		// NOTE: we use this to guard against max number of concurrent requests.
+		let _req_id = self.id_manager.acquire_request_id().await?;
+		let notif = NotificationSer::new(method, params);
+		let trace = RpcTracing::batch();
+		let _enter = trace.span().enter();
+
+		let raw = serde_json::to_string(&notif).map_err(Error::ParseError)?;
+		tx_log_from_str(&raw, self.max_log_length);
+
+		let mut sender = self.to_back.clone();
+		let fut = sender.send(FrontToBack::Notification(raw)).in_current_span();
+
+		match future::select(fut, Delay::new(self.request_timeout)).await {
+			Either::Left((Ok(()), _)) => Ok(()),
+			Either::Left((Err(_), _)) => Err(self.read_error_from_backend().await),
+			Either::Right((_, _)) => Err(Error::RequestTimeout),
+		}
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let mut attempts = 0;
+
+		loop {
+			attempts += 1;
+			let err = match self.request_once(method, params.clone()).await {
+				Err(err) => err,
+				res => return res,
+			};
+
+			match self.retry_policy.as_ref().and_then(|policy| policy.retry_after(method, attempts, &err)) {
+				Some(backoff) => {
+					tracing::debug!("Retrying call to `{}` in {:?}: {}", method, backoff, err);
+					Delay::new(backoff).await;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.batch_request_with_retries(batch)
+			.await?
+			.into_iter()
+			.map(|rp| match rp {
+				Ok(val) => serde_json::from_value(val).map_err(Error::ParseError),
+				Err(err) => Err(Error::Call(CallError::Custom(err))),
+			})
+			.collect()
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		self.batch_request_with_retries(batch.into_calls()).await.map(BatchResponse::new)
 	}
 }
 
@@ -354,6 +513,20 @@ impl SubscriptionClientT for Client {
 		params: Option<ParamsSer<'a>>,
 		unsubscribe_method: &'a str,
 	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.subscribe_with_buffer(subscribe_method, params, unsubscribe_method, self.subscription_buffer).await
+	}
+
+	/// Send a subscription request to the server, buffering up to `buffer.capacity` of its notifications.
+	async fn subscribe_with_buffer<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+		buffer: SubscriptionBuffer,
+	) -> Result<Subscription<N>, Error>
 	where
 		N: DeserializeOwned,
 	{
@@ -361,13 +534,14 @@ impl SubscriptionClientT for Client {
 			return Err(Error::SubscriptionNameConflict(unsubscribe_method.to_owned()));
 		}
 
-		let guard = self.id_manager.next_request_ids(2)?;
+		let guard = self.id_manager.acquire_request_ids(2).await?;
 		let mut ids: Vec<Id> = guard.inner();
 		let trace = RpcTracing::method_call(subscribe_method);
 		let _enter = trace.span().enter();
 
 		let id = ids[0].clone();
 
+		let call = MiddlewareCall::new(self.middleware.as_deref(), subscribe_method, params.as_ref(), &id);
 		let raw = serde_json::to_string(&RequestSer::new(&id, subscribe_method, params)).map_err(Error::ParseError)?;
 
 		tx_log_from_str(&raw, self.max_log_length);
@@ -382,6 +556,7 @@ impl SubscriptionClientT for Client {
 				unsubscribe_id: ids.swap_remove(0),
 				unsubscribe_method: unsubscribe_method.to_owned(),
 				send_back: send_back_tx,
+				buffer,
 			}))
 			.await
 			.is_err()
@@ -398,10 +573,92 @@ impl SubscriptionClientT for Client {
 		};
 
 		rx_log_from_json(&Response::new(&sub_id, id), self.max_log_length);
+		call.complete(true);
 
 		Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Subscription(sub_id)))
 	}
 
+	/// Send several subscription requests to the server in a single batch.
+	///
+	/// If any of the subscriptions fails, the other ones are closed.
+	async fn batch_subscribe<'a, N>(
+		&self,
+		batch: Vec<(&'a str, Option<ParamsSer<'a>>, &'a str)>,
+	) -> Result<Vec<Subscription<N>>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		if let Some((_, _, unsubscribe_method)) = batch.iter().find(|(sub, _, unsub)| sub == unsub) {
+			return Err(Error::SubscriptionNameConflict((*unsubscribe_method).to_owned()));
+		}
+
+		let guard = self.id_manager.acquire_request_ids(batch.len() * 2).await?;
+		let ids: Vec<Id> = guard.inner();
+		let trace = RpcTracing::batch();
+		let _enter = trace.span().enter();
+
+		let mut subscriptions = Vec::with_capacity(batch.len());
+		let mut pending = Vec::with_capacity(batch.len());
+		let mut calls = Vec::with_capacity(batch.len());
+
+		for ((subscribe_method, params, unsubscribe_method), ids) in batch.into_iter().zip(ids.chunks(2)) {
+			calls.push(MiddlewareCall::new(self.middleware.as_deref(), subscribe_method, params.as_ref(), &ids[0]));
+			let raw =
+				serde_json::to_string(&RequestSer::new(&ids[0], subscribe_method, params)).map_err(Error::ParseError)?;
+			tx_log_from_str(&raw, self.max_log_length);
+
+			let (send_back_tx, send_back_rx) = oneshot::channel();
+			subscriptions.push(SubscriptionMessage {
+				raw,
+				subscribe_id: ids[0].clone(),
+				unsubscribe_id: ids[1].clone(),
+				unsubscribe_method: unsubscribe_method.to_owned(),
+				send_back: send_back_tx,
+				buffer: self.subscription_buffer,
+			});
+			pending.push(send_back_rx);
+		}
+
+		if self.to_back.clone().send(FrontToBack::BatchSubscribe(subscriptions)).await.is_err() {
+			return Err(self.read_error_from_backend().await);
+		}
+
+		let responses = match future::select(future::join_all(pending), Delay::new(self.request_timeout)).await {
+			Either::Left((responses, _)) => responses,
+			Either::Right((_, _)) => return Err(Error::RequestTimeout),
+		};
+
+		if responses.iter().any(Result::is_err) {
+			return Err(self.read_error_from_backend().await);
+		}
+
+		let mut subscriptions = Vec::with_capacity(responses.len());
+		let mut error = None;
+
+		for ((res, ids), call) in responses.into_iter().flatten().zip(ids.chunks(2)).zip(calls) {
+			call.complete(res.is_ok());
+			match res {
+				Ok((notifs_rx, sub_id)) => {
+					rx_log_from_json(&Response::new(&sub_id, ids[0].clone()), self.max_log_length);
+					subscriptions.push(Subscription::new(
+						self.to_back.clone(),
+						notifs_rx,
+						SubscriptionKind::Subscription(sub_id),
+					));
+				}
+				Err(err) => {
+					error.get_or_insert(err);
+				}
+			}
+		}
+
+		// Dropping the subscriptions that succeeded closes them.
+		match error {
+			Some(err) => Err(err),
+			None => Ok(subscriptions),
+		}
+	}
+
 	/// Subscribe to a specific method.
 	async fn subscribe_to_method<'a, N>(&self, method: &'a str) -> Result<Subscription<N>, Error>
 	where
@@ -414,6 +671,7 @@ impl SubscriptionClientT for Client {
 			.send(FrontToBack::RegisterNotification(RegisterNotificationMessage {
 				send_back: send_back_tx,
 				method: method.to_owned(),
+				buffer: self.subscription_buffer,
 			}))
 			.await
 			.is_err()
@@ -440,18 +698,18 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 	message: Option<Result<ReceivedMessage, R::Error>>,
 	manager: &mut RequestManager,
 	sender: &mut S,
-	max_notifs_per_subscription: usize,
+	middleware: Option<&dyn ClientMiddleware>,
 ) -> Result<(), Error> {
 	// Handle raw messages of form `ReceivedMessage::Bytes` (Vec<u8>) or ReceivedMessage::Data` (String).
 	async fn handle_recv_message<S: TransportSenderT>(
 		raw: &[u8],
 		manager: &mut RequestManager,
 		sender: &mut S,
-		max_notifs_per_subscription: usize,
+		middleware: Option<&dyn ClientMiddleware>,
 	) -> Result<(), Error> {
 		// Single response to a request.
 		if let Ok(single) = serde_json::from_slice::<Response<_>>(&raw) {
-			match process_single_response(manager, single, max_notifs_per_subscription) {
+			match process_single_response(manager, single) {
 				Ok(Some(unsub)) => {
 					stop_subscription(sender, manager, unsub).await;
 				}
@@ -461,6 +719,9 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 		}
 		// Subscription response.
 		else if let Ok(response) = serde_json::from_slice::<SubscriptionResponse<_>>(&raw) {
+			if let Some(middleware) = middleware {
+				middleware.on_notification(&response.method);
+			}
 			if let Err(Some(unsub)) = process_subscription_response(manager, response) {
 				let _ = stop_subscription(sender, manager, unsub).await;
 			}
@@ -471,10 +732,21 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 		}
 		// Incoming Notification
 		else if let Ok(notif) = serde_json::from_slice::<Notification<_>>(&raw) {
+			if let Some(middleware) = middleware {
+				middleware.on_notification(&notif.method);
+			}
 			let _ = process_notification(manager, notif);
 		}
+		// Responses to a batch of subscription requests, which may contain errors.
+		else if let Some(batch) =
+			serde_json::from_slice::<Vec<&RawValue>>(raw).ok().filter(|batch| is_subscription_batch(manager, batch))
+		{
+			for unsub in process_subscription_batch_response(manager, batch)? {
+				stop_subscription(sender, manager, unsub).await;
+			}
+		}
 		// Batch response.
-		else if let Ok(batch) = serde_json::from_slice::<Vec<Response<_>>>(&raw) {
+		else if let Some(batch) = parse_batch_response(raw) {
 			if let Err(e) = process_batch_response(manager, batch) {
 				return Err(e);
 			}
@@ -504,10 +776,10 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 			tracing::debug!("recv pong");
 		}
 		Some(Ok(ReceivedMessage::Bytes(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription).await?;
+			handle_recv_message(raw.as_ref(), manager, sender, middleware).await?;
 		}
 		Some(Ok(ReceivedMessage::Text(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription).await?;
+			handle_recv_message(raw.as_ref(), manager, sender, middleware).await?;
 		}
 		Some(Err(e)) => {
 			tracing::error!("Error: {:?} terminating client", e);
@@ -529,7 +801,6 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 	message: Option<FrontToBack>,
 	manager: &mut RequestManager,
 	sender: &mut S,
-	max_notifs_per_subscription: usize,
 ) -> Result<(), Error> {
 	match message {
 		// User dropped the sender side of the channel.
@@ -572,6 +843,7 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 					sub.unsubscribe_id,
 					sub.send_back,
 					sub.unsubscribe_method,
+					sub.buffer,
 				)
 				.expect("Request ID unused checked above; qed"),
 			Err(e) => {
@@ -579,6 +851,33 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 				let _ = sub.send_back.send(Err(Error::Transport(e.into())));
 			}
 		},
+		// User called `batch_subscribe` on the front-end.
+		Some(FrontToBack::BatchSubscribe(subs)) => {
+			let raw = format!("[{}]", subs.iter().map(|sub| sub.raw.as_str()).collect::<Vec<_>>().join(","));
+
+			match sender.send(raw).await {
+				Ok(_) => {
+					for sub in subs {
+						manager
+							.insert_pending_subscription(
+								sub.subscribe_id,
+								sub.unsubscribe_id,
+								sub.send_back,
+								sub.unsubscribe_method,
+								sub.buffer,
+							)
+							.expect("Request ID unused checked above; qed");
+					}
+				}
+				Err(e) => {
+					tracing::warn!("[backend]: client batch subscription failed: {:?}", e);
+					// The frontend gives up on the whole batch after the first error.
+					if let Some(sub) = subs.into_iter().next() {
+						let _ = sub.send_back.send(Err(Error::Transport(e.into())));
+					}
+				}
+			}
+		}
 		// User dropped a subscription.
 		Some(FrontToBack::SubscriptionClosed(sub_id)) => {
 			tracing::trace!("Closing subscription: {:?}", sub_id);
@@ -593,7 +892,7 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 		}
 		// User called `register_notification` on the front-end.
 		Some(FrontToBack::RegisterNotification(reg)) => {
-			let (subscribe_tx, subscribe_rx) = mpsc::channel(max_notifs_per_subscription);
+			let (subscribe_tx, subscribe_rx) = notification_channel(reg.buffer);
 
 			if manager.insert_notification_handler(&reg.method, subscribe_tx).is_ok() {
 				let _ = reg.send_back.send(Ok((subscribe_rx, reg.method)));
@@ -616,8 +915,9 @@ async fn background_task<S, R>(
 	receiver: R,
 	mut frontend: mpsc::Receiver<FrontToBack>,
 	front_error: oneshot::Sender<Error>,
-	max_notifs_per_subscription: usize,
 	ping_interval: Option<Duration>,
+	pong_timeout: Option<Duration>,
+	middleware: Option<Arc<dyn ClientMiddleware>>,
 ) where
 	S: TransportSenderT,
 	R: TransportReceiverT,
@@ -636,6 +936,8 @@ async fn background_task<S, R>(
 	let next_frontend = frontend.next();
 	let next_backend = backend_event.next();
 	let mut message_fut = future::select(next_frontend, next_backend);
+	// Expires if nothing is received in time after submitting a ping, terminated while no ping is unanswered.
+	let mut pong_deadline = Fuse::<Delay>::terminated();
 
 	loop {
 		// Create either a valid delay fuse triggered every provided `duration`,
@@ -647,13 +949,10 @@ async fn background_task<S, R>(
 			Fuse::<Delay>::terminated()
 		};
 
-		match future::select(message_fut, submit_ping).await {
+		match future::select(message_fut, future::select(submit_ping, &mut pong_deadline)).await {
 			// Message received from the frontend.
 			Either::Left((Either::Left((frontend_value, backend)), _)) => {
-				if let Err(err) =
-					handle_frontend_messages(frontend_value, &mut manager, &mut sender, max_notifs_per_subscription)
-						.await
-				{
+				if let Err(err) = handle_frontend_messages(frontend_value, &mut manager, &mut sender).await {
 					tracing::warn!("{:?}", err);
 					let _ = front_error.send(err);
 					break;
@@ -663,11 +962,14 @@ async fn background_task<S, R>(
 			}
 			// Message received from the backend.
 			Either::Left((Either::Right((backend_value, frontend)), _)) => {
+				// Anything received shows that the connection is alive.
+				pong_deadline = Fuse::terminated();
+
 				if let Err(err) = handle_backend_messages::<S, R>(
 					backend_value,
 					&mut manager,
 					&mut sender,
-					max_notifs_per_subscription,
+					middleware.as_deref(),
 				)
 				.await
 				{
@@ -679,16 +981,27 @@ async fn background_task<S, R>(
 				message_fut = future::select(frontend, backend_event.next());
 			}
 			// Submit ping interval was triggered if enabled.
-			Either::Right((_, next_message_fut)) => {
+			Either::Right((Either::Left(_), next_message_fut)) => {
 				if let Err(e) = sender.send_ping().await {
 					tracing::warn!("[backend]: client send ping failed: {:?}", e);
 					let _ = front_error.send(Error::Custom("Could not send ping frame".into()));
 					break;
 				}
+				if let (Some(timeout), true) = (pong_timeout, pong_deadline.is_terminated()) {
+					pong_deadline = Delay::new(timeout).fuse();
+				}
 				message_fut = next_message_fut;
 			}
+			// Nothing was received in time after submitting a ping.
+			Either::Right((Either::Right(_), _)) => {
+				tracing::warn!("[backend]: no pong received in time; terminate client");
+				let _ = front_error.send(Error::ConnectionStale);
+				break;
+			}
 		};
 	}
+	// Mark the client as disconnected before the subscriptions are dropped with the manager.
+	frontend.close();
 	// Send close message to the server.
 	let _ = sender.close().await;
 }
@@ -0,0 +1,222 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Batches of calls returning different types
+//!
+//! A [`BatchRequestBuilder`] collects calls which may each return a different type, and is sent with
+//! [`ClientT::batch`](super::ClientT::batch). Each call answered with an error only fails its own entry of the
+//! [`BatchResponse`], the other responses can still be read.
+//!
+//! ```no_run
+//! use jsonrpsee_core::client::{BatchRequestBuilder, ClientT};
+//! use jsonrpsee_core::rpc_params;
+//!
+//! async fn balance_and_height(client: &impl ClientT) -> Result<(), jsonrpsee_core::Error> {
+//!     let mut batch = BatchRequestBuilder::new();
+//!     let balance = batch.insert::<String>("eth_getBalance", rpc_params!["0x407d73d8a49eeb85d32cf465507dd71d507100c1"]);
+//!     let height = batch.insert::<u64>("eth_blockNumber", None);
+//!
+//!     let responses = client.batch(batch).await?;
+//!     println!("balance: {:?}, height: {:?}", responses.get(&balance), responses.get(&height));
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use jsonrpsee_types::error::{CallError, ErrorObjectOwned};
+use jsonrpsee_types::{ErrorResponse, Id, ParamsSer, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+
+use crate::Error;
+
+/// Response to one of the calls of a batch, either its result or the error object returned by the server.
+pub type BatchEntryResponse = Result<JsonValue, ErrorObjectOwned>;
+
+/// Builder of a batch request whose calls may return different types.
+#[derive(Debug, Clone, Default)]
+pub struct BatchRequestBuilder<'a> {
+	calls: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+}
+
+impl<'a> BatchRequestBuilder<'a> {
+	/// Creates an empty batch.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a call to `method` to the batch, whose result is read from the [`BatchResponse`] with the returned
+	/// [`BatchEntry`].
+	pub fn insert<T: DeserializeOwned>(&mut self, method: &'a str, params: Option<ParamsSer<'a>>) -> BatchEntry<T> {
+		self.calls.push((method, params));
+		BatchEntry { index: self.calls.len() - 1, _marker: PhantomData }
+	}
+
+	/// Number of calls in the batch.
+	pub fn len(&self) -> usize {
+		self.calls.len()
+	}
+
+	/// Whether the batch has no calls.
+	pub fn is_empty(&self) -> bool {
+		self.calls.is_empty()
+	}
+
+	/// Returns the calls of the batch, in the order they were inserted.
+	pub fn into_calls(self) -> Vec<(&'a str, Option<ParamsSer<'a>>)> {
+		self.calls
+	}
+}
+
+/// Call inserted in a [`BatchRequestBuilder`], whose result is of type `T`.
+pub struct BatchEntry<T> {
+	index: usize,
+	_marker: PhantomData<fn() -> T>,
+}
+
+impl<T> BatchEntry<T> {
+	/// Position of the call in the batch.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+}
+
+impl<T> Clone for BatchEntry<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for BatchEntry<T> {}
+
+impl<T> fmt::Debug for BatchEntry<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BatchEntry").field("index", &self.index).finish()
+	}
+}
+
+/// Responses to a batch request, in the order of its calls.
+#[derive(Debug, Clone)]
+pub struct BatchResponse {
+	responses: Vec<BatchEntryResponse>,
+}
+
+impl BatchResponse {
+	/// Creates the responses to a batch from the response to each of its calls, in order.
+	pub fn new(responses: Vec<BatchEntryResponse>) -> Self {
+		Self { responses }
+	}
+
+	/// Reads the result of the call `entry`, failing with [`Error::Call`] if the server answered it with an error.
+	///
+	/// # Panics
+	///
+	/// Panics if `entry` comes from another batch with more calls.
+	pub fn get<T: DeserializeOwned>(&self, entry: &BatchEntry<T>) -> Result<T, Error> {
+		match &self.responses[entry.index] {
+			Ok(result) => T::deserialize(result).map_err(Error::ParseError),
+			Err(err) => Err(Error::Call(CallError::Custom(err.clone()))),
+		}
+	}
+
+	/// Number of responses, which is the number of calls in the batch.
+	pub fn len(&self) -> usize {
+		self.responses.len()
+	}
+
+	/// Whether the batch had no calls.
+	pub fn is_empty(&self) -> bool {
+		self.responses.is_empty()
+	}
+
+	/// Number of calls answered with an error.
+	pub fn num_failed(&self) -> usize {
+		self.responses.iter().filter(|response| response.is_err()).count()
+	}
+
+	/// Returns the response to each call, in order.
+	pub fn into_inner(self) -> Vec<BatchEntryResponse> {
+		self.responses
+	}
+}
+
+/// Parses the response of the server to a batch request into the id and the response of each call, which may be
+/// in any order. Returns `None` if `raw` isn't a batch response.
+pub fn parse_batch_response(raw: &[u8]) -> Option<Vec<(Id<'static>, BatchEntryResponse)>> {
+	let entries: Vec<&RawValue> = serde_json::from_slice(raw).ok()?;
+	entries
+		.into_iter()
+		.map(|entry| {
+			if let Ok(response) = serde_json::from_str::<Response<JsonValue>>(entry.get()) {
+				Some((response.id.into_owned(), Ok(response.result)))
+			} else {
+				let err = ErrorResponse::deserialize(&mut serde_json::Deserializer::from_str(entry.get())).ok()?;
+				Some((err.id().clone().into_owned(), Err(err.error_object().clone().into_owned())))
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_batch_response, BatchRequestBuilder, BatchResponse};
+	use crate::Error;
+	use jsonrpsee_types::Id;
+
+	#[test]
+	fn batch_response_parsing_keeps_errors() {
+		let raw = br#"[{"jsonrpc":"2.0","result":"hello","id":1},{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":0}]"#;
+		let entries = parse_batch_response(raw).unwrap();
+		assert_eq!(entries.len(), 2);
+		assert_eq!(entries[0].0, Id::Number(1));
+		assert_eq!(entries[0].1.as_ref().unwrap(), "hello");
+		assert_eq!(entries[1].0, Id::Number(0));
+		assert_eq!(entries[1].1.as_ref().unwrap_err().code(), -32601);
+
+		assert!(parse_batch_response(br#"{"jsonrpc":"2.0","result":"hello","id":1}"#).is_none());
+		assert!(parse_batch_response(br#"[{"jsonrpc":"2.0","id":1}]"#).is_none());
+	}
+
+	#[test]
+	fn typed_entries_are_read() {
+		let mut batch = BatchRequestBuilder::new();
+		let hello = batch.insert::<String>("say_hello", None);
+		let missing = batch.insert::<u64>("missing", None);
+		let number = batch.insert::<u64>("get_number", None);
+		assert_eq!(batch.len(), 3);
+
+		let raw = br#"[{"jsonrpc":"2.0","result":"hello","id":0},{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1},{"jsonrpc":"2.0","result":1337,"id":2}]"#;
+		let responses = BatchResponse::new(parse_batch_response(raw).unwrap().into_iter().map(|(_, rp)| rp).collect());
+		assert_eq!(responses.get(&hello).unwrap(), "hello");
+		assert!(matches!(responses.get(&missing), Err(Error::Call(_))));
+		assert_eq!(responses.get(&number).unwrap(), 1337);
+		assert_eq!(responses.num_failed(), 1);
+	}
+}
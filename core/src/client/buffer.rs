@@ -0,0 +1,324 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Buffer of the notifications of a subscription which weren't read yet.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures_util::stream::Stream;
+use serde_json::Value as JsonValue;
+
+use crate::Error;
+
+/// What to do with the notifications received while the buffer of a subscription is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+	/// Discard the oldest notification not read yet to make room for the new one.
+	///
+	/// The subscription yields [`Error::SubscriptionLagged`] in place of the discarded notifications.
+	DropOldest,
+	/// Discard the new notification.
+	///
+	/// The subscription yields [`Error::SubscriptionLagged`] in place of the discarded notifications.
+	DropNewest,
+	/// Close the subscription, which ends once the notifications already buffered are read.
+	#[default]
+	Close,
+	/// Close the subscription like [`OverflowPolicy::Close`], but yield [`Error::SubscriptionBufferFull`] once the
+	/// notifications already buffered are read, which tells the overflow apart from the server closing it.
+	CloseWithError,
+}
+
+/// Settings of the buffer of the notifications of a subscription which weren't read yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SubscriptionBuffer {
+	/// Capacity of the buffer, which like a bounded channel with a single sender holds up to `capacity + 1`
+	/// notifications.
+	pub capacity: usize,
+	/// What to do with the notifications received while the buffer is full.
+	pub policy: OverflowPolicy,
+}
+
+impl SubscriptionBuffer {
+	/// Creates the settings of a buffer of `capacity` notifications.
+	pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+		Self { capacity, policy }
+	}
+}
+
+impl Default for SubscriptionBuffer {
+	fn default() -> Self {
+		Self { capacity: 1024, policy: OverflowPolicy::Close }
+	}
+}
+
+/// Error returned by [`NotificationSender::try_send`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrySendError {
+	/// The buffer was full and the subscription was closed, as required by [`OverflowPolicy::Close`] and
+	/// [`OverflowPolicy::CloseWithError`].
+	Full,
+	/// The subscription was dropped or closed.
+	Disconnected,
+}
+
+#[derive(Debug)]
+struct State {
+	queue: VecDeque<JsonValue>,
+	buffer: SubscriptionBuffer,
+	/// No more notifications are sent, either because the sender was dropped or the buffer overflowed.
+	closed: bool,
+	/// Why the channel was closed, yielded once the notifications buffered were read.
+	error: Option<Error>,
+	/// Number of notifications dropped which the receiver wasn't told about yet.
+	lagged: usize,
+	/// Number of notifications to yield before telling the receiver about the dropped ones.
+	lagged_at: usize,
+	receiver_dropped: bool,
+	waker: Option<Waker>,
+}
+
+impl State {
+	fn wake(&mut self) {
+		if let Some(waker) = self.waker.take() {
+			waker.wake();
+		}
+	}
+
+	/// Records that `skipped` notifications were dropped after the ones buffered so far.
+	fn skip(&mut self, skipped: usize) {
+		if self.lagged == 0 {
+			self.lagged_at = self.queue.len();
+		}
+		self.lagged += skipped;
+	}
+}
+
+/// Creates the buffer over which the notifications of a [`Subscription`](crate::client::Subscription) are sent.
+pub fn notification_channel(buffer: SubscriptionBuffer) -> (NotificationSender, NotificationReceiver) {
+	let state = Arc::new(Mutex::new(State {
+		queue: VecDeque::new(),
+		buffer,
+		closed: false,
+		error: None,
+		lagged: 0,
+		lagged_at: 0,
+		receiver_dropped: false,
+		waker: None,
+	}));
+	(NotificationSender(state.clone()), NotificationReceiver(state))
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+	state.lock().expect("lock poisoned; qed")
+}
+
+/// Sending half of a [`notification_channel`], which closes it when dropped.
+#[derive(Debug)]
+pub struct NotificationSender(Arc<Mutex<State>>);
+
+impl NotificationSender {
+	/// Buffers `notif` for the receiver, applying the [`OverflowPolicy`] if the buffer is full.
+	pub fn try_send(&self, notif: JsonValue) -> Result<(), TrySendError> {
+		let mut state = lock(&self.0);
+		if state.closed || state.receiver_dropped {
+			return Err(TrySendError::Disconnected);
+		}
+
+		if state.queue.len() > state.buffer.capacity {
+			match state.buffer.policy {
+				OverflowPolicy::DropOldest => {
+					state.queue.pop_front();
+					state.lagged_at = 0;
+					state.lagged += 1;
+				}
+				OverflowPolicy::DropNewest => {
+					state.skip(1);
+					state.wake();
+					return Ok(());
+				}
+				OverflowPolicy::Close | OverflowPolicy::CloseWithError => {
+					state.closed = true;
+					if state.buffer.policy == OverflowPolicy::CloseWithError {
+						state.error = Some(Error::SubscriptionBufferFull);
+					}
+					state.wake();
+					return Err(TrySendError::Full);
+				}
+			}
+		}
+
+		state.queue.push_back(notif);
+		state.wake();
+		Ok(())
+	}
+
+	/// Tells the receiver that `skipped` notifications were dropped before reaching this channel, such as by
+	/// another buffer upstream.
+	pub fn skipped(&self, skipped: usize) {
+		let mut state = lock(&self.0);
+		if skipped > 0 && !state.closed && !state.receiver_dropped {
+			state.skip(skipped);
+			state.wake();
+		}
+	}
+
+	/// Closes the channel, the receiver yields `err` once the notifications already buffered are read.
+	pub fn close_with_error(&self, err: Error) {
+		let mut state = lock(&self.0);
+		if !state.closed {
+			state.closed = true;
+			state.error = Some(err);
+			state.wake();
+		}
+	}
+
+	/// Whether the receiver was dropped or the channel closed.
+	pub fn is_closed(&self) -> bool {
+		let state = lock(&self.0);
+		state.closed || state.receiver_dropped
+	}
+}
+
+impl Drop for NotificationSender {
+	fn drop(&mut self) {
+		let mut state = lock(&self.0);
+		state.closed = true;
+		state.wake();
+	}
+}
+
+/// Receiving half of a [`notification_channel`], yielding the buffered notifications until the channel is closed.
+#[derive(Debug)]
+pub struct NotificationReceiver(Arc<Mutex<State>>);
+
+impl Stream for NotificationReceiver {
+	type Item = Result<JsonValue, Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let mut state = lock(&self.0);
+		if state.lagged > 0 && state.lagged_at == 0 {
+			let lagged = std::mem::take(&mut state.lagged);
+			Poll::Ready(Some(Err(Error::SubscriptionLagged(lagged))))
+		} else if let Some(notif) = state.queue.pop_front() {
+			state.lagged_at = state.lagged_at.saturating_sub(1);
+			Poll::Ready(Some(Ok(notif)))
+		} else if let Some(err) = state.error.take() {
+			Poll::Ready(Some(Err(err)))
+		} else if state.closed {
+			Poll::Ready(None)
+		} else {
+			state.waker = Some(cx.waker().clone());
+			Poll::Pending
+		}
+	}
+}
+
+impl Drop for NotificationReceiver {
+	fn drop(&mut self) {
+		let mut state = lock(&self.0);
+		state.receiver_dropped = true;
+		state.queue.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{notification_channel, OverflowPolicy, SubscriptionBuffer, TrySendError};
+	use crate::Error;
+	use futures_util::{FutureExt, StreamExt};
+	use serde_json::json;
+
+	fn received(policy: OverflowPolicy) -> Vec<Result<u64, Error>> {
+		let (tx, mut rx) = notification_channel(SubscriptionBuffer::new(2, policy));
+		for n in 0..5 {
+			let _ = tx.try_send(json!(n));
+		}
+		drop(tx);
+
+		let mut received = Vec::new();
+		while let Some(notif) = rx.next().now_or_never().unwrap() {
+			received.push(notif.map(|n| n.as_u64().unwrap()));
+		}
+		received
+	}
+
+	#[test]
+	fn overflow_policies_work() {
+		assert!(matches!(
+			received(OverflowPolicy::DropOldest)[..],
+			[Err(Error::SubscriptionLagged(2)), Ok(2), Ok(3), Ok(4)]
+		));
+		assert!(matches!(
+			received(OverflowPolicy::DropNewest)[..],
+			[Ok(0), Ok(1), Ok(2), Err(Error::SubscriptionLagged(2))]
+		));
+		assert!(matches!(received(OverflowPolicy::Close)[..], [Ok(0), Ok(1), Ok(2)]));
+		assert!(matches!(
+			received(OverflowPolicy::CloseWithError)[..],
+			[Ok(0), Ok(1), Ok(2), Err(Error::SubscriptionBufferFull)]
+		));
+	}
+
+	#[test]
+	fn lag_is_reported_where_notifications_were_dropped() {
+		let (tx, mut rx) = notification_channel(SubscriptionBuffer::new(1, OverflowPolicy::DropNewest));
+		for n in 0..3 {
+			tx.try_send(json!(n)).unwrap();
+		}
+		assert_eq!(rx.next().now_or_never().unwrap().unwrap().unwrap(), json!(0));
+		tx.skipped(2);
+		tx.try_send(json!(3)).unwrap();
+		drop(tx);
+
+		let received: Vec<_> = rx.map(|notif| notif.map(|n| n.as_u64().unwrap())).collect().now_or_never().unwrap();
+		assert!(matches!(received[..], [Ok(1), Err(Error::SubscriptionLagged(3)), Ok(3)]));
+	}
+
+	#[test]
+	fn close_reason_is_yielded_last() {
+		let (tx, mut rx) = notification_channel(SubscriptionBuffer::default());
+		tx.try_send(json!(0)).unwrap();
+		tx.close_with_error(Error::SubscriptionBufferFull);
+		assert_eq!(tx.try_send(json!(1)), Err(TrySendError::Disconnected));
+
+		assert_eq!(rx.next().now_or_never().unwrap().unwrap().unwrap(), json!(0));
+		assert!(matches!(rx.next().now_or_never().unwrap(), Some(Err(Error::SubscriptionBufferFull))));
+		assert!(rx.next().now_or_never().unwrap().is_none());
+	}
+
+	#[test]
+	fn dropped_receiver_disconnects() {
+		let (tx, rx) = notification_channel(SubscriptionBuffer::default());
+		assert!(!tx.is_closed());
+		drop(rx);
+		assert!(tx.is_closed());
+		assert_eq!(tx.try_send(json!(0)), Err(TrySendError::Disconnected));
+	}
+}
@@ -0,0 +1,163 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Middleware for `jsonrpsee` clients.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonrpsee_types::{Id, ParamsSer};
+
+/// Defines a middleware with callbacks during the life-cycle of the calls made by a client, the client side
+/// counterpart of the server [`Middleware`](crate::middleware::Middleware). The primary use case for this is to
+/// trace calls or to collect metrics without wrapping every call site.
+///
+/// All callbacks do nothing by default, middlewares implement the ones they need.
+///
+/// See the `set_middleware` method of the client builders.
+pub trait ClientMiddleware: Send + Sync + 'static {
+	/// Called when a method call is sent, batch requests and batches of subscriptions trigger `on_request` once
+	/// for each call, and retried calls once for each attempt.
+	fn on_request(&self, _method: &str, _params: Option<&ParamsSer>, _id: &Id) {}
+
+	/// Called once a method call is completed, with the time elapsed since [`ClientMiddleware::on_request`] and
+	/// whether the call succeeded. Calls answered with an error, timed out or cancelled are not successful.
+	fn on_response(&self, _method: &str, _latency: Duration, _success: bool) {}
+
+	/// Called on each notification received from the server, including the notifications of subscriptions.
+	fn on_notification(&self, _method: &str) {}
+}
+
+impl fmt::Debug for dyn ClientMiddleware {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("ClientMiddleware")
+	}
+}
+
+/// Allows to share a middleware between several clients.
+impl<M: ClientMiddleware + ?Sized> ClientMiddleware for Arc<M> {
+	fn on_request(&self, method: &str, params: Option<&ParamsSer>, id: &Id) {
+		(**self).on_request(method, params, id);
+	}
+
+	fn on_response(&self, method: &str, latency: Duration, success: bool) {
+		(**self).on_response(method, latency, success);
+	}
+
+	fn on_notification(&self, method: &str) {
+		(**self).on_notification(method);
+	}
+}
+
+impl<A, B> ClientMiddleware for (A, B)
+where
+	A: ClientMiddleware,
+	B: ClientMiddleware,
+{
+	fn on_request(&self, method: &str, params: Option<&ParamsSer>, id: &Id) {
+		self.0.on_request(method, params, id);
+		self.1.on_request(method, params, id);
+	}
+
+	fn on_response(&self, method: &str, latency: Duration, success: bool) {
+		self.0.on_response(method, latency, success);
+		self.1.on_response(method, latency, success);
+	}
+
+	fn on_notification(&self, method: &str) {
+		self.0.on_notification(method);
+		self.1.on_notification(method);
+	}
+}
+
+/// Method call reported to a [`ClientMiddleware`]: [`ClientMiddleware::on_request`] is called when it's created and
+/// [`ClientMiddleware::on_response`] once it's completed, or as failed if it's dropped before.
+#[derive(Debug)]
+pub struct MiddlewareCall<'a> {
+	pending: Option<(&'a dyn ClientMiddleware, &'a str, Instant)>,
+}
+
+impl<'a> MiddlewareCall<'a> {
+	/// Reports the call of `method` to `middleware`, if any.
+	pub fn new(
+		middleware: Option<&'a dyn ClientMiddleware>,
+		method: &'a str,
+		params: Option<&ParamsSer>,
+		id: &Id,
+	) -> Self {
+		let pending = middleware.map(|middleware| {
+			middleware.on_request(method, params, id);
+			(middleware, method, Instant::now())
+		});
+		Self { pending }
+	}
+
+	/// Reports that the call was completed.
+	pub fn complete(mut self, success: bool) {
+		self.finish(success);
+	}
+
+	fn finish(&mut self, success: bool) {
+		if let Some((middleware, method, started_at)) = self.pending.take() {
+			middleware.on_response(method, started_at.elapsed(), success);
+		}
+	}
+}
+
+impl Drop for MiddlewareCall<'_> {
+	fn drop(&mut self) {
+		self.finish(false);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ClientMiddleware, MiddlewareCall};
+	use jsonrpsee_types::Id;
+	use std::sync::Mutex;
+	use std::time::Duration;
+
+	#[derive(Default)]
+	struct Responses(Mutex<Vec<(String, bool)>>);
+
+	impl ClientMiddleware for Responses {
+		fn on_response(&self, method: &str, _latency: Duration, success: bool) {
+			self.0.lock().unwrap().push((method.to_owned(), success));
+		}
+	}
+
+	#[test]
+	fn dropped_calls_are_reported_as_failed() {
+		let middleware = Responses::default();
+
+		MiddlewareCall::new(Some(&middleware), "completed", None, &Id::Number(0)).complete(true);
+		drop(MiddlewareCall::new(Some(&middleware), "dropped", None, &Id::Number(1)));
+		MiddlewareCall::new(None, "ignored", None, &Id::Number(2)).complete(true);
+
+		assert_eq!(*middleware.0.lock().unwrap(), [("completed".to_owned(), true), ("dropped".to_owned(), false)]);
+	}
+}
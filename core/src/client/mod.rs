@@ -32,6 +32,7 @@ use std::sync::Arc;
 use std::task;
 
 use crate::error::Error;
+use async_lock::{Semaphore, SemaphoreGuardArc};
 use async_trait::async_trait;
 use core::marker::PhantomData;
 use futures_channel::{mpsc, oneshot};
@@ -53,10 +54,31 @@ cfg_async_client! {
 	pub use async_client::{Client, ClientBuilder};
 }
 
+cfg_replay! {
+	pub mod replay;
+}
+
+pub mod retry;
+pub use retry::{RetryOn, RetryPolicy};
+
+pub mod buffer;
+pub use buffer::{
+	notification_channel, NotificationReceiver, NotificationSender, OverflowPolicy, SubscriptionBuffer, TrySendError,
+};
+
+pub mod batch;
+pub use batch::{BatchEntry, BatchEntryResponse, BatchRequestBuilder, BatchResponse};
+
+pub mod middleware;
+pub use middleware::{ClientMiddleware, MiddlewareCall};
+
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests and notifications.
 #[async_trait]
 pub trait ClientT {
 	/// Send a [notification request](https://www.jsonrpc.org/specification#notification)
+	///
+	/// Notifications carry no id and the server doesn't answer them, so this only waits until the notification is
+	/// handed to the transport (over HTTP, until the HTTP response is received).
 	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error>;
 
 	/// Send a [method call request](https://www.jsonrpc.org/specification#request_object).
@@ -73,6 +95,34 @@ pub trait ClientT {
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone;
+
+	/// Send a [batch request](https://www.jsonrpc.org/specification#batch) whose calls may each return a
+	/// different type, see [`BatchRequestBuilder`].
+	///
+	/// Returns `Ok` if the server answered the batch, even if some of its calls failed: the result or the error of
+	/// each call is read from the [`BatchResponse`].
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error>;
+
+	/// Send a [batch request](https://www.jsonrpc.org/specification#batch) whose calls may fail individually.
+	///
+	/// Unlike [`ClientT::batch_request`], a call answered with an error doesn't fail the whole batch: the result of
+	/// each call is returned in the same order as it was inserted in the batch, failed calls as [`Error::Call`]
+	/// holding the error object of the server.
+	///
+	/// Returns `Error` only if the batch itself fails, for instance if the connection is lost.
+	async fn batch_request_partial<'a, R>(
+		&self,
+		batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+	) -> Result<Vec<Result<R, Error>>, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let mut builder = BatchRequestBuilder::new();
+		let entries: Vec<BatchEntry<R>> =
+			batch.into_iter().map(|(method, params)| builder.insert(method, params)).collect();
+		let responses = self.batch(builder).await?;
+		Ok(entries.iter().map(|entry| responses.get(entry)).collect())
+	}
 }
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests, notifications and subscriptions.
@@ -99,6 +149,32 @@ pub trait SubscriptionClientT: ClientT {
 	where
 		Notif: DeserializeOwned;
 
+	/// Initiate a subscription like [`SubscriptionClientT::subscribe`], overriding the settings of the client for
+	/// the buffer of the notifications not read yet.
+	async fn subscribe_with_buffer<'a, Notif>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+		buffer: SubscriptionBuffer,
+	) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned;
+
+	/// Initiate several subscriptions in a single batch request, see [`SubscriptionClientT::subscribe`].
+	///
+	/// Each entry of the `batch` is the `subscribe_method`, `params` and `unsubscribe_method` of a subscription.
+	/// The subscriptions are returned in the order of the `batch`, and if any of them fails, the error is
+	/// returned and the other subscriptions are closed.
+	///
+	/// The server answers the batch once all subscriptions were accepted, so notifications sent before may be missed.
+	async fn batch_subscribe<'a, Notif>(
+		&self,
+		batch: Vec<(&'a str, Option<ParamsSer<'a>>, &'a str)>,
+	) -> Result<Vec<Subscription<Notif>>, Error>
+	where
+		Notif: DeserializeOwned;
+
 	/// Register a method subscription, this is used to filter only server notifications that a user is interested in.
 	///
 	/// The `Notif` param is a generic type to receive generic subscriptions, see [`Subscription`] for further
@@ -123,6 +199,9 @@ impl<T: Send> MaybeSend for T {}
 impl<T> MaybeSend for T {}
 
 /// Transport interface to send data asynchronous.
+///
+/// Custom transports implement it together with [`TransportReceiverT`] and are handed to the `ClientBuilder` of
+/// the async client, which takes care of the request ids, the batches and the subscriptions.
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait TransportSenderT: MaybeSend + 'static {
@@ -200,7 +279,7 @@ pub struct Subscription<Notif> {
 	/// Channel to send requests to the background task.
 	to_back: mpsc::Sender<FrontToBack>,
 	/// Channel from which we receive notifications from the server, as encoded `JsonValue`s.
-	notifs_rx: mpsc::Receiver<JsonValue>,
+	notifs_rx: NotificationReceiver,
 	/// Callback kind.
 	kind: Option<SubscriptionKind>,
 	/// Marker in order to pin the `Notif` parameter.
@@ -215,7 +294,7 @@ impl<Notif> Subscription<Notif> {
 	/// Create a new subscription.
 	pub fn new(
 		to_back: mpsc::Sender<FrontToBack>,
-		notifs_rx: mpsc::Receiver<JsonValue>,
+		notifs_rx: NotificationReceiver,
 		kind: SubscriptionKind,
 	) -> Self {
 		Self { to_back, notifs_rx, kind: Some(kind), marker: PhantomData }
@@ -248,7 +327,7 @@ pub struct BatchMessage {
 	/// Request IDs.
 	pub ids: Vec<Id<'static>>,
 	/// One-shot channel over which we send back the result of this request.
-	pub send_back: oneshot::Sender<Result<Vec<JsonValue>, Error>>,
+	pub send_back: oneshot::Sender<Result<Vec<BatchEntryResponse>, Error>>,
 }
 
 /// Request message.
@@ -273,10 +352,12 @@ pub struct SubscriptionMessage {
 	pub unsubscribe_id: Id<'static>,
 	/// Method to use to unsubscribe later. Used if the channel unexpectedly closes.
 	pub unsubscribe_method: String,
-	/// If the subscription succeeds, we return a [`mpsc::Receiver`] that will receive notifications.
+	/// Settings of the buffer of the notifications not read yet.
+	pub buffer: SubscriptionBuffer,
+	/// If the subscription succeeds, we return a [`NotificationReceiver`] that will receive notifications.
 	/// When we get a response from the server about that subscription, we send the result over
 	/// this channel.
-	pub send_back: oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId<'static>), Error>>,
+	pub send_back: oneshot::Sender<Result<(NotificationReceiver, SubscriptionId<'static>), Error>>,
 }
 
 /// RegisterNotification message.
@@ -284,10 +365,12 @@ pub struct SubscriptionMessage {
 pub struct RegisterNotificationMessage {
 	/// Method name this notification handler is attached to
 	pub method: String,
-	/// We return a [`mpsc::Receiver`] that will receive notifications.
+	/// Settings of the buffer of the notifications not read yet.
+	pub buffer: SubscriptionBuffer,
+	/// We return a [`NotificationReceiver`] that will receive notifications.
 	/// When we get a response from the server about that subscription, we send the result over
 	/// this channel.
-	pub send_back: oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, String), Error>>,
+	pub send_back: oneshot::Sender<Result<(NotificationReceiver, String), Error>>,
 }
 
 /// Message that the Client can send to the background task.
@@ -301,6 +384,8 @@ pub enum FrontToBack {
 	Request(RequestMessage),
 	/// Send a subscription request to the server.
 	Subscribe(SubscriptionMessage),
+	/// Send several subscription requests to the server in a single batch.
+	BatchSubscribe(Vec<SubscriptionMessage>),
 	/// Register a notification handler
 	RegisterNotification(RegisterNotificationMessage),
 	/// Unregister a notification handler
@@ -337,7 +422,7 @@ where
 	type Item = Result<Notif, Error>;
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
 		let n = futures_util::ready!(self.notifs_rx.poll_next_unpin(cx));
-		let res = n.map(|n| match serde_json::from_value::<Notif>(n) {
+		let res = n.map(|n| match serde_json::from_value::<Notif>(n?) {
 			Ok(parsed) => Ok(parsed),
 			Err(e) => Err(Error::ParseError(e)),
 		});
@@ -364,10 +449,10 @@ impl<Notif> Drop for Subscription<Notif> {
 #[derive(Debug)]
 /// Keep track of request IDs.
 pub struct RequestIdManager {
-	// Current pending requests.
-	current_pending: Arc<()>,
-	/// Max concurrent pending requests allowed.
-	max_concurrent_requests: usize,
+	/// Slots of the pending requests, one per allowed concurrent request.
+	slots: Arc<Semaphore>,
+	/// What to do with requests made while all the slots are taken.
+	limit_policy: RequestLimitPolicy,
 	/// Get the next request ID.
 	current_id: AtomicU64,
 	/// Request ID type.
@@ -377,47 +462,87 @@ pub struct RequestIdManager {
 impl RequestIdManager {
 	/// Create a new `RequestIdGuard` with the provided concurrency limit.
 	pub fn new(limit: usize, id_kind: IdKind) -> Self {
-		Self { current_pending: Arc::new(()), max_concurrent_requests: limit, current_id: AtomicU64::new(0), id_kind }
+		Self {
+			slots: Arc::new(Semaphore::new(limit)),
+			limit_policy: RequestLimitPolicy::Reject,
+			current_id: AtomicU64::new(0),
+			id_kind,
+		}
 	}
 
-	fn get_slot(&self) -> Result<Arc<()>, Error> {
-		// Strong count is 1 at start, so that's why we use `>` and not `>=`.
-		if Arc::strong_count(&self.current_pending) > self.max_concurrent_requests {
-			Err(Error::MaxSlotsExceeded)
-		} else {
-			Ok(self.current_pending.clone())
+	/// Set what to do with the requests made while the concurrency limit is reached (default is to reject them),
+	/// which only applies to [`RequestIdManager::acquire_request_id`] and [`RequestIdManager::acquire_request_ids`].
+	pub fn limit_policy(mut self, policy: RequestLimitPolicy) -> Self {
+		self.limit_policy = policy;
+		self
+	}
+
+	fn get_slot(&self) -> Result<SemaphoreGuardArc, Error> {
+		self.slots.try_acquire_arc().ok_or(Error::MaxSlotsExceeded)
+	}
+
+	async fn acquire_slot(&self) -> Result<SemaphoreGuardArc, Error> {
+		match self.limit_policy {
+			RequestLimitPolicy::Reject => self.get_slot(),
+			RequestLimitPolicy::Queue => Ok(self.slots.acquire_arc().await),
 		}
 	}
 
+	fn next_ids(&self, len: usize) -> Vec<Id<'static>> {
+		(0..len).map(|_| self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst))).collect()
+	}
+
 	/// Attempts to get the next request ID.
 	///
 	/// Fails if request limit has been exceeded.
 	pub fn next_request_id(&self) -> Result<RequestIdGuard<Id<'static>>, Error> {
-		let rc = self.get_slot()?;
+		let permit = self.get_slot()?;
 		let id = self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst));
-		Ok(RequestIdGuard { _rc: rc, id })
+		Ok(RequestIdGuard { _permit: permit, id })
 	}
 
 	/// Attempts to get the `n` number next IDs that only counts as one request.
 	///
 	/// Fails if request limit has been exceeded.
 	pub fn next_request_ids(&self, len: usize) -> Result<RequestIdGuard<Vec<Id<'static>>>, Error> {
-		let rc = self.get_slot()?;
-		let mut ids = Vec::with_capacity(len);
-		for _ in 0..len {
-			let id = self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst));
-			ids.push(id);
-		}
-		Ok(RequestIdGuard { _rc: rc, id: ids })
+		let permit = self.get_slot()?;
+		Ok(RequestIdGuard { _permit: permit, id: self.next_ids(len) })
+	}
+
+	/// Gets the next request ID, waiting for a pending request to complete if the request limit has been reached
+	/// and the [`RequestLimitPolicy`] is to queue requests.
+	///
+	/// Fails if request limit has been exceeded and the [`RequestLimitPolicy`] is to reject requests.
+	pub async fn acquire_request_id(&self) -> Result<RequestIdGuard<Id<'static>>, Error> {
+		let permit = self.acquire_slot().await?;
+		let id = self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst));
+		Ok(RequestIdGuard { _permit: permit, id })
+	}
+
+	/// Gets the `n` number next IDs that only counts as one request, like [`RequestIdManager::acquire_request_id`].
+	pub async fn acquire_request_ids(&self, len: usize) -> Result<RequestIdGuard<Vec<Id<'static>>>, Error> {
+		let permit = self.acquire_slot().await?;
+		Ok(RequestIdGuard { _permit: permit, id: self.next_ids(len) })
 	}
 }
 
-/// Reference counted request ID.
+/// What a client does with the requests made while `max_concurrent_requests` requests are already pending.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RequestLimitPolicy {
+	/// Fail them right away with [`Error::MaxSlotsExceeded`].
+	#[default]
+	Reject,
+	/// Send them once enough pending requests are completed. The time spent waiting doesn't count towards the
+	/// request timeout.
+	Queue,
+}
+
+/// Request ID holding one of the slots of the concurrent requests.
 #[derive(Debug)]
 pub struct RequestIdGuard<T: Clone> {
 	id: T,
-	/// Reference count decreased when dropped.
-	_rc: Arc<()>,
+	/// Slot released when dropped.
+	_permit: SemaphoreGuardArc,
 }
 
 impl<T: Clone> RequestIdGuard<T> {
@@ -438,12 +563,14 @@ pub enum CertificateStore {
 }
 
 /// JSON-RPC request object id data type.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum IdKind {
 	/// String.
 	String,
 	/// Number.
 	Number,
+	/// Random UUID (version 4) string, which helps correlating requests across services.
+	Uuid,
 }
 
 impl IdKind {
@@ -451,13 +578,16 @@ impl IdKind {
 		match self {
 			IdKind::Number => Id::Number(id),
 			IdKind::String => Id::Str(format!("{}", id).into()),
+			IdKind::Uuid => Id::Str(uuid::Uuid::new_v4().to_string().into()),
 		}
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{IdKind, RequestIdManager};
+	use super::{IdKind, RequestIdManager, RequestLimitPolicy};
+	use crate::Error;
+	use futures_util::FutureExt;
 
 	#[test]
 	fn request_id_guard_works() {
@@ -472,4 +602,30 @@ mod tests {
 
 		assert!(manager.next_request_id().is_ok());
 	}
+
+	#[test]
+	fn requests_over_the_limit_are_queued() {
+		let manager = RequestIdManager::new(1, IdKind::Number).limit_policy(RequestLimitPolicy::Queue);
+		let first = manager.acquire_request_id().now_or_never().unwrap().unwrap();
+
+		let mut second = Box::pin(manager.acquire_request_ids(2));
+		assert!((&mut second).now_or_never().is_none());
+		drop(first);
+		assert_eq!(second.now_or_never().unwrap().unwrap().inner().len(), 2);
+
+		let rejecting = RequestIdManager::new(1, IdKind::Number);
+		let _first = rejecting.acquire_request_id().now_or_never().unwrap().unwrap();
+		assert!(matches!(rejecting.acquire_request_id().now_or_never().unwrap(), Err(Error::MaxSlotsExceeded)));
+	}
+
+	#[test]
+	fn uuid_ids_are_unique() {
+		let manager = RequestIdManager::new(2, IdKind::Uuid);
+		let ids = manager.next_request_ids(3).unwrap().inner();
+		for id in &ids {
+			let id = id.as_str().unwrap();
+			assert!(uuid::Uuid::parse_str(id).is_ok(), "{} is not a UUID", id);
+		}
+		assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+	}
 }
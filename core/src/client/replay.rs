@@ -0,0 +1,355 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Replay
+//!
+//! This module handles replaying the traffic of a connection captured by the wire tap of a server, to reproduce
+//! bug reports without access to the client or the server they were observed with.
+//!
+//! The wire tap (`jsonrpsee_core::server::wire_tap`) records every request, response and subscription
+//! notification of the sampled connections as a line of JSON with a timestamp. Frames must be captured whole
+//! to be replayed, so the recording must be made without limiting their length:
+//!
+//! ```ignore
+//! let tap = WireTap::to_file("/tmp/session.jsonl")?.max_frame_len(usize::MAX);
+//! ```
+//!
+//! A recorded connection can then be replayed from either side:
+//!
+//! - [`Recording::replay_requests`] sends the frames of the client to a server, through any client transport.
+//! - [`Recording::mock_server`] returns a transport for a client, answering with the frames of the server. Each
+//!   frame is received once the client sent as many frames as it had when the frame was recorded, so the client
+//!   must make the same calls as the recorded one and use the same request ids.
+//!
+//! ```no_run
+//! use jsonrpsee_core::client::replay::Recording;
+//!
+//! let recording = Recording::from_file("/tmp/session.jsonl").unwrap();
+//! for session in recording.sessions() {
+//!     let frames = recording.session(session).frames().len();
+//!     println!("connection {}: {} frames", session, frames);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::client::{ReceivedMessage, TransportReceiverT, TransportSenderT};
+use async_trait::async_trait;
+use futures_channel::mpsc;
+use futures_util::stream::StreamExt;
+use serde::Deserialize;
+
+/// Peer which sent a recorded frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Origin {
+	/// Frame sent by the client, recorded as `inbound`.
+	#[serde(rename = "inbound")]
+	Client,
+	/// Frame sent by the server, recorded as `outbound`.
+	#[serde(rename = "outbound")]
+	Server,
+}
+
+/// A frame of a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+	/// Time at which the frame was recorded, in milliseconds since the Unix epoch.
+	pub ts: u64,
+	/// Connection to which the frame belongs.
+	pub session: u64,
+	/// Peer which sent the frame.
+	pub origin: Origin,
+	/// Contents of the frame.
+	pub data: Vec<u8>,
+}
+
+/// How [`Recording::replay_requests`] paces the frames it sends.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pacing {
+	/// Send the frames one after another without waiting.
+	Immediate,
+	/// Wait between frames as long as the recorded client did.
+	Recorded,
+}
+
+/// Line written by the wire tap.
+#[derive(Deserialize)]
+struct Line {
+	ts: u64,
+	session: u64,
+	direction: Origin,
+	truncated: bool,
+	encoding: String,
+	data: String,
+}
+
+/// Frames captured by a wire tap, in the order they were recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+	frames: Vec<Frame>,
+}
+
+impl Recording {
+	/// Read the recording written by a wire tap to the file at `path`.
+	pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+		Self::from_reader(BufReader::new(File::open(path)?))
+	}
+
+	/// Read the recording written by a wire tap to `reader`, one JSON object per line.
+	///
+	/// Fails with [`io::ErrorKind::InvalidData`] if a line is malformed or a frame was truncated.
+	pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+		let mut frames = Vec::new();
+
+		for (n, line) in reader.lines().enumerate() {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue;
+			}
+
+			let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {}", n + 1, msg));
+			let line: Line = serde_json::from_str(&line).map_err(|e| invalid(e.to_string()))?;
+			if line.truncated {
+				return Err(invalid("the frame was truncated, record with an unlimited frame length".into()));
+			}
+
+			let data = match line.encoding.as_str() {
+				"utf8" => line.data.into_bytes(),
+				"hex" => decode_hex(&line.data).ok_or_else(|| invalid("invalid hex data".into()))?,
+				other => return Err(invalid(format!("unknown encoding `{}`", other))),
+			};
+
+			frames.push(Frame { ts: line.ts, session: line.session, origin: line.direction, data });
+		}
+
+		Ok(Self { frames })
+	}
+
+	/// Returns the recorded frames.
+	pub fn frames(&self) -> &[Frame] {
+		&self.frames
+	}
+
+	/// Returns the recorded connections, in the order in which they sent their first frame.
+	pub fn sessions(&self) -> Vec<u64> {
+		let mut sessions = Vec::new();
+		for frame in &self.frames {
+			if !sessions.contains(&frame.session) {
+				sessions.push(frame.session);
+			}
+		}
+		sessions
+	}
+
+	/// Returns the frames of the connection `session`.
+	pub fn session(&self, session: u64) -> Recording {
+		Recording { frames: self.frames.iter().filter(|frame| frame.session == session).cloned().collect() }
+	}
+
+	/// Send the frames of the client through `sender`, returns the number of frames sent.
+	///
+	/// The recording should contain the frames of a single connection, see [`Recording::session`].
+	pub async fn replay_requests<S: TransportSenderT>(
+		&self,
+		sender: &mut S,
+		pacing: Pacing,
+	) -> Result<usize, S::Error> {
+		let mut sent = 0;
+		let mut last_ts = None;
+
+		for frame in self.frames.iter().filter(|frame| frame.origin == Origin::Client) {
+			if let (Pacing::Recorded, Some(last_ts)) = (pacing, last_ts) {
+				futures_timer::Delay::new(Duration::from_millis(frame.ts.saturating_sub(last_ts))).await;
+			}
+			last_ts = Some(frame.ts);
+
+			sender.send(String::from_utf8_lossy(&frame.data).into_owned()).await?;
+			sent += 1;
+		}
+
+		Ok(sent)
+	}
+
+	/// Create a transport for a client answering with the frames of the server.
+	///
+	/// The recording should contain the frames of a single connection, see [`Recording::session`].
+	pub fn mock_server(&self) -> (ReplaySender, ReplayReceiver) {
+		let mut client_frames = 0;
+		let mut frames = VecDeque::new();
+
+		for frame in &self.frames {
+			match frame.origin {
+				Origin::Client => client_frames += 1,
+				Origin::Server => frames.push_back((client_frames, frame.data.clone())),
+			}
+		}
+
+		let (tx, rx) = mpsc::unbounded();
+		(ReplaySender(tx), ReplayReceiver { frames, received: 0, rx })
+	}
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+	hex.as_bytes()
+		.chunks(2)
+		.map(|pair| match pair {
+			[hi, lo] => Some((char::from(*hi).to_digit(16)? * 16 + char::from(*lo).to_digit(16)?) as u8),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Error of the transport returned by [`Recording::mock_server`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+	/// All frames of the server were received.
+	#[error("The recording has no more frames")]
+	Exhausted,
+	/// The sending half of the transport was dropped.
+	#[error("The client closed the connection")]
+	Closed,
+}
+
+/// Sending half of the transport returned by [`Recording::mock_server`].
+pub struct ReplaySender(mpsc::UnboundedSender<String>);
+
+impl fmt::Debug for ReplaySender {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReplaySender").finish()
+	}
+}
+
+/// Receiving half of the transport returned by [`Recording::mock_server`].
+pub struct ReplayReceiver {
+	/// Frames of the server, with the number of frames the client sent before each of them.
+	frames: VecDeque<(usize, Vec<u8>)>,
+	/// Number of frames the client sent so far.
+	received: usize,
+	rx: mpsc::UnboundedReceiver<String>,
+}
+
+impl fmt::Debug for ReplayReceiver {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReplayReceiver").field("frames", &self.frames.len()).field("received", &self.received).finish()
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TransportSenderT for ReplaySender {
+	type Error = ReplayError;
+
+	async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+		tracing::trace!("send: {}", msg);
+		self.0.unbounded_send(msg).map_err(|_| ReplayError::Closed)
+	}
+
+	async fn send_ping(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TransportReceiverT for ReplayReceiver {
+	type Error = ReplayError;
+
+	async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+		loop {
+			match self.frames.front() {
+				None => return Err(ReplayError::Exhausted),
+				Some((after, _)) if self.received >= *after => {
+					let (_, data) = self.frames.pop_front().expect("Checked above; qed");
+					return Ok(ReceivedMessage::Bytes(data));
+				}
+				Some(_) => match self.rx.next().await {
+					Some(_) => self.received += 1,
+					None => return Err(ReplayError::Closed),
+				},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RECORDING: &str = r#"
+{"ts":1000,"session":0,"transport":"ws","peer":"127.0.0.1:1","direction":"inbound","len":8,"truncated":false,"encoding":"utf8","data":"{\"id\":0}"}
+{"ts":1001,"session":1,"transport":"ws","peer":"127.0.0.1:2","direction":"inbound","len":2,"truncated":false,"encoding":"hex","data":"ff00"}
+{"ts":1002,"session":0,"transport":"ws","peer":"127.0.0.1:1","direction":"outbound","len":12,"truncated":false,"encoding":"utf8","data":"{\"result\":0}"}
+{"ts":1003,"session":0,"transport":"ws","peer":"127.0.0.1:1","direction":"outbound","len":10,"truncated":false,"encoding":"utf8","data":"{\"notif\":0}"}
+{"ts":1004,"session":0,"transport":"ws","peer":"127.0.0.1:1","direction":"inbound","len":8,"truncated":false,"encoding":"utf8","data":"{\"id\":1}"}
+{"ts":1005,"session":0,"transport":"ws","peer":"127.0.0.1:1","direction":"outbound","len":12,"truncated":false,"encoding":"utf8","data":"{\"result\":1}"}
+"#;
+
+	fn text(msg: ReceivedMessage) -> Vec<u8> {
+		match msg {
+			ReceivedMessage::Bytes(bytes) => bytes,
+			other => panic!("Unexpected message: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn recording_is_parsed() {
+		let recording = Recording::from_reader(RECORDING.as_bytes()).unwrap();
+		assert_eq!(recording.frames().len(), 6);
+		assert_eq!(recording.sessions(), [0, 1]);
+		assert_eq!(recording.session(1).frames()[0].data, [0xff, 0x00]);
+		assert_eq!(recording.session(0).frames().len(), 5);
+
+		let truncated = RECORDING.replace(r#""len":2,"truncated":false"#, r#""len":2,"truncated":true"#);
+		let err = Recording::from_reader(truncated.as_bytes()).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[tokio::test]
+	async fn server_frames_follow_client_frames() {
+		let recording = Recording::from_reader(RECORDING.as_bytes()).unwrap().session(0);
+		let (mut sender, mut receiver) = recording.mock_server();
+
+		sender.send(r#"{"id":0}"#.into()).await.unwrap();
+		assert_eq!(text(receiver.receive().await.unwrap()), br#"{"result":0}"#);
+		assert_eq!(text(receiver.receive().await.unwrap()), br#"{"notif":0}"#);
+
+		// The last response is only received once the client sent its second request.
+		sender.send(r#"{"id":1}"#.into()).await.unwrap();
+		assert_eq!(text(receiver.receive().await.unwrap()), br#"{"result":1}"#);
+		assert!(matches!(receiver.receive().await, Err(ReplayError::Exhausted)));
+
+		// The client frames are replayed as they were recorded.
+		let (mut sender, mut receiver) = Recording::default().mock_server();
+		assert_eq!(recording.replay_requests(&mut sender, Pacing::Immediate).await.unwrap(), 2);
+		assert_eq!(receiver.rx.next().await.unwrap(), r#"{"id":0}"#);
+		assert_eq!(receiver.rx.next().await.unwrap(), r#"{"id":1}"#);
+	}
+}
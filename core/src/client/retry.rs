@@ -0,0 +1,198 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Retrying calls
+//!
+//! A [`RetryPolicy`] configured on a client makes it retry the method calls failing with one of the selected
+//! [classes of errors](RetryOn), waiting for an exponentially growing backoff between the attempts. Batches are
+//! retried as a whole, notifications are never retried since their outcome is unknown.
+//!
+//! A call may have been executed by the server although it failed on the client, for instance when it timed out,
+//! so only calls to idempotent methods should be retried. Unless all calls are, restrict the policy to the
+//! idempotent methods with [`RetryPolicy::methods`].
+//!
+//! ```
+//! use std::time::Duration;
+//! use jsonrpsee_core::client::{RetryOn, RetryPolicy};
+//!
+//! // Make up to 3 attempts, waiting 100 then 200 milliseconds, when a read-only method times out.
+//! let policy = RetryPolicy::new(3)
+//!     .backoff(Duration::from_millis(100), Duration::from_secs(1))
+//!     .retry_on([RetryOn::Timeout])
+//!     .methods(["eth_getBalance", "eth_blockNumber"]);
+//! ```
+
+use std::time::Duration;
+
+use jsonrpsee_types::error::{CallError, SERVER_IS_BUSY_CODE};
+
+use crate::Error;
+
+/// Class of errors on which a call is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryOn {
+	/// The request couldn't be sent or the response couldn't be read.
+	Transport,
+	/// The request timed out.
+	Timeout,
+	/// The server answered with an error object with the given code, for instance
+	/// [`SERVER_IS_BUSY_CODE`].
+	Code(i32),
+}
+
+impl RetryOn {
+	fn matches(&self, err: &Error) -> bool {
+		match (self, err) {
+			(Self::Transport, Error::Transport(_)) => true,
+			(Self::Timeout, Error::RequestTimeout) => true,
+			(Self::Code(code), Error::Call(CallError::Custom(err))) => err.code() == *code,
+			_ => false,
+		}
+	}
+}
+
+/// When and how often failed calls are retried.
+///
+/// By default calls are retried on [transport errors](RetryOn::Transport), [timeouts](RetryOn::Timeout) and
+/// [busy servers](RetryOn::Code), waiting 100 milliseconds before the first retry and doubling the backoff up to
+/// 10 seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	initial_backoff: Duration,
+	max_backoff: Duration,
+	retry_on: Vec<RetryOn>,
+	methods: Option<Vec<String>>,
+}
+
+impl RetryPolicy {
+	/// Make up to `max_attempts` attempts per call, including the first one.
+	pub fn new(max_attempts: u32) -> Self {
+		Self {
+			max_attempts: max_attempts.max(1),
+			initial_backoff: Duration::from_millis(100),
+			max_backoff: Duration::from_secs(10),
+			retry_on: vec![RetryOn::Transport, RetryOn::Timeout, RetryOn::Code(SERVER_IS_BUSY_CODE)],
+			methods: None,
+		}
+	}
+
+	/// Wait `initial` before the first retry, doubling the backoff with every retry up to `max`.
+	pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+		self.initial_backoff = initial;
+		self.max_backoff = max;
+		self
+	}
+
+	/// Set the classes of errors on which calls are retried, replacing the default ones.
+	pub fn retry_on(mut self, classes: impl IntoIterator<Item = RetryOn>) -> Self {
+		self.retry_on = classes.into_iter().collect();
+		self
+	}
+
+	/// Only retry calls to the given methods, which should be idempotent (default is to retry calls to all methods).
+	pub fn methods<S: Into<String>>(mut self, methods: impl IntoIterator<Item = S>) -> Self {
+		self.methods = Some(methods.into_iter().map(Into::into).collect());
+		self
+	}
+
+	/// Returns the backoff before retrying a call to `method` which failed with `err` after `attempts` attempts,
+	/// or `None` if it shouldn't be retried.
+	pub fn retry_after(&self, method: &str, attempts: u32, err: &Error) -> Option<Duration> {
+		if self.retries_method(method) {
+			self.backoff_after(attempts, err)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the backoff before retrying a batch of calls to `methods` which failed with `err` after `attempts`
+	/// attempts, or `None` if it shouldn't be retried. A batch is only retried if all its calls may be.
+	pub fn retry_batch_after<'a>(
+		&self,
+		mut methods: impl Iterator<Item = &'a str>,
+		attempts: u32,
+		err: &Error,
+	) -> Option<Duration> {
+		if methods.all(|method| self.retries_method(method)) {
+			self.backoff_after(attempts, err)
+		} else {
+			None
+		}
+	}
+
+	fn retries_method(&self, method: &str) -> bool {
+		match &self.methods {
+			Some(methods) => methods.iter().any(|m| m == method),
+			None => true,
+		}
+	}
+
+	fn backoff_after(&self, attempts: u32, err: &Error) -> Option<Duration> {
+		if attempts >= self.max_attempts || !self.retry_on.iter().any(|class| class.matches(err)) {
+			return None;
+		}
+
+		let backoff = self.initial_backoff.saturating_mul(2_u32.saturating_pow(attempts.saturating_sub(1)));
+		Some(backoff.min(self.max_backoff))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{RetryOn, RetryPolicy};
+	use crate::Error;
+	use jsonrpsee_types::error::{CallError, ErrorCode, ErrorObject};
+	use std::time::Duration;
+
+	fn call_error(code: ErrorCode) -> Error {
+		Error::Call(CallError::Custom(ErrorObject::from(code).into_owned()))
+	}
+
+	#[test]
+	fn backoff_grows_until_max_attempts() {
+		let policy = RetryPolicy::new(4).backoff(Duration::from_millis(100), Duration::from_millis(300));
+
+		assert_eq!(policy.retry_after("foo", 1, &Error::RequestTimeout), Some(Duration::from_millis(100)));
+		assert_eq!(policy.retry_after("foo", 2, &Error::RequestTimeout), Some(Duration::from_millis(200)));
+		assert_eq!(policy.retry_after("foo", 3, &Error::RequestTimeout), Some(Duration::from_millis(300)));
+		assert_eq!(policy.retry_after("foo", 4, &Error::RequestTimeout), None);
+	}
+
+	#[test]
+	fn only_selected_errors_and_methods_are_retried() {
+		let policy = RetryPolicy::new(2).retry_on([RetryOn::Code(ErrorCode::ServerIsBusy.code())]).methods(["foo"]);
+
+		assert!(policy.retry_after("foo", 1, &call_error(ErrorCode::ServerIsBusy)).is_some());
+		assert!(policy.retry_after("foo", 1, &call_error(ErrorCode::InternalError)).is_none());
+		assert!(policy.retry_after("foo", 1, &Error::RequestTimeout).is_none());
+		assert!(policy.retry_after("bar", 1, &call_error(ErrorCode::ServerIsBusy)).is_none());
+
+		let err = call_error(ErrorCode::ServerIsBusy);
+		assert!(policy.retry_batch_after(["foo", "foo"].into_iter(), 1, &err).is_some());
+		assert!(policy.retry_batch_after(["foo", "bar"].into_iter(), 1, &err).is_none());
+	}
+}
@@ -0,0 +1,180 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Compression Statistics
+//!
+//! This module tracks how well the messages of a connection compress. Servers report the statistics of every
+//! connection whose messages were compressed to [`Middleware::on_compression_info`](crate::middleware::Middleware::on_compression_info)
+//! when it's closed.
+//!
+//! Compressing messages that barely shrink wastes CPU on both ends. Once enough messages of a connection were
+//! compressed, compression is disabled for the rest of the connection if it saved less than the configured
+//! share of their size, see [`CompressionStats::new`].
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::middleware::{CompressionInfo, Transport};
+
+/// Number of messages compressed before deciding whether compressing the messages of a connection is worth it.
+pub const SAMPLE_SIZE: u64 = 16;
+
+/// Compression statistics of a connection, shared by the handles to it.
+#[derive(Debug, Clone, Default)]
+pub struct CompressionStats {
+	inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+	/// Minimum percentage of the size of the messages compression must save to stay enabled.
+	min_savings: u8,
+	negotiated: Mutex<Option<String>>,
+	messages: AtomicU64,
+	uncompressed_bytes: AtomicU64,
+	compressed_bytes: AtomicU64,
+	disabled: AtomicBool,
+}
+
+impl CompressionStats {
+	/// Create the statistics of a connection, compression is disabled once [`SAMPLE_SIZE`] messages were
+	/// compressed if it saved less than `min_savings` percent of their size. `0` keeps it enabled.
+	pub fn new(min_savings: u8) -> Self {
+		Self { inner: Arc::new(Inner { min_savings: min_savings.min(100), ..Default::default() }) }
+	}
+
+	/// Set the compression parameters negotiated with the peer.
+	pub fn set_negotiated(&self, negotiated: impl Into<String>) {
+		*self.inner.negotiated.lock().expect("lock is never poisoned; qed") = Some(negotiated.into());
+	}
+
+	/// Returns the compression parameters negotiated with the peer, `None` if compression wasn't negotiated.
+	pub fn negotiated(&self) -> Option<String> {
+		self.inner.negotiated.lock().expect("lock is never poisoned; qed").clone()
+	}
+
+	/// Returns whether the messages of the connection are still compressed.
+	pub fn is_enabled(&self) -> bool {
+		!self.inner.disabled.load(Ordering::Relaxed)
+	}
+
+	/// Stop compressing the messages of the connection.
+	pub fn disable(&self) {
+		self.inner.disabled.store(true, Ordering::Relaxed);
+	}
+
+	/// Record a message of `uncompressed` bytes that was compressed to `compressed` bytes, which disables
+	/// compression if it doesn't save enough.
+	pub fn record(&self, uncompressed: usize, compressed: usize) {
+		let messages = self.inner.messages.fetch_add(1, Ordering::Relaxed) + 1;
+		let uncompressed =
+			self.inner.uncompressed_bytes.fetch_add(uncompressed as u64, Ordering::Relaxed) + uncompressed as u64;
+		let compressed =
+			self.inner.compressed_bytes.fetch_add(compressed as u64, Ordering::Relaxed) + compressed as u64;
+
+		if self.inner.min_savings == 0 || messages < SAMPLE_SIZE || !self.is_enabled() {
+			return;
+		}
+
+		let saved = uncompressed.saturating_sub(compressed);
+		if saved * 100 < uncompressed * self.inner.min_savings as u64 {
+			tracing::debug!(
+				"Compression saved {} of {} bytes, below {}%; disabling it for the connection",
+				saved,
+				uncompressed,
+				self.inner.min_savings
+			);
+			self.disable();
+		}
+	}
+
+	/// Returns the statistics of the connection to the client at `remote_addr`, `None` if compression wasn't
+	/// negotiated.
+	pub fn info(&self, remote_addr: SocketAddr, transport: Transport) -> Option<CompressionInfo> {
+		let negotiated = self.negotiated()?;
+
+		Some(CompressionInfo {
+			remote_addr,
+			transport,
+			negotiated,
+			messages: self.inner.messages.load(Ordering::Relaxed),
+			uncompressed_bytes: self.inner.uncompressed_bytes.load(Ordering::Relaxed),
+			compressed_bytes: self.inner.compressed_bytes.load(Ordering::Relaxed),
+			disabled: !self.is_enabled(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CompressionStats, SAMPLE_SIZE};
+	use crate::middleware::Transport;
+
+	#[test]
+	fn compression_is_disabled_if_it_saves_too_little() {
+		let stats = CompressionStats::new(20);
+		stats.set_negotiated("gzip");
+
+		for _ in 0..SAMPLE_SIZE - 1 {
+			stats.record(100, 90);
+		}
+		assert!(stats.is_enabled());
+		stats.record(100, 90);
+		assert!(!stats.is_enabled());
+
+		let info = stats.info("127.0.0.1:9944".parse().unwrap(), Transport::Http).unwrap();
+		assert_eq!(info.negotiated, "gzip");
+		assert_eq!((info.messages, info.uncompressed_bytes, info.compressed_bytes), (16, 1600, 1440));
+		assert!(info.disabled);
+		assert!((info.ratio() - 1600.0 / 1440.0).abs() < f64::EPSILON);
+	}
+
+	#[test]
+	fn compression_stays_enabled_if_it_saves_enough() {
+		let stats = CompressionStats::new(20);
+		for _ in 0..2 * SAMPLE_SIZE {
+			stats.record(100, 30);
+		}
+		assert!(stats.is_enabled());
+
+		// Without a threshold, compression is only disabled explicitly.
+		let stats = CompressionStats::new(0);
+		for _ in 0..SAMPLE_SIZE {
+			stats.record(100, 120);
+		}
+		assert!(stats.is_enabled());
+		stats.disable();
+		assert!(!stats.is_enabled());
+	}
+
+	#[test]
+	fn info_requires_negotiated_compression() {
+		let stats = CompressionStats::new(0);
+		stats.record(100, 30);
+		assert!(stats.info("127.0.0.1:9944".parse().unwrap(), Transport::WebSocket).is_none());
+	}
+}
@@ -0,0 +1,476 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # WebSocket Compression
+//!
+//! This module implements the `permessage-deflate` WebSocket extension of
+//! [RFC 7692](https://www.rfc-editor.org/rfc/rfc7692), compressing every data message with DEFLATE. Servers
+//! pushing many subscription notifications benefit the most: consecutive notifications share most of their JSON,
+//! which is only sent once as both peers keep their compression context between messages unless they agree not
+//! to.
+//!
+//! The extension is negotiated during the handshake, connections to peers that don't support it are left
+//! uncompressed. Messages shorter than [`DeflateConfig::threshold`] are sent uncompressed as the savings
+//! wouldn't be worth the overhead, and compression can be disabled for the connections whose messages don't
+//! compress well, see [`DeflateConfig::min_savings`].
+//!
+//! ```
+//! use jsonrpsee_core::deflate::DeflateConfig;
+//!
+//! // Ask peers to compress with a 4 KiB window and only compress messages of at least 256 bytes.
+//! let config = DeflateConfig::new().window_bits(12).threshold(256);
+//! ```
+
+use std::io;
+
+use crate::compression::CompressionStats;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use soketto::base::{Header, OpCode};
+use soketto::connection::Mode;
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Storage};
+
+const SERVER_NO_CONTEXT_TAKEOVER: &str = "server_no_context_takeover";
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+const CLIENT_NO_CONTEXT_TAKEOVER: &str = "client_no_context_takeover";
+const CLIENT_MAX_WINDOW_BITS: &str = "client_max_window_bits";
+
+/// Size of the LZ77 window of the compressor, which can't be changed.
+const WINDOW_BITS: u8 = 15;
+
+/// Trailer of a DEFLATE block flushed with a sync flush, removed from compressed messages.
+const TRAILER: [u8; 4] = [0, 0, 0xff, 0xff];
+
+/// Configuration of the `permessage-deflate` extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateConfig {
+	window_bits: u8,
+	threshold: usize,
+	min_savings: u8,
+}
+
+impl Default for DeflateConfig {
+	fn default() -> Self {
+		Self { window_bits: WINDOW_BITS, threshold: 128, min_savings: 0 }
+	}
+}
+
+impl DeflateConfig {
+	/// Create the default configuration.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the base-2 logarithm of the size of the window clients compress their messages with, between 9 and 15
+	/// (default is 15, a 32 KiB window). Smaller windows save memory on the clients at the cost of compression.
+	///
+	/// Only used by servers, and only for the clients offering to negotiate the size of their window.
+	///
+	/// # Panics
+	///
+	/// If `bits` is not between 9 and 15.
+	pub fn window_bits(mut self, bits: u8) -> Self {
+		assert!((9..=WINDOW_BITS).contains(&bits), "The window bits of permessage-deflate must be in 9..=15");
+		self.window_bits = bits;
+		self
+	}
+
+	/// Set the size in bytes from which messages are compressed (default is 128).
+	pub fn threshold(mut self, threshold: usize) -> Self {
+		self.threshold = threshold;
+		self
+	}
+
+	/// Stop compressing the messages sent over a connection once compressing them saved less than `percent`
+	/// percent of their size, checked from the [`SAMPLE_SIZE`](crate::compression::SAMPLE_SIZE)th compressed
+	/// message on (default is 0, never stop). Compressing messages that barely shrink only wastes CPU.
+	///
+	/// # Panics
+	///
+	/// If `percent` is greater than 100.
+	pub fn min_savings(mut self, percent: u8) -> Self {
+		assert!(percent <= 100, "The minimum savings of permessage-deflate must be a percentage");
+		self.min_savings = percent;
+		self
+	}
+}
+
+/// The `permessage-deflate` extension of a connection, to be added to the handshake of a server or client.
+pub struct Deflate {
+	mode: Mode,
+	config: DeflateConfig,
+	max_message_len: usize,
+	enabled: bool,
+	params: Vec<Param<'static>>,
+	/// Whether the compression context is reset after every message.
+	no_context_takeover: bool,
+	compress: Compress,
+	decompress: Decompress,
+	buffer: Vec<u8>,
+	await_last_fragment: bool,
+	stats: CompressionStats,
+}
+
+impl std::fmt::Debug for Deflate {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Deflate")
+			.field("mode", &self.mode)
+			.field("config", &self.config)
+			.field("enabled", &self.enabled)
+			.field("no_context_takeover", &self.no_context_takeover)
+			.finish()
+	}
+}
+
+impl Deflate {
+	/// Create the extension of a connection in `mode`. Messages longer than `max_message_len` bytes once
+	/// decompressed are rejected.
+	pub fn new(mode: Mode, config: DeflateConfig, max_message_len: usize) -> Self {
+		Self {
+			mode,
+			config,
+			max_message_len,
+			enabled: false,
+			params: Vec::new(),
+			no_context_takeover: false,
+			compress: Compress::new(Compression::fast(), false),
+			decompress: Decompress::new(false),
+			buffer: Vec::new(),
+			await_last_fragment: false,
+			stats: CompressionStats::new(config.min_savings),
+		}
+	}
+
+	/// Returns the compression statistics of the connection, which are updated as messages are sent.
+	pub fn stats(&self) -> CompressionStats {
+		self.stats.clone()
+	}
+
+	/// Answer the offer of a client, leaves the extension disabled if the offer can't be accepted.
+	fn accept_offer(&mut self, params: &[Param]) {
+		let mut response = Vec::new();
+		let mut no_context_takeover = false;
+
+		for p in params {
+			match p.name() {
+				SERVER_NO_CONTEXT_TAKEOVER => {
+					no_context_takeover = true;
+					response.push(param(SERVER_NO_CONTEXT_TAKEOVER, None));
+				}
+				// The decompressor supports any window.
+				CLIENT_NO_CONTEXT_TAKEOVER => {}
+				CLIENT_MAX_WINDOW_BITS => {
+					if self.config.window_bits < WINDOW_BITS {
+						response.push(param(CLIENT_MAX_WINDOW_BITS, Some(self.config.window_bits)));
+					}
+				}
+				// The compressor only supports the largest window.
+				SERVER_MAX_WINDOW_BITS if window_bits(p) == Some(WINDOW_BITS) => {}
+				_ => {
+					tracing::debug!("Declining permessage-deflate offer with parameter {}", p);
+					return;
+				}
+			}
+		}
+
+		self.params = response;
+		self.no_context_takeover = no_context_takeover;
+		self.enabled = true;
+		self.stats.set_negotiated(negotiated(&self.params));
+	}
+
+	/// Check the response of a server to our offer.
+	fn accept_response(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		for p in params {
+			match p.name() {
+				CLIENT_NO_CONTEXT_TAKEOVER => self.no_context_takeover = true,
+				SERVER_NO_CONTEXT_TAKEOVER => {}
+				SERVER_MAX_WINDOW_BITS if window_bits(p).is_some() => {}
+				// We didn't offer to use a smaller window, which the compressor doesn't support.
+				CLIENT_MAX_WINDOW_BITS if window_bits(p) == Some(WINDOW_BITS) => {}
+				_ => return Err(format!("Unexpected permessage-deflate parameter {}", p).into()),
+			}
+		}
+
+		self.enabled = true;
+		self.stats.set_negotiated(negotiated(params));
+		Ok(())
+	}
+}
+
+/// Format the extension with its negotiated `params` like in the handshake.
+fn negotiated(params: &[Param]) -> String {
+	params.iter().fold(String::from("permessage-deflate"), |negotiated, p| format!("{}; {}", negotiated, p))
+}
+
+fn param(name: &'static str, value: Option<u8>) -> Param<'static> {
+	let mut param = Param::new(name);
+	param.set_value(value.map(|value| value.to_string()));
+	param
+}
+
+fn window_bits(param: &Param) -> Option<u8> {
+	param.value()?.parse().ok().filter(|bits| (8..=WINDOW_BITS).contains(bits))
+}
+
+impl Extension for Deflate {
+	fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	fn name(&self) -> &str {
+		"permessage-deflate"
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		&self.params
+	}
+
+	fn configure(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		match self.mode {
+			Mode::Server => {
+				// A client may make several offers, the first acceptable one is used.
+				if !self.enabled {
+					self.accept_offer(params);
+				}
+				Ok(())
+			}
+			Mode::Client => self.accept_response(params),
+		}
+	}
+
+	fn encode(&mut self, header: &mut Header, data: &mut Storage) -> Result<(), BoxedError> {
+		let input = data.as_ref();
+		if !matches!(header.opcode(), OpCode::Text | OpCode::Binary)
+			|| input.len() < self.config.threshold.max(1)
+			|| !self.stats.is_enabled()
+		{
+			return Ok(());
+		}
+
+		if self.no_context_takeover {
+			self.compress.reset();
+		}
+
+		self.buffer.clear();
+		self.buffer.reserve(input.len() / 2 + 64);
+		let start = self.compress.total_in();
+
+		loop {
+			let consumed = (self.compress.total_in() - start) as usize;
+			self.compress.compress_vec(&input[consumed..], &mut self.buffer, FlushCompress::Sync)?;
+
+			let done =
+				self.compress.total_in() - start == input.len() as u64 && self.buffer.len() < self.buffer.capacity();
+			if done {
+				break;
+			}
+			self.buffer.reserve(self.buffer.capacity().max(64));
+		}
+
+		if !self.buffer.ends_with(&TRAILER) {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "Compressed message misses its trailer").into());
+		}
+		self.buffer.truncate(self.buffer.len() - TRAILER.len());
+		self.stats.record(input.len(), self.buffer.len());
+
+		*data = Storage::Owned(std::mem::take(&mut self.buffer));
+		header.set_rsv1(true);
+		header.set_payload_len(data.as_ref().len());
+		Ok(())
+	}
+
+	fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		match header.opcode() {
+			OpCode::Text | OpCode::Binary if header.is_rsv1() => {
+				if !header.is_fin() {
+					self.await_last_fragment = true;
+					return Ok(());
+				}
+			}
+			OpCode::Continue if header.is_fin() && self.await_last_fragment => self.await_last_fragment = false,
+			_ => return Ok(()),
+		}
+
+		data.extend_from_slice(&TRAILER);
+		self.buffer.clear();
+		self.buffer.reserve(data.len() * 4);
+		let start = self.decompress.total_in();
+
+		loop {
+			let consumed = (self.decompress.total_in() - start) as usize;
+			self.decompress.decompress_vec(&data[consumed..], &mut self.buffer, FlushDecompress::Sync)?;
+
+			if self.buffer.len() > self.max_message_len {
+				return Err(format!("Decompressed message exceeds {} bytes", self.max_message_len).into());
+			}
+			let done =
+				self.decompress.total_in() - start == data.len() as u64 && self.buffer.len() < self.buffer.capacity();
+			if done {
+				break;
+			}
+			self.buffer.reserve(self.buffer.capacity().max(64));
+		}
+
+		std::mem::swap(data, &mut self.buffer);
+		header.set_rsv1(false);
+		header.set_payload_len(data.len());
+		Ok(())
+	}
+
+	fn reserved_bits(&self) -> (bool, bool, bool) {
+		(true, false, false)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn roundtrip(sender: &mut Deflate, receiver: &mut Deflate, message: &[u8]) -> (bool, Vec<u8>) {
+		let mut header = Header::new(OpCode::Text);
+		let mut data = Storage::Shared(message);
+		sender.encode(&mut header, &mut data).unwrap();
+		let compressed = header.is_rsv1();
+
+		let mut data = data.as_ref().to_vec();
+		receiver.decode(&mut header, &mut data).unwrap();
+		assert!(!header.is_rsv1());
+		(compressed, data)
+	}
+
+	fn negotiate(offer: &[Param]) -> (Deflate, Deflate) {
+		let mut server = Deflate::new(Mode::Server, DeflateConfig::new().threshold(16), 1024);
+		let mut client = Deflate::new(Mode::Client, DeflateConfig::new().threshold(16), 1024);
+		server.configure(offer).unwrap();
+		client.configure(server.params()).unwrap();
+		assert!(server.is_enabled() && client.is_enabled());
+		(server, client)
+	}
+
+	#[test]
+	fn messages_are_compressed_with_context_takeover() {
+		let (mut server, mut client) = negotiate(&[]);
+		assert!(server.params().is_empty());
+
+		let message = br#"{"jsonrpc":"2.0","method":"subscribe_hello","params":{"subscription":1,"result":"hello"}}"#;
+		let (compressed, data) = roundtrip(&mut server, &mut client, message);
+		assert!(compressed);
+		assert_eq!(data, message);
+
+		// The second message refers to the first one.
+		let first_len = {
+			let mut data = Storage::Shared(message);
+			let mut fresh = Deflate::new(Mode::Server, DeflateConfig::new().threshold(16), 1024);
+			fresh.encode(&mut Header::new(OpCode::Text), &mut data).unwrap();
+			data.as_ref().len()
+		};
+		let mut header = Header::new(OpCode::Text);
+		let mut data = Storage::Shared(&message[..]);
+		server.encode(&mut header, &mut data).unwrap();
+		assert!(data.as_ref().len() < first_len);
+
+		let mut data = data.as_ref().to_vec();
+		client.decode(&mut header, &mut data).unwrap();
+		assert_eq!(data, message);
+
+		let (compressed, data) = roundtrip(&mut client, &mut server, message);
+		assert!(compressed);
+		assert_eq!(data, message);
+	}
+
+	#[test]
+	fn short_messages_are_not_compressed() {
+		let (mut server, mut client) = negotiate(&[param(SERVER_NO_CONTEXT_TAKEOVER, None)]);
+		assert!(server.no_context_takeover);
+
+		assert_eq!(roundtrip(&mut server, &mut client, b"{}"), (false, b"{}".to_vec()));
+		let message = [b'a'; 1000];
+		assert_eq!(roundtrip(&mut server, &mut client, &message), (true, message.to_vec()));
+	}
+
+	#[test]
+	fn oversized_messages_are_rejected() {
+		let (mut server, mut client) = negotiate(&[]);
+		let mut header = Header::new(OpCode::Text);
+		let message = [b'a'; 2048];
+		let mut data = Storage::Shared(&message[..]);
+		client.encode(&mut header, &mut data).unwrap();
+
+		let mut data = data.as_ref().to_vec();
+		assert!(server.decode(&mut header, &mut data).is_err());
+	}
+
+	#[test]
+	fn client_window_is_negotiated() {
+		let mut server = Deflate::new(Mode::Server, DeflateConfig::new().window_bits(10), 1024);
+		server.configure(&[param(CLIENT_MAX_WINDOW_BITS, None), param(CLIENT_NO_CONTEXT_TAKEOVER, None)]).unwrap();
+		assert!(server.is_enabled());
+		assert_eq!(server.params(), [param(CLIENT_MAX_WINDOW_BITS, Some(10))]);
+
+		// Clients that didn't offer to negotiate their window use the largest one.
+		let mut server = Deflate::new(Mode::Server, DeflateConfig::new().window_bits(10), 1024);
+		server.configure(&[]).unwrap();
+		assert!(server.params().is_empty());
+	}
+
+	#[test]
+	fn compression_is_disabled_if_it_saves_too_little() {
+		let config = DeflateConfig::new().threshold(16).min_savings(50);
+		let mut server = Deflate::new(Mode::Server, config, 4096);
+		let mut client = Deflate::new(Mode::Client, config, 4096);
+		server.configure(&[param(SERVER_NO_CONTEXT_TAKEOVER, None)]).unwrap();
+		client.configure(server.params()).unwrap();
+		let stats = server.stats();
+		assert_eq!(stats.negotiated().unwrap(), "permessage-deflate; server_no_context_takeover");
+
+		// Random bytes don't compress.
+		let mut seed = 1u32;
+		let message: Vec<u8> = (0..1024)
+			.map(|_| {
+				seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+				(seed >> 16) as u8
+			})
+			.collect();
+		for _ in 0..crate::compression::SAMPLE_SIZE {
+			assert_eq!(roundtrip(&mut server, &mut client, &message), (true, message.clone()));
+		}
+		assert!(!stats.is_enabled());
+		assert_eq!(roundtrip(&mut server, &mut client, &message), (false, message.clone()));
+
+		// The messages of the peer are still decompressed.
+		assert_eq!(roundtrip(&mut client, &mut server, &message), (true, message));
+	}
+
+	#[test]
+	fn unsupported_offers_are_declined() {
+		let mut server = Deflate::new(Mode::Server, DeflateConfig::new(), 1024);
+		server.configure(&[param(SERVER_MAX_WINDOW_BITS, Some(10))]).unwrap();
+		assert!(!server.is_enabled());
+		server.configure(&[param(SERVER_MAX_WINDOW_BITS, Some(15))]).unwrap();
+		assert!(server.is_enabled());
+	}
+}
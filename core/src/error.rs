@@ -99,6 +99,22 @@ pub enum Error {
 	/// Request timeout
 	#[error("Request timeout")]
 	RequestTimeout,
+	/// The connection was closed because the server didn't answer a ping in time.
+	#[error("Connection stale: no pong received in time")]
+	ConnectionStale,
+	/// The connection was lost and the client is reconnecting.
+	#[error("Connection lost, reconnecting")]
+	Reconnecting,
+	/// The subscription was closed because its buffer of notifications was full, with the client's
+	/// `OverflowPolicy::CloseWithError`.
+	#[error("Subscription closed: its buffer of notifications was full")]
+	SubscriptionBufferFull,
+	/// The server closed the subscription with the given reason.
+	#[error("Subscription closed by the server: {}", .0.message())]
+	SubscriptionClosedByServer(ErrorObjectOwned),
+	/// Notifications of the subscription were dropped because its buffer was full, the number of which is given.
+	#[error("Subscription lagged: {0} notifications were dropped")]
+	SubscriptionLagged(usize),
 	/// Configured max number of request slots exceeded.
 	#[error("Configured max number of request slots exceeded")]
 	MaxSlotsExceeded,
@@ -120,12 +136,27 @@ pub enum Error {
 	/// Failed to initialize resources for a method at startup
 	#[error("Resource name `{0}` not found for method `{1}`")]
 	ResourceNameNotFoundForMethod(&'static str, &'static str),
+	/// The resource is not registered on the server
+	#[error("Resource name `{0}` not found")]
+	ResourceNameNotFound(String),
 	/// Trying to claim resources for a method execution, but the method resources have not been initialized
 	#[error("Method `{0}` has uninitialized resources")]
 	UninitializedMethod(Box<str>),
-	/// Failed to register a resource due to a maximum number of resources already registered
-	#[error("Maximum number of resources reached")]
-	MaxResourcesReached,
+	/// Failed to register a rate limit group due to a name conflict
+	#[error("Rate limit group name already taken: {0}")]
+	RateLimitGroupAlreadyTaken(&'static str),
+	/// Failed to register a rate limit group because a method already belongs to another group
+	#[error("Method `{0}` already belongs to a rate limit group")]
+	MethodAlreadyRateLimited(&'static str),
+	/// Failed to require a permission scope because the method already requires another one
+	#[error("Method `{0}` already requires a permission scope")]
+	MethodScopeAlreadyRequired(&'static str),
+	/// Failed to register a server in a server set due to a name conflict
+	#[error("Server name already taken: {0}")]
+	ServerNameAlreadyTaken(&'static str),
+	/// Invalid range of IP addresses.
+	#[error("Invalid IP range: {0}")]
+	InvalidIpRange(String),
 	/// Custom error.
 	#[error("Custom error: {0}")]
 	Custom(String),
@@ -144,12 +175,24 @@ impl Error {
 	{
 		Error::Call(CallError::from_std_error(err))
 	}
+
+	/// Returns the JSON-RPC error object sent by the server, if the call failed with one or the server closed the
+	/// subscription with one.
+	///
+	/// Its data can be deserialized with [`ErrorObject::data_as`](jsonrpsee_types::error::ErrorObject::data_as).
+	pub fn error_object(&self) -> Option<&ErrorObjectOwned> {
+		match self {
+			Error::Call(CallError::Custom(err)) | Error::SubscriptionClosedByServer(err) => Some(err),
+			_ => None,
+		}
+	}
 }
 
 impl Into<ErrorObjectOwned> for Error {
 	fn into(self) -> ErrorObjectOwned {
 		match self {
 			Error::Call(CallError::Custom(err)) => err,
+			Error::SubscriptionClosedByServer(err) => err,
 			Error::Call(CallError::InvalidParams(e)) => {
 				ErrorObject::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>)
 			}
@@ -26,6 +26,9 @@
 
 //! Utility methods relying on hyper
 
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
 use crate::error::GenericTransportError;
 use futures_util::stream::StreamExt;
 
@@ -36,9 +39,34 @@ use futures_util::stream::StreamExt;
 /// Returns `Err` if the body was too large or the body couldn't be read.
 pub async fn read_body(
 	headers: &hyper::HeaderMap,
-	mut body: hyper::Body,
+	body: hyper::Body,
 	max_request_body_size: u32,
 ) -> Result<(Vec<u8>, bool), GenericTransportError<hyper::Error>> {
+	let mut received_data = Vec::new();
+	let single = read_body_into(headers, body, max_request_body_size, &mut received_data).await?;
+	Ok((received_data, single))
+}
+
+/// Like [`read_body`], but reads the data into a buffer taken from `pool`, which is returned to the pool once
+/// the [`PooledBody`] is dropped.
+pub async fn read_pooled_body(
+	headers: &hyper::HeaderMap,
+	body: hyper::Body,
+	max_request_body_size: u32,
+	pool: &BodyBufferPool,
+) -> Result<(PooledBody, bool), GenericTransportError<hyper::Error>> {
+	let mut received_data = pool.take();
+	let single = read_body_into(headers, body, max_request_body_size, &mut received_data.buf).await?;
+	Ok((received_data, single))
+}
+
+/// Append the data of `body` to `received_data`, see [`read_body`].
+async fn read_body_into(
+	headers: &hyper::HeaderMap,
+	mut body: hyper::Body,
+	max_request_body_size: u32,
+	received_data: &mut Vec<u8>,
+) -> Result<bool, GenericTransportError<hyper::Error>> {
 	// NOTE(niklasad1): Values bigger than `u32::MAX` will be turned into zero here. This is unlikely to occur in
 	// practice and for that case we fallback to allocating in the while-loop below instead of pre-allocating.
 	let body_size = read_header_content_length(headers).unwrap_or(0);
@@ -62,7 +90,7 @@ pub async fn read_body(
 		_ => return Err(GenericTransportError::Malformed),
 	};
 
-	let mut received_data = Vec::with_capacity(body_size as usize);
+	received_data.reserve(body_size as usize);
 	received_data.extend_from_slice(&first_chunk);
 
 	while let Some(chunk) = body.next().await {
@@ -73,7 +101,88 @@ pub async fn read_body(
 		}
 		received_data.extend_from_slice(&chunk);
 	}
-	Ok((received_data, single))
+	Ok(single)
+}
+
+/// Pool of buffers the bodies of requests are read into, so that the memory of a request can be reused for
+/// the following ones rather than allocated again.
+///
+/// Buffers grown beyond the retained capacity are released rather than kept in the pool, so that a few large
+/// requests don't hold on to memory.
+#[derive(Debug, Clone)]
+pub struct BodyBufferPool {
+	inner: Arc<BodyBufferPoolInner>,
+}
+
+#[derive(Debug)]
+struct BodyBufferPoolInner {
+	buffers: Mutex<Vec<Vec<u8>>>,
+	max_buffers: usize,
+	max_retained_capacity: usize,
+}
+
+impl BodyBufferPool {
+	/// Create a pool keeping up to `max_buffers` buffers of at most `max_retained_capacity` bytes each.
+	pub fn new(max_buffers: usize, max_retained_capacity: usize) -> Self {
+		let inner = BodyBufferPoolInner { buffers: Mutex::new(Vec::new()), max_buffers, max_retained_capacity };
+		Self { inner: Arc::new(inner) }
+	}
+
+	/// Number of buffers currently available in the pool.
+	pub fn available(&self) -> usize {
+		self.buffers().len()
+	}
+
+	fn take(&self) -> PooledBody {
+		let buf = self.buffers().pop().unwrap_or_default();
+		PooledBody { buf, pool: Some(self.clone()) }
+	}
+
+	fn put(&self, mut buf: Vec<u8>) {
+		if buf.capacity() == 0 || buf.capacity() > self.inner.max_retained_capacity {
+			return;
+		}
+
+		let mut buffers = self.buffers();
+		if buffers.len() < self.inner.max_buffers {
+			buf.clear();
+			buffers.push(buf);
+		}
+	}
+
+	fn buffers(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+		self.inner.buffers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+/// Body of a request read by [`read_pooled_body`], its buffer is returned to the pool when dropped.
+#[derive(Debug)]
+pub struct PooledBody {
+	buf: Vec<u8>,
+	pool: Option<BodyBufferPool>,
+}
+
+impl Deref for PooledBody {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.buf
+	}
+}
+
+impl From<Vec<u8>> for PooledBody {
+	/// Wrap a buffer that doesn't belong to any pool.
+	fn from(buf: Vec<u8>) -> Self {
+		Self { buf, pool: None }
+	}
+}
+
+impl Drop for PooledBody {
+	fn drop(&mut self) {
+		if let Some(pool) = self.pool.take() {
+			pool.put(std::mem::take(&mut self.buf));
+		}
+	}
 }
 
 /// Read the `Content-Length` HTTP Header. Must fit into a `u32`; returns `None` otherwise.
@@ -105,6 +214,13 @@ pub fn read_header_values<'a>(
 	headers.get_all(header_name)
 }
 
+/// Returns all values for a given header name joined by commas, as if they were sent in a single header.
+pub fn join_header_values(headers: &hyper::header::HeaderMap, header_name: &str) -> Option<String> {
+	let values: Vec<&str> =
+		read_header_values(headers, header_name).iter().filter_map(|val| val.to_str().ok()).collect();
+	(!values.is_empty()).then(|| values.join(","))
+}
+
 /// Get the header values from the `access-control-request-headers` header.
 pub fn get_cors_request_headers<'a>(headers: &'a hyper::header::HeaderMap) -> impl Iterator<Item = &str> {
 	const ACCESS_CONTROL_REQUEST_HEADERS: &str = "access-control-request-headers";
@@ -119,7 +235,7 @@ pub fn get_cors_request_headers<'a>(headers: &'a hyper::header::HeaderMap) -> im
 
 #[cfg(test)]
 mod tests {
-	use super::{get_cors_request_headers, read_body, read_header_content_length};
+	use super::{get_cors_request_headers, read_body, read_header_content_length, read_pooled_body, BodyBufferPool};
 
 	#[tokio::test]
 	async fn body_to_bytes_size_limit_works() {
@@ -128,6 +244,33 @@ mod tests {
 		assert!(read_body(&headers, body, 127).await.is_err());
 	}
 
+	#[tokio::test]
+	async fn pooled_buffers_are_reused() {
+		let headers = hyper::header::HeaderMap::new();
+		let pool = BodyBufferPool::new(1, 64);
+
+		let (body, single) = read_pooled_body(&headers, hyper::Body::from("[1, 2]"), 128, &pool).await.unwrap();
+		assert_eq!(&*body, b"[1, 2]");
+		assert!(!single);
+		assert_eq!(pool.available(), 0);
+		drop(body);
+		assert_eq!(pool.available(), 1);
+
+		// The buffer is returned to the pool even if the body is rejected.
+		assert!(read_pooled_body(&headers, hyper::Body::from(vec![b' '; 129]), 128, &pool).await.is_err());
+		assert_eq!(pool.available(), 1);
+
+		let (body, single) = read_pooled_body(&headers, hyper::Body::from("{}"), 128, &pool).await.unwrap();
+		assert_eq!(&*body, b"{}");
+		assert!(single);
+		drop(body);
+
+		// Buffers grown beyond the retained capacity are released.
+		let (body, _) = read_pooled_body(&headers, hyper::Body::from(vec![b'{'; 100]), 128, &pool).await.unwrap();
+		drop(body);
+		assert_eq!(pool.available(), 0);
+	}
+
 	#[test]
 	fn read_content_length_works() {
 		let mut headers = hyper::header::HeaderMap::new();
@@ -42,10 +42,24 @@ pub mod traits;
 /// Middleware trait and implementation.
 pub mod middleware;
 
+pub mod compression;
+
 cfg_http_helpers! {
 	pub mod http_helpers;
 }
 
+cfg_tcp! {
+	pub mod tcp;
+}
+
+cfg_deflate! {
+	pub mod deflate;
+}
+
+cfg_stdio! {
+	pub mod stdio;
+}
+
 cfg_server! {
 	pub mod id_providers;
 	pub mod server;
@@ -70,6 +84,10 @@ pub mod __reexports {
 	pub use async_trait::async_trait;
 	pub use serde;
 	pub use serde_json;
+
+	cfg_schemas! {
+		pub use schemars;
+	}
 }
 
 pub use beef::Cow;
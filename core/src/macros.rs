@@ -20,6 +20,42 @@ macro_rules! cfg_server {
 	};
 }
 
+macro_rules! cfg_tcp {
+ ($($item:item)*) => {
+		cfg_feature!("tcp", $($item)*);
+	};
+}
+
+macro_rules! cfg_deflate {
+ ($($item:item)*) => {
+		cfg_feature!("deflate", $($item)*);
+	};
+}
+
+macro_rules! cfg_stdio {
+ ($($item:item)*) => {
+		cfg_feature!("stdio", $($item)*);
+	};
+}
+
+macro_rules! cfg_replay {
+ ($($item:item)*) => {
+		cfg_feature!("replay", $($item)*);
+	};
+}
+
+macro_rules! cfg_schemas {
+ ($($item:item)*) => {
+		cfg_feature!("schemas", $($item)*);
+	};
+}
+
+macro_rules! cfg_openrpc {
+ ($($item:item)*) => {
+		cfg_feature!("openrpc", $($item)*);
+	};
+}
+
 macro_rules! cfg_http_helpers {
  ($($item:item)*) => {
 		cfg_feature!("http-helpers", $($item)*);
@@ -26,6 +26,144 @@
 
 //! Middleware for `jsonrpsee` servers.
 
+use std::future::{self, Future};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+
+use jsonrpsee_types::{ErrorObject, ErrorObjectOwned, Id, Params};
+
+/// Future returned by [`Middleware::on_call_async`], resolving to an error if the call is rejected.
+pub type OnCallFuture = Pin<Box<dyn Future<Output = Result<(), ErrorObjectOwned>> + Send>>;
+
+/// Transport over which a call was received.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Transport {
+	/// HTTP.
+	Http,
+	/// WebSocket.
+	WebSocket,
+	/// Standard input and output of the process, or another pair of pipes.
+	Stdio,
+}
+
+/// Details of a JSON-RPC method call, passed to [`Middleware::on_call_info`].
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct CallInfo<'a> {
+	/// Name of the called method.
+	pub name: &'a str,
+	/// Raw parameters of the call.
+	pub params: &'a Params<'a>,
+	/// Id of the call.
+	pub id: &'a Id<'a>,
+	/// Transport over which the call was received.
+	pub transport: Transport,
+	/// Address of the client, which is the address reported by a trusted proxy for HTTP requests forwarded by
+	/// one.
+	pub client_ip: Option<IpAddr>,
+}
+
+impl<'a> CallInfo<'a> {
+	/// Create the details of a call.
+	pub fn new(name: &'a str, params: &'a Params<'a>, id: &'a Id<'a>, transport: Transport) -> Self {
+		Self { name, params, id, transport, client_ip: None }
+	}
+
+	/// Set the address of the client that made the call.
+	pub fn with_client_ip(mut self, client_ip: Option<IpAddr>) -> Self {
+		self.client_ip = client_ip;
+		self
+	}
+}
+
+/// Details of a WebSocket connection, passed to [`Middleware::on_connect_info`] and
+/// [`Middleware::on_disconnect_info`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+	/// Id of the connection, unique among the open connections of the server.
+	pub conn_id: usize,
+	/// Address of the client.
+	pub remote_addr: SocketAddr,
+}
+
+impl ConnectionInfo {
+	/// Create the details of a connection.
+	pub fn new(conn_id: usize, remote_addr: SocketAddr) -> Self {
+		Self { conn_id, remote_addr }
+	}
+}
+
+/// Details of a response, passed to [`Middleware::on_response_info`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResponseInfo {
+	/// Size of the response in bytes. For batches whose responses are streamed, this is the size of the
+	/// responses of the calls without the enclosing JSON array.
+	pub size: usize,
+}
+
+impl ResponseInfo {
+	/// Create the details of a response.
+	pub fn new(size: usize) -> Self {
+		Self { size }
+	}
+}
+
+/// Usage of a resource of the server, passed to [`Middleware::on_resource_usage`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResourceUsage {
+	/// Label of the resource.
+	pub label: &'static str,
+	/// Units claimed by the calls being executed.
+	pub in_use: u32,
+	/// Highest number of units claimed at the same time since the server started, or since the peak was reset.
+	pub peak: u32,
+	/// Capacity of the resource, `0` if it's not limited.
+	pub capacity: u16,
+}
+
+impl ResourceUsage {
+	/// Create the usage of a resource.
+	pub fn new(label: &'static str, in_use: u32, peak: u32, capacity: u16) -> Self {
+		Self { label, in_use, peak, capacity }
+	}
+}
+
+/// Compression statistics of a connection, passed to [`Middleware::on_compression_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompressionInfo {
+	/// Address of the client.
+	pub remote_addr: SocketAddr,
+	/// Transport of the connection.
+	pub transport: Transport,
+	/// Compression negotiated with the client: the `permessage-deflate` extension and its parameters for
+	/// WebSocket connections, the content coding of the last compressed response for HTTP connections.
+	pub negotiated: String,
+	/// Number of messages that were compressed.
+	pub messages: u64,
+	/// Size in bytes of the compressed messages before compression.
+	pub uncompressed_bytes: u64,
+	/// Size in bytes of the compressed messages after compression.
+	pub compressed_bytes: u64,
+	/// Whether compression was disabled for the connection because it didn't save enough.
+	pub disabled: bool,
+}
+
+impl CompressionInfo {
+	/// Returns the achieved compression ratio, the size of the messages before compression divided by their size
+	/// after it. `1.0` if no message was compressed.
+	pub fn ratio(&self) -> f64 {
+		if self.compressed_bytes == 0 {
+			1.0
+		} else {
+			self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+		}
+	}
+}
+
 /// Defines a middleware with callbacks during the RPC request life-cycle. The primary use case for
 /// this is to collect timings for a larger metrics collection solution but the only constraints on
 /// the associated type is that it be [`Send`] and [`Copy`], giving users some freedom to do what
@@ -42,20 +180,72 @@ pub trait Middleware: Send + Sync + Clone + 'static {
 	/// Called when a new client connects (WebSocket only)
 	fn on_connect(&self) {}
 
+	/// Called when a new client connects like [`Middleware::on_connect`], with the id of the connection and the
+	/// address of the client. Calls [`Middleware::on_connect`] by default, middlewares implement either of them.
+	fn on_connect_info(&self, _info: &ConnectionInfo) {
+		self.on_connect();
+	}
+
 	/// Called when a new JSON-RPC comes to the server.
 	fn on_request(&self) -> Self::Instant;
 
 	/// Called on each JSON-RPC method call, batch requests will trigger `on_call` multiple times.
 	fn on_call(&self, _name: &str) {}
 
+	/// Called on each JSON-RPC method call like [`Middleware::on_call`], with the parameters, id and transport of
+	/// the call. Calls [`Middleware::on_call`] by default, middlewares implement either of them.
+	fn on_call_info(&self, info: &CallInfo) {
+		self.on_call(info.name);
+	}
+
+	/// Called on each JSON-RPC method call that passed the authentication and rate limiting checks, right before
+	/// it's executed. The call is only executed once the returned future completes, which allows to do work that
+	/// needs to `await`, such as writing audit logs or consulting an authorization service.
+	///
+	/// Resolving to an error rejects the call, which is then answered with that error.
+	///
+	/// For WebSocket connections, no further single calls of the connection are processed until the future of
+	/// a single call completes.
+	fn on_call_async(&self, _name: &str, _params: &Params) -> OnCallFuture {
+		Box::pin(future::ready(Ok(())))
+	}
+
+	/// Called on each JSON-RPC method call answered with an error, right before [`Middleware::on_result`], with the
+	/// error sent to the client. Allows to tell apart unknown methods, application errors or busy servers, by code.
+	///
+	/// Not called for calls whose answer couldn't be sent. Note that `success` is `true` in the following call to
+	/// [`Middleware::on_result`] if the error was sent by the method itself.
+	fn on_error(&self, _name: &str, _error: &ErrorObject) {}
+
 	/// Called on each JSON-RPC method completion, batch requests will trigger `on_result` multiple times.
 	fn on_result(&self, _name: &str, _success: bool, _started_at: Self::Instant) {}
 
 	/// Called once the JSON-RPC request is finished and response is sent to the output buffer.
 	fn on_response(&self, _started_at: Self::Instant) {}
 
+	/// Called once the JSON-RPC request is finished like [`Middleware::on_response`], with the size of the
+	/// response. Calls [`Middleware::on_response`] by default, middlewares implement either of them.
+	fn on_response_info(&self, _info: &ResponseInfo, started_at: Self::Instant) {
+		self.on_response(started_at);
+	}
+
 	/// Called when a client disconnects (WebSocket only)
 	fn on_disconnect(&self) {}
+
+	/// Called when a client disconnects like [`Middleware::on_disconnect`], with the same details that were passed
+	/// to [`Middleware::on_connect_info`]. Calls [`Middleware::on_disconnect`] by default, middlewares implement
+	/// either of them.
+	fn on_disconnect_info(&self, _info: &ConnectionInfo) {
+		self.on_disconnect();
+	}
+
+	/// Called periodically with the usage of every registered resource, if the server is configured to report it
+	/// (see `resource_usage_interval` on the server builders).
+	fn on_resource_usage(&self, _usage: &[ResourceUsage]) {}
+
+	/// Called when a connection whose messages could be compressed is closed, with how well they compressed. For
+	/// HTTP, only connections over which at least one response was compressed are reported.
+	fn on_compression_info(&self, _info: &CompressionInfo) {}
 }
 
 impl Middleware for () {
@@ -71,6 +261,16 @@ where
 {
 	type Instant = (A::Instant, B::Instant);
 
+	fn on_connect(&self) {
+		self.0.on_connect();
+		self.1.on_connect();
+	}
+
+	fn on_connect_info(&self, info: &ConnectionInfo) {
+		self.0.on_connect_info(info);
+		self.1.on_connect_info(info);
+	}
+
 	fn on_request(&self) -> Self::Instant {
 		(self.0.on_request(), self.1.on_request())
 	}
@@ -80,6 +280,26 @@ where
 		self.1.on_call(name);
 	}
 
+	fn on_call_info(&self, info: &CallInfo) {
+		self.0.on_call_info(info);
+		self.1.on_call_info(info);
+	}
+
+	fn on_call_async(&self, name: &str, params: &Params) -> OnCallFuture {
+		let first = self.0.on_call_async(name, params);
+		let second = self.1.on_call_async(name, params);
+
+		Box::pin(async move {
+			first.await?;
+			second.await
+		})
+	}
+
+	fn on_error(&self, name: &str, error: &ErrorObject) {
+		self.0.on_error(name, error);
+		self.1.on_error(name, error);
+	}
+
 	fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
 		self.0.on_result(name, success, started_at.0);
 		self.1.on_result(name, success, started_at.1);
@@ -89,4 +309,29 @@ where
 		self.0.on_response(started_at.0);
 		self.1.on_response(started_at.1);
 	}
+
+	fn on_response_info(&self, info: &ResponseInfo, started_at: Self::Instant) {
+		self.0.on_response_info(info, started_at.0);
+		self.1.on_response_info(info, started_at.1);
+	}
+
+	fn on_disconnect(&self) {
+		self.0.on_disconnect();
+		self.1.on_disconnect();
+	}
+
+	fn on_disconnect_info(&self, info: &ConnectionInfo) {
+		self.0.on_disconnect_info(info);
+		self.1.on_disconnect_info(info);
+	}
+
+	fn on_resource_usage(&self, usage: &[ResourceUsage]) {
+		self.0.on_resource_usage(usage);
+		self.1.on_resource_usage(usage);
+	}
+
+	fn on_compression_info(&self, info: &CompressionInfo) {
+		self.0.on_compression_info(info);
+		self.1.on_compression_info(info);
+	}
 }
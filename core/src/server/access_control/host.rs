@@ -25,6 +25,13 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! Host header validation.
+//!
+//! Hosts are matched by hostname and port separately:
+//!
+//! - The hostname may contain wildcards, e.g. `*.example.com` matches all the subdomains of `example.com`.
+//!   IPv6 addresses are written in brackets, e.g. `[::1]`, and matched as they are.
+//! - A port of `*` matches any port as well as no port at all, e.g. `localhost:*` or `127.0.0.1:*`. Other port
+//!   patterns such as `85*` only match hosts with a port, and hosts without a port only match hosts without one.
 
 use crate::server::access_control::matcher::{Matcher, Pattern};
 use crate::Error;
@@ -36,7 +43,7 @@ const SPLIT_PROOF: &str = "split always returns non-empty iterator.";
 pub enum Port {
 	/// No port specified (default port)
 	None,
-	/// Port specified as a wildcard pattern
+	/// Port specified as a wildcard pattern, `*` matches any port including the default one.
 	Pattern(String),
 	/// Fixed numeric port
 	Fixed(u16),
@@ -64,6 +71,8 @@ pub struct Host {
 	port: Port,
 	host_with_port: String,
 	matcher: Matcher,
+	/// Only set for port patterns, boxed to keep hosts small.
+	port_matcher: Option<Box<Matcher>>,
 }
 
 impl<T: AsRef<str>> From<T> for Host {
@@ -78,18 +87,25 @@ impl Host {
 		let port = port.into();
 		let hostname = Self::pre_process(hostname);
 		let host_with_port = Self::from_str(&hostname, &port);
-		let matcher = Matcher::new(&host_with_port);
+		let matcher = match hostname.starts_with('[') {
+			// Brackets would be a character class in a glob.
+			true => Matcher::literal(&hostname),
+			false => Matcher::new(&hostname),
+		};
+		let port_matcher = match port {
+			Port::Pattern(ref port) => Some(Box::new(Matcher::new(port))),
+			_ => None,
+		};
 
-		Host { hostname, port, host_with_port, matcher }
+		Host { hostname, port, host_with_port, matcher, port_matcher }
 	}
 
 	/// Attempts to parse given string as a `Host`.
 	/// NOTE: This method always succeeds and falls back to sensible defaults.
 	pub fn parse(hostname: &str) -> Self {
 		let hostname = Self::pre_process(hostname);
-		let mut hostname = hostname.split(':');
-		let host = hostname.next().expect(SPLIT_PROOF);
-		let port = match hostname.next() {
+		let (host, port) = split_host_port(&hostname);
+		let port = match port {
 			None => Port::None,
 			Some(port) => match port.parse::<u16>().ok() {
 				Some(num) => Port::Fixed(num),
@@ -128,7 +144,31 @@ impl Host {
 
 impl Pattern for Host {
 	fn matches<T: AsRef<str>>(&self, other: T) -> bool {
-		self.matcher.matches(other)
+		let (hostname, port) = split_host_port(other.as_ref());
+		let port_matches = match (&self.port, port) {
+			(Port::None, None) => true,
+			(Port::Fixed(fixed), Some(port)) => port.parse() == Ok(*fixed),
+			(Port::Pattern(pattern), None) => pattern == "*",
+			(Port::Pattern(_), Some(port)) => {
+				port.parse::<u16>().is_ok() && self.port_matcher.as_ref().is_some_and(|m| m.matches(port))
+			}
+			_ => false,
+		};
+
+		port_matches && self.matcher.matches(hostname)
+	}
+}
+
+/// Splits `host` into its hostname and port, the hostname may be an IPv6 address in brackets.
+fn split_host_port(host: &str) -> (&str, Option<&str>) {
+	let hostname_end = match host.starts_with('[') {
+		true => host.find(']').map_or(host.len(), |end| end + 1),
+		false => 0,
+	};
+
+	match host[hostname_end..].find(':') {
+		Some(colon) => (&host[..hostname_end + colon], Some(&host[hostname_end + colon + 1..])),
+		None => (host, None),
 	}
 }
 
@@ -204,4 +244,43 @@ mod tests {
 	fn should_support_wildcards() {
 		assert!((AllowHosts::Only(vec!["*.web3.site:*".into()].into())).verify("parity.web3.site:8180").is_ok());
 	}
+
+	#[test]
+	fn should_match_wildcard_subdomains() {
+		let hosts = AllowHosts::Only(vec!["*.example.com".into()]);
+		assert!(hosts.verify("api.example.com").is_ok());
+		assert!(hosts.verify("eu.api.EXAMPLE.com").is_ok());
+		assert!(hosts.verify("example.com").is_err());
+		assert!(hosts.verify("api.example.com:8080").is_err());
+		assert!(hosts.verify("example.com.evil.io").is_err());
+	}
+
+	#[test]
+	fn should_match_any_port() {
+		let hosts = AllowHosts::Only(vec!["localhost:*".into(), "127.0.0.1:*".into(), "[::1]:*".into()]);
+		assert!(hosts.verify("localhost").is_ok());
+		assert!(hosts.verify("localhost:9944").is_ok());
+		assert!(hosts.verify("127.0.0.1:30333").is_ok());
+		assert!(hosts.verify("[::1]:9944").is_ok());
+		assert!(hosts.verify("[::1]").is_ok());
+		assert!(hosts.verify("localhost:abc").is_err());
+		assert!(hosts.verify("127.0.0.2:9944").is_err());
+		assert!(hosts.verify("[::2]:9944").is_err());
+	}
+
+	#[test]
+	fn should_match_port_patterns() {
+		let hosts = AllowHosts::Only(vec!["localhost:99*".into()]);
+		assert!(hosts.verify("localhost:9944").is_ok());
+		assert!(hosts.verify("localhost:8545").is_err());
+		assert!(hosts.verify("localhost").is_err());
+	}
+
+	#[test]
+	fn should_parse_ipv6_host() {
+		let host = Host::parse("http://[::1]:9944/somepath");
+		assert_eq!(host.hostname.as_str(), "[::1]");
+		assert_eq!(host.port, Port::Fixed(9944));
+		assert_eq!(Host::parse("[::1]"), Host::new("[::1]", None));
+	}
 }
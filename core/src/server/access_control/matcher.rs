@@ -35,8 +35,9 @@ pub(crate) trait Pattern {
 	fn matches<T: AsRef<str>>(&self, other: T) -> bool;
 }
 
+/// The compiled glob is boxed as it's large, while hosts and origins hold several matchers.
 #[derive(Clone)]
-pub(crate) struct Matcher(Option<GlobMatcher>, String);
+pub(crate) struct Matcher(Option<Box<GlobMatcher>>, String);
 
 impl Matcher {
 	pub(crate) fn new(string: &str) -> Matcher {
@@ -44,12 +45,17 @@ impl Matcher {
 			GlobBuilder::new(string)
 				.case_insensitive(true)
 				.build()
-				.map(|g| g.compile_matcher())
+				.map(|g| Box::new(g.compile_matcher()))
 				.map_err(|e| warn!("Invalid glob pattern for {}: {:?}", string, e))
 				.ok(),
 			string.into(),
 		)
 	}
+
+	/// Matcher of `string` itself, ignoring case, without interpreting it as a glob pattern.
+	pub(crate) fn literal(string: &str) -> Matcher {
+		Matcher(None, string.into())
+	}
 }
 
 impl Pattern for Matcher {
@@ -7,6 +7,8 @@ mod matcher;
 pub use cors::{AllowHeaders, AllowOrigin, Origin};
 pub use host::{AllowHosts, Host};
 
+use std::time::Duration;
+
 use crate::Error;
 
 use self::cors::get_cors_allow_origin;
@@ -17,6 +19,9 @@ pub struct AccessControl {
 	allowed_hosts: AllowHosts,
 	allowed_origins: Option<Vec<AllowOrigin>>,
 	allowed_headers: AllowHeaders,
+	allow_credentials: bool,
+	max_age: Option<Duration>,
+	exposed_headers: Vec<String>,
 }
 
 impl AccessControl {
@@ -67,11 +72,50 @@ impl AccessControl {
 	pub fn allowed_headers(&self) -> &AllowHeaders {
 		&self.allowed_headers
 	}
+
+	/// Return whether CORS requests may include credentials (`access-control-allow-credentials`).
+	pub fn allow_credentials(&self) -> bool {
+		self.allow_credentials
+	}
+
+	/// Return for how long browsers may cache the result of a preflight request (`access-control-max-age`).
+	pub fn max_age(&self) -> Option<Duration> {
+		self.max_age
+	}
+
+	/// Return the response headers exposed to browsers (`access-control-expose-headers`).
+	pub fn exposed_headers(&self) -> &[String] {
+		&self.exposed_headers
+	}
+
+	/// Return the additional `access-control-*` headers of the response to a preflight request.
+	pub fn preflight_headers(&self) -> Vec<(&'static str, String)> {
+		let mut headers = Vec::new();
+		if self.allow_credentials {
+			headers.push(("access-control-allow-credentials", "true".to_owned()));
+		}
+		if let Some(max_age) = self.max_age {
+			headers.push(("access-control-max-age", max_age.as_secs().to_string()));
+		}
+		headers
+	}
+
+	/// Return the additional `access-control-*` headers of the response to an actual CORS request.
+	pub fn response_headers(&self) -> Vec<(&'static str, String)> {
+		let mut headers = Vec::new();
+		if self.allow_credentials {
+			headers.push(("access-control-allow-credentials", "true".to_owned()));
+		}
+		if !self.exposed_headers.is_empty() {
+			headers.push(("access-control-expose-headers", self.exposed_headers.join(", ")));
+		}
+		headers
+	}
 }
 
 impl Default for AccessControl {
 	fn default() -> Self {
-		Self { allowed_hosts: AllowHosts::Any, allowed_origins: None, allowed_headers: AllowHeaders::Any }
+		AccessControlBuilder::default().build()
 	}
 }
 
@@ -81,11 +125,21 @@ pub struct AccessControlBuilder {
 	allowed_hosts: AllowHosts,
 	allowed_origins: Option<Vec<AllowOrigin>>,
 	allowed_headers: AllowHeaders,
+	allow_credentials: bool,
+	max_age: Option<Duration>,
+	exposed_headers: Vec<String>,
 }
 
 impl Default for AccessControlBuilder {
 	fn default() -> Self {
-		Self { allowed_hosts: AllowHosts::Any, allowed_origins: None, allowed_headers: AllowHeaders::Any }
+		Self {
+			allowed_hosts: AllowHosts::Any,
+			allowed_origins: None,
+			allowed_headers: AllowHeaders::Any,
+			allow_credentials: false,
+			max_age: None,
+			exposed_headers: Vec::new(),
+		}
 	}
 }
 
@@ -115,6 +169,9 @@ impl AccessControlBuilder {
 
 	/// Configure allowed hosts.
 	///
+	/// Hostnames may contain wildcards such as `*.example.com`, and a port of `*` allows any port, for instance
+	/// `localhost:*`. See the [`host`] module for the details.
+	///
 	/// Default - allow all.
 	pub fn set_allowed_hosts<List, H>(mut self, list: List) -> Result<Self, Error>
 	where
@@ -161,12 +218,45 @@ impl AccessControlBuilder {
 		Ok(self)
 	}
 
+	/// Configure whether CORS requests may include credentials such as cookies or an `authorization` header,
+	/// by sending `access-control-allow-credentials: true`.
+	///
+	/// Default - false.
+	pub fn allow_credentials(mut self, allow: bool) -> Self {
+		self.allow_credentials = allow;
+		self
+	}
+
+	/// Configure for how long browsers may cache the result of a preflight request, sent in whole seconds
+	/// as `access-control-max-age`.
+	///
+	/// Default - not sent, browsers use their own default.
+	pub fn set_max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = Some(max_age);
+		self
+	}
+
+	/// Configure the response headers browsers expose to scripts, sent as `access-control-expose-headers`.
+	///
+	/// Default - none.
+	pub fn set_exposed_headers<Header, List>(mut self, list: List) -> Self
+	where
+		List: IntoIterator<Item = Header>,
+		Header: Into<String>,
+	{
+		self.exposed_headers = list.into_iter().map(Into::into).collect();
+		self
+	}
+
 	/// Finalize the `AccessControl` settings.
 	pub fn build(self) -> AccessControl {
 		AccessControl {
 			allowed_hosts: self.allowed_hosts,
 			allowed_origins: self.allowed_origins,
 			allowed_headers: self.allowed_headers,
+			allow_credentials: self.allow_credentials,
+			max_age: self.max_age,
+			exposed_headers: self.exposed_headers,
 		}
 	}
 }
@@ -0,0 +1,247 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Authentication
+//!
+//! This module handles authenticating clients and restricting which methods they may call.
+//!
+//! Clients authenticate by providing a token in the `Authorization` header of the HTTP request or of the
+//! WebSocket handshake, optionally prefixed by `Bearer `. The token is checked by an [`AuthValidator`], either
+//! a table of [`StaticKeys`] or a user-provided async function, which grants the client a set of
+//! [`Permissions`]. Requests with an invalid token are rejected, while clients that don't provide a token
+//! get no permissions at all.
+//!
+//! Methods may require a permission scope, calls made by clients lacking that scope are denied execution
+//! and immediately return a JSON-RPC error object with code `-32008`. Methods without a required scope may
+//! be called by anyone.
+//!
+//! ```
+//! use jsonrpsee_core::server::auth::{Authenticator, Permissions, StaticKeys};
+//!
+//! let keys = StaticKeys::new()
+//!     .key("admin-secret", Permissions::new(["admin", "read"]))
+//!     .key("reader-secret", Permissions::new(["read"]));
+//!
+//! let authenticator = Authenticator::new(keys)
+//!     .require_scope("admin", ["admin_shutdown", "admin_peers"])
+//!     .unwrap()
+//!     .require_scope("read", ["balance"])
+//!     .unwrap();
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::Error;
+use futures_util::future::{self, BoxFuture, FutureExt};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Permission scopes granted to a client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Permissions {
+	scopes: Arc<FxHashSet<String>>,
+}
+
+impl Permissions {
+	/// Create permissions granting the given scopes.
+	pub fn new<S: Into<String>>(scopes: impl IntoIterator<Item = S>) -> Self {
+		Self { scopes: Arc::new(scopes.into_iter().map(Into::into).collect()) }
+	}
+
+	/// Returns whether the given scope is granted.
+	pub fn grants(&self, scope: &str) -> bool {
+		self.scopes.contains(scope)
+	}
+}
+
+/// Validates the tokens provided by clients.
+///
+/// Implemented for async functions taking the token and returning the granted [`Permissions`],
+/// or `None` if the token is invalid.
+pub trait AuthValidator: Send + Sync + 'static {
+	/// Returns the permissions granted by `token`, or `None` if the token is invalid.
+	fn validate(&self, token: &str) -> BoxFuture<'static, Option<Permissions>>;
+}
+
+impl<F, Fut> AuthValidator for F
+where
+	F: Fn(String) -> Fut + Send + Sync + 'static,
+	Fut: Future<Output = Option<Permissions>> + Send + 'static,
+{
+	fn validate(&self, token: &str) -> BoxFuture<'static, Option<Permissions>> {
+		(self)(token.to_owned()).boxed()
+	}
+}
+
+/// [`AuthValidator`] backed by a fixed table of keys.
+#[derive(Debug, Clone, Default)]
+pub struct StaticKeys {
+	keys: FxHashMap<String, Permissions>,
+}
+
+impl StaticKeys {
+	/// Create an empty table of keys.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a key granting the given permissions.
+	pub fn key(mut self, key: impl Into<String>, permissions: Permissions) -> Self {
+		self.keys.insert(key.into(), permissions);
+		self
+	}
+}
+
+impl AuthValidator for StaticKeys {
+	fn validate(&self, token: &str) -> BoxFuture<'static, Option<Permissions>> {
+		future::ready(self.keys.get(token).cloned()).boxed()
+	}
+}
+
+/// Authenticates clients and checks their permissions, shared by all connections of a server.
+///
+/// By default no client is authenticated and no method requires any permission.
+#[derive(Clone, Default)]
+pub struct Authenticator {
+	validator: Option<Arc<dyn AuthValidator>>,
+	/// Maps a method name to the scope required to call it.
+	scopes: Arc<FxHashMap<&'static str, &'static str>>,
+}
+
+impl fmt::Debug for Authenticator {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Authenticator")
+			.field("validator", &self.validator.is_some())
+			.field("scopes", &self.scopes)
+			.finish()
+	}
+}
+
+impl Authenticator {
+	/// Create an authenticator validating the tokens provided by clients with `validator`.
+	pub fn new(validator: impl AuthValidator) -> Self {
+		Self { validator: Some(Arc::new(validator)), scopes: Default::default() }
+	}
+
+	/// Require the given scope to call `methods`.
+	///
+	/// Errors if one of the methods already requires a scope.
+	pub fn require_scope(
+		mut self,
+		scope: &'static str,
+		methods: impl IntoIterator<Item = &'static str>,
+	) -> Result<Self, Error> {
+		let scopes = Arc::make_mut(&mut self.scopes);
+
+		for method in methods {
+			if scopes.insert(method, scope).is_some() {
+				return Err(Error::MethodScopeAlreadyRequired(method));
+			}
+		}
+
+		Ok(self)
+	}
+
+	/// Authenticate a client from the value of its `Authorization` header, if any.
+	///
+	/// Returns `None` if the provided token is invalid, clients without token get no permissions.
+	pub async fn authenticate(&self, authorization: Option<&str>) -> Option<Permissions> {
+		let (validator, authorization) = match (self.validator.as_ref(), authorization) {
+			(Some(validator), Some(authorization)) => (validator, authorization),
+			_ => return Some(Permissions::default()),
+		};
+
+		let token = authorization.strip_prefix("Bearer ").unwrap_or(authorization).trim();
+		validator.validate(token).await
+	}
+
+	/// Returns a handle to check the calls of a client that was granted `permissions`.
+	pub fn client(&self, permissions: Permissions) -> ClientAuth {
+		ClientAuth { scopes: self.scopes.clone(), permissions }
+	}
+}
+
+/// Permissions of a single authenticated client.
+#[derive(Debug, Clone)]
+pub struct ClientAuth {
+	scopes: Arc<FxHashMap<&'static str, &'static str>>,
+	permissions: Permissions,
+}
+
+impl ClientAuth {
+	/// Check whether this client may call `method`.
+	///
+	/// Returns the missing scope if it may not.
+	pub fn check(&self, method: &str) -> Result<(), &'static str> {
+		match self.scopes.get(method) {
+			Some(&scope) if !self.permissions.grants(scope) => Err(scope),
+			_ => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Authenticator, Permissions, StaticKeys};
+
+	fn authenticator() -> Authenticator {
+		let keys = StaticKeys::new().key("secret", Permissions::new(["admin"]));
+		Authenticator::new(keys).require_scope("admin", ["admin_shutdown"]).unwrap()
+	}
+
+	#[tokio::test]
+	async fn static_keys_work() {
+		let auth = authenticator();
+
+		let admin = auth.client(auth.authenticate(Some("Bearer secret")).await.unwrap());
+		assert!(admin.check("admin_shutdown").is_ok());
+		assert!(admin.check("say_hello").is_ok());
+
+		let anonymous = auth.client(auth.authenticate(None).await.unwrap());
+		assert_eq!(anonymous.check("admin_shutdown"), Err("admin"));
+		assert!(anonymous.check("say_hello").is_ok());
+
+		assert!(auth.authenticate(Some("Bearer wrong")).await.is_none());
+	}
+
+	#[tokio::test]
+	async fn async_validator_works() {
+		let auth =
+			Authenticator::new(|token: String| async move { (token == "secret").then(|| Permissions::new(["admin"])) })
+				.require_scope("admin", ["admin_shutdown"])
+				.unwrap();
+
+		let admin = auth.client(auth.authenticate(Some("secret")).await.unwrap());
+		assert!(admin.check("admin_shutdown").is_ok());
+		assert!(auth.authenticate(Some("wrong")).await.is_none());
+	}
+
+	#[test]
+	fn scope_conflicts_are_rejected() {
+		assert!(authenticator().require_scope("other", ["admin_shutdown"]).is_err());
+	}
+}
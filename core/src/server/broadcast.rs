@@ -0,0 +1,148 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Broadcast
+//!
+//! Most subscriptions send the same notifications to all their subscribers, such as the new blocks of a chain.
+//! A [`SubscriptionBroadcaster`] registers such a subscription method and keeps track of its subscribers, so that
+//! each notification is serialized once and sent to all of them with a single call.
+//!
+//! ```
+//! use jsonrpsee_core::server::broadcast::SubscriptionBroadcaster;
+//! use jsonrpsee_core::server::rpc_module::RpcModule;
+//!
+//! let mut module = RpcModule::new(());
+//! let blocks = SubscriptionBroadcaster::<u64>::register(&mut module, "subscribe_blocks", "blocks", "unsubscribe_blocks")
+//!     .unwrap();
+//!
+//! // Typically called from the task importing the blocks.
+//! let reached = blocks.broadcast(&42).unwrap();
+//! assert_eq!(reached, 0);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::server::rpc_module::{RpcModule, SubscriptionSink};
+use crate::Error;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::value::{to_raw_value, RawValue};
+
+/// Subscription method whose notifications are sent to all its subscribers at once, see the
+/// [module documentation](self).
+///
+/// Cloning it gives another handle to the same subscribers.
+pub struct SubscriptionBroadcaster<T> {
+	sinks: Arc<Mutex<Vec<SubscriptionSink>>>,
+	_marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> SubscriptionBroadcaster<T> {
+	/// Register a subscription method on `module` accepting all the subscription calls, see
+	/// [`RpcModule::register_subscription`] for the meaning of the method names.
+	pub fn register<Context: Send + Sync + 'static>(
+		module: &mut RpcModule<Context>,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+	) -> Result<Self, Error> {
+		let sinks: Arc<Mutex<Vec<SubscriptionSink>>> = Default::default();
+
+		let subscribers = sinks.clone();
+		module.register_subscription(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			move |_, mut sink, _| {
+				if sink.accept().is_ok() {
+					subscribers.lock().push(sink);
+				}
+				Ok(())
+			},
+		)?;
+
+		Ok(Self { sinks, _marker: PhantomData })
+	}
+
+	/// Send `item` to all the subscribers, serializing it once.
+	///
+	/// Returns the number of subscribers `item` was sent to, the closed subscriptions are forgotten.
+	pub fn broadcast(&self, item: &T) -> Result<usize, serde_json::Error> {
+		let item: Box<RawValue> = to_raw_value(item)?;
+
+		let mut sinks = self.sinks.lock();
+		// Only fails for items some subscriptions can't represent, which then miss it.
+		sinks.retain_mut(|sink| sink.send(&item).unwrap_or(true));
+		Ok(sinks.len())
+	}
+
+	/// Returns the number of subscribers, forgetting the closed subscriptions.
+	pub fn subscriber_count(&self) -> usize {
+		let mut sinks = self.sinks.lock();
+		sinks.retain(|sink| !sink.is_closed());
+		sinks.len()
+	}
+}
+
+impl<T> Clone for SubscriptionBroadcaster<T> {
+	fn clone(&self) -> Self {
+		Self { sinks: self.sinks.clone(), _marker: PhantomData }
+	}
+}
+
+impl<T> fmt::Debug for SubscriptionBroadcaster<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SubscriptionBroadcaster").field("subscribers", &self.sinks.lock().len()).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::SubscriptionBroadcaster;
+	use crate::server::rpc_module::RpcModule;
+	use jsonrpsee_types::EmptyParams;
+
+	#[tokio::test]
+	async fn broadcast_reaches_all_subscribers() {
+		let mut module = RpcModule::new(());
+		let broadcaster = SubscriptionBroadcaster::<String>::register(&mut module, "sub", "notif", "unsub").unwrap();
+
+		let mut first = module.subscribe("sub", EmptyParams::new()).await.unwrap();
+		let mut second = module.subscribe("sub", EmptyParams::new()).await.unwrap();
+		assert_eq!(broadcaster.subscriber_count(), 2);
+
+		assert_eq!(broadcaster.broadcast(&"hello".to_string()).unwrap(), 2);
+		assert_eq!(first.next::<String>().await.unwrap().unwrap().0, "hello");
+		assert_eq!(second.next::<String>().await.unwrap().unwrap().0, "hello");
+
+		drop(first);
+		assert_eq!(broadcaster.broadcast(&"bye".to_string()).unwrap(), 1);
+		assert_eq!(second.next::<String>().await.unwrap().unwrap().0, "bye");
+		assert_eq!(broadcaster.subscriber_count(), 1);
+	}
+}
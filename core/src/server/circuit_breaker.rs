@@ -0,0 +1,299 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Circuit Breaking
+//!
+//! This module handles answering calls to methods whose backend keeps failing with an error right away, instead
+//! of invoking the method and amplifying the load on the backend with every retry of the clients.
+//!
+//! Unlike [`rate_limiting`](super::rate_limiting), which budgets the calls of every client separately, the state
+//! of a circuit is shared by all clients of a server. The outcomes of the calls to a method are counted over a
+//! fixed window: once the share of calls answered with an error reaches the configured error rate, the circuit
+//! of the method opens. While open, calls are denied execution, immediately returning a JSON-RPC error object
+//! with code `-32011` whose `data` field contains the number of milliseconds after which the call may be
+//! retried. Once that delay elapsed, a single trial call is let through: the circuit closes again if it
+//! succeeds and stays open for another period otherwise.
+//!
+//! Calls answered with an invalid params error are caused by the client and don't count as failures.
+//!
+//! ```
+//! use std::time::Duration;
+//! use jsonrpsee_core::server::circuit_breaker::{BreakerPolicy, CircuitBreaker};
+//!
+//! let breaker = CircuitBreaker::new()
+//!     // Open the circuit of a method once half of its calls over 10 seconds failed.
+//!     .default_policy(BreakerPolicy::new(0.5, Duration::from_secs(10)))
+//!     // But be more tolerant with a flaky backend, and only retry it after a minute.
+//!     .method("eth_call", BreakerPolicy::new(0.9, Duration::from_secs(10)).open_for(Duration::from_secs(60)));
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonrpsee_types::error::{ErrorObjectOwned, INVALID_PARAMS_CODE};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+/// Minimum number of calls in a window before the circuit may open, unless configured otherwise.
+const DEFAULT_MIN_CALLS: u32 = 10;
+
+/// When the circuit of a method opens and for how long it stays open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakerPolicy {
+	error_rate: f64,
+	window: Duration,
+	min_calls: u32,
+	open_for: Duration,
+}
+
+impl BreakerPolicy {
+	/// Open the circuit once the share of failed calls within `window` reaches `error_rate`, between 0 and 1.
+	///
+	/// The circuit stays open for `window` by default, see [`BreakerPolicy::open_for`].
+	///
+	/// # Panics
+	///
+	/// If `error_rate` is not greater than 0 and at most 1.
+	pub fn new(error_rate: f64, window: Duration) -> Self {
+		assert!(error_rate > 0.0 && error_rate <= 1.0, "The error rate of a circuit breaker must be in (0, 1]");
+		Self { error_rate, window, min_calls: DEFAULT_MIN_CALLS, open_for: window }
+	}
+
+	/// Set the number of calls a window must contain before the circuit may open (default is 10), so that a
+	/// few failures of a rarely called method don't open its circuit.
+	pub fn min_calls(mut self, min_calls: u32) -> Self {
+		self.min_calls = min_calls.max(1);
+		self
+	}
+
+	/// Set how long the circuit stays open before a trial call is let through.
+	pub fn open_for(mut self, open_for: Duration) -> Self {
+		self.open_for = open_for;
+		self
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct BreakerConfig {
+	/// Policy of the methods without a policy of their own.
+	default: Option<BreakerPolicy>,
+	/// Policy of specific methods.
+	methods: FxHashMap<&'static str, BreakerPolicy>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Circuit {
+	/// Calls are executed, their outcomes counted in the window starting at `started_at`.
+	Closed { started_at: Instant, calls: u32, errors: u32 },
+	/// Calls are denied until `until`, after which one trial call is let through and `trial` is set. The
+	/// circuit stays open for another period meanwhile, in case the outcome of the trial is never recorded.
+	Open { until: Instant, trial: bool },
+}
+
+impl Circuit {
+	fn closed(now: Instant) -> Self {
+		Self::Closed { started_at: now, calls: 0, errors: 0 }
+	}
+}
+
+/// Circuit breaker shared by all connections of a server.
+///
+/// By default no policy is configured and calls are never denied.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+	config: Arc<BreakerConfig>,
+	circuits: Arc<Mutex<FxHashMap<Box<str>, Circuit>>>,
+}
+
+impl CircuitBreaker {
+	/// Create a circuit breaker without any policy.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the policy of the methods without a policy of their own. By default these are never denied.
+	pub fn default_policy(mut self, policy: BreakerPolicy) -> Self {
+		Arc::make_mut(&mut self.config).default = Some(policy);
+		self
+	}
+
+	/// Set the policy of `method`, replacing the default policy for it.
+	pub fn method(mut self, method: &'static str, policy: BreakerPolicy) -> Self {
+		Arc::make_mut(&mut self.config).methods.insert(method, policy);
+		self
+	}
+
+	/// Returns whether any policy is configured.
+	pub fn is_enabled(&self) -> bool {
+		self.config.default.is_some() || !self.config.methods.is_empty()
+	}
+
+	fn policy(&self, method: &str) -> Option<&BreakerPolicy> {
+		self.config.methods.get(method).or(self.config.default.as_ref())
+	}
+
+	/// Check whether a call to `method` may be executed.
+	///
+	/// Returns the time after which the call may be retried if the circuit of the method is open.
+	pub fn check(&self, method: &str) -> Result<(), Duration> {
+		let policy = match self.policy(method) {
+			Some(policy) => policy,
+			None => return Ok(()),
+		};
+
+		let now = Instant::now();
+		let mut circuits = self.circuits.lock();
+
+		match circuits.get_mut(method) {
+			Some(Circuit::Open { until, .. }) if now < *until => Err(*until - now),
+			Some(circuit @ Circuit::Open { .. }) => {
+				*circuit = Circuit::Open { until: now + policy.open_for, trial: true };
+				Ok(())
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Record the outcome of an executed call to `method`, `error` is the error it was answered with if any.
+	pub fn record(&self, method: &str, error: Option<&ErrorObjectOwned>) {
+		let policy = match self.policy(method) {
+			Some(policy) => *policy,
+			None => return,
+		};
+
+		let failed = error.is_some_and(|err| err.code() != INVALID_PARAMS_CODE);
+		let now = Instant::now();
+		let mut circuits = self.circuits.lock();
+		let circuit = circuits.entry(method.into()).or_insert_with(|| Circuit::closed(now));
+
+		match circuit {
+			Circuit::Open { trial: true, .. } if failed => {
+				tracing::warn!("Trial call to `{}` failed, keeping its circuit open", method);
+				*circuit = Circuit::Open { until: now + policy.open_for, trial: false };
+			}
+			Circuit::Open { trial: true, .. } => {
+				tracing::info!("Trial call to `{}` succeeded, closing its circuit", method);
+				*circuit = Circuit::closed(now);
+			}
+			// Calls admitted before the circuit opened don't affect it.
+			Circuit::Open { .. } => {}
+			Circuit::Closed { started_at, calls, errors } => {
+				if now.saturating_duration_since(*started_at) >= policy.window {
+					(*started_at, *calls, *errors) = (now, 0, 0);
+				}
+
+				*calls += 1;
+				*errors += failed as u32;
+
+				if *calls >= policy.min_calls && f64::from(*errors) >= policy.error_rate * f64::from(*calls) {
+					tracing::warn!("{} of {} calls to `{}` failed, opening its circuit", errors, calls, method);
+					*circuit = Circuit::Open { until: now + policy.open_for, trial: false };
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BreakerPolicy, CircuitBreaker};
+	use jsonrpsee_types::error::{ErrorCode, ErrorObjectOwned};
+	use std::time::Duration;
+
+	fn internal_error() -> ErrorObjectOwned {
+		ErrorCode::InternalError.into()
+	}
+
+	#[test]
+	fn disabled_by_default() {
+		let err = internal_error();
+		let breaker = CircuitBreaker::new();
+		assert!(!breaker.is_enabled());
+
+		for _ in 0..100 {
+			breaker.record("foo", Some(&err));
+			assert!(breaker.check("foo").is_ok());
+		}
+	}
+
+	#[test]
+	fn circuit_opens_at_error_rate() {
+		let err = internal_error();
+		let breaker = CircuitBreaker::new()
+			.default_policy(BreakerPolicy::new(0.5, Duration::from_secs(60)).min_calls(4))
+			.method("tolerant", BreakerPolicy::new(1.0, Duration::from_secs(60)).min_calls(4));
+
+		breaker.record("foo", None);
+		breaker.record("foo", Some(&err));
+		breaker.record("foo", None);
+		assert!(breaker.check("foo").is_ok());
+		breaker.record("foo", Some(&err));
+
+		let retry_after = breaker.check("foo").unwrap_err();
+		assert!(retry_after > Duration::from_secs(59) && retry_after <= Duration::from_secs(60));
+		// Other methods have their own circuit.
+		assert!(breaker.check("bar").is_ok());
+
+		for _ in 0..3 {
+			breaker.record("tolerant", Some(&err));
+		}
+		breaker.record("tolerant", None);
+		assert!(breaker.check("tolerant").is_ok());
+	}
+
+	#[test]
+	fn invalid_params_are_not_failures() {
+		let breaker =
+			CircuitBreaker::new().default_policy(BreakerPolicy::new(0.5, Duration::from_secs(60)).min_calls(1));
+		let invalid_params: ErrorObjectOwned = ErrorCode::InvalidParams.into();
+
+		breaker.record("foo", Some(&invalid_params));
+		assert!(breaker.check("foo").is_ok());
+	}
+
+	#[test]
+	fn trial_call_decides_whether_circuit_closes() {
+		let err = internal_error();
+		let policy = BreakerPolicy::new(1.0, Duration::from_secs(60)).min_calls(1).open_for(Duration::from_millis(50));
+		let breaker = CircuitBreaker::new().default_policy(policy);
+
+		breaker.record("foo", Some(&err));
+		assert!(breaker.check("foo").is_err());
+		std::thread::sleep(Duration::from_millis(60));
+
+		// Only a single trial call is let through, and it fails.
+		assert!(breaker.check("foo").is_ok());
+		assert!(breaker.check("foo").is_err());
+		breaker.record("foo", Some(&err));
+		assert!(breaker.check("foo").is_err());
+		std::thread::sleep(Duration::from_millis(60));
+
+		assert!(breaker.check("foo").is_ok());
+		breaker.record("foo", None);
+		assert!(breaker.check("foo").is_ok());
+		assert!(breaker.check("foo").is_ok());
+	}
+}
@@ -0,0 +1,186 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Forwarded Headers
+//!
+//! This module handles finding out the address of clients connecting through reverse proxies that add the
+//! `Forwarded` (RFC 7239) or `X-Forwarded-For` headers to the requests they forward.
+//!
+//! These headers can be forged by any client, they are only looked at when the request was received from one
+//! of the configured trusted proxies. The addresses listed in the headers are then walked from the most recent
+//! one, added by the proxy the request was received from, skipping the addresses of trusted proxies. The first
+//! other address is the address of the client. The `Forwarded` header is used if present, `X-Forwarded-For`
+//! otherwise.
+//!
+//! The address of the client is used instead of the address of the proxy for the IP filter, the rate limits and
+//! the middleware of the server.
+//!
+//! ```
+//! use jsonrpsee_core::server::forwarded::TrustedProxies;
+//!
+//! let proxies = TrustedProxies::new().trust(["10.0.0.0/8"]).unwrap();
+//!
+//! let client = proxies.client_ip("10.0.0.1".parse().unwrap(), None, Some("203.0.113.7, 10.0.0.2"));
+//! assert_eq!(client, "203.0.113.7".parse::<std::net::IpAddr>().unwrap());
+//!
+//! // Requests of untrusted peers are taken at face value.
+//! let client = proxies.client_ip("192.0.2.1".parse().unwrap(), None, Some("203.0.113.7"));
+//! assert_eq!(client, "192.0.2.1".parse::<std::net::IpAddr>().unwrap());
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+use crate::server::ip_filter::IpRange;
+use crate::Error;
+
+/// Proxies trusted to report the address of the clients of a server.
+///
+/// By default no proxy is trusted.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Arc<Vec<IpRange>>);
+
+impl TrustedProxies {
+	/// Create a set of trusted proxies without any proxy.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Trust the proxies whose address is in the given ranges, in CIDR notation.
+	pub fn trust<List, R>(mut self, list: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = R>,
+		R: AsRef<str>,
+	{
+		let parsed = list.into_iter().map(|range| range.as_ref().parse()).collect::<Result<Vec<IpRange>, _>>()?;
+		Arc::make_mut(&mut self.0).extend(parsed);
+		Ok(self)
+	}
+
+	/// Returns whether any proxy is trusted.
+	pub fn is_enabled(&self) -> bool {
+		!self.0.is_empty()
+	}
+
+	/// Returns whether `ip` is the address of a trusted proxy.
+	pub fn is_trusted(&self, ip: IpAddr) -> bool {
+		self.0.iter().any(|range| range.contains(ip))
+	}
+
+	/// Returns the address of the client of a request received from `peer`, given the values of its `Forwarded`
+	/// and `X-Forwarded-For` headers, with the values of repeated headers joined by commas.
+	///
+	/// Returns the last address that could be parsed if the addresses listed in the header stop being valid, as
+	/// is the case for obfuscated identifiers, and the first listed address if all of them are trusted.
+	pub fn client_ip(&self, peer: IpAddr, forwarded: Option<&str>, x_forwarded_for: Option<&str>) -> IpAddr {
+		if !self.is_trusted(peer) {
+			return peer;
+		}
+
+		let hops: Box<dyn DoubleEndedIterator<Item = &str>> = match (forwarded, x_forwarded_for) {
+			(Some(forwarded), _) => Box::new(forwarded.split(',').filter_map(forwarded_for)),
+			(None, Some(x_forwarded_for)) => Box::new(x_forwarded_for.split(',')),
+			(None, None) => return peer,
+		};
+
+		let mut client = peer;
+		for hop in hops.rev() {
+			match parse_node(hop) {
+				Some(ip) if self.is_trusted(ip) => client = ip,
+				Some(ip) => return ip,
+				None => break,
+			}
+		}
+		client
+	}
+}
+
+/// Returns the `for` parameter of an element of a `Forwarded` header.
+fn forwarded_for(element: &str) -> Option<&str> {
+	element.split(';').find_map(|pair| {
+		let (name, value) = pair.split_once('=')?;
+		name.trim().eq_ignore_ascii_case("for").then_some(value)
+	})
+}
+
+/// Parse the address of a node, optionally quoted and followed by a port, e.g. `"[2001:db8::1]:4711"`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+	let node = node.trim().trim_matches('"');
+
+	if let Some(rest) = node.strip_prefix('[') {
+		let (ip, _port) = rest.split_once(']')?;
+		return ip.parse().ok();
+	}
+
+	node.parse().ok().or_else(|| {
+		let (ip, _port) = node.rsplit_once(':')?;
+		ip.parse::<Ipv4Addr>().ok().map(IpAddr::V4)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::TrustedProxies;
+	use std::net::IpAddr;
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	fn proxies() -> TrustedProxies {
+		TrustedProxies::new().trust(["10.0.0.0/8", "fd00::/8"]).unwrap()
+	}
+
+	#[test]
+	fn untrusted_peers_are_taken_at_face_value() {
+		assert_eq!(TrustedProxies::new().client_ip(ip("10.0.0.1"), None, Some("1.2.3.4")), ip("10.0.0.1"));
+		assert_eq!(proxies().client_ip(ip("192.0.2.1"), Some("for=1.2.3.4"), Some("1.2.3.4")), ip("192.0.2.1"));
+		assert_eq!(proxies().client_ip(ip("10.0.0.1"), None, None), ip("10.0.0.1"));
+	}
+
+	#[test]
+	fn x_forwarded_for_is_walked_from_the_right() {
+		let proxies = proxies();
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("1.2.3.4")), ip("1.2.3.4"));
+		// The leftmost address may be forged by the client.
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("6.6.6.6, 1.2.3.4, 10.0.0.2")), ip("1.2.3.4"));
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("1.2.3.4:5678")), ip("1.2.3.4"));
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("[2001:db8::1]:80")), ip("2001:db8::1"));
+		// All addresses are trusted.
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("10.0.0.3, 10.0.0.2")), ip("10.0.0.3"));
+		// Invalid addresses stop the walk.
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), None, Some("1.2.3.4, unknown, 10.0.0.2")), ip("10.0.0.2"));
+	}
+
+	#[test]
+	fn forwarded_takes_precedence() {
+		let proxies = proxies();
+		let forwarded = r#"for=6.6.6.6, for="[2001:db8:cafe::17]:4711";proto=https, For=10.0.0.2;by=10.0.0.1"#;
+		assert_eq!(proxies.client_ip(ip("fd00::1"), Some(forwarded), Some("1.2.3.4")), ip("2001:db8:cafe::17"));
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), Some("for=_hidden"), Some("1.2.3.4")), ip("10.0.0.1"));
+		assert_eq!(proxies.client_ip(ip("10.0.0.1"), Some("proto=http"), None), ip("10.0.0.1"));
+	}
+}
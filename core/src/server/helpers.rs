@@ -24,16 +24,36 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::any::Any;
+use std::fmt;
 use std::io;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-
-use crate::tracing::tx_log_from_str;
-use crate::{Error};
+use std::time::Duration;
+
+use crate::middleware::{CallInfo, Middleware, ResponseInfo, Transport};
+use crate::server::auth::ClientAuth;
+use crate::server::circuit_breaker::CircuitBreaker;
+use crate::server::rate_limiting::ClientRateLimiter;
+use crate::server::resource_limiting::Resources;
+use crate::server::rpc_module::Methods;
+use crate::server::send_queue::{PendingMessages, QueueSender, SubscriptionBuffer};
+use crate::tracing::tx_log_from_bytes;
+use crate::Error;
+use bytes::{BufMut, Bytes, BytesMut};
 use futures_channel::mpsc;
+use futures_util::future::join_all;
 use futures_util::StreamExt;
-use jsonrpsee_types::error::{ErrorCode, ErrorObject, ErrorResponse, OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG};
-use jsonrpsee_types::{Id, InvalidRequest, Response};
+use jsonrpsee_types::error::{
+	reject_circuit_open, reject_rate_limited, reject_unauthorized, reject_unsafe_integer, ErrorCode, ErrorObject, ErrorObjectOwned, ErrorResponse, OVERSIZED_RESPONSE_CODE,
+	OVERSIZED_RESPONSE_MSG,
+};
+use jsonrpsee_types::number::NumberPolicy;
+use jsonrpsee_types::{Id, InvalidRequest, Params, Request, Response};
+use parking_lot::Mutex;
 use serde::Serialize;
+use serde_json::value::RawValue;
 use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 
 /// Bounded writer that allows writing at most `max_len` bytes.
@@ -50,17 +70,28 @@ use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 #[derive(Debug)]
 pub struct BoundedWriter {
 	max_len: usize,
-	buf: Vec<u8>,
+	buf: BytesMut,
 }
 
 impl BoundedWriter {
 	/// Create a new bounded writer.
 	pub fn new(max_len: usize) -> Self {
-		Self { max_len, buf: Vec::with_capacity(128) }
+		Self { max_len, buf: BytesMut::with_capacity(128) }
+	}
+
+	/// Create a new bounded writer that writes into `buf`, reusing its capacity. `buf` is cleared first.
+	pub fn with_buffer(max_len: usize, mut buf: BytesMut) -> Self {
+		buf.clear();
+		Self { max_len, buf }
 	}
 
 	/// Consume the writer and extract the written bytes.
 	pub fn into_bytes(self) -> Vec<u8> {
+		self.buf.into()
+	}
+
+	/// Consume the writer and extract the buffer holding the written bytes.
+	pub fn into_inner(self) -> BytesMut {
 		self.buf
 	}
 }
@@ -81,57 +112,247 @@ impl<'a> io::Write for &'a mut BoundedWriter {
 	}
 }
 
+/// Report of a panic in a method handler, passed to the [`PanicHook`].
+#[derive(Debug, Clone, Copy)]
+pub struct PanicReport<'a> {
+	/// Name of the method whose handler panicked.
+	pub method: &'a str,
+	/// Panic message.
+	pub message: &'a str,
+	/// Identifier of the panic, also sent to the client in the `data` of the error response.
+	pub correlation_id: &'a str,
+}
+
+/// Hook invoked whenever a method handler panics.
+#[derive(Clone)]
+pub struct PanicHook(Arc<dyn Fn(&PanicReport) + Send + Sync>);
+
+impl PanicHook {
+	/// Create a hook calling `hook` with the report of every panic.
+	pub fn new(hook: impl Fn(&PanicReport) + Send + Sync + 'static) -> Self {
+		Self(Arc::new(hook))
+	}
+}
+
+impl fmt::Debug for PanicHook {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("PanicHook")
+	}
+}
+
+/// Error returned by [`MethodSink::send_raw`] when the connection is closed, holding the message that wasn't sent.
+pub struct ConnectionClosed(Bytes);
+
+impl ConnectionClosed {
+	/// Returns the message that wasn't sent.
+	pub fn into_inner(self) -> Bytes {
+		self.0
+	}
+}
+
+impl fmt::Debug for ConnectionClosed {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("ConnectionClosed")
+	}
+}
+
+/// Channel the messages of a [`MethodSink`] are sent to.
+#[derive(Clone, Debug)]
+enum Tx {
+	Unbounded(mpsc::UnboundedSender<Bytes>),
+	Queue(QueueSender),
+}
+
 /// Sink that is used to send back the result to the server for a specific method.
 #[derive(Clone, Debug)]
 pub struct MethodSink {
 	/// Channel sender
-	tx: mpsc::UnboundedSender<String>,
+	tx: Tx,
+	/// Buffer the responses are serialized into, its spare capacity is reused by the following responses.
+	buffer: Arc<Mutex<BytesMut>>,
+	/// Number of bytes sent, counted separately for the sinks of single calls, see [`MethodSink::for_call`].
+	bytes_sent: Arc<AtomicUsize>,
+	/// Count of the sink this sink was created from by [`MethodSink::for_call`], which also counts the bytes sent.
+	outer_bytes_sent: Option<Arc<AtomicUsize>>,
 	/// Max response size in bytes for a executed call.
 	max_response_size: u32,
 	/// Max log length.
 	max_log_length: u32,
+	/// Hook invoked when a method handler panics.
+	panic_hook: Option<PanicHook>,
+	/// Handling of integers that can't be represented exactly as doubles.
+	number_policy: NumberPolicy,
+	/// Last error sent, only recorded by the sinks of single calls, see [`MethodSink::for_call`].
+	last_error: Option<Arc<Mutex<Option<ErrorObjectOwned>>>>,
+	/// Sink the notifications of the subscriptions accepted through this sink are sent to, if not this sink.
+	notifications: Option<Box<MethodSink>>,
+	/// Bound of the messages each subscription has waiting in the send queue.
+	subscription_buffer: Option<SubscriptionBuffer>,
 }
 
 impl MethodSink {
 	/// Create a new `MethodSink` with unlimited response size
-	pub fn new(tx: mpsc::UnboundedSender<String>) -> Self {
-		MethodSink { tx, max_response_size: u32::MAX, max_log_length: u32::MAX }
+	pub fn new(tx: mpsc::UnboundedSender<Bytes>) -> Self {
+		Self::with_tx(Tx::Unbounded(tx), u32::MAX, u32::MAX)
 	}
 
 	/// Create a new `MethodSink` with a limited response size
-	pub fn new_with_limit(tx: mpsc::UnboundedSender<String>, max_response_size: u32, max_log_length: u32) -> Self {
-		MethodSink { tx, max_response_size, max_log_length }
+	pub fn new_with_limit(tx: mpsc::UnboundedSender<Bytes>, max_response_size: u32, max_log_length: u32) -> Self {
+		Self::with_tx(Tx::Unbounded(tx), max_response_size, max_log_length)
+	}
+
+	/// Create a new `MethodSink` with a limited response size, sending to a bounded [`send_queue`].
+	///
+	/// [`send_queue`]: crate::server::send_queue::send_queue
+	pub fn new_queued(tx: QueueSender, max_response_size: u32, max_log_length: u32) -> Self {
+		Self::with_tx(Tx::Queue(tx), max_response_size, max_log_length)
+	}
+
+	fn with_tx(tx: Tx, max_response_size: u32, max_log_length: u32) -> Self {
+		MethodSink {
+			tx,
+			buffer: Default::default(),
+			bytes_sent: Default::default(),
+			outer_bytes_sent: None,
+			max_response_size,
+			max_log_length,
+			panic_hook: None,
+			number_policy: NumberPolicy::Preserve,
+			last_error: None,
+			notifications: None,
+			subscription_buffer: None,
+		}
+	}
+
+	/// Send the notifications of the subscriptions accepted through this sink to `sink`, for sinks that only
+	/// collect the responses of a batch and are dropped once the batch completed.
+	pub fn with_notifications_to(mut self, sink: MethodSink) -> Self {
+		self.notifications = Some(Box::new(sink));
+		self
+	}
+
+	/// Returns the sink the notifications of the subscriptions accepted through this sink are sent to.
+	pub(crate) fn notification_sink(&self) -> Self {
+		self.notifications.as_deref().unwrap_or(self).clone()
+	}
+
+	/// Returns a sink sending to the same client, to answer a single call. It records the last error it sends,
+	/// which is returned by [`MethodSink::take_error`], and counts the bytes it sends, returned by
+	/// [`MethodSink::bytes_sent`]. These bytes are counted by `self` as well.
+	pub fn for_call(&self) -> Self {
+		MethodSink {
+			last_error: Some(Default::default()),
+			bytes_sent: Default::default(),
+			outer_bytes_sent: Some(self.bytes_sent.clone()),
+			..self.clone()
+		}
+	}
+
+	/// Take the last error sent by a sink returned by [`MethodSink::for_call`].
+	pub fn take_error(&self) -> Option<ErrorObjectOwned> {
+		self.last_error.as_ref().and_then(|last_error| last_error.lock().take())
+	}
+
+	/// Returns a sink recording the errors it sends, reusing the slot of `self` if it already has one so that
+	/// the error stays visible to [`MethodSink::take_error`] on `self`.
+	pub(crate) fn recording(&self) -> Self {
+		if self.last_error.is_some() {
+			self.clone()
+		} else {
+			self.for_call()
+		}
+	}
+
+	/// Returns the last error sent by a recording sink, without taking it.
+	pub(crate) fn last_error(&self) -> Option<ErrorObjectOwned> {
+		self.last_error.as_ref().and_then(|last_error| last_error.lock().clone())
+	}
+
+	/// Returns the number of bytes sent by this sink and its clones.
+	pub fn bytes_sent(&self) -> usize {
+		self.bytes_sent.load(Ordering::Relaxed)
+	}
+
+	/// Set the hook invoked when a method handler panics.
+	pub fn with_panic_hook(mut self, panic_hook: Option<PanicHook>) -> Self {
+		self.panic_hook = panic_hook;
+		self
+	}
+
+	/// Set how integers that can't be represented exactly as doubles are handled in results.
+	pub fn with_number_policy(mut self, number_policy: NumberPolicy) -> Self {
+		self.number_policy = number_policy;
+		self
+	}
+
+	/// Returns how integers that can't be represented exactly as doubles are handled in results.
+	pub fn number_policy(&self) -> NumberPolicy {
+		self.number_policy
+	}
+
+	/// Bound the messages each subscription accepted through this sink has waiting in the send queue. Only
+	/// applies to sinks sending to a [`send_queue`](crate::server::send_queue::send_queue).
+	pub fn with_subscription_buffer(mut self, subscription_buffer: Option<SubscriptionBuffer>) -> Self {
+		self.subscription_buffer = subscription_buffer;
+		self
+	}
+
+	/// Returns the bound of the messages each subscription has waiting in the send queue, if any.
+	pub fn subscription_buffer(&self) -> Option<SubscriptionBuffer> {
+		self.subscription_buffer
 	}
 
 	/// Returns whether this channel is closed without needing a context.
 	pub fn is_closed(&self) -> bool {
-		self.tx.is_closed()
+		match &self.tx {
+			Tx::Unbounded(tx) => tx.is_closed(),
+			Tx::Queue(tx) => tx.is_closed(),
+		}
+	}
+
+	/// Wait until the channel has room for another message, see [`QueueSender::ready`]. Returns right away if the
+	/// channel is unbounded.
+	pub async fn ready(&self) {
+		if let Tx::Queue(tx) = &self.tx {
+			tx.ready().await;
+		}
 	}
 
 	/// Send a JSON-RPC response to the client. If the serialization of `result` exceeds `max_response_size`,
 	/// an error will be sent instead.
+	///
+	/// Integers in `result` that can't be represented exactly as doubles are handled according to the
+	/// [`NumberPolicy`] of the sink.
 	pub fn send_response(&self, id: Id, result: impl Serialize) -> bool {
-		let mut writer = BoundedWriter::new(self.max_response_size as usize);
+		if self.number_policy == NumberPolicy::Preserve {
+			return self.send_serialized_response(id, result);
+		}
 
-		let json = match serde_json::to_writer(&mut writer, &Response::new(result, id.clone())) {
-			Ok(_) => {
-				// Safety - serde_json does not emit invalid UTF-8.
-				unsafe { String::from_utf8_unchecked(writer.into_bytes()) }
-			}
-			Err(err) => {
-				tracing::error!("Error serializing response: {:?}", err);
-
-				if err.is_io() {
-					let data = format!("Exceeded max limit of {}", self.max_response_size);
-					let err = ErrorObject::owned(OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG, Some(data));
-					return self.send_error(id, err);
-				} else {
-					return self.send_error(id, ErrorCode::InternalError.into());
-				}
+		let result = match self.serialize(&result) {
+			Ok(json) => json,
+			Err(err) => return self.send_serialization_error(id, err),
+		};
+
+		// Safety - serde_json does not emit invalid UTF-8.
+		let result = unsafe { String::from_utf8_unchecked(result.to_vec()) };
+
+		match self.number_policy.apply(result).map(RawValue::from_string) {
+			Ok(Ok(result)) => self.send_serialized_response(id, result),
+			Ok(Err(err)) => self.send_serialization_error(id, err),
+			Err(value) => {
+				tracing::error!("Result contains the integer {} which can't be represented exactly", value);
+				self.send_error(id, reject_unsafe_integer(&value))
 			}
+		}
+	}
+
+	fn send_serialized_response(&self, id: Id, result: impl Serialize) -> bool {
+		let json = match self.serialize(&Response::new(result, id.clone())) {
+			Ok(json) => json,
+			Err(err) => return self.send_serialization_error(id, err),
 		};
 
-		tx_log_from_str(&json, self.max_log_length);
+		tx_log_from_bytes(&json, self.max_log_length);
 
 		if let Err(err) = self.send_raw(json) {
 			tracing::warn!("Error sending response {:?}", err);
@@ -141,8 +362,37 @@ impl MethodSink {
 		}
 	}
 
+	/// Serialize `value`, failing if it exceeds `max_response_size`.
+	///
+	/// The response is written to the buffer of the sink and split off it, the next responses are written to
+	/// the remaining capacity, or to the whole buffer again once the responses sharing it have been dropped.
+	/// Concurrent calls that find the buffer taken serialize to a new one.
+	fn serialize(&self, value: &impl Serialize) -> Result<Bytes, serde_json::Error> {
+		let buf = std::mem::take(&mut *self.buffer.lock());
+		let mut writer = BoundedWriter::with_buffer(self.max_response_size as usize, buf);
+		serde_json::to_writer(&mut writer, value)?;
+		let mut buf = writer.into_inner();
+		let json = buf.split().freeze();
+		*self.buffer.lock() = buf;
+		Ok(json)
+	}
+
+	fn send_serialization_error(&self, id: Id, err: serde_json::Error) -> bool {
+		tracing::error!("Error serializing response: {:?}", err);
+
+		if err.is_io() {
+			let data = format!("Exceeded max limit of {}", self.max_response_size);
+			let err = ErrorObject::owned(OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG, Some(data));
+			self.send_error(id, err)
+		} else {
+			self.send_error(id, ErrorCode::InternalError.into())
+		}
+	}
+
 	/// Send a JSON-RPC error to the client
 	pub fn send_error(&self, id: Id, error: ErrorObject) -> bool {
+		let recorded = self.last_error.as_ref().map(|_| error.clone().into_owned());
+
 		let json = match serde_json::to_string(&ErrorResponse::borrowed(error, id)) {
 			Ok(json) => json,
 			Err(err) => {
@@ -152,12 +402,15 @@ impl MethodSink {
 			}
 		};
 
-		tx_log_from_str(&json, self.max_log_length);
+		tx_log_from_bytes(json.as_bytes(), self.max_log_length);
 
 		if let Err(err) = self.send_raw(json) {
 			tracing::warn!("Error sending response {:?}", err);
 			false
 		} else {
+			if let (Some(last_error), Some(error)) = (&self.last_error, recorded) {
+				*last_error.lock() = Some(error);
+			}
 			true
 		}
 	}
@@ -167,16 +420,78 @@ impl MethodSink {
 		self.send_error(id, err.into())
 	}
 
+	/// Report the panic of the handler of `method` and send an internal error to the client, whose `data` contains
+	/// the correlation id of the panic.
+	pub fn send_panic(&self, id: Id, method: &str, panic: Box<dyn Any + Send>) -> bool {
+		let correlation_id = self.report_panic(method, panic);
+		let data = serde_json::json!({ "correlation_id": correlation_id });
+		let code = ErrorCode::InternalError;
+
+		self.send_error(id, ErrorObject::owned(code.code(), code.message(), Some(data)))
+	}
+
+	/// Report the panic of the handler of `method` to the panic hook, returns the correlation id of the panic.
+	pub fn report_panic(&self, method: &str, panic: Box<dyn Any + Send>) -> String {
+		let correlation_id = format!("{:016x}", rand::random::<u64>());
+		let message = match panic.downcast_ref::<&str>() {
+			Some(message) => message,
+			None => panic.downcast_ref::<String>().map_or("Box<dyn Any>", String::as_str),
+		};
+
+		tracing::error!("Method `{}` panicked: {} (correlation id: {})", method, message, correlation_id);
+
+		if let Some(hook) = &self.panic_hook {
+			(hook.0)(&PanicReport { method, message, correlation_id: &correlation_id });
+		}
+
+		correlation_id
+	}
+
 	/// Send a raw JSON-RPC message to the client, `MethodSink` does not check verify the validity
 	/// of the JSON being sent.
-	pub fn send_raw(&self, raw_json: String) -> Result<(), mpsc::TrySendError<String>> {
+	pub fn send_raw(&self, raw_json: impl Into<Bytes>) -> Result<(), ConnectionClosed> {
+		self.send_bytes(raw_json.into(), None)
+	}
+
+	/// Send a raw JSON-RPC message to the client like [`MethodSink::send_raw`], counting it in `pending` until
+	/// it's taken from the send queue. Messages sent to an unbounded channel aren't counted.
+	pub fn send_raw_tracked(
+		&self,
+		raw_json: impl Into<Bytes>,
+		pending: &Arc<PendingMessages>,
+	) -> Result<(), ConnectionClosed> {
+		self.send_bytes(raw_json.into(), Some(pending))
+	}
+
+	fn send_bytes(&self, raw_json: Bytes, pending: Option<&Arc<PendingMessages>>) -> Result<(), ConnectionClosed> {
+		let len = raw_json.len();
 		tracing::trace!("send: {:?}", raw_json);
-		self.tx.unbounded_send(raw_json)
+		match (&self.tx, pending) {
+			(Tx::Unbounded(tx), _) => tx.unbounded_send(raw_json).map_err(|err| ConnectionClosed(err.into_inner()))?,
+			(Tx::Queue(tx), Some(pending)) => tx.send_tracked(raw_json, pending).map_err(ConnectionClosed)?,
+			(Tx::Queue(tx), None) => tx.send(raw_json).map_err(ConnectionClosed)?,
+		}
+		self.bytes_sent.fetch_add(len, Ordering::Relaxed);
+		if let Some(outer_bytes_sent) = &self.outer_bytes_sent {
+			outer_bytes_sent.fetch_add(len, Ordering::Relaxed);
+		}
+		Ok(())
 	}
 
 	/// Close the channel for any further messages.
 	pub fn close(&self) {
-		self.tx.close_channel();
+		match &self.tx {
+			Tx::Unbounded(tx) => tx.close_channel(),
+			Tx::Queue(tx) => tx.close(),
+		}
+	}
+
+	/// Close the channel for any further messages, dropping the messages waiting in the send queue.
+	pub fn abort(&self) {
+		match &self.tx {
+			Tx::Unbounded(tx) => tx.close_channel(),
+			Tx::Queue(tx) => tx.abort(),
+		}
 	}
 }
 
@@ -190,21 +505,176 @@ pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
 }
 
 /// Read all the results of all method calls in a batch request from the ['Stream']. Format the result into a single
-/// `String` appropriately wrapped in `[`/`]`.
-pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<String>) -> String {
-	let mut buf = String::with_capacity(2048);
-	buf.push('[');
-	let mut buf = rx
-		.fold(buf, |mut acc, response| async move {
-			acc.push_str(&response);
-			acc.push(',');
-			acc
-		})
-		.await;
-	// Remove trailing comma
-	buf.pop();
-	buf.push(']');
-	buf
+/// buffer appropriately wrapped in `[`/`]`, allocated once with the exact size of the batch response.
+pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<Bytes>) -> Bytes {
+	let responses: Vec<Bytes> = rx.collect().await;
+	let len = responses.iter().map(|response| response.len() + 1).sum::<usize>().max(1) + 1;
+
+	let mut buf = BytesMut::with_capacity(len);
+	buf.put_u8(b'[');
+	for (i, response) in responses.iter().enumerate() {
+		if i > 0 {
+			buf.put_u8(b',');
+		}
+		buf.put_slice(response);
+	}
+	buf.put_u8(b']');
+	buf.freeze()
+}
+
+/// Checks performed on every call of a client before it's dispatched.
+#[derive(Debug, Clone)]
+pub struct CallPolicy {
+	auth: ClientAuth,
+	rate_limit: ClientRateLimiter,
+	circuit_breaker: CircuitBreaker,
+	client_ip: Option<IpAddr>,
+}
+
+impl CallPolicy {
+	/// Create a new [`CallPolicy`] from the permissions and the rate limiter of a client.
+	pub fn new(auth: ClientAuth, rate_limit: ClientRateLimiter) -> Self {
+		Self { auth, rate_limit, circuit_breaker: CircuitBreaker::default(), client_ip: None }
+	}
+
+	/// Set the address of the client, reported to the middleware in [`CallInfo::client_ip`].
+	pub fn with_client_ip(mut self, client_ip: IpAddr) -> Self {
+		self.client_ip = Some(client_ip);
+		self
+	}
+
+	/// Address of the client, if known.
+	pub fn client_ip(&self) -> Option<IpAddr> {
+		self.client_ip
+	}
+
+	/// Set the circuit breaker shared by all clients of the server.
+	pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+		self.circuit_breaker = circuit_breaker;
+		self
+	}
+
+	/// Check whether the client may call `method` of `methods`, returns why the call was denied if it may not.
+	///
+	/// Calls made through an alias are subject to the permission scope and the rate limit of the method it was
+	/// registered for, as well as to the scope required by the alias itself if any.
+	/// Unauthorized calls don't count towards the rate limit.
+	pub fn check(&self, method: &str, methods: &Methods) -> Result<(), CallDenied> {
+		let canonical = methods.canonical_name(method);
+		self.auth.check(canonical).and_then(|()| self.auth.check(method)).map_err(CallDenied::Unauthorized)?;
+		self.rate_limit.check(canonical).map_err(CallDenied::RateLimited)?;
+		self.circuit_breaker.check(method).map_err(CallDenied::CircuitOpen)
+	}
+
+	/// Record the outcome of an executed call to `method` answered through `sink`, which must be the sink
+	/// returned by [`MethodSink::for_call`] for the call.
+	pub fn record(&self, method: &str, sink: &MethodSink) {
+		if self.circuit_breaker.is_enabled() {
+			self.circuit_breaker.record(method, sink.last_error().as_ref());
+		}
+	}
+}
+
+/// Reason why a call was denied by the [`CallPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallDenied {
+	/// The client lacks the permission scope required by the method.
+	Unauthorized(&'static str),
+	/// The client exceeded its rate limit and may retry after the given duration.
+	RateLimited(Duration),
+	/// The circuit of the method is open and the call may be retried after the given duration.
+	CircuitOpen(Duration),
+}
+
+impl From<CallDenied> for ErrorObject<'static> {
+	fn from(denied: CallDenied) -> Self {
+		match denied {
+			CallDenied::Unauthorized(scope) => reject_unauthorized(scope),
+			CallDenied::RateLimited(retry_after) => reject_rate_limited(retry_after),
+			CallDenied::CircuitOpen(retry_after) => reject_circuit_open(retry_after),
+		}
+	}
+}
+
+/// Report the calls of a batch stage to [`Middleware::on_call_info`], then check them against the [`CallPolicy`]
+/// and the [`Middleware::on_call_async`] hook concurrently. Returns every call of the stage in order, paired with
+/// the error to answer it with if denied.
+pub async fn admit_calls<'a, M: Middleware>(
+	stage: Vec<Request<'a>>,
+	path: Option<&str>,
+	transport: Transport,
+	policy: &CallPolicy,
+	methods: &Methods,
+	middleware: &M,
+) -> Vec<(Request<'a>, Result<(), ErrorObjectOwned>)> {
+	join_all(stage.into_iter().map(|req| async move {
+		let params = Params::new(path, req.params.map(|params| params.get()));
+		middleware.on_call_info(&CallInfo::new(&req.method, &params, &req.id, transport).with_client_ip(policy.client_ip()));
+
+		let admitted = match policy.check(&req.method, methods) {
+			Err(denied) => Err(ErrorObject::from(denied)),
+			Ok(()) => middleware.on_call_async(&req.method, &params).await,
+		};
+		(req, admitted)
+	}))
+	.await
+}
+
+/// Report the result of a call answered through `sink` to [`Middleware::on_result`], and to
+/// [`Middleware::on_error`] first if the call was answered with an error.
+///
+/// `sink` must be the sink the call was answered through, returned by [`MethodSink::for_call`].
+pub fn report_result<M: Middleware>(
+	middleware: &M,
+	name: &str,
+	success: bool,
+	sink: &MethodSink,
+	started_at: M::Instant,
+) {
+	if let Some(error) = sink.take_error() {
+		middleware.on_error(name, &error);
+	}
+	middleware.on_result(name, success, started_at);
+}
+
+/// Report the end of a request whose response is `size` bytes long to [`Middleware::on_response_info`].
+pub fn report_response<M: Middleware>(middleware: &M, size: usize, started_at: M::Instant) {
+	middleware.on_response_info(&ResponseInfo::new(size), started_at);
+}
+
+/// Report the usage of `resources` to `middleware` every `period`, forever.
+pub async fn report_resource_usage<M: Middleware>(middleware: M, resources: Resources, period: Duration) {
+	let mut interval = tokio::time::interval(period);
+	loop {
+		interval.tick().await;
+		middleware.on_resource_usage(&resources.usage());
+	}
+}
+
+/// Split the calls of a batch request into stages that must be executed one after another.
+///
+/// Adjacent calls without execution hint or with a `parallel` hint share a stage and may run concurrently,
+/// while every call with a `sequential` hint gets a stage of its own.
+pub fn batch_stages(batch: Vec<Request>) -> Vec<Vec<Request>> {
+	let mut stages: Vec<Vec<Request>> = Vec::new();
+	let mut parallel = Vec::new();
+
+	for req in batch {
+		if req.is_sequential() {
+			if !parallel.is_empty() {
+				stages.push(std::mem::take(&mut parallel));
+			}
+			stages.push(vec![req]);
+		} else {
+			parallel.push(req);
+		}
+	}
+
+	if !parallel.is_empty() {
+		stages.push(parallel);
+	}
+
+	stages
 }
 
 /// A permitted subscription.
@@ -264,7 +734,27 @@ impl BoundedSubscriptions {
 mod tests {
 	use crate::server::helpers::BoundedSubscriptions;
 
-	use super::{BoundedWriter, Id, Response};
+	use super::{batch_stages, collect_batch_response, BoundedWriter, Id, MethodSink, Request, Response};
+	use futures_channel::mpsc;
+	use futures_util::StreamExt;
+
+	#[test]
+	fn batch_stages_works() {
+		let batch: Vec<Request> = serde_json::from_str(
+			r#"[
+				{"jsonrpc":"2.0","method":"a","id":1},
+				{"jsonrpc":"2.0","method":"b","id":2,"execution":"parallel"},
+				{"jsonrpc":"2.0","method":"c","id":3,"execution":"sequential"},
+				{"jsonrpc":"2.0","method":"d","id":4,"execution":"sequential"},
+				{"jsonrpc":"2.0","method":"e","id":5}
+			]"#,
+		)
+		.unwrap();
+
+		let stages: Vec<Vec<_>> =
+			batch_stages(batch).into_iter().map(|stage| stage.into_iter().map(|req| req.method).collect()).collect();
+		assert_eq!(stages, vec![vec!["a", "b"], vec!["c"], vec!["d"], vec!["e"]]);
+	}
 
 	#[test]
 	fn bounded_serializer_work() {
@@ -282,6 +772,38 @@ mod tests {
 		assert!(serde_json::to_writer(&mut writer, &"x".repeat(99)).is_err());
 	}
 
+	#[tokio::test]
+	async fn method_sink_reuses_its_buffer() {
+		let (tx, mut rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx);
+		let call = sink.for_call();
+
+		assert!(call.send_response(Id::Number(1), "x".repeat(1000)));
+		let first = rx.next().await.unwrap();
+		let (first_ptr, first_len) = (first.as_ptr(), first.len());
+		drop(first);
+
+		// Written to the spare capacity of the buffer, or to the start of the buffer again.
+		assert!(call.send_response(Id::Number(2), "y".repeat(1000)));
+		let second = rx.next().await.unwrap();
+		assert!(second.as_ptr() == first_ptr || second.as_ptr() == first_ptr.wrapping_add(first_len));
+
+		assert_eq!(call.bytes_sent(), first_len + second.len());
+		assert_eq!(sink.bytes_sent(), call.bytes_sent());
+	}
+
+	#[tokio::test]
+	async fn collect_batch_response_works() {
+		let (tx, rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx);
+		sink.send_response(Id::Number(1), "a");
+		sink.send_response(Id::Number(2), "b");
+		drop(sink);
+
+		let batch = collect_batch_response(rx).await;
+		assert_eq!(batch, r#"[{"jsonrpc":"2.0","result":"a","id":1},{"jsonrpc":"2.0","result":"b","id":2}]"#);
+	}
+
 	#[test]
 	fn bounded_subscriptions_work() {
 		let subs = BoundedSubscriptions::new(5);
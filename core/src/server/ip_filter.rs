@@ -0,0 +1,229 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # IP Filtering
+//!
+//! This module handles rejecting clients by their IP address, as a lightweight first line of defense. The
+//! connections of rejected clients are closed right after being accepted, before anything is read from them.
+//!
+//! Addresses are matched against ranges in CIDR notation, such as `10.0.0.0/8` or `fd00::/8`, or against single
+//! addresses. A client is rejected if its address is in a denied range, or if allowed ranges are configured and
+//! its address is in none of them. IPv4 addresses mapped to IPv6 (`::ffff:a.b.c.d`) are matched as IPv4
+//! addresses.
+//!
+//! ```
+//! use jsonrpsee_core::server::ip_filter::IpFilter;
+//!
+//! let filter = IpFilter::new()
+//!     .allow(["10.0.0.0/8", "127.0.0.1"])
+//!     .unwrap()
+//!     // Except for a subnet of untrusted machines.
+//!     .deny(["10.0.13.0/24"])
+//!     .unwrap();
+//!
+//! assert!(filter.is_allowed("10.1.2.3".parse().unwrap()));
+//! assert!(!filter.is_allowed("10.0.13.37".parse().unwrap()));
+//! assert!(!filter.is_allowed("192.168.1.1".parse().unwrap()));
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::Error;
+
+/// Range of IP addresses sharing a prefix, e.g. `192.168.0.0/16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+	network: IpAddr,
+	prefix_len: u8,
+}
+
+impl IpRange {
+	/// Create the range of the addresses whose first `prefix_len` bits are those of `addr`.
+	pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, Error> {
+		let max_len = if addr.is_ipv4() { 32 } else { 128 };
+		if prefix_len > max_len {
+			return Err(Error::InvalidIpRange(format!("{}/{}", addr, prefix_len)));
+		}
+		Ok(Self { network: mask(addr, prefix_len), prefix_len })
+	}
+
+	/// Returns whether `ip` belongs to the range.
+	pub fn contains(&self, ip: IpAddr) -> bool {
+		let ip = match ip {
+			IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+			ip => ip,
+		};
+		ip.is_ipv4() == self.network.is_ipv4() && mask(ip, self.prefix_len) == self.network
+	}
+}
+
+fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+	match addr {
+		IpAddr::V4(v4) => {
+			let mask = u32::MAX.checked_shl(32 - u32::from(prefix_len)).unwrap_or(0);
+			IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+		}
+		IpAddr::V6(v6) => {
+			let mask = u128::MAX.checked_shl(128 - u32::from(prefix_len)).unwrap_or(0);
+			IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+		}
+	}
+}
+
+impl FromStr for IpRange {
+	type Err = Error;
+
+	/// Parse a range in CIDR notation, or a single address.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || Error::InvalidIpRange(s.into());
+		let (addr, prefix_len) = match s.split_once('/') {
+			Some((addr, prefix_len)) => (addr, Some(prefix_len.parse().map_err(|_| invalid())?)),
+			None => (s, None),
+		};
+		let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+		let prefix_len = prefix_len.unwrap_or(if addr.is_ipv4() { 32 } else { 128 });
+
+		Self::new(addr, prefix_len)
+	}
+}
+
+impl fmt::Display for IpRange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}/{}", self.network, self.prefix_len)
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct Ranges {
+	allowed: Vec<IpRange>,
+	denied: Vec<IpRange>,
+}
+
+/// Filter of the IP addresses of the clients of a server.
+///
+/// By default all addresses are allowed.
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter(Arc<Ranges>);
+
+impl IpFilter {
+	/// Create a filter allowing all addresses.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only allow the addresses in the given ranges, in CIDR notation, unless they are denied.
+	pub fn allow<List, R>(self, list: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = R>,
+		R: AsRef<str>,
+	{
+		self.extend(list, |ranges| &mut ranges.allowed)
+	}
+
+	/// Deny the addresses in the given ranges, in CIDR notation.
+	pub fn deny<List, R>(self, list: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = R>,
+		R: AsRef<str>,
+	{
+		self.extend(list, |ranges| &mut ranges.denied)
+	}
+
+	fn extend<List, R>(mut self, list: List, field: fn(&mut Ranges) -> &mut Vec<IpRange>) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = R>,
+		R: AsRef<str>,
+	{
+		let parsed = list.into_iter().map(|range| range.as_ref().parse()).collect::<Result<Vec<IpRange>, _>>()?;
+		field(Arc::make_mut(&mut self.0)).extend(parsed);
+		Ok(self)
+	}
+
+	/// Returns whether any range is configured.
+	pub fn is_enabled(&self) -> bool {
+		!self.0.allowed.is_empty() || !self.0.denied.is_empty()
+	}
+
+	/// Returns whether a client with the address `ip` may connect.
+	pub fn is_allowed(&self, ip: IpAddr) -> bool {
+		let Ranges { allowed, denied } = &*self.0;
+		!denied.iter().any(|range| range.contains(ip))
+			&& (allowed.is_empty() || allowed.iter().any(|range| range.contains(ip)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{IpFilter, IpRange};
+	use std::net::IpAddr;
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn ranges_are_parsed() {
+		assert_eq!("10.1.2.3/8".parse::<IpRange>().unwrap().to_string(), "10.0.0.0/8");
+		assert_eq!("127.0.0.1".parse::<IpRange>().unwrap().to_string(), "127.0.0.1/32");
+		assert_eq!("fd00::1/8".parse::<IpRange>().unwrap().to_string(), "fd00::/8");
+		assert_eq!("0.0.0.0/0".parse::<IpRange>().unwrap().to_string(), "0.0.0.0/0");
+
+		for invalid in ["10.0.0.0/33", "::/129", "10.0.0.0/", "localhost", "10.0.0/8"] {
+			assert!(invalid.parse::<IpRange>().is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn ranges_contain_addresses() {
+		let range: IpRange = "192.168.0.0/16".parse().unwrap();
+		assert!(range.contains(ip("192.168.42.1")));
+		assert!(range.contains(ip("::ffff:192.168.42.1")));
+		assert!(!range.contains(ip("192.169.0.1")));
+		assert!(!range.contains(ip("::1")));
+
+		let any: IpRange = "::/0".parse().unwrap();
+		assert!(any.contains(ip("2001:db8::1")));
+		assert!(!any.contains(ip("10.0.0.1")));
+	}
+
+	#[test]
+	fn denied_ranges_take_precedence() {
+		assert!(IpFilter::new().is_allowed(ip("1.2.3.4")));
+
+		let filter = IpFilter::new().deny(["1.2.3.0/24"]).unwrap();
+		assert!(!filter.is_allowed(ip("1.2.3.4")));
+		assert!(filter.is_allowed(ip("1.2.4.4")));
+
+		let filter = filter.allow(["1.2.0.0/16", "::1"]).unwrap();
+		assert!(!filter.is_allowed(ip("1.2.3.4")));
+		assert!(filter.is_allowed(ip("1.2.4.4")));
+		assert!(filter.is_allowed(ip("::1")));
+		assert!(!filter.is_allowed(ip("1.3.0.1")));
+	}
+}
@@ -0,0 +1,166 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Load Shedding
+//!
+//! This module handles shedding work early when the process is under memory pressure, instead of accepting
+//! new work until it runs out of memory.
+//!
+//! A [`LoadShedder`] consults a user-supplied probe reporting the current memory usage, such as the resident
+//! set size of the process or the statistics of a custom allocator. Once the usage reaches the high watermark
+//! the servers reject new work: the HTTP server answers requests with a `ServerIsBusy` error before reading
+//! their bodies, and the WebSocket server rejects new connections and subscriptions. Work is accepted again
+//! once the usage dropped below the low watermark, which defaults to the high watermark.
+//!
+//! The probe is called for every request, connection and subscription so it must be cheap, e.g. by reading a
+//! value that is refreshed periodically in the background.
+//!
+//! ```
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::Arc;
+//! use jsonrpsee_core::server::load_shedding::LoadShedder;
+//!
+//! // Updated by a background task sampling the memory usage of the process.
+//! let rss = Arc::new(AtomicU64::new(0));
+//!
+//! let shedder = LoadShedder::new(2 * 1024 * 1024 * 1024, move || rss.load(Ordering::Relaxed))
+//!     // Only accept work again once the usage dropped back to 1.5 GiB.
+//!     .low_watermark(1536 * 1024 * 1024);
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+type Probe = Arc<dyn Fn() -> u64 + Send + Sync>;
+
+/// Sheds new work while the memory usage reported by a probe is above a watermark.
+///
+/// By default no probe is configured and work is never shed.
+#[derive(Clone, Default)]
+pub struct LoadShedder {
+	inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+	probe: Probe,
+	high_watermark: u64,
+	low_watermark: u64,
+	shedding: AtomicBool,
+}
+
+impl LoadShedder {
+	/// Create a load shedder rejecting new work while `probe` reports a usage of at least `high_watermark`.
+	pub fn new(high_watermark: u64, probe: impl Fn() -> u64 + Send + Sync + 'static) -> Self {
+		Self {
+			inner: Some(Arc::new(Inner {
+				probe: Arc::new(probe),
+				high_watermark,
+				low_watermark: high_watermark,
+				shedding: AtomicBool::new(false),
+			})),
+		}
+	}
+
+	/// Set the usage below which work is accepted again once the high watermark was reached.
+	///
+	/// Values above the high watermark are capped to it.
+	pub fn low_watermark(mut self, low_watermark: u64) -> Self {
+		if let Some(inner) = self.inner.as_mut().and_then(Arc::get_mut) {
+			inner.low_watermark = low_watermark.min(inner.high_watermark);
+		}
+		self
+	}
+
+	/// Returns whether new work should be rejected, consulting the probe.
+	pub fn is_overloaded(&self) -> bool {
+		let inner = match self.inner.as_ref() {
+			Some(inner) => inner,
+			None => return false,
+		};
+
+		let usage = (inner.probe)();
+		let shedding = if inner.shedding.load(Ordering::Relaxed) {
+			usage >= inner.low_watermark
+		} else {
+			usage >= inner.high_watermark
+		};
+
+		if inner.shedding.swap(shedding, Ordering::Relaxed) != shedding {
+			if shedding {
+				tracing::warn!("Memory usage {} reached the high watermark, shedding new work", usage);
+			} else {
+				tracing::info!("Memory usage {} dropped below the low watermark, accepting new work", usage);
+			}
+		}
+
+		shedding
+	}
+}
+
+impl fmt::Debug for LoadShedder {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.inner.as_ref() {
+			Some(inner) => f
+				.debug_struct("LoadShedder")
+				.field("high_watermark", &inner.high_watermark)
+				.field("low_watermark", &inner.low_watermark)
+				.field("shedding", &inner.shedding.load(Ordering::Relaxed))
+				.finish(),
+			None => f.write_str("LoadShedder(disabled)"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::AtomicU64;
+
+	#[test]
+	fn disabled_never_sheds() {
+		assert!(!LoadShedder::default().is_overloaded());
+	}
+
+	#[test]
+	fn watermarks_work() {
+		let usage = Arc::new(AtomicU64::new(0));
+		let probe = usage.clone();
+		let shedder = LoadShedder::new(100, move || probe.load(Ordering::Relaxed)).low_watermark(50);
+
+		assert!(!shedder.is_overloaded());
+		usage.store(100, Ordering::Relaxed);
+		assert!(shedder.is_overloaded());
+		// Still shedding between the watermarks.
+		usage.store(75, Ordering::Relaxed);
+		assert!(shedder.clone().is_overloaded());
+		usage.store(49, Ordering::Relaxed);
+		assert!(!shedder.is_overloaded());
+		// Not shedding again before the high watermark is reached.
+		usage.store(75, Ordering::Relaxed);
+		assert!(!shedder.is_overloaded());
+	}
+}
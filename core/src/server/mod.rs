@@ -28,9 +28,48 @@
 
 /// Access control verification.
 pub mod access_control;
+/// Authentication. Validate the credentials of clients and restrict which methods they may call.
+pub mod auth;
+/// Broadcast. Send the same notifications to all the subscribers of a method.
+pub mod broadcast;
+pub mod circuit_breaker;
+/// Forwarded headers. Find out the address of clients connecting through trusted reverse proxies.
+pub mod forwarded;
 /// Helpers.
 pub mod helpers;
+cfg_openrpc! {
+	/// OpenRPC. Describe the methods of a server so that clients can introspect the API.
+	pub mod openrpc;
+}
+/// IP filtering. Reject clients by their IP address as soon as they connect.
+pub mod ip_filter;
+/// Load shedding. Reject new work while the process is under memory pressure.
+pub mod load_shedding;
+/// Parsing. Parse the messages received by the servers without any IO, for instance to fuzz them.
+pub mod parse;
+/// Subscription polling. Buffer the notifications of subscriptions for clients that can't keep a connection open.
+pub mod poll;
+/// PROXY protocol. Find out the address of clients connecting through a load balancer.
+pub mod proxy_protocol;
+/// Rate limiting. Restrict how many calls each client may make over time.
+pub mod rate_limiting;
 /// Resource limiting. Create generic "resources" and configure their limits to ensure servers are not overloaded.
 pub mod resource_limiting;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
 pub mod rpc_module;
+/// Send queue. Bound the messages waiting to be sent to a client.
+pub mod send_queue;
+cfg_schemas! {
+	/// Schemas. Describe the parameters and results of methods with JSON schemas derived from their types.
+	pub mod schema;
+}
+cfg_schemas! {
+	/// TypeScript. Generate typed clients for web frontends from the schemas of methods.
+	pub mod typescript;
+}
+/// Server sets. Run several servers sharing the same methods under a single supervisor.
+pub mod server_set;
+/// Shutdown. Describe why a server stopped.
+pub mod shutdown;
+/// Wire tap. Capture the raw frames exchanged with clients to debug interoperability issues.
+pub mod wire_tap;
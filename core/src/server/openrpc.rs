@@ -0,0 +1,197 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # OpenRPC
+//!
+//! This module builds [OpenRPC](https://spec.open-rpc.org) documents describing the methods of a server, so that
+//! clients can introspect the API by calling `rpc.discover`.
+//!
+//! The schemas of parameters and results are [`Schemas`] generated with [`schemars`]. Types used by several methods
+//! are described once in the `components` section of the document and referenced from the methods.
+//!
+//! Documents are usually filled by the `openrpc` function that the `rpc` macro generates on server traits when
+//! the `openrpc` argument is passed to it, and then served with
+//! [`RpcModule::register_discover`](super::rpc_module::RpcModule::register_discover):
+//!
+//! ```
+//! use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+//! use jsonrpsee_core::server::openrpc::OpenRpc;
+//!
+//! #[rpc(server, openrpc)]
+//! pub trait Rpc {
+//!     /// Adds two numbers.
+//!     #[method(name = "add")]
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64>;
+//! }
+//!
+//! struct RpcImpl;
+//!
+//! impl RpcServer for RpcImpl {
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64> {
+//!         Ok(a + b)
+//!     }
+//! }
+//!
+//! let mut document = OpenRpc::new("Calculator", "1.0.0");
+//! <RpcImpl as RpcServer>::openrpc(&mut document);
+//!
+//! let mut module = RpcImpl.into_rpc();
+//! module.register_discover(&document).unwrap();
+//! ```
+
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use super::schema::Schemas;
+pub use super::schema::{ContentDescriptor, MethodSchema as Method};
+
+/// Version of the OpenRPC specification the documents conform to.
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Name of the method serving the OpenRPC document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// OpenRPC document describing the methods of a server.
+#[derive(Debug, Clone)]
+pub struct OpenRpc {
+	info: Info,
+	schemas: Schemas,
+}
+
+/// Metadata about the API.
+#[derive(Debug, Clone, Serialize)]
+struct Info {
+	title: String,
+	version: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+	openrpc: &'static str,
+	info: &'a Info,
+	methods: &'a [Method],
+	components: Components<'a>,
+}
+
+#[derive(Serialize)]
+struct Components<'a> {
+	schemas: &'a schemars::Map<String, Schema>,
+}
+
+impl OpenRpc {
+	/// Create a new document without methods for an API with the given `title` and `version`.
+	pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+		Self {
+			info: Info { title: title.into(), version: version.into(), description: None },
+			schemas: Schemas::with_definitions_path("#/components/schemas/"),
+		}
+	}
+
+	/// Set the description of the API.
+	pub fn description(mut self, description: impl Into<String>) -> Self {
+		self.info.description = Some(description.into());
+		self
+	}
+
+	/// Returns the schema of `T`, referencing the `components` section of the document where needed.
+	pub fn schema_for<T: ?Sized + JsonSchema>(&mut self) -> Schema {
+		self.schemas.schema_for::<T>()
+	}
+
+	/// Add a method to the document.
+	pub fn add_method(&mut self, method: Method) {
+		self.schemas.add_method(method);
+	}
+
+	/// Returns the methods described by the document.
+	pub fn methods(&self) -> &[Method] {
+		self.schemas.methods()
+	}
+
+	/// Returns the schemas of the methods described by the document, to validate calls with them for example.
+	pub fn schemas(&self) -> &Schemas {
+		&self.schemas
+	}
+
+	/// Returns the schemas of the methods described by the document, to add methods to them.
+	pub fn schemas_mut(&mut self) -> &mut Schemas {
+		&mut self.schemas
+	}
+
+	/// Returns the document as JSON.
+	pub fn to_json(&self) -> JsonValue {
+		let document = Document {
+			openrpc: OPENRPC_VERSION,
+			info: &self.info,
+			methods: self.schemas.methods(),
+			components: Components { schemas: self.schemas.definitions() },
+		};
+
+		serde_json::to_value(document).expect("OpenRPC documents are always valid JSON; qed")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[derive(JsonSchema)]
+	#[allow(dead_code)]
+	struct Block {
+		number: u64,
+	}
+
+	#[test]
+	fn document_works() {
+		let mut document = OpenRpc::new("Chain", "1.0.0").description("Blocks");
+		let method = Method {
+			name: "getBlock".into(),
+			description: Some("Returns a block.".into()),
+			params: vec![ContentDescriptor {
+				name: "number".into(),
+				required: false,
+				schema: document.schema_for::<Option<u64>>(),
+			}],
+			result: ContentDescriptor { name: "result".into(), required: true, schema: document.schema_for::<Block>() },
+			deprecated: false,
+		};
+		document.add_method(method);
+
+		let document = document.to_json();
+		assert_eq!(document["openrpc"], OPENRPC_VERSION);
+		assert_eq!(document["info"], json!({ "title": "Chain", "version": "1.0.0", "description": "Blocks" }));
+		assert_eq!(document["methods"][0]["name"], "getBlock");
+		assert_eq!(document["methods"][0]["params"][0]["required"], false);
+		assert!(document["methods"][0].get("deprecated").is_none());
+		assert_eq!(document["methods"][0]["result"]["schema"], json!({ "$ref": "#/components/schemas/Block" }));
+		assert_eq!(document["components"]["schemas"]["Block"]["properties"]["number"]["type"], "integer");
+	}
+}
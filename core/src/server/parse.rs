@@ -0,0 +1,116 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Parsing of the messages received by the servers.
+//!
+//! The servers parse every message through these functions, which do no IO and only depend on their input.
+//! They can be used to fuzz or differential-test the exact parsing done in production:
+//!
+//! ```
+//! use jsonrpsee::core::server::parse::{parse_batch, parse_request};
+//! use jsonrpsee::types::error::ErrorCode;
+//! use jsonrpsee::types::Id;
+//!
+//! let req = parse_request(br#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).unwrap();
+//! assert_eq!(req.method, "say_hello");
+//!
+//! assert_eq!(parse_request(br#"{"id":1}"#).unwrap_err(), (Id::Number(1), ErrorCode::InvalidRequest));
+//! assert_eq!(parse_batch(b"[").unwrap_err(), (Id::Null, ErrorCode::ParseError));
+//! ```
+
+use crate::server::helpers::prepare_error;
+use jsonrpsee_types::error::ErrorCode;
+use jsonrpsee_types::{Id, Notification, Request};
+use serde_json::value::RawValue;
+
+/// Notification as received by a server, with its parameters left unparsed.
+pub type RawNotification<'a> = Notification<'a, Option<&'a RawValue>>;
+
+/// Error a message that could not be parsed is answered with, along with the ID to answer it with.
+pub type ParseError<'a> = (Id<'a>, ErrorCode);
+
+/// Parse a method call.
+pub fn parse_request(data: &[u8]) -> Result<Request<'_>, ParseError<'_>> {
+	serde_json::from_slice(data).map_err(|_| prepare_error(data))
+}
+
+/// Parse a notification, a call without ID that is not answered.
+pub fn parse_notification(data: &[u8]) -> Result<RawNotification<'_>, ParseError<'_>> {
+	serde_json::from_slice(data).map_err(|_| prepare_error(data))
+}
+
+/// Parse a batch of method calls. The batch may be empty, which the servers answer with
+/// [`ErrorCode::InvalidRequest`].
+pub fn parse_batch(data: &[u8]) -> Result<Vec<Request<'_>>, ParseError<'_>> {
+	serde_json::from_slice(data).map_err(|_| prepare_error(data))
+}
+
+/// Parse a batch of notifications.
+pub fn parse_notification_batch(data: &[u8]) -> Result<Vec<RawNotification<'_>>, ParseError<'_>> {
+	serde_json::from_slice(data).map_err(|_| prepare_error(data))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_request_works() {
+		let req = parse_request(br#"{"jsonrpc":"2.0","method":"add","params":[1,2],"id":"a"}"#).unwrap();
+		assert_eq!(req.method, "add");
+		assert_eq!(req.id, Id::Str("a".into()));
+		assert_eq!(req.params.unwrap().get(), "[1,2]");
+
+		assert_eq!(
+			parse_request(br#"{"jsonrpc":"2.0","id":3}"#).unwrap_err(),
+			(Id::Number(3), ErrorCode::InvalidRequest)
+		);
+		assert_eq!(parse_request(b"{").unwrap_err(), (Id::Null, ErrorCode::ParseError));
+		assert_eq!(
+			parse_request(br#"{"jsonrpc":"1.0","method":"add","id":2}"#).unwrap_err(),
+			(Id::Number(2), ErrorCode::InvalidRequest)
+		);
+	}
+
+	#[test]
+	fn parse_notification_works() {
+		let notif = parse_notification(br#"{"jsonrpc":"2.0","method":"bye"}"#).unwrap();
+		assert_eq!(notif.method, "bye");
+		assert!(notif.params.is_none());
+	}
+
+	#[test]
+	fn parse_batch_works() {
+		let batch =
+			parse_batch(br#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","id":2}]"#).unwrap();
+		assert_eq!(batch.iter().map(|req| req.method.as_ref()).collect::<Vec<_>>(), ["a", "b"]);
+		assert!(parse_batch(b"[]").unwrap().is_empty());
+		assert_eq!(parse_batch(b"[1").unwrap_err(), (Id::Null, ErrorCode::ParseError));
+
+		let batch = parse_notification_batch(br#"[{"jsonrpc":"2.0","method":"a"}]"#).unwrap();
+		assert_eq!(batch[0].method, "a");
+	}
+}
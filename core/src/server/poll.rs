@@ -0,0 +1,280 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Subscription Polling
+//!
+//! This module handles giving clients that can't keep a connection open, such as HTTP-only clients, access to
+//! subscriptions. A subscription is established on their behalf by a poll session, which buffers its
+//! notifications on the server until the client fetches them.
+//!
+//! The two methods registered by [`Methods::register_subscription_polling`] are:
+//!
+//! - [`SUB_POLL_CREATE`], with the name of a subscription method and optionally its parameters as an array, e.g.
+//!   `["subscribe_blocks", [true]]`. Responds with the id of the new poll session.
+//! - [`SUB_POLL_NEXT`], with the id of a poll session, e.g. `["Fm2bQ4jYWm2HsBN3"]`. Responds with the results of
+//!   the notifications buffered since the previous poll as
+//!   `{"items":[<result>, ..],"dropped":<count>,"closed":<bool>}`, waiting for one up to the configured poll
+//!   timeout if there are none. `dropped` counts the notifications discarded because the buffer was full, and
+//!   `closed` is set once the subscription ended, after which the session is gone.
+//!
+//! Sessions that aren't polled for longer than their time-to-live expire and close their subscription.
+//!
+//! ```
+//! use std::time::Duration;
+//! use jsonrpsee_core::server::poll::SubscriptionPolling;
+//! use jsonrpsee_core::server::rpc_module::RpcModule;
+//!
+//! let mut module = RpcModule::new(());
+//! // Register the subscriptions first, sessions can only subscribe to the methods registered so far.
+//! module
+//!     .register_subscription_polling(
+//!         SubscriptionPolling::new().ttl(Duration::from_secs(120)).max_items(256),
+//!     )
+//!     .unwrap();
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::server::rpc_module::{MethodKind, Methods, Subscription};
+use futures_util::future::{self, Either};
+use jsonrpsee_types::error::{reject_too_many_subscriptions, CallError};
+use jsonrpsee_types::Params;
+use parking_lot::Mutex;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Name of the method creating a poll session, see [`Methods::register_subscription_polling`].
+pub const SUB_POLL_CREATE: &str = "sub_poll_create";
+
+/// Name of the method fetching the notifications of a poll session, see [`Methods::register_subscription_polling`].
+pub const SUB_POLL_NEXT: &str = "sub_poll_next";
+
+/// Length of the random ids of poll sessions, which clients need to know to poll them.
+const SESSION_ID_LEN: usize = 16;
+
+/// Limits of the poll sessions of a server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionPolling {
+	ttl: Duration,
+	poll_timeout: Duration,
+	max_items: usize,
+	max_sessions: usize,
+}
+
+impl Default for SubscriptionPolling {
+	fn default() -> Self {
+		Self {
+			ttl: Duration::from_secs(60),
+			poll_timeout: Duration::from_secs(20),
+			max_items: 1024,
+			max_sessions: 1024,
+		}
+	}
+}
+
+impl SubscriptionPolling {
+	/// Create the default limits.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set how long a session is kept without being polled (default is 60 seconds).
+	pub fn ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Set how long a poll waits for a notification if none is buffered (default is 20 seconds). This should be
+	/// lower than the request timeout of the clients.
+	pub fn poll_timeout(mut self, poll_timeout: Duration) -> Self {
+		self.poll_timeout = poll_timeout;
+		self
+	}
+
+	/// Set how many notifications a session buffers before discarding the oldest ones (default is 1024).
+	pub fn max_items(mut self, max_items: usize) -> Self {
+		self.max_items = max_items.max(1);
+		self
+	}
+
+	/// Set how many sessions may be open at once (default is 1024).
+	pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+		self.max_sessions = max_sessions;
+		self
+	}
+}
+
+#[derive(Debug)]
+struct Session {
+	items: VecDeque<Box<RawValue>>,
+	dropped: u64,
+	closed: bool,
+	expires_at: Instant,
+	notify: Arc<Notify>,
+}
+
+/// Notifications fetched by a poll, see the module documentation for the format.
+#[derive(Debug, Serialize)]
+struct Poll {
+	items: Vec<Box<RawValue>>,
+	dropped: u64,
+	closed: bool,
+}
+
+/// Poll sessions of a server, shared by the methods registered by [`Methods::register_subscription_polling`].
+#[derive(Debug, Clone)]
+pub(crate) struct PollSessions {
+	limits: SubscriptionPolling,
+	methods: Methods,
+	sessions: Arc<Mutex<FxHashMap<String, Session>>>,
+}
+
+impl PollSessions {
+	/// Create the sessions subscribing to the subscription methods of `methods`.
+	pub(crate) fn new(limits: SubscriptionPolling, methods: Methods) -> Self {
+		Self { limits, methods, sessions: Default::default() }
+	}
+
+	/// Create a session as requested by the params of a [`SUB_POLL_CREATE`] call, returning its id.
+	pub(crate) async fn create(&self, params: Params<'_>) -> Result<String, Error> {
+		let mut seq = params.sequence();
+		let method: String = seq.next()?;
+		let sub_params: Option<Box<RawValue>> = seq.optional_next()?;
+
+		if !matches!(self.methods.method(&method).map(|callback| callback.inner()), Some(MethodKind::Subscription(_))) {
+			return Err(CallError::InvalidParams(anyhow::anyhow!("`{}` is not a subscription method", method)).into());
+		}
+
+		{
+			let now = Instant::now();
+			let mut sessions = self.sessions.lock();
+			sessions.retain(|_, session| session.expires_at > now);
+			if sessions.len() >= self.limits.max_sessions {
+				return Err(Error::Call(CallError::Custom(reject_too_many_subscriptions(
+					self.limits.max_sessions as u32,
+				))));
+			}
+		}
+
+		let subscription = self.methods.subscribe_raw(&method, sub_params.as_deref()).await?;
+		let id: String = rand::thread_rng().sample_iter(Alphanumeric).take(SESSION_ID_LEN).map(char::from).collect();
+		let session = Session {
+			items: VecDeque::new(),
+			dropped: 0,
+			closed: false,
+			expires_at: Instant::now() + self.limits.ttl,
+			notify: Arc::new(Notify::new()),
+		};
+		self.sessions.lock().insert(id.clone(), session);
+		tokio::spawn(self.clone().buffer(id.clone(), subscription));
+
+		Ok(id)
+	}
+
+	/// Buffer the notifications of the subscription of session `id`, until it ends or the session expires.
+	async fn buffer(self, id: String, mut subscription: Subscription) {
+		loop {
+			let expires_at = match self.sessions.lock().get(&id) {
+				Some(session) => session.expires_at,
+				None => return,
+			};
+
+			let next = subscription.next::<Box<RawValue>>();
+			let expiry = tokio::time::sleep_until(expires_at);
+			futures_util::pin_mut!(next, expiry);
+			let outcome = future::select(next, expiry).await;
+
+			let mut sessions = self.sessions.lock();
+			let session = match sessions.get_mut(&id) {
+				Some(session) => session,
+				None => return,
+			};
+
+			match outcome {
+				Either::Left((Some(Ok((item, _))), _)) => {
+					if session.items.len() >= self.limits.max_items {
+						session.items.pop_front();
+						session.dropped += 1;
+					}
+					session.items.push_back(item);
+					session.notify.notify_one();
+				}
+				// The subscription ended, dropping it closes it otherwise.
+				Either::Left((_, _)) => {
+					session.closed = true;
+					session.notify.notify_one();
+					return;
+				}
+				Either::Right(_) if session.expires_at <= Instant::now() => {
+					tracing::debug!("Poll session {} expired", id);
+					sessions.remove(&id);
+					return;
+				}
+				Either::Right(_) => {}
+			}
+		}
+	}
+
+	/// Fetch the notifications of the session requested by the params of a [`SUB_POLL_NEXT`] call.
+	pub(crate) async fn next(&self, params: Params<'_>) -> Result<impl Serialize, Error> {
+		let id: String = params.one()?;
+		let deadline = Instant::now() + self.limits.poll_timeout;
+
+		loop {
+			let notify = {
+				let mut sessions = self.sessions.lock();
+				let session = sessions.get_mut(&id).ok_or_else(|| {
+					CallError::InvalidParams(anyhow::anyhow!("Unknown or expired poll session: {}", id))
+				})?;
+				session.expires_at = Instant::now() + self.limits.ttl;
+
+				if !session.items.is_empty() || session.closed || Instant::now() >= deadline {
+					let poll = Poll {
+						items: session.items.drain(..).collect(),
+						dropped: std::mem::take(&mut session.dropped),
+						closed: session.closed,
+					};
+					if poll.closed {
+						sessions.remove(&id);
+					}
+					return Ok(poll);
+				}
+				session.notify.clone()
+			};
+
+			// Woken up when a notification is buffered, or times out and responds without any.
+			let _ = tokio::time::timeout_at(deadline, notify.notified()).await;
+		}
+	}
+}
@@ -0,0 +1,231 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # PROXY Protocol
+//!
+//! This module handles reading the header of the [PROXY protocol](https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt),
+//! with which load balancers such as HAProxy or AWS ELB prefix the connections they forward to tell the address of
+//! the client. Both the text (version 1) and the binary (version 2) formats are supported.
+//!
+//! The servers read the header before anything else when the protocol is enabled, and see the client at the address
+//! it carries from then on. Connections without a valid header are closed, so the protocol must only be enabled when
+//! all clients connect through such a load balancer.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Time given to a client to send the header after it connected.
+pub const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum length of a version 1 header, including the final CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Signature starting the version 2 headers.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Reads the PROXY protocol header at the start of `stream`, without reading past it.
+///
+/// Returns the address of the client, or `None` if the header doesn't carry one, for instance because the load
+/// balancer connected on its own behalf to check the health of the server.
+pub async fn read_header<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Option<SocketAddr>> {
+	match tokio::time::timeout(HEADER_TIMEOUT, read_header_inner(stream)).await {
+		Ok(res) => res,
+		Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "PROXY protocol header not received in time")),
+	}
+}
+
+async fn read_header_inner<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Option<SocketAddr>> {
+	// Shorter than the headers of both versions, so that nothing past the header is read.
+	let mut start = [0; 12];
+	stream.read_exact(&mut start).await?;
+
+	if start == V2_SIGNATURE {
+		let mut fixed = [0; 4];
+		stream.read_exact(&mut fixed).await?;
+		let mut addresses = vec![0; u16::from_be_bytes([fixed[2], fixed[3]]) as usize];
+		stream.read_exact(&mut addresses).await?;
+
+		parse_v2(fixed[0], fixed[1], &addresses)
+	} else if start.starts_with(b"PROXY ") {
+		// The length of the header isn't known up front, it's read byte by byte to not consume the data following it.
+		let mut line = start.to_vec();
+		while !line.ends_with(b"\r\n") {
+			if line.len() == V1_MAX_LEN {
+				return Err(invalid("PROXY protocol header is too long"));
+			}
+			line.push(stream.read_u8().await?);
+		}
+
+		parse_v1(&line[..line.len() - 2])
+	} else {
+		Err(invalid("missing PROXY protocol header"))
+	}
+}
+
+/// Parse a version 1 header without its final CRLF, e.g. `PROXY TCP4 192.0.2.1 198.51.100.1 56324 443`.
+fn parse_v1(line: &[u8]) -> io::Result<Option<SocketAddr>> {
+	let line = std::str::from_utf8(line).map_err(|_| invalid("PROXY protocol header is not ASCII"))?;
+	let mut fields = line.split(' ').skip(1);
+
+	let is_v4 = match fields.next() {
+		Some("TCP4") => true,
+		Some("TCP6") => false,
+		Some("UNKNOWN") => return Ok(None),
+		_ => return Err(invalid("unsupported PROXY protocol transport")),
+	};
+
+	let malformed = || invalid("malformed PROXY protocol header");
+	let source: IpAddr = fields.next().and_then(|ip| ip.parse().ok()).ok_or_else(malformed)?;
+	let destination: IpAddr = fields.next().and_then(|ip| ip.parse().ok()).ok_or_else(malformed)?;
+	let source_port: u16 = fields.next().and_then(|port| port.parse().ok()).ok_or_else(malformed)?;
+	let _destination_port: u16 = fields.next().and_then(|port| port.parse().ok()).ok_or_else(malformed)?;
+
+	if fields.next().is_some() || source.is_ipv4() != is_v4 || destination.is_ipv4() != is_v4 {
+		return Err(malformed());
+	}
+
+	Ok(Some(SocketAddr::new(source, source_port)))
+}
+
+/// Parse a version 2 header from its version and command, its address family and protocol, and its addresses.
+fn parse_v2(version_command: u8, family: u8, addresses: &[u8]) -> io::Result<Option<SocketAddr>> {
+	if version_command >> 4 != 2 {
+		return Err(invalid("unsupported PROXY protocol version"));
+	}
+
+	match version_command & 0x0f {
+		// LOCAL, the connection was established by the load balancer itself.
+		0 => return Ok(None),
+		// PROXY
+		1 => {}
+		_ => return Err(invalid("unsupported PROXY protocol command")),
+	}
+
+	// The addresses may be followed by TLVs, which are ignored.
+	let too_short = || invalid("PROXY protocol addresses are truncated");
+	match family >> 4 {
+		// AF_INET
+		1 => {
+			let addresses: &[u8; 12] = addresses.get(..12).and_then(|a| a.try_into().ok()).ok_or_else(too_short)?;
+			let ip = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+			let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+			Ok(Some(SocketAddr::new(ip.into(), port)))
+		}
+		// AF_INET6
+		2 => {
+			let addresses: &[u8; 36] = addresses.get(..36).and_then(|a| a.try_into().ok()).ok_or_else(too_short)?;
+			let mut ip = [0; 16];
+			ip.copy_from_slice(&addresses[..16]);
+			let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+			Ok(Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port)))
+		}
+		// AF_UNSPEC or AF_UNIX, no address of the client to use.
+		_ => Ok(None),
+	}
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{read_header, V2_SIGNATURE};
+	use std::net::SocketAddr;
+	use tokio::io::AsyncReadExt;
+
+	async fn read(mut data: &[u8]) -> (std::io::Result<Option<SocketAddr>>, Vec<u8>) {
+		let res = read_header(&mut data).await;
+		let mut rest = Vec::new();
+		data.read_to_end(&mut rest).await.unwrap();
+		(res, rest)
+	}
+
+	fn v2(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+		let mut header = V2_SIGNATURE.to_vec();
+		header.extend([0x20 | command, family]);
+		header.extend((addresses.len() as u16).to_be_bytes());
+		header.extend(addresses);
+		header.extend(b"GET /");
+		header
+	}
+
+	#[tokio::test]
+	async fn v1_headers_are_read() {
+		let (res, rest) = read(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\nGET /").await;
+		assert_eq!(res.unwrap(), Some("192.0.2.1:56324".parse().unwrap()));
+		assert_eq!(rest, b"GET /");
+
+		let (res, rest) = read(b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\nGET /").await;
+		assert_eq!(res.unwrap(), Some("[2001:db8::1]:56324".parse().unwrap()));
+		assert_eq!(rest, b"GET /");
+
+		let (res, rest) = read(b"PROXY UNKNOWN\r\nGET /").await;
+		assert_eq!(res.unwrap(), None);
+		assert_eq!(rest, b"GET /");
+	}
+
+	#[tokio::test]
+	async fn v2_headers_are_read() {
+		let mut v4 = vec![192, 0, 2, 1, 198, 51, 100, 1];
+		v4.extend(56324u16.to_be_bytes());
+		v4.extend(443u16.to_be_bytes());
+		// A TLV, ignored.
+		v4.extend([0x04, 0, 1, 0]);
+		let (res, rest) = read(&v2(1, 0x11, &v4)).await;
+		assert_eq!(res.unwrap(), Some("192.0.2.1:56324".parse().unwrap()));
+		assert_eq!(rest, b"GET /");
+
+		let mut v6 = "2001:db8::1".parse::<std::net::Ipv6Addr>().unwrap().octets().to_vec();
+		v6.extend([0; 16]);
+		v6.extend(56324u16.to_be_bytes());
+		v6.extend(443u16.to_be_bytes());
+		let (res, _) = read(&v2(1, 0x21, &v6)).await;
+		assert_eq!(res.unwrap(), Some("[2001:db8::1]:56324".parse().unwrap()));
+
+		let (res, rest) = read(&v2(0, 0, &[])).await;
+		assert_eq!(res.unwrap(), None);
+		assert_eq!(rest, b"GET /");
+	}
+
+	#[tokio::test]
+	async fn invalid_headers_are_rejected() {
+		for invalid in [
+			&b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"[..],
+			b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n",
+			b"PROXY TCP4 2001:db8::1 198.51.100.1 56324 443\r\n",
+			b"PROXY UDP4 192.0.2.1 198.51.100.1 56324 443\r\n",
+			&[&b"PROXY UNKNOWN "[..], &[b'x'; 100]].concat(),
+			&v2(1, 0x11, &[192, 0, 2, 1]),
+			&v2(2, 0x11, &[]),
+		] {
+			assert!(read(invalid).await.0.is_err());
+		}
+	}
+}
@@ -0,0 +1,400 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Rate Limiting
+//!
+//! This module handles limiting the rate at which clients may call methods on the server.
+//!
+//! Unlike [`resource_limiting`](super::resource_limiting), which limits how many calls are executed concurrently,
+//! rate limiting restricts how many calls a single client may make over time. Each client is assigned a token
+//! bucket per method group: a call consumes one token, and tokens are refilled at a constant rate up to the
+//! configured burst size. Calls made while the bucket is empty are denied execution, immediately returning a
+//! JSON-RPC error object with code `-32007` whose `data` field contains the number of milliseconds after which
+//! the call may be retried.
+//!
+//! Clients are identified by their IP address or, for the HTTP server, by the value of a configurable header
+//! set by a trusted proxy, such as an API key it verified. The WebSocket server always identifies clients by
+//! their IP address.
+//!
+//! ```
+//! use std::time::Duration;
+//! use jsonrpsee_core::server::rate_limiting::{RateLimit, RateLimiter};
+//!
+//! let limiter = RateLimiter::new()
+//!     // Every client may make 100 calls per second to methods not part of any group.
+//!     .default_limit(RateLimit::new(100, Duration::from_secs(1)))
+//!     // But only 10 calls per minute to the expensive ones.
+//!     .group("expensive", RateLimit::new(10, Duration::from_secs(60)), ["trace_block", "trace_call"])
+//!     .unwrap()
+//!     // Identify clients by the API key reported by a trusted proxy, instead of their IP address.
+//!     .key_by_header("x-api-key");
+//! ```
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::Error;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+/// Number of tracked buckets above which buckets that are full again are discarded.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Token bucket configuration: allows bursts of up to `burst` calls, refilled at `burst` calls per `period`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+	burst: u32,
+	period: Duration,
+}
+
+impl RateLimit {
+	/// Allow `calls` calls per `period`, which may be made in a single burst.
+	///
+	/// # Panics
+	///
+	/// If `calls` is zero.
+	pub fn new(calls: u32, period: Duration) -> Self {
+		assert!(calls > 0, "A rate limit must allow at least one call");
+		Self { burst: calls, period }
+	}
+
+	/// Time it takes to refill a single token.
+	fn refill_interval(&self) -> Duration {
+		self.period / self.burst
+	}
+}
+
+/// Description of a configured limit, see [`RateLimiter::limits`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimitInfo {
+	/// Label of the group the limit applies to, `None` for the default limit.
+	pub group: Option<&'static str>,
+	/// Methods of the group, sorted by name. Empty for the default limit, which applies to all other methods.
+	pub methods: Vec<&'static str>,
+	/// Number of calls allowed per period, which may be made in a single burst.
+	pub calls: u32,
+	/// Period in milliseconds.
+	pub period_ms: u64,
+}
+
+/// Identifies a client for the purpose of rate limiting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+	/// Client identified by its IP address.
+	Ip(IpAddr),
+	/// Client identified by a header value, such as an API key.
+	Header(Box<str>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct RateLimitConfig {
+	/// Limit for methods that are not part of any group.
+	default: Option<RateLimit>,
+	/// Label and limit of every group.
+	groups: Vec<(&'static str, RateLimit)>,
+	/// Maps a method name to the index of its group.
+	methods: FxHashMap<&'static str, usize>,
+	/// HTTP header used to identify clients instead of their IP address.
+	key_header: Option<&'static str>,
+}
+
+/// Buckets of every client, per group (`None` for the default limit).
+type Buckets = FxHashMap<(RateLimitKey, Option<usize>), Bucket>;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+	tokens: f64,
+	updated_at: Instant,
+}
+
+impl Bucket {
+	fn full(limit: &RateLimit, now: Instant) -> Self {
+		Self { tokens: limit.burst as f64, updated_at: now }
+	}
+
+	fn refill(&mut self, limit: &RateLimit, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated_at);
+		let refilled = elapsed.as_secs_f64() / limit.refill_interval().as_secs_f64();
+		self.tokens = (self.tokens + refilled).min(limit.burst as f64);
+		self.updated_at = now;
+	}
+}
+
+/// Per-client rate limiter shared by all connections of a server.
+///
+/// By default no limits are configured and every call is allowed.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+	config: Arc<RateLimitConfig>,
+	buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+	/// Create a rate limiter without any limits.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the limit for methods that are not part of any group. By default these are not limited.
+	pub fn default_limit(mut self, limit: RateLimit) -> Self {
+		Arc::make_mut(&mut self.config).default = Some(limit);
+		self
+	}
+
+	/// Register a group of methods that share their own limit. Every client has a separate budget per group.
+	///
+	/// Errors if `label` is already registered or if one of the methods is already part of another group.
+	pub fn group(
+		mut self,
+		label: &'static str,
+		limit: RateLimit,
+		methods: impl IntoIterator<Item = &'static str>,
+	) -> Result<Self, Error> {
+		let config = Arc::make_mut(&mut self.config);
+
+		if config.groups.iter().any(|&(l, _)| l == label) {
+			return Err(Error::RateLimitGroupAlreadyTaken(label));
+		}
+
+		let idx = config.groups.len();
+
+		for method in methods {
+			if config.methods.insert(method, idx).is_some() {
+				return Err(Error::MethodAlreadyRateLimited(method));
+			}
+		}
+
+		config.groups.push((label, limit));
+
+		Ok(self)
+	}
+
+	/// Identify clients by the value of the given HTTP header when it's present, for instance an API key.
+	///
+	/// The header is only trusted on requests forwarded by one of the trusted proxies of the server, which are
+	/// expected to set it once they authenticated the client. Clients could otherwise send a different value
+	/// with every request to get a fresh budget. Other requests are identified by the IP address of the client.
+	/// Only supported by the HTTP server.
+	pub fn key_by_header(mut self, header: &'static str) -> Self {
+		Arc::make_mut(&mut self.config).key_header = Some(header);
+		self
+	}
+
+	/// Returns the header used to identify clients, if any.
+	pub fn key_header(&self) -> Option<&'static str> {
+		self.config.key_header
+	}
+
+	/// Returns whether any limit is configured.
+	pub fn is_enabled(&self) -> bool {
+		self.config.default.is_some() || !self.config.groups.is_empty()
+	}
+
+	/// Returns the configured limits, the default limit first.
+	pub fn limits(&self) -> Vec<RateLimitInfo> {
+		let info = |group, methods, limit: RateLimit| RateLimitInfo {
+			group,
+			methods,
+			calls: limit.burst,
+			period_ms: limit.period.as_millis() as u64,
+		};
+
+		let default = self.config.default.map(|limit| info(None, Vec::new(), limit));
+		let groups = self.config.groups.iter().enumerate().map(|(idx, &(label, limit))| {
+			let mut methods: Vec<_> =
+				self.config.methods.iter().filter(|&(_, &group)| group == idx).map(|(&method, _)| method).collect();
+			methods.sort_unstable();
+			info(Some(label), methods, limit)
+		});
+
+		default.into_iter().chain(groups).collect()
+	}
+
+	/// Returns a handle to check the calls of the client identified by `key`.
+	pub fn client(&self, key: RateLimitKey) -> ClientRateLimiter {
+		ClientRateLimiter { limiter: self.clone(), key }
+	}
+
+	/// Attempt to consume a call to `method` from the budget of the client identified by `key`.
+	///
+	/// Returns the time after which the call may be retried if the client has exhausted its budget.
+	pub fn check(&self, key: &RateLimitKey, method: &str) -> Result<(), Duration> {
+		let group = self.config.methods.get(method).copied();
+
+		let limit = match group {
+			Some(idx) => self.config.groups[idx].1,
+			None => match self.config.default {
+				Some(limit) => limit,
+				None => return Ok(()),
+			},
+		};
+
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock();
+
+		if buckets.len() > PRUNE_THRESHOLD {
+			self.prune(&mut buckets, now);
+		}
+
+		let bucket = buckets.entry((key.clone(), group)).or_insert_with(|| Bucket::full(&limit, now));
+		bucket.refill(&limit, now);
+
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			Ok(())
+		} else {
+			Err(limit.refill_interval().mul_f64(1.0 - bucket.tokens))
+		}
+	}
+
+	/// Discard the buckets that would be full by now, they're equivalent to a fresh bucket.
+	fn prune(&self, buckets: &mut Buckets, now: Instant) {
+		buckets.retain(|(_, group), bucket| {
+			let limit = match group {
+				Some(idx) => self.config.groups[*idx].1,
+				None => match self.config.default {
+					Some(limit) => limit,
+					None => return false,
+				},
+			};
+
+			let mut bucket = *bucket;
+			bucket.refill(&limit, now);
+			bucket.tokens < limit.burst as f64
+		});
+	}
+}
+
+/// Rate limiter bound to a single client.
+#[derive(Debug, Clone)]
+pub struct ClientRateLimiter {
+	limiter: RateLimiter,
+	key: RateLimitKey,
+}
+
+impl ClientRateLimiter {
+	/// Attempt to consume a call to `method` from the budget of this client.
+	///
+	/// Returns the time after which the call may be retried if the client has exhausted its budget.
+	pub fn check(&self, method: &str) -> Result<(), Duration> {
+		self.limiter.check(&self.key, method)
+	}
+
+	/// Returns the key identifying this client.
+	pub fn key(&self) -> &RateLimitKey {
+		&self.key
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{RateLimit, RateLimitInfo, RateLimitKey, RateLimiter};
+	use std::time::Duration;
+
+	fn key(ip: [u8; 4]) -> RateLimitKey {
+		RateLimitKey::Ip(ip.into())
+	}
+
+	#[test]
+	fn unlimited_by_default() {
+		let limiter = RateLimiter::new();
+		assert!(!limiter.is_enabled());
+
+		for _ in 0..1000 {
+			assert!(limiter.check(&key([127, 0, 0, 1]), "foo").is_ok());
+		}
+	}
+
+	#[test]
+	fn default_limit_works() {
+		let limiter = RateLimiter::new().default_limit(RateLimit::new(2, Duration::from_secs(60)));
+
+		assert!(limiter.check(&key([127, 0, 0, 1]), "foo").is_ok());
+		assert!(limiter.check(&key([127, 0, 0, 1]), "bar").is_ok());
+
+		let retry_after = limiter.check(&key([127, 0, 0, 1]), "foo").unwrap_err();
+		assert!(retry_after > Duration::from_secs(29) && retry_after <= Duration::from_secs(30));
+
+		// Other clients have their own budget.
+		assert!(limiter.check(&key([127, 0, 0, 2]), "foo").is_ok());
+		assert!(limiter.check(&RateLimitKey::Header("key".into()), "foo").is_ok());
+	}
+
+	#[test]
+	fn tokens_are_refilled() {
+		let limiter = RateLimiter::new().default_limit(RateLimit::new(1, Duration::from_millis(50)));
+
+		assert!(limiter.check(&key([127, 0, 0, 1]), "foo").is_ok());
+		assert!(limiter.check(&key([127, 0, 0, 1]), "foo").is_err());
+		std::thread::sleep(Duration::from_millis(60));
+		assert!(limiter.check(&key([127, 0, 0, 1]), "foo").is_ok());
+	}
+
+	#[test]
+	fn groups_have_separate_budgets() {
+		let limiter = RateLimiter::new()
+			.group("heavy", RateLimit::new(1, Duration::from_secs(60)), ["heavy_a", "heavy_b"])
+			.unwrap();
+
+		assert!(limiter.check(&key([127, 0, 0, 1]), "heavy_a").is_ok());
+		assert!(limiter.check(&key([127, 0, 0, 1]), "heavy_b").is_err());
+		// Methods outside of any group are not limited without a default limit.
+		assert!(limiter.check(&key([127, 0, 0, 1]), "light").is_ok());
+	}
+
+	#[test]
+	fn group_conflicts_are_rejected() {
+		let limit = RateLimit::new(1, Duration::from_secs(1));
+
+		assert!(RateLimiter::new().group("a", limit, ["foo"]).unwrap().group("a", limit, ["bar"]).is_err());
+		assert!(RateLimiter::new().group("a", limit, ["foo"]).unwrap().group("b", limit, ["foo"]).is_err());
+	}
+
+	#[test]
+	fn limits_are_described() {
+		assert!(RateLimiter::new().limits().is_empty());
+
+		let limiter = RateLimiter::new()
+			.default_limit(RateLimit::new(100, Duration::from_secs(1)))
+			.group("expensive", RateLimit::new(10, Duration::from_secs(60)), ["trace_call", "trace_block"])
+			.unwrap();
+
+		assert_eq!(
+			limiter.limits(),
+			vec![
+				RateLimitInfo { group: None, methods: vec![], calls: 100, period_ms: 1000 },
+				RateLimitInfo {
+					group: Some("expensive"),
+					methods: vec!["trace_block", "trace_call"],
+					calls: 10,
+					period_ms: 60_000
+				},
+			]
+		);
+	}
+}
@@ -37,7 +37,7 @@
 //! consume, in particular anything critical that is expected to result in a lot of stress on the server,
 //! and then defining your units such that the limits (`capacity`) can be adjusted for different hardware configurations.
 //!
-//! Up to 8 resources can be defined using the [`WsServerBuilder::register_resource`](../../../jsonrpsee_ws_server/struct.WsServerBuilder.html#method.register_resource)
+//! Any number of resources can be defined using the [`WsServerBuilder::register_resource`](../../../jsonrpsee_ws_server/struct.WsServerBuilder.html#method.register_resource)
 //! or [`HttpServerBuilder::register_resource`](../../../jsonrpsee_http_server/struct.HttpServerBuilder.html#method.register_resource) method
 //! for the WebSocket and HTTP server respectively.
 //!
@@ -48,6 +48,19 @@
 //! Setting the execution cost to `0` equates to the method effectively not being limited by a given resource. Likewise setting the
 //! `capacity` to `0` disables any limiting for a given resource.
 //!
+//! The capacities can be adjusted while the server is running with [`Resources::set_capacity`], for instance
+//! through the `resources` of the [`MethodsHandle`](crate::server::rpc_module::MethodsHandle) of the server.
+//! Calls that are already executing keep the units they claimed, so lowering a capacity below the units in use
+//! only denies new calls until enough of them completed.
+//!
+//! A resource can also be limited per connection with [`Resources::set_connection_quota`], so that a single client
+//! can't claim the whole capacity of the server. The calls of a connection claim their units both from the capacity
+//! of the server and from the quota of the connection, a quota of `0` doesn't limit the connections.
+//!
+//! The units in use and their peak are available from [`Resources::usage`] to monitor how saturated the resources
+//! are, the servers can also report them to their middleware periodically, see
+//! [`Middleware::on_resource_usage`](crate::middleware::Middleware::on_resource_usage).
+//!
 //! To specify a different than default number of units a method should use, use the `resources` argument in the
 //! `#[method]` attribute:
 //!
@@ -91,86 +104,190 @@
 
 use std::sync::Arc;
 
+use crate::middleware::ResourceUsage;
 use crate::Error;
-use arrayvec::ArrayVec;
 use parking_lot::Mutex;
 
-// The number of kinds of resources that can be used for limiting.
-const RESOURCE_COUNT: usize = 8;
-
-/// Fixed size table, mapping a resource to a (unitless) value indicating the amount of the resource that is available to RPC calls.
-pub type ResourceTable = [u16; RESOURCE_COUNT];
+/// Shared table, mapping every registered resource, in the order of registration, to the (unitless) amount of the resource
+/// claimed by a call.
+pub type ResourceTable = Arc<[u16]>;
 /// Variable size table, mapping a resource to a (unitless) value indicating the amount of the resource that is available to RPC calls.
-pub type ResourceVec<T> = ArrayVec<T, RESOURCE_COUNT>;
+pub type ResourceVec<T> = Vec<T>;
 
 /// User defined resources available to be used by calls on the JSON-RPC server.
-/// Each of the 8 possible resource kinds, for instance "cpu", "io", "nanobots",
-/// store a maximum `capacity` and a default. A value of `0` means no limits for the given resource.
+/// Each resource kind, for instance "cpu", "io", "nanobots", stores a maximum `capacity` and a default.
+/// A value of `0` means no limits for the given resource.
+///
+/// Clones share the units in use and the capacities, changing a capacity applies to all of them.
 #[derive(Debug, Default, Clone)]
 pub struct Resources {
-	/// Resources currently in use by executing calls. 0 for unused resource kinds.
-	totals: Arc<Mutex<ResourceTable>>,
-	/// Max capacity for all resource kinds
-	pub capacities: ResourceTable,
+	/// Units in use and capacities of all resource kinds.
+	usage: Arc<Mutex<Usage>>,
+	/// Units in use by the calls of a connection, set by [`Resources::for_connection`].
+	connection: Option<Arc<Mutex<Vec<u32>>>>,
 	/// Default value for all resource kinds; unless a method has a resource limit defined, this is the cost of a call (0 means no default limit)
-	pub defaults: ResourceTable,
+	pub defaults: ResourceVec<u16>,
 	/// Labels for every registered resource
 	pub labels: ResourceVec<&'static str>,
 }
 
 impl Resources {
-	/// Register a new resource kind. Errors if `label` is already registered, or if the total number of
-	/// registered resources would exceed 8.
+	/// Register a new resource kind. Errors if `label` is already registered.
 	pub fn register(&mut self, label: &'static str, capacity: u16, default: u16) -> Result<(), Error> {
 		if self.labels.iter().any(|&l| l == label) {
 			return Err(Error::ResourceNameAlreadyTaken(label));
 		}
 
-		let idx = self.labels.len();
+		let mut usage = self.usage.lock();
+		usage.totals.push(0);
+		usage.peaks.push(0);
+		usage.capacities.push(capacity);
+		usage.connection_quotas.push(0);
+		self.labels.push(label);
+		self.defaults.push(default);
+
+		Ok(())
+	}
+
+	/// Returns the capacity of the resource `label`, `None` if it's not registered.
+	pub fn capacity(&self, label: &str) -> Option<u16> {
+		let idx = self.index_of(label)?;
+		Some(self.usage.lock().capacities[idx])
+	}
 
-		self.labels.try_push(label).map_err(|_| Error::MaxResourcesReached)?;
+	/// Change the capacity of the resource `label`, `0` disables the limiting of the resource.
+	///
+	/// Takes effect immediately for the calls claiming the resource from now on.
+	pub fn set_capacity(&self, label: &str, capacity: u16) -> Result<(), Error> {
+		let idx = self.index_of(label).ok_or_else(|| Error::ResourceNameNotFound(label.into()))?;
+		self.usage.lock().capacities[idx] = capacity;
+		Ok(())
+	}
 
-		self.capacities[idx] = capacity;
-		self.defaults[idx] = default;
+	/// Returns the units of the resource `label` that a single connection may claim, `None` if it's not registered.
+	pub fn connection_quota(&self, label: &str) -> Option<u16> {
+		let idx = self.index_of(label)?;
+		Some(self.usage.lock().connection_quotas[idx])
+	}
 
+	/// Change the units of the resource `label` that a single connection may claim, `0` disables the limiting
+	/// of the connections.
+	///
+	/// Takes effect immediately for the calls claiming the resource from now on.
+	pub fn set_connection_quota(&self, label: &str, quota: u16) -> Result<(), Error> {
+		let idx = self.index_of(label).ok_or_else(|| Error::ResourceNameNotFound(label.into()))?;
+		self.usage.lock().connection_quotas[idx] = quota;
 		Ok(())
 	}
 
+	/// Returns the units in use, their peak and the capacity of every registered resource, in the order of
+	/// registration.
+	pub fn usage(&self) -> Vec<ResourceUsage> {
+		let usage = self.usage.lock();
+		self.labels
+			.iter()
+			.zip(usage.totals.iter().zip(&usage.peaks))
+			.zip(&usage.capacities)
+			.map(|((&label, (&in_use, &peak)), &capacity)| ResourceUsage::new(label, in_use, peak, capacity))
+			.collect()
+	}
+
+	/// Reset the peak of every resource to the units currently in use.
+	pub fn reset_peak_usage(&self) {
+		let mut usage = self.usage.lock();
+		let Usage { totals, peaks, .. } = &mut *usage;
+		peaks.copy_from_slice(totals);
+	}
+
+	/// Returns the resources for a new connection, whose calls are limited by the connection quotas as well.
+	pub fn for_connection(&self) -> Self {
+		let connection = Arc::new(Mutex::new(vec![0; self.labels.len()]));
+		Self { connection: Some(connection), ..self.clone() }
+	}
+
 	/// Attempt to claim `units` units for each resource, incrementing current totals.
 	/// If successful, returns a [`ResourceGuard`] which decrements the totals by the same
 	/// amounts once dropped.
-	pub fn claim(&self, units: ResourceTable) -> Result<ResourceGuard, Error> {
-		let mut totals = self.totals.lock();
-		let mut sum = *totals;
-
-		for (idx, sum) in sum.iter_mut().enumerate() {
-			match sum.checked_add(units[idx]) {
-				Some(s) if s <= self.capacities[idx] => *sum = s,
-				_ => {
-					let label = self.labels.get(idx).copied().unwrap_or("<UNKNOWN>");
-
-					return Err(Error::ResourceAtCapacity(label));
-				}
+	///
+	/// Resources with a capacity of `0` are not limited, the units claimed from them are still accounted for.
+	/// The units are claimed from the quotas of the connection as well if these are the resources of a connection,
+	/// see [`Resources::for_connection`].
+	pub fn claim(&self, units: &ResourceTable) -> Result<ResourceGuard, Error> {
+		let mut usage = self.usage.lock();
+		let mut connection = self.connection.as_ref().map(|connection| connection.lock());
+		let Usage { totals, peaks, capacities, connection_quotas } = &mut *usage;
+
+		let exceeds =
+			|total: u32, claimed: u16, limit: u16| limit != 0 && total + u32::from(claimed) > u32::from(limit);
+
+		for (idx, (&claimed, (&total, &capacity))) in units.iter().zip(totals.iter().zip(capacities.iter())).enumerate()
+		{
+			let over_quota = match connection.as_ref() {
+				Some(connection) => exceeds(connection[idx], claimed, connection_quotas[idx]),
+				None => false,
+			};
+
+			if over_quota || exceeds(total, claimed, capacity) {
+				let label = self.labels.get(idx).copied().unwrap_or("<UNKNOWN>");
+
+				return Err(Error::ResourceAtCapacity(label));
 			}
 		}
 
-		*totals = sum;
+		add_units(totals, units);
+		for (peak, &total) in peaks.iter_mut().zip(totals.iter()) {
+			*peak = (*peak).max(total);
+		}
+		if let Some(connection) = connection.as_mut() {
+			add_units(connection, units);
+		}
+
+		Ok(ResourceGuard { usage: self.usage.clone(), connection: self.connection.clone(), units: units.clone() })
+	}
+
+	fn index_of(&self, label: &str) -> Option<usize> {
+		self.labels.iter().position(|&l| l == label)
+	}
+}
+
+/// Units in use and limits of the resource kinds.
+#[derive(Debug, Default)]
+struct Usage {
+	/// Resources currently in use by executing calls.
+	totals: Vec<u32>,
+	/// Highest totals since the start or the last reset.
+	peaks: Vec<u32>,
+	/// Max capacity for all resource kinds
+	capacities: Vec<u16>,
+	/// Max units a single connection may claim for all resource kinds
+	connection_quotas: Vec<u16>,
+}
+
+fn add_units(totals: &mut [u32], units: &[u16]) {
+	for (total, &claimed) in totals.iter_mut().zip(units) {
+		*total += u32::from(claimed);
+	}
+}
 
-		Ok(ResourceGuard { totals: self.totals.clone(), units })
+fn remove_units(totals: &mut [u32], units: &[u16]) {
+	for (total, &claimed) in totals.iter_mut().zip(units) {
+		*total -= u32::from(claimed);
 	}
 }
 
 /// RAII style "lock" for claimed resources, will automatically release them once dropped.
 #[derive(Debug)]
 pub struct ResourceGuard {
-	totals: Arc<Mutex<ResourceTable>>,
+	usage: Arc<Mutex<Usage>>,
+	connection: Option<Arc<Mutex<Vec<u32>>>>,
 	units: ResourceTable,
 }
 
 impl Drop for ResourceGuard {
 	fn drop(&mut self) {
-		for (sum, claimed) in self.totals.lock().iter_mut().zip(self.units) {
-			*sum -= claimed;
+		remove_units(&mut self.usage.lock().totals, &self.units);
+		if let Some(connection) = self.connection.as_ref() {
+			remove_units(&mut connection.lock(), &self.units);
 		}
 	}
 }
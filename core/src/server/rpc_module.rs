@@ -28,14 +28,23 @@ use std::collections::hash_map::Entry;
 use std::fmt::{self, Debug};
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::error::{Error, SubscriptionClosed};
 use crate::id_providers::RandomIntegerIdProvider;
-use crate::server::helpers::{BoundedSubscriptions, MethodSink, SubscriptionPermit};
+use crate::middleware::Middleware;
+use crate::server::helpers::{report_response, BoundedSubscriptions, MethodSink, SubscriptionPermit};
+use crate::server::poll::{PollSessions, SubscriptionPolling, SUB_POLL_CREATE, SUB_POLL_NEXT};
+use crate::server::rate_limiting::RateLimitInfo;
 use crate::server::resource_limiting::{ResourceGuard, ResourceTable, ResourceVec, Resources};
-use crate::traits::{IdProvider, ToRpcParams};
-use futures_channel::mpsc;
+use crate::server::send_queue::{
+	PendingMessages, SlowSubscriberEviction, SubscriptionBuffer, SubscriptionOverflowPolicy,
+};
+use crate::traits::{Executor, IdProvider, ToRpcParams};
+use bytes::Bytes;
+use futures_channel::{mpsc, oneshot};
 use futures_util::future::Either;
 use futures_util::pin_mut;
 use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt, TryStream, TryStreamExt};
@@ -43,14 +52,16 @@ use jsonrpsee_types::error::{
 	CallError, ErrorCode, ErrorObject, ErrorObjectOwned, INTERNAL_ERROR_CODE,
 	SUBSCRIPTION_CLOSED_WITH_ERROR, SubscriptionAcceptRejectError
 };
+use jsonrpsee_types::number::NumberPolicy;
 use jsonrpsee_types::response::{SubscriptionError, SubscriptionPayloadError};
 use jsonrpsee_types::{
 	ErrorResponse, Id, Params, Request, Response, SubscriptionResult,
 	SubscriptionId as RpcSubscriptionId, SubscriptionPayload, SubscriptionResponse
 };
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use rustc_hash::FxHashMap;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
 use tokio::sync::watch;
 
 /// A `MethodCallback` is an RPC endpoint, callable with a standard JSON-RPC request,
@@ -67,16 +78,44 @@ pub type SubscriptionMethod = Arc<dyn Send + Sync + Fn(Id, Params, MethodSink, C
 // Method callback to unsubscribe.
 type UnsubscriptionMethod = Arc<dyn Send + Sync + Fn(Id, Params, &MethodSink, ConnectionId) -> bool>;
 
+/// Name of the method listing all registered methods, see [`Methods::register_rpc_methods`].
+pub const RPC_METHODS: &str = "rpc_methods";
+
+/// Name of the method describing the limits of a server, see [`Methods::register_system_limits`].
+pub const SYSTEM_LIMITS: &str = "system_limits";
+
+/// Effective limits of a server, which the [`SYSTEM_LIMITS`] method responds with so that clients can size their
+/// requests and batches instead of discovering the limits through errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemLimits {
+	/// Maximum size in bytes of a request.
+	pub max_request_body_size: u32,
+	/// Maximum size in bytes of a response.
+	pub max_response_body_size: u32,
+	/// Whether batch requests are supported.
+	pub batch_requests_supported: bool,
+	/// Maximum number of calls in a batch request, `None` for no limit.
+	pub max_batch_size: Option<usize>,
+	/// Maximum number of subscriptions per connection, `None` if subscriptions are not supported.
+	pub max_subscriptions_per_connection: Option<u32>,
+	/// Rate limits every client is subject to.
+	pub rate_limits: Vec<RateLimitInfo>,
+	/// Content encodings of requests supported besides `identity`.
+	pub request_encodings: Vec<&'static str>,
+	/// Content encodings of responses supported besides `identity`.
+	pub response_encodings: Vec<&'static str>,
+}
+
 /// Connection ID, used for stateful protocol such as WebSockets.
 /// For stateless protocols such as http it's unused, so feel free to set it some hardcoded value.
 pub type ConnectionId = usize;
 
 /// Raw response from an RPC
 /// A 3-tuple containing:
-///   - Call result as a `String`,
-///   - a [`mpsc::UnboundedReceiver<String>`] to receive future subscription results
+///   - Call result as [`Bytes`],
+///   - a [`mpsc::UnboundedReceiver<Bytes>`] to receive future subscription results
 ///   - a [`crate::server::helpers::SubscriptionPermit`] to allow subscribers to notify their [`SubscriptionSink`] when they disconnect.
-pub type RawRpcResponse = (String, mpsc::UnboundedReceiver<String>, SubscriptionPermit);
+pub type RawRpcResponse = (Bytes, mpsc::UnboundedReceiver<Bytes>, SubscriptionPermit);
 
 /// Helper struct to manage subscriptions.
 pub struct ConnState<'a> {
@@ -162,6 +201,7 @@ impl<T: Debug> Debug for MethodResult<T> {
 /// Builder for configuring resources used by a method.
 #[derive(Debug)]
 pub struct MethodResourcesBuilder<'a> {
+	name: &'static str,
 	build: ResourceVec<(&'static str, u16)>,
 	callback: &'a mut MethodCallback,
 }
@@ -169,9 +209,27 @@ pub struct MethodResourcesBuilder<'a> {
 impl<'a> MethodResourcesBuilder<'a> {
 	/// Define how many units of a given named resource the method uses during its execution.
 	pub fn resource(mut self, label: &'static str, units: u16) -> Result<Self, Error> {
-		self.build.try_push((label, units)).map_err(|_| Error::MaxResourcesReached)?;
+		self.build.push((label, units));
 		Ok(self)
 	}
+
+	/// Attach a middleware to this method only, on top of the middleware configured on the server.
+	///
+	/// Every call of the method is reported to `middleware`: [`Middleware::on_request`] and
+	/// [`Middleware::on_call`] before the callback runs, then [`Middleware::on_error`] if the call was
+	/// answered with an error, [`Middleware::on_result`] and [`Middleware::on_response`]. The connection
+	/// hooks and [`Middleware::on_call_async`] are never called.
+	///
+	/// ```
+	/// use jsonrpsee::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("hot", |_, _| Ok("hello")).unwrap().with_middleware(());
+	/// ```
+	pub fn with_middleware<M: Middleware>(self, middleware: M) -> Self {
+		*self.callback = self.callback.clone().with_middleware(self.name, middleware);
+		self
+	}
 }
 
 impl<'a> Drop for MethodResourcesBuilder<'a> {
@@ -208,7 +266,7 @@ impl MethodCallback {
 	pub fn claim(&self, name: &str, resources: &Resources) -> Result<ResourceGuard, Error> {
 		match self.resources {
 			MethodResources::Uninitialized(_) => Err(Error::UninitializedMethod(name.into())),
-			MethodResources::Initialized(units) => resources.claim(units),
+			MethodResources::Initialized(ref units) => resources.claim(units),
 		}
 	}
 
@@ -216,6 +274,70 @@ impl MethodCallback {
 	pub fn inner(&self) -> &MethodKind {
 		&self.callback
 	}
+
+	/// Wrap the callback so that its calls are reported to `middleware`.
+	fn with_middleware<M: Middleware>(self, name: &'static str, middleware: M) -> Self {
+		let callback = match self.callback {
+			MethodKind::Sync(callback) => MethodKind::Sync(Arc::new(move |id, params, sink| {
+				let started_at = middleware.on_request();
+				middleware.on_call(name);
+				let sink = sink.recording();
+				let success = callback(id, params, &sink);
+				report_to_middleware(&middleware, name, success, &sink, started_at);
+				success
+			})),
+			MethodKind::Async(callback) => MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+				let middleware = middleware.clone();
+				let callback = callback.clone();
+				async move {
+					let started_at = middleware.on_request();
+					middleware.on_call(name);
+					let sink = sink.recording();
+					let success = callback(id, params, sink.clone(), conn_id, claimed).await;
+					report_to_middleware(&middleware, name, success, &sink, started_at);
+					success
+				}
+				.boxed()
+			})),
+			MethodKind::Subscription(callback) => {
+				MethodKind::Subscription(Arc::new(move |id, params, sink, conn, claimed| {
+					let started_at = middleware.on_request();
+					middleware.on_call(name);
+					let sink = sink.recording();
+					let success = callback(id, params, sink.clone(), conn, claimed);
+					report_to_middleware(&middleware, name, success, &sink, started_at);
+					success
+				}))
+			}
+			MethodKind::Unsubscription(callback) => {
+				MethodKind::Unsubscription(Arc::new(move |id, params, sink, conn_id| {
+					let started_at = middleware.on_request();
+					middleware.on_call(name);
+					let sink = sink.recording();
+					let success = callback(id, params, &sink, conn_id);
+					report_to_middleware(&middleware, name, success, &sink, started_at);
+					success
+				}))
+			}
+		};
+
+		MethodCallback { callback, resources: self.resources }
+	}
+}
+
+/// Reports the outcome of a call to a middleware attached with [`MethodResourcesBuilder::with_middleware`].
+fn report_to_middleware<M: Middleware>(
+	middleware: &M,
+	name: &str,
+	success: bool,
+	sink: &MethodSink,
+	started_at: M::Instant,
+) {
+	if let Some(error) = sink.last_error() {
+		middleware.on_error(name, &error);
+	}
+	middleware.on_result(name, success, started_at);
+	report_response(middleware, sink.bytes_sent(), started_at);
 }
 
 impl Debug for MethodKind {
@@ -233,6 +355,8 @@ impl Debug for MethodKind {
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	/// Maps the name of every alias to the name of the method it was registered for.
+	aliases: Arc<FxHashMap<&'static str, &'static str>>,
 }
 
 impl Methods {
@@ -268,7 +392,7 @@ impl Methods {
 
 		for (&method_name, callback) in callbacks.iter_mut() {
 			if let MethodResources::Uninitialized(uninit) = &callback.resources {
-				let mut map = resources.defaults;
+				let mut map = resources.defaults.clone();
 
 				for &(label, units) in uninit.iter() {
 					let idx = match resources.labels.iter().position(|&l| l == label) {
@@ -276,16 +400,12 @@ impl Methods {
 						None => return Err(Error::ResourceNameNotFoundForMethod(label, method_name)),
 					};
 
-					// If resource capacity set to `0`, we ignore the unit value of the method
-					// and set it to `0` as well, effectively making the resource unlimited.
-					if resources.capacities[idx] == 0 {
-						map[idx] = 0;
-					} else {
-						map[idx] = units;
-					}
+					// Resources with a capacity of `0` are not limited, which is checked when claiming
+					// as the capacity may change at runtime.
+					map[idx] = units;
 				}
 
-				callback.resources = MethodResources::Initialized(map);
+				callback.resources = MethodResources::Initialized(map.into());
 			}
 		}
 
@@ -297,6 +417,15 @@ impl Methods {
 		Arc::make_mut(&mut self.callbacks)
 	}
 
+	/// Attach a middleware to every method registered so far, see [`MethodResourcesBuilder::with_middleware`].
+	///
+	/// Methods registered or merged afterwards are not affected.
+	pub fn attach_middleware<M: Middleware>(&mut self, middleware: M) {
+		for (name, callback) in self.mut_callbacks().iter_mut() {
+			*callback = callback.clone().with_middleware(name, middleware.clone());
+		}
+	}
+
 	/// Merge two [`Methods`]'s by adding all [`MethodCallback`]s from `other` into `self`.
 	/// Fails if any of the methods in `other` is present already.
 	pub fn merge(&mut self, other: impl Into<Methods>) -> Result<(), Error> {
@@ -312,9 +441,54 @@ impl Methods {
 			callbacks.insert(name, callback);
 		}
 
+		Arc::make_mut(&mut self.aliases).extend(other.aliases.iter());
+
 		Ok(())
 	}
 
+	/// Merge two [`Methods`]'s like [`Methods::merge`], registering the methods from `other` under their name
+	/// prepended with `prefix`, such as `eth_` or `eth.`, to avoid collisions between independently developed
+	/// modules. Fails if any of the prefixed names is present already.
+	///
+	/// The method names of subscription notifications are left unchanged.
+	///
+	/// The prefixed names are leaked, so this is intended for composing modules when the server is set up.
+	pub fn merge_with_prefix(&mut self, other: impl Into<Methods>, prefix: &str) -> Result<(), Error> {
+		let mut other = other.into();
+
+		for name in other.callbacks.keys() {
+			let prefixed = format!("{}{}", prefix, name);
+			if self.callbacks.contains_key(prefixed.as_str()) {
+				return Err(Error::MethodAlreadyRegistered(prefixed));
+			}
+		}
+
+		let callbacks = self.mut_callbacks();
+
+		let mut prefixed_names = FxHashMap::default();
+		for (name, callback) in other.mut_callbacks().drain() {
+			let prefixed: &'static str = Box::leak(format!("{}{}", prefix, name).into_boxed_str());
+			callbacks.insert(prefixed, callback);
+			prefixed_names.insert(name, prefixed);
+		}
+
+		let aliases = Arc::make_mut(&mut self.aliases);
+		for (alias, method) in other.aliases.iter() {
+			if let (Some(alias), Some(method)) = (prefixed_names.get(alias), prefixed_names.get(method)) {
+				aliases.insert(alias, method);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns the name of the method `method_name` is an alias of, or `method_name` itself if it's not an alias.
+	///
+	/// The permission scopes and the rate limits of a method also apply to the calls made through its aliases.
+	pub fn canonical_name<'a>(&self, method_name: &'a str) -> &'a str {
+		self.aliases.get(method_name).copied().unwrap_or(method_name)
+	}
+
 	/// Returns the method callback.
 	pub fn method(&self, method_name: &str) -> Option<&MethodCallback> {
 		self.callbacks.get(method_name)
@@ -326,6 +500,35 @@ impl Methods {
 		self.callbacks.get_key_value(method_name).map(|(k, v)| (*k, v))
 	}
 
+	/// Attempt to claim the resources of a batch of calls once for the whole batch, which is possible when
+	/// all of the calls are made to methods with identical resource requirements.
+	///
+	/// Returns `None` if the requirements differ, a method doesn't exist or is a subscription (which holds its
+	/// resources for its whole lifetime), or the resources could not be claimed, in which case each call should
+	/// claim its resources individually.
+	pub fn claim_batch<'a>(
+		&self,
+		method_names: impl IntoIterator<Item = &'a str>,
+		resources: &Resources,
+	) -> Option<ResourceGuard> {
+		let mut units: Option<&ResourceTable> = None;
+
+		for name in method_names {
+			let callback = self.callbacks.get(name)?;
+
+			match (&callback.callback, &callback.resources) {
+				(MethodKind::Sync(_) | MethodKind::Async(_), MethodResources::Initialized(u))
+					if units.is_none() || units == Some(u) =>
+				{
+					units = Some(u)
+				}
+				_ => return None,
+			}
+		}
+
+		resources.claim(units?).ok()
+	}
+
 	/// Helper to call a method on the `RPC module` without having to spin up a server.
 	///
 	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
@@ -358,10 +561,10 @@ impl Methods {
 		tracing::trace!("[Methods::call] Calling method: {:?}, params: {:?}", method, params);
 		let (resp, _, _) = self.inner_call(req).await;
 
-		let res = match serde_json::from_str::<Response<T>>(&resp) {
+		let res = match serde_json::from_slice::<Response<T>>(&resp) {
 			Ok(res) => Ok(res.result),
 			Err(e) => {
-				if let Ok(err) = serde_json::from_str::<ErrorResponse>(&resp) {
+				if let Ok(err) = serde_json::from_slice::<ErrorResponse>(&resp) {
 					Err(Error::Call(CallError::Custom(err.error_object().clone().into_owned())))
 				} else {
 					Err(e.into())
@@ -390,7 +593,7 @@ impl Methods {
 	///         Ok(())
 	///     }).unwrap();
 	///     let (resp, mut stream) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"hi","id":0}"#).await.unwrap();
-	///     let resp = serde_json::from_str::<Response<u64>>(&resp).unwrap();
+	///     let resp = serde_json::from_slice::<Response<u64>>(&resp).unwrap();
 	///     let sub_resp = stream.next().await.unwrap();
 	///     assert_eq!(
 	///         format!(r#"{{"jsonrpc":"2.0","method":"hi","params":{{"subscription":{},"result":"one answer"}}}}"#, resp.result),
@@ -398,7 +601,7 @@ impl Methods {
 	///     );
 	/// }
 	/// ```
-	pub async fn raw_json_request(&self, call: &str) -> Result<(String, mpsc::UnboundedReceiver<String>), Error> {
+	pub async fn raw_json_request(&self, call: &str) -> Result<(Bytes, mpsc::UnboundedReceiver<Bytes>), Error> {
 		tracing::trace!("[Methods::raw_json_request] {:?}", call);
 		let req: Request = serde_json::from_str(call)?;
 		let (resp, rx, _) = self.inner_call(req).await;
@@ -460,13 +663,18 @@ impl Methods {
 	/// ```
 	pub async fn subscribe(&self, sub_method: &str, params: impl ToRpcParams) -> Result<Subscription, Error> {
 		let params = params.to_rpc_params()?;
-		let req = Request::new(sub_method.into(), Some(&params), Id::Number(0));
+		self.subscribe_raw(sub_method, Some(&params)).await
+	}
+
+	/// Create a subscription like [`Methods::subscribe`], with params serialized already.
+	pub(crate) async fn subscribe_raw(&self, sub_method: &str, params: Option<&RawValue>) -> Result<Subscription, Error> {
+		let req = Request::new(sub_method.into(), params, Id::Number(0));
 		tracing::trace!("[Methods::subscribe] Calling subscription method: {:?}, params: {:?}", sub_method, params);
 		let (response, rx, close_notify) = self.inner_call(req).await;
 		tracing::trace!("[Methods::subscribe] response {:?}", response);
-		let subscription_response = match serde_json::from_str::<Response<RpcSubscriptionId>>(&response) {
+		let subscription_response = match serde_json::from_slice::<Response<RpcSubscriptionId>>(&response) {
 			Ok(r) => r,
-			Err(_) => match serde_json::from_str::<ErrorResponse>(&response) {
+			Err(_) => match serde_json::from_slice::<ErrorResponse>(&response) {
 				Ok(err) => return Err(Error::Call(CallError::Custom(err.error_object().clone().into_owned()))),
 				Err(err) => return Err(err.into()),
 			},
@@ -480,6 +688,136 @@ impl Methods {
 	pub fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
 		self.callbacks.keys().copied()
 	}
+
+	/// Remove the method with the given name, returning its callback if it was registered.
+	///
+	/// Removing a subscription method doesn't affect the subscriptions already established, the matching
+	/// unsubscription method has to be removed separately.
+	pub fn remove_method(&mut self, method_name: &str) -> Option<MethodCallback> {
+		if self.aliases.contains_key(method_name) {
+			Arc::make_mut(&mut self.aliases).remove(method_name);
+		}
+		self.mut_callbacks().remove(method_name)
+	}
+
+	/// Register the [`RPC_METHODS`] method, which responds with the sorted names of all methods and subscriptions
+	/// registered so far, including itself, as `{ "methods": [...] }`.
+	///
+	/// Call this after all modules were merged, methods registered afterwards are not listed.
+	pub fn register_rpc_methods(&mut self) -> Result<(), Error> {
+		self.verify_method_name(RPC_METHODS)?;
+
+		let mut methods: Vec<_> = self.method_names().chain(std::iter::once(RPC_METHODS)).collect();
+		methods.sort_unstable();
+		let response = serde_json::value::to_raw_value(&serde_json::json!({ "methods": methods }))?;
+
+		self.verify_and_insert(
+			RPC_METHODS,
+			MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_response(id, &response))),
+		)?;
+
+		Ok(())
+	}
+
+	/// Register the [`SUB_POLL_CREATE`] and [`SUB_POLL_NEXT`] methods, which let clients receive the notifications
+	/// of subscriptions by polling for them, within `limits`. See the module documentation of
+	/// [`poll`](crate::server::poll) for details.
+	///
+	/// Call this after all modules were merged, subscriptions registered afterwards can't be polled.
+	pub fn register_subscription_polling(&mut self, limits: SubscriptionPolling) -> Result<(), Error> {
+		self.verify_method_name(SUB_POLL_CREATE)?;
+		self.verify_method_name(SUB_POLL_NEXT)?;
+
+		let sessions = PollSessions::new(limits, self.clone());
+
+		let create = sessions.clone();
+		self.verify_and_insert(
+			SUB_POLL_CREATE,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, _, _| {
+				let sessions = create.clone();
+				async move {
+					match sessions.create(params).await {
+						Ok(session) => sink.send_response(id, session),
+						Err(err) => sink.send_call_error(id, err),
+					}
+				}
+				.boxed()
+			})),
+		)?;
+
+		self.verify_and_insert(
+			SUB_POLL_NEXT,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, _, _| {
+				let sessions = sessions.clone();
+				async move {
+					match sessions.next(params).await {
+						Ok(poll) => sink.send_response(id, poll),
+						Err(err) => sink.send_call_error(id, err),
+					}
+				}
+				.boxed()
+			})),
+		)?;
+
+		Ok(())
+	}
+
+	/// Register the [`SYSTEM_LIMITS`] method, which responds with `limits`.
+	///
+	/// The servers register it with their effective limits when configured to, so this is rarely called directly.
+	pub fn register_system_limits(&mut self, limits: &SystemLimits) -> Result<(), Error> {
+		self.verify_method_name(SYSTEM_LIMITS)?;
+
+		let response = serde_json::value::to_raw_value(limits)?;
+
+		self.verify_and_insert(
+			SYSTEM_LIMITS,
+			MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_response(id, &response))),
+		)?;
+
+		Ok(())
+	}
+}
+
+/// Shared handle to the methods of a running server, allowing to add and remove methods at runtime.
+///
+/// Every request is executed against a snapshot of the methods taken when it's received, so changes only
+/// apply to subsequent requests. For WebSocket connections a snapshot is taken for every message.
+#[derive(Debug, Clone)]
+pub struct MethodsHandle {
+	methods: Arc<RwLock<Methods>>,
+	resources: Resources,
+}
+
+impl MethodsHandle {
+	/// Create a new handle to `methods`, whose resources must have been initialized with `resources`.
+	pub fn new(methods: Methods, resources: Resources) -> Self {
+		Self { methods: Arc::new(RwLock::new(methods)), resources }
+	}
+
+	/// Returns the methods as they are now.
+	pub fn snapshot(&self) -> Methods {
+		self.methods.read().clone()
+	}
+
+	/// Add all methods of `module`, failing without adding any if one of them is registered already.
+	pub fn add_module(&self, module: impl Into<Methods>) -> Result<(), Error> {
+		let module = module.into().initialize_resources(&self.resources)?;
+		self.methods.write().merge(module)
+	}
+
+	/// Remove the method with the given name, returning whether it was registered.
+	///
+	/// See [`Methods::remove_method`].
+	pub fn remove_method(&self, method_name: &str) -> bool {
+		self.methods.write().remove_method(method_name).is_some()
+	}
+
+	/// Returns the resources of the server, whose capacities can be adjusted at runtime with
+	/// [`Resources::set_capacity`].
+	pub fn resources(&self) -> &Resources {
+		&self.resources
+	}
 }
 
 impl<Context> Deref for RpcModule<Context> {
@@ -540,13 +878,29 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		let ctx = self.ctx.clone();
 		let callback = self.methods.verify_and_insert(
 			method_name,
-			MethodCallback::new_sync(Arc::new(move |id, params, sink| match callback(params, &*ctx) {
-				Ok(res) => sink.send_response(id, res),
-				Err(err) => sink.send_call_error(id, err),
+			MethodCallback::new_sync(Arc::new(move |id, params, sink| {
+				match panic::catch_unwind(AssertUnwindSafe(|| callback(params, &*ctx))) {
+					Ok(Ok(res)) => sink.send_response(id, res),
+					Ok(Err(err)) => sink.send_call_error(id, err),
+					Err(panic) => sink.send_panic(id, method_name, panic),
+				}
 			})),
 		)?;
 
-		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+		Ok(MethodResourcesBuilder { name: method_name, build: ResourceVec::new(), callback })
+	}
+
+	/// Register the `rpc.discover` method, which responds with the given OpenRPC `document`.
+	///
+	/// The document is serialized once, methods added to it afterwards are not served.
+	#[cfg(feature = "openrpc")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "openrpc")))]
+	pub fn register_discover(
+		&mut self,
+		document: &crate::server::openrpc::OpenRpc,
+	) -> Result<MethodResourcesBuilder<'_>, Error> {
+		let document = serde_json::value::to_raw_value(&document.to_json())?;
+		self.register_method(crate::server::openrpc::DISCOVER_METHOD, move |_, _| Ok(document.clone()))
 	}
 
 	/// Register a new asynchronous RPC method, which computes the response with the given callback.
@@ -566,9 +920,10 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
 				let ctx = ctx.clone();
 				let future = async move {
-					let result = match callback(params, ctx).await {
-						Ok(res) => sink.send_response(id, res),
-						Err(err) => sink.send_call_error(id, err),
+					let result = match AssertUnwindSafe(async move { callback(params, ctx).await }).catch_unwind().await {
+						Ok(Ok(res)) => sink.send_response(id, res),
+						Ok(Err(err)) => sink.send_call_error(id, err),
+						Err(panic) => sink.send_panic(id, method_name, panic),
 					};
 
 					// Release claimed resources
@@ -580,7 +935,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			})),
 		)?;
 
-		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+		Ok(MethodResourcesBuilder { name: method_name, build: ResourceVec::new(), callback })
 	}
 
 	/// Register a new **blocking** synchronous RPC method, which computes the response with the given callback.
@@ -602,9 +957,10 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 				let ctx = ctx.clone();
 
 				tokio::task::spawn_blocking(move || {
-					let result = match callback(params, ctx) {
-						Ok(res) => sink.send_response(id, res),
-						Err(err) => sink.send_call_error(id, err),
+					let result = match panic::catch_unwind(AssertUnwindSafe(|| callback(params, ctx))) {
+						Ok(Ok(res)) => sink.send_response(id, res),
+						Ok(Err(err)) => sink.send_call_error(id, err),
+						Err(panic) => sink.send_panic(id, method_name, panic),
 					};
 
 					// Release claimed resources
@@ -623,7 +979,71 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			})),
 		)?;
 
-		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+		Ok(MethodResourcesBuilder { name: method_name, build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new synchronous RPC method whose callback is run by `executor` rather than by the tokio runtime.
+	///
+	/// This is meant for handlers that must run on specific threads, for instance because they call into
+	/// foreign code, or that should not compete with the server for the threads of the runtime. If the
+	/// executor drops the task without running it, the call is answered with an internal error.
+	///
+	/// ```
+	/// use jsonrpsee::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// let executor = |task: Box<dyn FnOnce() + Send>| {
+	///     std::thread::spawn(task);
+	/// };
+	/// module.register_method_on_executor("compute", executor, |_, _| Ok(42)).unwrap();
+	/// ```
+	pub fn register_method_on_executor<R, F, E>(
+		&mut self,
+		method_name: &'static str,
+		executor: E,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		Context: Send + Sync + 'static,
+		R: Serialize,
+		F: Fn(Params, Arc<Context>) -> Result<R, Error> + Copy + Send + Sync + 'static,
+		E: Executor,
+	{
+		let ctx = self.ctx.clone();
+		let executor = Arc::new(executor);
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
+				let ctx = ctx.clone();
+				let (tx, rx) = oneshot::channel();
+				let call_id = id.clone();
+				let call_sink = sink.clone();
+
+				executor.execute(Box::new(move || {
+					let result = match panic::catch_unwind(AssertUnwindSafe(|| callback(params, ctx))) {
+						Ok(Ok(res)) => sink.send_response(id, res),
+						Ok(Err(err)) => sink.send_call_error(id, err),
+						Err(panic) => sink.send_panic(id, method_name, panic),
+					};
+
+					// Release claimed resources
+					drop(claimed);
+
+					let _ = tx.send(result);
+				}));
+
+				rx.map(move |result| match result {
+					Ok(r) => r,
+					Err(_) => {
+						tracing::error!("Executor dropped the call to `{}` without running it", method_name);
+						call_sink.send_error(call_id, ErrorCode::InternalError.into())
+					}
+				})
+				.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { name: method_name, build: ResourceVec::new(), callback })
 	}
 
 	/// Register a new publish/subscribe interface using JSON-RPC notifications.
@@ -746,14 +1166,29 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						method: notif_method_name,
 						subscribers: subscribers.clone(),
 						uniq_sub: SubscriptionKey { conn_id: conn.conn_id, sub_id },
-						id: Some(id.clone().into_owned()),
+						id: Arc::new(Mutex::new(Some(id.clone().into_owned()))),
 						unsubscribe: None,
 						_claimed: claimed,
+						cleanup: None,
+						close_reason: None,
+						buffer: None,
+						full_since: None,
 					};
+					let pending = sink.id.clone();
 
 					// The callback returns a `SubscriptionResult` for better ergonomics and is not propagated further.
-					if let Err(_) = callback(params, sink, ctx.clone()) {
-						tracing::warn!("subscribe call `{}` failed", subscribe_method_name);
+					match panic::catch_unwind(AssertUnwindSafe(|| callback(params, sink, ctx.clone()))) {
+						Ok(Ok(())) => (),
+						Ok(Err(_)) => tracing::warn!("subscribe call `{}` failed", subscribe_method_name),
+						// Respond to the subscription call unless it was already accepted or rejected.
+						Err(panic) => match pending.lock().take() {
+							Some(id) => {
+								method_sink.send_panic(id, subscribe_method_name, panic);
+							}
+							None => {
+								method_sink.report_panic(subscribe_method_name, panic);
+							}
+						},
 					}
 
 					true
@@ -761,7 +1196,73 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			)?
 		};
 
-		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+		Ok(MethodResourcesBuilder { name: subscribe_method_name, build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new publish/subscribe interface whose callback is async, see
+	/// [`register_subscription`](RpcModule::register_subscription).
+	///
+	/// The future returned by the callback owns the [`SubscriptionSink`] and is spawned on the tokio runtime,
+	/// it may await before accepting or rejecting the subscription.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	/// use jsonrpsee_core::Error;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_async_subscription("sub", "notif_name", "unsub", |params, mut sink, _| async move {
+	///     let start = match params.one::<usize>() {
+	///         Ok(start) => start,
+	///         Err(e) => {
+	///             let err: Error = e.into();
+	///             sink.reject(err)?;
+	///             return Ok(());
+	///         }
+	///     };
+	///     // Look up the state of the subscription before accepting it.
+	///     tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+	///     sink.accept()?;
+	///     let _ = sink.send(&start);
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn register_async_subscription<F, Fut>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params<'static>, SubscriptionSink, Arc<Context>) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = SubscriptionResult> + Send + 'static,
+	{
+		self.register_subscription(subscribe_method_name, notif_method_name, unsubscribe_method_name, move |params, sink, ctx| {
+			let method_sink = sink.inner.clone();
+			let pending = sink.id.clone();
+			let future = callback(params.into_owned(), sink, ctx);
+
+			tokio::spawn(async move {
+				match AssertUnwindSafe(future).catch_unwind().await {
+					Ok(Ok(())) => (),
+					Ok(Err(_)) => tracing::warn!("subscribe call `{}` failed", subscribe_method_name),
+					// Respond to the subscription call unless it was already accepted or rejected.
+					Err(panic) => match pending.lock().take() {
+						Some(id) => {
+							method_sink.send_panic(id, subscribe_method_name, panic);
+						}
+						None => {
+							method_sink.report_panic(subscribe_method_name, panic);
+						}
+					},
+				}
+			});
+
+			Ok(())
+		})
 	}
 
 	/// Register an alias for an existing_method. Alias uniqueness is enforced.
@@ -774,6 +1275,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		};
 
 		self.methods.mut_callbacks().insert(alias, callback);
+		let method = self.methods.canonical_name(existing_method);
+		Arc::make_mut(&mut self.methods.aliases).insert(alias, method);
 
 		Ok(())
 	}
@@ -782,6 +1285,41 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 /// Returns once the unsubscribe method has been called.
 type UnsubscribeCall = Option<watch::Receiver<()>>;
 
+/// Why a subscription was closed, passed to the cleanup registered with [`SubscriptionSink::on_close`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionCloseReason {
+	/// The client unsubscribed.
+	Unsubscribed,
+	/// The connection to the client was closed.
+	Disconnected,
+	/// The server closed the subscription, for instance because its stream ended, or the subscription was
+	/// never accepted.
+	Closed,
+}
+
+/// Async cleanup of the state of a subscription.
+struct SubscriptionCleanup {
+	cleanup: Box<dyn FnOnce(SubscriptionCloseReason) -> BoxFuture<'static, ()> + Send>,
+	runtime: Option<tokio::runtime::Handle>,
+}
+
+impl SubscriptionCleanup {
+	fn run(self, reason: SubscriptionCloseReason) {
+		match self.runtime.or_else(|| tokio::runtime::Handle::try_current().ok()) {
+			Some(runtime) => {
+				runtime.spawn((self.cleanup)(reason));
+			}
+			None => tracing::error!("Subscription closed outside of a tokio runtime, its cleanup could not be run"),
+		}
+	}
+}
+
+impl Debug for SubscriptionCleanup {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("SubscriptionCleanup")
+	}
+}
+
 /// Represents a single subscription.
 #[derive(Debug)]
 pub struct SubscriptionSink {
@@ -799,19 +1337,30 @@ pub struct SubscriptionSink {
 	/// to reply to subscription method call and must only be used once.
 	///
 	/// *Note*: Having some value means the subscription was not accepted or rejected yet.
-	id: Option<Id<'static>>,
+	///
+	/// Shared with the subscribe call, which responds to the call itself if the callback panicked.
+	id: Arc<Mutex<Option<Id<'static>>>>,
 	/// Having some value means the subscription was accepted.
 	unsubscribe: UnsubscribeCall,
 	/// Claimed resources.
 	_claimed: Option<ResourceGuard>,
+	/// Cleanup run once the subscription is closed.
+	cleanup: Option<SubscriptionCleanup>,
+	/// Why the subscription was closed, if known already.
+	close_reason: Option<SubscriptionCloseReason>,
+	/// Bound of the messages waiting in the send queue and the count of these messages, set once accepted.
+	buffer: Option<(SubscriptionBuffer, Arc<PendingMessages>)>,
+	/// Since when the buffer of the subscription is known to be full.
+	full_since: Option<Instant>,
 }
 
 impl SubscriptionSink {
 	/// Reject the subscription call from [`ErrorObject`].
 	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
-		let id = self.id.take().ok_or(SubscriptionAcceptRejectError::AlreadyCalled)?;
+		let id = self.id.lock().take().ok_or(SubscriptionAcceptRejectError::AlreadyCalled)?;
 
-		if self.inner.send_error(id, err.into()) {
+		let err = err.into();
+		if self.respond(|sink| sink.send_error(id.clone(), err.clone())) {
 			Ok(())
 		} else {
 			Err(SubscriptionAcceptRejectError::RemotePeerAborted)
@@ -822,9 +1371,11 @@ impl SubscriptionSink {
 	///
 	/// Fails if the connection was closed, or if called multiple times.
 	pub fn accept(&mut self) -> Result<(), SubscriptionAcceptRejectError> {
-		let id = self.id.take().ok_or(SubscriptionAcceptRejectError::AlreadyCalled)?;
+		let id = self.id.lock().take().ok_or(SubscriptionAcceptRejectError::AlreadyCalled)?;
 
-		if self.inner.send_response(id, &self.uniq_sub.sub_id) {
+		if self.respond(|sink| sink.send_response(id.clone(), &self.uniq_sub.sub_id)) {
+			self.inner = self.inner.notification_sink();
+			self.buffer = self.inner.subscription_buffer().map(|buffer| (buffer, Default::default()));
 			let (tx, rx) = watch::channel(());
 			self.subscribers.lock().insert(self.uniq_sub.clone(), (self.inner.clone(), tx));
 			self.unsubscribe = Some(rx);
@@ -834,10 +1385,19 @@ impl SubscriptionSink {
 		}
 	}
 
+	/// Respond to the subscription call with `send`. If the call was part of a batch that was answered already,
+	/// the response is sent on its own instead.
+	fn respond(&self, send: impl Fn(&MethodSink) -> bool) -> bool {
+		send(&self.inner) || (self.inner.is_closed() && send(&self.inner.notification_sink()))
+	}
+
 	/// Send a message back to subscribers.
 	///
+	/// If the server bounds the messages of each subscription waiting to be sent, a message sent while the
+	/// bound is reached is handled according to the [`SubscriptionOverflowPolicy`].
+	///
 	/// Returns
-	/// - `Ok(true)` if the message could be send.
+	/// - `Ok(true)` if the message could be send, or was dropped because of [`SubscriptionOverflowPolicy::DropNewest`].
 	/// - `Ok(false)` if the sink was closed (either because the subscription was closed or the connection was terminated),
 	/// or the subscription could not be accepted.
 	/// - `Err(err)` if the message could not be serialized.
@@ -853,7 +1413,65 @@ impl SubscriptionSink {
 		}
 
 		let msg = self.build_message(result)?;
-		Ok(self.inner.send_raw(msg).is_ok())
+		match &self.buffer {
+			Some((buffer, pending)) if pending.len() >= buffer.max => {
+				let full_since = *self.full_since.get_or_insert_with(Instant::now);
+				match buffer.eviction {
+					Some(eviction) if full_since.elapsed() >= eviction.timeout => {
+						self.evict(eviction);
+						return Ok(false);
+					}
+					_ => (),
+				}
+				self.send_to_full_buffer(msg)
+			}
+			Some((_, pending)) => {
+				self.full_since = None;
+				Ok(self.inner.send_raw_tracked(msg, pending).is_ok())
+			}
+			None => Ok(self.inner.send_raw(msg).is_ok()),
+		}
+	}
+
+	/// Send `msg` while the buffer of the subscription is full, according to its [`SubscriptionOverflowPolicy`].
+	fn send_to_full_buffer(&mut self, msg: String) -> Result<bool, serde_json::Error> {
+		let (buffer, pending) = self.buffer.clone().expect("Only called with a buffer; qed");
+		match buffer.policy {
+				SubscriptionOverflowPolicy::Block => Ok(self.inner.send_raw_tracked(msg, &pending).is_ok()),
+				SubscriptionOverflowPolicy::DropNewest => {
+					tracing::debug!("Subscription {:?} buffer is full, dropping notification", self.uniq_sub.sub_id);
+					Ok(true)
+				}
+				SubscriptionOverflowPolicy::CloseSubscription => {
+					tracing::warn!("Subscription {:?} buffer is full, closing it", self.uniq_sub.sub_id);
+					let err = ErrorObject::owned(
+						SUBSCRIPTION_CLOSED_WITH_ERROR,
+						format!("Subscription closed: more than {} notifications waiting to be sent", buffer.max),
+						None::<()>,
+					);
+					self.close_with(err);
+					Ok(false)
+				}
+		}
+	}
+
+	/// Close the subscription whose buffer stayed full for too long, and its connection if configured so.
+	fn evict(&mut self, eviction: SlowSubscriberEviction) -> ErrorObjectOwned {
+		tracing::warn!(
+			"Subscription {:?} buffer was full for more than {:?}, closing it",
+			self.uniq_sub.sub_id,
+			eviction.timeout
+		);
+		let err = ErrorObject::owned(
+			SUBSCRIPTION_CLOSED_WITH_ERROR,
+			"Subscription closed: the client doesn't read its notifications",
+			None::<()>,
+		);
+		self.close_with(err.clone());
+		if eviction.close_connection {
+			self.inner.abort();
+		}
+		err
 	}
 
 	/// Reads data from the `stream` and sends back data on the subscription
@@ -933,7 +1551,25 @@ impl SubscriptionSink {
 				// The app sent us a value to send back to the subscribers
 				Either::Left((Ok(Some(result)), next_closed_fut)) => {
 					match self.send(&result) {
-						Ok(true) => (),
+						// Wait for room in the send queue of the connection and in the buffer of the subscription
+						// before taking the next item.
+						Ok(true) => {
+							self.inner.ready().await;
+							match self.buffer.clone() {
+								Some((buffer, pending)) if buffer.policy == SubscriptionOverflowPolicy::Block => {
+									match buffer.eviction {
+										Some(eviction) => {
+											let ready = tokio::time::timeout(eviction.timeout, pending.ready(buffer.max));
+											if ready.await.is_err() {
+												break SubscriptionClosed::Failed(self.evict(eviction));
+											}
+										}
+										None => pending.ready(buffer.max).await,
+									}
+								}
+								_ => (),
+							}
+						}
 						Ok(false) => {
 							break SubscriptionClosed::RemotePeerAborted;
 						}
@@ -951,7 +1587,11 @@ impl SubscriptionSink {
 					break SubscriptionClosed::Failed(err);
 				}
 				Either::Left((Ok(None), _)) => break SubscriptionClosed::Success,
-				Either::Right((_, _)) => {
+				Either::Right((closed, _)) => {
+					self.close_reason = Some(match closed {
+						Either::Left(_) => SubscriptionCloseReason::Disconnected,
+						Either::Right(_) => SubscriptionCloseReason::Unsubscribed,
+					});
 					break SubscriptionClosed::RemotePeerAborted;
 				}
 			}
@@ -985,11 +1625,98 @@ impl SubscriptionSink {
 		self.pipe_from_try_stream::<_, _, Error>(stream.map(|item| Ok(item))).await
 	}
 
+	/// Register an async cleanup that is run once the subscription is closed for any reason, to reliably
+	/// release external resources such as database cursors or watchers. Replaces any previously registered cleanup.
+	///
+	/// The cleanup is spawned on the tokio runtime when the sink is closed or dropped, which happens as soon as
+	/// the client unsubscribes or disconnects when the sink is fed by [`SubscriptionSink::pipe_from_stream`].
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub", "_", "unsub", |params, mut sink, _| {
+	///     sink.on_close(|reason| async move { println!("Subscription closed: {:?}", reason) });
+	///     let stream = futures_util::stream::iter(vec![1_usize, 2, 3]);
+	///     tokio::spawn(async move { sink.pipe_from_stream(stream).await; });
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn on_close<F, Fut>(&mut self, cleanup: F)
+	where
+		F: FnOnce(SubscriptionCloseReason) -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.cleanup = Some(SubscriptionCleanup {
+			cleanup: Box::new(move |reason| cleanup(reason).boxed()),
+			runtime: tokio::runtime::Handle::try_current().ok(),
+		});
+	}
+
 	/// Returns whether the subscription is closed.
 	pub fn is_closed(&self) -> bool {
 		self.inner.is_closed() || self.close_notify.is_none() || !self.is_active_subscription()
 	}
 
+	/// Returns a future that resolves once the client has unsubscribed, the connection was closed
+	/// or the subscription was closed by the server.
+	///
+	/// This lets a long-running task producing notifications stop as soon as nobody is listening,
+	/// instead of finding out on the next failed [`SubscriptionSink::send`].
+	/// The future does not borrow the sink and resolves immediately if [`SubscriptionSink::is_closed`]
+	/// is already `true`, including when the subscription has not been accepted yet.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub", "_", "unsub", |_, mut sink, _| {
+	///     sink.accept()?;
+	///     tokio::spawn(async move {
+	///         let closed = sink.closed();
+	///         tokio::pin!(closed);
+	///         let mut n = 0_u64;
+	///         loop {
+	///             tokio::select! {
+	///                 _ = &mut closed => break,
+	///                 _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {
+	///                     n += 1;
+	///                     sink.send(&n).unwrap();
+	///                 }
+	///             }
+	///         }
+	///     });
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+		let conn_closed = self.close_notify.as_ref().map(|cn| cn.handle());
+		let sub_closed = self.unsubscribe.clone();
+		let sink = self.inner.clone();
+
+		async move {
+			let (conn_closed, mut sub_closed) = match (conn_closed, sub_closed) {
+				(Some(conn_closed), Some(sub_closed)) => (conn_closed, sub_closed),
+				_ => return,
+			};
+
+			// Register for the connection close notification before checking the state, so that
+			// a close happening in between is not missed.
+			let conn_closed_fut = conn_closed.notified();
+			if sink.is_closed() || sub_closed.has_changed().is_err() {
+				return;
+			}
+
+			// The sender is dropped when the subscription is removed.
+			let sub_closed_fut = async move { while sub_closed.changed().await.is_ok() {} };
+			pin_mut!(conn_closed_fut);
+			pin_mut!(sub_closed_fut);
+			futures_util::future::select(conn_closed_fut, sub_closed_fut).await;
+		}
+	}
+
 	fn is_active_subscription(&self) -> bool {
 		match self.unsubscribe.as_ref() {
 			Some(unsubscribe) => !unsubscribe.has_changed().is_err(),
@@ -998,6 +1725,18 @@ impl SubscriptionSink {
 	}
 
 	fn build_message<T: Serialize>(&self, result: &T) -> Result<String, serde_json::Error> {
+		let policy = self.inner.number_policy();
+		if policy == NumberPolicy::Preserve {
+			return self.build_serialized_message(result);
+		}
+
+		let result = policy.apply(serde_json::to_string(result)?).map_err(|value| {
+			serde::ser::Error::custom(format!("integer {} can't be represented exactly", value))
+		})?;
+		self.build_serialized_message(&RawValue::from_string(result)?)
+	}
+
+	fn build_serialized_message<T: Serialize>(&self, result: &T) -> Result<String, serde_json::Error> {
 		serde_json::to_string(&SubscriptionResponse::new(
 			self.method.into(),
 			SubscriptionPayload { subscription: self.uniq_sub.sub_id.clone(), result },
@@ -1005,10 +1744,10 @@ impl SubscriptionSink {
 		.map_err(Into::into)
 	}
 
-	fn build_error_message<T: Serialize>(&self, error: &T) -> Result<String, serde_json::Error> {
+	fn build_error_message<T: Serialize>(&self, error: &T, reason: bool) -> Result<String, serde_json::Error> {
 		serde_json::to_string(&SubscriptionError::new(
 			self.method.into(),
-			SubscriptionPayloadError { subscription: self.uniq_sub.sub_id.clone(), error },
+			SubscriptionPayloadError { subscription: self.uniq_sub.sub_id.clone(), error, reason },
 		))
 		.map_err(Into::into)
 	}
@@ -1033,12 +1772,30 @@ impl SubscriptionSink {
 	/// }
 	/// ```
 	///
-	pub fn close(self, err: impl Into<ErrorObjectOwned>) -> bool {
+	pub fn close(mut self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.close_inner(err.into(), false)
+	}
+
+	/// Close the subscription like [`SubscriptionSink::close`] without consuming the sink, for instance from a
+	/// loop producing its notifications. The following messages sent are discarded.
+	///
+	/// Unlike [`SubscriptionSink::close`], `err` is sent as the reason the subscription was closed, with
+	/// `"reason": true` next to the `error` field: the clients of this crate yield it as the last item of the
+	/// subscription, which tells a subscription closed by the server apart from a lost connection.
+	///
+	/// Returns whether the notification could be sent, `false` if the subscription was closed already.
+	pub fn close_with(&mut self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.close_inner(err.into(), true)
+	}
+
+	fn close_inner(&mut self, err: ErrorObjectOwned, reason: bool) -> bool {
+		self.close_reason.get_or_insert(SubscriptionCloseReason::Closed);
+
 		if self.is_active_subscription() {
 			if let Some((sink, _)) = self.subscribers.lock().remove(&self.uniq_sub) {
 				tracing::debug!("Closing subscription: {:?}", self.uniq_sub.sub_id);
 
-				let msg = self.build_error_message(&err.into()).expect("valid json infallible; qed");
+				let msg = self.build_error_message(&err, reason).expect("valid json infallible; qed");
 				return sink.send_raw(msg).is_ok();
 			}
 		}
@@ -1048,12 +1805,29 @@ impl SubscriptionSink {
 
 impl Drop for SubscriptionSink {
 	fn drop(&mut self) {
-		if let Some(id) = self.id.take() {
+		// If the subscribe callback panicked, it responds to the subscription call itself once it caught the panic.
+		let panicked_in_callback = std::thread::panicking() && Arc::strong_count(&self.id) > 1;
+		let pending = if panicked_in_callback { None } else { self.id.lock().take() };
+
+		if let Some(cleanup) = self.cleanup.take() {
+			let reason = self.close_reason.unwrap_or_else(|| {
+				if self.inner.is_closed() {
+					SubscriptionCloseReason::Disconnected
+				} else if self.unsubscribe.is_some() && !self.is_active_subscription() {
+					SubscriptionCloseReason::Unsubscribed
+				} else {
+					SubscriptionCloseReason::Closed
+				}
+			});
+			cleanup.run(reason);
+		}
+
+		if let Some(id) = pending {
 			// Subscription was never accepted / rejected. As such,
 			// we default to assuming that the params were invalid,
 			// because that's how the previous PendingSubscription logic
 			// worked.
-			self.inner.send_error(id, ErrorCode::InvalidParams.into());
+			self.respond(|sink| sink.send_error(id.clone(), ErrorCode::InvalidParams.into()));
 		} else if self.is_active_subscription() {
 			self.subscribers.lock().remove(&self.uniq_sub);
 		}
@@ -1064,7 +1838,7 @@ impl Drop for SubscriptionSink {
 #[derive(Debug)]
 pub struct Subscription {
 	close_notify: Option<SubscriptionPermit>,
-	rx: mpsc::UnboundedReceiver<String>,
+	rx: mpsc::UnboundedReceiver<Bytes>,
 	sub_id: RpcSubscriptionId<'static>,
 }
 
@@ -1100,10 +1874,10 @@ impl Subscription {
 		}
 		let raw = self.rx.next().await?;
 
-		tracing::debug!("rx: {}", raw);
-		let res = match serde_json::from_str::<SubscriptionResponse<T>>(&raw) {
+		tracing::debug!("rx: {:?}", raw);
+		let res = match serde_json::from_slice::<SubscriptionResponse<T>>(&raw) {
 			Ok(r) => Some(Ok((r.params.result, r.params.subscription.into_owned()))),
-			Err(e) => match serde_json::from_str::<SubscriptionError<serde_json::Value>>(&raw) {
+			Err(e) => match serde_json::from_slice::<SubscriptionError<serde_json::Value>>(&raw) {
 				Ok(_) => None,
 				Err(_) => Some(Err(e.into())),
 			},
@@ -0,0 +1,496 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Schemas
+//!
+//! This module describes the parameters and results of methods with JSON schemas derived from their Rust types with
+//! [`schemars`], so that the descriptions never drift from the code.
+//!
+//! [`Schemas`] are usually filled by the `schemas` function that the `rpc` macro generates on server traits when the
+//! `schemas` or `openrpc` argument is passed to it. They're used to build
+//! [OpenRPC documents](super::openrpc::OpenRpc) and can validate the parameters of calls before they're executed,
+//! with the [`ParamsValidator`] middleware:
+//!
+//! ```
+//! use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+//! use jsonrpsee_core::server::schema::{ParamsValidator, Schemas};
+//!
+//! #[rpc(server, schemas)]
+//! pub trait Rpc {
+//!     #[method(name = "add")]
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64>;
+//! }
+//!
+//! struct RpcImpl;
+//!
+//! impl RpcServer for RpcImpl {
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64> {
+//!         Ok(a + b)
+//!     }
+//! }
+//!
+//! let mut schemas = Schemas::new();
+//! <RpcImpl as RpcServer>::schemas(&mut schemas);
+//!
+//! // Rejects calls such as `add(1, -1)` before they reach the method.
+//! let validator = ParamsValidator::new(&schemas);
+//! ```
+//!
+//! Validation supports the subset of JSON schema generated by [`schemars`] for common types: types, enumerations,
+//! references, combinations, number ranges, string lengths, array items and object properties. Other keywords, such
+//! as `pattern` or `format`, aren't checked.
+
+use std::future;
+use std::sync::Arc;
+
+use crate::middleware::{Middleware, OnCallFuture};
+use jsonrpsee_types::error::INVALID_PARAMS_CODE;
+use jsonrpsee_types::{ErrorObject, ErrorObjectOwned, Params};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+
+/// Schemas of the parameters and results of a set of methods.
+#[derive(Debug, Clone)]
+pub struct Schemas {
+	generator: SchemaGenerator,
+	methods: Vec<MethodSchema>,
+}
+
+/// Description of a single method.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MethodSchema {
+	/// Name of the method.
+	pub name: String,
+	/// Documentation of the method.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Parameters of the method, in order.
+	pub params: Vec<ContentDescriptor>,
+	/// Result of the method.
+	pub result: ContentDescriptor,
+	/// Whether the method is deprecated.
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub deprecated: bool,
+}
+
+/// Description of a parameter or result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContentDescriptor {
+	/// Name of the parameter or result.
+	pub name: String,
+	/// Whether the parameter must be provided.
+	pub required: bool,
+	/// JSON schema of the content.
+	pub schema: Schema,
+}
+
+impl Default for Schemas {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Schemas {
+	/// Create new schemas without methods, types used by several methods are defined under `#/definitions/`.
+	pub fn new() -> Self {
+		Self::with_definitions_path("#/definitions/")
+	}
+
+	/// Create new schemas without methods, types used by several methods are defined under `path`.
+	pub(crate) fn with_definitions_path(path: &str) -> Self {
+		let generator =
+			SchemaSettings::draft07().with(|settings| settings.definitions_path = path.into()).into_generator();
+		Self { generator, methods: Vec::new() }
+	}
+
+	/// Returns the schema of `T`, referencing the shared definitions where needed.
+	pub fn schema_for<T: ?Sized + JsonSchema>(&mut self) -> Schema {
+		self.generator.subschema_for::<T>()
+	}
+
+	/// Add a method.
+	pub fn add_method(&mut self, method: MethodSchema) {
+		self.methods.push(method);
+	}
+
+	/// Returns the described methods.
+	pub fn methods(&self) -> &[MethodSchema] {
+		&self.methods
+	}
+
+	/// Returns the method called `name`, if it's described.
+	pub fn method(&self, name: &str) -> Option<&MethodSchema> {
+		self.methods.iter().find(|method| method.name == name)
+	}
+
+	/// Returns the definitions of the types used by several methods, by name.
+	pub fn definitions(&self) -> &schemars::Map<String, Schema> {
+		self.generator.definitions()
+	}
+
+	/// Returns the path under which the definitions are referenced.
+	pub fn definitions_path(&self) -> &str {
+		&self.generator.settings().definitions_path
+	}
+}
+
+/// Middleware rejecting calls whose parameters don't match the [`Schemas`] of their method, with an
+/// `Invalid params` error.
+///
+/// Parameters are validated in [`Middleware::on_call_async`], after the authentication and rate limiting checks.
+#[derive(Debug, Clone)]
+pub struct ParamsValidator {
+	inner: Arc<Validator>,
+}
+
+#[derive(Debug)]
+struct Validator {
+	methods: Vec<MethodSchema>,
+	definitions: schemars::Map<String, Schema>,
+	definitions_path: String,
+}
+
+impl ParamsValidator {
+	/// Create a middleware validating the parameters of calls with `schemas`.
+	pub fn new(schemas: &Schemas) -> Self {
+		let inner = Validator {
+			methods: schemas.methods.clone(),
+			definitions: schemas.definitions().clone(),
+			definitions_path: schemas.definitions_path().to_owned(),
+		};
+		Self { inner: Arc::new(inner) }
+	}
+
+	/// Validate the parameters of a call to the method called `name`.
+	///
+	/// Calls to methods that aren't described are always valid.
+	pub fn validate_params(&self, name: &str, params: &Params) -> Result<(), ErrorObjectOwned> {
+		self.inner.validate_params(name, params)
+	}
+}
+
+impl Validator {
+	fn validate_params(&self, name: &str, params: &Params) -> Result<(), ErrorObjectOwned> {
+		let method = match self.methods.iter().find(|method| method.name == name) {
+			Some(method) => method,
+			None => return Ok(()),
+		};
+		let params: JsonValue =
+			params.parse().map_err(|e| ErrorObject::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>))?;
+
+		self.check_params(method, &params).map_err(|e| ErrorObject::owned(INVALID_PARAMS_CODE, e, None::<()>))
+	}
+
+	fn check_params(&self, method: &MethodSchema, params: &JsonValue) -> Result<(), String> {
+		let mut provided = Vec::with_capacity(method.params.len());
+
+		match params {
+			JsonValue::Null => provided.resize(method.params.len(), None),
+			JsonValue::Array(values) => {
+				if values.len() > method.params.len() {
+					return Err(format!("Expected at most {} parameters, got {}", method.params.len(), values.len()));
+				}
+				provided.extend(values.iter().map(Some));
+				provided.resize(method.params.len(), None);
+			}
+			JsonValue::Object(values) => provided.extend(method.params.iter().map(|param| values.get(&param.name))),
+			_ => return Err("Parameters must be an array or an object".into()),
+		}
+
+		for (param, value) in method.params.iter().zip(provided) {
+			match value {
+				Some(value) => self.check(&param.schema, value, &param.name)?,
+				None if param.required => return Err(format!("Missing required parameter `{}`", param.name)),
+				None => (),
+			}
+		}
+
+		Ok(())
+	}
+
+	fn check(&self, schema: &Schema, value: &JsonValue, path: &str) -> Result<(), String> {
+		match schema {
+			Schema::Bool(true) => Ok(()),
+			Schema::Bool(false) => Err(format!("Unexpected value at `{}`", path)),
+			Schema::Object(object) => self.check_object(object, value, path),
+		}
+	}
+
+	fn check_object(&self, schema: &SchemaObject, value: &JsonValue, path: &str) -> Result<(), String> {
+		if let Some(reference) = &schema.reference {
+			let definition = reference
+				.strip_prefix(&self.definitions_path)
+				.and_then(|name| self.definitions.get(name))
+				.ok_or_else(|| format!("Unknown schema reference `{}`", reference))?;
+			self.check(definition, value, path)?;
+		}
+
+		if let Some(types) = &schema.instance_type {
+			let matches = match types {
+				SingleOrVec::Single(ty) => is_instance_of(value, ty),
+				SingleOrVec::Vec(types) => types.iter().any(|ty| is_instance_of(value, ty)),
+			};
+			if !matches {
+				return Err(format!("Invalid type at `{}`", path));
+			}
+		}
+
+		if let Some(values) = &schema.enum_values {
+			if !values.contains(value) {
+				return Err(format!("Unexpected value at `{}`", path));
+			}
+		}
+
+		if let Some(expected) = &schema.const_value {
+			if expected != value {
+				return Err(format!("Unexpected value at `{}`", path));
+			}
+		}
+
+		if let Some(subschemas) = &schema.subschemas {
+			for subschema in subschemas.all_of.iter().flatten() {
+				self.check(subschema, value, path)?;
+			}
+			if let Some(any_of) = &subschemas.any_of {
+				if !any_of.iter().any(|subschema| self.check(subschema, value, path).is_ok()) {
+					return Err(format!("Invalid value at `{}`", path));
+				}
+			}
+			if let Some(one_of) = &subschemas.one_of {
+				if one_of.iter().filter(|subschema| self.check(subschema, value, path).is_ok()).count() != 1 {
+					return Err(format!("Invalid value at `{}`", path));
+				}
+			}
+			if let Some(not) = &subschemas.not {
+				if self.check(not, value, path).is_ok() {
+					return Err(format!("Invalid value at `{}`", path));
+				}
+			}
+		}
+
+		match value {
+			JsonValue::Number(n) => {
+				if let (Some(number), Some(n)) = (&schema.number, n.as_f64()) {
+					let out_of_range = matches!(number.minimum, Some(min) if n < min)
+						|| matches!(number.maximum, Some(max) if n > max)
+						|| matches!(number.exclusive_minimum, Some(min) if n <= min)
+						|| matches!(number.exclusive_maximum, Some(max) if n >= max);
+					if out_of_range {
+						return Err(format!("Number out of range at `{}`", path));
+					}
+				}
+			}
+			JsonValue::String(s) => {
+				if let Some(string) = &schema.string {
+					let len = s.chars().count() as u32;
+					if matches!(string.min_length, Some(min) if len < min)
+						|| matches!(string.max_length, Some(max) if len > max)
+					{
+						return Err(format!("Invalid string length at `{}`", path));
+					}
+				}
+			}
+			JsonValue::Array(items) => {
+				if let Some(array) = &schema.array {
+					self.check_array(array, items, path)?;
+				}
+			}
+			JsonValue::Object(properties) => {
+				if let Some(object) = &schema.object {
+					self.check_properties(object, properties, path)?;
+				}
+			}
+			JsonValue::Null | JsonValue::Bool(_) => (),
+		}
+
+		Ok(())
+	}
+
+	fn check_array(
+		&self,
+		array: &schemars::schema::ArrayValidation,
+		items: &[JsonValue],
+		path: &str,
+	) -> Result<(), String> {
+		let len = items.len() as u32;
+		if matches!(array.min_items, Some(min) if len < min) || matches!(array.max_items, Some(max) if len > max) {
+			return Err(format!("Invalid number of items at `{}`", path));
+		}
+
+		match &array.items {
+			Some(SingleOrVec::Single(schema)) => {
+				for (i, item) in items.iter().enumerate() {
+					self.check(schema, item, &format!("{}[{}]", path, i))?;
+				}
+			}
+			Some(SingleOrVec::Vec(schemas)) => {
+				for (i, item) in items.iter().enumerate() {
+					let item_path = format!("{}[{}]", path, i);
+					match (schemas.get(i), &array.additional_items) {
+						(Some(schema), _) => self.check(schema, item, &item_path)?,
+						(None, Some(schema)) => self.check(schema, item, &item_path)?,
+						(None, None) => (),
+					}
+				}
+			}
+			None => (),
+		}
+
+		Ok(())
+	}
+
+	fn check_properties(
+		&self,
+		object: &schemars::schema::ObjectValidation,
+		properties: &Map<String, JsonValue>,
+		path: &str,
+	) -> Result<(), String> {
+		if let Some(missing) = object.required.iter().find(|name| !properties.contains_key(*name)) {
+			return Err(format!("Missing property `{}` at `{}`", missing, path));
+		}
+
+		for (name, value) in properties {
+			let property_path = format!("{}.{}", path, name);
+			match (object.properties.get(name), &object.additional_properties) {
+				(Some(schema), _) => self.check(schema, value, &property_path)?,
+				(None, Some(schema)) => self.check(schema, value, &property_path)?,
+				(None, None) => (),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Middleware for ParamsValidator {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_call_async(&self, name: &str, params: &Params) -> OnCallFuture {
+		Box::pin(future::ready(self.validate_params(name, params)))
+	}
+}
+
+fn is_instance_of(value: &JsonValue, ty: &InstanceType) -> bool {
+	match (ty, value) {
+		(InstanceType::Null, JsonValue::Null)
+		| (InstanceType::Boolean, JsonValue::Bool(_))
+		| (InstanceType::Object, JsonValue::Object(_))
+		| (InstanceType::Array, JsonValue::Array(_))
+		| (InstanceType::Number, JsonValue::Number(_))
+		| (InstanceType::String, JsonValue::String(_)) => true,
+		(InstanceType::Integer, JsonValue::Number(n)) => n.is_i64() || n.is_u64(),
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[derive(JsonSchema)]
+	#[allow(dead_code)]
+	struct Transfer {
+		to: String,
+		amount: u64,
+		memo: Option<String>,
+	}
+
+	#[derive(JsonSchema)]
+	#[allow(dead_code)]
+	enum Speed {
+		Slow,
+		Fast,
+	}
+
+	fn schemas() -> Schemas {
+		let mut schemas = Schemas::new();
+		let method = MethodSchema {
+			name: "send".into(),
+			description: None,
+			params: vec![
+				ContentDescriptor {
+					name: "transfers".into(),
+					required: true,
+					schema: schemas.schema_for::<Vec<Transfer>>(),
+				},
+				ContentDescriptor {
+					name: "speed".into(),
+					required: false,
+					schema: schemas.schema_for::<Option<Speed>>(),
+				},
+			],
+			result: ContentDescriptor { name: "result".into(), required: true, schema: schemas.schema_for::<bool>() },
+			deprecated: false,
+		};
+		schemas.add_method(method);
+		schemas
+	}
+
+	fn validate(params: JsonValue) -> Result<(), String> {
+		let schemas = schemas();
+		ParamsValidator::new(&schemas).inner.check_params(schemas.method("send").unwrap(), &params)
+	}
+
+	#[test]
+	fn valid_params_pass() {
+		assert_eq!(validate(json!([[{ "to": "alice", "amount": 1 }]])), Ok(()));
+		assert_eq!(validate(json!([[{ "to": "alice", "amount": 1, "memo": null }], "Fast"])), Ok(()));
+		assert_eq!(validate(json!([[], null])), Ok(()));
+		assert_eq!(validate(json!({ "transfers": [], "speed": "Slow" })), Ok(()));
+	}
+
+	#[test]
+	fn invalid_params_are_rejected() {
+		assert_eq!(validate(JsonValue::Null), Err("Missing required parameter `transfers`".into()));
+		assert_eq!(validate(json!({ "speed": "Fast" })), Err("Missing required parameter `transfers`".into()));
+		assert_eq!(validate(json!([[], "Fast", 1])), Err("Expected at most 2 parameters, got 3".into()));
+		assert_eq!(validate(json!([[], "Faster"])), Err("Invalid value at `speed`".into()));
+		assert_eq!(
+			validate(json!([[{ "to": "alice", "amount": -1 }]])),
+			Err("Number out of range at `transfers[0].amount`".into())
+		);
+		assert_eq!(
+			validate(json!([[{ "to": "alice", "amount": 1.5 }]])),
+			Err("Invalid type at `transfers[0].amount`".into())
+		);
+		assert_eq!(validate(json!([[{ "amount": 1 }]])), Err("Missing property `to` at `transfers[0]`".into()));
+		assert_eq!(validate(json!("alice")), Err("Parameters must be an array or an object".into()));
+	}
+
+	#[test]
+	fn unknown_methods_are_valid() {
+		let params = Params::new(None, Some("[1, 2]"));
+		let validator = ParamsValidator::new(&schemas());
+		assert!(validator.validate_params("unknown", &params).is_ok());
+		assert_eq!(validator.validate_params("send", &params).unwrap_err().code(), INVALID_PARAMS_CODE);
+	}
+}
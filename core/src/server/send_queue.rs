@@ -0,0 +1,425 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Send queue
+//!
+//! Messages sent to a WebSocket client wait in a queue until they're written to the connection. A client that
+//! reads slower than the server produces messages, for instance with many busy subscriptions, makes this queue
+//! grow until the process runs out of memory.
+//!
+//! A queue created by [`send_queue`] holds at most a given number of messages, an [`OverflowPolicy`] decides
+//! what happens to the messages sent while it's full.
+//!
+//! ```
+//! use futures_util::{FutureExt, StreamExt};
+//! use jsonrpsee_core::server::send_queue::{send_queue, OverflowPolicy};
+//!
+//! let (tx, rx) = send_queue(2, OverflowPolicy::DropOldest);
+//! for msg in ["a", "b", "c"] {
+//!     tx.send(msg.into()).unwrap();
+//! }
+//! drop(tx);
+//!
+//! // The oldest message was dropped to make room for the last one.
+//! assert_eq!(rx.collect::<Vec<_>>().now_or_never().unwrap(), ["b", "c"]);
+//! ```
+//!
+//! The messages of a subscription can be bounded separately, see [`SubscriptionBuffer`], so that a busy
+//! subscription doesn't fill the whole queue of its connection.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::task::AtomicWaker;
+use futures_util::Stream;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// What happens to a message sent to a full [`send_queue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+	/// Wait for room in the queue: the WebSocket server stops reading the requests of the client, and
+	/// subscriptions fed by [`SubscriptionSink::pipe_from_stream`](crate::server::rpc_module::SubscriptionSink::pipe_from_stream)
+	/// stop taking items from their stream until the client caught up. Messages sent by other means, such as
+	/// [`SubscriptionSink::send`](crate::server::rpc_module::SubscriptionSink::send), are queued regardless.
+	#[default]
+	Block,
+	/// Drop the oldest message of the queue to make room for the new one. This includes responses to calls,
+	/// whose clients then time out.
+	DropOldest,
+	/// Close the connection of the client, dropping the messages of the queue.
+	CloseConnection,
+}
+
+/// What happens to a notification sent by a subscription which has as many messages waiting in the
+/// [`send_queue`] as its [`SubscriptionBuffer`] allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubscriptionOverflowPolicy {
+	/// Wait for room: subscriptions fed by
+	/// [`SubscriptionSink::pipe_from_stream`](crate::server::rpc_module::SubscriptionSink::pipe_from_stream)
+	/// stop taking items from their stream until the client caught up. Messages sent by other means are queued
+	/// regardless.
+	#[default]
+	Block,
+	/// Drop the notification.
+	DropNewest,
+	/// Close the subscription with an error notification.
+	CloseSubscription,
+}
+
+/// Force-closes the subscriptions whose [`SubscriptionBuffer`] stays full, so that a client not reading its
+/// notifications doesn't hold resources of the server forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowSubscriberEviction {
+	/// How long the buffer of a subscription may stay full before it's closed.
+	pub timeout: Duration,
+	/// Whether the connection of the subscription is closed too, dropping the messages waiting to be sent.
+	pub close_connection: bool,
+}
+
+/// Maximum number of messages a subscription has waiting in the [`send_queue`] of its connection, and what
+/// happens to the notifications it sends while that many are waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionBuffer {
+	/// Maximum number of messages waiting, zero is treated as one.
+	pub max: usize,
+	/// What happens to the notifications sent while `max` messages are waiting.
+	pub policy: SubscriptionOverflowPolicy,
+	/// Closing of the subscriptions whose buffer stays full, `None` to keep them.
+	pub eviction: Option<SlowSubscriberEviction>,
+}
+
+impl SubscriptionBuffer {
+	/// Create a buffer of `max` messages, the notifications sent while it's full are handled according to `policy`.
+	pub fn new(max: usize, policy: SubscriptionOverflowPolicy) -> Self {
+		Self { max: max.max(1), policy, eviction: None }
+	}
+
+	/// Close the subscriptions whose buffer stays full for longer than `timeout`, and their connection as well
+	/// if `close_connection` is set.
+	pub fn evict_slow_subscribers(mut self, timeout: Duration, close_connection: bool) -> Self {
+		self.eviction = Some(SlowSubscriberEviction { timeout, close_connection });
+		self
+	}
+}
+
+/// Number of messages sent with [`QueueSender::send_tracked`] which are still waiting in the queue.
+#[derive(Debug, Default)]
+pub struct PendingMessages {
+	count: AtomicUsize,
+	/// Notified when a message is taken from the queue or dropped.
+	room: Notify,
+}
+
+impl PendingMessages {
+	/// Returns the number of messages waiting.
+	pub fn len(&self) -> usize {
+		self.count.load(Ordering::Acquire)
+	}
+
+	/// Returns whether no messages are waiting.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Wait until fewer than `max` messages are waiting.
+	pub async fn ready(&self, max: usize) {
+		loop {
+			// Created before checking the count so that messages taken in between aren't missed.
+			let room = self.room.notified();
+			if self.len() < max {
+				return;
+			}
+			room.await;
+		}
+	}
+}
+
+/// Counts a message in its [`PendingMessages`] until dropped.
+struct Tracked(Arc<PendingMessages>);
+
+impl Tracked {
+	fn new(pending: &Arc<PendingMessages>) -> Self {
+		pending.count.fetch_add(1, Ordering::AcqRel);
+		Self(pending.clone())
+	}
+}
+
+impl Drop for Tracked {
+	fn drop(&mut self) {
+		self.0.count.fetch_sub(1, Ordering::AcqRel);
+		self.0.room.notify_waiters();
+	}
+}
+
+struct Shared {
+	queue: Mutex<VecDeque<(Bytes, Option<Tracked>)>>,
+	max: usize,
+	policy: OverflowPolicy,
+	closed: AtomicBool,
+	senders: AtomicUsize,
+	/// Woken when a message is queued or the queue is closed.
+	receiver: AtomicWaker,
+	/// Notified when a message is taken from the queue or the queue is closed.
+	room: Notify,
+}
+
+impl Shared {
+	fn close(&self) {
+		self.closed.store(true, Ordering::Release);
+		self.receiver.wake();
+		self.room.notify_waiters();
+	}
+}
+
+/// Create a queue holding at most `max` messages, the messages sent while it's full are handled according to
+/// `policy`.
+pub fn send_queue(max: usize, policy: OverflowPolicy) -> (QueueSender, QueueReceiver) {
+	let shared = Arc::new(Shared {
+		queue: Mutex::new(VecDeque::new()),
+		max: max.max(1),
+		policy,
+		closed: AtomicBool::new(false),
+		senders: AtomicUsize::new(1),
+		receiver: AtomicWaker::new(),
+		room: Notify::new(),
+	});
+
+	(QueueSender(shared.clone()), QueueReceiver(shared))
+}
+
+/// Sending half of a [`send_queue`].
+pub struct QueueSender(Arc<Shared>);
+
+impl QueueSender {
+	/// Queue `msg`, fails with the message if the queue is closed or was closed because it overflowed.
+	pub fn send(&self, msg: Bytes) -> Result<(), Bytes> {
+		self.push(msg, None)
+	}
+
+	/// Queue `msg` like [`QueueSender::send`], counting it in `pending` until it's taken from the queue or dropped.
+	pub fn send_tracked(&self, msg: Bytes, pending: &Arc<PendingMessages>) -> Result<(), Bytes> {
+		self.push(msg, Some(Tracked::new(pending)))
+	}
+
+	fn push(&self, msg: Bytes, tracked: Option<Tracked>) -> Result<(), Bytes> {
+		if self.is_closed() {
+			return Err(msg);
+		}
+
+		let mut queue = self.0.queue.lock();
+		if queue.len() >= self.0.max {
+			match self.0.policy {
+				OverflowPolicy::Block => (),
+				OverflowPolicy::DropOldest => {
+					tracing::warn!("Send queue is full ({} messages), dropping the oldest message", self.0.max);
+					queue.pop_front();
+				}
+				OverflowPolicy::CloseConnection => {
+					tracing::warn!("Send queue is full ({} messages), closing the connection", self.0.max);
+					queue.clear();
+					drop(queue);
+					self.0.close();
+					return Err(msg);
+				}
+			}
+		}
+		queue.push_back((msg, tracked));
+		drop(queue);
+
+		self.0.receiver.wake();
+		Ok(())
+	}
+
+	/// Wait until the queue has room for another message or is closed.
+	///
+	/// Only waits with [`OverflowPolicy::Block`], the other policies never make senders wait.
+	pub async fn ready(&self) {
+		if self.0.policy != OverflowPolicy::Block {
+			return;
+		}
+
+		loop {
+			// Created before checking the queue so that messages taken in between aren't missed.
+			let room = self.0.room.notified();
+			if self.is_closed() || self.0.queue.lock().len() < self.0.max {
+				return;
+			}
+			room.await;
+		}
+	}
+
+	/// Returns whether the queue is closed, no messages can be sent anymore.
+	pub fn is_closed(&self) -> bool {
+		self.0.closed.load(Ordering::Acquire)
+	}
+
+	/// Close the queue. The messages already queued are still received.
+	pub fn close(&self) {
+		self.0.close();
+	}
+
+	/// Close the queue, dropping the messages already queued.
+	pub fn abort(&self) {
+		self.0.queue.lock().clear();
+		self.0.close();
+	}
+}
+
+impl Clone for QueueSender {
+	fn clone(&self) -> Self {
+		self.0.senders.fetch_add(1, Ordering::Relaxed);
+		Self(self.0.clone())
+	}
+}
+
+impl Drop for QueueSender {
+	fn drop(&mut self) {
+		if self.0.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+			self.0.receiver.wake();
+		}
+	}
+}
+
+impl fmt::Debug for QueueSender {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("QueueSender").field("max", &self.0.max).field("policy", &self.0.policy).finish()
+	}
+}
+
+/// Receiving half of a [`send_queue`], a stream of the queued messages that ends once the queue is closed
+/// and empty, or all the senders are dropped.
+pub struct QueueReceiver(Arc<Shared>);
+
+impl Stream for QueueReceiver {
+	type Item = Bytes;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Bytes>> {
+		let shared = &self.0;
+
+		// Registered before checking the queue so that messages queued in between aren't missed.
+		shared.receiver.register(cx.waker());
+
+		if let Some((msg, _)) = shared.queue.lock().pop_front() {
+			shared.room.notify_waiters();
+			return Poll::Ready(Some(msg));
+		}
+
+		if shared.closed.load(Ordering::Acquire) || shared.senders.load(Ordering::Acquire) == 0 {
+			Poll::Ready(None)
+		} else {
+			Poll::Pending
+		}
+	}
+}
+
+impl Drop for QueueReceiver {
+	fn drop(&mut self) {
+		self.0.close();
+		// Nothing is taken from the queue anymore.
+		self.0.queue.lock().clear();
+	}
+}
+
+impl fmt::Debug for QueueReceiver {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("QueueReceiver").field("max", &self.0.max).field("policy", &self.0.policy).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{send_queue, OverflowPolicy, PendingMessages};
+	use futures_util::{FutureExt, StreamExt};
+	use std::sync::Arc;
+
+	#[tokio::test]
+	async fn block_waits_for_room() {
+		let (tx, mut rx) = send_queue(1, OverflowPolicy::Block);
+		tx.send("a".into()).unwrap();
+
+		let mut ready = Box::pin(tx.ready());
+		assert!((&mut ready).now_or_never().is_none());
+
+		assert_eq!(rx.next().await.unwrap(), "a");
+		assert!(ready.now_or_never().is_some());
+
+		// Messages are still queued when the queue is full.
+		tx.send("b".into()).unwrap();
+		tx.send("c".into()).unwrap();
+		drop(tx);
+		assert_eq!(rx.collect::<Vec<_>>().await, ["b", "c"]);
+	}
+
+	#[tokio::test]
+	async fn close_connection_closes_the_queue() {
+		let (tx, mut rx) = send_queue(1, OverflowPolicy::CloseConnection);
+		tx.send("a".into()).unwrap();
+		assert_eq!(tx.send("b".into()), Err("b".into()));
+
+		assert!(tx.is_closed());
+		assert!(rx.next().await.is_none());
+	}
+
+	#[tokio::test]
+	async fn tracked_messages_are_counted_until_taken() {
+		let (tx, mut rx) = send_queue(2, OverflowPolicy::DropOldest);
+		let pending = Arc::new(PendingMessages::default());
+		tx.send_tracked("a".into(), &pending).unwrap();
+		tx.send("b".into()).unwrap();
+		tx.send_tracked("c".into(), &pending).unwrap();
+
+		// "a" was dropped to make room for "c".
+		assert_eq!(pending.len(), 1);
+		let mut ready = Box::pin(pending.ready(1));
+		assert!((&mut ready).now_or_never().is_none());
+
+		assert_eq!(rx.next().await.unwrap(), "b");
+		assert_eq!(rx.next().await.unwrap(), "c");
+		assert!(pending.is_empty());
+		assert!(ready.now_or_never().is_some());
+
+		tx.send_tracked("d".into(), &pending).unwrap();
+		drop(rx);
+		assert!(pending.is_empty());
+	}
+
+	#[tokio::test]
+	async fn dropped_receiver_closes_the_queue() {
+		let (tx, rx) = send_queue(1, OverflowPolicy::Block);
+		drop(rx);
+
+		assert!(tx.is_closed());
+		assert!(tx.send("a".into()).is_err());
+		tx.ready().await;
+	}
+}
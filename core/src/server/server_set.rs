@@ -0,0 +1,498 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Server sets
+//!
+//! This module runs several servers, for instance an HTTP and a WebSocket server, that share one set of
+//! [`Methods`] under a single supervisor.
+//!
+//! Every server is registered with a name and a launcher: an async closure that builds and starts the server
+//! for the given methods. The set starts all servers together, stops them together and restarts servers that
+//! stopped without being asked to according to its [`RestartPolicy`]. Any server whose handle implements
+//! [`ServerControl`] can be part of a set.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use jsonrpsee::http_server::HttpServerBuilder;
+//! use jsonrpsee::ws_server::WsServerBuilder;
+//! use jsonrpsee_core::server::rpc_module::RpcModule;
+//! use jsonrpsee_core::server::server_set::{Launched, RestartPolicy, ServerSet};
+//!
+//! # async fn run() -> Result<(), jsonrpsee_core::Error> {
+//! let mut module = RpcModule::new(());
+//! module.register_method("say_hello", |_, _| Ok("lo"))?;
+//!
+//! let handle = ServerSet::new(module)
+//!     .server("http", |methods| async move {
+//!         let server = HttpServerBuilder::default().build("127.0.0.1:9933").await?;
+//!         let addrs = server.local_addrs().to_vec();
+//!         Ok(Launched::new(addrs, server.start(methods)?))
+//!     })?
+//!     .server("ws", |methods| async move {
+//!         let server = WsServerBuilder::default().build("127.0.0.1:9944").await?;
+//!         let addrs = server.local_addrs().to_vec();
+//!         Ok(Launched::new(addrs, server.start(methods)?))
+//!     })?
+//!     .restart_policy(RestartPolicy::OnCrash { max_restarts: Some(3), backoff: Duration::from_secs(1) })
+//!     .start()
+//!     .await?;
+//!
+//! for stats in handle.stats() {
+//!     println!("{} is {:?} on {:?}", stats.name, stats.status, stats.local_addrs);
+//! }
+//!
+//! handle.stop()?;
+//! handle.await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::server::rpc_module::Methods;
+use crate::Error;
+use futures_util::future::{self, BoxFuture, Either, FutureExt, JoinAll};
+use parking_lot::Mutex;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Handle to a running server that can be supervised by a [`ServerSet`].
+///
+/// The handle is a future that resolves once the server has stopped.
+pub trait ServerControl: Future<Output = ()> + Send + Unpin + 'static {
+	/// Requests the server to stop. Does nothing if the server was already stopped.
+	fn request_stop(&mut self);
+}
+
+/// A server started by the launcher of a [`ServerSet`].
+pub struct Launched {
+	local_addrs: Vec<SocketAddr>,
+	handle: Box<dyn ServerControl>,
+}
+
+impl Launched {
+	/// Wraps the handle of a server that is listening on `local_addrs`.
+	pub fn new(local_addrs: impl Into<Vec<SocketAddr>>, handle: impl ServerControl) -> Self {
+		Self { local_addrs: local_addrs.into(), handle: Box::new(handle) }
+	}
+
+	/// Requests the server to stop and waits until it did.
+	async fn stop(mut self) {
+		self.handle.request_stop();
+		self.handle.await;
+	}
+}
+
+impl fmt::Debug for Launched {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Launched").field("local_addrs", &self.local_addrs).finish()
+	}
+}
+
+/// Builds and starts a server for the given methods.
+type Launcher = Box<dyn Fn(Methods) -> BoxFuture<'static, Result<Launched, Error>> + Send + Sync>;
+
+/// Policy for restarting servers that stopped without being asked to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+	/// Crashed servers are not restarted and remain [`ServerStatus::Failed`].
+	#[default]
+	Never,
+	/// Crashed servers are restarted after waiting for `backoff`, a failed launch counts as a restart as well.
+	/// The server is given up on once it has been restarted `max_restarts` times, unless that is `None`.
+	OnCrash {
+		/// Maximum number of restarts of a single server.
+		max_restarts: Option<u32>,
+		/// Time to wait before every restart.
+		backoff: Duration,
+	},
+}
+
+/// Status of a server in a [`ServerSet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerStatus {
+	/// The server is running.
+	Running,
+	/// The server crashed and is waiting to be restarted.
+	Restarting,
+	/// The server was stopped by [`ServerSetHandle::stop`].
+	Stopped,
+	/// The server crashed and won't be restarted anymore.
+	Failed,
+}
+
+/// Statistics of a server in a [`ServerSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerStats {
+	/// Name the server was registered with.
+	pub name: &'static str,
+	/// Current status.
+	pub status: ServerStatus,
+	/// Addresses the server was last listening on.
+	pub local_addrs: Vec<SocketAddr>,
+	/// Number of times the server was restarted.
+	pub restarts: u32,
+}
+
+/// Set of servers sharing the same [`Methods`] which are started, supervised and stopped together.
+pub struct ServerSet {
+	methods: Methods,
+	servers: Vec<(&'static str, Launcher)>,
+	restart_policy: RestartPolicy,
+}
+
+impl fmt::Debug for ServerSet {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ServerSet")
+			.field("methods", &self.methods)
+			.field("servers", &self.servers.iter().map(|(name, _)| name).collect::<Vec<_>>())
+			.field("restart_policy", &self.restart_policy)
+			.finish()
+	}
+}
+
+impl ServerSet {
+	/// Create a new, empty set of servers that will serve `methods`.
+	pub fn new(methods: impl Into<Methods>) -> Self {
+		Self { methods: methods.into(), servers: Vec::new(), restart_policy: RestartPolicy::default() }
+	}
+
+	/// Register a server under `name`, started by `launcher` for the methods of the set.
+	///
+	/// The launcher is called again for every restart of the server.
+	///
+	/// Returns an error if a server with the same name was already registered.
+	pub fn server<F, Fut>(mut self, name: &'static str, launcher: F) -> Result<Self, Error>
+	where
+		F: Fn(Methods) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<Launched, Error>> + Send + 'static,
+	{
+		if self.servers.iter().any(|(other, _)| *other == name) {
+			return Err(Error::ServerNameAlreadyTaken(name));
+		}
+
+		self.servers.push((name, Box::new(move |methods| launcher(methods).boxed())));
+		Ok(self)
+	}
+
+	/// Configure the policy for restarting crashed servers (default: [`RestartPolicy::Never`]).
+	pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+		self.restart_policy = policy;
+		self
+	}
+
+	/// Start all servers in the order they were registered.
+	///
+	/// If any of them fails to start, the servers started so far are stopped again and the error is returned.
+	pub async fn start(self) -> Result<ServerSetHandle, Error> {
+		let mut launched = Vec::with_capacity(self.servers.len());
+
+		for (_, launcher) in &self.servers {
+			match launcher(self.methods.clone()).await {
+				Ok(server) => launched.push(server),
+				Err(err) => {
+					future::join_all(launched.into_iter().map(Launched::stop)).await;
+					return Err(err);
+				}
+			}
+		}
+
+		let (stop_tx, stop_rx) = watch::channel(false);
+		let mut stats = Vec::with_capacity(launched.len());
+		let mut tasks = Vec::with_capacity(launched.len());
+
+		for ((name, launcher), server) in self.servers.into_iter().zip(launched) {
+			let server_stats = Arc::new(Mutex::new(ServerStats {
+				name,
+				status: ServerStatus::Running,
+				local_addrs: server.local_addrs.clone(),
+				restarts: 0,
+			}));
+			let supervisor = Supervisor {
+				launcher,
+				methods: self.methods.clone(),
+				policy: self.restart_policy,
+				stats: server_stats.clone(),
+				stop: stop_rx.clone(),
+			};
+
+			stats.push(server_stats);
+			tasks.push(tokio::spawn(supervisor.run(server)));
+		}
+
+		Ok(ServerSetHandle { stop_tx, stop_requested: AtomicBool::new(false), stats, tasks: future::join_all(tasks) })
+	}
+}
+
+/// Handle to a started [`ServerSet`].
+///
+/// The handle is a future that resolves once all servers of the set have stopped or failed.
+pub struct ServerSetHandle {
+	stop_tx: watch::Sender<bool>,
+	stop_requested: AtomicBool,
+	stats: Vec<Arc<Mutex<ServerStats>>>,
+	tasks: JoinAll<JoinHandle<()>>,
+}
+
+impl fmt::Debug for ServerSetHandle {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ServerSetHandle").field("stats", &self.stats()).finish()
+	}
+}
+
+impl ServerSetHandle {
+	/// Requests all servers to stop. Returns an error if the set was already stopped.
+	pub fn stop(&self) -> Result<(), Error> {
+		if self.stop_requested.swap(true, Ordering::Relaxed) {
+			return Err(Error::AlreadyStopped);
+		}
+
+		// Fails only if all supervisors are gone already, in which case there is nothing left to stop.
+		let _ = self.stop_tx.send(true);
+		Ok(())
+	}
+
+	/// Returns the statistics of all servers, in the order they were registered.
+	pub fn stats(&self) -> Vec<ServerStats> {
+		self.stats.iter().map(|stats| stats.lock().clone()).collect()
+	}
+}
+
+impl Future for ServerSetHandle {
+	type Output = ();
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.tasks.poll_unpin(cx).map(|_| ())
+	}
+}
+
+/// Watches over a single server of the set.
+struct Supervisor {
+	launcher: Launcher,
+	methods: Methods,
+	policy: RestartPolicy,
+	stats: Arc<Mutex<ServerStats>>,
+	stop: watch::Receiver<bool>,
+}
+
+impl Supervisor {
+	async fn run(mut self, mut server: Launched) {
+		loop {
+			let stopped = Box::pin(stop_requested(self.stop.clone()));
+
+			if let Either::Right(_) = future::select(&mut server.handle, stopped).await {
+				server.stop().await;
+				self.stats.lock().status = ServerStatus::Stopped;
+				return;
+			}
+
+			tracing::warn!("Server `{}` stopped unexpectedly", self.stats.lock().name);
+
+			server = match self.restart().await {
+				Some(server) => server,
+				None => return,
+			};
+		}
+	}
+
+	/// Restarts the server according to the policy, returns `None` if it was given up on or the set was stopped.
+	async fn restart(&mut self) -> Option<Launched> {
+		let (max_restarts, backoff) = match self.policy {
+			RestartPolicy::Never => {
+				self.stats.lock().status = ServerStatus::Failed;
+				return None;
+			}
+			RestartPolicy::OnCrash { max_restarts, backoff } => (max_restarts, backoff),
+		};
+
+		loop {
+			{
+				let mut stats = self.stats.lock();
+				if max_restarts.is_some_and(|max| stats.restarts >= max) {
+					tracing::error!("Server `{}` crashed too often, giving up", stats.name);
+					stats.status = ServerStatus::Failed;
+					return None;
+				}
+				stats.status = ServerStatus::Restarting;
+			}
+
+			let backoff = Box::pin(tokio::time::sleep(backoff));
+			let stopped = Box::pin(stop_requested(self.stop.clone()));
+
+			if let Either::Right(_) = future::select(backoff, stopped).await {
+				self.stats.lock().status = ServerStatus::Stopped;
+				return None;
+			}
+
+			let launched = (self.launcher)(self.methods.clone()).await;
+			let mut stats = self.stats.lock();
+			stats.restarts += 1;
+
+			match launched {
+				Ok(server) => {
+					stats.status = ServerStatus::Running;
+					stats.local_addrs = server.local_addrs.clone();
+					return Some(server);
+				}
+				Err(err) => tracing::error!("Failed to restart server `{}`: {:?}", stats.name, err),
+			}
+		}
+	}
+}
+
+/// Resolves once the set was requested to stop. Never resolves if the handle of the set was dropped.
+async fn stop_requested(mut stop: watch::Receiver<bool>) {
+	while !*stop.borrow() {
+		if stop.changed().await.is_err() {
+			future::pending::<()>().await;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_channel::oneshot;
+
+	/// Server that runs until it's stopped or crashed through the sender kept by the test.
+	struct TestServer {
+		done: oneshot::Receiver<()>,
+		trigger: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+	}
+
+	impl Future for TestServer {
+		type Output = ();
+
+		fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+			self.done.poll_unpin(cx).map(|_| ())
+		}
+	}
+
+	impl ServerControl for TestServer {
+		fn request_stop(&mut self) {
+			self.trigger.lock().take();
+		}
+	}
+
+	fn launcher(
+		trigger: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+	) -> impl Fn(Methods) -> future::Ready<Result<Launched, Error>> + Send + Sync + 'static {
+		move |_| {
+			let (tx, done) = oneshot::channel();
+			*trigger.lock() = Some(tx);
+			future::ready(Ok(Launched::new(Vec::new(), TestServer { done, trigger: trigger.clone() })))
+		}
+	}
+
+	async fn wait_for_status(handle: &ServerSetHandle, status: ServerStatus) -> ServerStats {
+		loop {
+			let stats = handle.stats().remove(0);
+			if stats.status == status {
+				return stats;
+			}
+			tokio::time::sleep(Duration::from_millis(1)).await;
+		}
+	}
+
+	#[test]
+	fn duplicate_names_are_rejected() {
+		let trigger = Arc::new(Mutex::new(None));
+		let set = ServerSet::new(Methods::new()).server("a", launcher(trigger.clone())).unwrap();
+
+		assert!(matches!(set.server("a", launcher(trigger)), Err(Error::ServerNameAlreadyTaken("a"))));
+	}
+
+	#[tokio::test]
+	async fn crashed_servers_are_restarted() {
+		let trigger = Arc::new(Mutex::new(None));
+		let handle = ServerSet::new(Methods::new())
+			.server("a", launcher(trigger.clone()))
+			.unwrap()
+			.restart_policy(RestartPolicy::OnCrash { max_restarts: Some(1), backoff: Duration::from_millis(1) })
+			.start()
+			.await
+			.unwrap();
+
+		assert_eq!(handle.stats()[0].status, ServerStatus::Running);
+
+		// Dropping the sender crashes the server.
+		trigger.lock().take();
+		while handle.stats()[0].restarts == 0 {
+			tokio::time::sleep(Duration::from_millis(1)).await;
+		}
+		assert_eq!(wait_for_status(&handle, ServerStatus::Running).await.restarts, 1);
+
+		trigger.lock().take();
+		assert_eq!(wait_for_status(&handle, ServerStatus::Failed).await.restarts, 1);
+
+		handle.stop().unwrap();
+		assert!(matches!(handle.stop(), Err(Error::AlreadyStopped)));
+		handle.await;
+	}
+
+	#[tokio::test]
+	async fn stop_stops_all_servers() {
+		let a = Arc::new(Mutex::new(None));
+		let b = Arc::new(Mutex::new(None));
+		let handle = ServerSet::new(Methods::new())
+			.server("a", launcher(a.clone()))
+			.unwrap()
+			.server("b", launcher(b.clone()))
+			.unwrap()
+			.start()
+			.await
+			.unwrap();
+
+		handle.stop().unwrap();
+		let stats = handle.stats.clone();
+		handle.await;
+
+		assert!(stats.iter().all(|stats| stats.lock().status == ServerStatus::Stopped));
+		assert!(a.lock().is_none() && b.lock().is_none());
+	}
+
+	#[tokio::test]
+	async fn failed_start_stops_started_servers() {
+		let trigger = Arc::new(Mutex::new(None));
+		let res = ServerSet::new(Methods::new())
+			.server("a", launcher(trigger.clone()))
+			.unwrap()
+			.server("b", |_| future::ready(Err(Error::Custom("bind failed".into()))))
+			.unwrap()
+			.start()
+			.await;
+
+		assert!(matches!(res, Err(Error::Custom(_))));
+		assert!(trigger.lock().is_none());
+	}
+}
@@ -0,0 +1,123 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Shutdown
+//!
+//! This module describes why a server stopped, so that supervisors and clients can tell planned stops and
+//! restarts apart from failures.
+//!
+//! The reason is recorded when the server is asked to stop, or when it fails, and can be retrieved from its
+//! handle. Before closing their connection, the WebSocket server sends its clients a
+//! [`SHUTDOWN_NOTIFICATION`] whose params are `{"reason": <reason>}`, with the reason as returned by
+//! [`ShutdownReason::as_str`].
+
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Method of the notification sent to the clients of a WebSocket server before it closes their connection.
+pub const SHUTDOWN_NOTIFICATION: &str = "server_shutdown";
+
+/// Why a server stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownReason {
+	/// The server was stopped through its handle.
+	Stopped,
+	/// The server was stopped through its handle to be restarted, for instance with a new configuration.
+	Restart,
+	/// The server failed, for instance because it could no longer accept connections.
+	Failed(String),
+}
+
+impl ShutdownReason {
+	/// Returns the short machine-readable form of the reason: `stopped`, `restart` or `failed`.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Stopped => "stopped",
+			Self::Restart => "restart",
+			Self::Failed(_) => "failed",
+		}
+	}
+
+	/// Returns whether the shutdown was requested through the handle of the server.
+	pub fn is_planned(&self) -> bool {
+		!matches!(self, Self::Failed(_))
+	}
+}
+
+impl fmt::Display for ShutdownReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Failed(err) => write!(f, "failed: {}", err),
+			reason => f.write_str(reason.as_str()),
+		}
+	}
+}
+
+/// Reason of the shutdown of a server, shared by the server and its handles.
+///
+/// Only the first recorded reason is kept.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownRecord(Arc<Mutex<Option<ShutdownReason>>>);
+
+impl ShutdownRecord {
+	/// Create a record without any reason, the server is running.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record `reason` unless a reason was recorded already, returns whether it was recorded.
+	pub fn record(&self, reason: ShutdownReason) -> bool {
+		let mut recorded = self.0.lock();
+		if recorded.is_some() {
+			return false;
+		}
+		*recorded = Some(reason);
+		true
+	}
+
+	/// Returns the recorded reason, `None` if the server wasn't asked to stop and didn't fail.
+	pub fn reason(&self) -> Option<ShutdownReason> {
+		self.0.lock().clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ShutdownReason, ShutdownRecord};
+
+	#[test]
+	fn first_reason_is_kept() {
+		let record = ShutdownRecord::new();
+		assert_eq!(record.reason(), None);
+
+		assert!(record.clone().record(ShutdownReason::Restart));
+		assert!(!record.record(ShutdownReason::Failed("boom".into())));
+		assert_eq!(record.reason(), Some(ShutdownReason::Restart));
+		assert!(record.reason().unwrap().is_planned());
+	}
+}
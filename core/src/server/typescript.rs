@@ -0,0 +1,411 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # TypeScript
+//!
+//! This module generates TypeScript clients from the [`Schemas`] of methods, so that web frontends get typed
+//! bindings that never drift from the server.
+//!
+//! The generated code declares a type for every shared definition of the schemas and a class with a method per RPC
+//! method, which sends the calls through a `Transport` provided by the application:
+//!
+//! ```ts
+//! export interface Transport {
+//!   request(method: string, params: unknown[]): Promise<unknown>;
+//! }
+//! ```
+//!
+//! ```
+//! use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+//! use jsonrpsee_core::server::schema::Schemas;
+//! use jsonrpsee_core::server::typescript;
+//!
+//! #[rpc(server, schemas)]
+//! pub trait Rpc {
+//!     /// Adds two numbers.
+//!     #[method(name = "calc_add")]
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64>;
+//! }
+//!
+//! struct RpcImpl;
+//!
+//! impl RpcServer for RpcImpl {
+//!     fn add(&self, a: u64, b: u64) -> RpcResult<u64> {
+//!         Ok(a + b)
+//!     }
+//! }
+//!
+//! let mut schemas = Schemas::new();
+//! <RpcImpl as RpcServer>::schemas(&mut schemas);
+//!
+//! let client = typescript::generate(&schemas, "CalcClient");
+//! assert!(client.contains("calcAdd(a: number, b: number): Promise<number> {"));
+//! ```
+//!
+//! Integers are mapped to `number`, consider a [`NumberPolicy`](jsonrpsee_types::number::NumberPolicy) for
+//! integers that can't be represented exactly as doubles.
+
+use std::fmt::Write;
+
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value as JsonValue;
+
+use super::schema::{MethodSchema, Schemas};
+
+/// Indentation of the generated code.
+const INDENT: &str = "  ";
+
+/// Generate the TypeScript declarations of the types and a client class called `class_name` for the methods
+/// described by `schemas`.
+pub fn generate(schemas: &Schemas, class_name: &str) -> String {
+	let generator = Generator { definitions_path: schemas.definitions_path() };
+	let mut out = String::from("// Generated by jsonrpsee, do not edit.\n\n");
+
+	out.push_str("export interface Transport {\n");
+	let _ = writeln!(out, "{}request(method: string, params: unknown[]): Promise<unknown>;", INDENT);
+	out.push_str("}\n");
+
+	for (name, schema) in schemas.definitions() {
+		out.push('\n');
+		write_doc(&mut out, description(schema), "");
+		let _ = writeln!(out, "export type {} = {};", identifier(name), generator.type_of(schema, ""));
+	}
+
+	out.push('\n');
+	let _ = writeln!(out, "export class {} {{", identifier(class_name));
+	let _ = writeln!(out, "{}constructor(private readonly transport: Transport) {{}}", INDENT);
+	for method in schemas.methods() {
+		out.push('\n');
+		generator.write_method(&mut out, method);
+	}
+	out.push_str("}\n");
+
+	out
+}
+
+struct Generator<'a> {
+	definitions_path: &'a str,
+}
+
+impl Generator<'_> {
+	fn write_method(&self, out: &mut String, method: &MethodSchema) {
+		let indent = INDENT.repeat(2);
+		let mut doc = method.description.clone();
+		if method.deprecated {
+			doc = Some(match doc {
+				Some(doc) => format!("{}\n\n@deprecated", doc),
+				None => "@deprecated".to_owned(),
+			});
+		}
+		write_doc(out, doc.as_deref(), INDENT);
+
+		// Parameters can only be omitted if all the following ones can be omitted too.
+		let omittable = method.params.iter().rposition(|param| param.required).map_or(0, |last| last + 1);
+		let params: Vec<_> = method
+			.params
+			.iter()
+			.enumerate()
+			.map(|(i, param)| {
+				let optional = if i >= omittable { "?" } else { "" };
+				format!("{}{}: {}", identifier(&param.name), optional, self.type_of(&param.schema, &indent))
+			})
+			.collect();
+		let args: Vec<_> = method.params.iter().map(|param| identifier(&param.name)).collect();
+		let result = self.type_of(&method.result.schema, &indent);
+
+		let _ = writeln!(out, "{}{}({}): Promise<{}> {{", INDENT, method_name(&method.name), params.join(", "), result);
+		let _ = writeln!(
+			out,
+			"{}return this.transport.request({}, [{}]) as Promise<{}>;",
+			indent,
+			JsonValue::from(method.name.as_str()),
+			args.join(", "),
+			result
+		);
+		let _ = writeln!(out, "{}}}", INDENT);
+	}
+
+	/// Returns the TypeScript type of `schema`, nested object types are indented by `indent`.
+	fn type_of(&self, schema: &Schema, indent: &str) -> String {
+		match schema {
+			Schema::Bool(true) => "unknown".to_owned(),
+			Schema::Bool(false) => "never".to_owned(),
+			Schema::Object(object) => self.type_of_object(object, indent),
+		}
+	}
+
+	fn type_of_object(&self, schema: &SchemaObject, indent: &str) -> String {
+		if let Some(reference) = &schema.reference {
+			return match reference.strip_prefix(self.definitions_path) {
+				Some(name) => identifier(name),
+				None => "unknown".to_owned(),
+			};
+		}
+		if let Some(value) = &schema.const_value {
+			return value.to_string();
+		}
+		if let Some(values) = &schema.enum_values {
+			return union(values.iter().map(JsonValue::to_string).collect());
+		}
+
+		let mut parts = Vec::new();
+
+		if let Some(types) = &schema.instance_type {
+			let types: Vec<_> = match types {
+				SingleOrVec::Single(ty) => vec![**ty],
+				SingleOrVec::Vec(types) => types.clone(),
+			};
+			parts.push(union(types.into_iter().map(|ty| self.type_of_instance(schema, ty, indent)).collect()));
+		}
+
+		if let Some(subschemas) = &schema.subschemas {
+			if let Some(all_of) = &subschemas.all_of {
+				parts.extend(all_of.iter().map(|schema| self.type_of(schema, indent)));
+			}
+			for alternatives in [&subschemas.any_of, &subschemas.one_of].into_iter().flatten() {
+				parts.push(union(alternatives.iter().map(|schema| self.type_of(schema, indent)).collect()));
+			}
+		}
+
+		match parts.len() {
+			0 => "unknown".to_owned(),
+			1 => parts.remove(0),
+			_ => parts.into_iter().map(|part| format!("({})", part)).collect::<Vec<_>>().join(" & "),
+		}
+	}
+
+	fn type_of_instance(&self, schema: &SchemaObject, ty: InstanceType, indent: &str) -> String {
+		match ty {
+			InstanceType::Null => "null".to_owned(),
+			InstanceType::Boolean => "boolean".to_owned(),
+			InstanceType::Number | InstanceType::Integer => "number".to_owned(),
+			InstanceType::String => "string".to_owned(),
+			InstanceType::Array => match schema.array.as_ref().and_then(|array| array.items.as_ref()) {
+				Some(SingleOrVec::Single(items)) => {
+					let items = self.type_of(items, indent);
+					if is_compound(&items) {
+						format!("({})[]", items)
+					} else {
+						format!("{}[]", items)
+					}
+				}
+				Some(SingleOrVec::Vec(items)) => {
+					let items: Vec<_> = items.iter().map(|item| self.type_of(item, indent)).collect();
+					format!("[{}]", items.join(", "))
+				}
+				None => "unknown[]".to_owned(),
+			},
+			InstanceType::Object => self.type_of_properties(schema, indent),
+		}
+	}
+
+	fn type_of_properties(&self, schema: &SchemaObject, indent: &str) -> String {
+		let object = match &schema.object {
+			Some(object) => object,
+			None => return "Record<string, unknown>".to_owned(),
+		};
+
+		if object.properties.is_empty() {
+			return match &object.additional_properties {
+				Some(values) => format!("Record<string, {}>", self.type_of(values, indent)),
+				None => "Record<string, unknown>".to_owned(),
+			};
+		}
+
+		let inner = format!("{}{}", indent, INDENT);
+		let mut out = String::from("{\n");
+		for (name, property) in &object.properties {
+			write_doc(&mut out, description(property), &inner);
+			let optional = if object.required.contains(name) { "" } else { "?" };
+			let _ = writeln!(out, "{}{}{}: {};", inner, property_name(name), optional, self.type_of(property, &inner));
+		}
+		let _ = write!(out, "{}}}", indent);
+		out
+	}
+}
+
+fn description(schema: &Schema) -> Option<&str> {
+	match schema {
+		Schema::Object(object) => object.metadata.as_ref()?.description.as_deref(),
+		Schema::Bool(_) => None,
+	}
+}
+
+fn write_doc(out: &mut String, doc: Option<&str>, indent: &str) {
+	let doc = match doc {
+		Some(doc) => doc.replace("*/", "*\\/"),
+		None => return,
+	};
+
+	let _ = writeln!(out, "{}/**", indent);
+	for line in doc.lines() {
+		let _ = writeln!(out, "{} *{}{}", indent, if line.is_empty() { "" } else { " " }, line);
+	}
+	let _ = writeln!(out, "{} */", indent);
+}
+
+/// Returns whether `ty` is a union or an intersection, which must be parenthesized to be an array item.
+fn is_compound(ty: &str) -> bool {
+	let mut depth = 0;
+	let mut in_string = false;
+	let mut escaped = false;
+
+	for c in ty.chars() {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' if in_string => escaped = true,
+			'"' => in_string = !in_string,
+			_ if in_string => (),
+			'(' | '[' | '{' | '<' => depth += 1,
+			')' | ']' | '}' | '>' => depth -= 1,
+			'|' | '&' if depth == 0 => return true,
+			_ => (),
+		}
+	}
+
+	false
+}
+
+fn union(mut types: Vec<String>) -> String {
+	types.dedup();
+	types.join(" | ")
+}
+
+/// Returns `name` with the characters that aren't valid in identifiers replaced by `_`.
+fn identifier(name: &str) -> String {
+	let mut identifier: String =
+		name.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '$' { c } else { '_' }).collect();
+	if identifier.starts_with(|c: char| c.is_ascii_digit()) || identifier.is_empty() {
+		identifier.insert(0, '_');
+	}
+	identifier
+}
+
+/// Returns the name of the method of the client calling the RPC method `name`, in camel case.
+fn method_name(name: &str) -> String {
+	let mut words = name.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty());
+	let mut out = words.next().map(identifier).unwrap_or_else(|| "_".to_owned());
+	for word in words {
+		let mut chars = word.chars();
+		if let Some(first) = chars.next() {
+			out.extend(first.to_uppercase());
+			out.push_str(chars.as_str());
+		}
+	}
+	out
+}
+
+fn property_name(name: &str) -> String {
+	if identifier(name) == name {
+		name.to_owned()
+	} else {
+		JsonValue::from(name).to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::server::schema::ContentDescriptor;
+	use schemars::JsonSchema;
+
+	/// A block of the chain.
+	#[derive(JsonSchema)]
+	#[allow(dead_code)]
+	struct Block {
+		/// Height of the block.
+		number: u64,
+		parent: Option<String>,
+		txs: Vec<(String, u32)>,
+		#[serde(rename = "fee-rate")]
+		fee_rate: f64,
+	}
+
+	#[derive(JsonSchema)]
+	#[allow(dead_code)]
+	enum Mode {
+		Full,
+		Light,
+	}
+
+	fn schemas() -> Schemas {
+		let mut schemas = Schemas::new();
+		let method = MethodSchema {
+			name: "chain_getBlock".into(),
+			description: Some("Returns a block.".into()),
+			params: vec![
+				ContentDescriptor {
+					name: "number".into(),
+					required: false,
+					schema: schemas.schema_for::<Option<u64>>(),
+				},
+				ContentDescriptor { name: "mode".into(), required: true, schema: schemas.schema_for::<Mode>() },
+				ContentDescriptor {
+					name: "verbose".into(),
+					required: false,
+					schema: schemas.schema_for::<Option<bool>>(),
+				},
+			],
+			result: ContentDescriptor {
+				name: "result".into(),
+				required: true,
+				schema: schemas.schema_for::<Option<Block>>(),
+			},
+			deprecated: true,
+		};
+		schemas.add_method(method);
+		schemas
+	}
+
+	#[test]
+	fn client_is_generated() {
+		let client = generate(&schemas(), "ChainClient");
+
+		assert!(client.contains(
+			"/**\n * A block of the chain.\n */\nexport type Block = {\n  \"fee-rate\": number;\n  /**\n   * Height of the block.\n   */\n  number: number;\n  parent?: string | null;\n  txs: [string, number][];\n};\n"
+		));
+		assert!(client.contains("export type Mode = \"Full\" | \"Light\";\n"));
+		assert!(
+			client.contains("export class ChainClient {\n  constructor(private readonly transport: Transport) {}\n")
+		);
+		assert!(client.contains("  /**\n   * Returns a block.\n   *\n   * @deprecated\n   */\n"));
+		assert!(client.contains(
+			"  chainGetBlock(number: number | null, mode: Mode, verbose?: boolean | null): Promise<Block | null> {\n    return this.transport.request(\"chain_getBlock\", [number, mode, verbose]) as Promise<Block | null>;\n  }\n"
+		));
+	}
+
+	#[test]
+	fn names_are_sanitized() {
+		assert_eq!(method_name("rpc.discover"), "rpcDiscover");
+		assert_eq!(method_name("calc_add"), "calcAdd");
+		assert_eq!(identifier("Wrapper<u8>"), "Wrapper_u8_");
+		assert_eq!(identifier("2fa"), "_2fa");
+		assert_eq!(property_name("fee-rate"), "\"fee-rate\"");
+		assert!(is_compound("string | null"));
+		assert!(!is_compound("[string | null, number]"));
+		assert!(!is_compound("\"a|b\""));
+	}
+}
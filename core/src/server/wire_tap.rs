@@ -0,0 +1,298 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Wire Tap
+//!
+//! This module handles capturing the raw frames exchanged with clients, to debug interoperability issues
+//! without having to capture the traffic with external tools and decrypt it.
+//!
+//! A [`WireTap`] writes every frame as a line of JSON to a file or any other writer, before the servers parse
+//! inbound frames and after they serialized outbound ones: the bodies of HTTP requests and responses, and the
+//! data messages of WebSocket connections. Each line looks like:
+//!
+//! ```json
+//! {"ts":1650000000000,"session":3,"transport":"ws","peer":"127.0.0.1:51234","direction":"inbound","len":42,"truncated":false,"encoding":"utf8","data":"{\"jsonrpc\":\"2.0\",\"method\":\"say_hello\",\"id\":1}"}
+//! ```
+//!
+//! where `session` groups the frames of a connection and frames that aren't valid UTF-8 are hex encoded.
+//!
+//! Connections are sampled when they're opened, either all of their frames are captured or none of them.
+//! Frames longer than [`WireTap::max_frame_len`] are truncated, `len` always is the size of the whole frame.
+//!
+//! Frames are written synchronously while they're processed so the tap should only be enabled while debugging.
+//!
+//! ```no_run
+//! use jsonrpsee_core::server::wire_tap::WireTap;
+//!
+//! // Capture one in ten connections, keeping the first kilobyte of each frame.
+//! let tap = WireTap::to_file("/tmp/jsonrpsee-wire.jsonl").unwrap().sample_rate(0.1).max_frame_len(1024);
+//! ```
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+/// Default number of bytes of each frame that are captured.
+const DEFAULT_MAX_FRAME_LEN: usize = 4096;
+
+/// Direction of a captured frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+	/// Frame received from the client.
+	Inbound,
+	/// Frame sent to the client.
+	Outbound,
+}
+
+/// Captures the raw frames of a sample of connections.
+///
+/// By default no writer is configured and nothing is captured.
+#[derive(Clone, Default)]
+pub struct WireTap {
+	inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+	writer: Mutex<Box<dyn Write + Send>>,
+	sample_rate: f64,
+	max_frame_len: usize,
+	next_session: AtomicU64,
+}
+
+impl WireTap {
+	/// Create a wire tap writing the captured frames to `writer`, one JSON object per line.
+	pub fn new(writer: impl Write + Send + 'static) -> Self {
+		Self {
+			inner: Some(Arc::new(Inner {
+				writer: Mutex::new(Box::new(writer)),
+				sample_rate: 1.0,
+				max_frame_len: DEFAULT_MAX_FRAME_LEN,
+				next_session: AtomicU64::new(0),
+			})),
+		}
+	}
+
+	/// Create a wire tap appending the captured frames to the file at `path`, which is created if needed.
+	pub fn to_file(path: impl AsRef<Path>) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		Ok(Self::new(BufWriter::new(file)))
+	}
+
+	/// Set the fraction of connections that are captured, between `0.0` and `1.0` (default is `1.0`).
+	pub fn sample_rate(mut self, rate: f64) -> Self {
+		if let Some(inner) = self.inner.as_mut().and_then(Arc::get_mut) {
+			inner.sample_rate = rate.clamp(0.0, 1.0);
+		}
+		self
+	}
+
+	/// Set the number of bytes of each frame that are captured (default is 4096).
+	pub fn max_frame_len(mut self, len: usize) -> Self {
+		if let Some(inner) = self.inner.as_mut().and_then(Arc::get_mut) {
+			inner.max_frame_len = len;
+		}
+		self
+	}
+
+	/// Sample a new connection over `transport` with `peer`, returns `None` if its frames must not be captured.
+	pub fn session(&self, transport: &'static str, peer: SocketAddr) -> Option<WireTapSession> {
+		let inner = self.inner.as_ref()?;
+
+		if inner.sample_rate < 1.0 && rand::random::<f64>() >= inner.sample_rate {
+			return None;
+		}
+
+		let id = inner.next_session.fetch_add(1, Ordering::Relaxed);
+		Some(WireTapSession { inner: inner.clone(), id, transport, peer })
+	}
+}
+
+impl fmt::Debug for WireTap {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.inner.as_ref() {
+			Some(inner) => f
+				.debug_struct("WireTap")
+				.field("sample_rate", &inner.sample_rate)
+				.field("max_frame_len", &inner.max_frame_len)
+				.finish(),
+			None => f.write_str("WireTap(disabled)"),
+		}
+	}
+}
+
+/// Captures the frames of a sampled connection, see [`WireTap::session`].
+#[derive(Clone)]
+pub struct WireTapSession {
+	inner: Arc<Inner>,
+	id: u64,
+	transport: &'static str,
+	peer: SocketAddr,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+	ts: u128,
+	session: u64,
+	transport: &'a str,
+	peer: SocketAddr,
+	direction: Direction,
+	len: usize,
+	truncated: bool,
+	encoding: &'a str,
+	data: &'a str,
+}
+
+impl WireTapSession {
+	/// Capture a frame exchanged with the client.
+	pub fn record(&self, direction: Direction, frame: &[u8]) {
+		let truncated = frame.len() > self.inner.max_frame_len;
+		let captured = &frame[..frame.len().min(self.inner.max_frame_len)];
+
+		let hex;
+		let (encoding, data) = match std::str::from_utf8(captured) {
+			Ok(data) => ("utf8", data),
+			// Truncation may have split the last character.
+			Err(e) if truncated && e.error_len().is_none() => {
+				("utf8", std::str::from_utf8(&captured[..e.valid_up_to()]).expect("Valid up to here; qed"))
+			}
+			Err(_) => {
+				hex = captured.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+				("hex", hex.as_str())
+			}
+		};
+
+		let record = Record {
+			ts: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |ts| ts.as_millis()),
+			session: self.id,
+			transport: self.transport,
+			peer: self.peer,
+			direction,
+			len: frame.len(),
+			truncated,
+			encoding,
+			data,
+		};
+
+		let mut writer = self.inner.writer.lock();
+		let res = serde_json::to_writer(&mut *writer, &record)
+			.map_err(io::Error::from)
+			.and_then(|()| writer.write_all(b"\n"))
+			.and_then(|()| writer.flush());
+
+		if let Err(err) = res {
+			tracing::warn!("Failed to write captured frame: {}", err);
+		}
+	}
+}
+
+impl fmt::Debug for WireTapSession {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("WireTapSession")
+			.field("id", &self.id)
+			.field("transport", &self.transport)
+			.field("peer", &self.peer)
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::Value;
+
+	#[derive(Clone, Default)]
+	struct Capture(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for Capture {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl Capture {
+		fn records(&self) -> Vec<Value> {
+			let lines = self.0.lock();
+			lines.split(|&b| b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect()
+		}
+	}
+
+	fn peer() -> SocketAddr {
+		"127.0.0.1:1234".parse().unwrap()
+	}
+
+	#[test]
+	fn disabled_captures_nothing() {
+		assert!(WireTap::default().session("ws", peer()).is_none());
+	}
+
+	#[test]
+	fn frames_are_recorded() {
+		let capture = Capture::default();
+		let tap = WireTap::new(capture.clone()).max_frame_len(8);
+		let session = tap.session("http", peer()).unwrap();
+
+		session.record(Direction::Inbound, b"{\"id\":1}");
+		session.record(Direction::Outbound, "{\"ab\":\"é\"}".as_bytes());
+		session.record(Direction::Inbound, &[0xff, 0x00]);
+		tap.session("ws", peer()).unwrap().record(Direction::Inbound, b"[]");
+
+		let records = capture.records();
+		assert_eq!(records.len(), 4);
+		assert_eq!(records[0]["direction"], "inbound");
+		assert_eq!(records[0]["transport"], "http");
+		assert_eq!(records[0]["peer"], "127.0.0.1:1234");
+		assert_eq!(records[0]["data"], "{\"id\":1}");
+		assert_eq!(records[0]["truncated"], false);
+		// Truncated before the split character.
+		assert_eq!(records[1]["data"], "{\"ab\":\"");
+		assert_eq!(records[1]["len"], 11);
+		assert_eq!(records[1]["truncated"], true);
+		assert_eq!(records[2]["encoding"], "hex");
+		assert_eq!(records[2]["data"], "ff00");
+		assert_eq!(records[0]["session"], records[2]["session"]);
+		assert_ne!(records[0]["session"], records[3]["session"]);
+	}
+
+	#[test]
+	fn sampling_works() {
+		let tap = WireTap::new(io::sink()).sample_rate(0.0);
+		assert!((0..100).all(|_| tap.session("ws", peer()).is_none()));
+	}
+}
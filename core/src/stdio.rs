@@ -0,0 +1,191 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Framing of the JSON-RPC messages exchanged over a pair of pipes, such as the standard input and output of a
+//! process.
+//!
+//! Messages are either preceded by a `Content-Length` header as in the Language Server Protocol, or terminated
+//! by a newline:
+//!
+//! ```text
+//! Content-Length: 46\r\n
+//! \r\n
+//! {"jsonrpc":"2.0","method":"say_hello","id":1}
+//! ```
+
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Maximum length of a header line of the [`Framing::ContentLength`] framing.
+const MAX_HEADER_LEN: u64 = 1024;
+
+/// How messages are delimited on the pipes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+	/// Every message is preceded by a `Content-Length` header and an empty line, other headers are ignored.
+	#[default]
+	ContentLength,
+	/// Every message is terminated by a newline, messages must not contain newlines. Empty lines are ignored.
+	Newline,
+}
+
+/// Read the next message, returns `None` once the pipe is closed.
+///
+/// Fails with [`io::ErrorKind::InvalidData`] if the message is longer than `max_len` bytes or malformed, after
+/// skipping it so that the next message can be read.
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+	reader: &mut R,
+	framing: Framing,
+	max_len: usize,
+) -> io::Result<Option<Vec<u8>>> {
+	match framing {
+		Framing::ContentLength => read_content_length(reader, max_len).await,
+		Framing::Newline => read_line(reader, max_len).await,
+	}
+}
+
+async fn read_content_length<R: AsyncBufRead + Unpin>(reader: &mut R, max_len: usize) -> io::Result<Option<Vec<u8>>> {
+	let mut content_length = None;
+	let mut line = String::new();
+
+	loop {
+		line.clear();
+		if (&mut *reader).take(MAX_HEADER_LEN).read_line(&mut line).await? == 0 {
+			return Ok(None);
+		}
+
+		let header = line.trim_end_matches(['\r', '\n']);
+		if header.is_empty() {
+			// Stray newlines between messages.
+			if content_length.is_none() {
+				continue;
+			}
+			break;
+		}
+
+		if let Some((name, value)) = header.split_once(':') {
+			if name.trim().eq_ignore_ascii_case("content-length") {
+				content_length = value.trim().parse::<usize>().ok();
+			}
+		}
+	}
+
+	let len = content_length.expect("set before the end of the headers; qed");
+	if len > max_len {
+		tokio::io::copy(&mut (&mut *reader).take(len as u64), &mut tokio::io::sink()).await?;
+		return Err(too_large(max_len));
+	}
+
+	let mut message = vec![0; len];
+	reader.read_exact(&mut message).await?;
+	Ok(Some(message))
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, max_len: usize) -> io::Result<Option<Vec<u8>>> {
+	let mut message = Vec::new();
+
+	loop {
+		message.clear();
+		let read = (&mut *reader).take(max_len as u64 + 1).read_until(b'\n', &mut message).await?;
+		if read == 0 {
+			return Ok(None);
+		}
+
+		if message.last() != Some(&b'\n') && message.len() > max_len {
+			// Skip the rest of the line.
+			let mut rest = Vec::new();
+			reader.read_until(b'\n', &mut rest).await?;
+			return Err(too_large(max_len));
+		}
+
+		let len = message.trim_ascii_end().len();
+		if len > 0 {
+			message.truncate(len);
+			return Ok(Some(message));
+		}
+	}
+}
+
+fn too_large(max_len: usize) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, format!("Message exceeds the maximum length of {} bytes", max_len))
+}
+
+/// Write `message` and flush the pipe.
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, framing: Framing, message: &[u8]) -> io::Result<()> {
+	match framing {
+		Framing::ContentLength => {
+			writer.write_all(format!("Content-Length: {}\r\n\r\n", message.len()).as_bytes()).await?;
+			writer.write_all(message).await?;
+		}
+		Framing::Newline => {
+			writer.write_all(message).await?;
+			writer.write_all(b"\n").await?;
+		}
+	}
+	writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{read_message, write_message, Framing};
+	use std::io::ErrorKind;
+
+	async fn roundtrip(framing: Framing, input: &[u8], max_len: usize) -> Vec<Result<Vec<u8>, ErrorKind>> {
+		let mut reader = input;
+		let mut messages = Vec::new();
+		loop {
+			match read_message(&mut reader, framing, max_len).await {
+				Ok(Some(message)) => messages.push(Ok(message)),
+				Ok(None) => return messages,
+				Err(err) => messages.push(Err(err.kind())),
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn content_length_framing() {
+		let mut buf = Vec::new();
+		write_message(&mut buf, Framing::ContentLength, br#"{"id":1}"#).await.unwrap();
+		assert_eq!(buf, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+
+		buf.extend_from_slice(b"\r\ncontent-type: application/json\r\nCONTENT-LENGTH: 10\r\n\r\n[{\"id\":2}]");
+		buf.extend_from_slice(b"Content-Length: 2\r\n\r\n{}");
+		let messages = roundtrip(Framing::ContentLength, &buf, 8).await;
+		assert_eq!(messages, [Ok(br#"{"id":1}"#.to_vec()), Err(ErrorKind::InvalidData), Ok(b"{}".to_vec())]);
+	}
+
+	#[tokio::test]
+	async fn newline_framing() {
+		let mut buf = Vec::new();
+		write_message(&mut buf, Framing::Newline, br#"{"id":1}"#).await.unwrap();
+		assert_eq!(buf, b"{\"id\":1}\n");
+
+		buf.extend_from_slice(b"\r\n[{\"id\":2}]\r\n{}");
+		let messages = roundtrip(Framing::Newline, &buf, 8).await;
+		assert_eq!(messages, [Ok(br#"{"id":1}"#.to_vec()), Err(ErrorKind::InvalidData), Ok(b"{}".to_vec())]);
+	}
+}
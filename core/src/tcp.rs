@@ -0,0 +1,273 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Tuning of TCP sockets shared by the servers and clients.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+
+/// Size of the queue of pending connections of listening sockets.
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// TCP keepalive parameters.
+///
+/// Parameters left to `None` use the defaults of the operating system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpKeepalive {
+	/// Idle time before the first keepalive probe is sent (`TCP_KEEPIDLE`).
+	pub time: Option<Duration>,
+	/// Time between keepalive probes (`TCP_KEEPINTVL`), ignored on platforms that don't support it.
+	pub interval: Option<Duration>,
+	/// Number of unanswered probes before the connection is dropped (`TCP_KEEPCNT`), ignored on platforms
+	/// that don't support it.
+	pub retries: Option<u32>,
+}
+
+impl TcpKeepalive {
+	/// Create keepalive parameters sending the first probe after `time` of idleness.
+	pub fn new(time: Duration) -> Self {
+		Self { time: Some(time), ..Default::default() }
+	}
+
+	/// Set the time between keepalive probes.
+	pub fn interval(mut self, interval: Duration) -> Self {
+		self.interval = Some(interval);
+		self
+	}
+
+	/// Set the number of unanswered probes before the connection is dropped.
+	pub fn retries(mut self, retries: u32) -> Self {
+		self.retries = Some(retries);
+		self
+	}
+
+	fn to_socket2(self) -> socket2::TcpKeepalive {
+		let mut params = socket2::TcpKeepalive::new();
+		if let Some(time) = self.time {
+			params = params.with_time(time);
+		}
+		#[cfg(any(
+			target_os = "android",
+			target_os = "dragonfly",
+			target_os = "freebsd",
+			target_os = "fuchsia",
+			target_os = "illumos",
+			target_os = "linux",
+			target_os = "netbsd",
+			target_vendor = "apple",
+			windows,
+		))]
+		if let Some(interval) = self.interval {
+			params = params.with_interval(interval);
+		}
+		#[cfg(any(
+			target_os = "android",
+			target_os = "dragonfly",
+			target_os = "freebsd",
+			target_os = "fuchsia",
+			target_os = "illumos",
+			target_os = "linux",
+			target_os = "netbsd",
+			target_vendor = "apple",
+		))]
+		if let Some(retries) = self.retries {
+			params = params.with_retries(retries);
+		}
+		params
+	}
+}
+
+/// Options applied to TCP sockets.
+///
+/// Options left to `None` keep the value set by the operating system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpSettings {
+	/// Whether `TCP_NODELAY` is set, i.e. Nagle's algorithm is disabled.
+	pub nodelay: Option<bool>,
+	/// TCP keepalive parameters, keepalive is enabled if set.
+	pub keepalive: Option<TcpKeepalive>,
+	/// Size of the send buffer (`SO_SNDBUF`).
+	pub send_buffer_size: Option<usize>,
+	/// Size of the receive buffer (`SO_RCVBUF`).
+	pub recv_buffer_size: Option<usize>,
+	/// Linger on close (`SO_LINGER`), `Some(None)` disables lingering.
+	pub linger: Option<Option<Duration>>,
+}
+
+impl TcpSettings {
+	/// Apply the configured options to `socket`.
+	pub fn apply<S>(&self, socket: &S) -> io::Result<()>
+	where
+		for<'s> SockRef<'s>: From<&'s S>,
+	{
+		let socket = SockRef::from(socket);
+
+		if let Some(nodelay) = self.nodelay {
+			socket.set_nodelay(nodelay)?;
+		}
+		if let Some(keepalive) = self.keepalive {
+			socket.set_tcp_keepalive(&keepalive.to_socket2())?;
+		}
+		self.apply_buffers(&socket)
+	}
+
+	/// Apply the options that a listening socket passes on to the connections it accepts on most platforms,
+	/// that is the buffer sizes and linger.
+	pub fn apply_to_listener<S>(&self, listener: &S) -> io::Result<()>
+	where
+		for<'s> SockRef<'s>: From<&'s S>,
+	{
+		self.apply_buffers(&SockRef::from(listener))
+	}
+
+	fn apply_buffers(&self, socket: &SockRef) -> io::Result<()> {
+		if let Some(size) = self.send_buffer_size {
+			socket.set_send_buffer_size(size)?;
+		}
+		if let Some(size) = self.recv_buffer_size {
+			socket.set_recv_buffer_size(size)?;
+		}
+		if let Some(linger) = self.linger {
+			socket.set_linger(linger)?;
+		}
+		Ok(())
+	}
+}
+
+/// Options controlling to which addresses a server binds its listening sockets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BindSettings {
+	/// Whether `IPV6_V6ONLY` is set on IPv6 sockets, `Some(false)` makes a socket bound to `[::]` accept
+	/// IPv4 connections as well (dual-stack). `None` keeps the operating system default.
+	pub ipv6_only: Option<bool>,
+	/// Whether a separate socket is bound to every address rather than only to the first one that succeeds.
+	pub all_addresses: bool,
+}
+
+impl BindSettings {
+	/// Bind non-blocking listening sockets to `addrs` according to the settings.
+	///
+	/// Unless [`BindSettings::all_addresses`] is set, the addresses are tried in order and the first successfully
+	/// bound socket is returned, otherwise the error of the last attempt is returned.
+	pub fn bind(&self, addrs: impl IntoIterator<Item = SocketAddr>) -> io::Result<Vec<TcpListener>> {
+		let mut listeners = Vec::new();
+		let mut last_err = None;
+
+		for addr in addrs {
+			match self.bind_one(addr) {
+				Ok(listener) if self.all_addresses => listeners.push(listener),
+				Ok(listener) => return Ok(vec![listener]),
+				Err(err) if self.all_addresses => return Err(err),
+				Err(err) => last_err = Some(err),
+			}
+		}
+
+		if listeners.is_empty() {
+			Err(last_err
+				.unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "could not resolve to any address")))
+		} else {
+			Ok(listeners)
+		}
+	}
+
+	fn bind_one(&self, addr: SocketAddr) -> io::Result<TcpListener> {
+		let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+		if let (true, Some(ipv6_only)) = (addr.is_ipv6(), self.ipv6_only) {
+			socket.set_only_v6(ipv6_only)?;
+		}
+		// Matches the behavior of `tokio::net::TcpListener::bind`.
+		#[cfg(not(windows))]
+		socket.set_reuse_address(true)?;
+		socket.set_nonblocking(true)?;
+		socket.bind(&addr.into())?;
+		socket.listen(LISTEN_BACKLOG)?;
+		Ok(socket.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BindSettings, TcpKeepalive, TcpSettings};
+	use socket2::SockRef;
+	use std::net::{TcpListener, TcpStream};
+	use std::time::Duration;
+
+	#[test]
+	fn settings_are_applied() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+		let settings = TcpSettings {
+			nodelay: Some(true),
+			keepalive: Some(TcpKeepalive::new(Duration::from_secs(30))),
+			linger: Some(Some(Duration::from_secs(1))),
+			..Default::default()
+		};
+		settings.apply(&stream).unwrap();
+
+		let socket = SockRef::from(&stream);
+		assert!(socket.nodelay().unwrap());
+		assert!(socket.keepalive().unwrap());
+		assert_eq!(socket.linger().unwrap(), Some(Duration::from_secs(1)));
+	}
+
+	#[test]
+	fn default_settings_leave_socket_untouched() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let nodelay = SockRef::from(&stream).nodelay().unwrap();
+
+		TcpSettings::default().apply(&stream).unwrap();
+
+		assert_eq!(SockRef::from(&stream).nodelay().unwrap(), nodelay);
+	}
+
+	#[test]
+	fn binds_first_or_all_addresses() {
+		let occupied = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addrs = [occupied.local_addr().unwrap(), "127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+
+		let listeners = BindSettings::default().bind(addrs).unwrap();
+		assert_eq!(listeners.len(), 1);
+
+		assert!(BindSettings { all_addresses: true, ..Default::default() }.bind(addrs).is_err());
+		let listeners = BindSettings { all_addresses: true, ..Default::default() }.bind(addrs[1..].to_vec()).unwrap();
+		assert_eq!(listeners.len(), 2);
+
+		assert!(BindSettings::default().bind(Vec::new()).is_err());
+	}
+
+	#[test]
+	fn ipv6_only_is_applied() {
+		let settings = BindSettings { ipv6_only: Some(true), ..Default::default() };
+		// IPv6 may be unavailable in the test environment.
+		if let Ok(listeners) = settings.bind(["[::1]:0".parse().unwrap()]) {
+			assert!(SockRef::from(&listeners[0]).only_v6().unwrap());
+		}
+	}
+}
@@ -41,6 +41,13 @@ pub fn tx_log_from_str(s: impl AsRef<str>, max: u32) {
 	}
 }
 
+/// Helper for writing trace logs from bytes.
+pub fn tx_log_from_bytes(bytes: &[u8], max: u32) {
+	if tracing::enabled!(Level::TRACE) {
+		tx_log_from_str(String::from_utf8_lossy(bytes), max);
+	}
+}
+
 /// Helper for writing trace logs from JSON.
 pub fn tx_log_from_json(s: &impl Serialize, max: u32) {
 	if tracing::enabled!(Level::TRACE) {
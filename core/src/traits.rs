@@ -86,3 +86,16 @@ impl<T: IdProvider + ?Sized> IdProvider for Box<T> {
 		(**self).next_id()
 	}
 }
+
+/// Trait to execute method handlers on threads that are not managed by tokio, such as a dedicated thread pool.
+pub trait Executor: Send + Sync + 'static {
+	/// Run `task` on one of the threads of the executor.
+	fn execute(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+// Implement `Executor` for closures such as `|task| rayon::spawn(task)`.
+impl<F: Fn(Box<dyn FnOnce() + Send>) + Send + Sync + 'static> Executor for F {
+	fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+		self(task)
+	}
+}
@@ -0,0 +1,143 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client talking to an [`RpcModule`] of the same process through a custom in-memory transport.
+//!
+//! Any type implementing [`TransportSenderT`] and [`TransportReceiverT`] can be given to the [`ClientBuilder`],
+//! which takes care of the request ids, the batches and the subscriptions.
+
+use std::fmt;
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+use jsonrpsee::core::async_trait;
+use jsonrpsee::core::client::{
+	Client, ClientBuilder, ClientT, ReceivedMessage, SubscriptionClientT, TransportReceiverT, TransportSenderT,
+};
+use jsonrpsee::ws_server::RpcModule;
+use tokio::time::interval;
+use tokio_stream::wrappers::IntervalStream;
+
+#[derive(Debug)]
+struct Disconnected;
+
+impl fmt::Display for Disconnected {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("the in-memory server is gone")
+	}
+}
+
+impl std::error::Error for Disconnected {}
+
+struct Sender(mpsc::UnboundedSender<String>);
+
+#[async_trait]
+impl TransportSenderT for Sender {
+	type Error = Disconnected;
+
+	async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+		self.0.unbounded_send(msg).map_err(|_| Disconnected)
+	}
+
+	async fn send_ping(&mut self) -> Result<(), Self::Error> {
+		// There is no connection to keep alive.
+		Ok(())
+	}
+}
+
+struct Receiver(mpsc::UnboundedReceiver<String>);
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+	type Error = Disconnected;
+
+	async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+		self.0.next().await.map(ReceivedMessage::Text).ok_or(Disconnected)
+	}
+}
+
+/// Serves the calls of the client with `module`. Batches aren't supported by [`RpcModule::raw_json_request`].
+fn in_memory_transport(module: RpcModule<()>) -> (Sender, Receiver) {
+	let (to_server, mut requests) = mpsc::unbounded::<String>();
+	let (to_client, responses) = mpsc::unbounded();
+
+	tokio::spawn(async move {
+		while let Some(request) = requests.next().await {
+			let (response, mut notifications) = match module.raw_json_request(&request).await {
+				Ok(response) => response,
+				Err(err) => {
+					tracing::warn!("invalid request: {:?}", err);
+					continue;
+				}
+			};
+			let _ = to_client.unbounded_send(String::from_utf8_lossy(&response).into_owned());
+
+			// Subscriptions send their notifications through their own channel.
+			let to_client = to_client.clone();
+			tokio::spawn(async move {
+				while let Some(notification) = notifications.next().await {
+					if to_client.unbounded_send(String::from_utf8_lossy(&notification).into_owned()).is_err() {
+						break;
+					}
+				}
+			});
+		}
+	});
+
+	(Sender(to_server), Receiver(responses))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	tracing_subscriber::FmtSubscriber::builder()
+		.with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+		.try_init()
+		.expect("setting default subscriber failed");
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo"))?;
+	module.register_subscription("subscribe_hello", "s_hello", "unsubscribe_hello", |_, mut sink, _| {
+		let stream = IntervalStream::new(interval(Duration::from_millis(100))).map(|_| "hello");
+		tokio::spawn(async move {
+			sink.pipe_from_stream(stream).await;
+		});
+		Ok(())
+	})?;
+
+	let (tx, rx) = in_memory_transport(module);
+	let client: Client = ClientBuilder::default().build_with_tokio(tx, rx);
+
+	let response: String = client.request("say_hello", None).await?;
+	tracing::info!("response: {:?}", response);
+
+	let mut sub = client.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await?;
+	for _ in 0..3 {
+		tracing::info!("notification: {:?}", sub.next().await);
+	}
+
+	Ok(())
+}
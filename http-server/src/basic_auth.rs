@@ -0,0 +1,167 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! HTTP Basic authentication compatible with `bitcoin-cli` style clients.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+/// User name of the credentials stored in a cookie file.
+pub const COOKIE_USER: &str = "__cookie__";
+
+/// Length of the randomly generated cookie password.
+const COOKIE_PASSWORD_LEN: usize = 64;
+
+/// Credentials accepted by the server via HTTP Basic authentication.
+///
+/// Credentials can either be configured statically, similar to `rpcuser`/`rpcpassword`, or generated
+/// at startup and written to a cookie file that local clients read to authenticate.
+///
+/// ```no_run
+/// use jsonrpsee_http_server::BasicAuth;
+///
+/// let auth = BasicAuth::new().user("alice", "hunter2").cookie_file("/var/lib/node/.cookie").unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct BasicAuth {
+	/// Accepted `user:password` pairs.
+	credentials: Arc<Vec<String>>,
+	/// Path of the generated cookie file.
+	cookie_path: Option<PathBuf>,
+}
+
+impl BasicAuth {
+	/// Create a new [`BasicAuth`] without any accepted credentials.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Accept the given user name and password.
+	pub fn user(mut self, user: impl AsRef<str>, password: impl AsRef<str>) -> Self {
+		Arc::make_mut(&mut self.credentials).push(format!("{}:{}", user.as_ref(), password.as_ref()));
+		self
+	}
+
+	/// Generate a random password for the user [`COOKIE_USER`] and write the credentials to `path`
+	/// in the `user:password` format, overwriting any previous cookie.
+	///
+	/// The file is readable only by the owner on unix platforms.
+	pub fn cookie_file(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+		let password: String =
+			rand::thread_rng().sample_iter(&Alphanumeric).take(COOKIE_PASSWORD_LEN).map(char::from).collect();
+		let cookie = format!("{}:{}", COOKIE_USER, password);
+
+		write_private(path.as_ref(), cookie.as_bytes())?;
+		Arc::make_mut(&mut self.credentials).push(cookie);
+		self.cookie_path = Some(path.as_ref().to_path_buf());
+		Ok(self)
+	}
+
+	/// Returns the path of the generated cookie file if any.
+	pub fn cookie_path(&self) -> Option<&Path> {
+		self.cookie_path.as_deref()
+	}
+
+	/// Returns whether the value of an `Authorization` header carries accepted credentials.
+	pub(crate) fn verify(&self, authorization: Option<&str>) -> bool {
+		let encoded = match authorization.and_then(|value| value.strip_prefix("Basic ")) {
+			Some(encoded) => encoded.trim(),
+			None => return false,
+		};
+
+		match base64::decode(encoded) {
+			Ok(decoded) => self.credentials.iter().any(|c| constant_time_eq(c.as_bytes(), &decoded)),
+			Err(_) => false,
+		}
+	}
+}
+
+impl fmt::Debug for BasicAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Don't leak the credentials to the logs.
+		f.debug_struct("BasicAuth")
+			.field("credentials", &self.credentials.len())
+			.field("cookie_path", &self.cookie_path)
+			.finish()
+	}
+}
+
+/// Compare two byte strings in constant time to not reveal the credentials via timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+	use std::io::Write;
+	use std::os::unix::fs::OpenOptionsExt;
+
+	let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+	file.write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+	fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BasicAuth, COOKIE_USER};
+
+	fn header(credentials: &str) -> String {
+		format!("Basic {}", base64::encode(credentials))
+	}
+
+	#[test]
+	fn static_credentials_work() {
+		let auth = BasicAuth::new().user("alice", "hunter2");
+
+		assert!(auth.verify(Some(&header("alice:hunter2"))));
+		assert!(!auth.verify(Some(&header("alice:hunter3"))));
+		assert!(!auth.verify(Some("Basic not-base64")));
+		assert!(!auth.verify(Some("Bearer alice:hunter2")));
+		assert!(!auth.verify(None));
+	}
+
+	#[test]
+	fn cookie_file_works() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-cookie-{}", std::process::id()));
+		let auth = BasicAuth::new().cookie_file(&path).unwrap();
+		let cookie = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert!(cookie.starts_with(COOKIE_USER));
+		assert!(auth.verify(Some(&header(&cookie))));
+		assert!(!auth.verify(Some(&header(&format!("{}:wrong", COOKIE_USER)))));
+		assert_eq!(auth.cookie_path(), Some(path.as_path()));
+	}
+}
@@ -0,0 +1,396 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Compression of response bodies negotiated with the `Accept-Encoding` header, and decompression of request
+//! bodies sent with a `Content-Encoding` header.
+
+use std::io::{self, Write};
+
+use bytes::Bytes;
+use flate2::write::{GzDecoder, GzEncoder, ZlibEncoder};
+use futures_util::stream::StreamExt;
+use hyper::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, VARY};
+use jsonrpsee_core::compression::CompressionStats;
+use jsonrpsee_core::error::GenericTransportError;
+use jsonrpsee_core::http_helpers::read_header_value;
+
+/// Responses smaller than this are sent uncompressed, unless configured otherwise.
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// Content encodings of the request bodies the server decompresses, see [`is_gzip_body`].
+pub(crate) const REQUEST_ENCODINGS: &[&str] = &["gzip"];
+
+/// Content encodings the responses may be compressed with, see [`ResponseCompression`].
+pub(crate) const RESPONSE_ENCODINGS: &[&str] = &["gzip", "deflate"];
+
+/// Compression of the response bodies, negotiated with the `Accept-Encoding` header of every request.
+///
+/// Supports `gzip` and `deflate`, `gzip` is preferred when the client accepts both equally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCompression {
+	min_size: usize,
+	level: u32,
+	min_savings: u8,
+}
+
+impl Default for ResponseCompression {
+	fn default() -> Self {
+		Self { min_size: DEFAULT_MIN_SIZE, level: flate2::Compression::default().level(), min_savings: 0 }
+	}
+}
+
+impl ResponseCompression {
+	/// Create a new [`ResponseCompression`] with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the size in bytes below which responses are sent uncompressed (default is 1 KiB), because compressing
+	/// small responses costs more than it saves.
+	pub fn min_size(mut self, min_size: usize) -> Self {
+		self.min_size = min_size;
+		self
+	}
+
+	/// Set the compression level, from 0 (fastest) to 9 (smallest) (default is 6).
+	///
+	/// Values above 9 are capped to it.
+	pub fn level(mut self, level: u32) -> Self {
+		self.level = level.min(9);
+		self
+	}
+
+	/// Stop compressing the responses sent over a connection once compressing them saved less than `percent`
+	/// percent of their size, checked from the [`SAMPLE_SIZE`](jsonrpsee_core::compression::SAMPLE_SIZE)th compressed response on (default is 0, never
+	/// stop). Compressing responses that barely shrink only wastes CPU.
+	///
+	/// Values above 100 are capped to it.
+	pub fn min_savings(mut self, percent: u8) -> Self {
+		self.min_savings = percent.min(100);
+		self
+	}
+
+	/// Create the compression statistics of a connection.
+	pub(crate) fn stats(&self) -> CompressionStats {
+		CompressionStats::new(self.min_savings)
+	}
+
+	/// Compress `body` with the encoding preferred by the client that sent `request_headers`, if any, and
+	/// record it in the `stats` of the connection. Bodies sent over connections for which compression was
+	/// disabled are not compressed.
+	///
+	/// Returns the body to send and the headers to add to the response.
+	pub(crate) fn compress(
+		&self,
+		request_headers: &HeaderMap,
+		body: Bytes,
+		stats: &CompressionStats,
+	) -> (Bytes, HeaderMap) {
+		let mut headers = HeaderMap::new();
+		// The representation depends on the request headers, caches must take them into account.
+		headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+
+		let encoding = match negotiate(request_headers) {
+			Some(encoding) if body.len() >= self.min_size && stats.is_enabled() => encoding,
+			_ => return (body, headers),
+		};
+
+		let level = flate2::Compression::new(self.level);
+		let compressed = match encoding {
+			Encoding::Gzip => {
+				let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), level);
+				encoder.write_all(&body).and_then(|_| encoder.finish())
+			}
+			Encoding::Deflate => {
+				let mut encoder = ZlibEncoder::new(Vec::with_capacity(body.len() / 4), level);
+				encoder.write_all(&body).and_then(|_| encoder.finish())
+			}
+		}
+		.expect("writing to a Vec never fails; qed");
+		stats.set_negotiated(encoding.as_str());
+		stats.record(body.len(), compressed.len());
+
+		// Incompressible bodies are sent as they are.
+		if compressed.len() >= body.len() {
+			return (body, headers);
+		}
+
+		headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+		(compressed.into(), headers)
+	}
+}
+
+/// Content encoding of a response body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+	Gzip,
+	/// The zlib format, which is what HTTP calls `deflate`.
+	Deflate,
+}
+
+impl Encoding {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Gzip => "gzip",
+			Self::Deflate => "deflate",
+		}
+	}
+}
+
+/// Returns the supported encoding with the highest quality value in the `Accept-Encoding` headers, if any.
+fn negotiate(request_headers: &HeaderMap) -> Option<Encoding> {
+	let (mut gzip, mut deflate, mut any) = (None, None, None);
+
+	for value in request_headers.get_all(ACCEPT_ENCODING).iter().filter_map(|value| value.to_str().ok()) {
+		for coding in value.split(',') {
+			let mut parts = coding.split(';').map(str::trim);
+			let name = parts.next().unwrap_or_default();
+			let quality = parts
+				.find_map(|param| param.strip_prefix("q=").or_else(|| param.strip_prefix("Q=")))
+				.map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+
+			let slot = if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+				&mut gzip
+			} else if name.eq_ignore_ascii_case("deflate") {
+				&mut deflate
+			} else if name == "*" {
+				&mut any
+			} else {
+				continue;
+			};
+
+			if let Some(quality) = quality {
+				*slot = Some(quality);
+			}
+		}
+	}
+
+	let gzip = gzip.or(any).unwrap_or(0.0);
+	let deflate = deflate.or(any).unwrap_or(0.0);
+
+	if gzip > 0.0 && gzip >= deflate {
+		Some(Encoding::Gzip)
+	} else if deflate > 0.0 {
+		Some(Encoding::Deflate)
+	} else {
+		None
+	}
+}
+
+/// Returns whether the body of a request is compressed with gzip according to its `Content-Encoding` header.
+///
+/// Fails if the body is compressed with an unsupported encoding.
+pub(crate) fn is_gzip_body(request_headers: &HeaderMap) -> Result<bool, ()> {
+	if !request_headers.contains_key(CONTENT_ENCODING) {
+		return Ok(false);
+	}
+
+	match read_header_value(request_headers, CONTENT_ENCODING.as_str()).map(str::trim) {
+		Some(encoding) if encoding.eq_ignore_ascii_case("gzip") || encoding.eq_ignore_ascii_case("x-gzip") => Ok(true),
+		Some(encoding) if encoding.eq_ignore_ascii_case("identity") => Ok(false),
+		_ => Err(()),
+	}
+}
+
+/// Read a gzip compressed request body, see [`is_gzip_body`].
+///
+/// `max_request_body_size` is enforced on the decompressed data while it's decompressed, as well as on the
+/// compressed data. Returns the decompressed body and whether it's a single call rather than a batch.
+pub(crate) async fn read_gzip_body(
+	mut body: hyper::Body,
+	max_request_body_size: u32,
+) -> Result<(Vec<u8>, bool), GenericTransportError<hyper::Error>> {
+	let max = max_request_body_size as usize;
+	let mut decoder = GzDecoder::new(LimitedWriter { buf: Vec::new(), remaining: max });
+	let mut received = 0;
+
+	while let Some(chunk) = body.next().await {
+		let chunk = chunk.map_err(GenericTransportError::Inner)?;
+		received += chunk.len();
+		if received > max {
+			return Err(GenericTransportError::TooLarge);
+		}
+		decoder.write_all(&chunk).map_err(|err| decompression_error(&err))?;
+	}
+
+	let decompressed = decoder.finish().map_err(|err| decompression_error(&err))?.buf;
+	let single = match decompressed.iter().find(|byte| !byte.is_ascii_whitespace()) {
+		Some(b'{') => true,
+		Some(b'[') => false,
+		_ => return Err(GenericTransportError::Malformed),
+	};
+
+	Ok((decompressed, single))
+}
+
+fn decompression_error(err: &io::Error) -> GenericTransportError<hyper::Error> {
+	if err.get_ref().is_some_and(|err| err.is::<TooLarge>()) {
+		GenericTransportError::TooLarge
+	} else {
+		GenericTransportError::Malformed
+	}
+}
+
+#[derive(Debug)]
+struct TooLarge;
+
+impl std::fmt::Display for TooLarge {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("decompressed body is too large")
+	}
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Writer failing once more than `remaining` bytes were written to it.
+struct LimitedWriter {
+	buf: Vec<u8>,
+	remaining: usize,
+}
+
+impl Write for LimitedWriter {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		if data.len() > self.remaining {
+			return Err(io::Error::new(io::ErrorKind::OutOfMemory, TooLarge));
+		}
+		self.remaining -= data.len();
+		self.buf.extend_from_slice(data);
+		Ok(data.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{is_gzip_body, negotiate, read_gzip_body, Encoding, ResponseCompression};
+	use bytes::Bytes;
+	use hyper::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING};
+	use jsonrpsee_core::error::GenericTransportError;
+
+	fn accept(value: &'static str) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(value));
+		headers
+	}
+
+	#[test]
+	fn negotiate_works() {
+		assert_eq!(negotiate(&HeaderMap::new()), None);
+		assert_eq!(negotiate(&accept("identity")), None);
+		assert_eq!(negotiate(&accept("gzip, deflate, br")), Some(Encoding::Gzip));
+		assert_eq!(negotiate(&accept("deflate")), Some(Encoding::Deflate));
+		assert_eq!(negotiate(&accept("gzip;q=0.5, deflate")), Some(Encoding::Deflate));
+		assert_eq!(negotiate(&accept("gzip;q=0, deflate;q=0")), None);
+		assert_eq!(negotiate(&accept("*")), Some(Encoding::Gzip));
+		assert_eq!(negotiate(&accept("gzip;q=0, *")), Some(Encoding::Deflate));
+	}
+
+	#[test]
+	fn small_and_incompressible_bodies_are_not_compressed() {
+		let compression = ResponseCompression::new().min_size(16);
+		let stats = compression.stats();
+
+		let (body, headers) = compression.compress(&accept("gzip"), "tiny".into(), &stats);
+		assert_eq!(body, "tiny");
+		assert!(headers.get(CONTENT_ENCODING).is_none());
+
+		let (body, headers) = compression.compress(&accept("gzip"), "0123456789abcdef".into(), &stats);
+		assert_eq!(body, "0123456789abcdef");
+		assert!(headers.get(CONTENT_ENCODING).is_none());
+
+		let (body, headers) = compression.compress(&accept("gzip"), "a".repeat(1024).into(), &stats);
+		assert!(body.len() < 1024);
+		assert_eq!(headers.get(CONTENT_ENCODING).unwrap(), "gzip");
+	}
+
+	#[test]
+	fn compression_is_disabled_if_it_saves_too_little() {
+		use jsonrpsee_core::compression::SAMPLE_SIZE;
+
+		let compression = ResponseCompression::new().min_size(16).min_savings(10);
+		let stats = compression.stats();
+		let incompressible = Bytes::from((0..1024).map(|_| rand::random::<u8>()).collect::<Vec<_>>());
+
+		for _ in 0..SAMPLE_SIZE {
+			let (body, headers) = compression.compress(&accept("gzip"), incompressible.clone(), &stats);
+			assert_eq!(body, incompressible);
+			assert!(headers.get(CONTENT_ENCODING).is_none());
+		}
+		assert!(!stats.is_enabled());
+
+		// Not even compressible responses are compressed anymore.
+		let (body, headers) = compression.compress(&accept("gzip"), "a".repeat(1024).into(), &stats);
+		assert_eq!(body.len(), 1024);
+		assert!(headers.get(CONTENT_ENCODING).is_none());
+
+		let info = stats.info("127.0.0.1:9933".parse().unwrap(), jsonrpsee_core::middleware::Transport::Http).unwrap();
+		assert_eq!(info.negotiated, "gzip");
+		assert_eq!((info.messages, info.uncompressed_bytes), (SAMPLE_SIZE, SAMPLE_SIZE * 1024));
+		assert!(info.disabled);
+	}
+
+	#[test]
+	fn content_encoding_works() {
+		let encoded = |value: &'static str| {
+			let mut headers = HeaderMap::new();
+			headers.insert(CONTENT_ENCODING, HeaderValue::from_static(value));
+			is_gzip_body(&headers)
+		};
+
+		assert_eq!(is_gzip_body(&HeaderMap::new()), Ok(false));
+		assert_eq!(encoded("identity"), Ok(false));
+		assert_eq!(encoded("GZIP"), Ok(true));
+		assert!(encoded("br").is_err());
+		assert!(encoded("gzip, br").is_err());
+	}
+
+	#[tokio::test]
+	async fn read_gzip_body_enforces_limit_on_decompressed_data() {
+		use flate2::write::GzEncoder;
+		use std::io::Write;
+
+		let gzip = |data: &[u8]| {
+			let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+			encoder.write_all(data).unwrap();
+			hyper::Body::from(encoder.finish().unwrap())
+		};
+
+		let batch = format!("[{}]", vec![r#"{"jsonrpc":"2.0","method":"a","id":1}"#; 100].join(","));
+		let (body, single) = read_gzip_body(gzip(batch.as_bytes()), batch.len() as u32).await.unwrap();
+		assert_eq!(body, batch.as_bytes());
+		assert!(!single);
+
+		// Compresses to a few bytes, but exceeds the limit once decompressed.
+		let err = read_gzip_body(gzip(&vec![b' '; 1024 * 1024]), 1024).await.unwrap_err();
+		assert!(matches!(err, GenericTransportError::TooLarge));
+
+		let err = read_gzip_body(hyper::Body::from("not gzip"), 1024).await.unwrap_err();
+		assert!(matches!(err, GenericTransportError::Malformed));
+	}
+}
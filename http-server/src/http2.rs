@@ -0,0 +1,93 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Detection of pipelined HTTP/1.1 requests.
+use std::time::Duration;
+
+use hyper::server::Builder as HyperBuilder;
+
+/// HTTP/2 settings of the server.
+///
+/// HTTP/2 connections are accepted from clients with prior knowledge of the support, that is which start the
+/// connection with the HTTP/2 preface, the upgrade of HTTP/1.1 connections is not supported. Unlike pipelined
+/// HTTP/1.1 requests, the requests multiplexed on a connection are handled concurrently.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Http2 {
+	only: bool,
+	max_concurrent_streams: Option<u32>,
+	keep_alive: Option<(Duration, Duration)>,
+	adaptive_window: bool,
+}
+
+impl Http2 {
+	/// Create a new [`Http2`] with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only accept HTTP/2 connections (default is false, HTTP/1.1 connections are accepted as well).
+	pub fn only(mut self, only: bool) -> Self {
+		self.only = only;
+		self
+	}
+
+	/// Set the maximum number of requests handled concurrently on a connection (default is unlimited).
+	pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+		self.max_concurrent_streams = Some(max);
+		self
+	}
+
+	/// Ping idle connections every `interval`, closing those that don't acknowledge the ping within `timeout`
+	/// (default is disabled).
+	pub fn keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+		self.keep_alive = Some((interval, timeout));
+		self
+	}
+
+	/// Enables or disables adapting the flow control windows to the bandwidth-delay product of the connections,
+	/// which speeds up large responses on high latency links (default is disabled).
+	pub fn adaptive_window(mut self, enabled: bool) -> Self {
+		self.adaptive_window = enabled;
+		self
+	}
+
+	/// Apply the settings to `builder`, `None` to only accept HTTP/1.1 connections.
+	pub(crate) fn configure<I>(http2: Option<&Self>, builder: HyperBuilder<I>) -> HyperBuilder<I> {
+		let http2 = match http2 {
+			Some(http2) => http2,
+			None => return builder.http1_only(true),
+		};
+
+		let builder = builder
+			.http2_only(http2.only)
+			.http2_max_concurrent_streams(http2.max_concurrent_streams)
+			.http2_adaptive_window(http2.adaptive_window);
+
+		match http2.keep_alive {
+			Some((interval, timeout)) => builder.http2_keep_alive_interval(interval).http2_keep_alive_timeout(timeout),
+			None => builder,
+		}
+	}
+}
@@ -30,15 +30,35 @@
 //!
 //! `jsonrpsee-http-server` is a [JSON RPC](https://www.jsonrpc.org/specification) HTTPS server library that's is built for `async/await`.
 
+mod basic_auth;
+mod compression;
+mod http2;
+mod pipelining;
 mod server;
 
 /// Common builders for RPC responses.
 pub mod response;
 
+pub use basic_auth::{BasicAuth, COOKIE_USER};
+pub use compression::ResponseCompression;
+pub use http2::Http2;
+pub use pipelining::Pipelining;
 pub use jsonrpsee_core::server::access_control::{AccessControl, AccessControlBuilder};
+pub use jsonrpsee_core::server::auth::{AuthValidator, Authenticator, Permissions, StaticKeys};
+pub use jsonrpsee_core::server::circuit_breaker::{BreakerPolicy, CircuitBreaker};
+pub use jsonrpsee_core::server::forwarded::TrustedProxies;
+pub use jsonrpsee_core::server::ip_filter::IpFilter;
+pub use jsonrpsee_core::server::load_shedding::LoadShedder;
+pub use jsonrpsee_core::server::wire_tap::WireTap;
+pub use jsonrpsee_core::server::rate_limiting::{RateLimit, RateLimiter};
 pub use jsonrpsee_core::server::rpc_module::RpcModule;
+pub use jsonrpsee_core::server::shutdown::ShutdownReason;
+pub use jsonrpsee_core::tcp::TcpKeepalive;
 pub use jsonrpsee_types as types;
-pub use server::{Builder as HttpServerBuilder, Server as HttpServer, ServerHandle as HttpServerHandle};
+pub use server::{
+	Builder as HttpServerBuilder, HealthPolicy, MetricsEncoder, RouteHandler, Server as HttpServer,
+	ServerHandle as HttpServerHandle,
+};
 pub use tracing;
 
 #[cfg(test)]
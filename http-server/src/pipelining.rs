@@ -0,0 +1,262 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Detection of pipelined HTTP/1.1 requests.
+
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures_util::future::BoxFuture;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use jsonrpsee_core::server::proxy_protocol;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// How the server handles HTTP/1.1 requests pipelined on a connection, that is sent before the response to the
+/// previous request has been received.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Pipelining {
+	/// Pipelined requests are processed one after another and answered in the order they were received.
+	#[default]
+	Ordered,
+	/// Pipelined requests are answered with `400 Bad Request` and the connection is closed.
+	///
+	/// Detection relies on the order in which the data of the connection is read and written: a request is
+	/// considered pipelined if it was received before the response to the previous request was sent.
+	Reject,
+}
+
+/// Order of the reads and writes of a connection.
+#[derive(Debug, Default)]
+struct IoOrder {
+	/// Incremented on every read or write that transferred data.
+	seq: u64,
+	/// Sequence number of the last read that received data.
+	last_read: u64,
+	/// Sequence number of the last write that sent data.
+	last_write: u64,
+	/// Value of `last_write` when the previous request was received, `None` before the first request.
+	write_at_last_request: Option<u64>,
+}
+
+/// Tracks the reads and writes of a connection to tell whether its requests are pipelined.
+#[derive(Debug, Default)]
+pub(crate) struct PipelineDetector(Mutex<IoOrder>);
+
+impl PipelineDetector {
+	fn on_read(&self) {
+		let mut order = self.0.lock().expect("lock is never poisoned; qed");
+		order.seq += 1;
+		order.last_read = order.seq;
+	}
+
+	fn on_write(&self) {
+		let mut order = self.0.lock().expect("lock is never poisoned; qed");
+		order.seq += 1;
+		order.last_write = order.seq;
+	}
+
+	/// Called when a request of the connection is received, returns whether it was pipelined.
+	///
+	/// A client that waits for responses only sends a request after the response to the previous one was written,
+	/// so the request was pipelined if nothing was written since the previous request, or if the data of the
+	/// request had been read before the previous response was written.
+	pub(crate) fn on_request(&self) -> bool {
+		let mut order = self.0.lock().expect("lock is never poisoned; qed");
+		let pipelined = match order.write_at_last_request {
+			Some(write) => order.last_write == write || order.last_read < order.last_write,
+			None => false,
+		};
+		order.write_at_last_request = Some(order.last_write);
+		pipelined
+	}
+}
+
+/// Accepts connections whose requests are checked for pipelining, after reading their PROXY protocol header if
+/// enabled.
+pub(crate) struct TrackedIncoming {
+	incoming: AddrIncoming,
+	/// Connections whose PROXY protocol header is being read, `None` if the protocol is disabled.
+	proxied: Option<FuturesUnordered<BoxFuture<'static, io::Result<TrackedStream>>>>,
+}
+
+impl TrackedIncoming {
+	pub(crate) fn new(incoming: AddrIncoming, proxy_protocol: bool) -> Self {
+		Self { incoming, proxied: proxy_protocol.then(FuturesUnordered::new) }
+	}
+}
+
+impl std::fmt::Debug for TrackedIncoming {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TrackedIncoming")
+			.field("incoming", &self.incoming)
+			.field("proxy_protocol", &self.proxied.is_some())
+			.finish()
+	}
+}
+
+impl Accept for TrackedIncoming {
+	type Conn = TrackedStream;
+	type Error = io::Error;
+
+	fn poll_accept(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+		let this = Pin::into_inner(self);
+		let proxied = match this.proxied.as_mut() {
+			Some(proxied) => proxied,
+			None => return Pin::new(&mut this.incoming).poll_accept(cx).map_ok(TrackedStream::new),
+		};
+
+		// The headers are read concurrently so that slow clients don't hold back the others.
+		while let Poll::Ready(conn) = Pin::new(&mut this.incoming).poll_accept(cx) {
+			match conn {
+				Some(Ok(inner)) => proxied.push(Box::pin(TrackedStream::proxied(inner))),
+				conn => return Poll::Ready(conn.map(|conn| conn.map(TrackedStream::new))),
+			}
+		}
+
+		while let Poll::Ready(Some(conn)) = proxied.poll_next_unpin(cx) {
+			match conn {
+				Ok(conn) => return Poll::Ready(Some(Ok(conn))),
+				// Failing to accept would stop the server, the connection is closed instead.
+				Err(err) => tracing::warn!("Denied connection: invalid PROXY protocol header: {}", err),
+			}
+		}
+
+		Poll::Pending
+	}
+}
+
+/// Connection reporting its reads and writes to a [`PipelineDetector`].
+#[derive(Debug)]
+pub(crate) struct TrackedStream {
+	inner: AddrStream,
+	remote_addr: SocketAddr,
+	detector: Arc<PipelineDetector>,
+}
+
+impl TrackedStream {
+	fn new(inner: AddrStream) -> Self {
+		Self { remote_addr: inner.remote_addr(), inner, detector: Default::default() }
+	}
+
+	/// Reads the PROXY protocol header of the connection, the client is at the address it carries if any.
+	async fn proxied(mut inner: AddrStream) -> io::Result<Self> {
+		let remote_addr = proxy_protocol::read_header(&mut inner).await?.unwrap_or_else(|| inner.remote_addr());
+		Ok(Self { inner, remote_addr, detector: Default::default() })
+	}
+
+	pub(crate) fn remote_addr(&self) -> SocketAddr {
+		self.remote_addr
+	}
+
+	pub(crate) fn detector(&self) -> Arc<PipelineDetector> {
+		self.detector.clone()
+	}
+}
+
+impl AsyncRead for TrackedStream {
+	fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		let filled = buf.filled().len();
+		let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+		if matches!(res, Poll::Ready(Ok(()))) && buf.filled().len() > filled {
+			self.detector.on_read();
+		}
+		res
+	}
+}
+
+impl AsyncWrite for TrackedStream {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+		if matches!(res, Poll::Ready(Ok(n)) if n > 0) {
+			self.detector.on_write();
+		}
+		res
+	}
+
+	fn poll_write_vectored(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		bufs: &[io::IoSlice<'_>],
+	) -> Poll<io::Result<usize>> {
+		let res = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+		if matches!(res, Poll::Ready(Ok(n)) if n > 0) {
+			self.detector.on_write();
+		}
+		res
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		self.inner.is_write_vectored()
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.inner).poll_shutdown(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::PipelineDetector;
+
+	#[test]
+	fn sequential_requests_are_not_pipelined() {
+		let detector = PipelineDetector::default();
+		detector.on_read();
+		assert!(!detector.on_request());
+		detector.on_write();
+		detector.on_read();
+		assert!(!detector.on_request());
+		detector.on_write();
+		detector.on_write();
+		detector.on_read();
+		detector.on_read();
+		assert!(!detector.on_request());
+	}
+
+	#[test]
+	fn pipelined_requests_are_detected() {
+		// Both requests received at once, the second one is parsed before the first response is written.
+		let detector = PipelineDetector::default();
+		detector.on_read();
+		assert!(!detector.on_request());
+		assert!(detector.on_request());
+
+		// The first response is written before the second request, already received, is parsed.
+		let detector = PipelineDetector::default();
+		detector.on_read();
+		assert!(!detector.on_request());
+		detector.on_write();
+		assert!(detector.on_request());
+	}
+}
@@ -26,6 +26,8 @@
 
 //! Contains common builders for hyper responses.
 
+use std::time::Duration;
+
 use jsonrpsee_types::error::reject_too_big_request;
 
 use crate::types::error::{ErrorCode, ErrorResponse};
@@ -47,6 +49,25 @@ pub fn host_not_allowed() -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::FORBIDDEN, "Provided Host header is not whitelisted.\n".to_owned(), TEXT)
 }
 
+/// Create a text/plain response for requests forwarded on behalf of clients whose address is not allowed.
+pub fn ip_not_allowed() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::FORBIDDEN, "Address of the client is not allowed.\n".to_owned(), TEXT)
+}
+
+/// Create a text/plain response for requests with invalid credentials.
+pub fn unauthorized() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::UNAUTHORIZED, "Provided credentials are not valid.\n".to_owned(), TEXT)
+}
+
+/// Create a text/plain response for requests without valid HTTP Basic authentication credentials.
+pub fn basic_auth_required() -> hyper::Response<hyper::Body> {
+	let mut response = unauthorized();
+	response
+		.headers_mut()
+		.insert("www-authenticate", hyper::header::HeaderValue::from_static("Basic realm=\"jsonrpc\""));
+	response
+}
+
 /// Create a text/plain response for disallowed method used.
 pub fn method_not_allowed() -> hyper::Response<hyper::Body> {
 	from_template(
@@ -74,6 +95,11 @@ pub fn invalid_allow_headers() -> hyper::Response<hyper::Body> {
     )
 }
 
+/// Create a text/plain response for health requests while the server is draining.
+pub fn draining(status: hyper::StatusCode) -> hyper::Response<hyper::Body> {
+	from_template(status, "Server is draining.\n".to_owned(), TEXT)
+}
+
 /// Create a json response for oversized requests (413)
 pub fn too_large(limit: u32) -> hyper::Response<hyper::Body> {
 	let error = serde_json::to_string(&ErrorResponse::borrowed(reject_too_big_request(limit), Id::Null))
@@ -82,6 +108,36 @@ pub fn too_large(limit: u32) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::PAYLOAD_TOO_LARGE, error, JSON)
 }
 
+/// Create a json response for requests rejected because the server is busy.
+pub fn server_is_busy() -> hyper::Response<hyper::Body> {
+	ok_response(server_is_busy_body())
+}
+
+/// Create the json body of a `ServerIsBusy` error response.
+pub fn server_is_busy_body() -> String {
+	serde_json::to_string(&ErrorResponse::borrowed(ErrorCode::ServerIsBusy.into(), Id::Null))
+		.expect("built from known-good data; qed")
+}
+
+/// Create a json response for requests rejected because the client exceeded its rate limit (429).
+pub fn too_many_requests(body: impl Into<hyper::Body>, retry_after: Duration) -> hyper::Response<hyper::Body> {
+	with_retry_after(from_template(hyper::StatusCode::TOO_MANY_REQUESTS, body, JSON), retry_after)
+}
+
+/// Create a json response for requests rejected because the server is overloaded (503).
+pub fn service_unavailable(body: impl Into<hyper::Body>, retry_after: Duration) -> hyper::Response<hyper::Body> {
+	with_retry_after(from_template(hyper::StatusCode::SERVICE_UNAVAILABLE, body, JSON), retry_after)
+}
+
+/// Add a `Retry-After` header with the delay rounded up to whole seconds.
+fn with_retry_after(mut response: hyper::Response<hyper::Body>, retry_after: Duration) -> hyper::Response<hyper::Body> {
+	let secs = retry_after.as_nanos().div_ceil(1_000_000_000);
+	response
+		.headers_mut()
+		.insert("retry-after", hyper::header::HeaderValue::from(u64::try_from(secs).unwrap_or(u64::MAX)));
+	response
+}
+
 /// Create a json response for empty or malformed requests (400)
 pub fn malformed() -> hyper::Response<hyper::Body> {
 	let error = serde_json::to_string(&ErrorResponse::borrowed(ErrorCode::ParseError.into(), Id::Null))
@@ -90,6 +146,16 @@ pub fn malformed() -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::BAD_REQUEST, error, JSON)
 }
 
+/// Create a text/plain response for pipelined requests when pipelining is rejected (400).
+///
+/// The connection is closed once the response has been sent.
+pub fn pipelining_not_supported() -> hyper::Response<hyper::Body> {
+	let mut response =
+		from_template(hyper::StatusCode::BAD_REQUEST, "Pipelined requests are not supported.\n".to_owned(), TEXT);
+	response.headers_mut().insert(hyper::header::CONNECTION, hyper::header::HeaderValue::from_static("close"));
+	response
+}
+
 /// Create a response body.
 fn from_template<S: Into<hyper::Body>>(
 	status: hyper::StatusCode,
@@ -106,10 +172,34 @@ fn from_template<S: Into<hyper::Body>>(
 }
 
 /// Create a valid JSON response.
-pub fn ok_response(body: String) -> hyper::Response<hyper::Body> {
+pub fn ok_response(body: impl Into<hyper::Body>) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::OK, body, JSON)
 }
 
+/// Create a valid JSON response with a streamed body.
+pub fn ok_streamed_response(body: hyper::Body) -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::OK, body, JSON)
+}
+
+/// Create a json response summarizing the checks of an aggregated health endpoint.
+pub fn health_summary(status: hyper::StatusCode, body: impl Into<hyper::Body>) -> hyper::Response<hyper::Body> {
+	from_template(status, body, JSON)
+}
+
+/// Create a valid response for the metrics endpoint.
+pub fn ok_metrics_response(body: Vec<u8>, content_type: &'static str) -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::OK, body, content_type)
+}
+
+/// Create a response for request bodies compressed with an unsupported content encoding.
+pub fn unsupported_content_encoding() -> hyper::Response<hyper::Body> {
+	from_template(
+		hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+		"Supplied content encoding is not supported. Only gzip compressed requests are accepted\n".to_owned(),
+		TEXT,
+	)
+}
+
 /// Create a response for unsupported content type.
 pub fn unsupported_content_type() -> hyper::Response<hyper::Body> {
 	from_template(
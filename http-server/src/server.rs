@@ -24,32 +24,61 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::convert::Infallible;
 use std::future::Future;
-use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::net::{IpAddr, SocketAddr, TcpListener as StdTcpListener};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use crate::basic_auth::BasicAuth;
+use crate::compression::{self, ResponseCompression};
+use crate::http2::Http2;
+use crate::pipelining::{PipelineDetector, Pipelining, TrackedIncoming, TrackedStream};
 use crate::response;
 use crate::response::{internal_error, malformed};
-use futures_channel::mpsc;
-use futures_util::{future::join_all, stream::StreamExt, FutureExt};
+use bytes::Bytes;
+use futures_channel::{mpsc, oneshot};
+use futures_util::future::{self, join_all, BoxFuture, FutureExt};
+use futures_util::stream::{self, StreamExt};
 use hyper::header::{HeaderMap, HeaderValue};
-use hyper::server::{conn::AddrIncoming, Builder as HyperBuilder};
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::server::Builder as HyperBuilder;
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Error as HyperError, Method};
+use hyper::{Error as HyperError, Method, StatusCode};
+use jsonrpsee_core::compression::CompressionStats;
 use jsonrpsee_core::error::{Error, GenericTransportError};
-use jsonrpsee_core::http_helpers::{self, read_body};
-use jsonrpsee_core::middleware::Middleware;
+use jsonrpsee_core::http_helpers::{self, read_pooled_body, BodyBufferPool, PooledBody};
+use jsonrpsee_core::middleware::{CallInfo, Middleware, Transport};
 use jsonrpsee_core::server::access_control::AccessControl;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodSink};
+use jsonrpsee_core::server::auth::Authenticator;
+use jsonrpsee_core::server::circuit_breaker::CircuitBreaker;
+use jsonrpsee_core::server::forwarded::TrustedProxies;
+use jsonrpsee_core::server::helpers::{
+	admit_calls, batch_stages, collect_batch_response, prepare_error, report_resource_usage, report_response,
+	report_result, CallDenied, CallPolicy, MethodSink, PanicHook, PanicReport,
+};
+use jsonrpsee_core::server::ip_filter::IpFilter;
+use jsonrpsee_core::server::load_shedding::LoadShedder;
+use jsonrpsee_core::server::parse::{parse_batch, parse_notification, parse_notification_batch, parse_request};
+use jsonrpsee_core::server::rate_limiting::{RateLimitKey, RateLimiter};
 use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{MethodKind, Methods};
+use jsonrpsee_core::server::rpc_module::{MethodCallback, MethodKind, Methods, MethodsHandle, SystemLimits};
+use jsonrpsee_core::server::server_set::ServerControl;
+use jsonrpsee_core::server::shutdown::{ShutdownReason, ShutdownRecord};
+use jsonrpsee_core::server::wire_tap::{Direction, WireTap, WireTapSession};
+use jsonrpsee_core::tcp::{BindSettings, TcpKeepalive, TcpSettings};
 use jsonrpsee_core::tracing::{rx_log_from_json, RpcTracing};
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
-use jsonrpsee_types::error::{ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG};
-use jsonrpsee_types::{Id, Notification, Params, Request};
-use serde_json::value::RawValue;
-use tokio::net::{TcpListener, ToSocketAddrs};
+use jsonrpsee_types::error::{
+	reject_too_large_batch, ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG,
+};
+use jsonrpsee_types::number::NumberPolicy;
+use jsonrpsee_types::{v1, Id, Params, Request};
+use tokio::net::{lookup_host, ToSocketAddrs};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
 use tracing_futures::Instrument;
 
 /// Builder to create JSON-RPC HTTP server.
@@ -65,7 +94,35 @@ pub struct Builder<M = ()> {
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
 	max_log_length: u32,
-	health_api: Option<HealthApi>,
+	get_routes: Vec<GetRoute>,
+	draining_status: StatusCode,
+	max_concurrent_requests: Option<u32>,
+	concurrent_requests_wait: Option<Duration>,
+	stream_batch_responses: bool,
+	amortize_batch_resource_claims: bool,
+	max_batch_concurrency: Option<usize>,
+	max_batch_size: Option<usize>,
+	http_status_backpressure: bool,
+	response_compression: Option<ResponseCompression>,
+	json_rpc_v1_compat: bool,
+	system_limits_method: bool,
+	pipelining: Pipelining,
+	http2: Option<Http2>,
+	pooled_body_buffers: usize,
+	rate_limiter: RateLimiter,
+	ip_filter: IpFilter,
+	trusted_proxies: TrustedProxies,
+	proxy_protocol: bool,
+	circuit_breaker: CircuitBreaker,
+	load_shedder: LoadShedder,
+	wire_tap: WireTap,
+	authenticator: Authenticator,
+	basic_auth: Option<BasicAuth>,
+	tcp: TcpSettings,
+	bind: BindSettings,
+	panic_hook: Option<PanicHook>,
+	number_policy: NumberPolicy,
+	resource_usage_interval: Option<Duration>,
 }
 
 impl Default for Builder {
@@ -79,7 +136,35 @@ impl Default for Builder {
 			tokio_runtime: None,
 			middleware: (),
 			max_log_length: 4096,
-			health_api: None,
+			get_routes: Vec::new(),
+			draining_status: StatusCode::SERVICE_UNAVAILABLE,
+			max_concurrent_requests: None,
+			concurrent_requests_wait: None,
+			stream_batch_responses: false,
+			amortize_batch_resource_claims: false,
+			max_batch_concurrency: None,
+			max_batch_size: None,
+			http_status_backpressure: false,
+			response_compression: None,
+			json_rpc_v1_compat: false,
+			system_limits_method: false,
+			pipelining: Pipelining::Ordered,
+			http2: Some(Http2::default()),
+			pooled_body_buffers: DEFAULT_POOLED_BODY_BUFFERS,
+			rate_limiter: RateLimiter::default(),
+			ip_filter: IpFilter::default(),
+			trusted_proxies: TrustedProxies::default(),
+			proxy_protocol: false,
+			circuit_breaker: CircuitBreaker::default(),
+			load_shedder: LoadShedder::default(),
+			wire_tap: WireTap::default(),
+			authenticator: Authenticator::default(),
+			basic_auth: None,
+			tcp: TcpSettings::default(),
+			bind: BindSettings::default(),
+			panic_hook: None,
+			number_policy: NumberPolicy::Preserve,
+			resource_usage_interval: None,
 		}
 	}
 }
@@ -127,11 +212,41 @@ impl<M> Builder<M> {
 			tokio_runtime: self.tokio_runtime,
 			middleware,
 			max_log_length: self.max_log_length,
-			health_api: self.health_api,
+			get_routes: self.get_routes,
+			draining_status: self.draining_status,
+			max_concurrent_requests: self.max_concurrent_requests,
+			concurrent_requests_wait: self.concurrent_requests_wait,
+			stream_batch_responses: self.stream_batch_responses,
+			amortize_batch_resource_claims: self.amortize_batch_resource_claims,
+			max_batch_concurrency: self.max_batch_concurrency,
+			max_batch_size: self.max_batch_size,
+			http_status_backpressure: self.http_status_backpressure,
+			response_compression: self.response_compression,
+			json_rpc_v1_compat: self.json_rpc_v1_compat,
+			system_limits_method: self.system_limits_method,
+			pipelining: self.pipelining,
+			http2: self.http2,
+			pooled_body_buffers: self.pooled_body_buffers,
+			rate_limiter: self.rate_limiter,
+			ip_filter: self.ip_filter,
+			trusted_proxies: self.trusted_proxies,
+			proxy_protocol: self.proxy_protocol,
+			circuit_breaker: self.circuit_breaker,
+			load_shedder: self.load_shedder,
+			wire_tap: self.wire_tap,
+			authenticator: self.authenticator,
+			basic_auth: self.basic_auth,
+			tcp: self.tcp,
+			bind: self.bind,
+			panic_hook: self.panic_hook,
+			number_policy: self.number_policy,
+			resource_usage_interval: self.resource_usage_interval,
 		}
 	}
 
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
+	///
+	/// The limit applies to the decompressed data of gzip compressed bodies.
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
 		self
@@ -156,8 +271,366 @@ impl<M> Builder<M> {
 		self
 	}
 
-	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
-	/// registered resources on this server instance would exceed 8.
+	/// Enables or disables streaming of batch responses (default is disabled).
+	///
+	/// When enabled, the responses of a batch request are written as a chunked JSON array in the order
+	/// the calls complete, such that clients can start consuming early results before the slowest call
+	/// of the batch has finished.
+	pub fn stream_batch_responses(mut self, enabled: bool) -> Self {
+		self.stream_batch_responses = enabled;
+		self
+	}
+
+	/// Enables or disables claiming resources once per batch request (default is disabled).
+	///
+	/// When enabled and all calls of a batch are made to methods with identical resource requirements,
+	/// these resources are claimed once for the whole batch rather than for every call, reducing the
+	/// contention on the resource table for homogeneous bulk requests.
+	pub fn amortize_batch_resource_claims(mut self, enabled: bool) -> Self {
+		self.amortize_batch_resource_claims = enabled;
+		self
+	}
+
+	/// Configure the maximum number of calls of a batch request executed concurrently (default is unlimited).
+	///
+	/// The remaining calls are started as the running ones complete, the batch response still contains
+	/// the responses of all calls. Calls hinted to be executed sequentially are not affected, and a limit of
+	/// zero is treated as one.
+	pub fn max_batch_concurrency(mut self, max: usize) -> Self {
+		self.max_batch_concurrency = Some(max.max(1));
+		self
+	}
+
+	/// Configure the maximum number of calls in a batch request (default is unlimited).
+	///
+	/// Larger batches are rejected as a whole with a single
+	/// [`BATCH_TOO_LARGE_CODE`](crate::types::error::BATCH_TOO_LARGE_CODE) error, whatever their size in bytes,
+	/// which is limited separately.
+	pub fn max_batch_size(mut self, max: usize) -> Self {
+		self.max_batch_size = Some(max);
+		self
+	}
+
+	/// Enables or disables signaling backpressure with HTTP status codes (default is disabled).
+	///
+	/// When enabled, requests rejected because the client exceeded its rate limit are answered with
+	/// `429 Too Many Requests` and requests rejected because the server is busy with `503 Service Unavailable`,
+	/// both with a `Retry-After` header and the JSON-RPC error in the body. Otherwise these are answered with
+	/// `200 OK` and the JSON-RPC error only.
+	///
+	/// Batch requests are always answered with `200 OK` because their calls may fail for different reasons.
+	pub fn http_status_backpressure(mut self, enabled: bool) -> Self {
+		self.http_status_backpressure = enabled;
+		self
+	}
+
+	/// Enables compressing the responses with `gzip` or `deflate` if the client accepts it in the
+	/// `Accept-Encoding` header of its request (default is disabled).
+	///
+	/// Streamed batch responses, see [`Builder::stream_batch_responses`], are always sent uncompressed. The
+	/// compression statistics of every connection are reported to
+	/// [`Middleware::on_compression_info`](jsonrpsee_core::middleware::Middleware::on_compression_info).
+	///
+	/// ```
+	/// use jsonrpsee_http_server::{HttpServerBuilder, ResponseCompression};
+	///
+	/// // Compress the responses of at least 4 KiB.
+	/// let builder = HttpServerBuilder::default().set_response_compression(ResponseCompression::new().min_size(4096));
+	/// ```
+	pub fn set_response_compression(mut self, compression: ResponseCompression) -> Self {
+		self.response_compression = Some(compression);
+		self
+	}
+
+	/// Register a hook invoked whenever a method handler panics.
+	///
+	/// Panics are always caught, the call is answered with an internal error whose `data` contains the
+	/// correlation id passed to the hook, and the server keeps running.
+	pub fn panic_hook(mut self, hook: impl Fn(&PanicReport) + Send + Sync + 'static) -> Self {
+		self.panic_hook = Some(PanicHook::new(hook));
+		self
+	}
+
+	/// Configure how integers beyond `2^53 - 1` in magnitude are handled in results (default is to send them as they are).
+	///
+	/// Such integers are silently rounded by clients parsing numbers as doubles, such as JavaScript clients.
+	/// Clients can parse stringified integers with [`LenientInt`](jsonrpsee_types::number::LenientInt).
+	pub fn set_number_policy(mut self, policy: NumberPolicy) -> Self {
+		self.number_policy = policy;
+		self
+	}
+
+	/// Enables or disables accepting JSON-RPC 1.0 requests (default is disabled).
+	///
+	/// When enabled, requests without the `jsonrpc` member or with a version other than `2.0` are handled
+	/// as JSON-RPC 1.0 requests, as sent by many legacy wallet and exchange integrations, and are answered
+	/// with JSON-RPC 1.0 responses containing both `result` and `error`. Batches must not mix versions.
+	///
+	/// Batch responses to JSON-RPC 1.0 requests are never streamed.
+	pub fn json_rpc_v1_compat(mut self, enabled: bool) -> Self {
+		self.json_rpc_v1_compat = enabled;
+		self
+	}
+
+	/// Enables or disables the [`SYSTEM_LIMITS`](jsonrpsee_core::server::rpc_module::SYSTEM_LIMITS) method
+	/// (default is disabled).
+	///
+	/// When enabled, the server registers a method responding with its effective limits: the maximum request
+	/// and response sizes, the maximum batch size, the rate limits and the supported content encodings, see
+	/// [`SystemLimits`].
+	pub fn system_limits_method(mut self, enabled: bool) -> Self {
+		self.system_limits_method = enabled;
+		self
+	}
+
+	/// Configure how HTTP/1.1 requests pipelined on a connection are handled (default is [`Pipelining::Ordered`]).
+	///
+	/// Some legacy clients send several requests on a connection without waiting for the responses. By default
+	/// these requests are processed one after another and answered in order, [`Pipelining::Reject`] answers
+	/// them with `400 Bad Request` instead.
+	///
+	/// Pipelined requests can't be detected on the listeners of servers built with
+	/// [`Builder::build_from_hyper`], which fails if they are to be rejected.
+	pub fn pipelining(mut self, pipelining: Pipelining) -> Self {
+		self.pipelining = pipelining;
+		self
+	}
+
+	/// Enables or disables HTTP/2 connections. By default, support is enabled.
+	pub fn http2_supported(mut self, supported: bool) -> Self {
+		self.http2 = supported.then(|| self.http2.unwrap_or_default());
+		self
+	}
+
+	/// Configure HTTP/2 connections, see [`Http2`] for details. Enables HTTP/2 if it was disabled.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_http_server::{Http2, HttpServerBuilder};
+	///
+	/// let builder = HttpServerBuilder::default()
+	///     .set_http2(Http2::new().max_concurrent_streams(256).keep_alive(Duration::from_secs(30), Duration::from_secs(10)));
+	/// ```
+	///
+	/// The listeners of servers built with [`Builder::build_from_hyper`] keep their own HTTP/2 settings.
+	pub fn set_http2(mut self, http2: Http2) -> Self {
+		self.http2 = Some(http2);
+		self
+	}
+
+	/// Configure how many request body buffers are kept for reuse (default is 16).
+	///
+	/// Request bodies are read into buffers that are kept once the request has been answered, so that the memory
+	/// allocated for large requests is reused rather than allocated again. Buffers larger than 1 MiB are released.
+	/// Zero disables the reuse of buffers.
+	pub fn pooled_body_buffers(mut self, max: usize) -> Self {
+		self.pooled_body_buffers = max;
+		self
+	}
+
+	/// Sets the maximum number of HTTP requests that are processed concurrently (default is unlimited).
+	///
+	/// Requests that arrive while the limit is reached are rejected with a `ServerIsBusy` error,
+	/// unless [`Builder::concurrent_requests_wait`] is configured.
+	pub fn max_concurrent_requests(mut self, max: u32) -> Self {
+		self.max_concurrent_requests = Some(max);
+		self
+	}
+
+	/// Configures how long a request may wait for a free slot when [`Builder::max_concurrent_requests`]
+	/// is reached before it's rejected with a `ServerIsBusy` error.
+	///
+	/// Default: rejected immediately.
+	pub fn concurrent_requests_wait(mut self, timeout: Duration) -> Self {
+		self.concurrent_requests_wait = Some(timeout);
+		self
+	}
+
+	/// Configure per-client rate limiting (default is disabled).
+	///
+	/// Clients are identified by the value of [`RateLimiter::key_by_header`] if configured and set by one of the
+	/// [trusted proxies](Builder::set_trusted_proxies), otherwise by their IP address.
+	///
+	/// See the module documentation for [`rate_limiting`](../jsonrpsee_utils/server/rate_limiting/index.html#rate-limiting)
+	/// for details.
+	pub fn set_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+		self.rate_limiter = limiter;
+		self
+	}
+
+	/// Configure which IP addresses clients may connect from (default is all).
+	///
+	/// The connections of rejected clients are closed as soon as they are accepted, before any request is read.
+	///
+	/// See the module documentation for [`ip_filter`](../jsonrpsee_utils/server/ip_filter/index.html#ip-filtering)
+	/// for details.
+	pub fn set_ip_filter(mut self, filter: IpFilter) -> Self {
+		self.ip_filter = filter;
+		self
+	}
+
+	/// Configure the reverse proxies trusted to report the address of clients in the `Forwarded` or
+	/// `X-Forwarded-For` headers (default is none).
+	///
+	/// The address of the client of a request forwarded by a trusted proxy is used instead of the address of the
+	/// proxy for the IP filter, the rate limits and [`CallInfo::client_ip`](jsonrpsee_core::middleware::CallInfo).
+	/// The IP filter is then applied to every request rather than to the connection.
+	///
+	/// See the module documentation for [`forwarded`](../jsonrpsee_utils/server/forwarded/index.html#forwarded-headers)
+	/// for details.
+	pub fn set_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+		self.trusted_proxies = proxies;
+		self
+	}
+
+	/// Expects the connections to start with a PROXY protocol header, sent by the load balancer they go through,
+	/// and treats the clients as connecting from the address it carries (default is disabled).
+	///
+	/// Connections without a valid header are closed. The PROXY protocol is ignored by
+	/// [`Builder::build_from_hyper`].
+	///
+	/// See the module documentation for [`proxy_protocol`](../jsonrpsee_utils/server/proxy_protocol/index.html#proxy-protocol)
+	/// for details.
+	pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+		self.proxy_protocol = enabled;
+		self
+	}
+
+	/// Configure per-method circuit breaking (default is disabled).
+	///
+	/// While the circuit of a method is open, its calls are answered with an error without being executed, with
+	/// `503 Service Unavailable` if [`Builder::http_status_backpressure`] is enabled.
+	///
+	/// See the module documentation for [`circuit_breaker`](../jsonrpsee_utils/server/circuit_breaker/index.html#circuit-breaking)
+	/// for details.
+	pub fn set_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+		self.circuit_breaker = breaker;
+		self
+	}
+
+	/// Configure shedding requests under memory pressure (default is disabled).
+	///
+	/// While the server is overloaded, requests are rejected with a `ServerIsBusy` error before their bodies
+	/// are read, with `503 Service Unavailable` if [`Builder::http_status_backpressure`] is enabled.
+	///
+	/// See the module documentation for [`load_shedding`](../jsonrpsee_utils/server/load_shedding/index.html#load-shedding)
+	/// for details.
+	pub fn set_load_shedder(mut self, shedder: LoadShedder) -> Self {
+		self.load_shedder = shedder;
+		self
+	}
+
+	/// Configure capturing the raw request and response bodies of a sample of connections (default is disabled).
+	///
+	/// See the module documentation for [`wire_tap`](../jsonrpsee_utils/server/wire_tap/index.html#wire-tap)
+	/// for details.
+	pub fn set_wire_tap(mut self, tap: WireTap) -> Self {
+		self.wire_tap = tap;
+		self
+	}
+
+	/// Configure the authentication of clients and the permissions required to call methods (default is disabled).
+	///
+	/// Clients provide their token in the `Authorization` header, requests with an invalid token are rejected
+	/// with status code `401`.
+	///
+	/// See the module documentation for [`auth`](../jsonrpsee_utils/server/auth/index.html#authentication)
+	/// for details.
+	pub fn set_authenticator(mut self, authenticator: Authenticator) -> Self {
+		self.authenticator = authenticator;
+		self
+	}
+
+	/// Require HTTP Basic authentication for all JSON-RPC requests (default is disabled).
+	///
+	/// This is compatible with `bitcoin-cli` style clients authenticating with `rpcuser`/`rpcpassword`
+	/// or a cookie file. Requests without valid credentials are rejected with status code `401`.
+	///
+	/// When enabled, the `Authorization` header is consumed by the Basic authentication and clients are
+	/// treated as anonymous by the [`Builder::set_authenticator`].
+	pub fn set_basic_auth(mut self, auth: BasicAuth) -> Self {
+		self.basic_auth = Some(auth);
+		self
+	}
+
+	/// Enables or disables `TCP_NODELAY` on accepted connections (default is enabled for [`Builder::build`]).
+	///
+	/// The TCP options are ignored by [`Builder::build_from_hyper`].
+	pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+		self.tcp.nodelay = Some(enabled);
+		self
+	}
+
+	/// Enables TCP keepalive on accepted connections (default is the operating system default).
+	///
+	/// ```rust
+	/// use std::time::Duration;
+	/// use jsonrpsee_http_server::{HttpServerBuilder, TcpKeepalive};
+	///
+	/// let keepalive = TcpKeepalive::new(Duration::from_secs(60)).interval(Duration::from_secs(10)).retries(3);
+	/// let builder = HttpServerBuilder::default().tcp_keepalive(keepalive);
+	/// ```
+	pub fn tcp_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
+		self.tcp.keepalive = Some(keepalive);
+		self
+	}
+
+	/// Sets the size of the send buffer of accepted connections (default is the operating system default).
+	///
+	/// The option is set on the listening socket and inherited by the accepted connections on most platforms.
+	pub fn tcp_send_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.send_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets the size of the receive buffer of accepted connections (default is the operating system default).
+	///
+	/// The option is set on the listening socket and inherited by the accepted connections on most platforms.
+	pub fn tcp_recv_buffer_size(mut self, size: usize) -> Self {
+		self.tcp.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets `SO_LINGER` on accepted connections, `None` disables lingering (default is the operating system default).
+	///
+	/// The option is set on the listening socket and inherited by the accepted connections on most platforms.
+	pub fn tcp_linger(mut self, linger: Option<Duration>) -> Self {
+		self.tcp.linger = Some(linger);
+		self
+	}
+
+	/// Sets `IPV6_V6ONLY` on IPv6 listening sockets bound by [`Builder::build`] (default is the operating system default).
+	///
+	/// When disabled, a server bound to `[::]` accepts IPv4 connections as well on a single dual-stack socket.
+	/// When enabled, it only accepts IPv6 connections, combine it with [`Builder::bind_all_addresses`]
+	/// to listen on separate IPv4 and IPv6 sockets.
+	pub fn ipv6_only(mut self, enabled: bool) -> Self {
+		self.bind.ipv6_only = Some(enabled);
+		self
+	}
+
+	/// Enables or disables binding [`Builder::build`] to all given addresses (default is disabled).
+	///
+	/// When enabled, a separate listening socket is bound to every address and building the server fails if
+	/// any of them can't be bound. Otherwise only the first address that can be bound is used.
+	///
+	/// ```rust
+	/// use std::net::SocketAddr;
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let addrs: &[SocketAddr] = &["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+	///   let server = HttpServerBuilder::default().bind_all_addresses(true).build(addrs).await.unwrap();
+	///   assert_eq!(server.local_addrs().len(), 2);
+	/// }
+	/// ```
+	pub fn bind_all_addresses(mut self, enabled: bool) -> Self {
+		self.bind.all_addresses = enabled;
+		self
+	}
+
+	/// Register a new resource kind. Errors if `label` is already registered.
 	///
 	/// See the module documentation for [`resource_limiting`](../jsonrpsee_utils/server/resource_limiting/index.html#resource-limiting)
 	/// for details.
@@ -167,6 +640,22 @@ impl<M> Builder<M> {
 		Ok(self)
 	}
 
+	/// Limit the units of the registered resource `label` that the calls of a single connection may claim at
+	/// the same time, `0` (the default) for no limit. Errors if `label` isn't registered.
+	pub fn connection_resource_quota(self, label: &'static str, quota: u16) -> Result<Self, Error> {
+		self.resources.set_connection_quota(label, quota)?;
+
+		Ok(self)
+	}
+
+	/// Report the usage of the registered resources to [`Middleware::on_resource_usage`] every `period`.
+	///
+	/// Default: disabled.
+	pub fn resource_usage_interval(mut self, period: Duration) -> Self {
+		self.resource_usage_interval = Some(period);
+		self
+	}
+
 	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
 	///
 	/// Default: [`tokio::spawn`]
@@ -180,16 +669,146 @@ impl<M> Builder<M> {
 	/// Error returned from the method will be converted to status 500 response.
 	/// Expects a tuple with (</path>, <rpc-method-name>).
 	///
+	/// The method may report the server as unhealthy by returning `false` or an object whose `healthy` field is
+	/// `false`, which is converted to a status 503 response with the result as body.
+	///
+	/// Several endpoints can be enabled under different paths, enabling an endpoint under the path of another one
+	/// replaces it. This is a readiness endpoint, see [`Builder::health_api_liveness`] for the difference.
+	///
 	/// Fails if the path is missing `/`.
-	pub fn health_api(mut self, path: impl Into<String>, method: impl Into<String>) -> Result<Self, Error> {
-		let path = path.into();
+	///
+	/// ```
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// let builder = HttpServerBuilder::default()
+	///     .health_api_liveness("/health/live", "system_live")
+	///     .unwrap()
+	///     .health_api("/health/ready", "system_ready")
+	///     .unwrap();
+	/// ```
+	pub fn health_api(self, path: impl Into<String>, method: impl Into<String>) -> Result<Self, Error> {
+		self.add_health_api(path.into(), HealthChecks::Single(method.into()), false)
+	}
+
+	/// Enable a liveness health endpoint, like [`Builder::health_api`].
+	///
+	/// Liveness endpoints tell whether the server is running at all, rather than whether it's ready to handle
+	/// requests: unlike other health endpoints they keep invoking their method while the server is draining.
+	pub fn health_api_liveness(self, path: impl Into<String>, method: impl Into<String>) -> Result<Self, Error> {
+		self.add_health_api(path.into(), HealthChecks::Single(method.into()), true)
+	}
 
-		if !path.starts_with("/") {
+	fn add_health_api(self, path: String, checks: HealthChecks, liveness: bool) -> Result<Self, Error> {
+		if !path.starts_with('/') {
 			return Err(Error::Custom(format!("Health endpoint path must start with `/` to work, got: {}", path)));
 		}
 
-		self.health_api = Some(HealthApi { path: path, method: method.into() });
-		Ok(self)
+		Ok(self.add_get_route(path, GetHandler::Health { checks, liveness }))
+	}
+
+	fn add_get_route(mut self, path: String, handler: GetHandler) -> Self {
+		self.get_routes.retain(|route| route.path != path);
+		self.get_routes.push(GetRoute { path, handler });
+		self
+	}
+
+	/// Enable a health endpoint aggregating several methods.
+	/// Allows you to expose the combined outcome of `methods` under GET /<path>. Each method is invoked with no
+	/// parameters and the server is considered healthy when the outcomes satisfy `policy`.
+	///
+	/// The response body is a JSON summary of every check, the status is `200` when healthy and `503` otherwise:
+	/// `{"healthy":true,"checks":[{"method":"<name>","healthy":true,"result":<result>}, ..]}`.
+	///
+	/// A method reporting the server as unhealthy counts as a failed check, see [`Builder::health_api`].
+	///
+	/// Fails if the path is missing `/`, if no methods are given or if the quorum can't be reached.
+	///
+	/// ```
+	/// use jsonrpsee_http_server::{HealthPolicy, HttpServerBuilder};
+	///
+	/// let builder = HttpServerBuilder::default()
+	///     .health_api_aggregate("/health", ["chain_health", "peers_health", "db_health"], HealthPolicy::Quorum(2))
+	///     .unwrap();
+	/// ```
+	pub fn health_api_aggregate(
+		self,
+		path: impl Into<String>,
+		methods: impl IntoIterator<Item = impl Into<String>>,
+		policy: HealthPolicy,
+	) -> Result<Self, Error> {
+		let methods: Vec<String> = methods.into_iter().map(Into::into).collect();
+
+		if methods.is_empty() {
+			return Err(Error::Custom("Health endpoint must aggregate at least one method".into()));
+		}
+
+		if let HealthPolicy::Quorum(quorum) = policy {
+			if quorum == 0 || quorum > methods.len() {
+				return Err(Error::Custom(format!(
+					"Health endpoint quorum must be between 1 and {}, got: {}",
+					methods.len(),
+					quorum
+				)));
+			}
+		}
+
+		self.add_health_api(path.into(), HealthChecks::Aggregate(methods, policy), false)
+	}
+
+	/// Sets the status code of the health endpoint while the server is draining (default is `503`).
+	///
+	/// See [`ServerHandle::drain`].
+	pub fn health_draining_status(mut self, status: StatusCode) -> Self {
+		self.draining_status = status;
+		self
+	}
+
+	/// Enable metrics endpoint.
+	/// Allows you to expose metrics under GET /<path>, the response body is produced by `encoder` on each request.
+	/// Errors returned by the encoder will be converted to status 500 response.
+	///
+	/// Fails if the path is missing `/`.
+	///
+	/// ```
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// let builder = HttpServerBuilder::default()
+	///     .metrics_api("/metrics", || Ok(b"# Metrics in the Prometheus text format\n".to_vec()))
+	///     .unwrap();
+	/// ```
+	pub fn metrics_api(self, path: impl Into<String>, encoder: impl MetricsEncoder) -> Result<Self, Error> {
+		let path = path.into();
+
+		if !path.starts_with('/') {
+			return Err(Error::Custom(format!("Metrics endpoint path must start with `/` to work, got: {}", path)));
+		}
+
+		Ok(self.add_get_route(path, GetHandler::Metrics(Arc::new(encoder))))
+	}
+
+	/// Serve `GET` requests to `path` with `handler`, for instance to expose a version endpoint or a status page
+	/// without running a separate HTTP server. See [`Builder::health_api`] to serve the result of a method.
+	///
+	/// Requests are subject to the same host and origin checks as calls. Routes share their paths with the health
+	/// and metrics endpoints, registering a route under the path of another one replaces it.
+	///
+	/// Fails if the path is missing `/`.
+	///
+	/// ```
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// let builder = HttpServerBuilder::default()
+	///     .get_route("/version", |_request| async { hyper::Response::new(hyper::Body::from("1.0.0")) })
+	///     .unwrap();
+	/// ```
+	pub fn get_route(self, path: impl Into<String>, handler: impl RouteHandler) -> Result<Self, Error> {
+		let path = path.into();
+
+		if !path.starts_with('/') {
+			return Err(Error::Custom(format!("Route path must start with `/` to work, got: {}", path)));
+		}
+
+		Ok(self.add_get_route(path, GetHandler::Custom(Arc::new(handler))))
 	}
 
 	/// Finalizes the configuration of the server with customized TCP settings on the socket and on hyper.
@@ -229,10 +848,14 @@ impl<M> Builder<M> {
 		listener: hyper::server::Builder<AddrIncoming>,
 		local_addr: SocketAddr,
 	) -> Result<Server<M>, Error> {
+		if self.pipelining == Pipelining::Reject {
+			return Err(Error::Custom("Rejecting pipelined requests is not supported on hyper listeners".into()));
+		}
+
 		Ok(Server {
 			access_control: self.access_control,
-			listener,
-			local_addr: Some(local_addr),
+			listeners: vec![Listener::Hyper(listener)],
+			local_addrs: vec![local_addr],
 			max_request_body_size: self.max_request_body_size,
 			max_response_body_size: self.max_response_body_size,
 			batch_requests_supported: self.batch_requests_supported,
@@ -240,7 +863,32 @@ impl<M> Builder<M> {
 			tokio_runtime: self.tokio_runtime,
 			middleware: self.middleware,
 			max_log_length: self.max_log_length,
-			health_api: self.health_api,
+			get_routes: self.get_routes,
+			draining_status: self.draining_status,
+			max_concurrent_requests: self.max_concurrent_requests,
+			concurrent_requests_wait: self.concurrent_requests_wait,
+			stream_batch_responses: self.stream_batch_responses,
+			amortize_batch_resource_claims: self.amortize_batch_resource_claims,
+			max_batch_concurrency: self.max_batch_concurrency,
+			max_batch_size: self.max_batch_size,
+			http_status_backpressure: self.http_status_backpressure,
+			response_compression: self.response_compression,
+			json_rpc_v1_compat: self.json_rpc_v1_compat,
+			system_limits_method: self.system_limits_method,
+			pipelining: self.pipelining,
+			http2: self.http2,
+			pooled_body_buffers: self.pooled_body_buffers,
+			rate_limiter: self.rate_limiter,
+			ip_filter: self.ip_filter,
+			trusted_proxies: self.trusted_proxies,
+			circuit_breaker: self.circuit_breaker,
+			load_shedder: self.load_shedder,
+			wire_tap: self.wire_tap,
+			authenticator: self.authenticator,
+			basic_auth: self.basic_auth,
+			panic_hook: self.panic_hook,
+			number_policy: self.number_policy,
+			resource_usage_interval: self.resource_usage_interval,
 		})
 	}
 
@@ -270,13 +918,13 @@ impl<M> Builder<M> {
 	/// ```
 	pub fn build_from_tcp(self, listener: impl Into<StdTcpListener>) -> Result<Server<M>, Error> {
 		let listener = listener.into();
-		let local_addr = listener.local_addr().ok();
+		let local_addrs = listener.local_addr().ok().into_iter().collect();
 
-		let listener = hyper::Server::from_tcp(listener)?;
+		let listener = configure_tcp(&self.tcp, listener, self.proxy_protocol)?;
 
 		Ok(Server {
-			listener,
-			local_addr,
+			listeners: vec![listener],
+			local_addrs,
 			access_control: self.access_control,
 			max_request_body_size: self.max_request_body_size,
 			max_response_body_size: self.max_response_body_size,
@@ -285,7 +933,32 @@ impl<M> Builder<M> {
 			tokio_runtime: self.tokio_runtime,
 			middleware: self.middleware,
 			max_log_length: self.max_log_length,
-			health_api: self.health_api,
+			get_routes: self.get_routes,
+			draining_status: self.draining_status,
+			max_concurrent_requests: self.max_concurrent_requests,
+			concurrent_requests_wait: self.concurrent_requests_wait,
+			stream_batch_responses: self.stream_batch_responses,
+			amortize_batch_resource_claims: self.amortize_batch_resource_claims,
+			max_batch_concurrency: self.max_batch_concurrency,
+			max_batch_size: self.max_batch_size,
+			http_status_backpressure: self.http_status_backpressure,
+			response_compression: self.response_compression,
+			json_rpc_v1_compat: self.json_rpc_v1_compat,
+			system_limits_method: self.system_limits_method,
+			pipelining: self.pipelining,
+			http2: self.http2,
+			pooled_body_buffers: self.pooled_body_buffers,
+			rate_limiter: self.rate_limiter,
+			ip_filter: self.ip_filter,
+			trusted_proxies: self.trusted_proxies,
+			circuit_breaker: self.circuit_breaker,
+			load_shedder: self.load_shedder,
+			wire_tap: self.wire_tap,
+			authenticator: self.authenticator,
+			basic_auth: self.basic_auth,
+			panic_hook: self.panic_hook,
+			number_policy: self.number_policy,
+			resource_usage_interval: self.resource_usage_interval,
 		})
 	}
 
@@ -305,14 +978,18 @@ impl<M> Builder<M> {
 	/// }
 	/// ```
 	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
-		let listener = TcpListener::bind(addrs).await?.into_std()?;
+		let tcp = TcpSettings { nodelay: Some(self.tcp.nodelay.unwrap_or(true)), ..self.tcp };
+		let mut listeners = Vec::new();
+		let mut local_addrs = Vec::new();
 
-		let local_addr = listener.local_addr().ok();
-		let listener = hyper::Server::from_tcp(listener)?.tcp_nodelay(true);
+		for listener in self.bind.bind(lookup_host(addrs).await?)? {
+			local_addrs.extend(listener.local_addr().ok());
+			listeners.push(configure_tcp(&tcp, listener, self.proxy_protocol)?);
+		}
 
 		Ok(Server {
-			listener,
-			local_addr,
+			listeners,
+			local_addrs,
 			access_control: self.access_control,
 			max_request_body_size: self.max_request_body_size,
 			max_response_body_size: self.max_response_body_size,
@@ -321,15 +998,201 @@ impl<M> Builder<M> {
 			tokio_runtime: self.tokio_runtime,
 			middleware: self.middleware,
 			max_log_length: self.max_log_length,
-			health_api: self.health_api,
+			get_routes: self.get_routes,
+			draining_status: self.draining_status,
+			max_concurrent_requests: self.max_concurrent_requests,
+			concurrent_requests_wait: self.concurrent_requests_wait,
+			stream_batch_responses: self.stream_batch_responses,
+			amortize_batch_resource_claims: self.amortize_batch_resource_claims,
+			max_batch_concurrency: self.max_batch_concurrency,
+			max_batch_size: self.max_batch_size,
+			http_status_backpressure: self.http_status_backpressure,
+			response_compression: self.response_compression,
+			json_rpc_v1_compat: self.json_rpc_v1_compat,
+			system_limits_method: self.system_limits_method,
+			pipelining: self.pipelining,
+			http2: self.http2,
+			pooled_body_buffers: self.pooled_body_buffers,
+			rate_limiter: self.rate_limiter,
+			ip_filter: self.ip_filter,
+			trusted_proxies: self.trusted_proxies,
+			circuit_breaker: self.circuit_breaker,
+			load_shedder: self.load_shedder,
+			wire_tap: self.wire_tap,
+			authenticator: self.authenticator,
+			basic_auth: self.basic_auth,
+			panic_hook: self.panic_hook,
+			number_policy: self.number_policy,
+			resource_usage_interval: self.resource_usage_interval,
 		})
 	}
 }
 
+/// Apply the TCP options to the listener and the connections accepted by hyper, which start with a PROXY protocol
+/// header if `proxy_protocol` is set.
+fn configure_tcp(tcp: &TcpSettings, listener: StdTcpListener, proxy_protocol: bool) -> Result<Listener, Error> {
+	tcp.apply_to_listener(&listener)?;
+
+	listener.set_nonblocking(true)?;
+	let mut incoming = AddrIncoming::from_listener(tokio::net::TcpListener::from_std(listener)?)?;
+	if let Some(nodelay) = tcp.nodelay {
+		incoming.set_nodelay(nodelay);
+	}
+	if let Some(keepalive) = tcp.keepalive {
+		incoming
+			.set_keepalive(keepalive.time)
+			.set_keepalive_interval(keepalive.interval)
+			.set_keepalive_retries(keepalive.retries);
+	}
+	Ok(Listener::Tracked(hyper::Server::builder(TrackedIncoming::new(incoming, proxy_protocol))))
+}
+
+/// Listener of a server.
+#[derive(Debug)]
+enum Listener {
+	/// Listener configured by the user, see [`Builder::build_from_hyper`].
+	Hyper(HyperBuilder<AddrIncoming>),
+	/// Listener whose connections are checked for pipelined requests.
+	Tracked(HyperBuilder<TrackedIncoming>),
+}
+
 #[derive(Debug, Clone)]
-struct HealthApi {
+struct GetRoute {
 	path: String,
-	method: String,
+	handler: GetHandler,
+}
+
+#[derive(Clone)]
+enum GetHandler {
+	/// Health endpoint, `liveness` is set if it keeps invoking its methods while the server is draining.
+	Health { checks: HealthChecks, liveness: bool },
+	/// Metrics endpoint.
+	Metrics(Arc<dyn MetricsEncoder>),
+	/// User-provided handler.
+	Custom(Arc<dyn RouteHandler>),
+}
+
+impl std::fmt::Debug for GetHandler {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Health { checks, liveness } => {
+				f.debug_struct("Health").field("checks", checks).field("liveness", liveness).finish()
+			}
+			Self::Metrics(_) => f.write_str("Metrics"),
+			Self::Custom(_) => f.write_str("Custom"),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+enum HealthChecks {
+	/// Respond with the result of one method.
+	Single(String),
+	/// Respond with a summary of several methods combined by the policy.
+	Aggregate(Vec<String>, HealthPolicy),
+}
+
+/// How the outcomes of an aggregated health endpoint are combined, see [`Builder::health_api_aggregate`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HealthPolicy {
+	/// Every method must succeed.
+	All,
+	/// At least this many methods must succeed.
+	Quorum(usize),
+}
+
+impl HealthPolicy {
+	fn is_met(&self, succeeded: usize, total: usize) -> bool {
+		match *self {
+			Self::All => succeeded == total,
+			Self::Quorum(quorum) => succeeded >= quorum,
+		}
+	}
+}
+
+/// Produces the response body of the metrics endpoint, see [`Builder::metrics_api`].
+///
+/// Implemented for closures returning the metrics in the Prometheus text format.
+pub trait MetricsEncoder: Send + Sync + 'static {
+	/// Value of the `content-type` header of the response.
+	fn content_type(&self) -> &'static str {
+		"text/plain; version=0.0.4"
+	}
+
+	/// Encode the current state of the metrics.
+	fn encode(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl<F> MetricsEncoder for F
+where
+	F: Fn() -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> + Send + Sync + 'static,
+{
+	fn encode(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+		(self)()
+	}
+}
+
+/// Handles the `GET` requests to a path, see [`Builder::get_route`].
+///
+/// Implemented for async closures taking the request and returning the response.
+pub trait RouteHandler: Send + Sync + 'static {
+	/// Produce the response to `request`.
+	fn handle(&self, request: hyper::Request<hyper::Body>) -> BoxFuture<'static, hyper::Response<hyper::Body>>;
+}
+
+impl<F, Fut> RouteHandler for F
+where
+	F: Fn(hyper::Request<hyper::Body>) -> Fut + Send + Sync + 'static,
+	Fut: Future<Output = hyper::Response<hyper::Body>> + Send + 'static,
+{
+	fn handle(&self, request: hyper::Request<hyper::Body>) -> BoxFuture<'static, hyper::Response<hyper::Body>> {
+		Box::pin((self)(request))
+	}
+}
+
+/// Limits the number of requests that are processed concurrently.
+#[derive(Debug, Clone)]
+struct ConcurrencyLimit {
+	permits: Arc<Semaphore>,
+	wait: Option<Duration>,
+}
+
+impl ConcurrencyLimit {
+	fn new(max: u32, wait: Option<Duration>) -> Self {
+		Self { permits: Arc::new(Semaphore::new(max as usize)), wait }
+	}
+
+	/// Attempts to acquire a slot, waiting at most the configured duration for one to become available.
+	///
+	/// Returns `None` if no slot could be acquired.
+	async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+		let permits = self.permits.clone();
+
+		match self.wait {
+			None => permits.try_acquire_owned().ok(),
+			Some(wait) => tokio::time::timeout(wait, permits.acquire_owned()).await.ok()?.ok(),
+		}
+	}
+}
+
+/// Delay advertised to clients in the `Retry-After` header when the server is busy.
+const BUSY_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Default number of request body buffers kept for reuse.
+const DEFAULT_POOLED_BODY_BUFFERS: usize = 16;
+
+/// Capacity beyond which request body buffers are released rather than reused.
+const MAX_POOLED_BODY_CAPACITY: usize = 1024 * 1024;
+
+/// Reason why a single call was rejected because of the load on the server.
+#[derive(Debug, Clone, Copy)]
+enum Backpressure {
+	/// The client exceeded its rate limit and may retry after the given duration.
+	RateLimited(Duration),
+	/// The resources required by the call are exhausted.
+	ServerIsBusy,
+	/// The circuit of the method is open and the call may be retried after the given duration.
+	CircuitOpen(Duration),
 }
 
 /// Handle used to run or stop the server.
@@ -337,17 +1200,58 @@ struct HealthApi {
 pub struct ServerHandle {
 	stop_sender: mpsc::Sender<()>,
 	pub(crate) handle: Option<tokio::task::JoinHandle<()>>,
+	draining: Arc<AtomicBool>,
+	methods: MethodsHandle,
+	shutdown: ShutdownRecord,
 }
 
 impl ServerHandle {
+	/// Returns a handle to add and remove methods while the server is running.
+	pub fn methods(&self) -> &MethodsHandle {
+		&self.methods
+	}
+
+	/// Returns the resources of the server, to adjust their capacities while the server is running.
+	pub fn resources(&self) -> &Resources {
+		self.methods.resources()
+	}
+
+	/// Starts draining the server.
+	///
+	/// The health endpoint responds with the status configured by [`Builder::health_draining_status`]
+	/// from now on while all other requests are still served, so that load balancers remove the server
+	/// from rotation before it's stopped.
+	pub fn drain(&self) {
+		self.draining.store(true, Ordering::Relaxed);
+	}
+
+	/// Returns whether the server is draining.
+	pub fn is_draining(&self) -> bool {
+		self.draining.load(Ordering::Relaxed)
+	}
+
 	/// Requests server to stop. Returns an error if server was already stopped.
-	pub fn stop(mut self) -> Result<tokio::task::JoinHandle<()>, Error> {
+	pub fn stop(self) -> Result<tokio::task::JoinHandle<()>, Error> {
+		self.stop_with_reason(ShutdownReason::Stopped)
+	}
+
+	/// Requests server to stop for the given reason, for instance [`ShutdownReason::Restart`] if it's about to
+	/// be restarted. Returns an error if server was already stopped.
+	pub fn stop_with_reason(mut self, reason: ShutdownReason) -> Result<tokio::task::JoinHandle<()>, Error> {
 		let stop = self.stop_sender.try_send(()).map(|_| self.handle.take());
 		match stop {
-			Ok(Some(handle)) => Ok(handle),
+			Ok(Some(handle)) => {
+				self.shutdown.record(reason);
+				Ok(handle)
+			}
 			_ => Err(Error::AlreadyStopped),
 		}
 	}
+
+	/// Returns why the server stopped or is stopping, `None` while it's running.
+	pub fn shutdown_reason(&self) -> Option<ShutdownReason> {
+		self.shutdown.reason()
+	}
 }
 
 impl Future for ServerHandle {
@@ -363,13 +1267,21 @@ impl Future for ServerHandle {
 	}
 }
 
+impl ServerControl for ServerHandle {
+	fn request_stop(&mut self) {
+		if self.stop_sender.try_send(()).is_ok() {
+			self.shutdown.record(ShutdownReason::Stopped);
+		}
+	}
+}
+
 /// An HTTP JSON RPC server.
 #[derive(Debug)]
 pub struct Server<M = ()> {
-	/// Hyper server.
-	listener: HyperBuilder<AddrIncoming>,
-	/// Local address
-	local_addr: Option<SocketAddr>,
+	/// Hyper servers, one for every listening socket.
+	listeners: Vec<Listener>,
+	/// Local addresses of the listening sockets.
+	local_addrs: Vec<SocketAddr>,
 	/// Max request body size.
 	max_request_body_size: u32,
 	/// Max response body size.
@@ -387,51 +1299,214 @@ pub struct Server<M = ()> {
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
-	health_api: Option<HealthApi>,
+	/// Handlers of `GET` requests, by path.
+	get_routes: Vec<GetRoute>,
+	/// Status code of the health endpoint while the server is draining.
+	draining_status: StatusCode,
+	/// Max number of concurrently processed requests.
+	max_concurrent_requests: Option<u32>,
+	/// Max time a request may wait for a free slot before it's rejected.
+	concurrent_requests_wait: Option<Duration>,
+	/// Whether batch responses are streamed back as the calls complete.
+	stream_batch_responses: bool,
+	/// Whether the resources of homogeneous batches are claimed once per batch.
+	amortize_batch_resource_claims: bool,
+	/// Maximum number of calls of a batch executed concurrently, `None` for no limit.
+	max_batch_concurrency: Option<usize>,
+	/// Maximum number of calls in a batch, `None` for no limit.
+	max_batch_size: Option<usize>,
+	/// Whether rejections caused by the load on the server are signaled with HTTP status codes.
+	http_status_backpressure: bool,
+	/// Compression of the responses, `None` if disabled.
+	response_compression: Option<ResponseCompression>,
+	/// Whether JSON-RPC 1.0 requests are accepted.
+	json_rpc_v1_compat: bool,
+	/// Whether the `system_limits` method is registered.
+	system_limits_method: bool,
+	/// How pipelined HTTP/1.1 requests are handled.
+	pipelining: Pipelining,
+	/// HTTP/2 settings, `None` if only HTTP/1.1 is accepted.
+	http2: Option<Http2>,
+	/// Maximum number of request body buffers kept for reuse.
+	pooled_body_buffers: usize,
+	/// Per-client rate limiter.
+	rate_limiter: RateLimiter,
+	/// Filter of the IP addresses of the clients.
+	ip_filter: IpFilter,
+	/// Proxies trusted to report the address of the clients.
+	trusted_proxies: TrustedProxies,
+	/// Per-method circuit breaker.
+	circuit_breaker: CircuitBreaker,
+	/// Sheds requests under memory pressure.
+	load_shedder: LoadShedder,
+	/// Captures the raw bodies of a sample of connections.
+	wire_tap: WireTap,
+	/// Authentication of clients.
+	authenticator: Authenticator,
+	/// HTTP Basic authentication of clients.
+	basic_auth: Option<BasicAuth>,
+	/// Hook invoked when a method handler panics.
+	panic_hook: Option<PanicHook>,
+	/// Handling of integers that can't be represented exactly as doubles.
+	number_policy: NumberPolicy,
+	/// Period of the reports of the resource usage to the middleware, `None` to not report it.
+	resource_usage_interval: Option<Duration>,
 }
 
 impl<M: Middleware> Server<M> {
 	/// Returns socket address to which the server is bound.
+	///
+	/// If the server is bound to multiple addresses, the first one is returned.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
-		self.local_addr.ok_or_else(|| Error::Custom("Local address not found".into()))
+		self.local_addrs.first().copied().ok_or_else(|| Error::Custom("Local address not found".into()))
+	}
+
+	/// Returns all socket addresses to which the server is bound.
+	pub fn local_addrs(&self) -> &[SocketAddr] {
+		&self.local_addrs
 	}
 
 	/// Start the server.
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
-		let max_request_body_size = self.max_request_body_size;
-		let max_response_body_size = self.max_response_body_size;
-		let max_log_length = self.max_log_length;
+		let cfg = Arc::new(Settings {
+			max_request_body_size: self.max_request_body_size,
+			max_response_body_size: self.max_response_body_size,
+			max_log_length: self.max_log_length,
+			batch_requests_supported: self.batch_requests_supported,
+			stream_batch_responses: self.stream_batch_responses,
+			amortize_batch_resource_claims: self.amortize_batch_resource_claims,
+			max_batch_concurrency: self.max_batch_concurrency,
+			max_batch_size: self.max_batch_size,
+			http_status_backpressure: self.http_status_backpressure,
+			response_compression: self.response_compression,
+			json_rpc_v1_compat: self.json_rpc_v1_compat,
+			panic_hook: self.panic_hook,
+			number_policy: self.number_policy,
+			body_pool: BodyBufferPool::new(self.pooled_body_buffers, MAX_POOLED_BODY_CAPACITY),
+		});
 		let acl = self.access_control;
 		let (tx, mut rx) = mpsc::channel(1);
-		let listener = self.listener;
+		let listeners = self.listeners;
 		let resources = self.resources;
 		let middleware = self.middleware;
-		let batch_requests_supported = self.batch_requests_supported;
-		let methods = methods.into().initialize_resources(&resources)?;
-		let health_api = self.health_api;
-
-		let make_service = make_service_fn(move |_| {
+		let pipelining = self.pipelining;
+		let http2 = self.http2;
+		let rate_limiter = self.rate_limiter;
+		let ip_filter = self.ip_filter;
+		let trusted_proxies = self.trusted_proxies;
+		let circuit_breaker = self.circuit_breaker;
+		let load_shedder = self.load_shedder;
+		let wire_tap = self.wire_tap;
+		let authenticator = self.authenticator;
+		let basic_auth = self.basic_auth;
+		let usage_reports = self
+			.resource_usage_interval
+			.map(|period| report_resource_usage(middleware.clone(), resources.clone(), period));
+		let mut methods = methods.into();
+		if self.system_limits_method {
+			methods.register_system_limits(&SystemLimits {
+				max_request_body_size: cfg.max_request_body_size,
+				max_response_body_size: cfg.max_response_body_size,
+				batch_requests_supported: cfg.batch_requests_supported,
+				max_batch_size: cfg.max_batch_size,
+				max_subscriptions_per_connection: None,
+				rate_limits: rate_limiter.limits(),
+				request_encodings: compression::REQUEST_ENCODINGS.to_vec(),
+				response_encodings: match cfg.response_compression {
+					Some(_) => compression::RESPONSE_ENCODINGS.to_vec(),
+					None => Vec::new(),
+				},
+			})?;
+		}
+		let methods = MethodsHandle::new(methods.initialize_resources(&resources)?, resources.clone());
+		let methods_handle = methods.clone();
+		let get_routes = Arc::new(self.get_routes);
+		let draining_status = self.draining_status;
+		let draining = Arc::new(AtomicBool::new(false));
+		let draining_handle = draining.clone();
+		let concurrency_limit =
+			self.max_concurrent_requests.map(|max| ConcurrencyLimit::new(max, self.concurrent_requests_wait));
+
+		// Builds the service handling the requests of a connection, `detector` is set if pipelined requests are
+		// to be rejected.
+		let make_conn_service = move |remote_addr: SocketAddr, detector: Option<Arc<PipelineDetector>>| {
+			let remote_ip = remote_addr.ip();
+			// The requests of trusted proxies are filtered by the address of their client instead.
+			let proxied = trusted_proxies.is_trusted(remote_ip);
+			let allowed = proxied || ip_filter.is_allowed(remote_ip);
+			let ip_filter = ip_filter.clone();
+			let trusted_proxies = trusted_proxies.clone();
 			let methods = methods.clone();
 			let acl = acl.clone();
-			let resources = resources.clone();
+			let resources = resources.for_connection();
 			let middleware = middleware.clone();
-			let health_api = health_api.clone();
+			let get_routes = get_routes.clone();
+			let draining = draining.clone();
+			let concurrency_limit = concurrency_limit.clone();
+			let rate_limiter = rate_limiter.clone();
+			let circuit_breaker = circuit_breaker.clone();
+			let load_shedder = load_shedder.clone();
+			let authenticator = authenticator.clone();
+			let basic_auth = basic_auth.clone();
+			let cfg = cfg.clone();
+			let tap = wire_tap.session("http", remote_addr);
+			let compression = CompressionReport {
+				stats: cfg.response_compression.map(|compression| compression.stats()).unwrap_or_default(),
+				remote_addr,
+				middleware: middleware.clone(),
+			};
 
 			async move {
-				Ok::<_, HyperError>(service_fn(move |request| {
-					let methods = methods.clone();
+				// Failing to make the service closes the connection before anything is read from it.
+				if !allowed {
+					tracing::warn!("Denied connection from {}: address is not allowed", remote_ip);
+					return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "address is not allowed"));
+				}
+
+				Ok(service_fn(move |request| {
+					// The requests of HTTP/2 connections are multiplexed rather than pipelined.
+					let pipelined = request.version() < hyper::Version::HTTP_2
+						&& matches!(&detector, Some(detector) if detector.on_request());
+					let methods = methods.snapshot();
+					let ip_filter = ip_filter.clone();
+					let trusted_proxies = trusted_proxies.clone();
 					let acl = acl.clone();
 					let resources = resources.clone();
 					let middleware = middleware.clone();
-					let health_api = health_api.clone();
+					let get_routes = get_routes.clone();
+					let draining = draining.clone();
+					let concurrency_limit = concurrency_limit.clone();
+					let rate_limiter = rate_limiter.clone();
+					let circuit_breaker = circuit_breaker.clone();
+					let load_shedder = load_shedder.clone();
+					let authenticator = authenticator.clone();
+					let basic_auth = basic_auth.clone();
+					let cfg = cfg.clone();
+					let tap = tap.clone();
+					let compression = compression.stats();
 
 					// Run some validation on the http request, then read the body and try to deserialize it into one of
 					// two cases: a single RPC request or a batch of RPC requests.
 					async move {
+						if pipelined {
+							tracing::warn!("Denied request: pipelined requests are rejected");
+							return Ok(response::pipelining_not_supported());
+						}
+
+						let client_ip =
+							if proxied { client_ip(&trusted_proxies, &request, remote_ip) } else { remote_ip };
+						if proxied && !ip_filter.is_allowed(client_ip) {
+							tracing::warn!("Denied request from {}: address is not allowed", client_ip);
+							return Ok(response::ip_not_allowed());
+						}
+
 						let keys = request.headers().keys().map(|k| k.as_str());
 						let cors_request_headers = http_helpers::get_cors_request_headers(request.headers());
 
-						let host = match http_helpers::read_header_value(request.headers(), "host") {
+						// HTTP/2 requests carry the host in the `:authority` pseudo-header, which ends up in the URI.
+						let host = match http_helpers::read_header_value(request.headers(), "host")
+							.or_else(|| request.uri().authority().map(|a| a.as_str()))
+						{
 							Some(origin) => origin,
 							None => return Ok(malformed()),
 						};
@@ -465,15 +1540,18 @@ impl<M: Middleware> Server<M> {
 								let allowed_headers = acl.allowed_headers().to_cors_header_value();
 								let allowed_header_bytes = allowed_headers.as_bytes();
 
-								let res = hyper::Response::builder()
+								let mut res = hyper::Response::builder()
 									.header("access-control-allow-origin", origin)
 									.header("access-control-allow-methods", "POST")
-									.header("access-control-allow-headers", allowed_header_bytes)
-									.body(hyper::Body::empty())
-									.unwrap_or_else(|e| {
-										tracing::error!("Error forming preflight response: {}", e);
-										internal_error()
-									});
+									.header("access-control-allow-headers", allowed_header_bytes);
+								for (name, value) in acl.preflight_headers() {
+									res = res.header(name, value);
+								}
+
+								let res = res.body(hyper::Body::empty()).unwrap_or_else(|e| {
+									tracing::error!("Error forming preflight response: {}", e);
+									internal_error()
+								});
 
 								Ok(res)
 							}
@@ -481,36 +1559,98 @@ impl<M: Middleware> Server<M> {
 							// the access-control-allow-origin header (despite preflight) to allow it
 							// to be read in a browser.
 							Method::POST if content_type_is_json(&request) => {
+								let mut authorization =
+									http_helpers::read_header_value(request.headers(), "authorization");
+								if let Some(basic_auth) = basic_auth.as_ref() {
+									if !basic_auth.verify(authorization) {
+										tracing::warn!("Denied request: invalid basic auth credentials");
+										return Ok(response::basic_auth_required());
+									}
+									authorization = None;
+								}
+
+								if load_shedder.is_overloaded() {
+									tracing::warn!("Denied request: server is overloaded");
+									return Ok(if cfg.http_status_backpressure {
+										response::service_unavailable(response::server_is_busy_body(), BUSY_RETRY_AFTER)
+									} else {
+										response::server_is_busy()
+									});
+								}
+
+								// Clients are authenticated before taking a permit, so that unauthenticated ones can't hold
+								// all of them.
+								let permissions = match authenticator.authenticate(authorization).await {
+									Some(permissions) => permissions,
+									None => {
+										tracing::warn!("Denied request: invalid credentials");
+										return Ok(response::unauthorized());
+									}
+								};
+
+								// The permit is held until the response has been produced.
+								let permit = match concurrency_limit.as_ref() {
+									Some(limit) => match limit.acquire().await {
+										Some(permit) => Some(permit),
+										None => {
+											tracing::warn!("Denied request: too many concurrent requests");
+											return Ok(if cfg.http_status_backpressure {
+												response::service_unavailable(
+													response::server_is_busy_body(),
+													BUSY_RETRY_AFTER,
+												)
+											} else {
+												response::server_is_busy()
+											});
+										}
+									},
+									None => None,
+								};
+
 								let origin = return_origin_if_different_from_host(request.headers()).cloned();
-								let mut res = process_validated_request(
-									request,
-									middleware,
-									methods,
-									resources,
-									max_request_body_size,
-									max_response_body_size,
-									max_log_length,
-									batch_requests_supported,
+								let policy = CallPolicy::new(
+									authenticator.client(permissions),
+									rate_limiter.client(rate_limit_key(&rate_limiter, &request, client_ip, proxied)),
 								)
-								.await?;
+								.with_circuit_breaker(circuit_breaker.clone())
+								.with_client_ip(client_ip);
+								let ctx = RequestContext { middleware, methods, resources, policy, tap, compression };
+								let mut res = process_validated_request(request, ctx, &cfg, permit).await?;
 
 								if let Some(origin) = origin {
-									res.headers_mut().insert("access-control-allow-origin", origin);
+									let headers = res.headers_mut();
+									headers.insert("access-control-allow-origin", origin);
+									for (name, value) in acl.response_headers() {
+										if let Ok(value) = HeaderValue::from_str(&value) {
+											headers.insert(name, value);
+										}
+									}
 								}
 								Ok(res)
 							}
-							Method::GET => match health_api.as_ref() {
-								Some(health) if health.path.as_str() == request.uri().path() => {
+							Method::GET => match get_routes.iter().find(|route| route.path == request.uri().path()) {
+								Some(GetRoute { handler: GetHandler::Health { checks, liveness }, .. }) => {
+									if !liveness && draining.load(Ordering::Relaxed) {
+										return Ok(response::draining(draining_status));
+									}
+
 									process_health_request(
-										health,
+										checks,
 										middleware,
 										methods,
-										max_response_body_size,
-										max_log_length,
+										cfg.max_response_body_size,
+										cfg.max_log_length,
+										cfg.panic_hook.clone(),
 									)
 									.await
 								}
-								_ => Ok(response::method_not_allowed()),
+								Some(GetRoute { handler: GetHandler::Metrics(encoder), .. }) => {
+									Ok(process_metrics_request(encoder.as_ref()))
+								}
+								Some(GetRoute { handler: GetHandler::Custom(handler), .. }) => {
+									Ok(handler.handle(request).await)
+								}
+								None => Ok(response::method_not_allowed()),
 							},
 							// Error scenarios:
 							Method::POST => Ok(response::unsupported_content_type()),
@@ -519,19 +1659,78 @@ impl<M: Middleware> Server<M> {
 					}
 				}))
 			}
-		});
+		};
 
 		let rt = match self.tokio_runtime.take() {
 			Some(rt) => rt,
 			None => tokio::runtime::Handle::current(),
 		};
 
+		let shutdown_record = ShutdownRecord::new();
+		let failure_record = shutdown_record.clone();
+		let (failure_tx, mut failure_rx) = mpsc::channel(1);
+
 		let handle = rt.spawn(async move {
-			let server = listener.serve(make_service);
-			let _ = server.with_graceful_shutdown(async move { rx.next().await.map_or((), |_| ()) }).await;
+			// Dropping the sender notifies all servers to shut down.
+			let (stop_tx, stop_rx) = watch::channel(());
+			let servers = listeners.into_iter().map(|listener| {
+				let mut stop_rx = stop_rx.clone();
+				let shutdown = async move {
+					let _ = stop_rx.changed().await;
+				};
+				let make_conn_service = make_conn_service.clone();
+				let failure_record = failure_record.clone();
+				let mut failure_tx = failure_tx.clone();
+
+				let server = match listener {
+					Listener::Hyper(listener) => listener
+						.serve(make_service_fn(move |conn: &AddrStream| make_conn_service(conn.remote_addr(), None)))
+						.with_graceful_shutdown(shutdown)
+						.boxed(),
+					Listener::Tracked(listener) => Http2::configure(http2.as_ref(), listener)
+						.serve(make_service_fn(move |conn: &TrackedStream| {
+							let detector = (pipelining == Pipelining::Reject).then(|| conn.detector());
+							make_conn_service(conn.remote_addr(), detector)
+						}))
+						.with_graceful_shutdown(shutdown)
+						.boxed(),
+				};
+
+				// A listener that fails stops the whole server.
+				server.map(move |res| {
+					if let Err(err) = res {
+						tracing::error!("Server failed: {}", err);
+						if failure_record.record(ShutdownReason::Failed(err.to_string())) {
+							let _ = failure_tx.try_send(());
+						}
+					}
+				})
+			});
+
+			let stop = async move {
+				future::select(rx.next(), failure_rx.next()).await;
+				drop(stop_tx);
+			};
+			let serve = future::join(join_all(servers), stop);
+
+			// The reports never end, they're dropped once the server stopped.
+			match usage_reports {
+				Some(usage_reports) => {
+					future::select(Box::pin(serve), Box::pin(usage_reports)).await;
+				}
+				None => {
+					serve.await;
+				}
+			}
 		});
 
-		Ok(ServerHandle { handle: Some(handle), stop_sender: tx })
+		Ok(ServerHandle {
+			handle: Some(handle),
+			stop_sender: tx,
+			draining: draining_handle,
+			methods: methods_handle,
+			shutdown: shutdown_record,
+		})
 	}
 }
 
@@ -549,6 +1748,29 @@ fn return_origin_if_different_from_host(headers: &HeaderMap) -> Option<&HeaderVa
 	}
 }
 
+/// Returns the address of the client of `request`, as reported by the trusted proxy it was received from.
+fn client_ip(proxies: &TrustedProxies, request: &hyper::Request<hyper::Body>, remote_ip: IpAddr) -> IpAddr {
+	let forwarded = http_helpers::join_header_values(request.headers(), "forwarded");
+	let x_forwarded_for = http_helpers::join_header_values(request.headers(), "x-forwarded-for");
+	proxies.client_ip(remote_ip, forwarded.as_deref(), x_forwarded_for.as_deref())
+}
+
+/// Identifies the client by the configured rate limiting header if present, otherwise by its IP address.
+fn rate_limit_key(
+	limiter: &RateLimiter,
+	request: &hyper::Request<hyper::Body>,
+	client_ip: IpAddr,
+	proxied: bool,
+) -> RateLimitKey {
+	// Clients could pick a new value for every request to bypass the limits, so the header is only trusted when
+	// set by a trusted proxy.
+	limiter
+		.key_header()
+		.filter(|_| proxied)
+		.and_then(|header| http_helpers::read_header_value(request.headers(), header))
+		.map_or(RateLimitKey::Ip(client_ip), |value| RateLimitKey::Header(value.into()))
+}
+
 /// Checks that content type of received request is valid for JSON-RPC.
 fn content_type_is_json(request: &hyper::Request<hyper::Body>) -> bool {
 	is_json(request.headers().get("content-type"))
@@ -568,22 +1790,92 @@ fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool {
 	}
 }
 
-/// Process a verified request, it implies a POST request with content type JSON.
-async fn process_validated_request(
-	request: hyper::Request<hyper::Body>,
-	middleware: impl Middleware,
-	methods: Methods,
-	resources: Resources,
+/// Settings of the server used to process the requests, shared by all connections.
+struct Settings {
+	/// Max request body size.
 	max_request_body_size: u32,
+	/// Max response body size.
 	max_response_body_size: u32,
+	/// Max length for logging for request and response.
 	max_log_length: u32,
+	/// Whether batch requests are supported by this server or not.
 	batch_requests_supported: bool,
+	/// Whether batch responses are streamed back as the calls complete.
+	stream_batch_responses: bool,
+	/// Whether the resources of homogeneous batches are claimed once per batch.
+	amortize_batch_resource_claims: bool,
+	/// Maximum number of calls of a batch executed concurrently, `None` for no limit.
+	max_batch_concurrency: Option<usize>,
+	/// Maximum number of calls in a batch, `None` for no limit.
+	max_batch_size: Option<usize>,
+	/// Whether rejections caused by the load on the server are signaled with HTTP status codes.
+	http_status_backpressure: bool,
+	/// Compression of the responses, `None` if disabled.
+	response_compression: Option<ResponseCompression>,
+	/// Whether JSON-RPC 1.0 requests are accepted.
+	json_rpc_v1_compat: bool,
+	/// Hook invoked when a method handler panics.
+	panic_hook: Option<PanicHook>,
+	/// Handling of integers that can't be represented exactly as doubles.
+	number_policy: NumberPolicy,
+	/// Buffers reused to read the request bodies.
+	body_pool: BodyBufferPool,
+}
+
+/// What a request is processed with, besides the settings of the server.
+#[derive(Clone)]
+struct RequestContext<M> {
+	middleware: M,
+	/// The methods at the time the request was received.
+	methods: Methods,
+	resources: Resources,
+	policy: CallPolicy,
+	tap: Option<WireTapSession>,
+	/// Compression statistics of the connection.
+	compression: CompressionStats,
+}
+
+/// Reports the compression statistics of an HTTP connection to the middleware once it's closed.
+struct CompressionReport<M: Middleware> {
+	stats: CompressionStats,
+	remote_addr: SocketAddr,
+	middleware: M,
+}
+
+impl<M: Middleware> CompressionReport<M> {
+	fn stats(&self) -> CompressionStats {
+		self.stats.clone()
+	}
+}
+
+impl<M: Middleware> Drop for CompressionReport<M> {
+	fn drop(&mut self) {
+		if let Some(info) = self.stats.info(self.remote_addr, Transport::Http) {
+			self.middleware.on_compression_info(&info);
+		}
+	}
+}
+
+/// Process a verified request, it implies a POST request with content type JSON.
+async fn process_validated_request<M: Middleware>(
+	request: hyper::Request<hyper::Body>,
+	ctx: RequestContext<M>,
+	cfg: &Arc<Settings>,
+	permit: Option<OwnedSemaphorePermit>,
 ) -> Result<hyper::Response<hyper::Body>, HyperError> {
 	let (parts, body) = request.into_parts();
 
-	let (body, mut is_single) = match read_body(&parts.headers, body, max_request_body_size).await {
+	let read = match compression::is_gzip_body(&parts.headers) {
+		Ok(false) => read_pooled_body(&parts.headers, body, cfg.max_request_body_size, &cfg.body_pool).await,
+		Ok(true) => compression::read_gzip_body(body, cfg.max_request_body_size)
+			.await
+			.map(|(body, is_single)| (PooledBody::from(body), is_single)),
+		Err(()) => return Ok(response::unsupported_content_encoding()),
+	};
+
+	let (body, mut is_single) = match read {
 		Ok(r) => r,
-		Err(GenericTransportError::TooLarge) => return Ok(response::too_large(max_request_body_size)),
+		Err(GenericTransportError::TooLarge) => return Ok(response::too_large(cfg.max_request_body_size)),
 		Err(GenericTransportError::Malformed) => return Ok(response::malformed()),
 		Err(GenericTransportError::Inner(e)) => {
 			tracing::error!("Internal error reading request body: {}", e);
@@ -591,155 +1883,152 @@ async fn process_validated_request(
 		}
 	};
 
+	if let Some(tap) = ctx.tap.as_ref() {
+		tap.record(Direction::Inbound, &body);
+	}
+
+	// JSON-RPC 1.0 requests are processed as 2.0 requests and the response is converted back.
+	let (body, is_v1) = match cfg.json_rpc_v1_compat.then(|| v1::request_to_v2(&body)).flatten() {
+		Some(body) => (PooledBody::from(body), true),
+		None => (body, false),
+	};
+
+	let body = if !is_single && cfg.batch_requests_supported && cfg.stream_batch_responses && !is_v1 {
+		match process_streamed_batch(body, parts.uri.clone(), ctx.clone(), cfg.clone(), permit).await {
+			Ok(response) => return Ok(response),
+			// Not a non-empty batch of calls, handled as usual below.
+			Err(body) => body,
+		}
+	} else {
+		body
+	};
+
+	let RequestContext { middleware, methods, resources, policy, tap, compression } = &ctx;
 	let request_start = middleware.on_request();
 
 	// NOTE(niklasad1): it's a channel because it's needed for batch requests.
-	let (tx, mut rx) = mpsc::unbounded::<String>();
-	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
+	let (tx, mut rx) = mpsc::unbounded::<Bytes>();
+	let sink = MethodSink::new_with_limit(tx, cfg.max_response_body_size, cfg.max_log_length)
+		.with_panic_hook(cfg.panic_hook.clone())
+		.with_number_policy(cfg.number_policy);
 
-	type Notif<'a> = Notification<'a, Option<&'a RawValue>>;
+	// Set when a single call is rejected because of the load on the server.
+	let mut backpressure = None;
 
 	// Single request or notification
 	if is_single {
-		if let Ok(req) = serde_json::from_slice::<Request>(&body) {
+		if let Ok(req) = parse_request(&body) {
 			let method = req.method.as_ref();
+			let sink = sink.for_call();
 
 			let trace = RpcTracing::method_call(&req.method);
 			let _enter = trace.span().enter();
 
-			rx_log_from_json(&req, max_log_length);
-			middleware.on_call(method);
+			rx_log_from_json(&req, cfg.max_log_length);
 
 			let id = req.id.clone();
 			let params = Params::new(Some(parts.uri.path()), req.params.map(|params| params.get()));
-
-			let result = match methods.method_with_name(method) {
-				None => {
-					sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-					false
-				}
-				Some((name, method_callback)) => match method_callback.inner() {
-					MethodKind::Sync(callback) => match method_callback.claim(&req.method, &resources) {
-						Ok(guard) => {
-							let result = (callback)(id, params, &sink);
-							drop(guard);
-							result
-						}
-						Err(err) => {
-							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
-							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-							false
-						}
-					},
-					MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
-						Ok(guard) => {
-							let result = (callback)(id.into_owned(), params.into_owned(), sink.clone(), 0, Some(guard))
-								.in_current_span()
-								.await;
-
-							result
-						}
-						Err(err) => {
-							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
-							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-							false
-						}
-					},
-					MethodKind::Subscription(_) | MethodKind::Unsubscription(_) => {
-						tracing::error!("Subscriptions not supported on HTTP");
-						sink.send_error(req.id, ErrorCode::InternalError.into());
-						false
-					}
-				},
-			};
-			middleware.on_result(&req.method, result, request_start);
-		} else if let Ok(req) = serde_json::from_slice::<Notif>(&body) {
-			let trace = RpcTracing::notification(&req.method);
-			let _enter = trace.span().enter();
-
-			rx_log_from_json(&req, max_log_length);
-
-			return Ok::<_, HyperError>(response::ok_response("".into()));
-		} else {
-			let (id, code) = prepare_error(&body);
-			sink.send_error(id, code.into());
-		}
-	// Batch of requests or notifications
-	} else if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&body) {
-		let trace = RpcTracing::batch();
-		let _enter = trace.span().enter();
-
-		rx_log_from_json(&batch, max_log_length);
-
-		if !batch_requests_supported {
-			// Server was configured to not support batches.
-			is_single = true;
-			sink.send_error(
-				Id::Null,
-				ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
+			middleware.on_call_info(
+				&CallInfo::new(method, &params, &req.id, Transport::Http).with_client_ip(policy.client_ip()),
 			);
-		} else if !batch.is_empty() {
-			let middleware = &middleware;
-
-			join_all(batch.into_iter().filter_map(move |req| {
-				let id = req.id.clone();
-				let params = Params::new(Some(parts.uri.path()), req.params.map(|params| params.get()));
 
-				match methods.method_with_name(&req.method) {
+			let result = if let Err(denied) = policy.check(method, methods) {
+				let err = ErrorObject::from(denied);
+				tracing::warn!("Denied call to `{}`: {}", method, err.message());
+				match denied {
+					CallDenied::RateLimited(retry_after) => backpressure = Some(Backpressure::RateLimited(retry_after)),
+					CallDenied::CircuitOpen(retry_after) => backpressure = Some(Backpressure::CircuitOpen(retry_after)),
+					CallDenied::Unauthorized(_) => {}
+				}
+				sink.send_error(req.id, err);
+				false
+			} else if let Err(err) = middleware.on_call_async(method, &params).await {
+				tracing::warn!("Denied call to `{}`: {}", method, err.message());
+				sink.send_error(req.id, err);
+				false
+			} else {
+				match methods.method_with_name(method) {
 					None => {
 						sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-						None
+						false
 					}
 					Some((name, method_callback)) => match method_callback.inner() {
-						MethodKind::Sync(callback) => match method_callback.claim(name, &resources) {
+						MethodKind::Sync(callback) => match method_callback.claim(&req.method, resources) {
 							Ok(guard) => {
 								let result = (callback)(id, params, &sink);
-								middleware.on_result(name, result, request_start);
+								policy.record(name, &sink);
 								drop(guard);
-								None
+								result
 							}
 							Err(err) => {
 								tracing::error!(
 									"[Methods::execute_with_resources] failed to lock resources: {:?}",
 									err
 								);
+								backpressure = Some(Backpressure::ServerIsBusy);
 								sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-								middleware.on_result(name, false, request_start);
-								None
+								false
 							}
 						},
-						MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
+						MethodKind::Async(callback) => match method_callback.claim(name, resources) {
 							Ok(guard) => {
-								let sink = sink.clone();
-								let id = id.into_owned();
-								let params = params.into_owned();
-								let callback = callback.clone();
-
-								Some(async move {
-									let result = (callback)(id, params, sink, 0, Some(guard)).in_current_span().await;
-									middleware.on_result(name, result, request_start);
-								})
+								let result =
+									(callback)(id.into_owned(), params.into_owned(), sink.clone(), 0, Some(guard))
+										.in_current_span()
+										.await;
+								policy.record(name, &sink);
+
+								result
 							}
 							Err(err) => {
 								tracing::error!(
 									"[Methods::execute_with_resources] failed to lock resources: {:?}",
 									err
 								);
+								backpressure = Some(Backpressure::ServerIsBusy);
 								sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-								middleware.on_result(name, false, request_start);
-								None
+								false
 							}
 						},
 						MethodKind::Subscription(_) | MethodKind::Unsubscription(_) => {
 							tracing::error!("Subscriptions not supported on HTTP");
 							sink.send_error(req.id, ErrorCode::InternalError.into());
-							middleware.on_result(&req.method, false, request_start);
-							None
+							false
 						}
 					},
 				}
-			}))
-			.await;
+			};
+			report_result(middleware, &req.method, result, &sink, request_start);
+		} else if let Ok(req) = parse_notification(&body) {
+			let trace = RpcTracing::notification(&req.method);
+			let _enter = trace.span().enter();
+
+			rx_log_from_json(&req, cfg.max_log_length);
+
+			return Ok::<_, HyperError>(response::ok_response(""));
+		} else {
+			let (id, code) = prepare_error(&body);
+			sink.send_error(id, code.into());
+		}
+	// Batch of requests or notifications
+	} else if let Ok(batch) = parse_batch(&body) {
+		let trace = RpcTracing::batch();
+		let _enter = trace.span().enter();
+
+		rx_log_from_json(&batch, cfg.max_log_length);
+
+		if !cfg.batch_requests_supported {
+			// Server was configured to not support batches.
+			is_single = true;
+			sink.send_error(
+				Id::Null,
+				ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
+			);
+		} else if let Some(max) = cfg.max_batch_size.filter(|max| batch.len() > *max) {
+			is_single = true;
+			sink.send_error(Id::Null, reject_too_large_batch(max));
+		} else if !batch.is_empty() {
+			execute_batch(batch, &sink, &ctx, cfg, request_start, parts.uri.path()).await;
 		} else {
 			// "If the batch rpc call itself fails to be recognized as an valid JSON or as an
 			// Array with at least one value, the response from the Server MUST be a single
@@ -747,8 +2036,8 @@ async fn process_validated_request(
 			is_single = true;
 			sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
 		}
-	} else if let Ok(_batch) = serde_json::from_slice::<Vec<Notif>>(&body) {
-		return Ok(response::ok_response("".into()));
+	} else if let Ok(_batch) = parse_notification_batch(&body) {
+		return Ok(response::ok_response(""));
 	} else {
 		// "If the batch rpc call itself fails to be recognized as an valid JSON or as an
 		// Array with at least one value, the response from the Server MUST be a single
@@ -766,24 +2055,332 @@ async fn process_validated_request(
 	} else {
 		collect_batch_response(rx).await
 	};
+	let response =
+		if is_v1 { Bytes::from(v1::response_to_v1(String::from_utf8_lossy(&response).into_owned())) } else { response };
+
+	if let Some(tap) = tap.as_ref() {
+		tap.record(Direction::Outbound, &response);
+	}
+
+	report_response(middleware, response.len(), request_start);
+
+	let (response, headers) = match cfg.response_compression {
+		Some(config) => config.compress(&parts.headers, response, compression),
+		None => (response, HeaderMap::new()),
+	};
+	let mut res = match backpressure.filter(|_| cfg.http_status_backpressure) {
+		Some(Backpressure::RateLimited(retry_after)) => response::too_many_requests(response, retry_after),
+		Some(Backpressure::ServerIsBusy) => response::service_unavailable(response, BUSY_RETRY_AFTER),
+		Some(Backpressure::CircuitOpen(retry_after)) => response::service_unavailable(response, retry_after),
+		None => response::ok_response(response),
+	};
+	res.headers_mut().extend(headers);
+	Ok(res)
+}
+
+/// Process a batch request in a separate task and stream the responses back as a chunked JSON array
+/// in the order the calls complete.
+///
+/// Gives back the request body if it's not a non-empty batch of calls.
+async fn process_streamed_batch<M: Middleware>(
+	body: PooledBody,
+	uri: hyper::Uri,
+	ctx: RequestContext<M>,
+	cfg: Arc<Settings>,
+	permit: Option<OwnedSemaphorePermit>,
+) -> Result<hyper::Response<hyper::Body>, PooledBody> {
+	let (tx_response, rx_response) = oneshot::channel();
+
+	tokio::spawn(async move {
+		let batch = match parse_batch(&body) {
+			// Too large batches are rejected as usual.
+			Ok(batch) if !batch.is_empty() && !matches!(cfg.max_batch_size, Some(max) if batch.len() > max) => batch,
+			not_a_batch => {
+				// Release the borrow of `body` before handing it back.
+				drop(not_a_batch);
+				let _ = tx_response.send(Err(body));
+				return;
+			}
+		};
+
+		let trace = RpcTracing::batch();
+		rx_log_from_json(&batch, cfg.max_log_length);
+
+		let request_start = ctx.middleware.on_request();
+		let (tx, rx) = mpsc::unbounded::<Bytes>();
+		let sink = MethodSink::new_with_limit(tx, cfg.max_response_body_size, cfg.max_log_length)
+			.with_panic_hook(cfg.panic_hook.clone())
+			.with_number_policy(cfg.number_policy);
+
+		// The response body is dropped when the client goes away, there's nobody waiting for the
+		// remaining responses then and the calls still running are cancelled.
+		let (body_alive, body_dropped) = oneshot::channel::<()>();
+		let body = batch_response_body(rx, ctx.tap.clone(), body_alive);
+		if tx_response.send(Ok(response::ok_streamed_response(body))).is_err() {
+			return;
+		}
+
+		let batch = execute_batch(batch, &sink, &ctx, &cfg, request_start, uri.path()).instrument(trace.span().clone());
+
+		if let future::Either::Right(_) = future::select(Box::pin(batch), body_dropped).await {
+			tracing::debug!("Client disconnected, cancelled the remaining calls of the batch");
+		}
+
+		// Terminates the response body once all calls have been answered.
+		let size = sink.bytes_sent();
+		drop(sink);
+		drop(permit);
+		report_response(&ctx.middleware, size, request_start);
+	});
+
+	rx_response.await.unwrap_or_else(|_| Ok(response::internal_error()))
+}
+
+/// Build a response body that writes the responses received on `rx` as a JSON array.
+///
+/// Every chunk of the body is captured by `tap` separately. The responses are written as they are, the
+/// separators are chunks of their own. `alive` is dropped along with the body.
+fn batch_response_body(
+	rx: mpsc::UnboundedReceiver<Bytes>,
+	tap: Option<WireTapSession>,
+	alive: oneshot::Sender<()>,
+) -> hyper::Body {
+	let mut first = true;
+	let responses = rx.flat_map(move |response| {
+		let separator = if first { None } else { Some(Bytes::from_static(b",")) };
+		first = false;
+		stream::iter(separator.into_iter().chain(Some(response)))
+	});
+
+	let array = stream::once(future::ready(Bytes::from_static(b"[")))
+		.chain(responses)
+		.chain(stream::once(future::ready(Bytes::from_static(b"]"))));
+
+	let chunks = array.inspect(move |chunk| {
+		let _alive = &alive;
+		if let Some(tap) = tap.as_ref() {
+			tap.record(Direction::Outbound, chunk);
+		}
+	});
+
+	hyper::Body::wrap_stream(chunks.map(Ok::<_, Infallible>))
+}
+
+/// Execute the calls of a batch request, the responses are sent to `sink` as soon as each call completes.
+///
+/// Calls are executed concurrently unless they're hinted to be executed sequentially, see [`batch_stages`].
+/// If `amortize_batch_resource_claims` is set, the resources are claimed once for the whole batch when possible,
+/// see [`Methods::claim_batch`]. At most `max_batch_concurrency` calls of a stage are executed at the same time.
+async fn execute_batch<M: Middleware>(
+	batch: Vec<Request<'_>>,
+	sink: &MethodSink,
+	ctx: &RequestContext<M>,
+	cfg: &Settings,
+	request_start: M::Instant,
+	path: &str,
+) {
+	let RequestContext { middleware, methods, resources, policy, .. } = ctx;
+
+	// Claim the resources once for the whole batch if possible, the calls then don't claim their own.
+	let batch_guard = if cfg.amortize_batch_resource_claims {
+		methods.claim_batch(batch.iter().map(|req| req.method.as_ref()), resources)
+	} else {
+		None
+	};
+	let claim = |name: &str, callback: &MethodCallback| match batch_guard {
+		Some(_) => Ok(None),
+		None => callback.claim(name, resources).map(Some),
+	};
+
+	// Stages are executed one after another, the calls within a stage concurrently, at most
+	// `max_batch_concurrency` at a time.
+	for stage in batch_stages(batch) {
+		let stage = admit_calls(stage, Some(path), Transport::Http, policy, methods, middleware).await;
+
+		// Sync calls are answered right away, the async ones are collected to be executed below.
+		let calls = Vec::from_iter(stage.into_iter().filter_map(move |(req, admitted)| {
+			let sink = sink.for_call();
+
+			if let Err(err) = admitted {
+				tracing::warn!("Denied call to `{}`: {}", req.method, err.message());
+				sink.send_error(req.id, err);
+				report_result(middleware, &req.method, false, &sink, request_start);
+				return None;
+			}
+
+			let id = req.id.clone();
+			let params = Params::new(Some(path), req.params.map(|params| params.get()));
+
+			match methods.method_with_name(&req.method) {
+				None => {
+					sink.send_error(req.id, ErrorCode::MethodNotFound.into());
+					report_result(middleware, &req.method, false, &sink, request_start);
+					None
+				}
+				Some((name, method_callback)) => match method_callback.inner() {
+					MethodKind::Sync(callback) => match claim(name, method_callback) {
+						Ok(guard) => {
+							let result = (callback)(id, params, &sink);
+							policy.record(name, &sink);
+							report_result(middleware, name, result, &sink, request_start);
+							drop(guard);
+							None
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
+							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+							report_result(middleware, name, false, &sink, request_start);
+							None
+						}
+					},
+					MethodKind::Async(callback) => match claim(name, method_callback) {
+						Ok(guard) => {
+							let id = id.into_owned();
+							let params = params.into_owned();
+							let callback = callback.clone();
+
+							Some(async move {
+								let result = (callback)(id, params, sink.clone(), 0, guard).in_current_span().await;
+								policy.record(name, &sink);
+								report_result(middleware, name, result, &sink, request_start);
+							})
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
+							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+							report_result(middleware, name, false, &sink, request_start);
+							None
+						}
+					},
+					MethodKind::Subscription(_) | MethodKind::Unsubscription(_) => {
+						tracing::error!("Subscriptions not supported on HTTP");
+						sink.send_error(req.id, ErrorCode::InternalError.into());
+						report_result(middleware, &req.method, false, &sink, request_start);
+						None
+					}
+				},
+			}
+		}));
+		stream::iter(calls)
+			.buffer_unordered(cfg.max_batch_concurrency.unwrap_or(usize::MAX))
+			.for_each(|_| async {})
+			.await;
+	}
+
+	drop(batch_guard);
+}
 
-	middleware.on_response(request_start);
-	Ok(response::ok_response(response))
+fn process_metrics_request(encoder: &dyn MetricsEncoder) -> hyper::Response<hyper::Body> {
+	match encoder.encode() {
+		Ok(body) => response::ok_metrics_response(body, encoder.content_type()),
+		Err(e) => {
+			tracing::error!("Error encoding metrics: {}", e);
+			response::internal_error()
+		}
+	}
 }
 
 async fn process_health_request(
-	health_api: &HealthApi,
+	checks: &HealthChecks,
 	middleware: impl Middleware,
 	methods: Methods,
 	max_response_body_size: u32,
 	max_log_length: u32,
+	panic_hook: Option<PanicHook>,
 ) -> Result<hyper::Response<hyper::Body>, HyperError> {
-	let (tx, mut rx) = mpsc::unbounded::<String>();
-	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
-
 	let request_start = middleware.on_request();
 
-	let success = match methods.method_with_name(&health_api.method) {
+	let (middleware_ref, methods_ref) = (&middleware, &methods);
+	let health_check = move |method| {
+		run_health_check(
+			method,
+			middleware_ref,
+			methods_ref,
+			max_response_body_size,
+			max_log_length,
+			panic_hook.clone(),
+			request_start,
+		)
+	};
+
+	match checks {
+		HealthChecks::Single(method) => {
+			let (result, size) = health_check(method.as_str()).await;
+			report_response(&middleware, size, request_start);
+
+			match result {
+				Some(result) if reports_healthy(&result) => Ok(response::ok_response(result.get().to_owned())),
+				Some(result) => Ok(response::health_summary(StatusCode::SERVICE_UNAVAILABLE, result.get().to_owned())),
+				None => Ok(response::internal_error()),
+			}
+		}
+		HealthChecks::Aggregate(health_methods, policy) => {
+			#[derive(serde::Serialize)]
+			struct Check<'a> {
+				method: &'a str,
+				healthy: bool,
+				#[serde(skip_serializing_if = "Option::is_none")]
+				result: Option<Box<serde_json::value::RawValue>>,
+			}
+
+			#[derive(serde::Serialize)]
+			struct Summary<'a> {
+				healthy: bool,
+				checks: Vec<Check<'a>>,
+			}
+
+			let results = join_all(health_methods.iter().map(|method| health_check(method.as_str()))).await;
+			let checks: Vec<Check> = health_methods
+				.iter()
+				.zip(results)
+				.map(|(method, (result, _))| Check {
+					method,
+					healthy: result.as_deref().is_some_and(reports_healthy),
+					result,
+				})
+				.collect();
+
+			let succeeded = checks.iter().filter(|check| check.healthy).count();
+			let healthy = policy.is_met(succeeded, checks.len());
+			let body = serde_json::to_string(&Summary { healthy, checks }).expect("valid JSON; qed");
+			report_response(&middleware, body.len(), request_start);
+
+			let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+			Ok(response::health_summary(status, body))
+		}
+	}
+}
+
+/// Returns whether the result of a health check reports the server as healthy, see [`Builder::health_api`].
+fn reports_healthy(result: &serde_json::value::RawValue) -> bool {
+	#[derive(serde::Deserialize)]
+	#[serde(untagged)]
+	enum Reported {
+		Flag(bool),
+		Status { healthy: bool },
+		Other(serde::de::IgnoredAny),
+	}
+
+	match serde_json::from_str(result.get()) {
+		Ok(Reported::Flag(healthy) | Reported::Status { healthy }) => healthy,
+		Ok(Reported::Other(_)) | Err(_) => true,
+	}
+}
+
+/// Invoke `method` without parameters, returning its result if it succeeded and the size of its response.
+async fn run_health_check<M: Middleware>(
+	method: &str,
+	middleware: &M,
+	methods: &Methods,
+	max_response_body_size: u32,
+	max_log_length: u32,
+	panic_hook: Option<PanicHook>,
+	request_start: M::Instant,
+) -> (Option<Box<serde_json::value::RawValue>>, usize) {
+	let (tx, mut rx) = mpsc::unbounded::<Bytes>();
+	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length).with_panic_hook(panic_hook);
+
+	let success = match methods.method_with_name(method) {
 		None => false,
 		Some((name, method_callback)) => match method_callback.inner() {
 			MethodKind::Sync(callback) => {
@@ -805,20 +2402,29 @@ async fn process_health_request(
 	};
 
 	let data = rx.next().await;
-	middleware.on_response(request_start);
 
-	match data {
+	let result = match data {
 		Some(data) if success => {
 			#[derive(serde::Deserialize)]
-			struct RpcPayload<'a> {
-				#[serde(borrow)]
-				result: &'a serde_json::value::RawValue,
+			struct RpcPayload {
+				#[serde(default)]
+				result: Option<Box<serde_json::value::RawValue>>,
+				#[serde(default)]
+				error: Option<serde::de::IgnoredAny>,
 			}
 
-			let payload: RpcPayload = serde_json::from_str(&data)
-				.expect("valid JSON-RPC response must have a result field and be valid JSON; qed");
-			Ok(response::ok_response(payload.result.to_string()))
+			let payload: RpcPayload =
+				serde_json::from_slice(&data).expect("valid JSON-RPC response must be valid JSON; qed");
+			match payload.error {
+				// Error responses count as a failed check.
+				Some(_) => None,
+				None => Some(payload.result.unwrap_or_else(|| {
+					serde_json::value::RawValue::from_string("null".to_owned()).expect("valid JSON; qed")
+				})),
+			}
 		}
-		_ => Ok(response::internal_error()),
-	}
+		_ => None,
+	};
+
+	(result, sink.bytes_sent())
 }
@@ -30,7 +30,11 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use crate::types::error::CallError;
-use crate::{server::ServerHandle, HttpServerBuilder, RpcModule};
+use crate::{
+	server::ServerHandle, Authenticator, BasicAuth, BreakerPolicy, CircuitBreaker, Http2, HttpServerBuilder, IpFilter,
+	LoadShedder, Permissions, Pipelining, RateLimit, RateLimiter, ResponseCompression, RpcModule, ShutdownReason,
+	StaticKeys, TrustedProxies, WireTap,
+};
 use jsonrpsee_core::Error;
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, StatusCode, TestContext};
@@ -491,3 +495,1070 @@ async fn disabled_batches() {
 
 	handle.stop().unwrap();
 }
+
+#[tokio::test]
+async fn max_concurrent_requests_works() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default().max_concurrent_requests(1).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async move {
+			tokio::time::sleep(Duration::from_millis(500)).await;
+			Ok("ok")
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let first = tokio::spawn(http_request(req.into(), uri.clone()));
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, server_is_busy(Id::Null));
+
+	let response = first.await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	// The slot is released once the first request is answered.
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_requests_are_limited_after_authentication() {
+	let validator = |token: String| async move {
+		tokio::time::sleep(Duration::from_millis(300)).await;
+		(token == "secret").then(|| Permissions::new(["user"]))
+	};
+	let server = HttpServerBuilder::default()
+		.max_concurrent_requests(1)
+		.set_authenticator(Authenticator::new(validator))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let unauthenticated =
+		tokio::spawn(http_request_with_headers(req.into(), uri.clone(), &[("authorization", "wrong")]));
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	// The request being authenticated doesn't hold the only permit.
+	let response = http_request_with_headers(req.into(), uri.clone(), &[("authorization", "secret")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	let response = unauthenticated.await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn load_shedding_works() {
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::sync::Arc;
+
+	let usage = Arc::new(AtomicU64::new(0));
+	let probe = usage.clone();
+	let shedder = LoadShedder::new(100, move || probe.load(Ordering::Relaxed)).low_watermark(50);
+	let server = HttpServerBuilder::default()
+		.set_load_shedder(shedder)
+		.http_status_backpressure(true)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	usage.store(100, Ordering::Relaxed);
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(response.body, server_is_busy(Id::Null));
+
+	// Requests are accepted again once the usage dropped below the low watermark.
+	usage.store(75, Ordering::Relaxed);
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+	usage.store(10, Ordering::Relaxed);
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn wire_tap_works() {
+	let path = std::env::temp_dir().join(format!("jsonrpsee-http-wire-tap-{}.jsonl", std::process::id()));
+	let tap = WireTap::to_file(&path).unwrap().max_frame_len(16);
+	let server = HttpServerBuilder::default().set_wire_tap(tap).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	let records: Vec<JsonValue> =
+		std::fs::read_to_string(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(records.len(), 2);
+	assert_eq!(records[0]["transport"], "http");
+	assert_eq!(records[0]["direction"], "inbound");
+	assert_eq!(records[0]["data"], &req[..16]);
+	assert_eq!(records[0]["len"], req.len());
+	assert_eq!(records[0]["truncated"], true);
+	assert_eq!(records[1]["direction"], "outbound");
+	assert_eq!(records[1]["len"], response.body.len());
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_requests_wait_works() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default()
+		.max_concurrent_requests(1)
+		.concurrent_requests_wait(Duration::from_secs(5))
+		.build(addr)
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async move {
+			tokio::time::sleep(Duration::from_millis(200)).await;
+			Ok("ok")
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let first = tokio::spawn(http_request(req.into(), uri.clone()));
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	// Queued until the first request is done.
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	let response = first.await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn streamed_batch_responses_in_completion_order() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default().stream_batch_responses(true).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("slow", |_, _| async move {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			Ok("slow")
+		})
+		.unwrap();
+	module.register_method("fast", |_, _| Ok("fast")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"slow","id":1},
+		{"jsonrpc":"2.0","method":"fast","id":2}
+	]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.header.get("transfer-encoding").unwrap(), "chunked");
+	assert_eq!(response.body, r#"[{"jsonrpc":"2.0","result":"fast","id":2},{"jsonrpc":"2.0","result":"slow","id":1}]"#);
+
+	// Invalid batches are still answered with a single response.
+	let response = http_request("[]".into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_request(Id::Null));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"fast"}]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, "");
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn rate_limiting_works() {
+	let limiter = RateLimiter::new()
+		.default_limit(RateLimit::new(2, Duration::from_secs(60)))
+		.group("expensive", RateLimit::new(1, Duration::from_secs(60)), ["expensive"])
+		.unwrap()
+		.key_by_header("x-api-key");
+	let server = HttpServerBuilder::default().set_rate_limiter(limiter).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("cheap", |_, _| Ok("ok")).unwrap();
+	module.register_method("expensive", |_, _| Ok("ok")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let cheap = r#"{"jsonrpc":"2.0","method":"cheap","id":1}"#;
+	let expensive = r#"{"jsonrpc":"2.0","method":"expensive","id":1}"#;
+
+	for _ in 0..2 {
+		let response = http_request(cheap.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+	}
+	let response = http_request(cheap.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_rate_limited(&response.body, Duration::from_secs(30));
+
+	// Groups have their own budget.
+	let batch = format!("[{},{}]", expensive, expensive.replace(r#""id":1"#, r#""id":2"#));
+	let response = http_request(batch.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(responses[0], serde_json::json!({"jsonrpc":"2.0","result":"ok","id":1}));
+	assert_rate_limited(&responses[1].to_string(), Duration::from_secs(60));
+
+	// The API key is ignored unless set by a trusted proxy, clients could otherwise pick a new one every time.
+	let response = http_request_with_headers(cheap.into(), uri.clone(), &[("x-api-key", "secret")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_rate_limited(&response.body, Duration::from_secs(30));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn rate_limiting_by_header_of_trusted_proxies_works() {
+	let limiter =
+		RateLimiter::new().default_limit(RateLimit::new(1, Duration::from_secs(60))).key_by_header("x-api-key");
+	let server = HttpServerBuilder::default()
+		.set_rate_limiter(limiter)
+		.set_trusted_proxies(TrustedProxies::new().trust(["127.0.0.1"]).unwrap())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("cheap", |_, _| Ok("ok")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let cheap = r#"{"jsonrpc":"2.0","method":"cheap","id":1}"#;
+	let request = |key: &'static str| {
+		let uri = uri.clone();
+		async move { http_request_with_headers(cheap.into(), uri, &[("x-api-key", key)]).with_default_timeout().await }
+	};
+
+	// Clients providing an API key are limited separately.
+	for key in ["alice", "bob"] {
+		let response = request(key).await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+	}
+	let response = request("alice").await.unwrap().unwrap();
+	assert_rate_limited(&response.body, Duration::from_secs(60));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn circuit_breaker_works() {
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	let policy = BreakerPolicy::new(0.5, Duration::from_secs(60)).min_calls(2).open_for(Duration::from_millis(100));
+	let server = HttpServerBuilder::default()
+		.set_circuit_breaker(CircuitBreaker::new().default_policy(policy))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(AtomicBool::new(true));
+	module
+		.register_method("flaky", |_, failing| match failing.load(Ordering::SeqCst) {
+			true => Err(Error::Custom("backend unavailable".into())),
+			false => Ok("ok"),
+		})
+		.unwrap();
+	module.register_method("healthy", |_, _| Ok("ok")).unwrap();
+	module.register_method("recover", |_, failing| Ok(failing.swap(false, Ordering::SeqCst))).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let flaky = r#"{"jsonrpc":"2.0","method":"flaky","id":1}"#;
+	let healthy = r#"{"jsonrpc":"2.0","method":"healthy","id":1}"#;
+	let recover = r#"{"jsonrpc":"2.0","method":"recover","id":1}"#;
+
+	for _ in 0..2 {
+		let response = http_request(flaky.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		let error: JsonValue = serde_json::from_str(&response.body).unwrap();
+		assert_eq!(error["error"]["message"], "Custom error: backend unavailable");
+	}
+
+	// The circuit is open, the method is not executed anymore.
+	let response = http_request(flaky.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	let error: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(error["error"]["code"], -32011);
+	assert!(error["error"]["data"]["retry_after_ms"].as_u64().unwrap() <= 100);
+
+	// Other methods have their own circuit.
+	let response = http_request(healthy.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	// Once the backend recovered, the trial call closes the circuit.
+	http_request(recover.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	tokio::time::sleep(Duration::from_millis(150)).await;
+	for _ in 0..2 {
+		let response = http_request(flaky.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+	}
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn bind_all_addresses_works() {
+	let addrs: &[SocketAddr] = &["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+	let server = HttpServerBuilder::default().bind_all_addresses(true).build(addrs).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let local_addrs = server.local_addrs().to_vec();
+	assert_eq!(local_addrs.len(), 2);
+	assert_eq!(server.local_addr().unwrap(), local_addrs[0]);
+	let handle = server.start(module).unwrap();
+
+	for addr in local_addrs {
+		let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+		let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+	}
+
+	handle.stop().unwrap().await.unwrap();
+}
+
+#[tokio::test]
+async fn json_rpc_v1_compat_works() {
+	let server = HttpServerBuilder::default().json_rpc_v1_compat(true).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("getblockcount", |_, _| Ok(42)).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"1.0","method":"getblockcount","params":[],"id":"curltest"}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"{"error":null,"id":"curltest","result":42}"#);
+
+	let req = r#"{"method":"unknown","params":[],"id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"{"error":{"code":-32601,"message":"Method not found"},"id":1,"result":null}"#);
+
+	// Notifications have a `null` ID in JSON-RPC 1.0.
+	let req = r#"{"method":"getblockcount","params":[],"id":null}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, "");
+
+	// JSON-RPC 2.0 requests are answered as usual.
+	let req = r#"{"jsonrpc":"2.0","method":"getblockcount","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(42.into(), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn basic_auth_works() {
+	let cookie_path = std::env::temp_dir().join(format!("jsonrpsee-http-cookie-{}", std::process::id()));
+	let auth = BasicAuth::new().user("alice", "hunter2").cookie_file(&cookie_path).unwrap();
+	let cookie = std::fs::read_to_string(&cookie_path).unwrap();
+	std::fs::remove_file(&cookie_path).unwrap();
+
+	let server = HttpServerBuilder::default().set_basic_auth(auth).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+	assert_eq!(response.header.get("www-authenticate").unwrap(), "Basic realm=\"jsonrpc\"");
+
+	let wrong = format!("Basic {}", base64::encode("alice:hunter3"));
+	let response = http_request_with_headers(req.into(), uri.clone(), &[("authorization", leak(wrong))])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+	for credentials in ["alice:hunter2", cookie.as_str()] {
+		let header = format!("Basic {}", base64::encode(credentials));
+		let response = http_request_with_headers(req.into(), uri.clone(), &[("authorization", leak(header))])
+			.with_default_timeout()
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(response.body, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+	}
+
+	handle.stop().unwrap();
+}
+
+fn leak(s: String) -> &'static str {
+	Box::leak(s.into_boxed_str())
+}
+
+#[tokio::test]
+async fn http_status_backpressure_works() {
+	let limiter = RateLimiter::new().default_limit(RateLimit::new(1, Duration::from_secs(60)));
+	let server = HttpServerBuilder::default()
+		.set_rate_limiter(limiter)
+		.max_concurrent_requests(1)
+		.http_status_backpressure(true)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async move {
+			tokio::time::sleep(Duration::from_millis(500)).await;
+			Ok("ok")
+		})
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let first = tokio::spawn(http_request(req.into(), uri.clone()));
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(response.header.get("retry-after").unwrap(), "1");
+	assert_eq!(response.body, server_is_busy(Id::Null));
+
+	let response = first.await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(response.header.get("retry-after").unwrap(), "60");
+	assert_rate_limited(&response.body, Duration::from_secs(60));
+
+	// Batches are answered with `200 OK` regardless.
+	let response =
+		http_request(format!("[{}]", req).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+
+	handle.stop().unwrap();
+}
+
+fn assert_rate_limited(response: &str, max_retry_after: Duration) {
+	let response: JsonValue = serde_json::from_str(response).unwrap();
+	assert_eq!(response["error"]["code"], -32007);
+	let retry_after_ms = response["error"]["data"]["retry_after_ms"].as_u64().unwrap();
+	assert!(retry_after_ms > 0 && retry_after_ms <= max_retry_after.as_millis() as u64);
+}
+
+#[tokio::test]
+async fn sequential_batch_calls_observe_previous_calls() {
+	use std::sync::atomic::{AtomicU64, Ordering};
+
+	let mut module = RpcModule::new(AtomicU64::new(0));
+	module
+		.register_async_method("incr", |_, counter| async move {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			Ok(counter.fetch_add(1, Ordering::SeqCst) + 1)
+		})
+		.unwrap();
+	module.register_method("get", |_, counter| Ok(counter.load(Ordering::SeqCst))).unwrap();
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	// Without hints the calls are executed concurrently.
+	let req = r#"[{"jsonrpc":"2.0","method":"incr","id":1},{"jsonrpc":"2.0","method":"get","id":2}]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap().body;
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":0,"id":2},{"jsonrpc":"2.0","result":1,"id":1}]"#);
+
+	// A sequential call waits for the preceding calls to complete.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"incr","id":1},
+		{"jsonrpc":"2.0","method":"incr","id":2},
+		{"jsonrpc":"2.0","method":"get","id":3,"execution":"sequential"}
+	]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap().body;
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses[2], serde_json::json!({"jsonrpc":"2.0","result":3,"id":3}));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn authentication_works() {
+	let keys = StaticKeys::new().key("secret", Permissions::new(["admin"]));
+	let authenticator = Authenticator::new(keys).require_scope("admin", ["admin_only"]).unwrap();
+	let server = HttpServerBuilder::default().set_authenticator(authenticator).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("public", |_, _| Ok("ok")).unwrap();
+	module.register_method("admin_only", |_, _| Ok("ok")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let public = r#"{"jsonrpc":"2.0","method":"public","id":1}"#;
+	let admin_only = r#"{"jsonrpc":"2.0","method":"admin_only","id":1}"#;
+
+	// Anonymous clients may only call public methods.
+	let response = http_request(public.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+	let response = http_request(admin_only.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		r#"{"jsonrpc":"2.0","error":{"code":-32008,"message":"Unauthorized","data":{"required_scope":"admin"}},"id":1}"#
+	);
+
+	let response = http_request_with_headers(admin_only.into(), uri.clone(), &[("authorization", "Bearer secret")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	// Invalid credentials are rejected.
+	let response = http_request_with_headers(public.into(), uri.clone(), &[("authorization", "Bearer wrong")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn scopes_and_rate_limits_apply_to_aliases() {
+	let keys = StaticKeys::new().key("secret", Permissions::new(["admin"]));
+	let authenticator = Authenticator::new(keys).require_scope("admin", ["admin_only", "node_shutdown"]).unwrap();
+	let limiter =
+		RateLimiter::new().group("expensive", RateLimit::new(1, Duration::from_secs(60)), ["expensive"]).unwrap();
+	let server = HttpServerBuilder::default()
+		.set_authenticator(authenticator)
+		.set_rate_limiter(limiter)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("admin_only", |_, _| Ok("ok")).unwrap();
+	module.register_alias("admin_alias", "admin_only").unwrap();
+	module.register_method("expensive", |_, _| Ok("ok")).unwrap();
+	module.register_alias("pricey", "expensive").unwrap();
+	let mut node = RpcModule::new(());
+	node.register_method("shutdown", |_, _| Ok("ok")).unwrap();
+	node.register_alias("stop", "shutdown").unwrap();
+	module.merge_with_prefix(node, "node_").unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let call = |method: &str| format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method);
+	let unauthorized = r#"{"jsonrpc":"2.0","error":{"code":-32008,"message":"Unauthorized","data":{"required_scope":"admin"}},"id":1}"#;
+
+	// Anonymous clients can't call scoped methods through their aliases, nor through the aliases of merged methods.
+	for method in ["admin_alias", "node_stop"] {
+		let response = http_request(call(method).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, unauthorized);
+
+		let batch = format!("[{}]", call(method));
+		let response = http_request(batch.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, format!("[{}]", unauthorized));
+	}
+
+	let response = http_request_with_headers(call("node_stop").into(), uri.clone(), &[("authorization", "secret")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+
+	// Aliases share the rate limit of their method.
+	let response = http_request(call("expensive").into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("ok".to_owned()), Id::Num(1)));
+	let response = http_request(call("pricey").into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_rate_limited(&response.body, Duration::from_secs(60));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn batch_concurrency_is_limited() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	// Number of running calls, maximum number of calls observed running at the same time.
+	let mut module = RpcModule::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+	module
+		.register_async_method("work", |_, ctx| async move {
+			let running = ctx.0.fetch_add(1, Ordering::SeqCst) + 1;
+			ctx.1.fetch_max(running, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			ctx.0.fetch_sub(1, Ordering::SeqCst);
+			Ok(running)
+		})
+		.unwrap();
+	module.register_method("max_running", |_, ctx| Ok(ctx.1.load(Ordering::SeqCst))).unwrap();
+	let server = HttpServerBuilder::default().max_batch_concurrency(2).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let batch: Vec<_> = (1..=6).map(|id| format!(r#"{{"jsonrpc":"2.0","method":"work","id":{}}}"#, id)).collect();
+	let batch = format!("[{}]", batch.join(","));
+	let response = http_request(batch.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap().body;
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses.len(), 6);
+
+	let req = r#"{"jsonrpc":"2.0","method":"max_running","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap().body;
+	assert_eq!(response, ok_response(2.into(), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+/// Send two requests on a connection at once and return everything received until the connection is closed.
+async fn pipelined_requests(addr: SocketAddr) -> String {
+	use std::io::{Read, Write};
+
+	let request = |id: u32, extra_headers: &str| {
+		let body = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","id":{}}}"#, id);
+		format!(
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+			addr,
+			body.len(),
+			extra_headers,
+			body
+		)
+	};
+	let requests = request(1, "") + &request(2, "Connection: close\r\n");
+
+	tokio::task::spawn_blocking(move || {
+		let mut stream = std::net::TcpStream::connect(addr).unwrap();
+		stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+		stream.write_all(requests.as_bytes()).unwrap();
+		let mut responses = String::new();
+		stream.read_to_string(&mut responses).unwrap();
+		responses
+	})
+	.await
+	.unwrap()
+}
+
+#[tokio::test]
+async fn pipelined_requests_are_answered_in_order() {
+	let (addr, handle) = server().with_default_timeout().await.unwrap();
+
+	let responses = pipelined_requests(addr).await;
+	let first = responses.find(r#""id":1"#).unwrap();
+	let second = responses.find(r#""id":2"#).unwrap();
+	assert!(first < second);
+	assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 2);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn pipelined_requests_can_be_rejected() {
+	let server = HttpServerBuilder::default().pipelining(Pipelining::Reject).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let responses = pipelined_requests(addr).await;
+	let (first, second) = responses.split_once("HTTP/1.1 400 Bad Request").unwrap();
+	assert!(first.starts_with("HTTP/1.1 200 OK"));
+	assert!(first.contains(r#""id":1"#));
+	assert!(second.contains("Pipelined requests are not supported."));
+
+	// Requests sent after the previous response was received are answered as usual.
+	let uri = to_http_uri(addr);
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+
+	handle.stop().unwrap();
+
+	// Pipelined requests can't be detected on hyper listeners.
+	let addr = "127.0.0.1:0".parse().unwrap();
+	let listener = hyper::Server::bind(&addr);
+	assert!(HttpServerBuilder::default().pipelining(Pipelining::Reject).build_from_hyper(listener, addr).is_err());
+}
+
+#[tokio::test]
+async fn batch_size_is_limited() {
+	for stream_batch_responses in [false, true] {
+		let server = HttpServerBuilder::default()
+			.max_batch_size(2)
+			.stream_batch_responses(stream_batch_responses)
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let mut module = RpcModule::new(());
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+		let uri = to_http_uri(server.local_addr().unwrap());
+		let handle = server.start(module).unwrap();
+
+		let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+		let batch = format!("[{},{}]", req, req);
+		let response = http_request(batch.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		let responses: Vec<JsonValue> = serde_json::from_str(&response.body).unwrap();
+		assert_eq!(responses.len(), 2);
+
+		let batch = format!("[{},{},{}]", req, req, req);
+		let response = http_request(batch.into(), uri).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(
+			response.body,
+			r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Batch contains too many calls","data":"Exceeded max limit of 2"},"id":null}"#
+		);
+
+		handle.stop().unwrap();
+	}
+}
+
+#[tokio::test]
+async fn response_compression_works() {
+	use flate2::read::{GzDecoder, ZlibDecoder};
+	use hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING, VARY};
+	use std::io::Read;
+
+	let server = HttpServerBuilder::default()
+		.set_response_compression(ResponseCompression::new().min_size(256))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("large", |_, _| Ok("a".repeat(4096))).unwrap();
+	module.register_method("small", |_, _| Ok("a")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let request = |method: &str, accept_encoding: Option<&'static str>| {
+		let mut req = hyper::Request::post(uri.clone()).header("content-type", "application/json");
+		if let Some(accept_encoding) = accept_encoding {
+			req = req.header(ACCEPT_ENCODING, accept_encoding);
+		}
+		let body = format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method);
+		hyper::Client::new().request(req.body(body.into()).unwrap())
+	};
+	let large = ok_response(JsonValue::String("a".repeat(4096)), Id::Num(1));
+
+	let res = request("large", Some("gzip, deflate")).await.unwrap();
+	assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+	assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert!(body.len() < large.len());
+	let mut decoded = String::new();
+	GzDecoder::new(&body[..]).read_to_string(&mut decoded).unwrap();
+	assert_eq!(decoded, large);
+
+	let res = request("large", Some("deflate")).await.unwrap();
+	assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "deflate");
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let mut decoded = String::new();
+	ZlibDecoder::new(&body[..]).read_to_string(&mut decoded).unwrap();
+	assert_eq!(decoded, large);
+
+	// Not compressed without `Accept-Encoding` or below the minimum size.
+	let res = request("large", None).await.unwrap();
+	assert!(res.headers().get(CONTENT_ENCODING).is_none());
+	assert_eq!(hyper::body::to_bytes(res.into_body()).await.unwrap(), large);
+
+	let res = request("small", Some("gzip")).await.unwrap();
+	assert!(res.headers().get(CONTENT_ENCODING).is_none());
+	let small = ok_response(JsonValue::String("a".to_owned()), Id::Num(1));
+	assert_eq!(hyper::body::to_bytes(res.into_body()).await.unwrap(), small);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn compressed_requests_work() {
+	use flate2::write::GzEncoder;
+	use std::io::Write;
+
+	let server = HttpServerBuilder::default().max_request_body_size(1024).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let gzip = |data: &[u8]| {
+		let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+		encoder.write_all(data).unwrap();
+		hyper::Body::from(encoder.finish().unwrap())
+	};
+
+	let batch = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2}]"#;
+	let response = http_request_with_headers(gzip(batch.as_bytes()), uri.clone(), &[("content-encoding", "gzip")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(
+		response.body,
+		r#"[{"jsonrpc":"2.0","result":"hello","id":1},{"jsonrpc":"2.0","result":"hello","id":2}]"#
+	);
+
+	// The size limit applies to the decompressed body.
+	let padded = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","id":1}}{}"#, " ".repeat(1024));
+	let response = http_request_with_headers(gzip(padded.as_bytes()), uri.clone(), &[("content-encoding", "gzip")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+	let response = http_request_with_headers(batch.into(), uri.clone(), &[("content-encoding", "br")])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn http2_prior_knowledge_works() {
+	let server = HttpServerBuilder::default()
+		.pipelining(Pipelining::Reject)
+		.set_http2(Http2::new().max_concurrent_streams(16))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			Ok("done")
+		})
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	// Requests multiplexed on a single connection are handled concurrently and not rejected as pipelined.
+	let client = hyper::Client::builder().http2_only(true).build_http::<hyper::Body>();
+	let request = |id: u64| {
+		let body = format!(r#"{{"jsonrpc":"2.0","method":"sleep","id":{}}}"#, id);
+		let req = hyper::Request::post(uri.clone()).header("content-type", "application/json");
+		client.request(req.body(body.into()).unwrap())
+	};
+	let responses = futures_util::future::join_all((0..8).map(request)).with_default_timeout().await.unwrap();
+
+	for (id, res) in responses.into_iter().enumerate() {
+		let res = res.unwrap();
+		assert_eq!(res.version(), hyper::Version::HTTP_2);
+		assert_eq!(res.status(), StatusCode::OK);
+		let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+		assert_eq!(body, ok_response(JsonValue::String("done".into()), Id::Num(id as u64)));
+	}
+
+	handle.stop().unwrap().await.unwrap();
+
+	let server = HttpServerBuilder::default().http2_supported(false).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	let client = hyper::Client::builder().http2_only(true).build_http::<hyper::Body>();
+	let req = hyper::Request::post(uri).header("content-type", "application/json");
+	assert!(client.request(req.body(hyper::Body::from("{}")).unwrap()).await.is_err());
+
+	handle.stop().unwrap().await.unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_reason_is_recorded() {
+	use jsonrpsee_core::server::server_set::ServerControl;
+
+	let (_addr, mut handle) = server().with_default_timeout().await.unwrap();
+	assert_eq!(handle.shutdown_reason(), None);
+
+	// Supervisors keep the handle to find out why the server stopped.
+	handle.request_stop();
+	(&mut handle).with_default_timeout().await.unwrap();
+	assert_eq!(handle.shutdown_reason(), Some(ShutdownReason::Stopped));
+	assert!(handle.shutdown_reason().unwrap().is_planned());
+}
+
+#[tokio::test]
+async fn ip_filter_rejects_connections() {
+	for (filter, allowed) in [
+		(IpFilter::new().allow(["::1", "127.0.0.0/8"]).unwrap(), true),
+		(IpFilter::new().allow(["::1"]).unwrap(), false),
+		(IpFilter::new().deny(["127.0.0.1"]).unwrap(), false),
+	] {
+		let server = HttpServerBuilder::default().set_ip_filter(filter).build("127.0.0.1:0").await.unwrap();
+		let mut module = RpcModule::new(());
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+		let uri = to_http_uri(server.local_addr().unwrap());
+		let handle = server.start(module).unwrap();
+
+		let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+		let response = http_request(req.into(), uri).with_default_timeout().await.unwrap();
+		match response {
+			Ok(response) if allowed => assert_eq!(response.body, ok_response("hello".into(), Id::Num(1))),
+			// Rejected connections are closed before any request is read.
+			Err(_) if !allowed => {}
+			response => panic!("Unexpected response: {:?}", response),
+		}
+
+		handle.stop().unwrap();
+	}
+}
+
+#[tokio::test]
+async fn forwarded_client_ip_is_used_behind_trusted_proxies() {
+	let server = HttpServerBuilder::default()
+		.set_trusted_proxies(TrustedProxies::new().trust(["127.0.0.1"]).unwrap())
+		.set_ip_filter(IpFilter::new().deny(["192.0.2.0/24"]).unwrap())
+		.set_rate_limiter(RateLimiter::new().default_limit(RateLimit::new(1, Duration::from_secs(60))))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let request = |name: &'static str, value: &'static str| {
+		let uri = uri.clone();
+		async move { http_request_with_headers(req.into(), uri, &[(name, value)]).with_default_timeout().await }
+	};
+
+	// Every client has its own rate limit.
+	for client in ["203.0.113.7", "203.0.113.8"] {
+		let response = request("x-forwarded-for", client).await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+	}
+	let response = request("forwarded", "for=203.0.113.7;proto=http").await.unwrap().unwrap();
+	assert_rate_limited(&response.body, Duration::from_secs(60));
+
+	// The IP filter applies to the address of the client.
+	let response = request("x-forwarded-for", "192.0.2.1").await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+	// Addresses prepended by the client are ignored.
+	let response = request("x-forwarded-for", "192.0.2.1, 203.0.113.9").await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn proxy_protocol_works() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let filter = IpFilter::new().deny(["192.0.2.0/24"]).unwrap();
+	let server =
+		HttpServerBuilder::default().proxy_protocol(true).set_ip_filter(filter).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let request = |header: &'static str| async move {
+		let body = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+		let req = format!(
+			"{}POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			header,
+			body.len(),
+			body
+		);
+		let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+		let mut response = String::new();
+		if stream.write_all(req.as_bytes()).await.is_ok() {
+			let _ = stream.read_to_string(&mut response).await;
+		}
+		response
+	};
+
+	let response = request("PROXY TCP4 203.0.113.7 127.0.0.1 56324 80\r\n").with_default_timeout().await.unwrap();
+	assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+	assert!(response.ends_with(&ok_response("hello".into(), Id::Num(1))));
+
+	// The client is seen at the address of the header, which is denied.
+	let response = request("PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n").with_default_timeout().await.unwrap();
+	assert_eq!(response, "");
+
+	// Connections without a header are closed.
+	assert_eq!(request("").with_default_timeout().await.unwrap(), "");
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn calls_are_cancelled_when_the_client_disconnects() {
+	use tokio::io::AsyncWriteExt;
+	use tokio::sync::mpsc::UnboundedSender;
+
+	// Reports whether the call was dropped before completing.
+	struct Call(UnboundedSender<bool>, bool);
+
+	impl Drop for Call {
+		fn drop(&mut self) {
+			let _ = self.0.send(self.1);
+		}
+	}
+
+	let req = r#"{"jsonrpc":"2.0","method":"slow","id":1}"#;
+	for (stream_batch_responses, body) in [(false, req.to_owned()), (true, format!("[{}]", req))] {
+		let server = HttpServerBuilder::default()
+			.stream_batch_responses(stream_batch_responses)
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+		let mut module = RpcModule::new(tx);
+		module
+			.register_async_method("slow", |_, tx| async move {
+				let mut call = Call((*tx).clone(), false);
+				tokio::time::sleep(Duration::from_secs(60)).await;
+				call.1 = true;
+				Ok("done")
+			})
+			.unwrap();
+		let addr = server.local_addr().unwrap();
+		let handle = server.start(module).unwrap();
+
+		let req = format!(
+			"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+			body.len(),
+			body
+		);
+		let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+		stream.write_all(req.as_bytes()).await.unwrap();
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		drop(stream);
+
+		let completed = rx.recv().with_default_timeout().await.unwrap().unwrap();
+		assert!(!completed);
+
+		handle.stop().unwrap();
+	}
+}
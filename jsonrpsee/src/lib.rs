@@ -40,13 +40,20 @@
 //! - **`wasm-client`** - JSON-RPC client functionality over web-sys.
 //! - **`ws-client`** - JSON-RPC client functionality over WebSocket protocol.
 //! - **`ws-server`** - JSON-RPC server functionality over WebSocket protocol.
+//! - **`stdio-server`** - JSON-RPC server functionality over the standard input and output of the process.
 //! - **`macros`** - JSON-RPC API generation convenience by derive macros.
+//! - **`prometheus`** - Middleware recording server metrics into a Prometheus registry.
+//! - **`schemas`** - JSON schemas of method parameters and results derived from their types, their validation and
+//!   TypeScript client generation.
+//! - **`openrpc`** - OpenRPC document generation and the `rpc.discover` method, enables `schemas`.
+//! - **`replay`** - Replay of the connections recorded by the wire tap of a server.
 //! - **`client`** - Enables `http-client` and `ws-client` features.
-//! - **`server`** - Enables `http-server` and `ws-server` features.
+//! - **`server`** - Enables `http-server`, `ws-server` and `stdio-server` features.
 //! - **`full`** - Enables `client`, `server` and `macros` features.
 //! - **`async-client`** - Enables the async client without any transport.
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
+//! - **`client-stdio-transport`** - Enables the transport over the standard input and output of a process.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
@@ -82,6 +89,14 @@ cfg_ws_server! {
 	pub use jsonrpsee_ws_server as ws_server;
 }
 
+cfg_stdio_server! {
+	pub use jsonrpsee_stdio_server as stdio_server;
+}
+
+cfg_prometheus! {
+	pub use jsonrpsee_prometheus as prometheus;
+}
+
 cfg_proc_macros! {
 	pub use jsonrpsee_proc_macros as proc_macros;
 	pub use tracing;
@@ -50,7 +50,7 @@ macro_rules! cfg_client_transport {
 macro_rules! cfg_server {
     ($($item:item)*) => {
         $(
-            #[cfg(any(feature = "jsonrpsee-http-server", feature = "jsonrpsee-ws-server"))]
+            #[cfg(any(feature = "jsonrpsee-http-server", feature = "jsonrpsee-ws-server", feature = "jsonrpsee-stdio-server"))]
             $item
         )*
     }
@@ -68,6 +68,18 @@ macro_rules! cfg_ws_server {
 	};
 }
 
+macro_rules! cfg_stdio_server {
+	($($item:item)*) => {
+		cfg_feature!("jsonrpsee-stdio-server", $($item)*);
+	};
+}
+
+macro_rules! cfg_prometheus {
+    ($($item:item)*) => {
+		cfg_feature!("jsonrpsee-prometheus", $($item)*);
+	};
+}
+
 macro_rules! cfg_proc_macros {
     ($($item:item)*) => {
 		cfg_feature!("jsonrpsee-proc-macros", $($item)*);
@@ -83,7 +95,7 @@ macro_rules! cfg_types {
 macro_rules! cfg_client_or_server {
     ($($item:item)*) => {
         $(
-            #[cfg(any(feature = "jsonrpsee-http-client", feature = "jsonrpsee-ws-client", feature = "jsonrpsee-wasm-client", feature = "client", feature = "async-client", feature = "jsonrpsee-ws-server", feature = "jsonrpsee-http-server"))]
+            #[cfg(any(feature = "jsonrpsee-http-client", feature = "jsonrpsee-ws-client", feature = "jsonrpsee-wasm-client", feature = "client", feature = "async-client", feature = "jsonrpsee-ws-server", feature = "jsonrpsee-http-server", feature = "jsonrpsee-stdio-server"))]
             $item
         )*
     }
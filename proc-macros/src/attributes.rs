@@ -237,12 +237,30 @@ where
 }
 
 pub(crate) fn parse_param_kind(arg: Result<Argument, MissingArgument>) -> syn::Result<ParamKind> {
-	let kind: Option<syn::Ident> = optional(arg, Argument::value)?;
+	let kind: Option<ParamKindValue> = optional(arg, Argument::value)?;
 
 	match kind {
 		None => Ok(ParamKind::Array),
-		Some(ident) if ident == "array" => Ok(ParamKind::Array),
-		Some(ident) if ident == "map" => Ok(ParamKind::Map),
-		ident => Err(Error::new(ident.span(), "param_kind must be either `map` or `array`")),
+		Some(kind) if kind.value == "array" => Ok(ParamKind::Array),
+		Some(kind) if kind.value == "map" => Ok(ParamKind::Map),
+		Some(kind) => Err(Error::new(kind.span, "param_kind must be either `map` or `array`")),
+	}
+}
+
+/// Value of the `param_kind` argument, either as a bare identifier (`map`) or as a string literal (`"map"`).
+struct ParamKindValue {
+	value: String,
+	span: Span,
+}
+
+impl Parse for ParamKindValue {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		if input.peek(LitStr) {
+			let lit: LitStr = input.parse()?;
+			Ok(Self { value: lit.value(), span: lit.span() })
+		} else {
+			let ident: syn::Ident = input.parse()?;
+			Ok(Self { value: ident.to_string(), span: ident.span() })
+		}
 	}
 }
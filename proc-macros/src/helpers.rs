@@ -181,9 +181,45 @@ pub(crate) fn extract_doc_comments(attrs: &[syn::Attribute]) -> TokenStream2 {
 	quote! ( #(#docs)* )
 }
 
+/// Returns the text of the doc comments, or `None` if there are none.
+///
+/// The leading space that `///` comments are expanded with is stripped from every line.
+pub(crate) fn doc_comment_text(attrs: &[syn::Attribute]) -> Option<String> {
+	let lines: Vec<_> = attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("doc"))
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(text), .. })) => Some(text.value()),
+			_ => None,
+		})
+		.map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+		.collect();
+
+	let text = lines.join("\n");
+	let text = text.trim();
+
+	(!text.is_empty()).then(|| text.to_owned())
+}
+
+/// Returns the type of the successful result of a method returning `Result<T, E>` (or an alias like `RpcResult<T>`).
+pub(crate) fn ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+	let segment = match ty {
+		syn::Type::Path(path) => path.path.segments.last()?,
+		_ => return None,
+	};
+
+	match &segment.arguments {
+		syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+			syn::GenericArgument::Type(ty) => Some(ty),
+			_ => None,
+		}),
+		_ => None,
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::is_option;
+	use super::{doc_comment_text, is_option, ok_type};
 	use syn::parse_quote;
 
 	#[test]
@@ -194,4 +230,28 @@ mod tests {
 		assert!(is_option(&parse_quote!(std::option::Option<R>)));
 		assert!(!is_option(&parse_quote!(foo::bar::Option::Booyah)));
 	}
+
+	#[test]
+	fn ok_type_works() {
+		let ty: syn::Type = parse_quote!(u8);
+		assert_eq!(ok_type(&parse_quote!(RpcResult<u8>)), Some(&ty));
+		assert_eq!(ok_type(&parse_quote!(std::result::Result<u8, Error>)), Some(&ty));
+		assert_eq!(ok_type(&parse_quote!(u8)), None);
+	}
+
+	#[test]
+	fn doc_comment_text_works() {
+		let item: syn::ItemFn = parse_quote! {
+			/// Returns a block.
+			///
+			///   Indented.
+			fn block() {}
+		};
+		assert_eq!(doc_comment_text(&item.attrs).as_deref(), Some("Returns a block.\n\n  Indented."));
+
+		let item: syn::ItemFn = parse_quote!(
+			fn block() {}
+		);
+		assert_eq!(doc_comment_text(&item.attrs), None);
+	}
 }
@@ -145,6 +145,12 @@ pub(crate) mod visitor;
 ///   implementation's methods conveniently.
 /// - `namespace`: add a prefix to all the methods and subscriptions in this RPC. For example, with namespace `foo` and
 ///   method `spam`, the resulting method name will be `foo_spam`.
+/// - `schemas`: generate a `schemas` function on the server trait that adds JSON schemas of the parameters and results
+///   of the methods to `Schemas`, with their doc comments as description. Requires `server` and the `schemas` feature
+///   of `jsonrpsee`, and the types of all parameters and results must implement `schemars::JsonSchema`. Subscriptions
+///   are not described.
+/// - `openrpc`: like `schemas`, and also generate an `openrpc` function on the server trait that adds the methods to
+///   an OpenRPC document. Requires the `openrpc` feature of `jsonrpsee`.
 ///
 /// **Trait requirements:**
 ///
@@ -167,7 +173,7 @@ pub(crate) mod visitor;
 ///              Aliases are processed ignoring the namespace, so add the complete name, including the
 ///              namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
-/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `param_kind`: kind of structure to use for parameter passing. Can be `array` or `map` (optionally quoted), defaults to `array`.
 ///
 /// **Method requirements:**
 ///
@@ -175,7 +181,10 @@ pub(crate) mod visitor;
 ///
 /// - be either `async` or not;
 /// - have input parameters or not;
-/// - have a return value or not (in the latter case, it will be considered a notification method).
+/// - have a return value or not (in the latter case, it will be considered a notification method);
+/// - on the server, return `Result<T, E>` with a custom error type `E: Into<ErrorObjectOwned>` instead of
+///   `RpcResult<T>`, in which case the converted error object is sent to the client as is. Traits that also
+///   generate a client must use `RpcResult<T>`.
 ///
 /// ### `subscription` attribute
 ///
@@ -190,7 +199,7 @@ pub(crate) mod visitor;
 ///                         so add the complete name, including the namespace.
 /// - `unsubscribe_aliases` (optional): Similar to `aliases` but for `unsubscribe`.
 /// - `item` (mandatory): type of items yielded by the subscription. Note that it must be the type, not string.
-/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `param_kind`: kind of structure to use for parameter passing. Can be `array` or `map` (optionally quoted), defaults to `array`.
 ///
 /// **Method requirements:**
 ///
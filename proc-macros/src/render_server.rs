@@ -28,7 +28,7 @@ use std::collections::HashSet;
 
 use super::RpcDescription;
 use crate::attributes::Resource;
-use crate::helpers::{generate_where_clause, is_option};
+use crate::helpers::{generate_where_clause, is_option, ok_type};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, quote_spanned};
 use syn::punctuated::Punctuated;
@@ -42,6 +42,8 @@ impl RpcDescription {
 
 		let method_impls = self.render_methods()?;
 		let into_rpc_impl = self.render_into_rpc()?;
+		let schemas_impl = if self.needs_schemas { self.render_schemas() } else { TokenStream2::new() };
+		let openrpc_impl = if self.needs_openrpc { self.render_openrpc() } else { TokenStream2::new() };
 		let async_trait = self.jrps_server_item(quote! { core::__reexports::async_trait });
 
 		// Doc-comment to be associated with the server.
@@ -53,6 +55,8 @@ impl RpcDescription {
 			pub trait #trait_name #impl_generics: Sized + Send + Sync + 'static #where_clause {
 				#method_impls
 				#into_rpc_impl
+				#schemas_impl
+				#openrpc_impl
 			}
 		};
 
@@ -94,8 +98,83 @@ impl RpcDescription {
 		})
 	}
 
+	fn render_schemas(&self) -> TokenStream2 {
+		let schema = self.jrps_server_item(quote! { core::server::schema });
+		let schemars = self.jrps_server_item(quote! { core::__reexports::schemars });
+
+		let methods = self.methods.iter().map(|method| {
+			let name = self.rpc_identifier(&method.name);
+			let description = match &method.description {
+				Some(text) => quote! { Some(#text.into()) },
+				None => quote! { None },
+			};
+			let deprecated = !method.deprecated.is_empty();
+
+			let params = method.params.iter().map(|(name, ty)| {
+				let name = name.ident.to_string();
+				let required = !is_option(ty);
+				quote! {
+					#schema::ContentDescriptor {
+						name: #name.into(),
+						required: #required,
+						schema: schemas.schema_for::<#ty>(),
+					}
+				}
+			});
+
+			let result = match method.returns.as_ref().and_then(ok_type) {
+				Some(ty) => quote! { #ty },
+				None => quote! { () },
+			};
+
+			quote! {
+				let method = #schema::MethodSchema {
+					name: #name.into(),
+					description: #description,
+					params: vec![#(#params),*],
+					result: #schema::ContentDescriptor {
+						name: "result".into(),
+						required: true,
+						schema: schemas.schema_for::<#result>(),
+					},
+					deprecated: #deprecated,
+				};
+				schemas.add_method(method);
+			}
+		});
+
+		let type_params = self.trait_def.generics.type_params().map(|param| &param.ident);
+		let doc_comment =
+			format!("Adds the schemas of the methods of the `{}` RPC API to `schemas`.", &self.trait_def.ident);
+
+		quote! {
+			#[doc = #doc_comment]
+			fn schemas(schemas: &mut #schema::Schemas) where #(#type_params: #schemars::JsonSchema,)* {
+				#(#methods)*
+			}
+		}
+	}
+
+	fn render_openrpc(&self) -> TokenStream2 {
+		let openrpc = self.jrps_server_item(quote! { core::server::openrpc });
+		let schemars = self.jrps_server_item(quote! { core::__reexports::schemars });
+
+		let type_params = self.trait_def.generics.type_params().map(|param| &param.ident);
+		let doc_comment =
+			format!("Adds the methods of the `{}` RPC API to the given OpenRPC document.", &self.trait_def.ident);
+
+		quote! {
+			#[doc = #doc_comment]
+			fn openrpc(document: &mut #openrpc::OpenRpc) where #(#type_params: #schemars::JsonSchema,)* {
+				Self::schemas(document.schemas_mut());
+			}
+		}
+	}
+
 	fn render_into_rpc(&self) -> Result<TokenStream2, syn::Error> {
 		let rpc_module = self.jrps_server_item(quote! { RpcModule });
+		let err = self.jrps_server_item(quote! { core::Error });
+		let call_err = self.jrps_server_item(quote! { types::error::CallError });
 
 		let mut registered = HashSet::new();
 		let mut errors = Vec::new();
@@ -160,11 +239,14 @@ impl RpcDescription {
 
 				let resources = handle_resource_limits(&method.resources);
 
+				// Methods may return any error convertible into an `ErrorObject`, which is sent to the client as is.
+				let map_err = quote! { .map_err(|e| #err::Call(#call_err::Custom(e.into()))) };
+
 				if method.signature.sig.asyncness.is_some() {
 					handle_register_result(quote! {
 						rpc.register_async_method(#rpc_method_name, |params, context| async move {
 							#parsing
-							context.as_ref().#rust_method_name(#params_seq).await
+							context.as_ref().#rust_method_name(#params_seq).await #map_err
 						})
 						#resources
 					})
@@ -175,7 +257,7 @@ impl RpcDescription {
 					handle_register_result(quote! {
 						rpc.#register_kind(#rpc_method_name, |params, context| {
 							#parsing
-							context.#rust_method_name(#params_seq)
+							context.#rust_method_name(#params_seq) #map_err
 						})
 						#resources
 					})
@@ -31,7 +31,7 @@ use std::borrow::Cow;
 use crate::attributes::{
 	optional, parse_param_kind, Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, Resource,
 };
-use crate::helpers::extract_doc_comments;
+use crate::helpers::{doc_comment_text, extract_doc_comments};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
@@ -42,6 +42,8 @@ pub struct RpcMethod {
 	pub name: String,
 	pub blocking: bool,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used as description in the OpenRPC document.
+	pub description: Option<String>,
 	pub deprecated: TokenStream2,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
 	pub param_kind: ParamKind,
@@ -64,6 +66,7 @@ impl RpcMethod {
 
 		let sig = method.sig.clone();
 		let docs = extract_doc_comments(&method.attrs);
+		let description = doc_comment_text(&method.attrs);
 		let deprecated = match find_attr(&method.attrs, "deprecated") {
 			Some(attr) => quote!(#attr),
 			None => quote!(),
@@ -109,6 +112,7 @@ impl RpcMethod {
 			returns,
 			signature: method,
 			docs,
+			description,
 			resources,
 			deprecated,
 		})
@@ -212,6 +216,10 @@ pub struct RpcDescription {
 	/// Assuming that trait to which attribute is applied is named `Foo`, the generated
 	/// client trait will have `FooClient` name.
 	pub(crate) needs_client: bool,
+	/// Switch denoting that the server trait must provide a function describing the methods with JSON schemas.
+	pub(crate) needs_schemas: bool,
+	/// Switch denoting that the server trait must provide a function describing the methods in an OpenRPC document.
+	pub(crate) needs_openrpc: bool,
 	/// Optional prefix for RPC namespace.
 	pub(crate) namespace: Option<String>,
 	/// Trait definition in which all the attributes were stripped.
@@ -224,15 +232,25 @@ pub struct RpcDescription {
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace] = AttributeMeta::parse(attr)?.retain(["client", "server", "namespace"])?;
+		let [client, server, namespace, schemas, openrpc] =
+			AttributeMeta::parse(attr)?.retain(["client", "server", "namespace", "schemas", "openrpc"])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
+		let needs_openrpc = optional(openrpc, Argument::flag)?.is_some();
+		let needs_schemas = optional(schemas, Argument::flag)?.is_some() || needs_openrpc;
 		let namespace = optional(namespace, Argument::string)?;
 
 		if !needs_server && !needs_client {
 			return Err(syn::Error::new_spanned(&item.ident, "Either 'server' or 'client' attribute must be applied"));
 		}
+		if needs_schemas && !needs_server {
+			let attribute = if needs_openrpc { "openrpc" } else { "schemas" };
+			return Err(syn::Error::new_spanned(
+				&item.ident,
+				format!("The '{}' attribute requires the 'server' attribute", attribute),
+			));
+		}
 
 		let jsonrpsee_client_path = crate::helpers::find_jsonrpsee_client_crate().ok();
 		let jsonrpsee_server_path = crate::helpers::find_jsonrpsee_server_crate().ok();
@@ -309,6 +327,8 @@ impl RpcDescription {
 			jsonrpsee_server_path,
 			needs_server,
 			needs_client,
+			needs_schemas,
+			needs_openrpc,
 			namespace,
 			trait_def: item,
 			methods,
@@ -13,6 +13,9 @@ pub trait Rpc {
 	#[method(name="method_with_map_param", param_kind= map)]
 	async fn method_with_map_param(&self, param_a: u8, param_b: String) -> RpcResult<u16>;
 
+	#[method(name = "method_with_quoted_map_param", param_kind = "map")]
+	async fn method_with_quoted_map_param(&self, param_a: u8, param_b: String) -> RpcResult<u16>;
+
 	#[method(name="method_with_default_param")]
 	async fn method_with_default_param(&self, param_a: u8, param_b: String) -> RpcResult<u16>;
 }
@@ -33,6 +36,12 @@ impl RpcServer for RpcServerImpl {
 		Ok(42u16)
 	}
 
+	async fn method_with_quoted_map_param(&self, param_a: u8, param_b: String) -> RpcResult<u16> {
+		assert_eq!(param_a, 0);
+		assert_eq!(&param_b, "a");
+		Ok(42u16)
+	}
+
 	async fn method_with_default_param(&self, param_a: u8, param_b: String) -> RpcResult<u16> {
 		assert_eq!(param_a, 0);
 		assert_eq!(&param_b, "a");
@@ -57,5 +66,6 @@ async fn main() {
 
 	assert_eq!(client.method_with_array_param(0, "a".into()).await.unwrap(), 42);
 	assert_eq!(client.method_with_map_param(0, "a".into()).await.unwrap(), 42);
+	assert_eq!(client.method_with_quoted_map_param(0, "a".into()).await.unwrap(), 42);
 	assert_eq!(client.method_with_default_param(0, "a".into()).await.unwrap(), 42);
 }
@@ -0,0 +1,233 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_docs, missing_debug_implementations, unreachable_pub)]
+
+//! # jsonrpsee-prometheus
+//!
+//! [`Middleware`] for `jsonrpsee` servers that records metrics into a [`prometheus::Registry`]:
+//!
+//! - `{prefix}_calls_started_total{method}`: number of method calls received.
+//! - `{prefix}_calls_finished_total{method, success}`: number of method calls completed.
+//! - `{prefix}_call_errors_total{method, code}`: number of method calls answered with an error, by error code.
+//! - `{prefix}_call_duration_seconds{method}`: time from the reception of the request until the call completed.
+//! - `{prefix}_requests_total`: number of JSON-RPC requests (single calls or batches) received.
+//! - `{prefix}_request_duration_seconds`: time from the reception of a request until the response was produced.
+//! - `{prefix}_open_connections`: number of open WebSocket connections.
+//!
+//! Method names are used as label values as sent by the clients, which includes unknown methods.
+//!
+//! ```
+//! use jsonrpsee_prometheus::PrometheusMiddleware;
+//!
+//! let registry = prometheus::Registry::new();
+//! let middleware = PrometheusMiddleware::new(&registry).unwrap();
+//! // Pass `middleware` to `HttpServerBuilder::set_middleware` or `WsServerBuilder::set_middleware`.
+//! ```
+
+use std::time::Instant;
+
+use jsonrpsee_core::middleware::Middleware;
+use jsonrpsee_types::ErrorObject;
+use prometheus::{Error, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry};
+
+/// Default prefix of the metric names.
+pub const DEFAULT_PREFIX: &str = "jsonrpsee";
+
+/// Middleware recording metrics about the calls made to a server, see the crate documentation for details.
+#[derive(Debug, Clone)]
+pub struct PrometheusMiddleware {
+	calls_started: IntCounterVec,
+	calls_finished: IntCounterVec,
+	call_errors: IntCounterVec,
+	call_duration: HistogramVec,
+	requests: IntCounter,
+	request_duration: Histogram,
+	open_connections: IntGauge,
+}
+
+impl PrometheusMiddleware {
+	/// Create the metrics with the [`DEFAULT_PREFIX`] and register them in `registry`.
+	pub fn new(registry: &Registry) -> Result<Self, Error> {
+		Self::with_prefix(registry, DEFAULT_PREFIX)
+	}
+
+	/// Create the metrics with names starting with `prefix` and register them in `registry`.
+	///
+	/// Errors if metrics with the same names are already registered.
+	pub fn with_prefix(registry: &Registry, prefix: &str) -> Result<Self, Error> {
+		let this = Self {
+			calls_started: IntCounterVec::new(
+				Opts::new(format!("{}_calls_started_total", prefix), "Number of method calls received"),
+				&["method"],
+			)?,
+			calls_finished: IntCounterVec::new(
+				Opts::new(format!("{}_calls_finished_total", prefix), "Number of method calls completed"),
+				&["method", "success"],
+			)?,
+			call_errors: IntCounterVec::new(
+				Opts::new(format!("{}_call_errors_total", prefix), "Number of method calls answered with an error"),
+				&["method", "code"],
+			)?,
+			call_duration: HistogramVec::new(
+				HistogramOpts::new(
+					format!("{}_call_duration_seconds", prefix),
+					"Time from the reception of the request until the method call completed",
+				),
+				&["method"],
+			)?,
+			requests: IntCounter::new(format!("{}_requests_total", prefix), "Number of requests received")?,
+			request_duration: Histogram::with_opts(HistogramOpts::new(
+				format!("{}_request_duration_seconds", prefix),
+				"Time from the reception of a request until the response was produced",
+			))?,
+			open_connections: IntGauge::new(
+				format!("{}_open_connections", prefix),
+				"Number of open WebSocket connections",
+			)?,
+		};
+
+		registry.register(Box::new(this.calls_started.clone()))?;
+		registry.register(Box::new(this.calls_finished.clone()))?;
+		registry.register(Box::new(this.call_errors.clone()))?;
+		registry.register(Box::new(this.call_duration.clone()))?;
+		registry.register(Box::new(this.requests.clone()))?;
+		registry.register(Box::new(this.request_duration.clone()))?;
+		registry.register(Box::new(this.open_connections.clone()))?;
+
+		Ok(this)
+	}
+}
+
+impl Middleware for PrometheusMiddleware {
+	type Instant = Instant;
+
+	fn on_connect(&self) {
+		self.open_connections.inc();
+	}
+
+	fn on_request(&self) -> Self::Instant {
+		self.requests.inc();
+		Instant::now()
+	}
+
+	fn on_call(&self, name: &str) {
+		self.calls_started.with_label_values(&[name]).inc();
+	}
+
+	fn on_error(&self, name: &str, error: &ErrorObject) {
+		self.call_errors.with_label_values(&[name, &error.code().to_string()]).inc();
+	}
+
+	fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
+		let success = if success { "true" } else { "false" };
+		self.calls_finished.with_label_values(&[name, success]).inc();
+		self.call_duration.with_label_values(&[name]).observe(started_at.elapsed().as_secs_f64());
+	}
+
+	fn on_response(&self, started_at: Self::Instant) {
+		self.request_duration.observe(started_at.elapsed().as_secs_f64());
+	}
+
+	fn on_disconnect(&self) {
+		self.open_connections.dec();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Middleware, PrometheusMiddleware};
+	use jsonrpsee_types::error::ErrorCode;
+	use prometheus::proto::MetricType;
+	use prometheus::{Encoder, Registry, TextEncoder};
+
+	fn metric_value(registry: &Registry, name: &str, labels: &[(&str, &str)]) -> f64 {
+		let family = registry.gather().into_iter().find(|family| family.get_name() == name).unwrap();
+		let metric = family
+			.get_metric()
+			.iter()
+			.find(|metric| {
+				labels.iter().all(|(name, value)| {
+					metric.get_label().iter().any(|label| label.get_name() == *name && label.get_value() == *value)
+				})
+			})
+			.unwrap();
+
+		match family.get_field_type() {
+			MetricType::HISTOGRAM => metric.get_histogram().get_sample_count() as f64,
+			MetricType::GAUGE => metric.get_gauge().get_value(),
+			_ => metric.get_counter().get_value(),
+		}
+	}
+
+	#[test]
+	fn records_calls() {
+		let registry = Registry::new();
+		let middleware = PrometheusMiddleware::new(&registry).unwrap();
+
+		middleware.on_connect();
+		let started_at = middleware.on_request();
+		middleware.on_call("say_hello");
+		middleware.on_result("say_hello", true, started_at);
+		middleware.on_call("say_hello");
+		middleware.on_error("say_hello", &ErrorCode::ServerIsBusy.into());
+		middleware.on_result("say_hello", false, started_at);
+		middleware.on_response(started_at);
+
+		assert_eq!(metric_value(&registry, "jsonrpsee_requests_total", &[]), 1.0);
+		assert_eq!(metric_value(&registry, "jsonrpsee_request_duration_seconds", &[]), 1.0);
+		assert_eq!(metric_value(&registry, "jsonrpsee_calls_started_total", &[("method", "say_hello")]), 2.0);
+		assert_eq!(
+			metric_value(&registry, "jsonrpsee_calls_finished_total", &[("method", "say_hello"), ("success", "true")]),
+			1.0
+		);
+		assert_eq!(
+			metric_value(&registry, "jsonrpsee_calls_finished_total", &[("method", "say_hello"), ("success", "false")]),
+			1.0
+		);
+		assert_eq!(
+			metric_value(&registry, "jsonrpsee_call_errors_total", &[("method", "say_hello"), ("code", "-32604")]),
+			1.0
+		);
+		assert_eq!(metric_value(&registry, "jsonrpsee_call_duration_seconds", &[("method", "say_hello")]), 2.0);
+		assert_eq!(metric_value(&registry, "jsonrpsee_open_connections", &[]), 1.0);
+
+		middleware.on_disconnect();
+		assert_eq!(metric_value(&registry, "jsonrpsee_open_connections", &[]), 0.0);
+
+		let mut buf = Vec::new();
+		TextEncoder::new().encode(&registry.gather(), &mut buf).unwrap();
+		assert!(String::from_utf8(buf).unwrap().contains(r#"jsonrpsee_calls_started_total{method="say_hello"} 2"#));
+	}
+
+	#[test]
+	fn duplicate_registration_fails() {
+		let registry = Registry::new();
+		assert!(PrometheusMiddleware::new(&registry).is_ok());
+		assert!(PrometheusMiddleware::new(&registry).is_err());
+		assert!(PrometheusMiddleware::with_prefix(&registry, "other").is_ok());
+	}
+}
@@ -0,0 +1,106 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transport connecting a client to a server running in the same process.
+
+use std::fmt;
+
+use bytes::Bytes;
+use futures_channel::mpsc;
+use futures_util::stream::StreamExt;
+use jsonrpsee_core::async_trait;
+use jsonrpsee_core::client::{ReceivedMessage, TransportReceiverT, TransportSenderT};
+
+/// Error of the in-memory transport.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The server stopped.
+	#[error("The server closed the connection")]
+	Closed,
+}
+
+/// Sending end of the in-memory transport, returned by
+/// [`StdioServer::serve_in_memory`](crate::StdioServer::serve_in_memory).
+pub struct InMemorySender(mpsc::UnboundedSender<String>);
+
+impl InMemorySender {
+	pub(crate) fn new(tx: mpsc::UnboundedSender<String>) -> Self {
+		Self(tx)
+	}
+}
+
+impl fmt::Debug for InMemorySender {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("InMemorySender").finish()
+	}
+}
+
+/// Receiving end of the in-memory transport, returned by
+/// [`StdioServer::serve_in_memory`](crate::StdioServer::serve_in_memory).
+pub struct InMemoryReceiver(mpsc::UnboundedReceiver<Bytes>);
+
+impl InMemoryReceiver {
+	pub(crate) fn new(rx: mpsc::UnboundedReceiver<Bytes>) -> Self {
+		Self(rx)
+	}
+}
+
+impl fmt::Debug for InMemoryReceiver {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("InMemoryReceiver").finish()
+	}
+}
+
+#[async_trait]
+impl TransportSenderT for InMemorySender {
+	type Error = Error;
+
+	async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+		tracing::trace!("send: {}", msg);
+		self.0.unbounded_send(msg).map_err(|_| Error::Closed)
+	}
+
+	async fn send_ping(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	async fn close(&mut self) -> Result<(), Self::Error> {
+		self.0.close_channel();
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl TransportReceiverT for InMemoryReceiver {
+	type Error = Error;
+
+	async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+		match self.0.next().await {
+			Some(msg) => Ok(ReceivedMessage::Bytes(msg.to_vec())),
+			None => Err(Error::Closed),
+		}
+	}
+}
@@ -0,0 +1,47 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+//! # jsonrpsee-stdio-server
+//!
+//! `jsonrpsee-stdio-server` is a [JSON RPC](https://www.jsonrpc.org/specification) server library speaking over
+//! the standard input and output of the process, or any other pair of pipes, for tools launched by another
+//! program. See [`jsonrpsee_core::stdio`] for how messages are framed.
+//!
+//! The server can also be connected to a client running in the same process, which is convenient for tests: see
+//! [`StdioServer::serve_in_memory`].
+
+mod in_memory;
+mod server;
+
+pub use in_memory::{Error as InMemoryError, InMemoryReceiver, InMemorySender};
+pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
+pub use jsonrpsee_core::stdio::Framing;
+pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
+pub use jsonrpsee_types as types;
+pub use server::{Builder as StdioServerBuilder, Server as StdioServer};
+pub use tracing;
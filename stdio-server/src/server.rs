@@ -0,0 +1,481 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::io;
+use std::sync::Arc;
+
+use crate::in_memory::{InMemoryReceiver, InMemorySender};
+use bytes::Bytes;
+use futures_channel::mpsc;
+use futures_util::future::join_all;
+use futures_util::stream::StreamExt;
+use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
+use jsonrpsee_core::middleware::{CallInfo, Middleware, Transport};
+use jsonrpsee_core::server::helpers::{
+	batch_stages, collect_batch_response, prepare_error, report_response, report_result, BoundedSubscriptions,
+	MethodSink,
+};
+use jsonrpsee_core::server::parse::{parse_batch, parse_notification, parse_notification_batch, parse_request};
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::{ConnState, MethodKind, Methods};
+use jsonrpsee_core::stdio::{read_message, write_message, Framing};
+use jsonrpsee_core::tracing::{rx_log_from_json, RpcTracing};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use jsonrpsee_types::error::{
+	reject_too_big_request, reject_too_many_subscriptions, ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE,
+	BATCHES_NOT_SUPPORTED_MSG,
+};
+use jsonrpsee_types::{Id, Params, Request};
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tracing_futures::Instrument;
+
+/// Id of the single connection of the server, passed to the method callbacks.
+const CONN_ID: usize = 0;
+
+/// A JSON-RPC server speaking over a pair of pipes, the standard input and output of the process by default.
+///
+/// The server has a single client: it stops once its input is closed.
+#[derive(Debug)]
+pub struct Server<M = ()> {
+	cfg: Settings,
+	resources: Resources,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl<M: Middleware> Server<M> {
+	/// Serve `methods` over the standard input and output of the process until the input is closed.
+	///
+	/// Nothing else must be written to the standard output meanwhile, logs should go to the standard error.
+	pub async fn serve(self, methods: impl Into<Methods>) -> Result<(), Error> {
+		self.serve_with(methods, tokio::io::stdin(), tokio::io::stdout()).await
+	}
+
+	/// Serve `methods`, reading requests from `reader` and writing responses to `writer`, until `reader` is
+	/// closed.
+	///
+	/// Returns once the responses to all requests and the notifications of all subscriptions were written.
+	pub async fn serve_with<R, W>(self, methods: impl Into<Methods>, reader: R, writer: W) -> Result<(), Error>
+	where
+		R: AsyncRead + Unpin,
+		W: AsyncWrite + Unpin,
+	{
+		let cfg = self.cfg;
+		let (conn, sink, mut rx) = self.connect(methods)?;
+
+		let read = async {
+			let mut reader = BufReader::new(reader);
+
+			loop {
+				match read_message(&mut reader, cfg.framing, cfg.max_request_body_size as usize).await {
+					Ok(Some(data)) => conn.dispatch(data, &sink),
+					Ok(None) => break,
+					Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+						tracing::warn!("Skipping request: {}", err);
+						sink.send_error(Id::Null, reject_too_big_request(cfg.max_request_body_size));
+					}
+					Err(err) => return Err(err),
+				}
+			}
+
+			// Subscriptions end once the input is closed, the responses of pending calls are still written.
+			conn.bounded_subscriptions.close();
+			drop(sink);
+			Ok::<_, io::Error>(())
+		};
+
+		let write = async {
+			let mut writer = writer;
+
+			while let Some(response) = rx.next().await {
+				write_message(&mut writer, cfg.framing, &response).await?;
+			}
+			Ok::<_, io::Error>(())
+		};
+
+		let result = tokio::try_join!(read, write);
+		conn.middleware.on_disconnect();
+		result.map(|_| ()).map_err(Into::into)
+	}
+
+	/// Serve `methods` to a client running in the same process, without any socket, pipe or framing.
+	///
+	/// Returns the transport to build the client with, see [`ClientBuilder::build_with_tokio`]. The server stops
+	/// once the client is dropped.
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::core::client::{ClientBuilder, ClientT};
+	///     use jsonrpsee::stdio_server::{RpcModule, StdioServerBuilder};
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///
+	///     let (sender, receiver) = StdioServerBuilder::default().build().serve_in_memory(module).unwrap();
+	///     let client = ClientBuilder::default().build_with_tokio(sender, receiver);
+	///     let response: String = client.request("say_hello", None).await.unwrap();
+	///     assert_eq!(response, "lo");
+	/// }
+	/// ```
+	///
+	/// [`ClientBuilder::build_with_tokio`]: jsonrpsee_core::client::ClientBuilder::build_with_tokio
+	pub fn serve_in_memory(self, methods: impl Into<Methods>) -> Result<(InMemorySender, InMemoryReceiver), Error> {
+		let (conn, sink, rx) = self.connect(methods)?;
+		let (tx_requests, mut rx_requests) = mpsc::unbounded::<String>();
+
+		let serve = async move {
+			while let Some(request) = rx_requests.next().await {
+				conn.dispatch(request.into_bytes(), &sink);
+			}
+
+			conn.bounded_subscriptions.close();
+			conn.middleware.on_disconnect();
+		};
+		tokio::spawn(serve.in_current_span());
+
+		Ok((InMemorySender::new(tx_requests), InMemoryReceiver::new(rx)))
+	}
+
+	/// Create the state of the connection to the single client.
+	fn connect(self, methods: impl Into<Methods>) -> Result<Connected<M>, Error> {
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		let cfg = self.cfg;
+		let (tx, rx) = mpsc::unbounded();
+		let sink = MethodSink::new_with_limit(tx, cfg.max_response_body_size, cfg.max_log_length);
+		let conn = Arc::new(Connection {
+			methods,
+			resources: self.resources,
+			middleware: self.middleware,
+			id_provider: self.id_provider,
+			bounded_subscriptions: BoundedSubscriptions::new(cfg.max_subscriptions),
+			cfg,
+		});
+
+		conn.middleware.on_connect();
+		Ok((conn, sink, rx))
+	}
+}
+
+/// State of the connection to the client, the sink answering its requests and the receiving end of that sink.
+type Connected<M> = (Arc<Connection<M>>, MethodSink, mpsc::UnboundedReceiver<Bytes>);
+
+/// State shared by the tasks answering the requests of the client.
+struct Connection<M> {
+	cfg: Settings,
+	methods: Methods,
+	resources: Resources,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+	bounded_subscriptions: BoundedSubscriptions,
+}
+
+impl<M: Middleware> Connection<M> {
+	/// Answer the message `data` through `sink` in a task of its own, so that the next message can be read.
+	fn dispatch(self: &Arc<Self>, data: Vec<u8>, sink: &MethodSink) {
+		if data.len() > self.cfg.max_request_body_size as usize {
+			sink.send_error(Id::Null, reject_too_big_request(self.cfg.max_request_body_size));
+		} else {
+			tokio::spawn(self.clone().process(data, sink.for_call()).in_current_span());
+		}
+	}
+
+	/// Answer a single request or a batch through `sink`.
+	async fn process(self: Arc<Self>, data: Vec<u8>, sink: MethodSink) {
+		let middleware = &self.middleware;
+		let request_start = middleware.on_request();
+
+		match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+			Some(b'[') => {
+				if let Ok(batch) = parse_batch(&data) {
+					if !self.cfg.batch_requests_supported {
+						sink.send_error(
+							Id::Null,
+							ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
+						);
+					} else if !batch.is_empty() {
+						let trace = RpcTracing::batch();
+						rx_log_from_json(&batch, self.cfg.max_log_length);
+
+						// Batch responses must be sent back as a single message, the calls are answered through a
+						// channel of their own to collect their responses.
+						let (tx_batch, rx_batch) = mpsc::unbounded();
+						let sink_batch = MethodSink::new_with_limit(
+							tx_batch,
+							self.cfg.max_response_body_size,
+							self.cfg.max_log_length,
+						)
+						.with_notifications_to(sink.clone());
+
+						let (this, calls_sink) = (&self, &sink_batch);
+						async move {
+							for stage in batch_stages(batch) {
+								join_all(
+									stage
+										.into_iter()
+										.map(|req| this.execute(req, calls_sink.for_call(), request_start)),
+								)
+								.await;
+							}
+						}
+						.instrument(trace.span().clone())
+						.await;
+
+						drop(sink_batch);
+						if let Err(err) = sink.send_raw(collect_batch_response(rx_batch).await) {
+							tracing::warn!("Error sending batch response to the client: {:?}", err);
+						}
+					} else {
+						sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
+					}
+				} else if let Ok(batch) = parse_notification_batch(&data) {
+					rx_log_from_json(&batch, self.cfg.max_log_length);
+					return;
+				} else {
+					let (id, code) = prepare_error(&data);
+					sink.send_error(id, code.into());
+				}
+			}
+			_ => {
+				if let Ok(req) = parse_request(&data) {
+					let trace = RpcTracing::method_call(&req.method);
+					self.execute(req, sink.clone(), request_start).instrument(trace.span().clone()).await;
+				} else if let Ok(req) = parse_notification(&data) {
+					let trace = RpcTracing::notification(&req.method);
+					let _enter = trace.span().enter();
+					rx_log_from_json(&req, self.cfg.max_log_length);
+					return;
+				} else {
+					let (id, code) = prepare_error(&data);
+					sink.send_error(id, code.into());
+				}
+			}
+		}
+
+		report_response(middleware, sink.bytes_sent(), request_start);
+	}
+
+	/// Execute a method call, answering it through `sink`, which must be returned by [`MethodSink::for_call`].
+	async fn execute(&self, req: Request<'_>, sink: MethodSink, request_start: M::Instant) {
+		let middleware = &self.middleware;
+		rx_log_from_json(&req, self.cfg.max_log_length);
+
+		let id = req.id.clone();
+		let params = Params::new(None, req.params.map(|params| params.get()));
+		middleware.on_call_info(&CallInfo::new(&req.method, &params, &req.id, Transport::Stdio));
+
+		if let Err(err) = middleware.on_call_async(&req.method, &params).await {
+			tracing::warn!("Denied call to `{}`: {}", req.method, err.message());
+			sink.send_error(req.id, err);
+			report_result(middleware, &req.method, false, &sink, request_start);
+			return;
+		}
+
+		let (name, method) = match self.methods.method_with_name(&req.method) {
+			Some(method) => method,
+			None => {
+				sink.send_error(req.id, ErrorCode::MethodNotFound.into());
+				report_result(middleware, &req.method, false, &sink, request_start);
+				return;
+			}
+		};
+
+		// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+		let claimed = match method.inner() {
+			MethodKind::Unsubscription(_) => Ok(None),
+			_ => method.claim(name, &self.resources).map(Some),
+		};
+		let guard = match claimed {
+			Ok(guard) => guard,
+			Err(err) => {
+				tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
+				sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+				report_result(middleware, name, false, &sink, request_start);
+				return;
+			}
+		};
+
+		let result = match method.inner() {
+			MethodKind::Sync(callback) => callback(id, params, &sink),
+			MethodKind::Async(callback) => {
+				callback(id.into_owned(), params.into_owned(), sink.clone(), CONN_ID, guard).await
+			}
+			MethodKind::Subscription(callback) => match self.bounded_subscriptions.acquire() {
+				Some(close_notify) => {
+					let conn_state = ConnState { conn_id: CONN_ID, close_notify, id_provider: &*self.id_provider };
+					callback(id, params, sink.clone(), conn_state, guard)
+				}
+				None => {
+					sink.send_error(req.id, reject_too_many_subscriptions(self.bounded_subscriptions.max()));
+					false
+				}
+			},
+			MethodKind::Unsubscription(callback) => callback(id, params, &sink, CONN_ID),
+		};
+
+		report_result(middleware, name, result, &sink, request_start);
+	}
+}
+
+/// Configuration of the server.
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+	/// How messages are delimited.
+	framing: Framing,
+	/// Maximum size in bytes of a request.
+	max_request_body_size: u32,
+	/// Maximum size in bytes of a response.
+	max_response_body_size: u32,
+	/// Maximum number of incoming messages that will be logged.
+	max_log_length: u32,
+	/// Maximum number of active subscriptions.
+	max_subscriptions: u32,
+	/// Whether batch requests are supported by this server or not.
+	batch_requests_supported: bool,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			framing: Framing::default(),
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			max_response_body_size: TEN_MB_SIZE_BYTES,
+			max_log_length: 4096,
+			max_subscriptions: 1024,
+			batch_requests_supported: true,
+		}
+	}
+}
+
+/// Builder to configure and create a JSON-RPC stdio server.
+#[derive(Debug)]
+pub struct Builder<M = ()> {
+	settings: Settings,
+	resources: Resources,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl Default for Builder {
+	fn default() -> Self {
+		Builder {
+			settings: Settings::default(),
+			resources: Resources::default(),
+			middleware: (),
+			id_provider: Arc::new(RandomIntegerIdProvider),
+		}
+	}
+}
+
+impl Builder {
+	/// Create a default server builder.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl<M> Builder<M> {
+	/// Set how messages are delimited. Default is [`Framing::ContentLength`].
+	pub fn framing(mut self, framing: Framing) -> Self {
+		self.settings.framing = framing;
+		self
+	}
+
+	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.settings.max_request_body_size = size;
+		self
+	}
+
+	/// Set the maximum size of a response body in bytes. Default is 10 MiB.
+	pub fn max_response_body_size(mut self, size: u32) -> Self {
+		self.settings.max_response_body_size = size;
+		self
+	}
+
+	/// Set the maximum number of active subscriptions. Default is 1024.
+	pub fn max_subscriptions(mut self, max: u32) -> Self {
+		self.settings.max_subscriptions = max;
+		self
+	}
+
+	/// Enables or disables support of [batch requests](https://www.jsonrpc.org/specification#batch).
+	/// By default, support is enabled.
+	pub fn batch_requests_supported(mut self, supported: bool) -> Self {
+		self.settings.batch_requests_supported = supported;
+		self
+	}
+
+	/// Register a new resource kind. Errors if `label` is already registered.
+	///
+	/// See the module documentation for [`resurce_limiting`](../jsonrpsee_utils/server/resource_limiting/index.html#resource-limiting)
+	/// for details.
+	pub fn register_resource(mut self, label: &'static str, capacity: u16, default: u16) -> Result<Self, Error> {
+		self.resources.register(label, capacity, default)?;
+		Ok(self)
+	}
+
+	/// Add a middleware to the builder [`Middleware`](../jsonrpsee_core/middleware/trait.Middleware.html).
+	///
+	/// [`Middleware::on_connect`] is called once the server starts, and [`Middleware::on_disconnect`] once it
+	/// stops.
+	pub fn set_middleware<T: Middleware>(self, middleware: T) -> Builder<T> {
+		Builder { settings: self.settings, resources: self.resources, middleware, id_provider: self.id_provider }
+	}
+
+	/// Configure how [JSON-RPC subscription IDs](https://www.jsonrpc.org/specification#response_object) are
+	/// generated. Default is [`RandomIntegerIdProvider`].
+	pub fn set_id_provider<I: IdProvider + 'static>(mut self, id_provider: I) -> Self {
+		self.id_provider = Arc::new(id_provider);
+		self
+	}
+
+	/// Finalize the configuration of the server.
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee_stdio_server::{Framing, RpcModule, StdioServerBuilder};
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///
+	///     let server = StdioServerBuilder::default().framing(Framing::Newline).build();
+	///     // Requests are read from the standard input until it's closed.
+	///     let input: &[u8] = b"{\"jsonrpc\":\"2.0\",\"method\":\"say_hello\",\"id\":1}\n";
+	///     server.serve_with(module, input, tokio::io::sink()).await.unwrap();
+	/// }
+	/// ```
+	pub fn build(self) -> Server<M> {
+		Server {
+			cfg: self.settings,
+			resources: self.resources,
+			middleware: self.middleware,
+			id_provider: self.id_provider,
+		}
+	}
+}
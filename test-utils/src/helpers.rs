@@ -128,6 +128,13 @@ pub fn internal_error(id: Id) -> String {
 	)
 }
 
+pub fn server_is_busy(id: Id) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":-32604,"message":"Server is busy, try again later"}},"id":{}}}"#,
+		serde_json::to_string(&id).unwrap()
+	)
+}
+
 pub fn server_error(id: Id) -> String {
 	format!(
 		r#"{{"jsonrpc":"2.0","error":{{"code":-32000,"message":"Server error"}},"id":{}}}"#,
@@ -159,11 +166,21 @@ pub fn server_notification(method: &str, params: Value) -> String {
 }
 
 pub async fn http_request(body: Body, uri: Uri) -> Result<HttpResponse, String> {
+	http_request_with_headers(body, uri, &[]).await
+}
+
+pub async fn http_request_with_headers(
+	body: Body,
+	uri: Uri,
+	headers: &[(&'static str, &'static str)],
+) -> Result<HttpResponse, String> {
 	let client = hyper::Client::new();
-	let r = hyper::Request::post(uri)
-		.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"))
-		.body(body)
-		.expect("uri and request headers are valid; qed");
+	let mut r = hyper::Request::post(uri)
+		.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+	for (name, value) in headers {
+		r = r.header(*name, *value);
+	}
+	let r = r.body(body).expect("uri and request headers are valid; qed");
 	let res = client.request(r).await.map_err(|e| format!("{:?}", e))?;
 
 	let (parts, body) = res.into_parts();
@@ -146,6 +146,8 @@ pub enum ServerMode {
 	Subscription { subscription_id: String, subscription_response: String },
 	// Send out a notification after timeout
 	Notification(String),
+	// Accept the connection but never read from it, not even to answer pings.
+	Unresponsive,
 }
 
 /// JSONRPC v2 dummy WebSocket server that sends a hardcoded response.
@@ -203,6 +205,16 @@ impl WebSocketTestServer {
 		Self { local_addr, exit: tx }
 	}
 
+	// Spawns a dummy WebSocket server that accepts connections and then never reads from them.
+	pub async fn unresponsive(sockaddr: SocketAddr) -> Self {
+		let listener = tokio::net::TcpListener::bind(sockaddr).await.unwrap();
+		let local_addr = listener.local_addr().unwrap();
+		let (tx, rx) = mpsc::channel::<()>(4);
+		tokio::spawn(server_backend(listener, rx, ServerMode::Unresponsive));
+
+		Self { local_addr, exit: tx }
+	}
+
 	pub fn local_addr(&self) -> SocketAddr {
 		self.local_addr
 	}
@@ -257,6 +269,11 @@ async fn connection_task(socket: tokio::net::TcpStream, mode: ServerMode, mut ex
 
 	let (mut sender, receiver) = server.into_builder().finish();
 
+	if let ServerMode::Unresponsive = mode {
+		let _ = exit.next().await;
+		return;
+	}
+
 	let ws_stream = stream::unfold(receiver, move |mut receiver| async {
 		let mut buf = Vec::new();
 		let ret = match receiver.receive_data(&mut buf).await {
@@ -25,15 +25,20 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use futures::{SinkExt, StreamExt};
+use jsonrpsee::core::client::ClientMiddleware;
 use jsonrpsee::core::error::SubscriptionClosed;
 use jsonrpsee::core::server::access_control::{AccessControl, AccessControlBuilder};
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::types::error::{ErrorObject, SUBSCRIPTION_CLOSED_WITH_ERROR};
+use jsonrpsee::types::{Id, ParamsSer};
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
 use jsonrpsee::RpcModule;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
 
@@ -103,7 +108,7 @@ pub async fn websocket_server_with_subscription() -> (SocketAddr, WsServerHandle
 					"Server closed the stream because it was lazy",
 					None::<()>,
 				);
-				sink.close(err);
+				sink.close_with(err);
 			});
 			Ok(())
 		})
@@ -244,3 +249,106 @@ pub async fn http_server_with_access_control(acl: AccessControl) -> (SocketAddr,
 	let handle = server.start(module).unwrap();
 	(addr, handle)
 }
+
+/// SOCKS5 proxy requiring the `username`/`password` credentials, which relays every connection to
+/// `upstream` whatever the requested address.
+pub async fn socks5_proxy(upstream: SocketAddr, username: &'static str, password: &'static str) -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+
+	tokio::spawn(async move {
+		while let Ok((mut socket, _)) = listener.accept().await {
+			tokio::spawn(async move {
+				let mut greeting = [0; 2];
+				socket.read_exact(&mut greeting).await?;
+				let mut methods = vec![0; greeting[1] as usize];
+				socket.read_exact(&mut methods).await?;
+				assert!(methods.contains(&2), "username/password authentication not offered");
+				socket.write_all(&[5, 2]).await?;
+
+				let mut auth_header = [0; 2];
+				socket.read_exact(&mut auth_header).await?;
+				let mut user = vec![0; auth_header[1] as usize];
+				socket.read_exact(&mut user).await?;
+				let mut pass = vec![0; socket.read_u8().await? as usize];
+				socket.read_exact(&mut pass).await?;
+				let authenticated = user == username.as_bytes() && pass == password.as_bytes();
+				socket.write_all(&[1, if authenticated { 0 } else { 1 }]).await?;
+				if !authenticated {
+					return Ok(());
+				}
+
+				let mut request = [0; 4];
+				socket.read_exact(&mut request).await?;
+				let addr_len = match request[3] {
+					1 => 4,
+					4 => 16,
+					_ => socket.read_u8().await? as usize,
+				};
+				let mut requested_addr = vec![0; addr_len + 2];
+				socket.read_exact(&mut requested_addr).await?;
+				socket.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+
+				relay(socket, upstream).await
+			});
+		}
+	});
+
+	addr
+}
+
+/// HTTP proxy which tunnels every `CONNECT` request to `upstream` whatever the requested address.
+pub async fn http_connect_proxy(upstream: SocketAddr) -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+
+	tokio::spawn(async move {
+		while let Ok((mut socket, _)) = listener.accept().await {
+			tokio::spawn(async move {
+				let mut head = Vec::new();
+				while !head.ends_with(b"\r\n\r\n") {
+					head.push(socket.read_u8().await?);
+				}
+				assert!(head.starts_with(b"CONNECT "), "expected a CONNECT request");
+				socket.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await?;
+
+				relay(socket, upstream).await
+			});
+		}
+	});
+
+	addr
+}
+
+async fn relay(mut socket: TcpStream, upstream: SocketAddr) -> std::io::Result<()> {
+	let mut upstream = TcpStream::connect(upstream).await?;
+	tokio::io::copy_bidirectional(&mut socket, &mut upstream).await?;
+	Ok(())
+}
+
+/// Client middleware recording the events it's notified of.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingMiddleware {
+	events: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingMiddleware {
+	/// Returns the events recorded so far.
+	pub fn events(&self) -> Vec<String> {
+		self.events.lock().unwrap().clone()
+	}
+}
+
+impl ClientMiddleware for RecordingMiddleware {
+	fn on_request(&self, method: &str, _params: Option<&ParamsSer>, _id: &Id) {
+		self.events.lock().unwrap().push(format!("request {}", method));
+	}
+
+	fn on_response(&self, method: &str, _latency: Duration, success: bool) {
+		self.events.lock().unwrap().push(format!("response {} {}", method, success));
+	}
+
+	fn on_notification(&self, method: &str) {
+		self.events.lock().unwrap().push(format!("notification {}", method));
+	}
+}
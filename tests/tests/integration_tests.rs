@@ -31,15 +31,18 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use futures::{channel::mpsc, StreamExt, TryStreamExt};
-use helpers::{http_server, http_server_with_access_control, websocket_server, websocket_server_with_subscription};
-use jsonrpsee::core::client::{ClientT, IdKind, Subscription, SubscriptionClientT};
+use helpers::{
+	http_connect_proxy, http_server, http_server_with_access_control, socks5_proxy, websocket_server,
+	websocket_server_with_subscription, RecordingMiddleware,
+};
+use jsonrpsee::core::client::{BatchRequestBuilder, ClientT, IdKind, Subscription, SubscriptionClientT};
 use jsonrpsee::core::error::SubscriptionClosed;
 use jsonrpsee::core::{Error, JsonValue};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::AccessControlBuilder;
 use jsonrpsee::rpc_params;
 use jsonrpsee::types::error::ErrorObject;
-use jsonrpsee::ws_client::WsClientBuilder;
+use jsonrpsee::ws_client::{OverflowPolicy, RequestLimitPolicy, SubscriptionBuffer, WsClientBuilder};
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
 
@@ -70,6 +73,35 @@ async fn ws_subscription_works() {
 	}
 }
 
+#[tokio::test]
+async fn ws_batch_subscription_works() {
+	use jsonrpsee::types::error::{CallError, METHOD_NOT_FOUND_CODE};
+
+	init_logger();
+
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let batch = vec![("subscribe_hello", None, "unsubscribe_hello"), ("subscribe_foo", None, "unsubscribe_foo")];
+	let mut subs: Vec<Subscription<JsonValue>> = client.batch_subscribe(batch).await.unwrap();
+	assert_eq!(subs.len(), 2);
+
+	for _ in 0..3 {
+		assert_eq!(subs[0].next().await.unwrap().unwrap(), "hello from subscription");
+		assert_eq!(subs[1].next().await.unwrap().unwrap(), 1337);
+	}
+
+	// A failed subscription fails the whole batch, the client keeps working.
+	let batch = vec![("subscribe_hello", None, "unsubscribe_hello"), ("subscribe_unknown", None, "unsubscribe_unknown")];
+	let err = client.batch_subscribe::<JsonValue>(batch).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == METHOD_NOT_FOUND_CODE));
+
+	let sub: Subscription<u64> = client.subscribe("subscribe_foo", None, "unsubscribe_foo").await.unwrap();
+	drop(sub);
+	assert!(client.is_connected());
+}
+
 #[tokio::test]
 async fn ws_unsubscription_works() {
 	init_logger();
@@ -139,6 +171,17 @@ async fn ws_method_call_str_id_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn ws_method_call_uuid_id_works() {
+	init_logger();
+
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().id_format(IdKind::Uuid).build(&server_url).await.unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
 #[tokio::test]
 async fn http_method_call_works() {
 	init_logger();
@@ -161,6 +204,70 @@ async fn http_method_call_str_id_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn http_method_call_uuid_id_works() {
+	init_logger();
+
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().id_format(IdKind::Uuid).build(&uri).unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let batch = vec![("say_hello", None), ("say_hello", None)];
+	let responses: Vec<String> = client.batch_request(batch).await.unwrap();
+	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
+}
+
+#[tokio::test]
+async fn ws_batch_with_partial_failures_works() {
+	use jsonrpsee::types::error::{CallError, METHOD_NOT_FOUND_CODE};
+
+	init_logger();
+
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let mut batch = BatchRequestBuilder::new();
+	let hello = batch.insert::<String>("say_hello", None);
+	let missing = batch.insert::<u64>("unknown_method", None);
+	let raw_hello = batch.insert::<JsonValue>("say_hello", None);
+	let responses = client.batch(batch).await.unwrap();
+
+	assert_eq!(responses.len(), 3);
+	assert_eq!(responses.num_failed(), 1);
+	assert_eq!(responses.get(&hello).unwrap(), "hello");
+	assert!(matches!(responses.get(&missing), Err(Error::Call(CallError::Custom(err))) if err.code() == METHOD_NOT_FOUND_CODE));
+	assert_eq!(responses.get(&raw_hello).unwrap(), JsonValue::String("hello".into()));
+
+	// The connection is still usable.
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
+#[tokio::test]
+async fn http_batch_with_partial_failures_works() {
+	use jsonrpsee::types::error::{CallError, METHOD_NOT_FOUND_CODE};
+
+	init_logger();
+
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let mut batch = BatchRequestBuilder::new();
+	let hello = batch.insert::<String>("say_hello", None);
+	let missing = batch.insert::<u64>("unknown_method", None);
+	let health = batch.insert::<JsonValue>("system_health", None);
+	let responses = client.batch(batch).await.unwrap();
+
+	assert_eq!(responses.len(), 3);
+	assert_eq!(responses.get(&hello).unwrap(), "hello");
+	assert!(matches!(responses.get(&missing), Err(Error::Call(CallError::Custom(err))) if err.code() == METHOD_NOT_FOUND_CODE));
+	assert_eq!(responses.get(&health).unwrap(), serde_json::json!({ "health": true }));
+}
+
 #[tokio::test]
 async fn http_concurrent_method_call_limits_works() {
 	init_logger();
@@ -176,6 +283,88 @@ async fn http_concurrent_method_call_limits_works() {
 	assert!(matches!(second, Err(Error::MaxSlotsExceeded)));
 }
 
+#[tokio::test]
+async fn ws_client_middleware_works() {
+	init_logger();
+
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let middleware = RecordingMiddleware::default();
+	let client = WsClientBuilder::default().set_middleware(middleware.clone()).build(&server_url).await.unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+	client.request::<String>("unknown_method", None).await.unwrap_err();
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	sub.next().await.unwrap().unwrap();
+
+	let events = middleware.events();
+	assert_eq!(
+		events[..5],
+		[
+			"request say_hello",
+			"response say_hello true",
+			"request unknown_method",
+			"response unknown_method false",
+			"request subscribe_hello",
+		]
+	);
+	// The first notification may be received before the subscription is reported as successful.
+	assert!(events.contains(&"response subscribe_hello true".to_string()));
+	assert!(events.contains(&"notification subscribe_hello".to_string()));
+}
+
+#[tokio::test]
+async fn http_client_middleware_works() {
+	init_logger();
+
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let middleware = RecordingMiddleware::default();
+	let client = HttpClientBuilder::default().set_middleware(middleware.clone()).build(&uri).unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+	let batch = vec![("say_hello", None), ("unknown_method", None)];
+	let responses: Vec<Result<String, Error>> = client.batch_request_partial(batch).await.unwrap();
+	assert!(responses[0].is_ok() && responses[1].is_err());
+
+	assert_eq!(
+		middleware.events(),
+		[
+			"request say_hello",
+			"response say_hello true",
+			"request say_hello",
+			"request unknown_method",
+			"response say_hello true",
+			"response unknown_method false",
+		]
+	);
+}
+
+#[tokio::test]
+async fn ws_concurrent_method_calls_over_limit_are_queued() {
+	init_logger();
+
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default()
+		.max_concurrent_requests(1)
+		.request_limit_policy(RequestLimitPolicy::Queue)
+		.build(&server_url)
+		.await
+		.unwrap();
+
+	let started_at = std::time::Instant::now();
+	let (first, second) =
+		tokio::join!(client.request::<String>("slow_hello", None), client.request::<String>("slow_hello", None));
+
+	assert_eq!(first.unwrap(), "hello");
+	assert_eq!(second.unwrap(), "hello");
+	// The second call was only sent once the first one completed.
+	assert!(started_at.elapsed() >= Duration::from_secs(2));
+}
+
 #[tokio::test]
 async fn ws_subscription_several_clients() {
 	init_logger();
@@ -274,6 +463,52 @@ async fn ws_subscription_without_polling_doesnt_make_client_unuseable() {
 	other_sub.next().await.unwrap().unwrap();
 }
 
+#[tokio::test]
+async fn ws_subscription_overflow_policies_work() {
+	init_logger();
+
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+
+	let client = WsClientBuilder::default()
+		.max_notifs_per_subscription(2)
+		.subscription_overflow_policy(OverflowPolicy::DropOldest)
+		.build(&server_url)
+		.await
+		.unwrap();
+	let drop_oldest: Subscription<usize> = client.subscribe("subscribe_5_ints", None, "unsubscribe_5_ints").await.unwrap();
+	let drop_newest: Subscription<usize> = client
+		.subscribe_with_buffer(
+			"subscribe_5_ints",
+			None,
+			"unsubscribe_5_ints",
+			SubscriptionBuffer::new(2, OverflowPolicy::DropNewest),
+		)
+		.await
+		.unwrap();
+	let close_with_error: Subscription<usize> = client
+		.subscribe_with_buffer(
+			"subscribe_5_ints",
+			None,
+			"unsubscribe_5_ints",
+			SubscriptionBuffer::new(2, OverflowPolicy::CloseWithError),
+		)
+		.await
+		.unwrap();
+
+	// don't poll the subscription streams until the server has sent all the items.
+	tokio::time::sleep(Duration::from_secs(1)).await;
+
+	// Capacity is `num_sender` + `capacity`
+	let drop_oldest: Vec<_> = drop_oldest.collect().await;
+	assert!(matches!(drop_oldest[..], [Err(Error::SubscriptionLagged(2)), Ok(3), Ok(4), Ok(5)]));
+	let drop_newest: Vec<_> = drop_newest.collect().await;
+	assert!(matches!(drop_newest[..], [Ok(1), Ok(2), Ok(3), Err(Error::SubscriptionLagged(2))]));
+	let close_with_error: Vec<_> = close_with_error.collect().await;
+	assert!(matches!(close_with_error[..], [Ok(1), Ok(2), Ok(3), Err(Error::SubscriptionBufferFull)]));
+	assert!(client.is_connected());
+}
+
 #[tokio::test]
 async fn ws_making_more_requests_than_allowed_should_not_deadlock() {
 	init_logger();
@@ -374,6 +609,13 @@ async fn server_should_be_able_to_close_subscriptions() {
 
 	let mut sub: Subscription<String> = client.subscribe("subscribe_noop", None, "unsubscribe_noop").await.unwrap();
 
+	// The reason the server gave is the last item.
+	match sub.next().await {
+		Some(Err(Error::SubscriptionClosedByServer(err))) => {
+			assert_eq!(err.message(), "Server closed the stream because it was lazy")
+		}
+		other => panic!("Expected the reason the subscription was closed, got {:?}", other),
+	}
 	assert!(sub.next().await.is_none());
 }
 
@@ -819,6 +1061,62 @@ async fn http_cors_preflight_works() {
 	assert!(has(&allow_origins, "https://foo.com") || has(&allow_origins, "*"));
 }
 
+#[tokio::test]
+async fn http_cors_credentials_max_age_and_exposed_headers_work() {
+	use hyper::{Body, Client, Method, Request};
+
+	init_logger();
+
+	let acl = AccessControlBuilder::new()
+		.set_allowed_origins(vec!["https://foo.com"])
+		.unwrap()
+		.allow_credentials(true)
+		.set_max_age(Duration::from_secs(600))
+		.set_exposed_headers(vec!["x-request-id", "retry-after"])
+		.build();
+	let (server_addr, _handle) = http_server_with_access_control(acl).await;
+
+	let http_client = Client::new();
+	let uri = format!("http://{}", server_addr);
+
+	let preflight_req = Request::builder()
+		.method(Method::OPTIONS)
+		.uri(&uri)
+		.header("host", "bar.com")
+		.header("origin", "https://foo.com")
+		.header("access-control-request-method", "POST")
+		.header("access-control-request-headers", "content-type")
+		.body(Body::empty())
+		.expect("preflight request builder");
+
+	let preflight_res = http_client.request(preflight_req).await.unwrap();
+	let preflight_headers = preflight_res.headers();
+	assert!(preflight_res.status().is_success());
+	assert_eq!(comma_separated_header_values(preflight_headers, "access-control-allow-credentials"), vec!["true"]);
+	assert_eq!(comma_separated_header_values(preflight_headers, "access-control-max-age"), vec!["600"]);
+	assert!(preflight_headers.get("access-control-expose-headers").is_none());
+
+	let req = Request::builder()
+		.method(Method::POST)
+		.uri(&uri)
+		.header("host", "bar.com")
+		.header("origin", "https://foo.com")
+		.header("content-type", "application/json")
+		.body(Body::from(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#))
+		.expect("actual request builder");
+
+	let res = http_client.request(req).await.unwrap();
+	let headers = res.headers();
+	assert!(res.status().is_success());
+	assert_eq!(comma_separated_header_values(headers, "access-control-allow-origin"), vec!["https://foo.com"]);
+	assert_eq!(comma_separated_header_values(headers, "access-control-allow-credentials"), vec!["true"]);
+	assert_eq!(
+		comma_separated_header_values(headers, "access-control-expose-headers"),
+		vec!["x-request-id", "retry-after"]
+	);
+	assert!(headers.get("access-control-max-age").is_none());
+}
+
 fn comma_separated_header_values(headers: &hyper::HeaderMap, header: &str) -> Vec<String> {
 	headers
 		.get_all(header)
@@ -865,49 +1163,1060 @@ async fn http_health_api_works() {
 }
 
 #[tokio::test]
-async fn ws_host_filtering_wildcard_works() {
-	use jsonrpsee::ws_server::*;
+async fn http_health_api_draining_works() {
+	use hyper::{Body, Client, Request, StatusCode};
 
 	init_logger();
 
-	let acl = AccessControlBuilder::default()
-		.set_allowed_hosts(vec!["http://localhost:*", "http://127.0.0.1:*"])
+	let (server_addr, handle) = http_server().await;
+
+	let http_client = Client::new();
+	let uri = format!("http://{}/health", server_addr);
+	let health = || Request::builder().method("GET").uri(&uri).body(Body::empty()).expect("request builder");
+
+	assert!(!handle.is_draining());
+	assert!(http_client.request(health()).await.unwrap().status().is_success());
+
+	handle.drain();
+	assert!(handle.is_draining());
+	assert_eq!(http_client.request(health()).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+	// Calls are still served while draining.
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
+#[tokio::test]
+async fn http_health_api_aggregate_works() {
+	use hyper::{Body, Client, Request, StatusCode};
+	use jsonrpsee::http_server::{HealthPolicy, HttpServerBuilder};
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	async fn health(policy: HealthPolicy) -> (StatusCode, JsonValue) {
+		let server = HttpServerBuilder::default()
+			.health_api_aggregate("/health", ["chain_health", "peers_health", "db_health"], policy)
+			.unwrap()
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let mut module = RpcModule::new(());
+		module.register_method("chain_health", |_, _| Ok(serde_json::json!({ "synced": true }))).unwrap();
+		module.register_async_method("peers_health", |_, _| async { Ok(8) }).unwrap();
+		module
+			.register_method::<(), _>("db_health", |_, _| Err(Error::Custom("database unreachable".into())))
+			.unwrap();
+		let server_addr = server.local_addr().unwrap();
+		let _handle = server.start(module).unwrap();
+
+		let uri = format!("http://{}/health", server_addr);
+		let req = Request::builder().method("GET").uri(&uri).body(Body::empty()).expect("request builder");
+		let res = Client::new().request(req).await.unwrap();
+		let status = res.status();
+		let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+		(status, serde_json::from_slice(&bytes).unwrap())
+	}
+
+	let (status, summary) = health(HealthPolicy::Quorum(2)).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(
+		summary,
+		serde_json::json!({
+			"healthy": true,
+			"checks": [
+				{ "method": "chain_health", "healthy": true, "result": { "synced": true } },
+				{ "method": "peers_health", "healthy": true, "result": 8 },
+				{ "method": "db_health", "healthy": false },
+			]
+		})
+	);
+
+	let (status, summary) = health(HealthPolicy::All).await;
+	assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(summary["healthy"], false);
+	assert_eq!(summary["checks"].as_array().unwrap().len(), 3);
+
+	// Invalid configurations are rejected.
+	assert!(HttpServerBuilder::default().health_api_aggregate("/health", Vec::<String>::new(), HealthPolicy::All).is_err());
+	assert!(HttpServerBuilder::default().health_api_aggregate("/health", ["a", "b"], HealthPolicy::Quorum(3)).is_err());
+	assert!(HttpServerBuilder::default().health_api_aggregate("health", ["a"], HealthPolicy::All).is_err());
+}
+
+#[tokio::test]
+async fn http_health_api_liveness_and_readiness_work() {
+	use hyper::{Body, Client, Request, StatusCode};
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::RpcModule;
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	init_logger();
+
+	let server = HttpServerBuilder::default()
+		.health_api_liveness("/health/live", "system_live")
 		.unwrap()
-		.build();
+		.health_api("/health/ready", "system_ready")
+		.unwrap()
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(AtomicBool::new(false));
+	module.register_method("system_live", |_, _| Ok(true)).unwrap();
+	module
+		.register_method("system_ready", |_, synced| {
+			Ok(serde_json::json!({ "healthy": synced.load(Ordering::Relaxed), "peers": 3 }))
+		})
+		.unwrap();
+	module
+		.register_method("set_synced", |_, synced| {
+			synced.store(true, Ordering::Relaxed);
+			Ok(())
+		})
+		.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
 
-	let server = WsServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let http_client = Client::new();
+	let health = |path: &str| {
+		let req = Request::builder()
+			.method("GET")
+			.uri(format!("http://{}{}", server_addr, path))
+			.body(Body::empty())
+			.expect("request builder");
+		let res = http_client.request(req);
+		async move {
+			let res = res.await.unwrap();
+			let status = res.status();
+			let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+			(status, String::from_utf8(bytes.to_vec()).unwrap())
+		}
+	};
+
+	assert_eq!(health("/health/live").await, (StatusCode::OK, "true".to_owned()));
+	// The method reports the server as not ready yet.
+	assert_eq!(health("/health/ready").await, (StatusCode::SERVICE_UNAVAILABLE, r#"{"healthy":false,"peers":3}"#.to_owned()));
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let _: () = client.request("set_synced", None).await.unwrap();
+	assert_eq!(health("/health/ready").await, (StatusCode::OK, r#"{"healthy":true,"peers":3}"#.to_owned()));
+
+	// Only readiness endpoints fail while draining.
+	handle.drain();
+	assert_eq!(health("/health/ready").await.0, StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(health("/health/live").await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn http_metrics_api_works() {
+	use hyper::{Body, Client, Request};
+	use jsonrpsee::prometheus::PrometheusMiddleware;
+	use jsonrpsee::{http_server::HttpServerBuilder, RpcModule};
+	use prometheus::{Encoder, Registry, TextEncoder};
+
+	init_logger();
+
+	let registry = Registry::new();
+	let middleware = PrometheusMiddleware::new(&registry).unwrap();
+	let server = HttpServerBuilder::default()
+		.set_middleware(middleware)
+		.metrics_api("/metrics", move || {
+			let mut buf = Vec::new();
+			TextEncoder::new().encode(&registry.gather(), &mut buf)?;
+			Ok(buf)
+		})
+		.unwrap()
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
 	let mut module = RpcModule::new(());
-	let addr = server.local_addr().unwrap();
 	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
-
+	let server_addr = server.local_addr().unwrap();
 	let _handle = server.start(module).unwrap();
 
-	let server_url = format!("ws://{}", addr);
-	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
 
-	assert!(client.request::<String>("say_hello", None).await.is_ok());
+	let http_client = Client::new();
+	let uri = format!("http://{}/metrics", server_addr);
+	let req = Request::builder().method("GET").uri(&uri).body(Body::empty()).expect("request builder");
+	let res = http_client.request(req).await.unwrap();
+
+	assert!(res.status().is_success());
+	assert_eq!(res.headers().get("content-type").unwrap(), "text/plain; version=0.0.4");
+
+	let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let out = String::from_utf8(bytes.to_vec()).unwrap();
+	assert!(out.contains(r#"jsonrpsee_calls_started_total{method="say_hello"} 1"#));
+
+	// Other paths are still not allowed.
+	let uri = format!("http://{}/other", server_addr);
+	let req = Request::builder().method("GET").uri(&uri).body(Body::empty()).expect("request builder");
+	let res = http_client.request(req).await.unwrap();
+	assert_eq!(res.status(), hyper::StatusCode::METHOD_NOT_ALLOWED);
 }
 
 #[tokio::test]
-async fn http_host_filtering_wildcard_works() {
-	use jsonrpsee::http_server::*;
+async fn http_get_routes_work() {
+	use hyper::{Body, Client, Request, Response, StatusCode};
+	use jsonrpsee::{http_server::HttpServerBuilder, RpcModule};
 
 	init_logger();
 
-	let acl = AccessControlBuilder::default()
-		.set_allowed_hosts(vec!["http://localhost:*", "http://127.0.0.1:*"])
+	let server = HttpServerBuilder::default()
+		.get_route("/version", |_request| async { Response::new(Body::from("1.2.3")) })
 		.unwrap()
-		.build();
+		.get_route("/favicon.ico", |_request| async {
+			Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty()).unwrap()
+		})
+		.unwrap()
+		.health_api("/health", "system_health")
+		.unwrap()
+		// Replaces the health endpoint.
+		.get_route("/health", |request: Request<Body>| async move { Response::new(Body::from(request.uri().to_string())) })
+		.unwrap()
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
 
-	let server = HttpServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
-	let mut module = RpcModule::new(());
-	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let http_client = Client::new();
+	let get = |path: &str| {
+		let req = Request::builder()
+			.method("GET")
+			.uri(format!("http://{}{}", server_addr, path))
+			.body(Body::empty())
+			.expect("request builder");
+		let res = http_client.request(req);
+		async move {
+			let res = res.await.unwrap();
+			let status = res.status();
+			let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+			(status, String::from_utf8(bytes.to_vec()).unwrap())
+		}
+	};
 
-	let _handle = server.start(module).unwrap();
+	assert_eq!(get("/version").await, (StatusCode::OK, "1.2.3".to_owned()));
+	assert_eq!(get("/favicon.ico").await, (StatusCode::NO_CONTENT, String::new()));
+	assert_eq!(get("/health?verbose").await, (StatusCode::OK, "/health?verbose".to_owned()));
+	assert_eq!(get("/unknown").await.0, StatusCode::METHOD_NOT_ALLOWED);
 
-	let server_url = format!("http://{}", addr);
-	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	assert!(HttpServerBuilder::default().get_route("version", |_| async { Response::new(Body::empty()) }).is_err());
+}
 
-	assert!(client.request::<String>("say_hello", None).await.is_ok());
+#[tokio::test]
+async fn ws_authentication_works() {
+	use jsonrpsee::types::error::{CallError, UNAUTHORIZED_CODE};
+	use jsonrpsee::ws_server::{Authenticator, Permissions, StaticKeys, WsServerBuilder};
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let keys = StaticKeys::new().key("secret", Permissions::new(["admin"]));
+	let authenticator = Authenticator::new(keys).require_scope("admin", ["admin_only"]).unwrap();
+	let server = WsServerBuilder::default().set_authenticator(authenticator).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("public", |_, _| Ok("ok")).unwrap();
+	module.register_method("admin_only", |_, _| Ok("ok")).unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	// Anonymous clients may only call public methods.
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	assert_eq!(client.request::<String>("public", None).await.unwrap(), "ok");
+	match client.request::<String>("admin_only", None).await.unwrap_err() {
+		Error::Call(CallError::Custom(err)) => assert_eq!(err.code(), UNAUTHORIZED_CODE),
+		e => panic!("Expected unauthorized error, got: {:?}", e),
+	}
+
+	let client = WsClientBuilder::default().add_header("Authorization", "Bearer secret").build(&server_url).await.unwrap();
+	assert_eq!(client.request::<String>("admin_only", None).await.unwrap(), "ok");
+
+	// Invalid credentials are rejected during the handshake.
+	assert!(WsClientBuilder::default().add_header("Authorization", "Bearer wrong").build(&server_url).await.is_err());
+}
+
+#[tokio::test]
+async fn http_authentication_works() {
+	use jsonrpsee::http_server::{Authenticator, HttpServerBuilder, Permissions, StaticKeys};
+	use jsonrpsee::types::error::{CallError, UNAUTHORIZED_CODE};
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let keys = StaticKeys::new().key("secret", Permissions::new(["admin"]));
+	let authenticator = Authenticator::new(keys).require_scope("admin", ["admin_only"]).unwrap();
+	let server = HttpServerBuilder::default().set_authenticator(authenticator).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("admin_only", |_, _| Ok("ok")).unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	match client.request::<String>("admin_only", None).await.unwrap_err() {
+		Error::Call(CallError::Custom(err)) => assert_eq!(err.code(), UNAUTHORIZED_CODE),
+		e => panic!("Expected unauthorized error, got: {:?}", e),
+	}
+
+	let client = HttpClientBuilder::default().add_header("Authorization", "Bearer secret").build(&server_url).unwrap();
+	assert_eq!(client.request::<String>("admin_only", None).await.unwrap(), "ok");
+
+	assert!(HttpClientBuilder::default().add_header("Authorization", "Bearer\nsecret").build(&server_url).is_err());
+}
+
+#[tokio::test]
+async fn ws_client_connects_through_proxies() {
+	use jsonrpsee::ws_client::Proxy;
+
+	init_logger();
+
+	let server_addr = websocket_server().await;
+	// The host name can't be resolved locally, connecting only works if the proxy takes care of it.
+	let server_url = format!("ws://jsonrpsee.invalid:{}", server_addr.port());
+
+	let socks5 = socks5_proxy(server_addr, "alice", "secret").await.to_string();
+	let proxy = Proxy::socks5(&socks5).credentials("alice", "secret");
+	let client = WsClientBuilder::default().proxy(proxy).build(&server_url).await.unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	let proxy = Proxy::socks5(&socks5).credentials("alice", "wrong");
+	assert!(WsClientBuilder::default().proxy(proxy).build(&server_url).await.is_err());
+
+	let proxy = Proxy::http(http_connect_proxy(server_addr).await.to_string());
+	let client = WsClientBuilder::default().proxy(proxy).build(&server_url).await.unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn ws_host_filtering_wildcard_works() {
+	use jsonrpsee::ws_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default()
+		.set_allowed_hosts(vec!["http://localhost:*", "http://127.0.0.1:*"])
+		.unwrap()
+		.build();
+
+	let server = WsServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("ws://{}", addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+}
+
+#[tokio::test]
+async fn http_host_filtering_wildcard_works() {
+	use jsonrpsee::http_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default()
+		.set_allowed_hosts(vec!["http://localhost:*", "http://127.0.0.1:*"])
+		.unwrap()
+		.build();
+
+	let server = HttpServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("http://{}", addr);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+}
+
+#[tokio::test]
+async fn server_set_works() {
+	use jsonrpsee::core::server::server_set::{Launched, ServerSet, ServerStatus};
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let handle = ServerSet::new(module)
+		.server("http", |methods| async move {
+			let server = HttpServerBuilder::default().build("127.0.0.1:0").await?;
+			let addrs = server.local_addrs().to_vec();
+			Ok(Launched::new(addrs, server.start(methods)?))
+		})
+		.unwrap()
+		.server("ws", |methods| async move {
+			let server = WsServerBuilder::default().build("127.0.0.1:0").await?;
+			let addrs = server.local_addrs().to_vec();
+			Ok(Launched::new(addrs, server.start(methods)?))
+		})
+		.unwrap()
+		.start()
+		.await
+		.unwrap();
+
+	let stats = handle.stats();
+	assert_eq!(stats.iter().map(|stats| stats.name).collect::<Vec<_>>(), ["http", "ws"]);
+	assert!(stats.iter().all(|stats| stats.status == ServerStatus::Running && stats.restarts == 0));
+
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", stats[0].local_addrs[0])).unwrap();
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", stats[1].local_addrs[0])).await.unwrap();
+	let response: String = http_client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+	let response: String = ws_client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	handle.stop().unwrap();
+	let stopped = tokio::time::timeout(Duration::from_secs(5), handle).await;
+	assert!(stopped.is_ok());
+	assert!(http_client.request::<String>("say_hello", None).await.is_err());
+}
+
+#[tokio::test]
+async fn panicking_methods_are_caught_and_reported() {
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::types::error::{CallError, ErrorCode};
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+	use std::sync::Mutex;
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method::<(), _>("sync_panic", |_, _| panic!("sync boom")).unwrap();
+	module.register_async_method::<(), _, _>("async_panic", |_, _| async { panic!("async boom") }).unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let reports = Arc::new(Mutex::new(Vec::new()));
+	let http_reports = reports.clone();
+	let ws_reports = reports.clone();
+
+	let http_server = HttpServerBuilder::default()
+		.panic_hook(move |report| {
+			http_reports.lock().unwrap().push((report.method.to_owned(), report.message.to_owned()))
+		})
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let http_addr = http_server.local_addr().unwrap();
+	let _http_handle = http_server.start(module.clone()).unwrap();
+
+	let ws_server = WsServerBuilder::default()
+		.panic_hook(move |report| {
+			ws_reports.lock().unwrap().push((report.method.to_owned(), report.message.to_owned()))
+		})
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let ws_addr = ws_server.local_addr().unwrap();
+	let _ws_handle = ws_server.start(module).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+
+	for method in ["sync_panic", "async_panic"] {
+		for err in [
+			http_client.request::<JsonValue>(method, None).await.unwrap_err(),
+			ws_client.request::<JsonValue>(method, None).await.unwrap_err(),
+		] {
+			match err {
+				Error::Call(CallError::Custom(err)) => {
+					assert_eq!(err.code(), ErrorCode::InternalError.code());
+					let data: JsonValue = serde_json::from_str(err.data().unwrap().get()).unwrap();
+					assert!(data["correlation_id"].is_string());
+				}
+				err => panic!("Unexpected error: {:?}", err),
+			}
+		}
+	}
+
+	// The server and the connection survive the panics.
+	let response: String = http_client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+	let response: String = ws_client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut reports = reports.lock().unwrap().clone();
+	reports.sort();
+	assert_eq!(
+		reports,
+		[
+			("async_panic".to_owned(), "async boom".to_owned()),
+			("async_panic".to_owned(), "async boom".to_owned()),
+			("sync_panic".to_owned(), "sync boom".to_owned()),
+			("sync_panic".to_owned(), "sync boom".to_owned()),
+		]
+	);
+}
+
+#[tokio::test]
+async fn methods_can_be_added_and_removed_at_runtime() {
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::types::error::{CallError, METHOD_NOT_FOUND_CODE};
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let mut admin = RpcModule::new(());
+	admin.register_method("admin_unlock", |_, _| Ok(true)).unwrap();
+
+	let http_server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let http_addr = http_server.local_addr().unwrap();
+	let http_handle = http_server.start(module.clone()).unwrap();
+	let ws_server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let ws_addr = ws_server.local_addr().unwrap();
+	let ws_handle = ws_server.start(module).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+	// The connection is established before the methods are changed.
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+
+	let is_method_not_found =
+		|res: Result<bool, Error>| matches!(res, Err(Error::Call(CallError::Custom(err))) if err.code() == METHOD_NOT_FOUND_CODE);
+	assert!(is_method_not_found(http_client.request("admin_unlock", None).await));
+	assert!(is_method_not_found(ws_client.request("admin_unlock", None).await));
+
+	http_handle.methods().add_module(admin.clone()).unwrap();
+	ws_handle.methods().add_module(admin.clone()).unwrap();
+	assert!(matches!(http_handle.methods().add_module(admin), Err(Error::MethodAlreadyRegistered(_))));
+	assert!(http_client.request::<bool>("admin_unlock", None).await.unwrap());
+	assert!(ws_client.request::<bool>("admin_unlock", None).await.unwrap());
+
+	assert!(http_handle.methods().remove_method("admin_unlock"));
+	assert!(ws_handle.methods().remove_method("admin_unlock"));
+	assert!(!ws_handle.methods().remove_method("admin_unlock"));
+	assert!(is_method_not_found(http_client.request("admin_unlock", None).await));
+	assert!(is_method_not_found(ws_client.request("admin_unlock", None).await));
+
+	let response: String = ws_client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
+#[tokio::test]
+async fn ws_subscription_cleanup_runs_on_close() {
+	use jsonrpsee::core::server::rpc_module::SubscriptionCloseReason;
+	use jsonrpsee::{ws_server::WsServerBuilder, RpcModule};
+
+	init_logger();
+
+	let (tx, mut reasons) = mpsc::unbounded();
+	let mut module = RpcModule::new(tx);
+	module
+		.register_subscription("subscribe_ticks", "tick", "unsubscribe_ticks", |params, mut sink, tx| {
+			let count: Option<usize> = params.one().ok();
+			let tx = (*tx).clone();
+			sink.on_close(move |reason| async move { tx.unbounded_send(reason).unwrap() });
+			tokio::spawn(async move {
+				let stream = IntervalStream::new(interval(Duration::from_millis(10))).map(|_| 0_usize);
+				match count {
+					Some(count) => sink.pipe_from_stream(stream.take(count)).await,
+					None => sink.pipe_from_stream(stream).await,
+				};
+			});
+			Ok(())
+		})
+		.unwrap();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let sub = client.subscribe::<usize>("subscribe_ticks", None, "unsubscribe_ticks").await.unwrap();
+	sub.unsubscribe().await.unwrap();
+	assert_eq!(reasons.next().await, Some(SubscriptionCloseReason::Unsubscribed));
+
+	let _sub = client.subscribe::<usize>("subscribe_ticks", rpc_params![1_usize], "unsubscribe_ticks").await.unwrap();
+	assert_eq!(reasons.next().await, Some(SubscriptionCloseReason::Closed));
+
+	let _sub = client.subscribe::<usize>("subscribe_ticks", None, "unsubscribe_ticks").await.unwrap();
+	drop(client);
+	assert_eq!(reasons.next().await, Some(SubscriptionCloseReason::Disconnected));
+}
+
+#[tokio::test]
+async fn unsafe_integers_follow_number_policy() {
+	use jsonrpsee::types::error::{CallError, UNSAFE_INTEGER_CODE};
+	use jsonrpsee::types::number::{LenientInt, NumberPolicy};
+	use jsonrpsee::{http_server::HttpServerBuilder, ws_server::WsServerBuilder, RpcModule};
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("balance", |_, _| Ok(serde_json::json!({ "amount": u64::MAX, "fee": 1 }))).unwrap();
+	module
+		.register_subscription("subscribe_balance", "balance", "unsubscribe_balance", |_, mut sink, _| {
+			sink.send(&u64::MAX).unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let server = HttpServerBuilder::default().set_number_policy(NumberPolicy::Reject).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let _http_handle = server.start(module.clone()).unwrap();
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	match client.request::<JsonValue>("balance", None).await {
+		Err(Error::Call(CallError::Custom(err))) => assert_eq!(err.code(), UNSAFE_INTEGER_CODE),
+		res => panic!("Expected the result to be rejected, got: {:?}", res),
+	}
+
+	let server = WsServerBuilder::default().set_number_policy(NumberPolicy::Stringify).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _ws_handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let balance: JsonValue = client.request("balance", None).await.unwrap();
+	assert_eq!(balance, serde_json::json!({ "amount": u64::MAX.to_string(), "fee": 1 }));
+
+	let mut sub: Subscription<LenientInt<u64>> =
+		client.subscribe("subscribe_balance", None, "unsubscribe_balance").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), LenientInt(u64::MAX));
+}
+
+#[tokio::test]
+async fn system_limits_method_works() {
+	use jsonrpsee::http_server::{HttpServerBuilder, RateLimit, RateLimiter, ResponseCompression};
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let limiter = RateLimiter::new().default_limit(RateLimit::new(100, Duration::from_secs(1)));
+
+	let server = HttpServerBuilder::default()
+		.max_request_body_size(1024)
+		.max_batch_size(10)
+		.set_rate_limiter(limiter.clone())
+		.set_response_compression(ResponseCompression::new())
+		.system_limits_method(true)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let http_addr = server.local_addr().unwrap();
+	let _http_handle = server.start(RpcModule::new(())).unwrap();
+
+	let ws_server = WsServerBuilder::default()
+		.batch_requests_supported(false)
+		.max_subscriptions_per_connection(8)
+		.set_rate_limiter(limiter)
+		.system_limits_method(true)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let ws_addr = ws_server.local_addr().unwrap();
+	let _ws_handle = ws_server.start(RpcModule::new(())).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+	let limits: JsonValue = http_client.request("system_limits", None).await.unwrap();
+	assert_eq!(
+		limits,
+		serde_json::json!({
+			"max_request_body_size": 1024,
+			"max_response_body_size": 10 * 1024 * 1024,
+			"batch_requests_supported": true,
+			"max_batch_size": 10,
+			"max_subscriptions_per_connection": null,
+			"rate_limits": [{ "group": null, "methods": [], "calls": 100, "period_ms": 1000 }],
+			"request_encodings": ["gzip"],
+			"response_encodings": ["gzip", "deflate"],
+		})
+	);
+
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+	let limits: JsonValue = ws_client.request("system_limits", None).await.unwrap();
+	assert_eq!(limits["batch_requests_supported"], false);
+	assert_eq!(limits["max_batch_size"], JsonValue::Null);
+	assert_eq!(limits["max_subscriptions_per_connection"], 8);
+	assert_eq!(limits["response_encodings"], serde_json::json!([]));
+
+	// Not registered unless enabled.
+	let (server_addr, _handle) = http_server().await;
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	assert!(client.request::<JsonValue>("system_limits", None).await.is_err());
+}
+
+#[tokio::test]
+async fn ws_shutdown_reason_is_sent_to_clients() {
+	use jsonrpsee::ws_server::{RpcModule, ShutdownReason, WsServerBuilder};
+
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+	assert_eq!(handle.shutdown_reason(), None);
+
+	let client = WsClientBuilder::default().build(format!("ws://{}", server_addr)).await.unwrap();
+	let mut shutdown: Subscription<JsonValue> = client.subscribe_to_method("server_shutdown").await.unwrap();
+
+	let stopped = handle.clone().stop_with_reason(ShutdownReason::Restart).unwrap();
+	tokio::time::timeout(Duration::from_secs(5), stopped).await.unwrap();
+
+	let notif = tokio::time::timeout(Duration::from_secs(5), shutdown.next()).await.unwrap().unwrap().unwrap();
+	assert_eq!(notif, serde_json::json!({ "reason": "restart" }));
+	assert_eq!(handle.shutdown_reason(), Some(ShutdownReason::Restart));
+	assert!(matches!(handle.stop(), Err(Error::AlreadyStopped)));
+}
+
+#[tokio::test]
+async fn ws_client_reconnects_and_replays_subscriptions() {
+	use futures::FutureExt;
+	use jsonrpsee::ws_client::{ConnectionEvent, ReconnectPolicy};
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder, WsServerHandle};
+	use std::net::SocketAddr;
+
+	async fn server(addr: SocketAddr) -> (SocketAddr, WsServerHandle) {
+		let server = WsServerBuilder::default().build(addr).await.unwrap();
+		let mut module = RpcModule::new(());
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+		module
+			.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, mut sink, _| {
+				tokio::spawn(async move {
+					while let Ok(true) = sink.send(&"hello") {
+						tokio::time::sleep(Duration::from_millis(20)).await;
+					}
+				});
+				Ok(())
+			})
+			.unwrap();
+		(server.local_addr().unwrap(), server.start(module).unwrap())
+	}
+
+	init_logger();
+
+	let (server_addr, handle) = server("127.0.0.1:0".parse().unwrap()).await;
+	let policy = ReconnectPolicy::new().initial_delay(Duration::from_millis(50)).max_delay(Duration::from_millis(200));
+	let client =
+		WsClientBuilder::default().build_reconnecting(format!("ws://{}", server_addr), policy).await.unwrap();
+	let mut events = client.events();
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+
+	handle.stop().unwrap().await;
+	let event = tokio::time::timeout(Duration::from_secs(5), events.next()).await.unwrap();
+	assert!(matches!(event, Some(ConnectionEvent::Disconnected(_))));
+	assert!(!client.is_connected());
+	// Calls fail right away during the outage.
+	assert!(matches!(client.request::<String>("say_hello", None).await, Err(Error::Reconnecting)));
+
+	let (_, _handle) = server(server_addr).await;
+	let event = tokio::time::timeout(Duration::from_secs(5), events.next()).await.unwrap();
+	assert!(matches!(event, Some(ConnectionEvent::Reconnected(_))));
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+
+	// The subscription yields the notifications of the new server.
+	while let Some(Some(_)) = sub.next().now_or_never() {}
+	let notif = tokio::time::timeout(Duration::from_secs(5), sub.next()).await.unwrap();
+	assert_eq!(notif.unwrap().unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn clients_retry_failed_calls() {
+	use jsonrpsee::core::client::{RetryOn, RetryPolicy};
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::types::error::{CallError, ErrorCode};
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	// Every call fails twice before succeeding.
+	let calls = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(calls.clone());
+	module
+		.register_method("flaky", |_, calls| match calls.fetch_add(1, Ordering::SeqCst) % 3 {
+			2 => Ok("done"),
+			_ => Err(CallError::Custom(ErrorCode::ServerIsBusy.into()).into()),
+		})
+		.unwrap();
+
+	let http_server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let http_addr = http_server.local_addr().unwrap();
+	let _http_handle = http_server.start(module.clone()).unwrap();
+	let ws_server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let ws_addr = ws_server.local_addr().unwrap();
+	let _ws_handle = ws_server.start(module).unwrap();
+
+	let policy = RetryPolicy::new(3).backoff(Duration::from_millis(10), Duration::from_millis(10));
+
+	let client = HttpClientBuilder::default().retry_policy(policy.clone()).build(format!("http://{}", http_addr)).unwrap();
+	let response: String = client.request("flaky", None).await.unwrap();
+	assert_eq!(response, "done");
+	assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+	let client =
+		WsClientBuilder::default().retry_policy(policy.clone()).build(format!("ws://{}", ws_addr)).await.unwrap();
+	let response: String = client.request("flaky", None).await.unwrap();
+	assert_eq!(response, "done");
+	assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+	// Gives up once out of attempts, and doesn't retry on other errors or other methods.
+	let client = HttpClientBuilder::default()
+		.retry_policy(RetryPolicy::new(2).backoff(Duration::from_millis(10), Duration::from_millis(10)))
+		.build(format!("http://{}", http_addr))
+		.unwrap();
+	assert!(client.request::<String>("flaky", None).await.is_err());
+	assert_eq!(calls.swap(0, Ordering::SeqCst), 8);
+
+	let client = HttpClientBuilder::default()
+		.retry_policy(policy.clone().retry_on([RetryOn::Timeout]))
+		.build(format!("http://{}", http_addr))
+		.unwrap();
+	assert!(client.request::<String>("flaky", None).await.is_err());
+	assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+	let client = HttpClientBuilder::default()
+		.retry_policy(policy.methods(["other"]))
+		.build(format!("http://{}", http_addr))
+		.unwrap();
+	assert!(client.request::<String>("flaky", None).await.is_err());
+	assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn ws_compression_works() {
+	use jsonrpsee::ws_client::DeflateConfig;
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	init_logger();
+
+	let payload = "compressible ".repeat(10_000);
+	let mut module = RpcModule::new(payload.clone());
+	module.register_method("echo", |params, _| params.one::<String>().map_err(Into::into)).unwrap();
+	module
+		.register_subscription("subscribe_big", "big", "unsubscribe_big", |_, mut sink, payload| {
+			sink.send(&*payload).unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let server = WsServerBuilder::default().set_compression(DeflateConfig::new()).build("127.0.0.1:0").await.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default()
+		.compression(DeflateConfig::new().threshold(16))
+		.build(format!("ws://{}", server_addr))
+		.await
+		.unwrap();
+	let echoed: String = client.request("echo", rpc_params![&payload]).await.unwrap();
+	assert_eq!(echoed, payload);
+	let mut sub: Subscription<String> = client.subscribe("subscribe_big", None, "unsubscribe_big").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), payload);
+
+	// Servers without compression are still reachable by clients offering it.
+	let plain_addr = websocket_server().await;
+	let client =
+		WsClientBuilder::default().compression(DeflateConfig::new()).build(format!("ws://{}", plain_addr)).await.unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn stdio_server_and_client_work() {
+	use jsonrpsee::client_transport::stdio::{self, Framing};
+	use jsonrpsee::core::client::ClientBuilder;
+	use jsonrpsee::stdio_server::{RpcModule, StdioServerBuilder};
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			let stream = IntervalStream::new(interval(Duration::from_millis(50))).map(move |_| "hello");
+			tokio::spawn(async move {
+				sink.pipe_from_stream(stream).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	for framing in [Framing::ContentLength, Framing::Newline] {
+		let (client_out, server_in) = tokio::io::duplex(1024);
+		let (server_out, client_in) = tokio::io::duplex(1024);
+		let server = StdioServerBuilder::default().framing(framing).build();
+		let server = tokio::spawn(server.serve_with(module.clone(), server_in, server_out));
+
+		let (sender, receiver) = stdio::connect(client_in, client_out, framing);
+		let client = ClientBuilder::default().build_with_tokio(sender, receiver);
+
+		let response: String = client.request("say_hello", None).await.unwrap();
+		assert_eq!(response, "hello");
+
+		let batch = vec![("say_hello", rpc_params![]), ("say_hello", rpc_params![])];
+		let responses: Vec<String> = client.batch_request(batch).await.unwrap();
+		assert_eq!(responses, ["hello", "hello"]);
+
+		let mut sub: Subscription<String> =
+			client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+		assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+		sub.unsubscribe().await.unwrap();
+
+		// The server stops once its input is closed.
+		drop(client);
+		tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap().unwrap();
+	}
+}
+
+#[tokio::test]
+async fn in_memory_client_works() {
+	use jsonrpsee::core::client::ClientBuilder;
+	use jsonrpsee::stdio_server::{RpcModule, StdioServerBuilder};
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module.register_async_method("add", |params, _| async move {
+		let (a, b): (u64, u64) = params.parse()?;
+		Ok(a + b)
+	})
+	.unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			let stream = IntervalStream::new(interval(Duration::from_millis(50))).map(move |_| "hello");
+			tokio::spawn(async move {
+				sink.pipe_from_stream(stream).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let (sender, receiver) = StdioServerBuilder::default().max_subscriptions(1).build().serve_in_memory(module).unwrap();
+	let client = ClientBuilder::default().id_format(IdKind::String).build_with_tokio(sender, receiver);
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+	let response: u64 = client.request("add", rpc_params![1, 2]).await.unwrap();
+	assert_eq!(response, 3);
+	assert!(matches!(client.request::<String>("unknown", None).await, Err(Error::Call(_))));
+
+	let batch = vec![("say_hello", rpc_params![]), ("add", rpc_params![3, 4])];
+	let responses: Vec<JsonValue> = client.batch_request(batch).await.unwrap();
+	assert_eq!(responses, [serde_json::json!("hello"), serde_json::json!(7)]);
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	// The limit of subscriptions applies to the in-memory client too.
+	assert!(client.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.is_err());
+	sub.unsubscribe().await.unwrap();
+}
+
+#[tokio::test]
+async fn recorded_session_can_be_replayed() {
+	use jsonrpsee::core::client::replay::{Pacing, Recording};
+	use jsonrpsee::core::client::{ClientBuilder, ReceivedMessage, TransportReceiverT};
+	use jsonrpsee::stdio_server::StdioServerBuilder;
+	use jsonrpsee::ws_server::{RpcModule, WireTap, WsServerBuilder};
+	use std::io::Write;
+	use std::sync::Mutex;
+
+	#[derive(Clone, Default)]
+	struct Capture(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for Capture {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			sink.send(&"hello").unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	// Record a session.
+	let capture = Capture::default();
+	let tap = WireTap::new(capture.clone()).max_frame_len(usize::MAX);
+	let server = WsServerBuilder::default().set_wire_tap(tap).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module.clone()).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	drop(sub);
+	drop(client);
+
+	let recording = Recording::from_reader(capture.0.lock().unwrap().as_slice()).unwrap();
+	let recording = recording.session(recording.sessions()[0]);
+
+	// A client making the same calls receives the recorded answers.
+	let (sender, receiver) = recording.mock_server();
+	let client = ClientBuilder::default().build_with_tokio(sender, receiver);
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+
+	// The recorded calls can be sent to another server.
+	let (mut sender, mut receiver) = StdioServerBuilder::default().build().serve_in_memory(module).unwrap();
+	assert!(recording.replay_requests(&mut sender, Pacing::Immediate).await.unwrap() >= 2);
+	let response = match receiver.receive().await.unwrap() {
+		ReceivedMessage::Bytes(bytes) => serde_json::from_slice::<JsonValue>(&bytes).unwrap(),
+		other => panic!("Unexpected message: {:?}", other),
+	};
+	assert_eq!(response, serde_json::json!({ "jsonrpc": "2.0", "result": "hello", "id": 0 }));
+}
+
+#[tokio::test]
+async fn error_data_can_be_read_by_the_client() {
+	use jsonrpsee::types::error::CallError;
+	use jsonrpsee::{ws_server::WsServerBuilder, RpcModule};
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct InsufficientFunds {
+		needed: u64,
+		available: u64,
+	}
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_method("transfer", |_, _| -> Result<(), Error> {
+			let data = InsufficientFunds { needed: 10, available: 3 };
+			Err(CallError::Custom(ErrorObject::owned_with_data(-32001, "Insufficient funds", data)).into())
+		})
+		.unwrap();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let err = client.request::<()>("transfer", None).await.unwrap_err();
+	let err = err.error_object().unwrap();
+	assert_eq!(err.code(), -32001);
+	assert_eq!(err.data_as::<InsufficientFunds>().unwrap(), Some(InsufficientFunds { needed: 10, available: 3 }));
 }
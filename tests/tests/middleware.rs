@@ -29,10 +29,16 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use jsonrpsee::core::{client::ClientT, middleware::Middleware, Error};
+use jsonrpsee::core::middleware::{
+	CallInfo, CompressionInfo, ConnectionInfo, Middleware, OnCallFuture, ResponseInfo, Transport,
+};
+use jsonrpsee::core::{client::ClientT, Error, JsonValue};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::rpc_params;
+use jsonrpsee::types::error::{CallError, ErrorObject, UNAUTHORIZED_CODE};
+use jsonrpsee::types::{Id, Params};
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
 use jsonrpsee::RpcModule;
@@ -194,3 +200,410 @@ async fn http_server_middleware() {
 	// HTTP server doesn't track connections
 	assert_eq!(inner.connections, (0, 0));
 }
+
+/// Middleware recording every call after awaiting, and rejecting calls to `admin_` methods.
+#[derive(Clone, Default)]
+struct Auditor {
+	log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Auditor {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_call_async(&self, name: &str, params: &Params) -> OnCallFuture {
+		let entry = match params.one::<u64>() {
+			Ok(n) => format!("{}[{}]", name, n),
+			Err(_) => name.to_owned(),
+		};
+		let denied = name.starts_with("admin_");
+		let log = self.log.clone();
+
+		Box::pin(async move {
+			sleep(Duration::from_millis(10)).await;
+			log.lock().unwrap().push(entry);
+
+			if denied {
+				Err(ErrorObject::owned(UNAUTHORIZED_CODE, "Admin methods are disabled", None::<()>))
+			} else {
+				Ok(())
+			}
+		})
+	}
+}
+
+fn audited_module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module.register_method("admin_unlock", |_, _| Ok(true)).unwrap();
+	module
+}
+
+fn assert_unauthorized<T: std::fmt::Debug>(res: Result<T, Error>) {
+	match res {
+		Err(Error::Call(CallError::Custom(err))) => assert_eq!(err.code(), UNAUTHORIZED_CODE),
+		res => panic!("Expected the call to be denied, got: {:?}", res),
+	}
+}
+
+async fn assert_audited(client: &impl ClientT) {
+	assert_eq!(client.request::<String>("say_hello", rpc_params![1]).await.unwrap(), "hello");
+	assert_unauthorized(client.request::<bool>("admin_unlock", None).await);
+
+	let batch = vec![("say_hello", None), ("say_hello", rpc_params![2])];
+	assert_eq!(client.batch_request::<String>(batch).await.unwrap(), ["hello", "hello"]);
+	// Batches mixing results and errors can't be decoded by the clients.
+	assert!(client.batch_request::<bool>(vec![("admin_unlock", None)]).await.is_err());
+}
+
+#[tokio::test]
+async fn async_middleware_hook_works() {
+	let auditor = Auditor::default();
+	let http_server = HttpServerBuilder::default().set_middleware(auditor.clone()).build("127.0.0.1:0").await.unwrap();
+	let http_url = format!("http://{}", http_server.local_addr().unwrap());
+	let _http_handle = http_server.start(audited_module()).unwrap();
+	let ws_server = WsServerBuilder::default().set_middleware(auditor.clone()).build("127.0.0.1:0").await.unwrap();
+	let ws_url = format!("ws://{}", ws_server.local_addr().unwrap());
+	let _ws_handle = ws_server.start(audited_module()).unwrap();
+
+	assert_audited(&HttpClientBuilder::default().build(&http_url).unwrap()).await;
+	assert_audited(&WsClientBuilder::default().build(&ws_url).await.unwrap()).await;
+
+	let mut log = auditor.log.lock().unwrap().clone();
+	log.sort();
+	assert_eq!(
+		log,
+		["admin_unlock"; 4]
+			.into_iter()
+			.chain(["say_hello"; 2])
+			.chain(["say_hello[1]"; 2])
+			.chain(["say_hello[2]"; 2])
+			.collect::<Vec<_>>()
+	);
+}
+
+/// Name, parameters, id and transport of a call.
+type RecordedCall = (String, JsonValue, Id<'static>, Transport);
+
+/// Middleware recording the details of every call.
+#[derive(Clone, Default)]
+struct CallRecorder {
+	calls: Arc<Mutex<Vec<RecordedCall>>>,
+}
+
+impl Middleware for CallRecorder {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_call_info(&self, info: &CallInfo) {
+		let params = info.params.parse::<JsonValue>().unwrap();
+		self.calls.lock().unwrap().push((info.name.to_owned(), params, info.id.clone().into_owned(), info.transport));
+	}
+}
+
+#[tokio::test]
+async fn middleware_receives_call_info() {
+	let recorder = CallRecorder::default();
+	let http_server = HttpServerBuilder::default().set_middleware(recorder.clone()).build("127.0.0.1:0").await.unwrap();
+	let http_url = format!("http://{}", http_server.local_addr().unwrap());
+	let _http_handle = http_server.start(audited_module()).unwrap();
+	let ws_server = WsServerBuilder::default().set_middleware(recorder.clone()).build("127.0.0.1:0").await.unwrap();
+	let ws_url = format!("ws://{}", ws_server.local_addr().unwrap());
+	let _ws_handle = ws_server.start(audited_module()).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(&http_url).unwrap();
+	http_client.request::<String>("say_hello", rpc_params![1]).await.unwrap();
+	let batch = vec![("say_hello", None), ("admin_unlock", rpc_params!["key"])];
+	http_client.batch_request::<JsonValue>(batch).await.unwrap();
+	let ws_client = WsClientBuilder::default().build(&ws_url).await.unwrap();
+	ws_client.request::<String>("say_hello", None).await.unwrap();
+
+	let calls = recorder.calls.lock().unwrap().clone();
+	let details: Vec<_> =
+		calls.iter().map(|(name, params, _, transport)| (name.as_str(), params, *transport)).collect();
+	assert_eq!(
+		details,
+		[
+			("say_hello", &serde_json::json!([1]), Transport::Http),
+			("say_hello", &JsonValue::Null, Transport::Http),
+			("admin_unlock", &serde_json::json!(["key"]), Transport::Http),
+			("say_hello", &JsonValue::Null, Transport::WebSocket),
+		]
+	);
+	// The calls of the batch have distinct ids.
+	assert!(calls.iter().all(|(_, _, id, _)| matches!(id, Id::Number(_))));
+	assert_ne!(calls[1].2, calls[2].2);
+}
+
+/// Middleware recording the connections that were opened and closed.
+#[derive(Clone, Default)]
+struct ConnectionTracker {
+	opened: Arc<Mutex<Vec<ConnectionInfo>>>,
+	closed: Arc<Mutex<Vec<ConnectionInfo>>>,
+}
+
+impl Middleware for ConnectionTracker {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_connect_info(&self, info: &ConnectionInfo) {
+		self.opened.lock().unwrap().push(*info);
+	}
+
+	fn on_disconnect_info(&self, info: &ConnectionInfo) {
+		self.closed.lock().unwrap().push(*info);
+	}
+}
+
+#[tokio::test]
+async fn ws_middleware_receives_connection_info() {
+	let tracker = ConnectionTracker::default();
+	let counter = Counter::default();
+	let server = WsServerBuilder::default()
+		.set_middleware((tracker.clone(), counter.clone()))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	let handle = server.start(test_module()).unwrap();
+
+	let first = WsClientBuilder::default().build(&url).await.unwrap();
+	let second = WsClientBuilder::default().build(&url).await.unwrap();
+	assert_eq!(first.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert_eq!(second.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	let opened = tracker.opened.lock().unwrap().clone();
+	assert_eq!(opened.len(), 2);
+	assert_ne!(opened[0].conn_id, opened[1].conn_id);
+	assert!(opened.iter().all(|info| info.remote_addr.ip().is_loopback()));
+	assert_ne!(opened[0].remote_addr, opened[1].remote_addr);
+	// Middlewares only implementing `on_connect` are still notified.
+	assert_eq!(counter.inner.lock().unwrap().connections, (2, 0));
+
+	handle.stop().unwrap().await;
+
+	let mut closed = tracker.closed.lock().unwrap().clone();
+	closed.sort_by_key(|info| info.conn_id);
+	assert_eq!(closed, opened);
+	assert_eq!(counter.inner.lock().unwrap().connections, (2, 2));
+}
+
+/// Middleware recording the method names and error codes of failed calls.
+#[derive(Clone, Default)]
+struct ErrorRecorder {
+	errors: Arc<Mutex<Vec<(String, i32)>>>,
+}
+
+impl Middleware for ErrorRecorder {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_error(&self, name: &str, error: &ErrorObject) {
+		self.errors.lock().unwrap().push((name.to_owned(), error.code()));
+	}
+}
+
+fn failing_module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_async_method("fail", |_, _| async {
+			Err::<(), _>(Error::Call(CallError::Custom(ErrorObject::owned(1234, "Application error", None::<()>))))
+		})
+		.unwrap();
+	module
+}
+
+async fn assert_errors_recorded(client: &impl ClientT, recorder: &ErrorRecorder) {
+	recorder.errors.lock().unwrap().clear();
+
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert!(client.request::<()>("fail", None).await.is_err());
+	assert!(client.request::<()>("unknown_method", None).await.is_err());
+	assert!(client.batch_request::<JsonValue>(vec![("fail", None), ("say_hello", None)]).await.is_err());
+
+	let mut errors = recorder.errors.lock().unwrap().clone();
+	errors.sort();
+	assert_eq!(errors, [("fail".to_owned(), 1234), ("fail".to_owned(), 1234), ("unknown_method".to_owned(), -32601)]);
+}
+
+#[tokio::test]
+async fn middleware_receives_error_details() {
+	let recorder = ErrorRecorder::default();
+	let http_server = HttpServerBuilder::default().set_middleware(recorder.clone()).build("127.0.0.1:0").await.unwrap();
+	let http_url = format!("http://{}", http_server.local_addr().unwrap());
+	let _http_handle = http_server.start(failing_module()).unwrap();
+	let ws_server = WsServerBuilder::default().set_middleware(recorder.clone()).build("127.0.0.1:0").await.unwrap();
+	let ws_url = format!("ws://{}", ws_server.local_addr().unwrap());
+	let _ws_handle = ws_server.start(failing_module()).unwrap();
+
+	assert_errors_recorded(&HttpClientBuilder::default().build(&http_url).unwrap(), &recorder).await;
+	assert_errors_recorded(&WsClientBuilder::default().build(&ws_url).await.unwrap(), &recorder).await;
+}
+
+#[tokio::test]
+async fn per_method_middleware_works() {
+	let counter = Counter::default();
+	let method_errors = ErrorRecorder::default();
+	let server_errors = ErrorRecorder::default();
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap().with_middleware(counter.clone());
+	module.register_method("say_goodbye", |_, _| Ok("goodbye")).unwrap();
+	module
+		.register_async_method("fail", |_, _| async {
+			Err::<(), _>(Error::Call(CallError::Custom(ErrorObject::owned(1234, "Application error", None::<()>))))
+		})
+		.unwrap()
+		.with_middleware(method_errors.clone());
+
+	let server = WsServerBuilder::default().set_middleware(server_errors.clone()).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert_eq!(client.request::<String>("say_goodbye", None).await.unwrap(), "goodbye");
+	assert!(client.request::<()>("fail", None).await.is_err());
+
+	{
+		let inner = counter.inner.lock().unwrap();
+		assert_eq!(inner.requests, (2, 2));
+		assert_eq!(inner.calls.len(), 1);
+		assert_eq!(inner.calls["say_hello"], (2, vec![0, 1]));
+	}
+	assert_eq!(*method_errors.errors.lock().unwrap(), [("fail".to_owned(), 1234)]);
+	assert_eq!(*server_errors.errors.lock().unwrap(), [("fail".to_owned(), 1234)]);
+}
+
+/// Middleware recording the size of the responses, and of the responses of `say_hello` on its own when
+/// attached to that method.
+#[derive(Clone, Default)]
+struct ResponseSizeRecorder {
+	sizes: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Middleware for ResponseSizeRecorder {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_response_info(&self, info: &ResponseInfo, _: ()) {
+		self.sizes.lock().unwrap().push(info.size);
+	}
+}
+
+#[tokio::test]
+async fn middleware_receives_response_size() {
+	let server_sizes = ResponseSizeRecorder::default();
+	let method_sizes = ResponseSizeRecorder::default();
+	let module = || {
+		let mut module = RpcModule::new(());
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap().with_middleware(method_sizes.clone());
+		module
+	};
+
+	let http_server =
+		HttpServerBuilder::default().set_middleware(server_sizes.clone()).build("127.0.0.1:0").await.unwrap();
+	let http_url = format!("http://{}", http_server.local_addr().unwrap());
+	let _http_handle = http_server.start(module()).unwrap();
+	let ws_server = WsServerBuilder::default().set_middleware(server_sizes.clone()).build("127.0.0.1:0").await.unwrap();
+	let ws_url = format!("ws://{}", ws_server.local_addr().unwrap());
+	let _ws_handle = ws_server.start(module()).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(&http_url).unwrap();
+	http_client.request::<String>("say_hello", None).await.unwrap();
+	http_client.batch_request::<String>(vec![("say_hello", None), ("say_hello", None)]).await.unwrap();
+	let ws_client = WsClientBuilder::default().build(&ws_url).await.unwrap();
+	ws_client.request::<String>("say_hello", None).await.unwrap();
+
+	// The ids of all the calls have a single digit.
+	let hello = r#"{"jsonrpc":"2.0","result":"hello","id":0}"#.len();
+	assert_eq!(*server_sizes.sizes.lock().unwrap(), [hello, 2 * hello + 3, hello]);
+	assert_eq!(*method_sizes.sizes.lock().unwrap(), [hello; 4]);
+}
+
+/// Middleware recording the compression statistics of the connections.
+#[derive(Clone, Default)]
+struct CompressionRecorder {
+	connections: Arc<Mutex<Vec<CompressionInfo>>>,
+}
+
+impl Middleware for CompressionRecorder {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_compression_info(&self, info: &CompressionInfo) {
+		self.connections.lock().unwrap().push(info.clone());
+	}
+}
+
+#[tokio::test]
+async fn middleware_receives_compression_info() {
+	use jsonrpsee::http_server::ResponseCompression;
+	use jsonrpsee::ws_server::DeflateConfig;
+
+	let recorder = CompressionRecorder::default();
+	let module = || {
+		let mut module = RpcModule::new(());
+		module.register_method("large", |_, _| Ok("compressible ".repeat(1000))).unwrap();
+		module
+	};
+
+	let ws_server = WsServerBuilder::default()
+		.set_middleware(recorder.clone())
+		.set_compression(DeflateConfig::new())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let ws_url = format!("ws://{}", ws_server.local_addr().unwrap());
+	let ws_handle = ws_server.start(module()).unwrap();
+	let http_server = HttpServerBuilder::default()
+		.set_middleware(recorder.clone())
+		.set_response_compression(ResponseCompression::new())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let http_uri = format!("http://{}", http_server.local_addr().unwrap());
+	let http_handle = http_server.start(module()).unwrap();
+
+	let ws_client = WsClientBuilder::default().compression(DeflateConfig::new()).build(&ws_url).await.unwrap();
+	ws_client.request::<String>("large", None).await.unwrap();
+	ws_client.request::<String>("large", None).await.unwrap();
+	// Connections without compression are not reported.
+	let plain_client = WsClientBuilder::default().build(&ws_url).await.unwrap();
+	plain_client.request::<String>("large", None).await.unwrap();
+
+	let request = hyper::Request::post(&http_uri)
+		.header("content-type", "application/json")
+		.header("accept-encoding", "gzip")
+		.body(r#"{"jsonrpc":"2.0","method":"large","id":1}"#.into())
+		.unwrap();
+	let response = hyper::Client::new().request(request).await.unwrap();
+	assert_eq!(response.headers()["content-encoding"], "gzip");
+
+	ws_handle.stop().unwrap().await;
+	http_handle.stop().unwrap().await.unwrap();
+
+	let mut connections = recorder.connections.lock().unwrap().clone();
+	connections.sort_by_key(|info| info.transport == Transport::WebSocket);
+	assert_eq!(connections.len(), 2);
+
+	let http = &connections[0];
+	assert_eq!(http.transport, Transport::Http);
+	assert_eq!(http.negotiated, "gzip");
+	assert_eq!(http.messages, 1);
+
+	let ws = &connections[1];
+	assert_eq!(ws.negotiated, "permessage-deflate");
+	// Only the messages sent by the server are counted.
+	assert_eq!(ws.messages, 2);
+	assert!(ws.ratio() > 10.0);
+	assert!(!ws.disabled);
+}
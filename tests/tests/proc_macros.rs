@@ -60,7 +60,7 @@ mod rpc_impl {
 		#[subscription(name = "echo", unsubscribe = "unsubscribe_echo", aliases = ["alias_echo"], item = u32)]
 		fn sub_with_params(&self, val: u32);
 
-		#[method(name = "params")]
+		#[method(name = "params", aliases = ["foo_parameters"])]
 		fn params(&self, a: u8, b: &str) -> RpcResult<String> {
 			Ok(format!("Called with: {}, {}", a, b))
 		}
@@ -119,6 +119,22 @@ mod rpc_impl {
 	}
 
 	/// Trait to ensure that the trait bounds are correct.
+	#[rpc(server, namespace = "calc", openrpc)]
+	pub trait Calc<T> {
+		/// Adds two numbers.
+		///
+		/// Overflows are an error.
+		#[method(name = "add")]
+		fn add(&self, a: T, b: Option<T>) -> RpcResult<T>;
+
+		#[deprecated(note = "use `add` instead")]
+		#[method(name = "concat")]
+		fn concat(&self, a: &str, b: &str) -> RpcResult<String>;
+
+		#[method(name = "reset")]
+		async fn reset(&self) -> RpcResult<()>;
+	}
+
 	#[rpc(client, server, namespace = "generic_call")]
 	pub trait OnlyGenericCall<I, R> {
 		#[method(name = "getHeader")]
@@ -180,6 +196,23 @@ mod rpc_impl {
 		}
 	}
 
+	pub struct CalcServerImpl;
+
+	#[async_trait]
+	impl CalcServer<u64> for CalcServerImpl {
+		fn add(&self, a: u64, b: Option<u64>) -> RpcResult<u64> {
+			a.checked_add(b.unwrap_or_default()).ok_or_else(|| jsonrpsee::core::Error::Custom("Overflow".into()))
+		}
+
+		fn concat(&self, a: &str, b: &str) -> RpcResult<String> {
+			Ok(format!("{}{}", a, b))
+		}
+
+		async fn reset(&self) -> RpcResult<()> {
+			Ok(())
+		}
+	}
+
 	#[async_trait]
 	impl OnlyGenericCallServer<String, String> for RpcServerImpl {
 		fn call(&self, _: String) -> RpcResult<String> {
@@ -197,7 +230,7 @@ mod rpc_impl {
 }
 
 // Use generated implementations of server and client.
-use rpc_impl::{RpcClient, RpcServer, RpcServerImpl};
+use rpc_impl::{CalcServer, CalcServerImpl, RpcClient, RpcServer, RpcServerImpl};
 
 pub async fn websocket_server() -> SocketAddr {
 	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
@@ -246,6 +279,16 @@ async fn macro_param_parsing() {
 	assert_eq!(&res, "Called with: 42, Hello");
 }
 
+#[tokio::test]
+async fn macro_method_aliases_work() {
+	let module = RpcServerImpl.into_rpc();
+
+	// Aliases are not prefixed with the namespace.
+	let res: String = module.call("foo_parameters", [json!(42_u64), json!("Hello")]).await.unwrap();
+	assert_eq!(&res, "Called with: 42, Hello");
+	assert!(module.method("foo_foo_parameters").is_none());
+}
+
 #[tokio::test]
 async fn macro_optional_param_parsing() {
 	let module = RpcServerImpl.into_rpc();
@@ -374,3 +417,188 @@ async fn calls_with_bad_params() {
 		matches!(err, Error::Call(CallError::Custom (err)) if err.message().contains("invalid type: integer `99`, expected a string") && err.code() == ErrorCode::InvalidParams.code())
 	);
 }
+
+#[tokio::test]
+async fn macro_openrpc_discover_works() {
+	use jsonrpsee::core::server::openrpc::OpenRpc;
+	use jsonrpsee::types::EmptyParams;
+
+	let mut document = OpenRpc::new("Calculator", "1.0.0");
+	<CalcServerImpl as CalcServer<u64>>::openrpc(&mut document);
+
+	let mut module = CalcServerImpl.into_rpc();
+	module.register_discover(&document).unwrap();
+
+	let res: u64 = module.call("calc_add", [1_u64, 2]).await.unwrap();
+	assert_eq!(res, 3);
+
+	let document: serde_json::Value = module.call("rpc.discover", EmptyParams::new()).await.unwrap();
+	assert_eq!(document["info"], json!({ "title": "Calculator", "version": "1.0.0" }));
+
+	let methods = document["methods"].as_array().unwrap();
+	assert_eq!(methods.len(), 3);
+
+	assert_eq!(methods[0]["name"], "calc_add");
+	assert_eq!(methods[0]["description"], "Adds two numbers.\n\nOverflows are an error.");
+	assert_eq!(methods[0]["params"][0]["name"], "a");
+	assert_eq!(methods[0]["params"][0]["required"], true);
+	assert_eq!(methods[0]["params"][0]["schema"]["type"], "integer");
+	assert_eq!(methods[0]["params"][1]["name"], "b");
+	assert_eq!(methods[0]["params"][1]["required"], false);
+	assert_eq!(methods[0]["result"]["schema"]["type"], "integer");
+
+	assert_eq!(methods[1]["name"], "calc_concat");
+	assert_eq!(methods[1]["deprecated"], true);
+	assert_eq!(methods[1]["params"][0]["schema"]["type"], "string");
+	assert_eq!(methods[1]["result"]["schema"]["type"], "string");
+
+	assert_eq!(methods[2]["name"], "calc_reset");
+	assert_eq!(methods[2]["params"], json!([]));
+	assert_eq!(methods[2]["result"]["schema"]["type"], "null");
+}
+
+#[tokio::test]
+async fn macro_timestamp_types_work() {
+	use jsonrpsee::core::server::openrpc::OpenRpc;
+	use jsonrpsee::core::RpcResult;
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::timestamp::{Rfc3339Timestamp, UnixTimestamp};
+
+	#[rpc(server, openrpc)]
+	pub trait Clock {
+		#[method(name = "clock_toUnix")]
+		fn to_unix(&self, at: Rfc3339Timestamp) -> RpcResult<UnixTimestamp>;
+	}
+
+	impl ClockServer for () {
+		fn to_unix(&self, at: Rfc3339Timestamp) -> RpcResult<UnixTimestamp> {
+			Ok(at.0.into())
+		}
+	}
+
+	let mut document = OpenRpc::new("Clock", "1.0.0");
+	<() as ClockServer>::openrpc(&mut document);
+	let document = document.to_json();
+	assert_eq!(document["methods"][0]["params"][0]["schema"], json!({ "type": "string", "format": "date-time" }));
+	assert_eq!(document["methods"][0]["result"]["schema"], json!({ "type": "integer", "format": "int64" }));
+
+	let module = ().into_rpc();
+	let secs: i64 = module.call("clock_toUnix", ["2022-05-01T14:30:00+02:00"]).await.unwrap();
+	assert_eq!(secs, 1_651_408_200);
+}
+
+#[tokio::test]
+async fn macro_schemas_validate_params() {
+	use jsonrpsee::core::server::schema::{ParamsValidator, Schemas};
+	use jsonrpsee::core::RpcResult;
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+
+	#[rpc(server, schemas)]
+	pub trait Wallet {
+		/// Sends `amount` to `to`.
+		#[method(name = "wallet_send")]
+		fn send(&self, to: String, amount: u64, memo: Option<String>) -> RpcResult<bool>;
+	}
+
+	impl WalletServer for () {
+		fn send(&self, _to: String, _amount: u64, _memo: Option<String>) -> RpcResult<bool> {
+			Ok(true)
+		}
+	}
+
+	let mut schemas = Schemas::new();
+	<() as WalletServer>::schemas(&mut schemas);
+
+	let method = schemas.method("wallet_send").unwrap();
+	assert_eq!(method.description.as_deref(), Some("Sends `amount` to `to`."));
+	assert_eq!(method.params.iter().map(|param| param.required).collect::<Vec<_>>(), [true, true, false]);
+
+	let server =
+		HttpServerBuilder::default().set_middleware(ParamsValidator::new(&schemas)).build("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}", server.local_addr().unwrap());
+	let _handle = server.start(().into_rpc()).unwrap();
+	let client = HttpClientBuilder::default().build(&url).unwrap();
+
+	assert!(client.request::<bool>("wallet_send", rpc_params!["alice", 1]).await.unwrap());
+	assert!(client.request::<bool>("wallet_send", rpc_params!["alice", 1, "rent"]).await.unwrap());
+
+	for params in [rpc_params!["alice", -1], rpc_params!["alice"], rpc_params!["alice", 1, 2]] {
+		match client.request::<bool>("wallet_send", params).await {
+			Err(Error::Call(CallError::Custom(err))) => assert_eq!(err.code(), INVALID_PARAMS_CODE),
+			res => panic!("Expected invalid params, got: {:?}", res),
+		}
+	}
+}
+
+#[tokio::test]
+async fn macro_custom_error_types_work() {
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::error::ErrorObjectOwned;
+
+	pub enum BankError {
+		InsufficientFunds { missing: u64 },
+		AccountFrozen,
+	}
+
+	impl From<BankError> for ErrorObjectOwned {
+		fn from(err: BankError) -> Self {
+			match err {
+				BankError::InsufficientFunds { missing } => {
+					ErrorObjectOwned::owned(1001, "Insufficient funds", Some(missing))
+				}
+				BankError::AccountFrozen => ErrorObjectOwned::owned(1002, "Account frozen", None::<()>),
+			}
+		}
+	}
+
+	#[rpc(server)]
+	pub trait Bank {
+		#[method(name = "bank_withdraw")]
+		fn withdraw(&self, amount: u64) -> Result<u64, BankError>;
+
+		#[method(name = "bank_close")]
+		async fn close(&self, account: String) -> Result<(), BankError>;
+	}
+
+	#[jsonrpsee::core::async_trait]
+	impl BankServer for () {
+		fn withdraw(&self, amount: u64) -> Result<u64, BankError> {
+			match amount {
+				0..=100 => Ok(100 - amount),
+				_ => Err(BankError::InsufficientFunds { missing: amount - 100 }),
+			}
+		}
+
+		async fn close(&self, _account: String) -> Result<(), BankError> {
+			Err(BankError::AccountFrozen)
+		}
+	}
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(().into_rpc()).unwrap();
+	let client = WsClientBuilder::default().build(&url).await.unwrap();
+
+	assert_eq!(client.request::<u64>("bank_withdraw", rpc_params![40]).await.unwrap(), 60);
+
+	match client.request::<u64>("bank_withdraw", rpc_params![150]).await {
+		Err(Error::Call(CallError::Custom(err))) => {
+			assert_eq!(err.code(), 1001);
+			assert_eq!(err.message(), "Insufficient funds");
+			assert_eq!(err.data_as::<u64>().unwrap(), Some(50));
+		}
+		res => panic!("Expected a custom error, got: {:?}", res),
+	}
+
+	match client.request::<()>("bank_close", rpc_params!["alice"]).await {
+		Err(Error::Call(CallError::Custom(err))) => assert_eq!(err.code(), 1002),
+		res => panic!("Expected a custom error, got: {:?}", res),
+	}
+
+	// Parameter errors are still reported as such.
+	match client.request::<u64>("bank_withdraw", rpc_params!["all"]).await {
+		Err(Error::Call(CallError::Custom(err))) => assert_eq!(err.code(), ErrorCode::InvalidParams.code()),
+		res => panic!("Expected invalid params, got: {:?}", res),
+	}
+}
@@ -25,9 +25,11 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
+use jsonrpsee::core::middleware::{Middleware, ResourceUsage};
 use jsonrpsee::core::Error;
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
@@ -291,3 +293,238 @@ async fn http_server_with_macro_module() {
 
 	run_tests_on_http_server(server_addr, server_handle).await;
 }
+
+#[tokio::test]
+async fn ws_server_amortizes_batch_resource_claims() {
+	for amortize in [false, true] {
+		let server = WsServerBuilder::default()
+			.register_resource("CPU", 6, 2)
+			.unwrap()
+			.register_resource("MEM", 10, 1)
+			.unwrap()
+			.register_resource("SUB", 6, 1)
+			.unwrap()
+			.amortize_batch_resource_claims(amortize)
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let server_addr = server.local_addr().unwrap();
+		let server_handle = server.start(module_manual().unwrap()).unwrap();
+
+		let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+		// 3 CPU units per call, so only 2 calls fit unless the batch is claimed once.
+		let batch = vec![("expensive_call", None), ("expensive_call", None), ("expensive_call", None)];
+		let result = client.batch_request::<String>(batch).await;
+
+		if amortize {
+			assert_eq!(result.unwrap(), vec!["hello expensive call"; 3]);
+		} else {
+			// The clients fail to decode batch responses that contain errors.
+			assert!(result.is_err());
+		}
+
+		server_handle.stop().unwrap().await;
+	}
+}
+
+#[tokio::test]
+async fn more_than_eight_resources_can_be_registered() {
+	const LABELS: [&str; 12] = ["R0", "R1", "R2", "R3", "R4", "R5", "R6", "R7", "R8", "R9", "R10", "R11"];
+
+	let mut builder = WsServerBuilder::default();
+	for label in LABELS {
+		builder = builder.register_resource(label, 1, 0).unwrap();
+	}
+	let server = builder.build("127.0.0.1:0").await.unwrap();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("say_hello", |_, _| async move {
+			sleep(Duration::from_millis(50)).await;
+			Ok("hello")
+		})
+		.unwrap()
+		.resource("R11", 1)
+		.unwrap();
+
+	let server_addr = server.local_addr().unwrap();
+	let server_handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+
+	let (pass, fail) =
+		tokio::join!(client.request::<String>("say_hello", None), client.request::<String>("say_hello", None));
+	assert!(pass.is_ok());
+	assert_server_busy(fail);
+
+	server_handle.stop().unwrap().await;
+}
+
+#[tokio::test]
+async fn ws_server_limits_resources_per_connection() {
+	let server = WsServerBuilder::default()
+		.register_resource("CPU", 10, 2)
+		.unwrap()
+		.register_resource("MEM", 10, 1)
+		.unwrap()
+		.register_resource("SUB", 6, 1)
+		.unwrap()
+		.connection_resource_quota("CPU", 4)
+		.unwrap()
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let server_handle = server.start(module_manual().unwrap()).unwrap();
+	let server_url = format!("ws://{}", server_addr);
+	let client1 = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let client2 = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	// 2 CPU units (default) per call, so only 2 calls of each client fit in its quota of 4 units even though
+	// the capacity of the server would allow 5 calls.
+	let (pass1, pass2, fail1, pass3, pass4, fail2) = tokio::join!(
+		client1.request::<String>("say_hello", None),
+		client1.request::<String>("say_hello", None),
+		client1.request::<String>("say_hello", None),
+		client2.request::<String>("say_hello", None),
+		client2.request::<String>("say_hello", None),
+		client2.request::<String>("say_hello", None),
+	);
+
+	assert!(pass1.is_ok());
+	assert!(pass2.is_ok());
+	assert_server_busy(fail1);
+	assert!(pass3.is_ok());
+	assert!(pass4.is_ok());
+	assert_server_busy(fail2);
+
+	// The units are released once the calls completed.
+	assert!(client1.request::<String>("say_hello", None).await.is_ok());
+	assert!(matches!(
+		WsServerBuilder::default().connection_resource_quota("CPU", 1),
+		Err(Error::ResourceNameNotFound(label)) if label == "CPU"
+	));
+
+	server_handle.stop().unwrap().await;
+}
+
+#[tokio::test]
+async fn resource_usage_is_reported() {
+	#[derive(Clone, Default)]
+	struct UsageRecorder(Arc<Mutex<Vec<ResourceUsage>>>);
+
+	impl Middleware for UsageRecorder {
+		type Instant = ();
+
+		fn on_request(&self) {}
+
+		fn on_resource_usage(&self, usage: &[ResourceUsage]) {
+			*self.0.lock().unwrap() = usage.to_vec();
+		}
+	}
+
+	let recorder = UsageRecorder::default();
+	let server = WsServerBuilder::default()
+		.register_resource("CPU", 6, 2)
+		.unwrap()
+		.register_resource("MEM", 10, 1)
+		.unwrap()
+		.register_resource("SUB", 6, 1)
+		.unwrap()
+		.set_middleware(recorder.clone())
+		.resource_usage_interval(Duration::from_millis(10))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let server_handle = server.start(module_manual().unwrap()).unwrap();
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let resources = server_handle.resources().clone();
+
+	// 3 CPU units, and the default of 1 MEM and 1 SUB unit per call.
+	let (a, b) = tokio::join!(
+		client.request::<String>("expensive_call", None),
+		client.request::<String>("expensive_call", None),
+	);
+	assert!(a.is_ok() && b.is_ok());
+
+	let usage = resources.usage();
+	assert_eq!(usage.iter().map(|usage| usage.label).collect::<Vec<_>>(), ["CPU", "MEM", "SUB"]);
+	assert_eq!((usage[0].in_use, usage[0].peak, usage[0].capacity), (0, 6, 6));
+	assert_eq!((usage[1].in_use, usage[1].peak, usage[1].capacity), (0, 2, 10));
+	assert_eq!((usage[2].in_use, usage[2].peak, usage[2].capacity), (0, 2, 6));
+
+	sleep(Duration::from_millis(100)).await;
+	assert_eq!(*recorder.0.lock().unwrap(), usage);
+
+	resources.reset_peak_usage();
+	assert!(resources.usage().iter().all(|usage| usage.peak == 0));
+
+	server_handle.stop().unwrap().await;
+}
+
+#[tokio::test]
+async fn resource_capacity_can_be_changed_at_runtime() {
+	let (server_addr, server_handle) = websocket_server(module_manual().unwrap()).await.unwrap();
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let resources = server_handle.resources().clone();
+
+	// 3 CPU units per call, so only 2 calls fit in the 6 units.
+	let expensive_calls = || async {
+		let (a, b, c) = tokio::join!(
+			client.request::<String>("expensive_call", None),
+			client.request::<String>("expensive_call", None),
+			client.request::<String>("expensive_call", None),
+		);
+		[a, b, c].into_iter().filter(|result| result.is_ok()).count()
+	};
+	assert_eq!(expensive_calls().await, 2);
+
+	resources.set_capacity("CPU", 9).unwrap();
+	assert_eq!(resources.capacity("CPU"), Some(9));
+	assert_eq!(expensive_calls().await, 3);
+
+	resources.set_capacity("CPU", 3).unwrap();
+	assert_eq!(expensive_calls().await, 1);
+
+	// A capacity of `0` disables the limit.
+	resources.set_capacity("CPU", 0).unwrap();
+	assert_eq!(expensive_calls().await, 3);
+
+	assert!(matches!(resources.set_capacity("GPU", 1), Err(Error::ResourceNameNotFound(label)) if label == "GPU"));
+	assert_eq!(resources.capacity("GPU"), None);
+
+	server_handle.stop().unwrap().await;
+}
+
+#[tokio::test]
+async fn http_server_amortizes_batch_resource_claims() {
+	for amortize in [false, true] {
+		let server = HttpServerBuilder::default()
+			.register_resource("CPU", 6, 2)
+			.unwrap()
+			.register_resource("MEM", 10, 1)
+			.unwrap()
+			.register_resource("SUB", 6, 1)
+			.unwrap()
+			.amortize_batch_resource_claims(amortize)
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let server_addr = server.local_addr().unwrap();
+		let server_handle = server.start(module_manual().unwrap()).unwrap();
+
+		let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+		// 3 CPU units per call, so only 2 calls fit unless the batch is claimed once.
+		let batch = vec![("expensive_call", None), ("expensive_call", None), ("expensive_call", None)];
+		let result = client.batch_request::<String>(batch).await;
+
+		if amortize {
+			assert_eq!(result.unwrap(), vec!["hello expensive call"; 3]);
+		} else {
+			// The clients fail to decode batch responses that contain errors.
+			assert!(result.is_err());
+		}
+
+		server_handle.stop().unwrap().await.unwrap();
+	}
+}
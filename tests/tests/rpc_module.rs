@@ -58,6 +58,53 @@ fn rpc_modules_with_different_contexts_can_be_merged() {
 	assert!(mod1.method("bla with String context").is_some());
 }
 
+#[tokio::test]
+async fn rpc_modules_can_be_merged_with_prefix() {
+	let mut module = RpcModule::new(());
+	module.register_method("block_number", |_: Params, _| Ok(1_u64)).unwrap();
+	let mut eth = RpcModule::new(String::from("eth"));
+	eth.register_method("block_number", |_: Params, ctx| Ok(format!("{} 2", ctx))).unwrap();
+	eth.register_subscription("subscribe_heads", "heads", "unsubscribe_heads", |_, mut sink, _| {
+		sink.accept()?;
+		Ok(())
+	})
+	.unwrap();
+
+	module.merge_with_prefix(eth.clone(), "eth_").unwrap();
+	assert!(
+		matches!(module.merge_with_prefix(eth, "eth_"), Err(Error::MethodAlreadyRegistered(name)) if name.starts_with("eth_"))
+	);
+
+	let res: u64 = module.call("block_number", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, 1);
+	let res: String = module.call("eth_block_number", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, "eth 2");
+	assert!(module.method("eth_subscribe_heads").is_some());
+	assert!(module.method("eth_unsubscribe_heads").is_some());
+	assert!(module.method("subscribe_heads").is_none());
+}
+
+#[tokio::test]
+async fn methods_run_on_executor() {
+	let mut module = RpcModule::new(());
+	let executor = |task: Box<dyn FnOnce() + Send>| {
+		std::thread::Builder::new().name("ffi-pool".into()).spawn(task).unwrap();
+	};
+	module
+		.register_method_on_executor("thread_name", executor, |_, _| {
+			Ok(std::thread::current().name().map(ToOwned::to_owned))
+		})
+		.unwrap();
+	// An executor that drops its tasks, e.g. because it's shutting down.
+	module.register_method_on_executor("dropped", |_task| (), |_, _| Ok(())).unwrap();
+
+	let res: Option<String> = module.call("thread_name", EmptyParams::new()).await.unwrap();
+	assert_eq!(res.as_deref(), Some("ffi-pool"));
+
+	let err = module.call::<_, ()>("dropped", EmptyParams::new()).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == ErrorCode::InternalError.code()));
+}
+
 #[test]
 fn flatten_rpc_modules() {
 	let mod1 = RpcModule::new(String::new());
@@ -87,6 +134,80 @@ fn rpc_register_alias() {
 	assert!(module.method("hello_foobar").is_some());
 }
 
+#[tokio::test]
+async fn rpc_methods_lists_merged_methods() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_: Params, _| Ok("hello")).unwrap();
+	let mut other = RpcModule::new(String::new());
+	other.register_subscription("sub_hello", "sub_hello", "unsub_hello", |_, _, _| Ok(())).unwrap();
+
+	let mut methods: Methods = module.into();
+	methods.merge(other).unwrap();
+	methods.register_rpc_methods().unwrap();
+	assert!(matches!(methods.register_rpc_methods(), Err(Error::MethodAlreadyRegistered(_))));
+
+	let res: serde_json::Value = methods.call(RPC_METHODS, EmptyParams::new()).await.unwrap();
+	assert_eq!(res, serde_json::json!({ "methods": ["rpc_methods", "say_hello", "sub_hello", "unsub_hello"] }));
+}
+
+#[tokio::test]
+async fn subscription_polling_without_server() {
+	use jsonrpsee::core::server::poll::{SubscriptionPolling, SUB_POLL_CREATE, SUB_POLL_NEXT};
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::Arc;
+
+	let closed = Arc::new(AtomicBool::new(false));
+	let mut module = RpcModule::new(closed.clone());
+	module
+		.register_subscription("count", "count", "uncount", |params, mut sink, _| {
+			let n: usize = params.one()?;
+			sink.accept()?;
+			for i in 0..n {
+				sink.send(&i).unwrap();
+			}
+			sink.close(ErrorObject::borrowed(0, &"done", None).into_owned());
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription("idle", "idle", "unidle", |_, mut sink, closed| {
+			sink.accept()?;
+			std::thread::spawn(move || {
+				while !sink.is_closed() {
+					std::thread::sleep(Duration::from_millis(10));
+				}
+				closed.store(true, Ordering::SeqCst);
+			});
+			Ok(())
+		})
+		.unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let polling =
+		SubscriptionPolling::new().max_items(3).poll_timeout(Duration::from_millis(50)).ttl(Duration::from_millis(200));
+	module.register_subscription_polling(polling).unwrap();
+
+	// The oldest notifications are dropped once the buffer is full.
+	let session: String = module.call(SUB_POLL_CREATE, ("count", [5])).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let poll: serde_json::Value = module.call(SUB_POLL_NEXT, [&session]).await.unwrap();
+	assert_eq!(poll, serde_json::json!({ "items": [2, 3, 4], "dropped": 2, "closed": true }));
+	// The session is gone once its subscription ended.
+	assert!(module.call::<_, serde_json::Value>(SUB_POLL_NEXT, [&session]).await.is_err());
+
+	// Polls wait for a notification up to the poll timeout.
+	let session: String = module.call(SUB_POLL_CREATE, ["idle"]).await.unwrap();
+	let poll: serde_json::Value = module.call(SUB_POLL_NEXT, [&session]).await.unwrap();
+	assert_eq!(poll, serde_json::json!({ "items": [], "dropped": 0, "closed": false }));
+
+	// Sessions that aren't polled expire, closing their subscription.
+	tokio::time::sleep(Duration::from_millis(400)).await;
+	assert!(module.call::<_, serde_json::Value>(SUB_POLL_NEXT, [&session]).await.is_err());
+	assert!(closed.load(Ordering::SeqCst));
+
+	// Only subscriptions can be polled.
+	assert!(module.call::<_, String>(SUB_POLL_CREATE, ["say_hello"]).await.is_err());
+}
+
 #[tokio::test]
 async fn calling_method_without_server() {
 	// Call sync method with no params
@@ -283,6 +404,32 @@ async fn close_test_subscribing_without_server() {
 	assert!(matches!(my_sub.next::<String>().await, None));
 }
 
+#[tokio::test]
+async fn subscription_sink_closed_resolves_when_client_goes_away() {
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut module = RpcModule::new(tx);
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, tx| {
+			sink.accept()?;
+
+			tokio::spawn(async move {
+				sink.send(&"lo").unwrap();
+				sink.closed().await;
+				tx.send(()).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let (val, _) = my_sub.next::<String>().await.unwrap().unwrap();
+	assert_eq!(&val, "lo");
+	assert!(tokio::time::timeout(Duration::from_millis(100), rx.recv()).await.is_err());
+
+	my_sub.close();
+	tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+}
+
 #[tokio::test]
 async fn subscribing_without_server_bad_params() {
 	let mut module = RpcModule::new(());
@@ -310,6 +457,59 @@ async fn subscribing_without_server_bad_params() {
 	);
 }
 
+#[tokio::test]
+async fn subscribing_without_server_panic() {
+	let mut module = RpcModule::new(());
+	module.register_subscription("my_sub", "my_sub", "my_unsub", |_, _, _| panic!("subscription boom")).unwrap();
+
+	let sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap_err();
+
+	assert!(
+		matches!(sub, Error::Call(CallError::Custom(e)) if e.code() == ErrorCode::InternalError.code() && e.data().unwrap().get().contains("correlation_id"))
+	);
+}
+
+#[tokio::test]
+async fn async_subscription_without_server() {
+	let mut module = RpcModule::new(5_usize);
+	module
+		.register_async_subscription("my_sub", "my_sub", "my_unsub", |params, mut sink, ctx| async move {
+			let start = match params.one::<usize>() {
+				Ok(start) => start,
+				Err(e) => {
+					sink.reject(Error::from(e))?;
+					return Ok(());
+				}
+			};
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			sink.accept()?;
+			for i in start..*ctx {
+				sink.send(&i).unwrap();
+			}
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_async_subscription("my_panic_sub", "my_panic_sub", "my_panic_unsub", |_, sink, _| async move {
+			let _sink = sink;
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			panic!("subscription boom")
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", [3_usize]).await.unwrap();
+	assert_eq!(sub.next::<usize>().await.unwrap().unwrap().0, 3);
+	assert_eq!(sub.next::<usize>().await.unwrap().unwrap().0, 4);
+
+	let sub_err = module.subscribe("my_sub", EmptyParams::new()).await.unwrap_err();
+	assert!(matches!(sub_err, Error::Call(CallError::Custom(e)) if e.code() == ErrorCode::InvalidParams.code()));
+
+	let sub_err = module.subscribe("my_panic_sub", EmptyParams::new()).await.unwrap_err();
+	assert!(
+		matches!(sub_err, Error::Call(CallError::Custom(e)) if e.code() == ErrorCode::InternalError.code() && e.data().unwrap().get().contains("correlation_id"))
+	);
+}
+
 #[tokio::test]
 async fn subscribe_unsubscribe_without_server() {
 	let mut module = RpcModule::new(());
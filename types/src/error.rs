@@ -25,6 +25,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::fmt;
+use std::time::Duration;
 
 use crate::params::{Id, TwoPointZero};
 use serde::de::Deserializer;
@@ -157,12 +158,38 @@ impl<'a> ErrorObject<'a> {
 		self.data.as_ref().map(|d| d.borrow())
 	}
 
+	/// Deserialize the data associated with this error into `T`, `Ok(None)` if there is no data.
+	///
+	/// ```
+	/// use jsonrpsee_types::error::ErrorObject;
+	/// use serde::{Deserialize, Serialize};
+	///
+	/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+	/// struct InsufficientFunds {
+	///     needed: u64,
+	/// }
+	///
+	/// let err = ErrorObject::owned_with_data(-32001, "Insufficient funds", InsufficientFunds { needed: 10 });
+	/// assert_eq!(err.data_as::<InsufficientFunds>().unwrap(), Some(InsufficientFunds { needed: 10 }));
+	/// ```
+	pub fn data_as<'b, T: Deserialize<'b>>(&'b self) -> Result<Option<T>, serde_json::Error> {
+		self.data().map(|data| serde_json::from_str(data.get())).transpose()
+	}
+
 	/// Create a new `ErrorObjectOwned` with optional data.
 	pub fn owned<S: Serialize>(code: i32, message: impl Into<String>, data: Option<S>) -> ErrorObject<'static> {
 		let data = data.and_then(|d| serde_json::value::to_raw_value(&d).ok());
 		ErrorObject { code: code.into(), message: message.into().into(), data: data.map(StdCow::Owned) }
 	}
 
+	/// Create a new `ErrorObjectOwned` with machine-readable `data`, which clients can read back with
+	/// [`ErrorObject::data_as`].
+	///
+	/// The data is left out if it can't be serialized, like with [`ErrorObject::owned`].
+	pub fn owned_with_data<S: Serialize>(code: i32, message: impl Into<String>, data: S) -> ErrorObject<'static> {
+		ErrorObject::owned(code, message, Some(data))
+	}
+
 	/// Create a new [`ErrorObject`] with optional data.
 	pub fn borrowed(code: i32, message: &'a impl AsRef<str>, data: Option<&'a RawValue>) -> ErrorObject<'a> {
 		ErrorObject { code: code.into(), message: StdCow::Borrowed(message.as_ref()), data: data.map(StdCow::Borrowed) }
@@ -239,6 +266,16 @@ pub const SUBSCRIPTION_CLOSED_WITH_ERROR: i32 = -32004;
 pub const BATCHES_NOT_SUPPORTED_CODE: i32 = -32005;
 /// Subscription limit per connection was exceeded.
 pub const TOO_MANY_SUBSCRIPTIONS_CODE: i32 = -32006;
+/// Client exceeded its rate limit.
+pub const RATE_LIMITED_CODE: i32 = -32007;
+/// Client lacks the permission to call the method.
+pub const UNAUTHORIZED_CODE: i32 = -32008;
+/// Result contains an integer that can't be represented exactly as a double.
+pub const UNSAFE_INTEGER_CODE: i32 = -32009;
+/// Batch contains more calls than the server accepts.
+pub const BATCH_TOO_LARGE_CODE: i32 = -32010;
+/// Circuit of the method is open because its calls keep failing.
+pub const CIRCUIT_OPEN_CODE: i32 = -32011;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -262,6 +299,16 @@ pub const SERVER_ERROR_MSG: &str = "Server error";
 pub const BATCHES_NOT_SUPPORTED_MSG: &str = "Batched requests are not supported by this server";
 /// Subscription limit per connection was exceeded.
 pub const TOO_MANY_SUBSCRIPTIONS_MSG: &str = "Too many subscriptions on the connection";
+/// Rate limited error message.
+pub const RATE_LIMITED_MSG: &str = "Rate limit exceeded, try again later";
+/// Unauthorized error message.
+pub const UNAUTHORIZED_MSG: &str = "Unauthorized";
+/// Unsafe integer error message.
+pub const UNSAFE_INTEGER_MSG: &str = "Result contains an integer that can't be represented exactly";
+/// Batch too large error message.
+pub const BATCH_TOO_LARGE_MSG: &str = "Batch contains too many calls";
+/// Circuit open error message.
+pub const CIRCUIT_OPEN_MSG: &str = "Method is temporarily unavailable, try again later";
 
 /// JSONRPC error code
 #[derive(Error, Debug, PartialEq, Copy, Clone)]
@@ -390,6 +437,11 @@ pub fn reject_too_many_subscriptions(limit: u32) -> ErrorObject<'static> {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a batch contains more calls than the server accepts.
+pub fn reject_too_large_batch(limit: usize) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(BATCH_TOO_LARGE_CODE, BATCH_TOO_LARGE_MSG, Some(format!("Exceeded max limit of {}", limit)))
+}
+
 /// Helper to get a `JSON-RPC` error object when the maximum request size limit have been exceeded.
 pub fn reject_too_big_request(limit: u32) -> ErrorObject<'static> {
 	ErrorObjectOwned::owned(
@@ -399,6 +451,46 @@ pub fn reject_too_big_request(limit: u32) -> ErrorObject<'static> {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a client has exceeded its rate limit.
+///
+/// The `data` field contains the number of milliseconds after which the call may be retried.
+pub fn reject_rate_limited(retry_after: Duration) -> ErrorObject<'static> {
+	// Round up so that retrying after the advertised delay is guaranteed to succeed.
+	let retry_after_ms = u64::try_from(retry_after.as_nanos().div_ceil(1_000_000)).unwrap_or(u64::MAX);
+	ErrorObjectOwned::owned(
+		RATE_LIMITED_CODE,
+		RATE_LIMITED_MSG,
+		Some(serde_json::json!({ "retry_after_ms": retry_after_ms })),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when the circuit of a method is open because its calls keep failing.
+///
+/// The `data` field contains the number of milliseconds after which the call may be retried.
+pub fn reject_circuit_open(retry_after: Duration) -> ErrorObject<'static> {
+	let retry_after_ms = u64::try_from(retry_after.as_nanos().div_ceil(1_000_000)).unwrap_or(u64::MAX);
+	ErrorObjectOwned::owned(
+		CIRCUIT_OPEN_CODE,
+		CIRCUIT_OPEN_MSG,
+		Some(serde_json::json!({ "retry_after_ms": retry_after_ms })),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when a client lacks the permission scope required to call a method.
+pub fn reject_unauthorized(required_scope: &str) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(
+		UNAUTHORIZED_CODE,
+		UNAUTHORIZED_MSG,
+		Some(serde_json::json!({ "required_scope": required_scope })),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when a result contains an integer rejected by
+/// [`NumberPolicy::Reject`](crate::number::NumberPolicy::Reject).
+pub fn reject_unsafe_integer(value: &str) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(UNSAFE_INTEGER_CODE, UNSAFE_INTEGER_MSG, Some(serde_json::json!({ "value": value })))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::{ErrorCode, ErrorObject, ErrorResponse, Id, TwoPointZero};
@@ -457,6 +549,26 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn typed_data_roundtrip() {
+		#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+		struct Details<'a> {
+			field: &'a str,
+			limit: u32,
+		}
+
+		let err = ErrorObject::owned_with_data(-32001, "Invalid field", Details { field: "name", limit: 3 });
+		let ser = serde_json::to_string(&err).unwrap();
+		assert_eq!(ser, r#"{"code":-32001,"message":"Invalid field","data":{"field":"name","limit":3}}"#);
+
+		let err: ErrorObject = serde_json::from_str(&ser).unwrap();
+		assert_eq!(err.data_as::<Details>().unwrap(), Some(Details { field: "name", limit: 3 }));
+		assert!(err.data_as::<u32>().is_err());
+
+		let err = ErrorObject::owned(-32001, "Invalid field", None::<()>);
+		assert_eq!(err.data_as::<Details>().unwrap(), None);
+	}
+
 	#[test]
 	fn serialize_works() {
 		let exp = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":1337}"#;
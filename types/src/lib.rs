@@ -43,9 +43,16 @@ pub mod response;
 /// JSON-RPC response error object related types.
 pub mod error;
 
+pub mod number;
+
+pub mod timestamp;
+
+/// JSON-RPC 1.0 compatibility helpers.
+pub mod v1;
+
 pub use error::{ErrorObject, ErrorObjectOwned, ErrorResponse, SubscriptionEmptyError, SubscriptionResult};
 pub use params::{Id, Params, ParamsSequence, ParamsSer, SubscriptionId, TwoPointZero};
-pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer};
+pub use request::{BatchExecution, InvalidRequest, Notification, NotificationSer, Request, RequestSer};
 pub use response::{Response, SubscriptionPayload, SubscriptionResponse};
 
 /// Empty `RpcParams` type;
@@ -0,0 +1,284 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Handling of integers that can't be represented exactly by JSON parsers using IEEE-754 doubles.
+//!
+//! Many JSON implementations, JavaScript's included, parse every number as a double and silently round integers
+//! beyond [`MAX_SAFE_INTEGER`]. Servers can be configured with a [`NumberPolicy`] to reject or stringify such
+//! integers in their results, and clients can use [`LenientInt`] or the [`lenient`] module to parse integers
+//! whether they were stringified or not.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Largest integer that IEEE-754 doubles represent exactly along with all smaller ones, `2^53 - 1`.
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How servers handle integers beyond [`MAX_SAFE_INTEGER`] in magnitude in the results they send.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NumberPolicy {
+	/// Send integers as they are.
+	#[default]
+	Preserve,
+	/// Send such integers as JSON strings instead of numbers.
+	Stringify,
+	/// Answer with an error instead of sending such integers.
+	Reject,
+}
+
+impl NumberPolicy {
+	/// Apply the policy to the serialized JSON value `json`.
+	///
+	/// Returns the offending integer if the policy is [`NumberPolicy::Reject`] and `json` contains one.
+	pub fn apply(self, json: String) -> Result<String, String> {
+		if self == NumberPolicy::Preserve {
+			return Ok(json);
+		}
+
+		let bytes = json.as_bytes();
+		// Only allocated once an integer has to be rewritten.
+		let mut rewritten: Option<String> = None;
+		let mut copied = 0;
+		let mut in_string = false;
+		let mut escaped = false;
+		let mut i = 0;
+
+		while i < bytes.len() {
+			let b = bytes[i];
+
+			if in_string {
+				match b {
+					_ if escaped => escaped = false,
+					b'\\' => escaped = true,
+					b'"' => in_string = false,
+					_ => (),
+				}
+				i += 1;
+				continue;
+			}
+
+			match b {
+				b'"' => in_string = true,
+				b'-' | b'0'..=b'9' => {
+					let start = i;
+					while i < bytes.len() && matches!(bytes[i], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+						i += 1;
+					}
+
+					let number = &json[start..i];
+					if !is_safe_number(number) {
+						if self == NumberPolicy::Reject {
+							return Err(number.to_owned());
+						}
+
+						let out = rewritten.get_or_insert_with(|| String::with_capacity(json.len() + 8));
+						out.push_str(&json[copied..start]);
+						out.push('"');
+						out.push_str(number);
+						out.push('"');
+						copied = i;
+					}
+					continue;
+				}
+				_ => (),
+			}
+			i += 1;
+		}
+
+		match rewritten {
+			Some(mut out) => {
+				out.push_str(&json[copied..]);
+				Ok(out)
+			}
+			None => Ok(json),
+		}
+	}
+}
+
+/// Returns whether the JSON number `number` is a float or an integer within [`MAX_SAFE_INTEGER`] in magnitude.
+fn is_safe_number(number: &str) -> bool {
+	if number.contains(['.', 'e', 'E']) {
+		return true;
+	}
+
+	let digits = number.trim_start_matches('-');
+	matches!(digits.parse::<u64>(), Ok(n) if n <= MAX_SAFE_INTEGER)
+}
+
+/// Integer deserialized from either a JSON number or a string, such as those stringified by
+/// [`NumberPolicy::Stringify`]. It's serialized as a JSON number.
+///
+/// Note that JSON numbers beyond the range of 64-bit integers can only be deserialized from strings.
+///
+/// ```
+/// use jsonrpsee_types::number::LenientInt;
+///
+/// let LenientInt(amount) = serde_json::from_str::<LenientInt<u128>>(r#""340282366920938463463374607431768211455""#).unwrap();
+/// assert_eq!(amount, u128::MAX);
+/// let LenientInt(amount) = serde_json::from_str::<LenientInt<u64>>("42").unwrap();
+/// assert_eq!(amount, 42);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LenientInt<T>(pub T);
+
+impl<T: Serialize> Serialize for LenientInt<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+impl<'de, T: LenientInteger> Deserialize<'de> for LenientInt<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		lenient::deserialize(deserializer).map(LenientInt)
+	}
+}
+
+/// Integer types that can be deserialized leniently, see [`LenientInt`].
+pub trait LenientInteger: FromStr + TryFrom<u64> + TryFrom<i64> + TryFrom<u128> + TryFrom<i128> + Sized {}
+
+impl<T: FromStr + TryFrom<u64> + TryFrom<i64> + TryFrom<u128> + TryFrom<i128>> LenientInteger for T {}
+
+/// Serde helpers to (de)serialize integer fields like [`LenientInt`], to be used with `#[serde(with = "...")]`.
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Balance {
+///     #[serde(with = "jsonrpsee_types::number::lenient")]
+///     amount: u64,
+/// }
+///
+/// let balance: Balance = serde_json::from_str(r#"{"amount":"9007199254740993"}"#).unwrap();
+/// assert_eq!(balance.amount, 9007199254740993);
+/// ```
+pub mod lenient {
+	use super::*;
+
+	/// Serialize `value` as a JSON number.
+	pub fn serialize<T: Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		value.serialize(serializer)
+	}
+
+	/// Deserialize an integer from either a JSON number or a string.
+	pub fn deserialize<'de, T: LenientInteger, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+		deserializer.deserialize_any(LenientVisitor(PhantomData))
+	}
+}
+
+struct LenientVisitor<T>(PhantomData<T>);
+
+impl<T> LenientVisitor<T> {
+	fn out_of_range<E: de::Error, N: fmt::Display>(n: N) -> E {
+		E::custom(format_args!("integer {} is out of range", n))
+	}
+}
+
+impl<'de, T: LenientInteger> Visitor<'de> for LenientVisitor<T> {
+	type Value = T;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("an integer or a string containing an integer")
+	}
+
+	fn visit_u64<E: de::Error>(self, n: u64) -> Result<T, E> {
+		T::try_from(n).map_err(|_| Self::out_of_range(n))
+	}
+
+	fn visit_i64<E: de::Error>(self, n: i64) -> Result<T, E> {
+		T::try_from(n).map_err(|_| Self::out_of_range(n))
+	}
+
+	fn visit_u128<E: de::Error>(self, n: u128) -> Result<T, E> {
+		T::try_from(n).map_err(|_| Self::out_of_range(n))
+	}
+
+	fn visit_i128<E: de::Error>(self, n: i128) -> Result<T, E> {
+		T::try_from(n).map_err(|_| Self::out_of_range(n))
+	}
+
+	fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+		s.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(s), &self))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LenientInt, NumberPolicy, MAX_SAFE_INTEGER};
+
+	#[test]
+	fn preserve_keeps_integers() {
+		let json = format!("[{}]", u64::MAX);
+		assert_eq!(NumberPolicy::Preserve.apply(json.clone()), Ok(json));
+	}
+
+	#[test]
+	fn stringify_works() {
+		let json = format!(
+			r#"{{"safe":{},"big":{},"neg":-{},"float":1.5e300,"str":"12345678901234567890 \"99999999999999999\"","arr":[{}]}}"#,
+			MAX_SAFE_INTEGER,
+			MAX_SAFE_INTEGER + 1,
+			u128::MAX,
+			i64::MIN
+		);
+		let expected = format!(
+			r#"{{"safe":{},"big":"{}","neg":"-{}","float":1.5e300,"str":"12345678901234567890 \"99999999999999999\"","arr":["{}"]}}"#,
+			MAX_SAFE_INTEGER,
+			MAX_SAFE_INTEGER + 1,
+			u128::MAX,
+			i64::MIN
+		);
+		assert_eq!(NumberPolicy::Stringify.apply(json), Ok(expected));
+		assert_eq!(NumberPolicy::Stringify.apply("-7".into()), Ok("-7".into()));
+	}
+
+	#[test]
+	fn reject_works() {
+		assert_eq!(
+			NumberPolicy::Reject.apply(format!("[1,{}]", MAX_SAFE_INTEGER)),
+			Ok(format!("[1,{}]", MAX_SAFE_INTEGER))
+		);
+		assert_eq!(NumberPolicy::Reject.apply(format!("[1,{}]", u64::MAX)), Err(u64::MAX.to_string()));
+	}
+
+	#[test]
+	fn lenient_int_works() {
+		assert_eq!(serde_json::from_str::<LenientInt<u64>>("42").unwrap(), LenientInt(42));
+		assert_eq!(serde_json::from_str::<LenientInt<i64>>(r#""-42""#).unwrap(), LenientInt(-42));
+		assert_eq!(
+			serde_json::from_str::<LenientInt<i128>>(&format!(r#""{}""#, i128::MIN)).unwrap(),
+			LenientInt(i128::MIN)
+		);
+		assert!(serde_json::from_str::<LenientInt<u8>>("256").is_err());
+		assert!(serde_json::from_str::<LenientInt<u64>>(r#""4.2""#).is_err());
+		assert_eq!(serde_json::to_string(&LenientInt(u64::MAX)).unwrap(), u64::MAX.to_string());
+	}
+}
@@ -46,15 +46,34 @@ pub struct Request<'a> {
 	/// Parameter values of the request.
 	#[serde(borrow)]
 	pub params: Option<&'a RawValue>,
+	/// Execution hint for calls of a batch, this is an extension to the spec.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub execution: Option<BatchExecution>,
 }
 
 impl<'a> Request<'a> {
 	/// Create a new [`Request`].
 	pub fn new(method: Cow<'a, str>, params: Option<&'a RawValue>, id: Id<'a>) -> Self {
-		Self { jsonrpc: TwoPointZero, id, method, params }
+		Self { jsonrpc: TwoPointZero, id, method, params, execution: None }
+	}
+
+	/// Returns whether the call must be executed in isolation within a batch.
+	pub fn is_sequential(&self) -> bool {
+		self.execution == Some(BatchExecution::Sequential)
 	}
 }
 
+/// How a call of a batch is executed relative to the other calls of that batch.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchExecution {
+	/// The call may run concurrently with the adjacent parallel calls of the batch (default).
+	Parallel,
+	/// The call starts once all preceding calls of the batch have completed, and the following calls
+	/// start once it has completed.
+	Sequential,
+}
+
 /// JSON-RPC Invalid request as defined in the [spec](https://www.jsonrpc.org/specification#request-object).
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct InvalidRequest<'a> {
@@ -129,7 +148,9 @@ impl<'a> NotificationSer<'a> {
 
 #[cfg(test)]
 mod test {
-	use super::{Id, InvalidRequest, Notification, NotificationSer, ParamsSer, Request, RequestSer, TwoPointZero};
+	use super::{
+		BatchExecution, Id, InvalidRequest, Notification, NotificationSer, ParamsSer, Request, RequestSer, TwoPointZero,
+	};
 	use serde_json::{value::RawValue, Value};
 
 	fn assert_request<'a>(request: Request<'a>, id: Id<'a>, method: &str, params: Option<&str>) {
@@ -165,6 +186,21 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn deserialize_call_execution_hint() {
+		let ser = r#"{"jsonrpc":"2.0","id":1,"method":"m","execution":"sequential"}"#;
+		let req: Request = serde_json::from_str(ser).unwrap();
+		assert_eq!(req.execution, Some(BatchExecution::Sequential));
+		assert!(req.is_sequential());
+
+		let ser = r#"{"jsonrpc":"2.0","id":1,"method":"m","execution":"parallel"}"#;
+		let req: Request = serde_json::from_str(ser).unwrap();
+		assert!(!req.is_sequential());
+
+		let ser = r#"{"jsonrpc":"2.0","id":1,"method":"m","execution":"whenever"}"#;
+		assert!(serde_json::from_str::<Request>(ser).is_err());
+	}
+
 	#[test]
 	fn deserialize_call_escaped_method_name() {
 		let ser = r#"{"jsonrpc":"2.0","id":1,"method":"\"m\""}"#;
@@ -80,6 +80,10 @@ pub struct SubscriptionPayloadError<'a, T> {
 	pub subscription: SubscriptionId<'a>,
 	/// Result.
 	pub error: T,
+	/// Whether `error` is the reason the server closed the subscription with, which the client yields as the last
+	/// item of the subscription. Otherwise the subscription just ends.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub reason: bool,
 }
 
 #[cfg(test)]
@@ -0,0 +1,428 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Timestamps in parameters and results.
+//!
+//! Two canonical representations are provided, both holding a [`SystemTime`]:
+//!
+//! - [`Rfc3339Timestamp`] is serialized as an RFC 3339 string in UTC such as `"2022-05-01T12:30:00.25Z"`, and
+//!   deserialized from RFC 3339 strings with any offset, which are converted to UTC.
+//! - [`UnixTimestamp`] is serialized as the integer number of seconds since the unix epoch, sub-second precision
+//!   is truncated.
+//!
+//! The [`rfc3339`] and [`unix_seconds`] modules (de)serialize [`SystemTime`] fields the same way with
+//! `#[serde(with = "...")]`. With the `openrpc` feature of `jsonrpsee`, both types describe themselves in the
+//! OpenRPC documents of `#[rpc(server, openrpc)]` traits.
+//!
+//! ```
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//! use jsonrpsee_types::timestamp::{Rfc3339Timestamp, UnixTimestamp};
+//!
+//! let time = UNIX_EPOCH + Duration::from_secs(1_651_408_200);
+//! assert_eq!(serde_json::to_string(&Rfc3339Timestamp(time)).unwrap(), r#""2022-05-01T12:30:00Z""#);
+//! assert_eq!(serde_json::to_string(&UnixTimestamp(time)).unwrap(), "1651408200");
+//!
+//! let parsed: Rfc3339Timestamp = serde_json::from_str(r#""2022-05-01T14:30:00+02:00""#).unwrap();
+//! assert_eq!(parsed.0, time);
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Timestamp serialized as an RFC 3339 string in UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rfc3339Timestamp(pub SystemTime);
+
+/// Timestamp serialized as the integer number of seconds since the unix epoch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixTimestamp(pub SystemTime);
+
+/// Error parsing an RFC 3339 timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid RFC 3339 timestamp: {0}")]
+pub struct InvalidTimestamp(&'static str);
+
+impl Rfc3339Timestamp {
+	/// Current time.
+	pub fn now() -> Self {
+		Self(SystemTime::now())
+	}
+}
+
+impl UnixTimestamp {
+	/// Current time.
+	pub fn now() -> Self {
+		Self(SystemTime::now())
+	}
+
+	/// Timestamp `secs` seconds after the unix epoch, or before it if negative.
+	pub fn from_secs(secs: i64) -> Self {
+		Self(from_unix(secs, 0))
+	}
+
+	/// Number of whole seconds since the unix epoch, negative before it.
+	pub fn secs(&self) -> i64 {
+		to_unix(self.0).0
+	}
+}
+
+impl From<SystemTime> for Rfc3339Timestamp {
+	fn from(time: SystemTime) -> Self {
+		Self(time)
+	}
+}
+
+impl From<Rfc3339Timestamp> for SystemTime {
+	fn from(timestamp: Rfc3339Timestamp) -> Self {
+		timestamp.0
+	}
+}
+
+impl From<SystemTime> for UnixTimestamp {
+	fn from(time: SystemTime) -> Self {
+		Self(time)
+	}
+}
+
+impl From<UnixTimestamp> for SystemTime {
+	fn from(timestamp: UnixTimestamp) -> Self {
+		timestamp.0
+	}
+}
+
+impl fmt::Display for Rfc3339Timestamp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (secs, nanos) = to_unix(self.0);
+		let (year, month, day) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+		let time = secs.rem_euclid(SECS_PER_DAY);
+
+		if !(0..=9999).contains(&year) {
+			return Err(fmt::Error);
+		}
+
+		write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, time / 3600, time % 3600 / 60, time % 60)?;
+		if nanos > 0 {
+			let fraction = format!("{:09}", nanos);
+			write!(f, ".{}", fraction.trim_end_matches('0'))?;
+		}
+		f.write_str("Z")
+	}
+}
+
+impl FromStr for Rfc3339Timestamp {
+	type Err = InvalidTimestamp;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let b = s.as_bytes();
+		if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+			return Err(InvalidTimestamp("expected `YYYY-MM-DDTHH:MM:SS`"));
+		}
+		if !matches!(b[10], b'T' | b't' | b' ') {
+			return Err(InvalidTimestamp("expected `T` between date and time"));
+		}
+
+		let year = digits(&b[0..4])?;
+		let month = digits(&b[5..7])?;
+		let day = digits(&b[8..10])?;
+		let hour = digits(&b[11..13])?;
+		let minute = digits(&b[14..16])?;
+		// Leap seconds are folded into the following second.
+		let second = digits(&b[17..19])?;
+
+		if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+			return Err(InvalidTimestamp("date out of range"));
+		}
+		if hour > 23 || minute > 59 || second > 60 {
+			return Err(InvalidTimestamp("time out of range"));
+		}
+
+		let mut rest = &b[19..];
+		let mut nanos = 0;
+		if let Some(fraction) = rest.strip_prefix(b".") {
+			let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+			if len == 0 {
+				return Err(InvalidTimestamp("expected digits after `.`"));
+			}
+			// Digits beyond nanoseconds are truncated.
+			for (i, digit) in fraction[..len].iter().take(9).enumerate() {
+				nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+			}
+			rest = &fraction[len..];
+		}
+
+		let offset = match rest {
+			b"Z" | b"z" => 0,
+			[sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+				let hours = digits(&[*h1, *h2])?;
+				let minutes = digits(&[*m1, *m2])?;
+				if hours > 23 || minutes > 59 {
+					return Err(InvalidTimestamp("offset out of range"));
+				}
+				let offset = hours * 3600 + minutes * 60;
+				if *sign == b'-' {
+					-offset
+				} else {
+					offset
+				}
+			}
+			_ => return Err(InvalidTimestamp("expected `Z` or an offset such as `+02:00`")),
+		};
+
+		let secs = days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset;
+		Ok(Self(from_unix(secs, nanos)))
+	}
+}
+
+impl Serialize for Rfc3339Timestamp {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Rfc3339Timestamp {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = <beef::Cow<'de, str>>::deserialize(deserializer)?;
+		s.parse().map_err(de::Error::custom)
+	}
+}
+
+impl Serialize for UnixTimestamp {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_i64(self.secs())
+	}
+}
+
+impl<'de> Deserialize<'de> for UnixTimestamp {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		i64::deserialize(deserializer).map(Self::from_secs)
+	}
+}
+
+#[cfg(feature = "schemars")]
+mod schema {
+	use super::{Rfc3339Timestamp, UnixTimestamp};
+	use schemars::gen::SchemaGenerator;
+	use schemars::schema::{InstanceType, Schema, SchemaObject};
+	use schemars::JsonSchema;
+
+	impl JsonSchema for Rfc3339Timestamp {
+		fn schema_name() -> String {
+			"Rfc3339Timestamp".into()
+		}
+
+		fn is_referenceable() -> bool {
+			false
+		}
+
+		fn json_schema(_: &mut SchemaGenerator) -> Schema {
+			SchemaObject {
+				instance_type: Some(InstanceType::String.into()),
+				format: Some("date-time".into()),
+				..Default::default()
+			}
+			.into()
+		}
+	}
+
+	impl JsonSchema for UnixTimestamp {
+		fn schema_name() -> String {
+			"UnixTimestamp".into()
+		}
+
+		fn is_referenceable() -> bool {
+			false
+		}
+
+		fn json_schema(_: &mut SchemaGenerator) -> Schema {
+			SchemaObject {
+				instance_type: Some(InstanceType::Integer.into()),
+				format: Some("int64".into()),
+				..Default::default()
+			}
+			.into()
+		}
+	}
+}
+
+/// Serde helpers to (de)serialize [`SystemTime`] fields like [`Rfc3339Timestamp`], to be used with
+/// `#[serde(with = "jsonrpsee_types::timestamp::rfc3339")]`.
+pub mod rfc3339 {
+	use super::*;
+
+	/// Serialize `time` as an RFC 3339 string in UTC.
+	pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+		Rfc3339Timestamp(*time).serialize(serializer)
+	}
+
+	/// Deserialize a time from an RFC 3339 string with any offset.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+		Rfc3339Timestamp::deserialize(deserializer).map(|timestamp| timestamp.0)
+	}
+}
+
+/// Serde helpers to (de)serialize [`SystemTime`] fields like [`UnixTimestamp`], to be used with
+/// `#[serde(with = "jsonrpsee_types::timestamp::unix_seconds")]`.
+pub mod unix_seconds {
+	use super::*;
+
+	/// Serialize `time` as the integer number of seconds since the unix epoch.
+	pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+		UnixTimestamp(*time).serialize(serializer)
+	}
+
+	/// Deserialize a time from the integer number of seconds since the unix epoch.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+		UnixTimestamp::deserialize(deserializer).map(|timestamp| timestamp.0)
+	}
+}
+
+fn digits(b: &[u8]) -> Result<i64, InvalidTimestamp> {
+	b.iter().try_fold(0, |n, b| match b {
+		b'0'..=b'9' => Ok(n * 10 + i64::from(b - b'0')),
+		_ => Err(InvalidTimestamp("expected a digit")),
+	})
+}
+
+/// Seconds since the unix epoch rounded towards negative infinity, and the nanoseconds past them.
+fn to_unix(time: SystemTime) -> (i64, u32) {
+	match time.duration_since(UNIX_EPOCH) {
+		Ok(after) => (after.as_secs() as i64, after.subsec_nanos()),
+		Err(err) => {
+			let before = err.duration();
+			match before.subsec_nanos() {
+				0 => (-(before.as_secs() as i64), 0),
+				nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+			}
+		}
+	}
+}
+
+fn from_unix(secs: i64, nanos: u32) -> SystemTime {
+	if secs >= 0 {
+		UNIX_EPOCH + Duration::new(secs as u64, nanos)
+	} else {
+		UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()) + Duration::from_nanos(nanos.into())
+	}
+}
+
+fn is_leap_year(year: i64) -> bool {
+	year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+	match month {
+		2 if is_leap_year(year) => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	}
+}
+
+/// Days since the unix epoch of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146_097 + day_of_era - 719_468
+}
+
+/// Date of the proleptic Gregorian calendar of a number of days since the unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+	let days = days + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(secs: i64, nanos: u32) -> SystemTime {
+		from_unix(secs, nanos)
+	}
+
+	#[test]
+	fn rfc3339_formatting_works() {
+		assert_eq!(Rfc3339Timestamp(UNIX_EPOCH).to_string(), "1970-01-01T00:00:00Z");
+		assert_eq!(Rfc3339Timestamp(at(951_782_400, 0)).to_string(), "2000-02-29T00:00:00Z");
+		assert_eq!(Rfc3339Timestamp(at(1_651_408_200, 250_000_000)).to_string(), "2022-05-01T12:30:00.25Z");
+		assert_eq!(Rfc3339Timestamp(at(-1, 999_999_999)).to_string(), "1969-12-31T23:59:59.999999999Z");
+	}
+
+	#[test]
+	fn rfc3339_parsing_works() {
+		let parse = |s: &str| s.parse::<Rfc3339Timestamp>().map(|t| t.0);
+
+		assert_eq!(parse("2022-05-01T12:30:00Z"), Ok(at(1_651_408_200, 0)));
+		assert_eq!(parse("2022-05-01t12:30:00.25z"), Ok(at(1_651_408_200, 250_000_000)));
+		assert_eq!(parse("2022-05-01T14:30:00+02:00"), Ok(at(1_651_408_200, 0)));
+		assert_eq!(parse("2022-05-01T07:00:00.5-05:30"), Ok(at(1_651_408_200, 500_000_000)));
+		assert_eq!(parse("1969-12-31T23:59:59.9999999999Z"), Ok(at(-1, 999_999_999)));
+
+		assert!(parse("2022-02-29T00:00:00Z").is_err());
+		assert!(parse("2022-05-01T24:00:00Z").is_err());
+		assert!(parse("2022-05-01T12:30:00").is_err());
+		assert!(parse("2022-05-01T12:30:00.Z").is_err());
+		assert!(parse("2022-05-01T12:30:00+0200").is_err());
+		assert!(parse("1651408200").is_err());
+	}
+
+	#[test]
+	fn serde_works() {
+		let time = at(1_651_408_200, 999);
+		assert_eq!(serde_json::to_string(&UnixTimestamp(time)).unwrap(), "1651408200");
+		assert_eq!(serde_json::from_str::<UnixTimestamp>("-86400").unwrap().0, at(-86_400, 0));
+		assert_eq!(UnixTimestamp(at(-2, 1)).secs(), -2);
+
+		let json = serde_json::to_string(&Rfc3339Timestamp(time)).unwrap();
+		assert_eq!(json, r#""2022-05-01T12:30:00.000000999Z""#);
+		assert_eq!(serde_json::from_str::<Rfc3339Timestamp>(&json).unwrap().0, time);
+		assert!(serde_json::from_str::<Rfc3339Timestamp>("1651408200").is_err());
+	}
+
+	#[test]
+	fn civil_conversion_roundtrips() {
+		for days in (-800_000..800_000).step_by(97) {
+			let (year, month, day) = civil_from_days(days);
+			assert_eq!(days_from_civil(year, month, day), days);
+		}
+	}
+}
@@ -0,0 +1,153 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+
+//! Compatibility with [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1).
+//!
+//! JSON-RPC 1.0 requests lack the `jsonrpc` member (or set it to another version such as `"1.0"`) and
+//! are notifications if their `id` is `null`. Their responses always contain both the `result` and the
+//! `error` member, one of them being `null`, and no `jsonrpc` member.
+//!
+//! The helpers in this module translate 1.0 requests into 2.0 requests and 2.0 responses back into
+//! 1.0 responses so that servers can process them unchanged.
+
+use serde_json::{Map, Value};
+
+/// Rewrite a JSON-RPC 1.0 request or batch of 1.0 requests into JSON-RPC 2.0.
+///
+/// Returns `None` if `body` is not valid JSON, not a 1.0 request or a batch mixing 1.0 and 2.0 requests.
+pub fn request_to_v2(body: &[u8]) -> Option<Vec<u8>> {
+	let mut value: Value = serde_json::from_slice(body).ok()?;
+
+	match &mut value {
+		Value::Object(request) if is_v1(request) => upgrade(request),
+		Value::Array(batch) if !batch.is_empty() && batch.iter().all(|r| r.as_object().is_some_and(is_v1)) => {
+			batch.iter_mut().filter_map(Value::as_object_mut).for_each(upgrade)
+		}
+		_ => return None,
+	}
+
+	Some(serde_json::to_vec(&value).expect("valid JSON; qed"))
+}
+
+/// Rewrite a serialized JSON-RPC 2.0 response or batch of responses into JSON-RPC 1.0.
+///
+/// Anything that's not a response, such as the empty response to notifications, is returned unchanged.
+pub fn response_to_v1(response: String) -> String {
+	let mut value: Value = match serde_json::from_str(&response) {
+		Ok(value) => value,
+		Err(_) => return response,
+	};
+
+	match &mut value {
+		Value::Object(response) => downgrade(response),
+		Value::Array(batch) => batch.iter_mut().filter_map(Value::as_object_mut).for_each(downgrade),
+		_ => return response,
+	}
+
+	serde_json::to_string(&value).expect("valid JSON; qed")
+}
+
+fn is_v1(request: &Map<String, Value>) -> bool {
+	match request.get("jsonrpc") {
+		None => true,
+		Some(Value::String(version)) => version != "2.0",
+		Some(_) => false,
+	}
+}
+
+fn upgrade(request: &mut Map<String, Value>) {
+	request.insert("jsonrpc".to_owned(), Value::String("2.0".to_owned()));
+	// Requests with a `null` ID are notifications in JSON-RPC 1.0.
+	if request.get("id").is_some_and(Value::is_null) {
+		request.remove("id");
+	}
+}
+
+fn downgrade(response: &mut Map<String, Value>) {
+	response.remove("jsonrpc");
+	response.entry("result").or_insert(Value::Null);
+	response.entry("error").or_insert(Value::Null);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{request_to_v2, response_to_v1};
+	use serde_json::{json, Value};
+
+	fn upgraded(request: Value) -> Option<Value> {
+		request_to_v2(request.to_string().as_bytes()).map(|body| serde_json::from_slice(&body).unwrap())
+	}
+
+	#[test]
+	fn v1_requests_are_upgraded() {
+		assert_eq!(
+			upgraded(json!({"method": "getblockcount", "params": [], "id": 1})),
+			Some(json!({"jsonrpc": "2.0", "method": "getblockcount", "params": [], "id": 1}))
+		);
+		assert_eq!(
+			upgraded(json!({"jsonrpc": "1.0", "method": "getblockcount", "id": "curltest"})),
+			Some(json!({"jsonrpc": "2.0", "method": "getblockcount", "id": "curltest"}))
+		);
+		assert_eq!(
+			upgraded(json!([{"method": "a", "id": 1}, {"method": "b", "id": 2}])),
+			Some(json!([{"jsonrpc": "2.0", "method": "a", "id": 1}, {"jsonrpc": "2.0", "method": "b", "id": 2}]))
+		);
+	}
+
+	#[test]
+	fn v1_notifications_have_no_id() {
+		assert_eq!(
+			upgraded(json!({"method": "ping", "params": [], "id": null})),
+			Some(json!({"jsonrpc": "2.0", "method": "ping", "params": []}))
+		);
+	}
+
+	#[test]
+	fn v2_requests_are_not_upgraded() {
+		assert_eq!(upgraded(json!({"jsonrpc": "2.0", "method": "a", "id": 1})), None);
+		assert_eq!(upgraded(json!([{"jsonrpc": "2.0", "method": "a", "id": 1}, {"method": "b", "id": 2}])), None);
+		assert_eq!(upgraded(json!([])), None);
+		assert_eq!(request_to_v2(b"{invalid"), None);
+	}
+
+	#[test]
+	fn responses_are_downgraded() {
+		let ok = response_to_v1(r#"{"jsonrpc":"2.0","result":5,"id":1}"#.to_owned());
+		assert_eq!(serde_json::from_str::<Value>(&ok).unwrap(), json!({"result": 5, "error": null, "id": 1}));
+
+		let err = response_to_v1(
+			r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#.to_owned(),
+		);
+		assert_eq!(
+			serde_json::from_str::<Value>(&err).unwrap(),
+			json!({"result": null, "error": {"code": -32601, "message": "Method not found"}, "id": 1})
+		);
+
+		let batch = response_to_v1(r#"[{"jsonrpc":"2.0","result":5,"id":1}]"#.to_owned());
+		assert_eq!(serde_json::from_str::<Value>(&batch).unwrap(), json!([{"result": 5, "error": null, "id": 1}]));
+
+		assert_eq!(response_to_v1(String::new()), "");
+	}
+}
@@ -34,6 +34,10 @@ use std::task::{Context, Poll};
 
 use futures_util::future::FutureExt;
 use futures_util::task::AtomicWaker;
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::MethodsHandle;
+use jsonrpsee_core::server::server_set::ServerControl;
+use jsonrpsee_core::server::shutdown::{ShutdownReason, ShutdownRecord};
 use jsonrpsee_core::Error;
 use tokio::time::{self, Duration, Interval};
 
@@ -160,7 +164,7 @@ struct MonitorInner {
 
 /// Monitor for checking whether the server has been flagged to shut down.
 #[derive(Debug, Clone)]
-pub(crate) struct StopMonitor(Arc<MonitorInner>);
+pub(crate) struct StopMonitor(Arc<MonitorInner>, ShutdownRecord);
 
 impl Drop for StopMonitor {
 	fn drop(&mut self) {
@@ -172,7 +176,10 @@ impl Drop for StopMonitor {
 
 impl StopMonitor {
 	pub(crate) fn new() -> Self {
-		StopMonitor(Arc::new(MonitorInner { shutdown_requested: AtomicBool::new(false), waker: AtomicWaker::new() }))
+		StopMonitor(
+			Arc::new(MonitorInner { shutdown_requested: AtomicBool::new(false), waker: AtomicWaker::new() }),
+			ShutdownRecord::new(),
+		)
 	}
 
 	pub(crate) fn shutdown_requested(&self) -> bool {
@@ -181,41 +188,90 @@ impl StopMonitor {
 		self.0.shutdown_requested.load(Ordering::Relaxed)
 	}
 
-	pub(crate) fn handle(&self) -> ServerHandle {
-		ServerHandle(Arc::downgrade(&self.0))
+	/// Flags the server to shut down for the given reason, returns whether it wasn't flagged already.
+	pub(crate) fn stop(&self, reason: ShutdownReason) -> bool {
+		stop(&self.0, &self.1, reason)
 	}
+
+	/// Returns why the server is shutting down, `None` if it wasn't flagged to.
+	pub(crate) fn shutdown_reason(&self) -> Option<ShutdownReason> {
+		self.1.reason()
+	}
+
+	pub(crate) fn handle(&self, methods: MethodsHandle) -> ServerHandle {
+		ServerHandle { monitor: Arc::downgrade(&self.0), methods, shutdown: self.1.clone() }
+	}
+}
+
+// The reason is recorded before the flag is raised, so that it's known to everyone seeing the flag.
+fn stop(monitor: &MonitorInner, shutdown: &ShutdownRecord, reason: ShutdownReason) -> bool {
+	// We proceed only if the previous value of the flag was `false`
+	shutdown.record(reason) && !monitor.shutdown_requested.swap(true, Ordering::Relaxed)
 }
 
 /// Handle that is able to stop the running server or wait for it to finish
 /// its execution.
 #[derive(Debug, Clone)]
-pub struct ServerHandle(Weak<MonitorInner>);
+pub struct ServerHandle {
+	monitor: Weak<MonitorInner>,
+	methods: MethodsHandle,
+	shutdown: ShutdownRecord,
+}
 
 impl ServerHandle {
 	/// Requests server to stop. Returns an error if server was already stopped.
 	///
 	/// Returns a future that can be awaited for when the server shuts down.
 	pub fn stop(self) -> Result<ShutdownWaiter, Error> {
-		if let Some(arc) = Weak::upgrade(&self.0) {
-			// We proceed only if the previous value of the flag was `false`
-			if !arc.shutdown_requested.swap(true, Ordering::Relaxed) {
-				return Ok(ShutdownWaiter(self.0));
+		self.stop_with_reason(ShutdownReason::Stopped)
+	}
+
+	/// Requests server to stop for the given reason, for instance [`ShutdownReason::Restart`] if it's about to
+	/// be restarted. Returns an error if server was already stopped.
+	///
+	/// The reason is sent to the clients before their connection is closed, see
+	/// [`SHUTDOWN_NOTIFICATION`](jsonrpsee_core::server::shutdown::SHUTDOWN_NOTIFICATION).
+	pub fn stop_with_reason(self, reason: ShutdownReason) -> Result<ShutdownWaiter, Error> {
+		if let Some(arc) = Weak::upgrade(&self.monitor) {
+			if stop(&arc, &self.shutdown, reason) {
+				return Ok(ShutdownWaiter(self.monitor));
 			}
 		}
 		Err(Error::AlreadyStopped)
 	}
+
+	/// Returns why the server stopped or is stopping, `None` while it's running.
+	pub fn shutdown_reason(&self) -> Option<ShutdownReason> {
+		self.shutdown.reason()
+	}
+
+	/// Returns a handle to add and remove methods while the server is running.
+	pub fn methods(&self) -> &MethodsHandle {
+		&self.methods
+	}
+
+	/// Returns the resources of the server, to adjust their capacities while the server is running.
+	pub fn resources(&self) -> &Resources {
+		self.methods.resources()
+	}
 }
 
 impl Future for ServerHandle {
 	type Output = ();
 
 	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-		let mut shutdown_waiter = ShutdownWaiter(self.0.clone());
+		let mut shutdown_waiter = ShutdownWaiter(self.monitor.clone());
 
 		shutdown_waiter.poll_unpin(cx)
 	}
 }
 
+impl ServerControl for ServerHandle {
+	fn request_stop(&mut self) {
+		let _ = self.clone().stop();
+	}
+}
+
 /// A `Future` that resolves once the server has stopped.
 #[derive(Debug)]
 pub struct ShutdownWaiter(Weak<MonitorInner>);
@@ -39,7 +39,18 @@ mod server;
 mod tests;
 
 pub use future::{ServerHandle as WsServerHandle, ShutdownWaiter as WsShutdownWaiter};
+pub use jsonrpsee_core::server::auth::{AuthValidator, Authenticator, Permissions, StaticKeys};
+pub use jsonrpsee_core::server::broadcast::SubscriptionBroadcaster;
+pub use jsonrpsee_core::deflate::DeflateConfig;
+pub use jsonrpsee_core::server::circuit_breaker::{BreakerPolicy, CircuitBreaker};
+pub use jsonrpsee_core::server::ip_filter::IpFilter;
+pub use jsonrpsee_core::server::load_shedding::LoadShedder;
+pub use jsonrpsee_core::server::wire_tap::WireTap;
+pub use jsonrpsee_core::server::rate_limiting::{RateLimit, RateLimiter};
 pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
+pub use jsonrpsee_core::server::send_queue::{OverflowPolicy, SubscriptionOverflowPolicy};
+pub use jsonrpsee_core::server::shutdown::ShutdownReason;
+pub use jsonrpsee_core::tcp::TcpKeepalive;
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
 pub use server::{Builder as WsServerBuilder, Server as WsServer};
@@ -33,27 +33,49 @@ use std::time::Duration;
 
 use crate::future::{FutureDriver, ServerHandle, StopMonitor};
 use crate::types::error::{ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG};
-use crate::types::{Id, Request};
+use crate::types::Id;
+use bytes::Bytes;
 use futures_channel::mpsc;
-use futures_util::future::{join_all, Either, FutureExt};
+use futures_util::future::{Either, FutureExt};
 use futures_util::io::{BufReader, BufWriter};
-use futures_util::stream::StreamExt;
+use futures_util::stream::{self, StreamExt};
+use jsonrpsee_core::compression::CompressionStats;
+use jsonrpsee_core::deflate::{Deflate, DeflateConfig};
 use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
-use jsonrpsee_core::middleware::Middleware;
+use jsonrpsee_core::middleware::{CallInfo, ConnectionInfo, Middleware, Transport};
 use jsonrpsee_core::server::access_control::AccessControl;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, BoundedSubscriptions, MethodSink};
+use jsonrpsee_core::server::auth::Authenticator;
+use jsonrpsee_core::server::circuit_breaker::CircuitBreaker;
+use jsonrpsee_core::server::helpers::{
+	admit_calls, batch_stages, collect_batch_response, prepare_error, report_resource_usage, report_response,
+	report_result, BoundedSubscriptions, CallPolicy, MethodSink, PanicHook, PanicReport,
+};
+use jsonrpsee_core::server::ip_filter::IpFilter;
+use jsonrpsee_core::server::load_shedding::LoadShedder;
+use jsonrpsee_core::server::parse::{parse_batch, parse_request};
+use jsonrpsee_core::server::proxy_protocol;
+use jsonrpsee_core::server::rate_limiting::{RateLimitKey, RateLimiter};
 use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, MethodKind, Methods};
+use jsonrpsee_core::server::rpc_module::{
+	ConnState, ConnectionId, MethodCallback, MethodKind, Methods, MethodsHandle, SystemLimits,
+};
+use jsonrpsee_core::server::send_queue::{
+	self, OverflowPolicy, SlowSubscriberEviction, SubscriptionBuffer, SubscriptionOverflowPolicy,
+};
+use jsonrpsee_core::server::shutdown::{ShutdownReason, SHUTDOWN_NOTIFICATION};
+use jsonrpsee_core::server::wire_tap::{Direction, WireTap, WireTapSession};
+use jsonrpsee_core::tcp::{BindSettings, TcpKeepalive, TcpSettings};
 use jsonrpsee_core::tracing::{rx_log_from_json, RpcTracing};
 use jsonrpsee_core::traits::IdProvider;
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
-use jsonrpsee_types::error::{reject_too_big_request, reject_too_many_subscriptions};
+use jsonrpsee_types::error::{reject_too_big_request, reject_too_large_batch, reject_too_many_subscriptions};
+use jsonrpsee_types::number::NumberPolicy;
 use jsonrpsee_types::Params;
-use soketto::connection::Error as SokettoError;
+use soketto::connection::{Error as SokettoError, Mode};
 use soketto::data::ByteSlice125;
 use soketto::handshake::{server::Response, Server as SokettoServer};
 use soketto::Sender;
-use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::net::{lookup_host, TcpListener, TcpStream, ToSocketAddrs};
 use tokio_stream::wrappers::IntervalStream;
 use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 use tracing_futures::Instrument;
@@ -63,10 +85,12 @@ const MAX_CONNECTIONS: u64 = 100;
 
 /// A WebSocket JSON RPC server.
 pub struct Server<M> {
-	listener: TcpListener,
+	listeners: Vec<TcpListener>,
+	local_addrs: Vec<SocketAddr>,
 	cfg: Settings,
 	stop_monitor: StopMonitor,
 	resources: Resources,
+	methods: MethodsHandle,
 	middleware: M,
 	id_provider: Arc<dyn IdProvider>,
 }
@@ -74,7 +98,7 @@ pub struct Server<M> {
 impl<M> std::fmt::Debug for Server<M> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Server")
-			.field("listener", &self.listener)
+			.field("listeners", &self.listeners)
 			.field("cfg", &self.cfg)
 			.field("stop_monitor", &self.stop_monitor)
 			.field("id_provider", &self.id_provider)
@@ -85,72 +109,110 @@ impl<M> std::fmt::Debug for Server<M> {
 
 impl<M: Middleware> Server<M> {
 	/// Returns socket address to which the server is bound.
+	///
+	/// If the server is bound to multiple addresses, the first one is returned.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
-		self.listener.local_addr().map_err(Into::into)
+		self.local_addrs.first().copied().ok_or_else(|| Error::Custom("Local address not found".into()))
+	}
+
+	/// Returns all socket addresses to which the server is bound.
+	pub fn local_addrs(&self) -> &[SocketAddr] {
+		&self.local_addrs
 	}
 
 	/// Returns the handle to stop the running server.
 	pub fn server_handle(&self) -> ServerHandle {
-		self.stop_monitor.handle()
+		self.stop_monitor.handle(self.methods.clone())
 	}
 
 	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
-		let methods = methods.into().initialize_resources(&self.resources)?;
+		let mut methods = methods.into();
+		if self.cfg.system_limits_method {
+			methods.register_system_limits(&SystemLimits {
+				max_request_body_size: self.cfg.max_request_body_size,
+				max_response_body_size: self.cfg.max_response_body_size,
+				batch_requests_supported: self.cfg.batch_requests_supported,
+				max_batch_size: self.cfg.max_batch_size,
+				max_subscriptions_per_connection: Some(self.cfg.max_subscriptions_per_connection),
+				rate_limits: self.cfg.rate_limiter.limits(),
+				request_encodings: Vec::new(),
+				response_encodings: Vec::new(),
+			})?;
+		}
+		self.methods.add_module(methods)?;
 		let handle = self.server_handle();
 
 		match self.cfg.tokio_runtime.take() {
-			Some(rt) => rt.spawn(self.start_inner(methods)),
-			None => tokio::spawn(self.start_inner(methods)),
+			Some(rt) => rt.spawn(self.start_inner()),
+			None => tokio::spawn(self.start_inner()),
 		};
 
 		Ok(handle)
 	}
 
-	async fn start_inner(self, methods: Methods) {
+	async fn start_inner(self) {
+		let methods = self.methods;
 		let stop_monitor = self.stop_monitor;
 		let resources = self.resources;
 		let middleware = self.middleware;
+		let cfg = Arc::new(self.cfg);
+
+		let usage_reports = cfg
+			.resource_usage_interval
+			.map(|period| tokio::spawn(report_resource_usage(middleware.clone(), resources.clone(), period)));
 
 		let mut id = 0;
 		let mut connections = FutureDriver::default();
-		let mut incoming = Monitored::new(Incoming(self.listener), &stop_monitor);
+		let mut incoming = Monitored::new(Incoming(self.listeners), &stop_monitor);
 
 		loop {
 			match connections.select_with(&mut incoming).await {
-				Ok((socket, _addr)) => {
-					if let Err(e) = socket.set_nodelay(true) {
-						tracing::error!("Could not set NODELAY on socket: {:?}", e);
+				Ok((socket, remote_addr)) => {
+					// With the PROXY protocol, the address of the client is only known once the header was read.
+					if !cfg.proxy_protocol && !cfg.ip_filter.is_allowed(remote_addr.ip()) {
+						tracing::warn!("Denied connection from {}: address is not allowed", remote_addr.ip());
 						continue;
 					}
 
-					if connections.count() >= self.cfg.max_connections as usize {
+					if let Err(e) = cfg.tcp.apply(&socket) {
+						tracing::error!("Could not configure socket: {:?}", e);
+						continue;
+					}
+
+					if connections.count() >= cfg.max_connections as usize {
 						tracing::warn!("Too many connections. Try again in a while.");
 						connections.add(Box::pin(handshake(socket, HandshakeResponse::Reject { status_code: 429 })));
 						continue;
 					}
 
 					let methods = &methods;
-					let cfg = &self.cfg;
 					let id_provider = self.id_provider.clone();
 
 					connections.add(Box::pin(handshake(
 						socket,
 						HandshakeResponse::Accept {
 							conn_id: id,
+							remote_addr,
 							methods,
 							resources: &resources,
-							cfg,
+							cfg: &cfg,
 							stop_monitor: &stop_monitor,
 							middleware: middleware.clone(),
 							id_provider,
 						},
 					)));
 
-					tracing::info!("Accepting new connection {}/{}", connections.count(), self.cfg.max_connections);
+					tracing::info!("Accepting new connection {}/{}", connections.count(), cfg.max_connections);
 
 					id = id.wrapping_add(1);
 				}
+				Err(MonitoredError::Selector(err)) if err.kind() == std::io::ErrorKind::InvalidInput => {
+					// The socket is no longer listening, retrying would fail forever.
+					tracing::error!("Listener failed, stopping the server: {:?}", err);
+					stop_monitor.stop(ShutdownReason::Failed(err.to_string()));
+					break;
+				}
 				Err(MonitoredError::Selector(err)) => {
 					tracing::error!("Error while awaiting a new connection: {:?}", err);
 				}
@@ -158,7 +220,11 @@ impl<M: Middleware> Server<M> {
 			}
 		}
 
-		connections.await
+		connections.await;
+
+		if let Some(usage_reports) = usage_reports {
+			usage_reports.abort();
+		}
 	}
 }
 
@@ -179,7 +245,7 @@ enum MonitoredError<E> {
 	Selector(E),
 }
 
-struct Incoming(TcpListener);
+struct Incoming(Vec<TcpListener>);
 
 impl<'a> Future for Monitored<'a, Incoming> {
 	type Output = Result<(TcpStream, SocketAddr), MonitoredError<std::io::Error>>;
@@ -191,7 +257,13 @@ impl<'a> Future for Monitored<'a, Incoming> {
 			return Poll::Ready(Err(MonitoredError::Shutdown));
 		}
 
-		this.future.0.poll_accept(cx).map_err(MonitoredError::Selector)
+		for listener in &this.future.0 {
+			if let Poll::Ready(res) = listener.poll_accept(cx) {
+				return Poll::Ready(res.map_err(MonitoredError::Selector));
+			}
+		}
+
+		Poll::Pending
 	}
 }
 
@@ -218,19 +290,37 @@ enum HandshakeResponse<'a, M> {
 	},
 	Accept {
 		conn_id: ConnectionId,
-		methods: &'a Methods,
+		remote_addr: SocketAddr,
+		methods: &'a MethodsHandle,
 		resources: &'a Resources,
-		cfg: &'a Settings,
+		cfg: &'a Arc<Settings>,
 		stop_monitor: &'a StopMonitor,
 		middleware: M,
 		id_provider: Arc<dyn IdProvider>,
 	},
 }
 
-async fn handshake<M>(socket: tokio::net::TcpStream, mode: HandshakeResponse<'_, M>) -> Result<(), Error>
+async fn handshake<M>(mut socket: tokio::net::TcpStream, mut mode: HandshakeResponse<'_, M>) -> Result<(), Error>
 where
 	M: Middleware,
 {
+	if let HandshakeResponse::Accept { remote_addr, cfg, .. } = &mut mode {
+		if cfg.proxy_protocol {
+			match proxy_protocol::read_header(&mut socket).await {
+				Ok(addr) => *remote_addr = addr.unwrap_or(*remote_addr),
+				Err(err) => {
+					tracing::warn!("Denied connection: invalid PROXY protocol header: {}", err);
+					return Err(err.into());
+				}
+			}
+
+			if !cfg.ip_filter.is_allowed(remote_addr.ip()) {
+				tracing::warn!("Denied connection from {}: address is not allowed", remote_addr.ip());
+				return Ok(());
+			}
+		}
+	}
+
 	// For each incoming background_task we perform a handshake.
 	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
 
@@ -247,7 +337,24 @@ where
 
 			Ok(())
 		}
-		HandshakeResponse::Accept { conn_id, methods, resources, cfg, stop_monitor, middleware, id_provider } => {
+		HandshakeResponse::Accept {
+			conn_id,
+			remote_addr,
+			methods,
+			resources,
+			cfg,
+			stop_monitor,
+			middleware,
+			id_provider,
+		} => {
+			let compression = cfg.compression.map(|config| {
+				let max_message_len = cfg.max_request_body_size as usize;
+				let deflate = Deflate::new(Mode::Server, config, max_message_len);
+				let stats = deflate.stats();
+				server.add_extension(Box::new(deflate));
+				stats
+			});
+
 			let key = {
 				let req = server.receive_request().await?;
 
@@ -267,10 +374,33 @@ where
 				host_check.and(origin_check).map(|()| req.key())
 			};
 
-			match key {
+			// The handshake request is still buffered, read the headers soketto doesn't expose from it.
+			let authorization = read_raw_header(&server.take_buffer(), "authorization");
+
+			let permissions = match key {
+				Ok(_) if cfg.load_shedder.is_overloaded() => {
+					tracing::warn!("Rejected connection: server is overloaded");
+					let reject = Response::Reject { status_code: 503 };
+					server.send_response(&reject).await?;
+
+					return Err(Error::Custom("Server is overloaded".into()));
+				}
 				Ok(key) => {
+					let permissions = match cfg.authenticator.authenticate(authorization.as_deref()).await {
+						Some(permissions) => permissions,
+						None => {
+							tracing::warn!("Rejected connection: invalid credentials");
+							let reject = Response::Reject { status_code: 401 };
+							server.send_response(&reject).await?;
+
+							return Err(Error::HttpHeaderRejected("Authorization", "Invalid credentials".to_string()));
+						}
+					};
+
 					let accept = Response::Accept { key, protocol: None };
 					server.send_response(&accept).await?;
+
+					permissions
 				}
 				Err(err) => {
 					tracing::warn!("Rejected connection: {:?}", err);
@@ -279,24 +409,26 @@ where
 
 					return Err(err);
 				}
-			}
+			};
 
-			let join_result = tokio::spawn(background_task(
-				server,
-				conn_id,
-				methods.clone(),
-				resources.clone(),
-				cfg.max_request_body_size,
-				cfg.max_response_body_size,
-				cfg.max_log_length,
-				cfg.batch_requests_supported,
-				BoundedSubscriptions::new(cfg.max_subscriptions_per_connection),
-				stop_monitor.clone(),
+			let conn = Connection {
+				id: conn_id,
+				remote_addr,
+				methods: methods.clone(),
+				resources: resources.for_connection(),
+				stop_server: stop_monitor.clone(),
 				middleware,
 				id_provider,
-				cfg.ping_interval,
-			))
-			.await;
+				policy: CallPolicy::new(
+					cfg.authenticator.client(permissions),
+					cfg.rate_limiter.client(RateLimitKey::Ip(remote_addr.ip())),
+				)
+				.with_circuit_breaker(cfg.circuit_breaker.clone())
+				.with_client_ip(remote_addr.ip()),
+				tap: cfg.wire_tap.session("ws", remote_addr),
+				compression,
+			};
+			let join_result = tokio::spawn(background_task(server, conn, cfg.clone())).await;
 
 			match join_result {
 				Err(_) => Err(Error::Custom("Background task was aborted".into())),
@@ -306,32 +438,83 @@ where
 	}
 }
 
-async fn background_task(
-	server: SokettoServer<'_, BufReader<BufWriter<Compat<tokio::net::TcpStream>>>>,
-	conn_id: ConnectionId,
-	methods: Methods,
+/// Read the value of the header `name` from a raw HTTP request.
+fn read_raw_header(request: &[u8], name: &str) -> Option<String> {
+	request
+		.split(|&b| b == b'\n')
+		// Skip the request line and stop at the end of the headers.
+		.skip(1)
+		.take_while(|line| !line.is_empty() && *line != b"\r")
+		.find_map(|line| {
+			let colon = line.iter().position(|&b| b == b':')?;
+			let (header, value) = line.split_at(colon);
+
+			if header.eq_ignore_ascii_case(name.as_bytes()) {
+				std::str::from_utf8(&value[1..]).ok().map(|value| value.trim().to_owned())
+			} else {
+				None
+			}
+		})
+}
+
+/// A connection accepted by the server, handed over to its background task.
+struct Connection<M> {
+	id: ConnectionId,
+	remote_addr: SocketAddr,
+	methods: MethodsHandle,
 	resources: Resources,
-	max_request_body_size: u32,
-	max_response_body_size: u32,
-	max_log_length: u32,
-	batch_requests_supported: bool,
-	bounded_subscriptions: BoundedSubscriptions,
 	stop_server: StopMonitor,
-	middleware: impl Middleware,
+	middleware: M,
 	id_provider: Arc<dyn IdProvider>,
-	ping_interval: Duration,
+	policy: CallPolicy,
+	tap: Option<WireTapSession>,
+	/// Compression statistics of the connection, `None` if compression is disabled on the server.
+	compression: Option<CompressionStats>,
+}
+
+async fn background_task(
+	server: SokettoServer<'_, BufReader<BufWriter<Compat<tokio::net::TcpStream>>>>,
+	conn: Connection<impl Middleware>,
+	cfg: Arc<Settings>,
 ) -> Result<(), Error> {
+	let Connection {
+		id: conn_id,
+		remote_addr,
+		methods,
+		resources,
+		stop_server,
+		middleware,
+		id_provider,
+		policy,
+		tap,
+		compression,
+	} = conn;
+	let cfg = &*cfg;
+	let subscription_buffer =
+		cfg.subscription_buffer.map(|buffer| SubscriptionBuffer { eviction: cfg.slow_subscriber_eviction, ..buffer });
+	let bounded_subscriptions = BoundedSubscriptions::new(cfg.max_subscriptions_per_connection);
+
 	// And we can finally transition to a websocket background_task.
 	let mut builder = server.into_builder();
-	builder.set_max_message_size(max_request_body_size as usize);
+	builder.set_max_message_size(cfg.max_request_body_size as usize);
 	let (mut sender, mut receiver) = builder.finish();
-	let (tx, mut rx) = mpsc::unbounded::<String>();
+	let (max_queued, overflow_policy) = cfg.send_queue.unwrap_or((usize::MAX, OverflowPolicy::Block));
+	let (tx, mut rx) = send_queue::send_queue(max_queued, overflow_policy);
 	let bounded_subscriptions2 = bounded_subscriptions.clone();
 
 	let stop_server2 = stop_server.clone();
-	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
+	let sink = MethodSink::new_queued(tx, cfg.max_response_body_size, cfg.max_log_length)
+		.with_panic_hook(cfg.panic_hook.clone())
+		.with_number_policy(cfg.number_policy)
+		.with_subscription_buffer(subscription_buffer);
+
+	let conn_info = ConnectionInfo::new(conn_id, remote_addr);
+	middleware.on_connect_info(&conn_info);
 
-	middleware.on_connect();
+	let tx_tap = tap.clone();
+	// Pings are submitted at a fixed interval, so a live client sends something at least that often.
+	let ping_interval = cfg.ping_interval;
+	let max_silence = cfg.pong_timeout.map(|timeout| ping_interval + timeout);
 
 	// Send results back to the client.
 	tokio::spawn(async move {
@@ -348,6 +531,10 @@ async fn background_task(
 			// Note: Although, this is cancel-safe already, avoid using `select!` macro for future proofing.
 			match futures_util::future::select(rx_item, next_ping).await {
 				Either::Left((Some(response), ping)) => {
+					if let Some(tap) = tx_tap.as_ref() {
+						tap.record(Direction::Outbound, &response);
+					}
+
 					// If websocket message send fail then terminate the connection.
 					if let Err(err) = send_ws_message(&mut sender, response).await {
 						tracing::warn!("WS send error: {}; terminate connection", err);
@@ -371,6 +558,15 @@ async fn background_task(
 			}
 		}
 
+		// Tell the client why the server is shutting down, the close message can't carry a reason.
+		if let Some(reason) = stop_server2.shutdown_reason() {
+			let notification = shutdown_notification(&reason);
+			if let Some(tap) = tx_tap.as_ref() {
+				tap.record(Direction::Outbound, notification.as_bytes());
+			}
+			let _ = send_ws_message(&mut sender, notification.into()).await;
+		}
+
 		// Terminate connection and send close message.
 		let _ = sender.close().await;
 
@@ -386,12 +582,34 @@ async fn background_task(
 	let result = loop {
 		data.clear();
 
+		// Stop reading requests while the client doesn't keep up with the responses.
+		{
+			let ready = async {
+				sink.ready().await;
+				Ok::<_, SokettoError>(())
+			};
+			tokio::pin!(ready);
+
+			if let Err(MonitoredError::Shutdown) =
+				method_executors.select_with(Monitored::new(ready, &stop_server)).await
+			{
+				break Ok(());
+			}
+		}
+
 		{
 			// Need the extra scope to drop this pinned future and reclaim access to `data`
 			let receive = async {
 				// Identical loop to `soketto::receive_data` with debug logs for `Pong` frames.
 				loop {
-					match receiver.receive(&mut data).await? {
+					let incoming = match max_silence {
+						Some(max_silence) => tokio::time::timeout(max_silence, receiver.receive(&mut data))
+							.await
+							.map_err(|_| SokettoError::Io(std::io::ErrorKind::TimedOut.into()))?,
+						None => receiver.receive(&mut data).await,
+					};
+
+					match incoming? {
 						soketto::Incoming::Data(d) => break Ok(d),
 						soketto::Incoming::Pong(_) => tracing::debug!("recv pong"),
 						_ => continue,
@@ -408,13 +626,18 @@ async fn background_task(
 						sink.close();
 						break Ok(());
 					}
+					MonitoredError::Selector(SokettoError::Io(err)) if err.kind() == std::io::ErrorKind::TimedOut => {
+						tracing::warn!("WS transport: no pong received, terminate connection {}", conn_id);
+						sink.close();
+						break Ok(());
+					}
 					MonitoredError::Selector(SokettoError::MessageTooLarge { current, maximum }) => {
 						tracing::warn!(
 							"WS transport error: outgoing message is too big error ({} bytes, max is {})",
 							current,
 							maximum
 						);
-						sink.send_error(Id::Null, reject_too_big_request(max_request_body_size));
+						sink.send_error(Id::Null, reject_too_big_request(cfg.max_request_body_size));
 						continue;
 					}
 					// These errors can not be gracefully handled, so just log them and terminate the connection.
@@ -428,117 +651,148 @@ async fn background_task(
 			};
 		};
 
+		if let Some(tap) = tap.as_ref() {
+			tap.record(Direction::Inbound, &data);
+		}
+
 		let request_start = middleware.on_request();
+		// Methods may be added or removed while the connection is open, use the current ones for this message.
+		let methods = methods.snapshot();
 
 		let first_non_whitespace = data.iter().find(|byte| !byte.is_ascii_whitespace());
 
 		match first_non_whitespace {
 			Some(b'{') => {
-				if let Ok(req) = serde_json::from_slice::<Request>(&data) {
+				if let Ok(req) = parse_request(&data) {
 					let trace = RpcTracing::method_call(&req.method);
 					let _enter = trace.span().enter();
 
-					rx_log_from_json(&req, max_log_length);
+					rx_log_from_json(&req, cfg.max_log_length);
 
+					let sink = sink.for_call();
 					let id = req.id.clone();
 					let params = Params::new(None, req.params.map(|params| params.get()));
 
-					middleware.on_call(&req.method);
-
-					match methods.method_with_name(&req.method) {
-						None => {
-							sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-							middleware.on_response(request_start);
-						}
-						Some((name, method)) => match &method.inner() {
-							MethodKind::Sync(callback) => match method.claim(name, &resources) {
-								Ok(guard) => {
-									let result = (callback)(id, params, &sink);
-
-									middleware.on_result(name, result, request_start);
-									middleware.on_response(request_start);
-									drop(guard);
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
-								}
-							},
-							MethodKind::Async(callback) => match method.claim(name, &resources) {
-								Ok(guard) => {
-									let sink = sink.clone();
-									let id = id.into_owned();
-									let params = params.into_owned();
-
-									let fut = async move {
-										let result = (callback)(id, params, sink, conn_id, Some(guard)).await;
-										middleware.on_result(name, result, request_start);
-										middleware.on_response(request_start);
-									};
-
-									method_executors.add(fut.in_current_span().boxed());
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
-								}
-							},
-							MethodKind::Subscription(callback) => match method.claim(&req.method, &resources) {
-								Ok(guard) => {
-									let result = if let Some(cn) = bounded_subscriptions.acquire() {
-										let conn_state =
-											ConnState { conn_id, close_notify: cn, id_provider: &*id_provider };
-										callback(id, params, sink.clone(), conn_state, Some(guard))
-									} else {
-										sink.send_error(
-											req.id,
-											reject_too_many_subscriptions(bounded_subscriptions.max()),
+					middleware.on_call_info(
+						&CallInfo::new(&req.method, &params, &req.id, Transport::WebSocket)
+							.with_client_ip(policy.client_ip()),
+					);
+
+					let admitted = match policy.check(&req.method, &methods) {
+						Err(denied) => Err(ErrorObject::from(denied)),
+						Ok(()) => middleware.on_call_async(&req.method, &params).await,
+					};
+
+					if let Err(err) = admitted {
+						tracing::warn!("Denied call to `{}`: {}", req.method, err.message());
+						sink.send_error(req.id, err);
+						report_result(middleware, &req.method, false, &sink, request_start);
+						report_response(middleware, sink.bytes_sent(), request_start);
+					} else {
+						match methods.method_with_name(&req.method) {
+							None => {
+								sink.send_error(req.id, ErrorCode::MethodNotFound.into());
+								report_result(middleware, &req.method, false, &sink, request_start);
+								report_response(middleware, sink.bytes_sent(), request_start);
+							}
+							Some((name, method)) => match &method.inner() {
+								MethodKind::Sync(callback) => match method.claim(name, &resources) {
+									Ok(guard) => {
+										let result = (callback)(id, params, &sink);
+
+										policy.record(name, &sink);
+										report_result(middleware, name, result, &sink, request_start);
+										report_response(middleware, sink.bytes_sent(), request_start);
+										drop(guard);
+									}
+									Err(err) => {
+										tracing::error!(
+											"[Methods::execute_with_resources] failed to lock resources: {:?}",
+											err
 										);
-										false
-									};
-									middleware.on_result(name, result, request_start);
-									middleware.on_response(request_start);
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
+										sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+										report_result(middleware, name, false, &sink, request_start);
+										report_response(middleware, sink.bytes_sent(), request_start);
+									}
+								},
+								MethodKind::Async(callback) => match method.claim(name, &resources) {
+									Ok(guard) => {
+										let callback = callback.clone();
+										let id = id.into_owned();
+										let params = params.into_owned();
+										let policy = &policy;
+
+										let fut = async move {
+											let result =
+												(callback)(id, params, sink.clone(), conn_id, Some(guard)).await;
+											policy.record(name, &sink);
+											report_result(middleware, name, result, &sink, request_start);
+											report_response(middleware, sink.bytes_sent(), request_start);
+										};
+
+										method_executors.add(fut.in_current_span().boxed());
+									}
+									Err(err) => {
+										tracing::error!(
+											"[Methods::execute_with_resources] failed to lock resources: {:?}",
+											err
+										);
+										sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+										report_result(middleware, name, false, &sink, request_start);
+										report_response(middleware, sink.bytes_sent(), request_start);
+									}
+								},
+								MethodKind::Subscription(callback) => match method.claim(&req.method, &resources) {
+									Ok(guard) => {
+										let result = if cfg.load_shedder.is_overloaded() {
+											sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+											false
+										} else if let Some(cn) = bounded_subscriptions.acquire() {
+											let conn_state =
+												ConnState { conn_id, close_notify: cn, id_provider: &*id_provider };
+											callback(id, params, sink.clone(), conn_state, Some(guard))
+										} else {
+											sink.send_error(
+												req.id,
+												reject_too_many_subscriptions(bounded_subscriptions.max()),
+											);
+											false
+										};
+										report_result(middleware, name, result, &sink, request_start);
+										report_response(middleware, sink.bytes_sent(), request_start);
+									}
+									Err(err) => {
+										tracing::error!(
+											"[Methods::execute_with_resources] failed to lock resources: {:?}",
+											err
+										);
+										sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+										report_result(middleware, name, false, &sink, request_start);
+										report_response(middleware, sink.bytes_sent(), request_start);
+									}
+								},
+								MethodKind::Unsubscription(callback) => {
+									// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+									let result = callback(id, params, &sink, conn_id);
+									report_result(middleware, name, result, &sink, request_start);
+									report_response(middleware, sink.bytes_sent(), request_start);
 								}
 							},
-							MethodKind::Unsubscription(callback) => {
-								// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
-								let result = callback(id, params, &sink, conn_id);
-								middleware.on_result(name, result, request_start);
-								middleware.on_response(request_start);
-							}
-						},
+						}
 					}
 				} else {
+					let sink = sink.for_call();
 					let (id, code) = prepare_error(&data);
 					sink.send_error(id, code.into());
-					middleware.on_response(request_start);
+					report_response(middleware, sink.bytes_sent(), request_start);
 				}
 			}
 			Some(b'[') => {
 				// Make sure the following variables are not moved into async closure below.
 				let d = std::mem::take(&mut data);
 				let resources = &resources;
-				let methods = &methods;
-				let sink = sink.clone();
+				let policy = &policy;
+				let sink = sink.for_call();
 				let id_provider = id_provider.clone();
 				let bounded_subscriptions2 = bounded_subscriptions.clone();
 
@@ -547,96 +801,72 @@ async fn background_task(
 					// request in the batch and read the results off of a new channel, `rx_batch`, and then send the
 					// complete batch response back to the client over `tx`.
 					let (tx_batch, mut rx_batch) = mpsc::unbounded();
-					let sink_batch = MethodSink::new_with_limit(tx_batch, max_response_body_size, max_log_length);
-					if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&d) {
-						if !batch_requests_supported {
+					let sink_batch =
+						MethodSink::new_with_limit(tx_batch, cfg.max_response_body_size, cfg.max_log_length)
+							.with_panic_hook(cfg.panic_hook.clone())
+							.with_number_policy(cfg.number_policy)
+							.with_notifications_to(sink.clone());
+					if let Ok(batch) = parse_batch(&d) {
+						if !cfg.batch_requests_supported {
 							sink.send_error(
 								Id::Null,
 								ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
 							);
-							middleware.on_response(request_start);
+							report_response(middleware, sink.bytes_sent(), request_start);
+						} else if let Some(max) = cfg.max_batch_size.filter(|max| batch.len() > *max) {
+							sink.send_error(Id::Null, reject_too_large_batch(max));
+							report_response(middleware, sink.bytes_sent(), request_start);
 						} else if !batch.is_empty() {
 							let trace = RpcTracing::batch();
 							let _enter = trace.span().enter();
 
-							rx_log_from_json(&batch, max_log_length);
+							rx_log_from_json(&batch, cfg.max_log_length);
 
-							join_all(batch.into_iter().filter_map(move |req| {
-								let id = req.id.clone();
-								let params = Params::new(None, req.params.map(|params| params.get()));
-								let name = &req.method;
-
-								match methods.method_with_name(name) {
-									None => {
-										sink_batch.send_error(req.id, ErrorCode::MethodNotFound.into());
-										None
+							// Claim the resources once for the whole batch if possible, the calls then don't claim their own.
+							let batch_guard = if cfg.amortize_batch_resource_claims {
+								methods.claim_batch(batch.iter().map(|req| req.method.as_ref()), resources)
+							} else {
+								None
+							};
+							let claim = |name: &str, callback: &MethodCallback| match batch_guard {
+								Some(_) => Ok(None),
+								None => callback.claim(name, resources).map(Some),
+							};
+
+							// Stages are executed one after another, the calls within a stage concurrently, at most
+							// `cfg.max_batch_concurrency` at a time.
+							for stage in batch_stages(batch) {
+								let stage =
+									admit_calls(stage, None, Transport::WebSocket, policy, &methods, middleware).await;
+
+								// Sync calls are answered right away, the async ones are collected to be executed below.
+								let calls = Vec::from_iter(stage.into_iter().filter_map(|(req, admitted)| {
+									let sink_batch = sink_batch.for_call();
+
+									if let Err(err) = admitted {
+										tracing::warn!("Denied call to `{}`: {}", req.method, err.message());
+										sink_batch.send_error(req.id, err);
+										report_result(middleware, &req.method, false, &sink_batch, request_start);
+										return None;
 									}
-									Some((name, method_callback)) => match &method_callback.inner() {
-										MethodKind::Sync(callback) => match method_callback.claim(name, resources) {
-											Ok(guard) => {
-												let result = (callback)(id, params, &sink_batch);
-												middleware.on_result(name, result, request_start);
-												drop(guard);
-												None
-											}
-											Err(err) => {
-												tracing::error!(
-													"[Methods::execute_with_resources] failed to lock resources: {:?}",
-													err
-												);
-												sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-												middleware.on_result(&req.method, false, request_start);
-												None
-											}
-										},
-										MethodKind::Async(callback) => match method_callback
-											.claim(&req.method, resources)
-										{
-											Ok(guard) => {
-												let sink_batch = sink_batch.clone();
-												let id = id.into_owned();
-												let params = params.into_owned();
-
-												Some(async move {
-													let result =
-														(callback)(id, params, sink_batch, conn_id, Some(guard)).await;
-													middleware.on_result(&req.method, result, request_start);
-												})
-											}
-											Err(err) => {
-												tracing::error!(
-													"[Methods::execute_with_resources] failed to lock resources: {:?}",
-													err
-												);
-												sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-												middleware.on_result(&req.method, false, request_start);
-												None
-											}
-										},
-										MethodKind::Subscription(callback) => {
-											match method_callback.claim(&req.method, resources) {
+
+									let id = req.id.clone();
+									let params = Params::new(None, req.params.map(|params| params.get()));
+									let name = &req.method;
+
+									match methods.method_with_name(name) {
+										None => {
+											sink_batch.send_error(req.id, ErrorCode::MethodNotFound.into());
+											report_result(middleware, &req.method, false, &sink_batch, request_start);
+											None
+										}
+										Some((name, method_callback)) => match &method_callback.inner() {
+											MethodKind::Sync(callback) => match claim(name, method_callback) {
 												Ok(guard) => {
-													let result = if let Some(cn) = bounded_subscriptions2.acquire() {
-														let conn_state = ConnState {
-															conn_id,
-															close_notify: cn,
-															id_provider: &*id_provider,
-														};
-														callback(
-															id,
-															params,
-															sink_batch.clone(),
-															conn_state,
-															Some(guard),
-														)
-													} else {
-														sink_batch.send_error(
-															req.id,
-															reject_too_many_subscriptions(bounded_subscriptions2.max()),
-														);
-														false
-													};
-													middleware.on_result(&req.method, result, request_start);
+													let result = (callback)(id, params, &sink_batch);
+													policy.record(name, &sink_batch);
+													report_result(middleware, name, result, &sink_batch, request_start);
+													drop(guard);
 													None
 												}
 												Err(err) => {
@@ -644,40 +874,146 @@ async fn background_task(
 														"[Methods::execute_with_resources] failed to lock resources: {:?}",
 														err
 													);
-
 													sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-													middleware.on_result(&req.method, false, request_start);
+													report_result(
+														middleware,
+														&req.method,
+														false,
+														&sink_batch,
+														request_start,
+													);
 													None
 												}
+											},
+											MethodKind::Async(callback) => match claim(name, method_callback) {
+												Ok(guard) => {
+													let id = id.into_owned();
+													let params = params.into_owned();
+
+													Some(async move {
+														let result =
+															(callback)(id, params, sink_batch.clone(), conn_id, guard)
+																.await;
+														policy.record(name, &sink_batch);
+														report_result(
+															middleware,
+															&req.method,
+															result,
+															&sink_batch,
+															request_start,
+														);
+													})
+												}
+												Err(err) => {
+													tracing::error!(
+														"[Methods::execute_with_resources] failed to lock resources: {:?}",
+														err
+													);
+													sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
+													report_result(
+														middleware,
+														&req.method,
+														false,
+														&sink_batch,
+														request_start,
+													);
+													None
+												}
+											},
+											MethodKind::Subscription(callback) => {
+												match method_callback.claim(&req.method, resources) {
+													Ok(guard) => {
+														let result = if cfg.load_shedder.is_overloaded() {
+															sink_batch
+																.send_error(req.id, ErrorCode::ServerIsBusy.into());
+															false
+														} else if let Some(cn) = bounded_subscriptions2.acquire() {
+															let conn_state = ConnState {
+																conn_id,
+																close_notify: cn,
+																id_provider: &*id_provider,
+															};
+															callback(
+																id,
+																params,
+																sink_batch.clone(),
+																conn_state,
+																Some(guard),
+															)
+														} else {
+															sink_batch.send_error(
+																req.id,
+																reject_too_many_subscriptions(
+																	bounded_subscriptions2.max(),
+																),
+															);
+															false
+														};
+														report_result(
+															middleware,
+															&req.method,
+															result,
+															&sink_batch,
+															request_start,
+														);
+														None
+													}
+													Err(err) => {
+														tracing::error!(
+															"[Methods::execute_with_resources] failed to lock resources: {:?}",
+															err
+														);
+
+														sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
+														report_result(
+															middleware,
+															&req.method,
+															false,
+															&sink_batch,
+															request_start,
+														);
+														None
+													}
+												}
 											}
-										}
-										MethodKind::Unsubscription(callback) => {
-											// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
-											let result = callback(id, params, &sink_batch, conn_id);
-											middleware.on_result(&req.method, result, request_start);
-											None
-										}
-									},
-								}
-							}))
-							.await;
+											MethodKind::Unsubscription(callback) => {
+												// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+												let result = callback(id, params, &sink_batch, conn_id);
+												report_result(
+													middleware,
+													&req.method,
+													result,
+													&sink_batch,
+													request_start,
+												);
+												None
+											}
+										},
+									}
+								}));
+								stream::iter(calls)
+									.buffer_unordered(cfg.max_batch_concurrency.unwrap_or(usize::MAX))
+									.for_each(|_| async {})
+									.await;
+							}
 
+							drop(batch_guard);
 							rx_batch.close();
 							let results = collect_batch_response(rx_batch).await;
 
 							if let Err(err) = sink.send_raw(results) {
 								tracing::warn!("Error sending batch response to the client: {:?}", err)
 							} else {
-								middleware.on_response(request_start);
+								report_response(middleware, sink.bytes_sent(), request_start);
 							}
 						} else {
 							sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
-							middleware.on_response(request_start);
+							report_response(middleware, sink.bytes_sent(), request_start);
 						}
 					} else {
 						let (id, code) = prepare_error(&d);
 						sink.send_error(id, code.into());
-						middleware.on_response(request_start);
+						report_response(middleware, sink.bytes_sent(), request_start);
 					}
 				};
 
@@ -689,7 +1025,10 @@ async fn background_task(
 		}
 	};
 
-	middleware.on_disconnect();
+	if let Some(info) = compression.and_then(|stats| stats.info(remote_addr, Transport::WebSocket)) {
+		middleware.on_compression_info(&info);
+	}
+	middleware.on_disconnect_info(&conn_info);
 
 	// Drive all running methods to completion.
 	// **NOTE** Do not return early in this function. This `await` needs to run to guarantee
@@ -718,10 +1057,54 @@ struct Settings {
 	access_control: AccessControl,
 	/// Whether batch requests are supported by this server or not.
 	batch_requests_supported: bool,
+	/// Whether the resources of homogeneous batches are claimed once per batch.
+	amortize_batch_resource_claims: bool,
+	/// Maximum number of calls of a batch executed concurrently, `None` for no limit.
+	max_batch_concurrency: Option<usize>,
+	/// Maximum number of calls in a batch, `None` for no limit.
+	max_batch_size: Option<usize>,
+	/// Maximum number of messages waiting to be sent to a client and what happens once it's reached, `None` for
+	/// no limit.
+	send_queue: Option<(usize, OverflowPolicy)>,
+	/// Maximum number of messages of each subscription waiting to be sent and what happens once it's reached,
+	/// `None` for no limit.
+	subscription_buffer: Option<SubscriptionBuffer>,
+	/// Closing of the subscriptions whose buffer stays full, `None` to keep them.
+	slow_subscriber_eviction: Option<SlowSubscriberEviction>,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	/// The interval at which `Ping` frames are submitted.
 	ping_interval: Duration,
+	/// How long to wait for a `Pong` frame before terminating the connection, `None` to wait forever.
+	pong_timeout: Option<Duration>,
+	/// Period of the reports of the resource usage to the middleware, `None` to not report it.
+	resource_usage_interval: Option<Duration>,
+	/// Per-client rate limiter.
+	rate_limiter: RateLimiter,
+	/// Filter of the IP addresses of the clients.
+	ip_filter: IpFilter,
+	/// Whether connections start with a PROXY protocol header.
+	proxy_protocol: bool,
+	/// Per-method circuit breaker.
+	circuit_breaker: CircuitBreaker,
+	/// Sheds connections and subscriptions under memory pressure.
+	load_shedder: LoadShedder,
+	/// Captures the raw messages of a sample of connections.
+	wire_tap: WireTap,
+	/// Compression of the messages with the clients supporting it.
+	compression: Option<DeflateConfig>,
+	/// Authentication of clients.
+	authenticator: Authenticator,
+	/// Options applied to the sockets of accepted connections.
+	tcp: TcpSettings,
+	/// Options controlling to which addresses the server binds.
+	bind: BindSettings,
+	/// Hook invoked when a method handler panics.
+	panic_hook: Option<PanicHook>,
+	/// Handling of integers that can't be represented exactly as doubles.
+	number_policy: NumberPolicy,
+	/// Whether the `system_limits` method is registered.
+	system_limits_method: bool,
 }
 
 impl Default for Settings {
@@ -733,9 +1116,30 @@ impl Default for Settings {
 			max_subscriptions_per_connection: 1024,
 			max_connections: MAX_CONNECTIONS,
 			batch_requests_supported: true,
+			amortize_batch_resource_claims: false,
+			max_batch_concurrency: None,
+			max_batch_size: None,
+			send_queue: None,
+			subscription_buffer: None,
+			slow_subscriber_eviction: None,
 			access_control: AccessControl::default(),
 			tokio_runtime: None,
 			ping_interval: Duration::from_secs(60),
+			pong_timeout: None,
+			resource_usage_interval: None,
+			rate_limiter: RateLimiter::default(),
+			ip_filter: IpFilter::default(),
+			proxy_protocol: false,
+			circuit_breaker: CircuitBreaker::default(),
+			load_shedder: LoadShedder::default(),
+			wire_tap: WireTap::default(),
+			compression: None,
+			authenticator: Authenticator::default(),
+			tcp: TcpSettings { nodelay: Some(true), ..Default::default() },
+			bind: BindSettings::default(),
+			panic_hook: None,
+			number_policy: NumberPolicy::Preserve,
+			system_limits_method: false,
 		}
 	}
 }
@@ -793,14 +1197,124 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Enables or disables claiming resources once per batch request (default is disabled).
+	///
+	/// When enabled and all calls of a batch are made to methods with identical resource requirements,
+	/// these resources are claimed once for the whole batch rather than for every call, reducing the
+	/// contention on the resource table for homogeneous bulk requests.
+	pub fn amortize_batch_resource_claims(mut self, enabled: bool) -> Self {
+		self.settings.amortize_batch_resource_claims = enabled;
+		self
+	}
+
+	/// Configure the maximum number of calls of a batch request executed concurrently (default is unlimited).
+	///
+	/// The remaining calls are started as the running ones complete, the batch response still contains
+	/// the responses of all calls. Calls hinted to be executed sequentially are not affected, and a limit of
+	/// zero is treated as one.
+	pub fn max_batch_concurrency(mut self, max: usize) -> Self {
+		self.settings.max_batch_concurrency = Some(max.max(1));
+		self
+	}
+
+	/// Configure the maximum number of calls in a batch request (default is unlimited).
+	///
+	/// Larger batches are rejected as a whole with a single
+	/// [`BATCH_TOO_LARGE_CODE`](crate::types::error::BATCH_TOO_LARGE_CODE) error, whatever their size in bytes,
+	/// which is limited separately.
+	pub fn max_batch_size(mut self, max: usize) -> Self {
+		self.settings.max_batch_size = Some(max);
+		self
+	}
+
+	/// Configure the maximum number of messages waiting to be sent to a client (default is unlimited), and what
+	/// happens to the messages sent while that many are waiting.
+	///
+	/// Bounds the memory used by clients that don't read the responses and notifications of their subscriptions
+	/// as fast as the server produces them. A limit of zero is treated as one.
+	///
+	/// ```
+	/// use jsonrpsee_ws_server::{OverflowPolicy, WsServerBuilder};
+	///
+	/// let builder = WsServerBuilder::default().bounded_send_queue(1024, OverflowPolicy::CloseConnection);
+	/// ```
+	pub fn bounded_send_queue(mut self, max: usize, policy: OverflowPolicy) -> Self {
+		self.settings.send_queue = Some((max, policy));
+		self
+	}
+
+	/// Configure the maximum number of messages each subscription has waiting to be sent to its client (default
+	/// is unlimited), and what happens to the notifications sent while that many are waiting.
+	///
+	/// Unlike [`Builder::bounded_send_queue`], this keeps a busy subscription from holding up the responses and
+	/// the other subscriptions of its connection. A limit of zero is treated as one.
+	///
+	/// ```
+	/// use jsonrpsee_ws_server::{SubscriptionOverflowPolicy, WsServerBuilder};
+	///
+	/// let builder = WsServerBuilder::default().subscription_buffer(128, SubscriptionOverflowPolicy::DropNewest);
+	/// ```
+	pub fn subscription_buffer(mut self, max: usize, policy: SubscriptionOverflowPolicy) -> Self {
+		self.settings.subscription_buffer = Some(SubscriptionBuffer::new(max, policy));
+		self
+	}
+
+	/// Close the subscriptions whose buffer stays full for longer than `timeout` (disabled by default), and their
+	/// connection as well if `close_connection` is set.
+	///
+	/// Protects the server from clients which don't read the notifications of their subscriptions at all. Only
+	/// applies if the messages of each subscription are bounded with [`Builder::subscription_buffer`].
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_ws_server::{SubscriptionOverflowPolicy, WsServerBuilder};
+	///
+	/// let builder = WsServerBuilder::default()
+	///     .subscription_buffer(128, SubscriptionOverflowPolicy::Block)
+	///     .evict_slow_subscribers(Duration::from_secs(30), false);
+	/// ```
+	pub fn evict_slow_subscribers(mut self, timeout: Duration, close_connection: bool) -> Self {
+		self.settings.slow_subscriber_eviction = Some(SlowSubscriberEviction { timeout, close_connection });
+		self
+	}
+
+	/// Register a hook invoked whenever a method handler panics.
+	///
+	/// Panics are always caught, the call is answered with an internal error whose `data` contains the
+	/// correlation id passed to the hook, and the connection is kept open.
+	pub fn panic_hook(mut self, hook: impl Fn(&PanicReport) + Send + Sync + 'static) -> Self {
+		self.settings.panic_hook = Some(PanicHook::new(hook));
+		self
+	}
+
+	/// Configure how integers beyond `2^53 - 1` in magnitude are handled in results and subscription
+	/// notifications (default is to send them as they are).
+	///
+	/// Such integers are silently rounded by clients parsing numbers as doubles, such as JavaScript clients.
+	/// Clients can parse stringified integers with [`LenientInt`](jsonrpsee_types::number::LenientInt).
+	pub fn set_number_policy(mut self, policy: NumberPolicy) -> Self {
+		self.settings.number_policy = policy;
+		self
+	}
+
 	/// Set the maximum number of connections allowed. Default is 1024.
 	pub fn max_subscriptions_per_connection(mut self, max: u32) -> Self {
 		self.settings.max_subscriptions_per_connection = max;
 		self
 	}
 
-	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
-	/// registered resources on this server instance would exceed 8.
+	/// Enables or disables the [`SYSTEM_LIMITS`](jsonrpsee_core::server::rpc_module::SYSTEM_LIMITS) method
+	/// (default is disabled).
+	///
+	/// When enabled, the server registers a method responding with its effective limits: the maximum message
+	/// sizes, the maximum batch size, the maximum number of subscriptions per connection and the rate limits,
+	/// see [`SystemLimits`].
+	pub fn system_limits_method(mut self, enabled: bool) -> Self {
+		self.settings.system_limits_method = enabled;
+		self
+	}
+
+	/// Register a new resource kind. Errors if `label` is already registered.
 	///
 	/// See the module documentation for [`resurce_limiting`](../jsonrpsee_utils/server/resource_limiting/index.html#resource-limiting)
 	/// for details.
@@ -809,6 +1323,21 @@ impl<M> Builder<M> {
 		Ok(self)
 	}
 
+	/// Limit the units of the registered resource `label` that the calls of a single connection may claim at
+	/// the same time, `0` (the default) for no limit. Errors if `label` isn't registered.
+	pub fn connection_resource_quota(self, label: &'static str, quota: u16) -> Result<Self, Error> {
+		self.resources.set_connection_quota(label, quota)?;
+		Ok(self)
+	}
+
+	/// Report the usage of the registered resources to [`Middleware::on_resource_usage`] every `period`.
+	///
+	/// Default: disabled.
+	pub fn resource_usage_interval(mut self, period: Duration) -> Self {
+		self.settings.resource_usage_interval = Some(period);
+		self
+	}
+
 	/// Add a middleware to the builder [`Middleware`](../jsonrpsee_core/middleware/trait.Middleware.html).
 	///
 	/// ```
@@ -849,7 +1378,8 @@ impl<M> Builder<M> {
 	/// Configure the interval at which pings are submitted.
 	///
 	/// This option is used to keep the connection alive, and is just submitting `Ping` frames,
-	/// without making any assumptions about when a `Pong` frame should be received.
+	/// without making any assumptions about when a `Pong` frame should be received unless a
+	/// [`pong_timeout`](Builder::pong_timeout) is configured.
 	///
 	/// Default: 60 seconds.
 	///
@@ -867,6 +1397,99 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Terminate connections on which nothing, not even a `Pong` frame, was received within `timeout` of a
+	/// `Ping` frame being submitted, see [`ping_interval`](Builder::ping_interval).
+	///
+	/// The subscriptions of dead connections are then dropped, instead of lingering until the operating system
+	/// gives up on the TCP connection.
+	///
+	/// Default: disabled.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use std::time::Duration;
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	///
+	/// // Ping every 10 seconds and drop the clients that didn't answer 5 seconds later.
+	/// let builder = WsServerBuilder::default()
+	///     .ping_interval(Duration::from_secs(10))
+	///     .pong_timeout(Duration::from_secs(5));
+	/// ```
+	pub fn pong_timeout(mut self, timeout: Duration) -> Self {
+		self.settings.pong_timeout = Some(timeout);
+		self
+	}
+
+	/// Enables or disables `TCP_NODELAY` on accepted connections (default is enabled).
+	pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+		self.settings.tcp.nodelay = Some(enabled);
+		self
+	}
+
+	/// Enables TCP keepalive on accepted connections (default is the operating system default).
+	///
+	/// ```rust
+	/// use std::time::Duration;
+	/// use jsonrpsee_ws_server::{TcpKeepalive, WsServerBuilder};
+	///
+	/// let keepalive = TcpKeepalive::new(Duration::from_secs(60)).interval(Duration::from_secs(10)).retries(3);
+	/// let builder = WsServerBuilder::default().tcp_keepalive(keepalive);
+	/// ```
+	pub fn tcp_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
+		self.settings.tcp.keepalive = Some(keepalive);
+		self
+	}
+
+	/// Sets the size of the send buffer of accepted connections (default is the operating system default).
+	pub fn tcp_send_buffer_size(mut self, size: usize) -> Self {
+		self.settings.tcp.send_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets the size of the receive buffer of accepted connections (default is the operating system default).
+	pub fn tcp_recv_buffer_size(mut self, size: usize) -> Self {
+		self.settings.tcp.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// Sets `SO_LINGER` on accepted connections, `None` disables lingering (default is the operating system default).
+	pub fn tcp_linger(mut self, linger: Option<Duration>) -> Self {
+		self.settings.tcp.linger = Some(linger);
+		self
+	}
+
+	/// Sets `IPV6_V6ONLY` on IPv6 listening sockets (default is the operating system default).
+	///
+	/// When disabled, a server bound to `[::]` accepts IPv4 connections as well on a single dual-stack socket.
+	/// When enabled, it only accepts IPv6 connections, combine it with [`Builder::bind_all_addresses`]
+	/// to listen on separate IPv4 and IPv6 sockets.
+	pub fn ipv6_only(mut self, enabled: bool) -> Self {
+		self.settings.bind.ipv6_only = Some(enabled);
+		self
+	}
+
+	/// Enables or disables binding to all given addresses (default is disabled).
+	///
+	/// When enabled, a separate listening socket is bound to every address and building the server fails if
+	/// any of them can't be bound. Otherwise only the first address that can be bound is used.
+	///
+	/// ```rust
+	/// use std::net::SocketAddr;
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let addrs: &[SocketAddr] = &["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+	///   let server = WsServerBuilder::default().bind_all_addresses(true).build(addrs).await.unwrap();
+	///   assert_eq!(server.local_addrs().len(), 2);
+	/// }
+	/// ```
+	pub fn bind_all_addresses(mut self, enabled: bool) -> Self {
+		self.settings.bind.all_addresses = enabled;
+		self
+	}
+
 	/// Configure custom `subscription ID` provider for the server to use
 	/// to when getting new subscription calls.
 	///
@@ -892,6 +1515,97 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Configure per-client rate limiting (default is disabled).
+	///
+	/// Clients are always identified by their IP address, [`RateLimiter::key_by_header`] is not supported
+	/// because the headers of the WebSocket handshake are not available.
+	///
+	/// See the module documentation for [`rate_limiting`](../jsonrpsee_utils/server/rate_limiting/index.html#rate-limiting)
+	/// for details.
+	pub fn set_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+		self.settings.rate_limiter = limiter;
+		self
+	}
+
+	/// Configure which IP addresses clients may connect from (default is all).
+	///
+	/// The connections of rejected clients are closed as soon as they are accepted, before the handshake.
+	///
+	/// See the module documentation for [`ip_filter`](../jsonrpsee_utils/server/ip_filter/index.html#ip-filtering)
+	/// for details.
+	pub fn set_ip_filter(mut self, filter: IpFilter) -> Self {
+		self.settings.ip_filter = filter;
+		self
+	}
+
+	/// Expects the connections to start with a PROXY protocol header, sent by the load balancer they go through,
+	/// and treats the clients as connecting from the address it carries (default is disabled).
+	///
+	/// Connections without a valid header are closed. The IP filter applies to the address of the header.
+	///
+	/// See the module documentation for [`proxy_protocol`](../jsonrpsee_utils/server/proxy_protocol/index.html#proxy-protocol)
+	/// for details.
+	pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+		self.settings.proxy_protocol = enabled;
+		self
+	}
+
+	/// Configure per-method circuit breaking (default is disabled).
+	///
+	/// While the circuit of a method is open, its calls are answered with an error without being executed.
+	///
+	/// See the module documentation for [`circuit_breaker`](../jsonrpsee_utils/server/circuit_breaker/index.html#circuit-breaking)
+	/// for details.
+	pub fn set_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+		self.settings.circuit_breaker = breaker;
+		self
+	}
+
+	/// Configure shedding work under memory pressure (default is disabled).
+	///
+	/// While the server is overloaded, new connections are rejected with `503 Service Unavailable` and
+	/// subscription calls with a `ServerIsBusy` error. Other calls on established connections and
+	/// unsubscriptions are still served.
+	///
+	/// See the module documentation for [`load_shedding`](../jsonrpsee_utils/server/load_shedding/index.html#load-shedding)
+	/// for details.
+	pub fn set_load_shedder(mut self, shedder: LoadShedder) -> Self {
+		self.settings.load_shedder = shedder;
+		self
+	}
+
+	/// Configure capturing the raw messages of a sample of connections (default is disabled).
+	///
+	/// See the module documentation for [`wire_tap`](../jsonrpsee_utils/server/wire_tap/index.html#wire-tap)
+	/// for details.
+	pub fn set_wire_tap(mut self, tap: WireTap) -> Self {
+		self.settings.wire_tap = tap;
+		self
+	}
+
+	/// Enable compressing the messages exchanged with the clients supporting the `permessage-deflate` extension
+	/// (default is disabled). The compression statistics of their connections are reported to
+	/// [`Middleware::on_compression_info`](jsonrpsee_core::middleware::Middleware::on_compression_info).
+	///
+	/// See the module documentation for [`deflate`](../jsonrpsee_utils/deflate/index.html#websocket-compression)
+	/// for details.
+	pub fn set_compression(mut self, config: DeflateConfig) -> Self {
+		self.settings.compression = Some(config);
+		self
+	}
+
+	/// Configure the authentication of clients and the permissions required to call methods (default is disabled).
+	///
+	/// Clients provide their token in the `Authorization` header of the handshake, connections with an invalid
+	/// token are rejected with status code `401`.
+	///
+	/// See the module documentation for [`auth`](../jsonrpsee_utils/server/auth/index.html#authentication)
+	/// for details.
+	pub fn set_authenticator(mut self, authenticator: Authenticator) -> Self {
+		self.settings.authenticator = authenticator;
+		self
+	}
+
 	/// Sets access control settings.
 	pub fn set_access_control(mut self, acl: AccessControl) -> Self {
 		self.settings.access_control = acl;
@@ -915,13 +1629,22 @@ impl<M> Builder<M> {
 	/// ```
 	///
 	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
-		let listener = TcpListener::bind(addrs).await?;
+		let mut listeners = Vec::new();
+		let mut local_addrs = Vec::new();
+
+		for listener in self.settings.bind.bind(lookup_host(addrs).await?)? {
+			local_addrs.push(listener.local_addr()?);
+			listeners.push(TcpListener::from_std(listener)?);
+		}
+
 		let stop_monitor = StopMonitor::new();
 		let resources = self.resources;
 		Ok(Server {
-			listener,
+			listeners,
+			local_addrs,
 			cfg: self.settings,
 			stop_monitor,
+			methods: MethodsHandle::new(Methods::new(), resources.clone()),
 			resources,
 			middleware: self.middleware,
 			id_provider: self.id_provider,
@@ -931,12 +1654,18 @@ impl<M> Builder<M> {
 
 async fn send_ws_message(
 	sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>,
-	response: String,
+	response: Bytes,
 ) -> Result<(), Error> {
-	sender.send_text_owned(response).await?;
+	// The server doesn't mask its frames, the response is written without being copied.
+	let response = std::str::from_utf8(&response).map_err(|err| Error::Custom(err.to_string()))?;
+	sender.send_text(response).await?;
 	sender.flush().await.map_err(Into::into)
 }
 
+fn shutdown_notification(reason: &ShutdownReason) -> String {
+	format!(r#"{{"jsonrpc":"2.0","method":"{}","params":{{"reason":"{}"}}}}"#, SHUTDOWN_NOTIFICATION, reason.as_str())
+}
+
 async fn send_ws_ping(sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>) -> Result<(), Error> {
 	tracing::debug!("send ping");
 	// Submit empty slice as "optional" parameter.
@@ -31,9 +31,13 @@ use std::time::Duration;
 
 use crate::types::error::CallError;
 use crate::types::{Response, SubscriptionId};
-use crate::{future::ServerHandle, RpcModule, WsServerBuilder};
+use crate::{
+	future::ServerHandle, IpFilter, LoadShedder, OverflowPolicy, RateLimit, RateLimiter, RpcModule,
+	SubscriptionOverflowPolicy, WireTap, WsServerBuilder,
+};
 use anyhow::anyhow;
 use futures_util::future::join;
+use jsonrpsee_core::error::SubscriptionClosed;
 use jsonrpsee_core::{traits::IdProvider, DeserializeOwned, Error};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, TestContext, WebSocketTestClient, WebSocketTestError};
@@ -259,6 +263,89 @@ async fn can_set_max_connections() {
 	handle.stop().unwrap();
 }
 
+#[tokio::test]
+async fn ip_filter_rejects_connections() {
+	init_logger();
+
+	for (filter, allowed) in [
+		(IpFilter::new().allow(["10.0.0.0/8", "127.0.0.0/8"]).unwrap(), true),
+		(IpFilter::new().allow(["10.0.0.0/8"]).unwrap(), false),
+		(IpFilter::new().deny(["127.0.0.1"]).unwrap(), false),
+	] {
+		let server = WsServerBuilder::default().set_ip_filter(filter).build("127.0.0.1:0").await.unwrap();
+		let addr = server.local_addr().unwrap();
+		let handle = server.start(RpcModule::new(())).unwrap();
+
+		// Rejected connections are closed before the handshake.
+		let conn = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+		assert_eq!(conn.is_ok(), allowed, "{:?}", conn.err());
+
+		handle.stop().unwrap();
+	}
+}
+
+#[tokio::test]
+async fn proxy_protocol_works() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	init_logger();
+
+	let filter = IpFilter::new().deny(["192.0.2.0/24"]).unwrap();
+	let server =
+		WsServerBuilder::default().proxy_protocol(true).set_ip_filter(filter).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	// Returns the start of the response to the handshake.
+	let handshake = |header: &'static str| async move {
+		let req = format!(
+			"{}GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+			Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+			header
+		);
+		let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+		let mut response = [0; 12];
+		let read = match stream.write_all(req.as_bytes()).await {
+			Ok(()) => stream.read(&mut response).await.unwrap_or(0),
+			Err(_) => 0,
+		};
+		String::from_utf8_lossy(&response[..read]).into_owned()
+	};
+
+	let v1 = "PROXY TCP4 203.0.113.7 127.0.0.1 56324 80\r\n";
+	assert_eq!(handshake(v1).with_default_timeout().await.unwrap(), "HTTP/1.1 101");
+
+	// The client is seen at the address of the header, which is denied.
+	let v1 = "PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n";
+	assert_eq!(handshake(v1).with_default_timeout().await.unwrap(), "");
+
+	// Connections without a header are closed.
+	assert_eq!(handshake("").with_default_timeout().await.unwrap(), "");
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn bind_all_addresses_works() {
+	let addrs: &[SocketAddr] = &["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+	let server = WsServerBuilder::default().bind_all_addresses(true).build(addrs).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let local_addrs = server.local_addrs().to_vec();
+	assert_eq!(local_addrs.len(), 2);
+	assert_eq!(server.local_addr().unwrap(), local_addrs[0]);
+	let handle = server.start(module).unwrap();
+
+	for addr in local_addrs {
+		let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+		let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+		let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+	}
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn single_method_calls_works() {
 	let addr = server().await;
@@ -736,3 +823,370 @@ async fn disabled_batches() {
 
 	handle.stop().unwrap();
 }
+
+#[tokio::test]
+async fn rate_limiting_works() {
+	init_logger();
+
+	let limiter = RateLimiter::new().default_limit(RateLimit::new(2, Duration::from_secs(60)));
+	let server = WsServerBuilder::default().set_rate_limiter(limiter).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	// The budget is shared by all connections of the same client.
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let batch = format!("[{},{}]", req, req.replace(r#""id":1"#, r#""id":2"#));
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses[0], serde_json::json!({"jsonrpc":"2.0","result":"hello","id":1}));
+	assert_eq!(responses[1]["error"]["code"], -32007);
+
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(response["error"]["code"], -32007);
+	assert!(response["error"]["data"]["retry_after_ms"].as_u64().unwrap() <= 30_000);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn load_shedding_works() {
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::sync::Arc;
+
+	init_logger();
+
+	let usage = Arc::new(AtomicU64::new(0));
+	let probe = usage.clone();
+	let shedder = LoadShedder::new(100, move || probe.load(Ordering::Relaxed));
+	let server = WsServerBuilder::default().set_load_shedder(shedder).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			sink.accept()?;
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	usage.store(100, Ordering::Relaxed);
+
+	// New connections are rejected.
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_err());
+
+	// So are new subscriptions, but calls on established connections are still served.
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, server_is_busy(Id::Num(1)));
+	let batch = format!("[{}]", req);
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, format!("[{}]", server_is_busy(Id::Num(1))));
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(2)));
+
+	usage.store(0, Ordering::Relaxed);
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn wire_tap_works() {
+	init_logger();
+
+	let path = std::env::temp_dir().join(format!("jsonrpsee-ws-wire-tap-{}.jsonl", std::process::id()));
+	let tap = WireTap::to_file(&path).unwrap();
+	let server = WsServerBuilder::default().set_wire_tap(tap).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let batch = format!("[{}]", req);
+	let batch_response = client.send_request_text(batch.clone()).with_default_timeout().await.unwrap().unwrap();
+
+	let records: Vec<JsonValue> =
+		std::fs::read_to_string(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+	std::fs::remove_file(&path).unwrap();
+
+	let frames: Vec<_> =
+		records.iter().map(|r| (r["direction"].as_str().unwrap(), r["data"].as_str().unwrap())).collect();
+	assert_eq!(
+		frames,
+		[
+			("inbound", req),
+			("outbound", response.as_str()),
+			("inbound", batch.as_str()),
+			("outbound", batch_response.as_str())
+		]
+	);
+	assert!(records.iter().all(|r| r["transport"] == "ws" && r["session"] == records[0]["session"]));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn sequential_batch_calls_observe_previous_calls() {
+	use std::sync::atomic::{AtomicU64, Ordering};
+
+	let mut module = RpcModule::new(AtomicU64::new(0));
+	module
+		.register_async_method("incr", |_, counter| async move {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			Ok(counter.fetch_add(1, Ordering::SeqCst) + 1)
+		})
+		.unwrap();
+	module.register_method("get", |_, counter| Ok(counter.load(Ordering::SeqCst))).unwrap();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	// Without hints the calls are executed concurrently.
+	let req = r#"[{"jsonrpc":"2.0","method":"incr","id":1},{"jsonrpc":"2.0","method":"get","id":2}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":0,"id":2},{"jsonrpc":"2.0","result":1,"id":1}]"#);
+
+	// A sequential call waits for the preceding calls to complete.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"incr","id":1},
+		{"jsonrpc":"2.0","method":"incr","id":2},
+		{"jsonrpc":"2.0","method":"get","id":3,"execution":"sequential"}
+	]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses[2], serde_json::json!({"jsonrpc":"2.0","result":3,"id":3}));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn batch_concurrency_is_limited() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	// Number of running calls, maximum number of calls observed running at the same time.
+	let mut module = RpcModule::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+	module
+		.register_async_method("work", |_, ctx| async move {
+			let running = ctx.0.fetch_add(1, Ordering::SeqCst) + 1;
+			ctx.1.fetch_max(running, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			ctx.0.fetch_sub(1, Ordering::SeqCst);
+			Ok(running)
+		})
+		.unwrap();
+	module.register_method("max_running", |_, ctx| Ok(ctx.1.load(Ordering::SeqCst))).unwrap();
+	let server = WsServerBuilder::default().max_batch_concurrency(2).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let batch: Vec<_> = (1..=6).map(|id| format!(r#"{{"jsonrpc":"2.0","method":"work","id":{}}}"#, id)).collect();
+	let batch = format!("[{}]", batch.join(","));
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses.len(), 6);
+
+	let req = r#"{"jsonrpc":"2.0","method":"max_running","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(2.into(), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn batch_size_is_limited() {
+	init_logger();
+
+	let server = WsServerBuilder::default().max_batch_size(2).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response =
+		client.send_request_text(format!("[{},{}]", req, req)).with_default_timeout().await.unwrap().unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(responses.len(), 2);
+
+	let batch = format!("[{},{},{}]", req, req, req);
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Batch contains too many calls","data":"Exceeded max limit of 2"},"id":null}"#
+	);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn send_queue_overflow_closes_connection() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.bounded_send_queue(16, OverflowPolicy::CloseConnection)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let (tx_sent, rx_sent) = futures_channel::oneshot::channel();
+	let tx_sent = std::sync::Mutex::new(Some(tx_sent));
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", move |_, mut sink, _| {
+			let tx_sent = tx_sent.lock().unwrap().take().unwrap();
+			tokio::spawn(async move {
+				// Far more than the client and the socket buffers can take without the client reading.
+				let sent = (0..100_000).take_while(|_| sink.send(&"x".repeat(1024)).unwrap()).count();
+				tx_sent.send(sent).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#;
+	// The notifications are sent faster than the connection takes them, which may even be closed before the
+	// subscription call is answered.
+	let _ = client.send_request_text(req).with_default_timeout().await.unwrap();
+
+	// The client doesn't read the notifications, the connection is closed once 16 of them are waiting.
+	let sent = rx_sent.with_default_timeout().await.unwrap().unwrap();
+	assert!(sent < 100_000);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn subscription_buffer_overflow_closes_subscription() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.subscription_buffer(16, SubscriptionOverflowPolicy::CloseSubscription)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let (tx_sent, rx_sent) = futures_channel::oneshot::channel();
+	let tx_sent = std::sync::Mutex::new(Some(tx_sent));
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", move |_, mut sink, _| {
+			let tx_sent = tx_sent.lock().unwrap().take().unwrap();
+			tokio::spawn(async move {
+				// Far more than the client and the socket buffers can take without the client reading.
+				let sent = (0..100_000).take_while(|_| sink.send(&"x".repeat(1024)).unwrap()).count();
+				tx_sent.send(sent).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(r#""id":1"#));
+
+	// The client doesn't read the notifications, the subscription is closed once 16 of them are waiting.
+	let sent = rx_sent.with_default_timeout().await.unwrap().unwrap();
+	assert!(sent < 100_000);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn slow_subscriber_is_evicted() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.subscription_buffer(16, SubscriptionOverflowPolicy::Block)
+		.evict_slow_subscribers(Duration::from_millis(100), false)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let (tx_closed, rx_closed) = futures_channel::oneshot::channel();
+	let tx_closed = std::sync::Mutex::new(Some(tx_closed));
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", move |_, mut sink, _| {
+			let tx_closed = tx_closed.lock().unwrap().take().unwrap();
+			tokio::spawn(async move {
+				// Far more than the client and the socket buffers can take without the client reading.
+				let stream = futures_util::stream::iter((0..100_000).map(|_| "x".repeat(1024)));
+				tx_closed.send(sink.pipe_from_stream(stream).await).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(r#""id":1"#));
+
+	// The client doesn't read the notifications, the subscription is closed once its buffer stayed full.
+	let closed = rx_closed.with_default_timeout().await.unwrap().unwrap();
+	assert!(matches!(closed, SubscriptionClosed::Failed(_)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn unresponsive_client_is_disconnected() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.ping_interval(Duration::from_millis(50))
+		.pong_timeout(Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let (tx_closed, rx_closed) = futures_channel::oneshot::channel();
+	let tx_closed = std::sync::Mutex::new(Some(tx_closed));
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", move |_, mut sink, _| {
+			let tx_closed = tx_closed.lock().unwrap().take().unwrap();
+			tokio::spawn(async move {
+				while let Ok(true) = sink.send(&"hello") {
+					tokio::time::sleep(Duration::from_millis(10)).await;
+				}
+				tx_closed.send(()).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#;
+	client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+
+	// The client stops reading and never answers the pings, the subscription is dropped with the connection.
+	rx_closed.with_default_timeout().await.unwrap().unwrap();
+
+	handle.stop().unwrap();
+}